@@ -16,6 +16,14 @@ pub enum Subcommand {
 	Key(sc_cli::KeySubcommand),
 	/// Build a chain specification.
 	BuildSpec(sc_cli::BuildSpecCmd),
+	/// Export the configuration of the node, from its options and configuration file.
+	ExportConfig(sc_cli::ExportConfigCmd),
+	/// Generate the shell completion script of the node.
+	Completions(sc_cli::CompletionsCmd),
+	/// Sign a message, with a given (secret) key or a key of the keystore of the node.
+	Sign(sc_cli::SignCmd),
+	/// Verify a signature for a message, provided on STDIN, with a given (public or secret) key.
+	Verify(sc_cli::VerifyCmd),
 
 	/// Validate blocks.
 	CheckBlock(sc_cli::CheckBlockCmd),
@@ -23,19 +31,32 @@ pub enum Subcommand {
 	/// Export blocks.
 	ExportBlocks(sc_cli::ExportBlocksCmd),
 
-	/// Export the state of a given block into a chain spec.
+	/// Export the state of a given block into a chain spec or a binary state.
 	ExportState(sc_cli::ExportStateCmd),
 
+	/// Export the metadata and the version of the runtime of a given block.
+	ExportMetadata(sc_cli::ExportMetadataCmd),
+
 	/// Import blocks.
 	ImportBlocks(sc_cli::ImportBlocksCmd),
 
+	/// Seed a new database with a state exported by `export-state --binary`.
+	ImportState(sc_cli::ImportStateCmd),
+
 	/// Remove the whole chain.
 	PurgeChain(sc_cli::PurgeChainCmd),
 
 	/// Revert the chain to a previous state.
 	Revert(sc_cli::RevertCmd),
 
+	/// Snapshot and restore the database.
+	Db(sc_cli::DbCmd),
+
 	/// The custom benchmark subcommmand benchmarking runtime pallets.
 	#[structopt(name = "benchmark", about = "Benchmark runtime pallets.")]
 	Benchmark(frame_benchmarking_cli::BenchmarkCmd),
+
+	/// The benchmark subcommand measuring the latencies of the database of the node.
+	#[structopt(name = "benchmark-storage", about = "Benchmark the reads and writes of the state.")]
+	BenchmarkStorage(frame_benchmarking_cli::StorageCmd),
 }
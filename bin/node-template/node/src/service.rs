@@ -4,7 +4,7 @@ use std::sync::Arc;
 use std::time::Duration;
 use sc_client_api::{ExecutorProvider, RemoteBackend};
 use node_template_runtime::{self, opaque::Block, RuntimeApi};
-use sc_service::{error::Error as ServiceError, Configuration, TaskManager};
+use sc_service::{error::Error as ServiceError, Configuration, TaskGroup, TaskManager};
 use sp_inherents::InherentDataProviders;
 use sc_executor::native_executor_instance;
 pub use sc_executor::NativeExecutor;
@@ -44,7 +44,7 @@ pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponen
 		return Err(ServiceError::Other(
 			format!("Remote Keystores are not supported.")))
 	}
-	let inherent_data_providers = InherentDataProviders::new();
+	let inherent_data_providers = sc_service::new_inherent_data_providers(&config)?;
 
 	let (client, backend, keystore_container, task_manager) =
 		sc_service::new_full_parts::<Block, RuntimeApi, Executor>(&config)?;
@@ -53,7 +53,7 @@ pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponen
 	let select_chain = sc_consensus::LongestChain::new(backend.clone());
 
 	let transaction_pool = sc_transaction_pool::BasicPool::new_full(
-		config.transaction_pool.clone(),
+		sc_service::new_transaction_pool_options::<node_template_runtime::Address>(config),
 		config.role.is_authority().into(),
 		config.prometheus_registry(),
 		task_manager.spawn_handle(),
@@ -74,7 +74,7 @@ pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponen
 			justification_import: Some(Box::new(grandpa_block_import.clone())),
 			client: client.clone(),
 			inherent_data_providers: inherent_data_providers.clone(),
-			spawner: &task_manager.spawn_essential_handle(),
+			spawner: &task_manager.spawn_essential_handle().in_group(TaskGroup::Import),
 			can_author_with: sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone()),
 			slot_duration: sc_consensus_aura::slot_duration(&*client)?,
 			registry: config.prometheus_registry(),
@@ -116,6 +116,16 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		other: (block_import, grandpa_link),
 	} = new_partial(&config)?;
 
+	if config.transaction_pool.persist {
+		let persistence = sc_transaction_pool::persistence::enable(
+			&transaction_pool,
+			client.clone(),
+			task_manager.spawn_handle(),
+		);
+		// persisted before the tasks are stopped, while the pool is still complete.
+		task_manager.on_shutdown("txpool-persistence", async move { persistence.persist() });
+	}
+
 	if let Some(url) = &config.keystore_remote {
 		match remote_keystore(url) {
 			Ok(k) => keystore_container.set_remote_keystore(k),
@@ -137,6 +147,7 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 			import_queue,
 			on_demand: None,
 			block_announce_validator_builder: None,
+			hooks: None,
 		})?;
 
 	if config.offchain_worker.enabled {
@@ -178,6 +189,7 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 			task_manager: &mut task_manager,
 			transaction_pool: transaction_pool.clone(),
 			rpc_extensions_builder,
+			hooks_rpc_extensions: None,
 			on_demand: None,
 			remote_blockchain: None,
 			backend,
@@ -213,12 +225,15 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 				can_author_with,
 				sync_oracle: network.clone(),
 				block_proposal_slot_portion: SlotProportion::new(2f32 / 3f32),
+				registry: prometheus_registry.clone(),
 			},
 		)?;
 
 		// the AURA authoring task is considered essential, i.e. if it
 		// fails we take down the service with it.
-		task_manager.spawn_essential_handle().spawn_blocking("aura", aura);
+		task_manager.spawn_essential_handle()
+			.in_group(TaskGroup::Consensus)
+			.spawn_blocking("aura", aura);
 	}
 
 	// if the node isn't actively participating in consensus then it doesn't
@@ -258,7 +273,7 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 
 		// the GRANDPA voter task is considered infallible, i.e.
 		// if it fails we take down the service with it.
-		task_manager.spawn_essential_handle().spawn_blocking(
+		task_manager.spawn_essential_handle().in_group(TaskGroup::Consensus).spawn_blocking(
 			"grandpa-voter",
 			sc_finality_grandpa::run_grandpa_voter(grandpa_config)?
 		);
@@ -302,7 +317,7 @@ pub fn new_light(mut config: Configuration) -> Result<TaskManager, ServiceError>
 			justification_import: Some(Box::new(grandpa_block_import.clone())),
 			client: client.clone(),
 			inherent_data_providers: InherentDataProviders::new(),
-			spawner: &task_manager.spawn_essential_handle(),
+			spawner: &task_manager.spawn_essential_handle().in_group(TaskGroup::Import),
 			can_author_with: sp_consensus::NeverCanAuthor,
 			slot_duration: sc_consensus_aura::slot_duration(&*client)?,
 			registry: config.prometheus_registry(),
@@ -319,6 +334,7 @@ pub fn new_light(mut config: Configuration) -> Result<TaskManager, ServiceError>
 			import_queue,
 			on_demand: Some(on_demand.clone()),
 			block_announce_validator_builder: None,
+			hooks: None,
 		})?;
 
 	if config.offchain_worker.enabled {
@@ -336,6 +352,7 @@ pub fn new_light(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		task_manager: &mut task_manager,
 		on_demand: Some(on_demand),
 		rpc_extensions_builder: Box::new(|_, _| ()),
+		hooks_rpc_extensions: None,
 		config,
 		client,
 		keystore: keystore_container.sync_keystore(),
@@ -96,7 +96,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: create_runtime_str!("node-template"),
 	impl_name: create_runtime_str!("node-template"),
 	authoring_version: 1,
-	spec_version: 1,
+	spec_version: 2,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
 	transaction_version: 1,
@@ -399,6 +399,13 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl sp_session::DisabledValidators<Block> for Runtime {
+		fn disabled_validators() -> Vec<u32> {
+			// The template doesn't include the session pallet, so no validator is ever disabled.
+			Vec::new()
+		}
+	}
+
 	impl fg_primitives::GrandpaApi<Block> for Runtime {
 		fn grandpa_authorities() -> GrandpaAuthorityList {
 			Grandpa::grandpa_authorities()
@@ -444,6 +451,11 @@ impl_runtime_apis! {
 		) -> pallet_transaction_payment::FeeDetails<Balance> {
 			TransactionPayment::query_fee_details(uxt, len)
 		}
+		fn query_info_batch(
+			uxts: Vec<(<Block as BlockT>::Extrinsic, u32)>,
+		) -> Vec<pallet_transaction_payment_rpc_runtime_api::RuntimeDispatchInfo<Balance>> {
+			uxts.into_iter().map(|(uxt, len)| TransactionPayment::query_info(uxt, len)).collect()
+		}
 	}
 
 	#[cfg(feature = "runtime-benchmarks")]
@@ -40,10 +40,13 @@ use sp_runtime::{
 	OpaqueExtrinsic,
 };
 use sp_transaction_pool::{
+	BlockHash,
 	ImportNotificationStream,
+	PoolEventStream,
 	PoolFuture,
 	PoolStatus,
 	TransactionFor,
+	TransactionSimulation,
 	TransactionSource,
 	TransactionStatusStreamFor,
 	TxHash,
@@ -219,6 +222,8 @@ impl sp_transaction_pool::InPoolTransaction for PoolTransaction {
 	fn provides(&self) -> &[Vec<u8>] { unimplemented!() }
 
 	fn is_propagable(&self) -> bool { unimplemented!() }
+
+	fn is_local(&self) -> bool { false }
 }
 
 #[derive(Clone, Debug)]
@@ -250,6 +255,15 @@ impl sp_transaction_pool::TransactionPool for Transactions {
 		unimplemented!()
 	}
 
+	fn submit_from_peer(
+		&self,
+		at: &BlockId<Self::Block>,
+		_peer: String,
+		xt: TransactionFor<Self>,
+	) -> PoolFuture<TxHash<Self>, Self::Error> {
+		self.submit_one(at, TransactionSource::External, xt)
+	}
+
 	fn submit_and_watch(
 		&self,
 		_at: &BlockId<Self::Block>,
@@ -259,6 +273,15 @@ impl sp_transaction_pool::TransactionPool for Transactions {
 		unimplemented!()
 	}
 
+	fn submit_and_simulate(
+		&self,
+		_at: &BlockId<Self::Block>,
+		_xt: TransactionFor<Self>,
+	) -> PoolFuture<TransactionSimulation<TxHash<Self>>, Self::Error> {
+		// the transactions of the benchmark are fixed, nothing is ever applied to them.
+		Box::pin(futures::future::ready(Err(sp_transaction_pool::error::Error::ImmediatelyDropped)))
+	}
+
 	fn ready_at(&self, _at: NumberFor<Self::Block>)
 		-> Pin<Box<dyn Future<Output=Box<dyn Iterator<Item=Arc<Self::InPoolTransaction>> + Send>> + Send>>
 	{
@@ -274,6 +297,18 @@ impl sp_transaction_pool::TransactionPool for Transactions {
 		Default::default()
 	}
 
+	fn ban(
+		&self,
+		_hashes: &[TxHash<Self>],
+		_duration: Option<std::time::Duration>,
+	) -> Vec<Arc<Self::InPoolTransaction>> {
+		Default::default()
+	}
+
+	fn unban(&self, _hash: &TxHash<Self>) -> bool {
+		false
+	}
+
 	fn status(&self) -> PoolStatus {
 		unimplemented!()
 	}
@@ -282,6 +317,11 @@ impl sp_transaction_pool::TransactionPool for Transactions {
 		unimplemented!()
 	}
 
+	fn pool_event_stream(&self) -> PoolEventStream<TxHash<Self>, BlockHash<Self>> {
+		// no event ever happens in the pool of the benchmark.
+		futures::channel::mpsc::channel(0).1
+	}
+
 	fn on_broadcasted(&self, _propagations: HashMap<TxHash<Self>, Vec<String>>) {
 		unimplemented!()
 	}
@@ -290,6 +330,10 @@ impl sp_transaction_pool::TransactionPool for Transactions {
 		unimplemented!()
 	}
 
+	fn futures(&self) -> Vec<Arc<Self::InPoolTransaction>> {
+		Default::default()
+	}
+
 	fn ready_transaction(&self, _hash: &TxHash<Self>) -> Option<Arc<Self::InPoolTransaction>> {
 		unimplemented!()
 	}
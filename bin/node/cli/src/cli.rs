@@ -47,38 +47,63 @@ pub enum Subcommand {
 	#[structopt(name = "benchmark", about = "Benchmark runtime pallets.")]
 	Benchmark(frame_benchmarking_cli::BenchmarkCmd),
 
+	/// The benchmark subcommand measuring the latencies of the database of the node.
+	#[structopt(name = "benchmark-storage", about = "Benchmark the reads and writes of the state.")]
+	BenchmarkStorage(frame_benchmarking_cli::StorageCmd),
+
 	/// Try some experimental command on the runtime. This includes migration and runtime-upgrade
 	/// testing.
 	#[cfg(feature = "try-runtime")]
 	TryRuntime(try_runtime_cli::TryRuntimeCmd),
 
+	/// Dry-run the upgrade to a runtime, reporting the changes of the storage and the weight of
+	/// its migrations.
+	#[cfg(feature = "try-runtime")]
+	#[structopt(name = "try-upgrade")]
+	TryUpgrade(try_runtime_cli::TryUpgradeCmd),
+
 	/// Verify a signature for a message, provided on STDIN, with a given (public or secret) key.
 	Verify(VerifyCmd),
 
 	/// Generate a seed that provides a vanity address.
 	Vanity(VanityCmd),
 
-	/// Sign a message, with a given (secret) key.
+	/// Sign a message, with a given (secret) key or a key of the keystore of the node.
 	Sign(SignCmd),
 
 	/// Build a chain specification.
 	BuildSpec(sc_cli::BuildSpecCmd),
 
+	/// Export the configuration of the node, from its options and configuration file.
+	ExportConfig(sc_cli::ExportConfigCmd),
+
+	/// Generate the shell completion script of the node.
+	Completions(sc_cli::CompletionsCmd),
+
 	/// Validate blocks.
 	CheckBlock(sc_cli::CheckBlockCmd),
 
 	/// Export blocks.
 	ExportBlocks(sc_cli::ExportBlocksCmd),
 
-	/// Export the state of a given block into a chain spec.
+	/// Export the state of a given block into a chain spec or a binary state.
 	ExportState(sc_cli::ExportStateCmd),
 
+	/// Export the metadata and the version of the runtime of a given block.
+	ExportMetadata(sc_cli::ExportMetadataCmd),
+
 	/// Import blocks.
 	ImportBlocks(sc_cli::ImportBlocksCmd),
 
+	/// Seed a new database with a state exported by `export-state --binary`.
+	ImportState(sc_cli::ImportStateCmd),
+
 	/// Remove the whole chain.
 	PurgeChain(sc_cli::PurgeChainCmd),
 
 	/// Revert the chain to a previous state.
 	Revert(sc_cli::RevertCmd),
+
+	/// Snapshot and restore the database.
+	Db(sc_cli::DbCmd),
 }
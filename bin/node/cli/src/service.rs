@@ -25,7 +25,8 @@ use sc_consensus_babe;
 use node_primitives::Block;
 use node_runtime::RuntimeApi;
 use sc_service::{
-	config::Configuration, error::Error as ServiceError, RpcHandlers, TaskManager,
+	config::Configuration, error::Error as ServiceError, RestartPolicy, RpcHandlers, TaskGroup,
+	TaskManager,
 };
 use sp_inherents::InherentDataProviders;
 use sc_network::{Event, NetworkService};
@@ -67,7 +68,7 @@ pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponen
 	let select_chain = sc_consensus::LongestChain::new(backend.clone());
 
 	let transaction_pool = sc_transaction_pool::BasicPool::new_full(
-		config.transaction_pool.clone(),
+		sc_service::new_transaction_pool_options::<node_runtime::Address>(config),
 		config.role.is_authority().into(),
 		config.prometheus_registry(),
 		task_manager.spawn_handle(),
@@ -85,7 +86,7 @@ pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponen
 		client.clone(),
 	)?;
 
-	let inherent_data_providers = sp_inherents::InherentDataProviders::new();
+	let inherent_data_providers = sc_service::new_inherent_data_providers(&config)?;
 
 	let import_queue = sc_consensus_babe::import_queue(
 		babe_link.clone(),
@@ -94,7 +95,7 @@ pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponen
 		client.clone(),
 		select_chain.clone(),
 		inherent_data_providers.clone(),
-		&task_manager.spawn_essential_handle(),
+		&task_manager.spawn_essential_handle().in_group(TaskGroup::Import),
 		config.prometheus_registry(),
 		sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone()),
 	)?;
@@ -192,6 +193,16 @@ pub fn new_full_base(
 		other: (rpc_extensions_builder, import_setup, rpc_setup),
 	} = new_partial(&config)?;
 
+	if config.transaction_pool.persist {
+		let persistence = sc_transaction_pool::persistence::enable(
+			&transaction_pool,
+			client.clone(),
+			task_manager.spawn_handle(),
+		);
+		// persisted before the tasks are stopped, while the pool is still complete.
+		task_manager.on_shutdown("txpool-persistence", async move { persistence.persist() });
+	}
+
 	let shared_voter_state = rpc_setup;
 
 	config.network.extra_sets.push(grandpa::grandpa_peers_set_config());
@@ -215,6 +226,7 @@ pub fn new_full_base(
 			import_queue,
 			on_demand: None,
 			block_announce_validator_builder: None,
+			hooks: None,
 		})?;
 
 	if config.offchain_worker.enabled {
@@ -242,6 +254,7 @@ pub fn new_full_base(
 			keystore: keystore_container.sync_keystore(),
 			network: network.clone(),
 			rpc_extensions_builder: Box::new(rpc_extensions_builder),
+			hooks_rpc_extensions: None,
 			transaction_pool: transaction_pool.clone(),
 			task_manager: &mut task_manager,
 			on_demand: None,
@@ -280,31 +293,43 @@ pub fn new_full_base(
 			babe_link,
 			can_author_with,
 			block_proposal_slot_portion: SlotProportion::new(0.5),
+			registry: prometheus_registry.clone(),
 		};
 
 		let babe = sc_consensus_babe::start_babe(babe_config)?;
-		task_manager.spawn_essential_handle().spawn_blocking("babe-proposer", babe);
+		task_manager.spawn_essential_handle()
+			.in_group(TaskGroup::Consensus)
+			.spawn_blocking("babe-proposer", babe);
 	}
 
-	// Spawn authority discovery module.
+	// Spawn authority discovery module, restarted with a new worker if it fails.
 	if role.is_authority() {
-		let authority_discovery_role = sc_authority_discovery::Role::PublishAndDiscover(
-			keystore_container.keystore(),
-		);
-		let dht_event_stream = network.event_stream("authority-discovery")
-			.filter_map(|e| async move { match e {
-				Event::Dht(e) => Some(e),
-				_ => None,
-			}});
-		let (authority_discovery_worker, _service) = sc_authority_discovery::new_worker_and_service(
-			client.clone(),
-			network.clone(),
-			Box::pin(dht_event_stream),
-			authority_discovery_role,
-			prometheus_registry.clone(),
-		);
+		let client = client.clone();
+		let network = network.clone();
+		let keystore = keystore_container.keystore();
+		let prometheus_registry = prometheus_registry.clone();
+		let new_authority_discovery_worker = move || {
+			let dht_event_stream = network.event_stream("authority-discovery")
+				.filter_map(|e| async move { match e {
+					Event::Dht(e) => Some(e),
+					_ => None,
+				}});
+			let (authority_discovery_worker, _service) =
+				sc_authority_discovery::new_worker_and_service(
+					client.clone(),
+					network.clone(),
+					Box::pin(dht_event_stream),
+					sc_authority_discovery::Role::PublishAndDiscover(keystore.clone()),
+					prometheus_registry.clone(),
+				);
+			authority_discovery_worker.run()
+		};
 
-		task_manager.spawn_handle().spawn("authority-discovery-worker", authority_discovery_worker.run());
+		task_manager.spawn_handle().spawn_with_restart(
+			"authority-discovery-worker",
+			RestartPolicy::on_failure(),
+			new_authority_discovery_worker,
+		);
 	}
 
 	// if the node isn't actively participating in consensus then it doesn't
@@ -344,7 +369,7 @@ pub fn new_full_base(
 
 		// the GRANDPA voter task is considered infallible, i.e.
 		// if it fails we take down the service with it.
-		task_manager.spawn_essential_handle().spawn_blocking(
+		task_manager.spawn_essential_handle().in_group(TaskGroup::Consensus).spawn_blocking(
 			"grandpa-voter",
 			grandpa::run_grandpa_voter(grandpa_config)?
 		);
@@ -411,7 +436,7 @@ pub fn new_light_base(mut config: Configuration) -> Result<(
 		client.clone(),
 		select_chain.clone(),
 		inherent_data_providers.clone(),
-		&task_manager.spawn_essential_handle(),
+		&task_manager.spawn_essential_handle().in_group(TaskGroup::Import),
 		config.prometheus_registry(),
 		sp_consensus::NeverCanAuthor,
 	)?;
@@ -425,6 +450,7 @@ pub fn new_light_base(mut config: Configuration) -> Result<(
 			import_queue,
 			on_demand: Some(on_demand.clone()),
 			block_announce_validator_builder: None,
+			hooks: None,
 		})?;
 	network_starter.start_network();
 
@@ -451,6 +477,7 @@ pub fn new_light_base(mut config: Configuration) -> Result<(
 			on_demand: Some(on_demand),
 			remote_blockchain: Some(backend.remote_blockchain()),
 			rpc_extensions_builder: Box::new(sc_service::NoopRpcExtensionBuilder(rpc_extensions)),
+			hooks_rpc_extensions: None,
 			client: client.clone(),
 			transaction_pool: transaction_pool.clone(),
 			keystore: keystore_container.sync_keystore(),
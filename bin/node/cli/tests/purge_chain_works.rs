@@ -41,3 +41,47 @@ fn purge_chain_works() {
 	assert!(base_path.path().join("chains/dev/").exists());
 	assert!(!base_path.path().join("chains/dev/db").exists());
 }
+
+#[test]
+#[cfg(unix)]
+fn purge_chain_keeps_the_keys() {
+	let base_path = tempdir().expect("could not create a temp dir");
+
+	common::run_dev_node_for_a_while(base_path.path());
+	let network_key = base_path.path().join("chains/dev/network/secret_ed25519");
+	assert!(network_key.exists());
+
+	let status = Command::new(cargo_bin("substrate"))
+		.args(&["purge-chain", "--dev", "-d"])
+		.arg(base_path.path())
+		.arg("-y")
+		.status()
+		.unwrap();
+	assert!(status.success());
+
+	assert!(!base_path.path().join("chains/dev/db").exists());
+	assert!(base_path.path().join("chains/dev/keystore").exists());
+	assert!(network_key.exists());
+}
+
+#[test]
+#[cfg(unix)]
+fn purge_chain_removes_the_keys_on_request() {
+	let base_path = tempdir().expect("could not create a temp dir");
+
+	common::run_dev_node_for_a_while(base_path.path());
+	let network_key = base_path.path().join("chains/dev/network/secret_ed25519");
+	assert!(network_key.exists());
+
+	let status = Command::new(cargo_bin("substrate"))
+		.args(&["purge-chain", "--dev", "--keystore", "--network-key", "-d"])
+		.arg(base_path.path())
+		.arg("-y")
+		.status()
+		.unwrap();
+	assert!(status.success());
+
+	assert!(!base_path.path().join("chains/dev/db").exists());
+	assert!(!base_path.path().join("chains/dev/keystore").exists());
+	assert!(!network_key.exists());
+}
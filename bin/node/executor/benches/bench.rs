@@ -213,7 +213,7 @@ fn bench_execute_block(c: &mut Criterion) {
 			ExecutionMethod::Native,
 			ExecutionMethod::Wasm(WasmExecutionMethod::Interpreted),
 			#[cfg(feature = "wasmtime")]
-			ExecutionMethod::Wasm(WasmExecutionMethod::Compiled),
+			ExecutionMethod::Wasm(WasmExecutionMethod::Compiled(Default::default())),
 		],
 	);
 }
@@ -41,7 +41,9 @@ pub struct InspectCmd {
 /// A possible inspect sub-commands.
 #[derive(Debug, StructOpt)]
 pub enum InspectSubCmd {
-	/// Decode block with native version of runtime and print out the details.
+	/// Decode block with the metadata of the runtime of the block and print out the details.
+	///
+	/// The blocks given as bytes are decoded with the runtime of the best block.
 	Block {
 		/// Address of the block to print out.
 		///
@@ -51,7 +53,9 @@ pub enum InspectSubCmd {
 		#[structopt(value_name = "HASH or NUMBER or BYTES")]
 		input: String,
 	},
-	/// Decode extrinsic with native version of runtime and print out the details.
+	/// Decode extrinsic with the metadata of the runtime of its block and print out the details.
+	///
+	/// The extrinsics given as bytes are decoded with the runtime of the best block.
 	Extrinsic {
 		/// Address of an extrinsic to print out.
 		///
@@ -61,4 +65,20 @@ pub enum InspectSubCmd {
 		#[structopt(value_name = "BLOCK:INDEX or BYTES")]
 		input: String,
 	},
+	/// Print the BABE epoch a child of the given block would be authored in.
+	///
+	/// Shows the epoch index, randomness, slot range and authorities with their weights, as
+	/// recorded in the node's epoch changes tree.
+	BabeEpoch {
+		/// Hash of the parent block to look up the epoch for (no 0x prefix).
+		#[structopt(value_name = "HASH")]
+		hash: String,
+
+		/// Slot to look up the epoch in.
+		///
+		/// Defaults to the slot following the slot of the given block. For the genesis block it
+		/// defaults to the slot of the first block, or the current slot if there is none yet.
+		#[structopt(long, value_name = "SLOT")]
+		slot: Option<u64>,
+	},
 }
@@ -19,11 +19,19 @@
 //! Command ran by the CLI
 
 use crate::cli::{InspectCmd, InspectSubCmd};
-use crate::Inspector;
+use crate::{BlockAddress, BlockAddressFor, ExtrinsicAddress, Inspector};
+use crate::metadata::{Metadata, MetadataPrinter};
+use codec::Decode;
 use sc_cli::{CliConfiguration, ImportParams, Result, SharedParams};
-use sc_service::{new_full_client, Configuration, NativeExecutionDispatch};
-use sp_runtime::traits::Block;
-use std::str::FromStr;
+use sc_client_api::{AuxStore, CallExecutor, ExecutionStrategy, ExecutorProvider};
+use sc_consensus_babe::{BabeGenesisConfiguration, aux_schema::load_epoch_changes};
+use sc_service::{new_full_parts, Configuration, NativeExecutionDispatch, TFullClient};
+use sp_blockchain::{HeaderBackend, HeaderMetadata};
+use sp_consensus::SlotData;
+use sp_consensus_babe::Slot;
+use sp_core::hexdisplay::HexDisplay;
+use sp_runtime::{generic::BlockId, traits::{Block, Header, One, Zero}};
+use std::{fmt::Write, str::FromStr, time::{SystemTime, UNIX_EPOCH}};
 
 impl InspectCmd {
 	/// Run the inspect command, passing the inspector.
@@ -34,26 +42,169 @@ impl InspectCmd {
 		RA: Send + Sync + 'static,
 		EX: NativeExecutionDispatch + 'static,
 	{
-		let client = new_full_client::<B, RA, EX>(&config)?;
-		let inspect = Inspector::<B>::new(client);
+		let (client, backend, _, _task_manager) = new_full_parts::<B, RA, EX>(&config)?;
+
+		if let InspectSubCmd::BabeEpoch { hash, slot } = &self.command {
+			let hash = hash.parse().map_err(|_| format!("Invalid block hash: {}", hash))?;
+			let res = babe_epoch(&client, &*backend, hash, *slot)?;
+			println!("{}", res);
+			return Ok(())
+		}
+
+		let inspector = |metadata| {
+			Inspector::<B, _>::with_printer(client.clone(), MetadataPrinter(metadata))
+		};
 
 		match &self.command {
 			InspectSubCmd::Block { input } => {
 				let input = input.parse()?;
+				let inspect = inspector(runtime_metadata::<B, RA, EX>(&client, Some(&input))?);
 				let res = inspect.block(input).map_err(|e| format!("{}", e))?;
 				println!("{}", res);
 				Ok(())
 			}
 			InspectSubCmd::Extrinsic { input } => {
 				let input = input.parse()?;
+				let block = match &input {
+					ExtrinsicAddress::Block(block, _) => Some(block),
+					ExtrinsicAddress::Bytes(_) => None,
+				};
+				let inspect = inspector(runtime_metadata::<B, RA, EX>(&client, block)?);
 				let res = inspect.extrinsic(input).map_err(|e| format!("{}", e))?;
 				println!("{}", res);
 				Ok(())
 			}
+			InspectSubCmd::BabeEpoch { .. } => unreachable!("handled above; qed"),
 		}
 	}
 }
 
+/// Load the metadata of the runtime of the given block of the database, the extrinsics are
+/// decoded with, or of the runtime of the best block for the blocks and extrinsics given as
+/// bytes.
+fn runtime_metadata<B, RA, EX>(
+	client: &TFullClient<B, RA, EX>,
+	input: Option<&BlockAddressFor<B>>,
+) -> Result<Metadata>
+where
+	B: Block,
+	RA: Send + Sync + 'static,
+	EX: NativeExecutionDispatch + 'static,
+{
+	let id = match input {
+		Some(BlockAddress::Hash(hash)) => BlockId::Hash(*hash),
+		Some(BlockAddress::Number(number)) => BlockId::Number(*number),
+		Some(BlockAddress::Bytes(_)) | None => BlockId::Hash(client.info().best_hash),
+	};
+	let executor = client.executor();
+	let version = executor.runtime_version(&id)?;
+	let encoded = executor.call(
+		&id,
+		"Metadata_metadata",
+		&[],
+		ExecutionStrategy::AlwaysWasm,
+		None,
+	)?;
+	println!("Runtime: {}-{}", version.spec_name, version.spec_version);
+	Ok(Metadata::decode(&encoded)?)
+}
+
+/// Look up the BABE epoch a child of the block with the given hash would be authored in and
+/// format it for printing.
+fn babe_epoch<B, C, A>(
+	client: &C,
+	aux: &A,
+	hash: B::Hash,
+	slot: Option<u64>,
+) -> Result<String>
+where
+	B: Block,
+	C: HeaderBackend<B> + HeaderMetadata<B, Error = sp_blockchain::Error>,
+	A: AuxStore,
+{
+	let genesis_config = aux.get_aux(<BabeGenesisConfiguration as SlotData>::SLOT_KEY)?
+		.ok_or_else(|| "BABE configuration not found in the database. Is this a BABE chain?")?;
+	let genesis_config = BabeGenesisConfiguration::decode(&mut &genesis_config[..])
+		.map_err(|e| format!("Invalid BABE configuration in the database: {}", e))?;
+	let epoch_changes = load_epoch_changes::<B, _>(aux, &genesis_config)?;
+
+	let header = client.header(BlockId::Hash(hash))?
+		.ok_or_else(|| format!("Could not find block {:?}", hash))?;
+	let slot = match slot {
+		Some(slot) => slot.into(),
+		None => child_slot(client, &header, genesis_config.slot_duration)?,
+	};
+
+	let epoch = sc_consensus_babe::epoch_for_child_of(
+		client,
+		&epoch_changes,
+		&genesis_config,
+		hash,
+		*header.number(),
+		slot,
+	).map_err(|e| format!("{}", e))?;
+
+	let mut res = String::new();
+	let _ = writeln!(res, "Epoch for a child of {:?} at slot {}:", hash, slot);
+	let _ = writeln!(res, "Index: {}", epoch.epoch_index);
+	let _ = writeln!(
+		res,
+		"Slots: {} - {} ({} slots)",
+		epoch.start_slot,
+		epoch.start_slot + epoch.duration.saturating_sub(1),
+		epoch.duration,
+	);
+	let _ = writeln!(res, "Randomness: 0x{}", HexDisplay::from(&epoch.randomness));
+	let _ = writeln!(
+		res,
+		"Config: c = {:?}, allowed slots = {:?}",
+		epoch.config.c,
+		epoch.config.allowed_slots,
+	);
+
+	let total_weight: u64 = epoch.authorities.iter().map(|(_, weight)| *weight).sum();
+	let _ = writeln!(
+		res,
+		"Authorities ({}, total weight {}):",
+		epoch.authorities.len(),
+		total_weight,
+	);
+	for (index, (authority, weight)) in epoch.authorities.iter().enumerate() {
+		let _ = writeln!(res, "- {}: {} (weight {})", index, authority, weight);
+	}
+
+	Ok(res)
+}
+
+/// The default slot to look up the epoch of a child of the given block in.
+///
+/// This is the slot following the slot of the block. The genesis block has no slot, the genesis
+/// epoch starts at the slot of the first block instead, so that slot is used if the first block
+/// has been imported already and the current slot otherwise.
+fn child_slot<B, C>(client: &C, header: &B::Header, slot_duration: u64) -> Result<Slot>
+where
+	B: Block,
+	C: HeaderBackend<B>,
+{
+	let pre_digest_slot = |header: &B::Header| sc_consensus_babe::find_pre_digest::<B>(header)
+		.map(|pre_digest| pre_digest.slot())
+		.map_err(|e| format!("{}", e));
+
+	if !header.number().is_zero() {
+		return Ok(pre_digest_slot(header)? + 1)
+	}
+
+	match client.header(BlockId::Number(One::one()))? {
+		Some(first) => Ok(pre_digest_slot(&first)?),
+		None => {
+			let now = SystemTime::now()
+				.duration_since(UNIX_EPOCH)
+				.map_err(|e| format!("Current time is before unix epoch: {}", e))?;
+			Ok((now.as_millis() as u64 / slot_duration.max(1)).into())
+		},
+	}
+}
+
 impl CliConfiguration for InspectCmd {
 	fn shared_params(&self) -> &SharedParams {
 		&self.shared_params
@@ -63,3 +214,70 @@ impl CliConfiguration for InspectCmd {
 		Some(&self.import_params)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+	use sp_consensus_babe::{AllowedSlots, BabeGenesisConfiguration};
+	use substrate_test_runtime_client::{
+		DefaultTestClientBuilderExt, TestClientBuilderExt, runtime::Block,
+	};
+
+	fn babe_client() -> substrate_test_runtime_client::TestClient {
+		let client = substrate_test_runtime_client::TestClientBuilder::new().build();
+		let config = BabeGenesisConfiguration {
+			slot_duration: 6000,
+			epoch_length: 10,
+			c: (1, 4),
+			genesis_authorities: Vec::new(),
+			randomness: [0; 32],
+			allowed_slots: AllowedSlots::PrimaryAndSecondaryPlainSlots,
+		};
+		client.insert_aux(
+			&[(<BabeGenesisConfiguration as SlotData>::SLOT_KEY, &config.encode()[..])],
+			&[],
+		).unwrap();
+		client
+	}
+
+	#[test]
+	fn prints_the_genesis_epoch_at_the_given_slot() {
+		let client = babe_client();
+		let genesis = client.info().genesis_hash;
+
+		let res = babe_epoch::<Block, _, _>(&client, &client, genesis, Some(42)).unwrap();
+
+		assert!(res.contains("at slot 42:"));
+		assert!(res.contains("Index: 0"));
+		assert!(res.contains("Slots: 42 - 51 (10 slots)"));
+	}
+
+	#[test]
+	fn prints_the_genesis_epoch_without_a_slot() {
+		let client = babe_client();
+		let genesis = client.info().genesis_hash;
+		let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64;
+
+		let res = babe_epoch::<Block, _, _>(&client, &client, genesis, None).unwrap();
+
+		// no block has been imported yet, so the genesis epoch starts at the current slot.
+		let slot = res.lines().next().unwrap()
+			.rsplit(' ').next().unwrap()
+			.trim_end_matches(':')
+			.parse::<u64>().unwrap();
+		assert!(slot >= now / 6000);
+		assert!(res.contains("Index: 0"));
+		assert!(res.contains(&format!("Slots: {} - {} (10 slots)", slot, slot + 9)));
+	}
+
+	#[test]
+	fn fails_without_babe_configuration() {
+		let client = substrate_test_runtime_client::new();
+		let genesis = client.info().genesis_hash;
+
+		let err = babe_epoch::<Block, _, _>(&client, &client, genesis, None).unwrap_err();
+
+		assert!(err.to_string().contains("BABE configuration not found"));
+	}
+}
@@ -26,6 +26,7 @@
 
 pub mod cli;
 pub mod command;
+pub mod metadata;
 
 use std::{
 	fmt,
@@ -36,10 +37,15 @@ use std::{
 use codec::{Encode, Decode};
 use sc_client_api::BlockBackend;
 use sp_blockchain::HeaderBackend;
+use sp_consensus_babe::{
+	BABE_ENGINE_ID, ConsensusLog,
+	digests::{NextConfigDescriptor::V1 as NextConfigV1, PreDigest},
+};
 use sp_core::hexdisplay::HexDisplay;
 use sp_runtime::{
+	DigestItem,
 	generic::BlockId,
-	traits::{Block, HashFor, NumberFor, Hash}
+	traits::{Block, HashFor, Header, NumberFor, Hash}
 };
 
 /// A helper type for a generic block input.
@@ -61,9 +67,7 @@ pub trait PrettyPrinter<TBlock: Block> {
 pub struct DebugPrinter;
 impl<TBlock: Block> PrettyPrinter<TBlock> for DebugPrinter {
 	fn fmt_block(&self, fmt: &mut fmt::Formatter, block: &TBlock) -> fmt::Result {
-		writeln!(fmt, "Header:")?;
-		writeln!(fmt, "{:?}", block.header())?;
-		writeln!(fmt, "Block bytes: {:?}", HexDisplay::from(&block.encode()))?;
+		fmt_header(fmt, block)?;
 		writeln!(fmt, "Extrinsics ({})", block.extrinsics().len())?;
 		for (idx, ex) in block.extrinsics().iter().enumerate() {
 			writeln!(fmt, "- {}:", idx)?;
@@ -79,6 +83,74 @@ impl<TBlock: Block> PrettyPrinter<TBlock> for DebugPrinter {
 	}
 }
 
+/// Prints the header of the given block, with its digest, and the bytes of the block.
+fn fmt_header<TBlock: Block>(fmt: &mut fmt::Formatter, block: &TBlock) -> fmt::Result {
+	writeln!(fmt, "Header:")?;
+	writeln!(fmt, "{:?}", block.header())?;
+	let logs = block.header().digest().logs();
+	writeln!(fmt, "Digest ({})", logs.len())?;
+	for item in logs {
+		writeln!(fmt, "- {}", DigestItemPrinter(item))?;
+	}
+	writeln!(fmt, "Block bytes: {:?}", HexDisplay::from(&block.encode()))
+}
+
+/// Prints a digest item, decoding those of BABE.
+struct DigestItemPrinter<'a, Hash>(&'a DigestItem<Hash>);
+
+impl<'a, Hash: Debug> fmt::Display for DigestItemPrinter<'a, Hash> {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		let engine = |id: &[u8; 4]| String::from_utf8_lossy(id).into_owned();
+		match self.0 {
+			DigestItem::PreRuntime(id, data) if *id == BABE_ENGINE_ID => {
+				match PreDigest::decode(&mut &data[..]) {
+					Ok(pre_digest) => {
+						let kind = match pre_digest {
+							PreDigest::Primary(_) => "primary",
+							PreDigest::SecondaryPlain(_) => "secondary plain",
+							PreDigest::SecondaryVRF(_) => "secondary VRF",
+						};
+						write!(
+							fmt,
+							"PreRuntime(BABE): {} slot {} claimed by authority {}",
+							kind,
+							pre_digest.slot(),
+							pre_digest.authority_index(),
+						)
+					},
+					Err(e) => write!(fmt, "PreRuntime(BABE): invalid pre-digest ({})", e),
+				}
+			},
+			DigestItem::Consensus(id, data) if *id == BABE_ENGINE_ID => {
+				match ConsensusLog::decode(&mut &data[..]) {
+					Ok(ConsensusLog::NextEpochData(epoch)) => write!(
+						fmt,
+						"Consensus(BABE): next epoch with {} authorities, randomness 0x{}",
+						epoch.authorities.len(),
+						HexDisplay::from(&epoch.randomness),
+					),
+					Ok(ConsensusLog::OnDisabled(index)) =>
+						write!(fmt, "Consensus(BABE): authority {} disabled", index),
+					Ok(ConsensusLog::NextConfigData(NextConfigV1 { c, allowed_slots })) => write!(
+						fmt,
+						"Consensus(BABE): next config with c = {:?}, {:?}",
+						c,
+						allowed_slots,
+					),
+					Err(e) => write!(fmt, "Consensus(BABE): invalid log ({})", e),
+				}
+			},
+			DigestItem::PreRuntime(id, data) =>
+				write!(fmt, "PreRuntime({}): 0x{}", engine(id), HexDisplay::from(data)),
+			DigestItem::Consensus(id, data) =>
+				write!(fmt, "Consensus({}): 0x{}", engine(id), HexDisplay::from(data)),
+			DigestItem::Seal(id, data) =>
+				write!(fmt, "Seal({}): 0x{}", engine(id), HexDisplay::from(data)),
+			item => write!(fmt, "{:?}", item),
+		}
+	}
+}
+
 /// Aggregated error for `Inspector` operations.
 #[derive(Debug, derive_more::From, derive_more::Display)]
 pub enum Error {
@@ -281,6 +353,29 @@ mod tests {
 	use super::*;
 	use sp_core::hash::H160 as Hash;
 
+	#[test]
+	fn should_decode_babe_digests() {
+		use sp_consensus_babe::digests::SecondaryPlainPreDigest;
+		let print = |item: DigestItem<Hash>| DigestItemPrinter(&item).to_string();
+
+		let pre_digest = PreDigest::SecondaryPlain(SecondaryPlainPreDigest {
+			authority_index: 3,
+			slot: 42.into(),
+		});
+		assert_eq!(
+			print(DigestItem::PreRuntime(BABE_ENGINE_ID, pre_digest.encode())),
+			"PreRuntime(BABE): secondary plain slot 42 claimed by authority 3",
+		);
+		assert_eq!(
+			print(DigestItem::Consensus(BABE_ENGINE_ID, ConsensusLog::OnDisabled(1).encode())),
+			"Consensus(BABE): authority 1 disabled",
+		);
+		assert_eq!(
+			print(DigestItem::Seal(*b"FRNK", vec![1, 2])),
+			"Seal(FRNK): 0x0102",
+		);
+	}
+
 	#[test]
 	fn should_parse_block_strings() {
 		type BlockAddress = super::BlockAddress<Hash, u64>;
@@ -0,0 +1,453 @@
+// This file is part of Substrate.
+//
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Decoding of the extrinsics with the metadata of the runtime of their block, rather than
+//! with the native runtime.
+//!
+//! The metadata gives the pallets, the calls and the signed extensions of the runtime, the
+//! arguments of the calls are decoded from the names of their types. The addresses and the
+//! signatures of the signed extrinsics are those of the node, `MultiAddress` and
+//! `MultiSignature`.
+
+use std::fmt;
+use codec::{Compact, Decode, Encode};
+use frame_metadata::{
+	DecodeDifferent, FunctionMetadata, RuntimeMetadata, RuntimeMetadataPrefixed,
+	RuntimeMetadataV12, META_RESERVED,
+};
+use sp_core::{crypto::AccountId32, hexdisplay::HexDisplay};
+use sp_runtime::{MultiAddress, MultiSignature, generic::Era, traits::Block};
+
+use crate::{PrettyPrinter, fmt_header};
+
+/// The address of the signed extrinsics of the node.
+type Address = MultiAddress<AccountId32, u32>;
+
+/// The metadata of a runtime, as returned by its `Metadata_metadata` entry point.
+pub struct Metadata(RuntimeMetadataV12);
+
+impl Metadata {
+	/// Decode the given metadata, a SCALE-encoded `OpaqueMetadata`.
+	pub fn decode(encoded: &[u8]) -> Result<Self, String> {
+		let metadata = Vec::<u8>::decode(&mut &encoded[..])
+			.and_then(|metadata| RuntimeMetadataPrefixed::decode(&mut &metadata[..]))
+			.map_err(|e| format!("Invalid runtime metadata: {}", e))?;
+		match metadata {
+			RuntimeMetadataPrefixed(META_RESERVED, RuntimeMetadata::V12(metadata)) =>
+				Ok(Metadata(metadata)),
+			_ => Err("Unsupported runtime metadata, only V12 is supported".into()),
+		}
+	}
+
+	/// Returns the name of the given pallet and the metadata of the given call of it.
+	fn call(&self, pallet: u8, call: u8) -> Option<(&str, &FunctionMetadata)> {
+		let pallets = decoded(&self.0.modules)?;
+		let pallet = pallets.iter().find(|module| module.index == pallet)?;
+		let calls = decoded(pallet.calls.as_ref()?)?;
+		Some((decoded(&pallet.name)?, calls.get(call as usize)?))
+	}
+}
+
+fn hex(bytes: &[u8]) -> String {
+	format!("0x{}", HexDisplay::from(&bytes))
+}
+
+fn decoded<B, O>(value: &DecodeDifferent<B, O>) -> Option<&O> {
+	match value {
+		DecodeDifferent::Decoded(value) => Some(value),
+		DecodeDifferent::Encode(_) => None,
+	}
+}
+
+/// Prints the blocks and extrinsics, decoding the extrinsics with the metadata of a runtime.
+///
+/// The extrinsics which can't be decoded with it are printed as their bytes, with the error.
+pub struct MetadataPrinter(pub Metadata);
+
+impl<TBlock: Block> PrettyPrinter<TBlock> for MetadataPrinter {
+	fn fmt_block(&self, fmt: &mut fmt::Formatter, block: &TBlock) -> fmt::Result {
+		fmt_header(fmt, block)?;
+		writeln!(fmt, "Extrinsics ({})", block.extrinsics().len())?;
+		for (idx, ex) in block.extrinsics().iter().enumerate() {
+			writeln!(fmt, "- {}:", idx)?;
+			<MetadataPrinter as PrettyPrinter<TBlock>>::fmt_extrinsic(self, fmt, ex)?;
+		}
+		Ok(())
+	}
+
+	fn fmt_extrinsic(
+		&self,
+		fmt: &mut fmt::Formatter,
+		extrinsic: &TBlock::Extrinsic,
+	) -> fmt::Result {
+		let bytes = extrinsic.encode();
+		let mut decoded = String::new();
+		match decode_extrinsic(&self.0, &bytes, &mut decoded) {
+			Ok(()) => write!(fmt, "{}", decoded)?,
+			Err(e) => {
+				write!(fmt, "{}", decoded)?;
+				writeln!(fmt, " Could not decode the extrinsic: {}", e)?;
+			},
+		}
+		writeln!(fmt, " Bytes: {:?}", HexDisplay::from(&bytes))?;
+		Ok(())
+	}
+}
+
+type DecodeResult<T> = Result<T, String>;
+
+fn read<T: Decode>(input: &mut &[u8], what: &str) -> DecodeResult<T> {
+	T::decode(input).map_err(|e| format!("invalid {}: {}", what, e))
+}
+
+/// Decodes the given encoded extrinsic, writing its lines to `out`.
+fn decode_extrinsic(metadata: &Metadata, bytes: &[u8], out: &mut String) -> DecodeResult<()> {
+	use std::fmt::Write;
+
+	let body: Vec<u8> = read(&mut &bytes[..], "extrinsic")?;
+	let mut input = &body[..];
+	let version: u8 = read(&mut input, "version")?;
+	if version & 0b0111_1111 != metadata.0.extrinsic.version {
+		return Err(format!("unsupported extrinsic version {}", version & 0b0111_1111));
+	}
+
+	if version & 0b1000_0000 != 0 {
+		let address: Address = read(&mut input, "address")?;
+		let signature: MultiSignature = read(&mut input, "signature")?;
+		let signature = match signature {
+			MultiSignature::Ed25519(_) => "Ed25519",
+			MultiSignature::Sr25519(_) => "Sr25519",
+			MultiSignature::Ecdsa(_) => "ECDSA",
+		};
+		let _ = writeln!(out, " Signed by {} ({})", fmt_address(&address), signature);
+		for extension in &metadata.0.extrinsic.signed_extensions {
+			let extension = decoded(extension).map(String::as_str).unwrap_or_default();
+			if let Some(value) = decode_extension(extension, &mut input)? {
+				let _ = writeln!(out, " {}: {}", extension, value);
+			}
+		}
+	} else {
+		let _ = writeln!(out, " Unsigned");
+	}
+
+	let call = decode_call(metadata, &mut input);
+	let _ = writeln!(out, " Call: {}", call.as_ref().unwrap_or_else(|(call, _)| call));
+	match call {
+		Ok(_) if !input.is_empty() =>
+			Err(format!("{} bytes left after the call", input.len())),
+		Ok(_) => Ok(()),
+		Err((_, e)) => Err(e),
+	}
+}
+
+/// Decodes the additional data of the given signed extension, `None` if it has none.
+fn decode_extension(extension: &str, input: &mut &[u8]) -> DecodeResult<Option<String>> {
+	Ok(match extension {
+		"CheckSpecVersion" | "CheckTxVersion" | "CheckGenesis" | "CheckWeight" => None,
+		"CheckMortality" | "CheckEra" => Some(format!("{:?}", read::<Era>(input, extension)?)),
+		"CheckNonce" => Some(read::<Compact<u32>>(input, extension)?.0.to_string()),
+		"ChargeTransactionPayment" => Some(read::<Compact<u128>>(input, extension)?.0.to_string()),
+		extension => return Err(format!("unknown signed extension `{}`", extension)),
+	})
+}
+
+/// Decodes a call, e.g. `Balances.transfer(dest: ..., value: 1000)`.
+///
+/// On failure, the returned call has the arguments decoded before the error followed by the
+/// bytes left.
+fn decode_call(metadata: &Metadata, input: &mut &[u8]) -> Result<String, (String, String)> {
+	let (pallet, index) = read::<(u8, u8)>(input, "call index")
+		.map_err(|e| (hex(input), e))?;
+	let (pallet_name, call) = metadata.call(pallet, index).ok_or_else(|| (
+		hex(input),
+		format!("unknown call {} of pallet {}", index, pallet),
+	))?;
+
+	let arguments = decoded(&call.arguments).map(Vec::as_slice).unwrap_or_default();
+	let mut decoded_arguments = Vec::new();
+	for argument in arguments {
+		let name = decoded(&argument.name).map(String::as_str).unwrap_or_default();
+		let ty = decoded(&argument.ty).map(String::as_str).unwrap_or_default();
+		let start = *input;
+		match decode_value(metadata, ty, input) {
+			Ok(value) => decoded_arguments.push(format!("{}: {}", name, value)),
+			Err(e) => {
+				decoded_arguments.push(format!("{}: {}", name, hex(start)));
+				let call = fmt_call(pallet_name, call, &decoded_arguments);
+				return Err((call, format!("argument `{}`: {}", name, e)))
+			},
+		}
+	}
+	Ok(fmt_call(pallet_name, call, &decoded_arguments))
+}
+
+fn fmt_call(pallet: &str, call: &FunctionMetadata, arguments: &[String]) -> String {
+	let name = decoded(&call.name).map(String::as_str).unwrap_or_default();
+	format!("{}.{}({})", pallet, name, arguments.join(", "))
+}
+
+fn fmt_address(address: &Address) -> String {
+	match address {
+		MultiAddress::Id(account) => account.to_string(),
+		MultiAddress::Index(index) => format!("index {}", index),
+		MultiAddress::Raw(raw) => hex(raw),
+		MultiAddress::Address32(address) => hex(address),
+		MultiAddress::Address20(address) => hex(address),
+	}
+}
+
+/// Returns the given type of the metadata without its `T::` or `<T as Config>::` qualification
+/// and its whitespace, e.g. `Compact<Balance>` for `Compact<T::Balance>`.
+fn normalize(ty: &str) -> String {
+	let mut ty: String = ty.chars().filter(|c| !c.is_whitespace()).collect();
+	loop {
+		let start = ty.find("T::").filter(|start| {
+			*start == 0 || !ty[..*start].ends_with(|c: char| c.is_alphanumeric() || c == '_')
+		});
+		if let Some(start) = start {
+			ty.replace_range(start..start + 3, "");
+		} else if let Some(start) = ty.find("<Tas") {
+			match ty[start..].find(">::") {
+				Some(end) => ty.replace_range(start..start + end + 3, ""),
+				None => break,
+			}
+		} else {
+			break
+		}
+	}
+	ty
+}
+
+/// Splits the given comma-separated types, not splitting the generic arguments.
+fn split_types(types: &str) -> Vec<&str> {
+	let mut depth = 0;
+	let mut start = 0;
+	let mut split = Vec::new();
+	for (i, c) in types.char_indices() {
+		match c {
+			'<' | '(' | '[' => depth += 1,
+			'>' | ')' | ']' => depth -= 1,
+			',' if depth == 0 => {
+				split.push(&types[start..i]);
+				start = i + 1;
+			},
+			_ => {},
+		}
+	}
+	if start < types.len() {
+		split.push(&types[start..]);
+	}
+	split
+}
+
+/// Decodes a value of the given type of the metadata.
+fn decode_value(metadata: &Metadata, ty: &str, input: &mut &[u8]) -> DecodeResult<String> {
+	let ty = normalize(ty);
+	let generic = |name: &str| ty.strip_prefix(name)
+		.and_then(|ty| ty.strip_prefix('<'))
+		.and_then(|ty| ty.strip_suffix('>'));
+	let bytes = |input: &mut &[u8], len: usize| -> DecodeResult<String> {
+		if input.len() < len {
+			return Err(format!("expected {} bytes of `{}`", len, ty))
+		}
+		let (bytes, rest) = input.split_at(len);
+		*input = rest;
+		Ok(hex(bytes))
+	};
+
+	Ok(match ty.as_str() {
+		"bool" => read::<bool>(input, &ty)?.to_string(),
+		"u8" | "Percent" => read::<u8>(input, &ty)?.to_string(),
+		"u16" => read::<u16>(input, &ty)?.to_string(),
+		"u32" | "BlockNumber" | "Index" | "AccountIndex" | "Perbill" | "Permill" | "SessionIndex" |
+		"EraIndex" | "ReferendumIndex" | "PropIndex" | "ProposalIndex" | "MemberCount" =>
+			read::<u32>(input, &ty)?.to_string(),
+		"u64" | "Moment" | "Weight" => read::<u64>(input, &ty)?.to_string(),
+		"u128" | "Balance" => read::<u128>(input, &ty)?.to_string(),
+		"i8" => read::<i8>(input, &ty)?.to_string(),
+		"i16" => read::<i16>(input, &ty)?.to_string(),
+		"i32" => read::<i32>(input, &ty)?.to_string(),
+		"i64" => read::<i64>(input, &ty)?.to_string(),
+		"i128" => read::<i128>(input, &ty)?.to_string(),
+		"Vec<u8>" | "Bytes" => {
+			let len = read::<Compact<u32>>(input, &ty)?.0;
+			bytes(input, len as usize)?
+		},
+		"AccountId" | "AccountId32" => read::<AccountId32>(input, &ty)?.to_string(),
+		"Hash" | "H256" | "BlockHash" => bytes(input, 32)?,
+		"Call" => decode_call(metadata, input).map_err(|(_, e)| e)?,
+		ty if ty.ends_with("StaticLookup>::Source") || ty == "LookupSource" =>
+			fmt_address(&read::<Address>(input, ty)?),
+		ty if ty.starts_with("BalanceOf<") => read::<u128>(input, ty)?.to_string(),
+		_ => if generic("Compact").is_some() {
+			read::<Compact<u128>>(input, &ty)?.0.to_string()
+		} else if let Some(inner) = generic("Box") {
+			decode_value(metadata, inner, input)?
+		} else if let Some(inner) = generic("Option") {
+			match read::<u8>(input, &ty)? {
+				0 => "None".into(),
+				1 => format!("Some({})", decode_value(metadata, inner, input)?),
+				_ => return Err(format!("invalid `{}`", ty)),
+			}
+		} else if let Some(inner) = generic("Vec") {
+			let len = read::<Compact<u32>>(input, &ty)?.0;
+			let items = (0..len)
+				.map(|_| decode_value(metadata, inner, input))
+				.collect::<DecodeResult<Vec<_>>>()?;
+			format!("[{}]", items.join(", "))
+		} else if let Some(types) = ty.strip_prefix('(').and_then(|ty| ty.strip_suffix(')')) {
+			let items = split_types(types)
+				.into_iter()
+				.map(|ty| decode_value(metadata, ty, input))
+				.collect::<DecodeResult<Vec<_>>>()?;
+			format!("({})", items.join(", "))
+		} else if let Some(len) = ty.strip_prefix("[u8;").and_then(|ty| ty.strip_suffix(']')) {
+			let len = len.parse().map_err(|_| format!("unsupported type `{}`", ty))?;
+			bytes(input, len)?
+		} else {
+			return Err(format!("unsupported type `{}`", ty))
+		},
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_metadata::{
+		DecodeDifferent::Encode as Static, ErrorMetadata, ExtrinsicMetadata, FnEncode,
+		FunctionArgumentMetadata, ModuleConstantMetadata, ModuleMetadata,
+	};
+
+	fn metadata() -> Metadata {
+		fn calls() -> &'static [FunctionMetadata] {
+			&[
+				FunctionMetadata {
+					name: Static("remark"),
+					arguments: Static(&[
+						FunctionArgumentMetadata { name: Static("_remark"), ty: Static("Vec<u8>") },
+					]),
+					documentation: Static(&[]),
+				},
+				FunctionMetadata {
+					name: Static("transfer"),
+					arguments: Static(&[
+						FunctionArgumentMetadata {
+							name: Static("dest"),
+							ty: Static("<T::Lookup as StaticLookup>::Source"),
+						},
+						FunctionArgumentMetadata {
+							name: Static("value"),
+							ty: Static("Compact<T::Balance>"),
+						},
+					]),
+					documentation: Static(&[]),
+				},
+			]
+		}
+		fn no_constants() -> &'static [ModuleConstantMetadata] {
+			&[]
+		}
+		fn no_errors() -> &'static [ErrorMetadata] {
+			&[]
+		}
+		let metadata = RuntimeMetadataPrefixed(META_RESERVED, RuntimeMetadata::V12(
+			RuntimeMetadataV12 {
+				modules: Static(&[ModuleMetadata {
+					name: Static("Pallet"),
+					storage: None,
+					calls: Some(Static(FnEncode(calls))),
+					event: None,
+					constants: Static(FnEncode(no_constants)),
+					errors: Static(FnEncode(no_errors)),
+					index: 5,
+				}]),
+				extrinsic: ExtrinsicMetadata {
+					version: 4,
+					signed_extensions: vec![Static("CheckEra"), Static("CheckNonce")],
+				},
+			}
+		));
+		Metadata::decode(&metadata.encode().encode()).unwrap()
+	}
+
+	fn decode(extrinsic: Vec<u8>) -> (String, DecodeResult<()>) {
+		let mut out = String::new();
+		let result = decode_extrinsic(&metadata(), &extrinsic.encode(), &mut out);
+		(out, result)
+	}
+
+	#[test]
+	fn decodes_the_extrinsics_with_the_metadata() {
+		let account = AccountId32::new([1; 32]);
+		let mut transfer = vec![5, 1];
+		transfer.extend(Address::Id(account.clone()).encode());
+		transfer.extend(Compact(1000u128).encode());
+
+		let mut unsigned = vec![4];
+		unsigned.extend(&transfer);
+		assert_eq!(
+			decode(unsigned),
+			(
+				format!(" Unsigned\n Call: Pallet.transfer(dest: {}, value: 1000)\n", account),
+				Ok(()),
+			),
+		);
+
+		let mut signed = vec![0b1000_0100];
+		signed.extend(Address::Id(account.clone()).encode());
+		signed.extend(MultiSignature::Sr25519(Default::default()).encode());
+		signed.extend(Era::Immortal.encode());
+		signed.extend(Compact(3u32).encode());
+		signed.extend(&[5, 0, 8, 1, 2]);
+		assert_eq!(
+			decode(signed),
+			(
+				format!(
+					" Signed by {} (Sr25519)\n CheckEra: Immortal\n CheckNonce: 3\n \
+					Call: Pallet.remark(_remark: 0x0102)\n",
+					account,
+				),
+				Ok(()),
+			),
+		);
+	}
+
+	#[test]
+	fn reports_the_extrinsics_not_matching_the_metadata() {
+		assert_eq!(
+			decode(vec![4, 6, 0]),
+			(" Unsigned\n Call: 0x\n".into(), Err("unknown call 0 of pallet 6".into())),
+		);
+		assert_eq!(
+			decode(vec![4, 5, 0, 8, 1]),
+			(
+				" Unsigned\n Call: Pallet.remark(_remark: 0x0801)\n".into(),
+				Err("argument `_remark`: expected 2 bytes of `Vec<u8>`".into()),
+			),
+		);
+		assert_eq!(decode(vec![3, 5, 0, 0]).1, Err("unsupported extrinsic version 3".into()));
+	}
+
+	#[test]
+	fn normalizes_the_types() {
+		assert_eq!(normalize("Compact<T::Balance>"), "Compact<Balance>");
+		assert_eq!(normalize("Box<<T as Config>::Call>"), "Box<Call>");
+		assert_eq!(normalize("Vec<(T::AccountId, BalanceOf<T>)>"), "Vec<(AccountId,BalanceOf<T>)>");
+		assert_eq!(split_types("AccountId,BalanceOf<T,I>"), vec!["AccountId", "BalanceOf<T,I>"]);
+	}
+}
@@ -113,7 +113,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// and set impl_version to 0. If only runtime
 	// implementation changes and behavior does not, then leave spec_version as
 	// is and increment impl_version.
-	spec_version: 265,
+	spec_version: 266,
 	impl_version: 0,
 	apis: RUNTIME_API_VERSIONS,
 	transaction_version: 2,
@@ -1373,6 +1373,11 @@ impl_runtime_apis! {
 		fn query_fee_details(uxt: <Block as BlockT>::Extrinsic, len: u32) -> FeeDetails<Balance> {
 			TransactionPayment::query_fee_details(uxt, len)
 		}
+		fn query_info_batch(
+			uxts: Vec<(<Block as BlockT>::Extrinsic, u32)>,
+		) -> Vec<RuntimeDispatchInfo<Balance>> {
+			uxts.into_iter().map(|(uxt, len)| TransactionPayment::query_info(uxt, len)).collect()
+		}
 	}
 
 	impl pallet_mmr::primitives::MmrApi<
@@ -1410,6 +1415,12 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl sp_session::DisabledValidators<Block> for Runtime {
+		fn disabled_validators() -> Vec<u32> {
+			Session::disabled_validators()
+		}
+	}
+
 	#[cfg(feature = "try-runtime")]
 	impl frame_try_runtime::TryRuntime<Block> for Runtime {
 		fn on_runtime_upgrade() -> Result<(Weight, Weight), sp_runtime::RuntimeString> {
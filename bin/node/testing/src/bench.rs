@@ -236,6 +236,7 @@ impl DatabaseType {
 			},
 			Self::ParityDb => sc_client_db::DatabaseSettingsSrc::ParityDb {
 				path,
+				migrate_from: None,
 			}
 		}
 	}
@@ -410,16 +411,23 @@ impl BenchDb {
 		let db_config = sc_client_db::DatabaseSettings {
 			state_cache_size: 16*1024*1024,
 			state_cache_child_ratio: Some((0, 100)),
+			trie_cache_size: 0,
 			state_pruning: PruningMode::ArchiveAll,
 			source: database_type.into_settings(dir.into()),
 			keep_blocks: sc_client_db::KeepBlocks::All,
 			transaction_storage: sc_client_db::TransactionStorageMode::BlockBody,
+			index_extrinsics: false,
+			compress_blocks: false,
+			trie_cache_warm_up: false,
+			cold_storage: None,
+			max_canonicalization_delay: None,
 		};
 		let task_executor = TaskExecutor::new();
 
 		let (client, backend) = sc_service::new_client(
 			db_config,
-			NativeExecutor::new(WasmExecutionMethod::Compiled, None, 8),
+			sc_service::CANONICALIZATION_DELAY,
+			NativeExecutor::new(WasmExecutionMethod::Compiled(Default::default()), None, 8),
 			&keyring.generate_genesis(),
 			None,
 			None,
@@ -632,6 +640,7 @@ impl Profile {
 				block_construction: ExecutionStrategy::AlwaysWasm,
 				offchain_worker: ExecutionStrategy::AlwaysWasm,
 				other: ExecutionStrategy::AlwaysWasm,
+				methods: Default::default(),
 			},
 			Profile::Native => ExecutionStrategies {
 				syncing: ExecutionStrategy::NativeElseWasm,
@@ -639,6 +648,7 @@ impl Profile {
 				block_construction: ExecutionStrategy::NativeElseWasm,
 				offchain_worker: ExecutionStrategy::NativeElseWasm,
 				other: ExecutionStrategy::NativeElseWasm,
+				methods: Default::default(),
 			}
 		}
 	}
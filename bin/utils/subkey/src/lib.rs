@@ -42,7 +42,7 @@ pub enum Subkey {
 	/// Print the peer ID corresponding to the node key in the given file
 	InspectNodeKey(InspectNodeKeyCmd),
 
-	/// Sign a message, with a given (secret) key.
+	/// Sign a message, with a given (secret) key or a key of a keystore.
 	Sign(SignCmd),
 
 	/// Generate a seed that provides a vanity address.
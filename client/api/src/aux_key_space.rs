@@ -0,0 +1,158 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Key spaces of the auxiliary storage reserved by downstream crates.
+//!
+//! A crate keeping its own data in the node database, e.g. a chain-specific index, reserves
+//! a key space under a unique name. The keys of a key space are prefixed so they never collide
+//! with the keys of other key spaces or with the other auxiliary data. The changes of a key space
+//! are written atomically, either on their own or along with the import of a block.
+
+use std::collections::{HashMap, HashSet};
+
+use codec::Encode;
+use parking_lot::Mutex;
+
+use crate::backend::AuxStore;
+
+/// The prefix of the keys of all the key spaces.
+const KEY_SPACE_PREFIX: &[u8] = b":aux_key_space:";
+
+/// The names of the reserved key spaces.
+#[derive(Debug, Default)]
+pub struct AuxKeySpaces(Mutex<HashSet<Vec<u8>>>);
+
+impl AuxKeySpaces {
+	/// Reserve the key space with the given name.
+	///
+	/// Fails if it is already reserved, so two crates can't write to the same keys.
+	pub fn reserve(&self, name: &[u8]) -> sp_blockchain::Result<AuxKeySpace> {
+		if !self.0.lock().insert(name.to_vec()) {
+			return Err(sp_blockchain::Error::Backend(format!(
+				"Auxiliary key space {} is already reserved",
+				String::from_utf8_lossy(name),
+			)));
+		}
+
+		let mut prefix = KEY_SPACE_PREFIX.to_vec();
+		// the name is prefixed with its length, so a key space never contains another.
+		name.encode_to(&mut prefix);
+		Ok(AuxKeySpace { prefix })
+	}
+}
+
+/// A reserved key space of the auxiliary storage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuxKeySpace {
+	prefix: Vec<u8>,
+}
+
+impl AuxKeySpace {
+	/// Returns the key of the auxiliary storage the given key of the key space is stored at.
+	pub fn key(&self, key: &[u8]) -> Vec<u8> {
+		let mut prefixed = Vec::with_capacity(self.prefix.len() + key.len());
+		prefixed.extend_from_slice(&self.prefix);
+		prefixed.extend_from_slice(key);
+		prefixed
+	}
+
+	/// Returns the value of the given key of the key space.
+	pub fn get<S: AuxStore + ?Sized>(
+		&self,
+		store: &S,
+		key: &[u8],
+	) -> sp_blockchain::Result<Option<Vec<u8>>> {
+		store.get_aux(&self.key(key))
+	}
+
+	/// Start a batch of changes of the key space, to be written atomically.
+	pub fn batch(&self) -> AuxBatch {
+		AuxBatch { key_space: self.clone(), changes: Vec::new() }
+	}
+}
+
+/// Changes of a key space, written atomically.
+#[derive(Debug, Clone)]
+pub struct AuxBatch {
+	key_space: AuxKeySpace,
+	changes: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+}
+
+impl AuxBatch {
+	/// Set the value of the given key of the key space.
+	pub fn set(&mut self, key: &[u8], value: Vec<u8>) -> &mut Self {
+		self.changes.push((self.key_space.key(key), Some(value)));
+		self
+	}
+
+	/// Remove the value of the given key of the key space.
+	pub fn remove(&mut self, key: &[u8]) -> &mut Self {
+		self.changes.push((self.key_space.key(key), None));
+		self
+	}
+
+	/// Write the changes to the given store.
+	pub fn commit<S: AuxStore + ?Sized>(self, store: &S) -> sp_blockchain::Result<()> {
+		// later changes of a key override the earlier ones, while `insert_aux` applies
+		// all the insertions before the deletions.
+		let latest = self.changes.into_iter().collect::<HashMap<_, _>>();
+
+		let insert = latest.iter()
+			.filter_map(|(key, value)| value.as_ref().map(|value| (&key[..], &value[..])))
+			.collect::<Vec<_>>();
+		let delete = latest.iter()
+			.filter(|(_, value)| value.is_none())
+			.map(|(key, _)| &key[..])
+			.collect::<Vec<_>>();
+		store.insert_aux(&insert, &delete)
+	}
+
+	/// Returns the changes as auxiliary data of a block import (e.g. to add to the
+	/// `auxiliary` changes of its `BlockImportParams`), so they are only written
+	/// along with the block.
+	pub fn into_aux(self) -> Vec<(Vec<u8>, Option<Vec<u8>>)> {
+		self.changes
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{backend::Backend as _, in_mem::Backend};
+	use substrate_test_runtime::Block;
+
+	#[test]
+	fn key_spaces_are_isolated() {
+		let backend = Backend::<Block>::new();
+		let index = backend.reserve_aux_key_space(b"index").unwrap();
+		let other = backend.reserve_aux_key_space(b"index_other").unwrap();
+		assert!(backend.reserve_aux_key_space(b"index").is_err());
+
+		let mut batch = index.batch();
+		batch.set(b"a", vec![1]).set(b"b", vec![2]).remove(b"b");
+		batch.commit(&backend).unwrap();
+		let mut batch = other.batch();
+		batch.set(b"a", vec![3]);
+		batch.commit(&backend).unwrap();
+
+		assert_eq!(index.get(&backend, b"a").unwrap(), Some(vec![1]));
+		assert_eq!(index.get(&backend, b"b").unwrap(), None);
+		assert_eq!(other.get(&backend, b"a").unwrap(), Some(vec![3]));
+		assert_eq!(AuxStore::get_aux(&backend, b"a").unwrap(), None);
+	}
+}
@@ -34,7 +34,7 @@ use crate::{
 		Backend as BlockchainBackend, well_known_cache_keys
 	},
 	light::RemoteBlockchain,
-	UsageInfo,
+	AuxKeySpace, AuxKeySpaces, UsageInfo,
 };
 use sp_blockchain;
 use sp_consensus::BlockOrigin;
@@ -273,6 +273,7 @@ pub trait AuxStore {
 /// An `Iterator` that iterates keys in a given block under a prefix.
 pub struct KeyIterator<'a, State, Block> {
 	state: State,
+	child_storage: Option<ChildInfo>,
 	prefix: Option<&'a StorageKey>,
 	current_key: Vec<u8>,
 	_phantom: PhantomData<Block>,
@@ -283,6 +284,23 @@ impl <'a, State, Block> KeyIterator<'a, State, Block> {
 	pub fn new(state: State, prefix: Option<&'a StorageKey>, current_key: Vec<u8>) -> Self {
 		Self {
 			state,
+			child_storage: None,
+			prefix,
+			current_key,
+			_phantom: PhantomData,
+		}
+	}
+
+	/// Create a `KeyIterator` instance for a child storage.
+	pub fn new_child(
+		state: State,
+		child_info: ChildInfo,
+		prefix: Option<&'a StorageKey>,
+		current_key: Vec<u8>,
+	) -> Self {
+		Self {
+			state,
+			child_storage: Some(child_info),
 			prefix,
 			current_key,
 			_phantom: PhantomData,
@@ -297,10 +315,11 @@ impl<'a, State, Block> Iterator for KeyIterator<'a, State, Block> where
 	type Item = StorageKey;
 
 	fn next(&mut self) -> Option<Self::Item> {
-		let next_key = self.state
-			.next_storage_key(&self.current_key)
-			.ok()
-			.flatten()?;
+		let next_key = match self.child_storage.as_ref() {
+			Some(child_info) => self.state.next_child_storage_key(child_info, &self.current_key),
+			None => self.state.next_storage_key(&self.current_key),
+		};
+		let next_key = next_key.ok().flatten()?;
 		// this terminates the iterator the first time it fails.
 		if let Some(prefix) = self.prefix {
 			if !next_key.starts_with(&prefix.0[..]) {
@@ -312,6 +331,15 @@ impl<'a, State, Block> Iterator for KeyIterator<'a, State, Block> where
 	}
 }
 
+/// Provides access to the state of the chain at a given block.
+pub trait StateProvider<Block: BlockT> {
+	/// The state backend.
+	type State: StateBackend<HashFor<Block>>;
+
+	/// Returns the state at the given block.
+	fn state_at(&self, id: &BlockId<Block>) -> sp_blockchain::Result<Self::State>;
+}
+
 /// Provides acess to storage primitives
 pub trait StorageProvider<Block: BlockT, B: Backend<Block>> {
 	/// Given a `BlockId` and a key, return the value under the key in that block.
@@ -338,6 +366,16 @@ pub trait StorageProvider<Block: BlockT, B: Backend<Block>> {
 		start_key: Option<&StorageKey>
 	) -> sp_blockchain::Result<KeyIterator<'a, B::State, Block>>;
 
+	/// Given a `BlockId`, a key prefix and a child storage key, return a `KeyIterator` that
+	/// iterates matching child storage keys in that block.
+	fn child_storage_keys_iter<'a>(
+		&self,
+		id: &BlockId<Block>,
+		child_info: ChildInfo,
+		prefix: Option<&'a StorageKey>,
+		start_key: Option<&StorageKey>
+	) -> sp_blockchain::Result<KeyIterator<'a, B::State, Block>>;
+
 	/// Given a `BlockId`, a key and a child storage key, return the value under the key in that block.
 	fn child_storage(
 		&self,
@@ -487,6 +525,36 @@ pub trait Backend<Block: BlockT>: AuxStore + Send + Sync {
 	/// something that the import of a block would interfere with, e.g. importing
 	/// a new block or calculating the best head.
 	fn get_import_lock(&self) -> &RwLock<()>;
+
+	/// Keep the state and the body of the given block from being pruned, until it is
+	/// unpinned as many times as it was pinned.
+	///
+	/// Fails if the block is unknown or its state is already pruned. Backends that don't
+	/// prune blocks keep them all anyway.
+	fn pin_block(&self, _hash: &Block::Hash) -> sp_blockchain::Result<()> {
+		Ok(())
+	}
+
+	/// Release a pin of the given block, see `pin_block`. Once it isn't pinned anymore,
+	/// the block is pruned if it should have been already.
+	fn unpin_block(&self, _hash: &Block::Hash) {}
+
+	/// Returns the key spaces of the auxiliary storage reserved so far.
+	fn aux_key_spaces(&self) -> &AuxKeySpaces;
+
+	/// Reserve the key space of the auxiliary storage with the given name, so a downstream
+	/// crate can keep its own data (e.g. an index) in the database.
+	///
+	/// Fails if the key space is already reserved.
+	fn reserve_aux_key_space(&self, name: &[u8]) -> sp_blockchain::Result<AuxKeySpace> {
+		self.aux_key_spaces().reserve(name)
+	}
+
+	/// Write a consistent copy of the database to the directory at `path`, while the node keeps
+	/// running.
+	fn snapshot(&self, _path: &std::path::Path) -> sp_blockchain::Result<()> {
+		Err(sp_blockchain::Error::Backend("Snapshots are not supported by the backend".into()))
+	}
 }
 
 /// Changes trie storage that supports pruning.
@@ -26,10 +26,11 @@ use sp_runtime::{
 	Justification,
 };
 use sp_consensus::BlockOrigin;
+use sp_version::RuntimeVersion;
 
 use crate::blockchain::Info;
 use crate::notifications::StorageEventStream;
-use sp_utils::mpsc::TracingUnboundedReceiver;
+use sp_utils::mpsc::{TracingUnboundedReceiver, TracingUnboundedSender};
 use sp_blockchain;
 
 /// Type that implements `futures::Stream` of block import events.
@@ -38,6 +39,10 @@ pub type ImportNotifications<Block> = TracingUnboundedReceiver<BlockImportNotifi
 /// A stream of block finality notifications.
 pub type FinalityNotifications<Block> = TracingUnboundedReceiver<FinalityNotification<Block>>;
 
+/// A stream of runtime upgrade notifications.
+pub type RuntimeUpgradeNotifications<Block> =
+	TracingUnboundedReceiver<RuntimeUpgradeNotification<Block>>;
+
 /// Expected hashes of blocks at given heights.
 ///
 /// This may be used as chain spec extension to set trusted checkpoints, i.e.
@@ -76,6 +81,13 @@ pub trait BlockchainEvents<Block: BlockT> {
 	) -> sp_blockchain::Result<StorageEventStream<Block::Hash>>;
 }
 
+/// A source of runtime upgrade notifications.
+pub trait RuntimeUpgradeEvents<Block: BlockT> {
+	/// Get a stream of the runtime upgrades, notified at the finalized blocks the `:code` changed
+	/// at. Only the last block of the blocks finalized at once is checked.
+	fn runtime_upgrade_notification_stream(&self) -> RuntimeUpgradeNotifications<Block>;
+}
+
 /// Interface for fetching block data.
 pub trait BlockBackend<Block: BlockT> {
 	/// Get block body by ID. Returns `None` if the body is not stored.
@@ -106,6 +118,15 @@ pub trait BlockBackend<Block: BlockT> {
 	fn have_extrinsic(&self, hash: &Block::Hash) -> sp_blockchain::Result<bool> {
 		Ok(self.extrinsic(hash)?.is_some())
 	}
+
+	/// Get the hash of the best chain block the extrinsic with the given hash is included in,
+	/// and its index in the block, if extrinsics are indexed.
+	fn extrinsic_location(
+		&self,
+		_hash: &Block::Hash,
+	) -> sp_blockchain::Result<Option<(Block::Hash, u32)>> {
+		Ok(None)
+	}
 }
 
 /// Provide a list of potential uncle headers for a given block.
@@ -170,6 +191,8 @@ pub struct StateDbMemoryInfo {
 pub struct MemoryInfo {
 	/// Size of state cache.
 	pub state_cache: MemorySize,
+	/// Size of the trie node and value cache.
+	pub trie_cache: MemorySize,
 	/// Size of backend database cache.
 	pub database_cache: MemorySize,
 	/// Size of the state db.
@@ -201,6 +224,14 @@ pub struct IoInfo {
 	pub state_writes_cache: u64,
 	/// State write (trie nodes) to backend db.
 	pub state_writes_nodes: u64,
+	/// Trie node reads found in the trie node cache.
+	pub trie_cache_hits: u64,
+	/// Trie node reads missing from the trie node cache.
+	pub trie_cache_misses: u64,
+	/// State value reads found in the trie value cache.
+	pub trie_value_cache_hits: u64,
+	/// State value reads missing from the trie value cache.
+	pub trie_value_cache_misses: u64,
 }
 
 /// Usage statistics for running client instance.
@@ -220,10 +251,13 @@ impl fmt::Display for UsageInfo {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		write!(
 			f,
-			"caches: ({} state, {} db overlay), \
+			"caches: ({} state, {} trie nodes, {} db overlay), \
 			 state db: ({} non-canonical, {} pruning, {} pinned), \
-			 i/o: ({} tx, {} write, {} read, {} avg tx, {}/{} key cache reads/total, {} trie nodes writes)",
+			 i/o: ({} tx, {} write, {} read, {} avg tx, {}/{} key cache reads/total, \
+			 {}/{} trie node cache hits/misses, {}/{} trie value cache hits/misses, \
+			 {} trie nodes writes)",
 			self.memory.state_cache,
+			self.memory.trie_cache,
 			self.memory.database_cache,
 			self.memory.state_db.non_canonical,
 			self.memory.state_db.pruning.unwrap_or_default(),
@@ -234,6 +268,10 @@ impl fmt::Display for UsageInfo {
 			self.io.average_transaction_size,
 			self.io.state_reads_cache,
 			self.io.state_reads,
+			self.io.trie_cache_hits,
+			self.io.trie_cache_misses,
+			self.io.trie_value_cache_hits,
+			self.io.trie_value_cache_misses,
 			self.io.state_writes_nodes,
 		)
 	}
@@ -254,6 +292,10 @@ pub struct BlockImportNotification<Block: BlockT> {
 	///
 	/// If `None`, there was no re-org while importing.
 	pub tree_route: Option<Arc<sp_blockchain::TreeRoute<Block>>>,
+	/// Keeps the block from being pruned while the notification is in use.
+	///
+	/// `None` if the block couldn't be pinned.
+	pub unpin_handle: Option<UnpinHandle<Block>>,
 }
 
 /// Summary of a finalized block.
@@ -263,6 +305,58 @@ pub struct FinalityNotification<Block: BlockT> {
 	pub hash: Block::Hash,
 	/// Imported block header.
 	pub header: Block::Header,
+	/// Keeps the block from being pruned while the notification is in use.
+	///
+	/// `None` if the block couldn't be pinned.
+	pub unpin_handle: Option<UnpinHandle<Block>>,
+}
+
+/// The runtime of the chain changed at a finalized block.
+#[derive(Clone, Debug)]
+pub struct RuntimeUpgradeNotification<Block: BlockT> {
+	/// The hash of the finalized block whose state contains the new runtime.
+	pub hash: Block::Hash,
+	/// The number of the finalized block whose state contains the new runtime.
+	pub number: NumberFor<Block>,
+	/// The version of the runtime before the upgrade.
+	pub old_version: RuntimeVersion,
+	/// The version of the runtime after the upgrade.
+	pub new_version: RuntimeVersion,
+}
+
+/// Keeps a block pinned in the backend, so it isn't pruned, until the handle and all its
+/// clones are dropped.
+///
+/// The block is unpinned by whoever pinned it, once it receives the identifier of the pin
+/// sent when the handle is dropped. It may also be unpinned earlier, if the subscriber holding
+/// the handle lags too far behind.
+#[derive(Clone, Debug)]
+pub struct UnpinHandle<Block: BlockT>(Arc<UnpinHandleInner<Block>>);
+
+#[derive(Debug)]
+struct UnpinHandleInner<Block: BlockT> {
+	hash: Block::Hash,
+	pin: u64,
+	unpin_sender: TracingUnboundedSender<u64>,
+}
+
+impl<Block: BlockT> UnpinHandle<Block> {
+	/// Create a handle of the given pin of a block, sending the identifier of the pin to
+	/// `unpin_sender` once dropped.
+	pub fn new(hash: Block::Hash, pin: u64, unpin_sender: TracingUnboundedSender<u64>) -> Self {
+		UnpinHandle(Arc::new(UnpinHandleInner { hash, pin, unpin_sender }))
+	}
+
+	/// Returns the hash of the pinned block.
+	pub fn hash(&self) -> &Block::Hash {
+		&self.0.hash
+	}
+}
+
+impl<Block: BlockT> Drop for UnpinHandleInner<Block> {
+	fn drop(&mut self) {
+		let _ = self.unpin_sender.unbounded_send(self.pin);
+	}
 }
 
 impl<B: BlockT> TryFrom<BlockImportNotification<B>> for sp_transaction_pool::ChainEvent<B> {
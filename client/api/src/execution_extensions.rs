@@ -22,11 +22,12 @@
 //! strategy for the runtime calls and provide the right `Externalities`
 //! extensions to support APIs for particular execution context & capabilities.
 
-use std::sync::{Weak, Arc};
+use std::{collections::HashMap, sync::{Weak, Arc}};
 use codec::Decode;
 use sp_core::{
 	ExecutionContext,
 	offchain::{self, OffchainExt, TransactionPoolExt},
+	traits::{MemoryLimits, MemoryLimitsExt},
 };
 use sp_keystore::{KeystoreExt, SyncCryptoStorePtr};
 use sp_runtime::{
@@ -50,6 +51,16 @@ pub struct ExecutionStrategies {
 	pub offchain_worker: ExecutionStrategy,
 	/// Execution strategy used in other cases.
 	pub other: ExecutionStrategy,
+	/// Execution strategies of individual runtime API methods, e.g. `Core_execute_block`,
+	/// used instead of the strategy of the context they are called in.
+	pub methods: HashMap<String, ExecutionStrategy>,
+}
+
+impl ExecutionStrategies {
+	/// Returns the strategy of the given runtime API method, `default` unless it is overridden.
+	pub fn for_method(&self, method: &str, default: ExecutionStrategy) -> ExecutionStrategy {
+		self.methods.get(method).copied().unwrap_or(default)
+	}
 }
 
 impl Default for ExecutionStrategies {
@@ -60,10 +71,24 @@ impl Default for ExecutionStrategies {
 			block_construction: ExecutionStrategy::AlwaysWasm,
 			offchain_worker: ExecutionStrategy::NativeWhenPossible,
 			other: ExecutionStrategy::NativeElseWasm,
+			methods: Default::default(),
 		}
 	}
 }
 
+/// Memory limits of the runtime calls, by execution context.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExecutionMemoryLimits {
+	/// Limits of the calls syncing, importing or constructing blocks.
+	///
+	/// A node can't import the blocks of a runtime exceeding them.
+	pub importing: MemoryLimits,
+	/// Limits of the other calls, e.g. of the RPC clients and the offchain workers.
+	///
+	/// They should cap the heap pages of the calls, see `MemoryLimits::cap_heap_pages`.
+	pub other: MemoryLimits,
+}
+
 /// Generate the starting set of ExternalitiesExtensions based upon the given capabilities
 pub trait ExtensionsFactory: Send + Sync {
 	/// Make `Extensions` for given `Capabilities`.
@@ -83,6 +108,7 @@ impl ExtensionsFactory for () {
 /// for each call, based on required `Capabilities`.
 pub struct ExecutionExtensions<Block: traits::Block> {
 	strategies: ExecutionStrategies,
+	memory_limits: ExecutionMemoryLimits,
 	keystore: Option<SyncCryptoStorePtr>,
 	// FIXME: these two are only RwLock because of https://github.com/paritytech/substrate/issues/4587
 	//        remove when fixed.
@@ -98,6 +124,7 @@ impl<Block: traits::Block> Default for ExecutionExtensions<Block> {
 	fn default() -> Self {
 		Self {
 			strategies: Default::default(),
+			memory_limits: Default::default(),
 			keystore: None,
 			transaction_pool: RwLock::new(None),
 			extensions_factory: RwLock::new(Box::new(())),
@@ -115,17 +142,29 @@ impl<Block: traits::Block> ExecutionExtensions<Block> {
 		let extensions_factory = Box::new(());
 		Self {
 			strategies,
+			memory_limits: Default::default(),
 			keystore,
 			extensions_factory: RwLock::new(extensions_factory),
 			transaction_pool,
 		}
 	}
 
+	/// Limit the memory of the runtime calls.
+	pub fn with_memory_limits(mut self, memory_limits: ExecutionMemoryLimits) -> Self {
+		self.memory_limits = memory_limits;
+		self
+	}
+
 	/// Get a reference to the execution strategies.
 	pub fn strategies(&self) -> &ExecutionStrategies {
 		&self.strategies
 	}
 
+	/// Get a reference to the memory limits of the runtime calls.
+	pub fn memory_limits(&self) -> &ExecutionMemoryLimits {
+		&self.memory_limits
+	}
+
 	/// Set the new extensions_factory
 	pub fn set_extensions_factory(&self, maker: Box<dyn ExtensionsFactory>) {
 		*self.extensions_factory.write() = maker;
@@ -164,6 +203,16 @@ impl<Block: traits::Block> ExecutionExtensions<Block> {
 			}
 		}
 
+		let memory_limits = match context {
+			ExecutionContext::Syncing |
+			ExecutionContext::Importing |
+			ExecutionContext::BlockConstruction => self.memory_limits.importing,
+			ExecutionContext::OffchainCall(_) => self.memory_limits.other,
+		};
+		if memory_limits.max_heap_pages.is_some() {
+			extensions.register(MemoryLimitsExt(memory_limits));
+		}
+
 		if let ExecutionContext::OffchainCall(Some(ext)) = context {
 			extensions.register(
 				OffchainExt::new(offchain::LimitedExternalities::new(capabilities, ext.0)),
@@ -173,7 +222,8 @@ impl<Block: traits::Block> ExecutionExtensions<Block> {
 		extensions
 	}
 
-	/// Create `ExecutionManager` and `Extensions` for given offchain call.
+	/// Create `ExecutionManager` and `Extensions` for given offchain call of the given
+	/// runtime API method.
 	///
 	/// Based on the execution context and capabilities it produces
 	/// the right manager and extensions object to support desired set of APIs.
@@ -181,22 +231,20 @@ impl<Block: traits::Block> ExecutionExtensions<Block> {
 		&self,
 		at: &BlockId<Block>,
 		context: ExecutionContext,
+		method: &str,
 	) -> (
 		ExecutionManager<DefaultHandler<R, E>>,
 		Extensions,
 	) {
-		let manager = match context {
-			ExecutionContext::BlockConstruction =>
-				self.strategies.block_construction.get_manager(),
-			ExecutionContext::Syncing =>
-				self.strategies.syncing.get_manager(),
-			ExecutionContext::Importing =>
-				self.strategies.importing.get_manager(),
+		let strategy = match context {
+			ExecutionContext::BlockConstruction => self.strategies.block_construction,
+			ExecutionContext::Syncing => self.strategies.syncing,
+			ExecutionContext::Importing => self.strategies.importing,
 			ExecutionContext::OffchainCall(Some((_, capabilities))) if capabilities.has_all() =>
-				self.strategies.offchain_worker.get_manager(),
-			ExecutionContext::OffchainCall(_) =>
-				self.strategies.other.get_manager(),
+				self.strategies.offchain_worker,
+			ExecutionContext::OffchainCall(_) => self.strategies.other,
 		};
+		let manager = self.strategies.for_method(method, strategy).get_manager();
 
 		(manager, self.extensions(at, context))
 	}
@@ -39,6 +39,7 @@ use crate::{
 	blockchain::{
 		self, BlockStatus, HeaderBackend, well_known_cache_keys::Id as CacheKeyId
 	},
+	AuxKeySpaces,
 	UsageInfo,
 	light,
 	leaves::LeafSet,
@@ -595,6 +596,7 @@ pub struct Backend<Block: BlockT> where Block::Hash: Ord {
 	states: RwLock<HashMap<Block::Hash, InMemoryBackend<HashFor<Block>>>>,
 	blockchain: Blockchain<Block>,
 	import_lock: RwLock<()>,
+	aux_key_spaces: AuxKeySpaces,
 }
 
 impl<Block: BlockT> Backend<Block> where Block::Hash: Ord {
@@ -604,6 +606,7 @@ impl<Block: BlockT> Backend<Block> where Block::Hash: Ord {
 			states: RwLock::new(HashMap::new()),
 			blockchain: Blockchain::new(),
 			import_lock: Default::default(),
+			aux_key_spaces: Default::default(),
 		}
 	}
 }
@@ -737,6 +740,10 @@ impl<Block: BlockT> backend::Backend<Block> for Backend<Block> where Block::Hash
 	fn get_import_lock(&self) -> &RwLock<()> {
 		&self.import_lock
 	}
+
+	fn aux_key_spaces(&self) -> &AuxKeySpaces {
+		&self.aux_key_spaces
+	}
 }
 
 impl<Block: BlockT> backend::LocalBackend<Block> for Backend<Block> where Block::Hash: Ord {}
@@ -19,6 +19,7 @@
 //! Substrate client interfaces.
 #![warn(missing_docs)]
 
+pub mod aux_key_space;
 pub mod backend;
 pub mod call_executor;
 pub mod client;
@@ -31,6 +32,7 @@ pub mod notifications;
 pub mod proof_provider;
 
 pub use sp_blockchain as blockchain;
+pub use aux_key_space::{AuxBatch, AuxKeySpace, AuxKeySpaces};
 pub use backend::*;
 pub use notifications::*;
 pub use call_executor::*;
@@ -39,7 +41,7 @@ pub use light::*;
 pub use notifications::*;
 pub use proof_provider::*;
 
-pub use sp_state_machine::{StorageProof, ExecutionStrategy};
+pub use sp_state_machine::{StorageProof, CompactProof, ExecutionStrategy};
 
 /// Usage Information Provider interface
 ///
@@ -21,9 +21,20 @@ use sp_runtime::{
 	generic::BlockId,
 	traits::{Block as BlockT},
 };
-use crate::{StorageProof, ChangesProof};
+use crate::{StorageProof, CompactProof, ChangesProof};
 use sp_storage::{ChildInfo, StorageKey, PrefixedStorageKey};
 
+/// A chunk of the state at a block, see `ProofProvider::read_state_chunk`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateChunk {
+	/// The key-value pairs of the chunk, in key order.
+	pub entries: Vec<(Vec<u8>, Vec<u8>)>,
+	/// Whether the trie ends after the entries.
+	pub complete: bool,
+	/// The proof of the entries and of the key following them, if any.
+	pub proof: CompactProof,
+}
+
 /// Interface for providing block proving utilities.
 pub trait ProofProvider<Block: BlockT> {
 	/// Reads storage value at a given block + key, returning read proof.
@@ -52,6 +63,41 @@ pub trait ProofProvider<Block: BlockT> {
 		method: &str,
 		call_data: &[u8],
 	) -> sp_blockchain::Result<(Vec<u8>, StorageProof)>;
+
+	/// Reads storage values at a given block + keys, returning them with a compact proof
+	/// of the reads.
+	fn read_with_proof(
+		&self,
+		id: &BlockId<Block>,
+		keys: &mut dyn Iterator<Item=&[u8]>,
+	) -> sp_blockchain::Result<(Vec<Option<Vec<u8>>>, CompactProof)>;
+
+	/// Execute a runtime API call on top of the state of a given block, returning its result
+	/// with a compact proof of the storage it read.
+	///
+	/// No changes are made.
+	fn execute_with_proof(
+		&self,
+		id: &BlockId<Block>,
+		method: &str,
+		call_data: &[u8],
+	) -> sp_blockchain::Result<(Vec<u8>, CompactProof)>;
+
+	/// Reads a chunk of the state at a given block, returning it with a compact proof.
+	///
+	/// The key-value pairs of the child trie are read if `child_info` is given, of the top
+	/// trie, including the roots of the child tries, otherwise. They are read in key order, from
+	/// the key following `start_at` or the first key, until their values add up to `size_limit`
+	/// bytes. The whole trie is read by starting the next chunk at the last key of the previous
+	/// one until a chunk is complete, and the chunks are the same on every node.
+	fn read_state_chunk(
+		&self,
+		id: &BlockId<Block>,
+		child_info: Option<&ChildInfo>,
+		start_at: Option<&[u8]>,
+		size_limit: usize,
+	) -> sp_blockchain::Result<StateChunk>;
+
 	/// Reads given header and generates CHT-based header proof.
 	fn header_proof(&self, id: &BlockId<Block>) -> sp_blockchain::Result<(Block::Header, StorageProof)>;
 
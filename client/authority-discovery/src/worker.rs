@@ -32,7 +32,9 @@ use async_trait::async_trait;
 use codec::Decode;
 use libp2p::{core::multiaddr, multihash::{Multihash, Hasher}};
 use log::{debug, error, log_enabled};
-use prometheus_endpoint::{Counter, CounterVec, Gauge, Opts, U64, register};
+use prometheus_endpoint::{
+	Counter, CounterVec, Gauge, Opts, U64, prometheus::core::Collector, register,
+};
 use prost::Message;
 use rand::{seq::SliceRandom, thread_rng};
 use sc_client_api::blockchain::HeaderBackend;
@@ -600,42 +602,40 @@ pub(crate) struct Metrics {
 	dht_event_received: CounterVec<U64>,
 	handle_value_found_event_failure: Counter<U64>,
 	known_authorities_count: Gauge<U64>,
+	_registration: Arc<Registration>,
 }
 
 impl Metrics {
 	pub(crate) fn register(registry: &prometheus_endpoint::Registry) -> Result<Self> {
+		let mut registration = Registration { registry: registry.clone(), collectors: Vec::new() };
 		Ok(Self {
-			publish: register(
+			publish: registration.register(
 				Counter::new(
 					"authority_discovery_times_published_total",
 					"Number of times authority discovery has published external addresses."
 				)?,
-				registry,
 			)?,
-			amount_addresses_last_published: register(
+			amount_addresses_last_published: registration.register(
 				Gauge::new(
 					"authority_discovery_amount_external_addresses_last_published",
 					"Number of external addresses published when authority discovery last \
 					 published addresses."
 				)?,
-				registry,
 			)?,
-			requests: register(
+			requests: registration.register(
 				Counter::new(
 					"authority_discovery_authority_addresses_requested_total",
 					"Number of times authority discovery has requested external addresses of a \
 					 single authority."
 				)?,
-				registry,
 			)?,
-			requests_pending: register(
+			requests_pending: registration.register(
 				Gauge::new(
 					"authority_discovery_authority_address_requests_pending",
 					"Number of pending authority address requests."
 				)?,
-				registry,
 			)?,
-			dht_event_received: register(
+			dht_event_received: registration.register(
 				CounterVec::new(
 					Opts::new(
 						"authority_discovery_dht_event_received",
@@ -643,26 +643,47 @@ impl Metrics {
 					),
 					&["name"],
 				)?,
-				registry,
 			)?,
-			handle_value_found_event_failure: register(
+			handle_value_found_event_failure: registration.register(
 				Counter::new(
 					"authority_discovery_handle_value_found_event_failure",
 					"Number of times handling a dht value found event failed."
 				)?,
-				registry,
 			)?,
-			known_authorities_count: register(
+			known_authorities_count: registration.register(
 				Gauge::new(
 					"authority_discovery_known_authorities_count",
 					"Number of authorities known by authority discovery."
 				)?,
-				registry,
 			)?,
+			_registration: Arc::new(registration),
 		})
 	}
 }
 
+/// The metrics registered by a [`Worker`], unregistered once the worker is dropped, so a new
+/// worker, e.g. restarted after a failure, can register them again.
+struct Registration {
+	registry: prometheus_endpoint::Registry,
+	collectors: Vec<Box<dyn Collector>>,
+}
+
+impl Registration {
+	fn register<T: Collector + Clone + 'static>(&mut self, metric: T) -> Result<T> {
+		let metric = register(metric, &self.registry)?;
+		self.collectors.push(Box::new(metric.clone()));
+		Ok(metric)
+	}
+}
+
+impl Drop for Registration {
+	fn drop(&mut self) {
+		for collector in self.collectors.drain(..) {
+			let _ = self.registry.unregister(collector);
+		}
+	}
+}
+
 // Helper functions for unit testing.
 #[cfg(test)]
 impl<Block, Client, Network, DhtEventStream> Worker<Client, Network, Block, DhtEventStream> {
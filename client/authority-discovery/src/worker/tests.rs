@@ -214,7 +214,7 @@ fn new_registers_metrics() {
 	let registry = prometheus_endpoint::Registry::new();
 
 	let (_to_worker, from_service) = mpsc::channel(0);
-	Worker::new(
+	let _worker = Worker::new(
 		from_service,
 		test_api,
 		network.clone(),
@@ -720,3 +720,12 @@ fn lookup_throttling() {
 		assert_eq!(network.get_value_call.lock().unwrap().len(), MAX_IN_FLIGHT_LOOKUPS);
 	}.boxed_local());
 }
+
+#[test]
+fn metrics_are_unregistered_once_dropped() {
+	let registry = prometheus_endpoint::Registry::new();
+	let metrics = Metrics::register(&registry).unwrap();
+	assert!(Metrics::register(&registry).is_err());
+	drop(metrics);
+	assert!(Metrics::register(&registry).is_ok());
+}
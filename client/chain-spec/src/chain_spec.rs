@@ -24,7 +24,7 @@ use serde::{Serialize, Deserialize};
 use sp_core::storage::{StorageKey, StorageData, ChildInfo, Storage, StorageChild};
 use sp_runtime::BuildStorage;
 use serde_json as json;
-use crate::{RuntimeGenesis, ChainType, extension::GetExtension, Properties};
+use crate::{RuntimeGenesis, ChainType, JsonValue, extension::GetExtension, Properties};
 use sc_network::config::MultiaddrWithPeerId;
 use sc_telemetry::TelemetryEndpoints;
 use sp_runtime::traits::{Block as BlockT, NumberFor};
@@ -153,6 +153,12 @@ struct ClientSpec<E> {
 	telemetry_endpoints: Option<TelemetryEndpoints>,
 	protocol_id: Option<String>,
 	properties: Option<Properties>,
+	/// The limit of the stack height of the runtimes, counted in values and frames of the wasm
+	/// stack machine. All the nodes of the chain must agree on it, since it decides which calls
+	/// fail. The runtimes using the bulk memory operations or the SIMD instructions can't be
+	/// limited.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	wasm_max_stack_height: Option<u32>,
 	#[serde(flatten)]
 	extensions: E,
 	// Never used, left only for backward compatibility.
@@ -220,6 +226,16 @@ impl<G, E> ChainSpec<G, E> {
 		self.client_spec.boot_nodes.push(addr)
 	}
 
+	/// The limit of the stack height of the runtimes of the chain, if any.
+	pub fn wasm_max_stack_height(&self) -> Option<u32> {
+		self.client_spec.wasm_max_stack_height
+	}
+
+	/// Set the limit of the stack height of the runtimes of the chain.
+	pub fn set_wasm_max_stack_height(&mut self, max_stack_height: Option<u32>) {
+		self.client_spec.wasm_max_stack_height = max_stack_height;
+	}
+
 	/// Returns a reference to defined chain spec extensions.
 	pub fn extensions(&self) -> &E {
 		&self.client_spec.extensions
@@ -245,6 +261,7 @@ impl<G, E> ChainSpec<G, E> {
 			telemetry_endpoints,
 			protocol_id: protocol_id.map(str::to_owned),
 			properties,
+			wasm_max_stack_height: None,
 			extensions,
 			consensus_engine: (),
 			genesis: Default::default(),
@@ -333,6 +350,26 @@ impl<G: RuntimeGenesis, E: serde::Serialize + Clone + 'static> ChainSpec<G, E> {
 		json::to_string_pretty(&container)
 			.map_err(|e| format!("Error generating spec json: {}", e))
 	}
+
+	/// Apply the given JSON merge patch to the runtime genesis config, see RFC 7396.
+	pub fn patch_genesis(&mut self, patch: JsonValue) -> Result<(), String> {
+		let genesis = match self.genesis.resolve()? {
+			Genesis::Runtime(genesis) => json::to_string(&genesis)
+				.map_err(|e| format!("Error generating genesis json: {}", e))?,
+			Genesis::Raw(_) =>
+				return Err("The genesis of a raw chain spec can't be patched".into()),
+		};
+		let mut runtime = JsonValue::parse(&genesis)?;
+		runtime.merge(patch);
+
+		let genesis = GenesisSource::Binary(
+			format!(r#"{{"genesis":{{"runtime":{}}}}}"#, runtime).into_bytes().into()
+		);
+		// the patched genesis config is checked once, rather than every time it is resolved.
+		genesis.resolve().map_err(|e| format!("Invalid patched genesis config: {}", e))?;
+		self.genesis = genesis;
+		Ok(())
+	}
 }
 
 impl<G, E> crate::ChainSpec for ChainSpec<G, E>
@@ -372,6 +409,10 @@ where
 		ChainSpec::add_boot_node(self, addr)
 	}
 
+	fn wasm_max_stack_height(&self) -> Option<u32> {
+		ChainSpec::wasm_max_stack_height(self)
+	}
+
 	fn extensions(&self) -> &dyn GetExtension {
 		ChainSpec::extensions(self) as &dyn GetExtension
 	}
@@ -395,6 +436,10 @@ where
 	fn set_light_sync_state(&mut self, light_sync_state: SerializableLightSyncState) {
 		ChainSpec::set_light_sync_state(self, light_sync_state)
 	}
+
+	fn patch_genesis(&mut self, patch: JsonValue) -> Result<(), String> {
+		ChainSpec::patch_genesis(self, patch)
+	}
 }
 
 /// Hardcoded infomation that allows light clients to sync quickly.
@@ -500,4 +545,44 @@ mod tests {
 
 		assert_eq!(spec.extensions().my_property, "Test Extension");
 	}
+
+	#[test]
+	fn keeps_the_stack_height_limit_of_the_runtimes() {
+		let spec = TestSpec::from_json_bytes(Cow::Owned(
+			include_bytes!("../res/chain_spec.json").to_vec()
+		)).unwrap();
+		assert_eq!(spec.wasm_max_stack_height(), None);
+		assert!(!spec.as_json(false).unwrap().contains("wasmMaxStackHeight"));
+
+		let mut spec = spec;
+		spec.set_wasm_max_stack_height(Some(65536));
+		let json = spec.as_json(false).unwrap();
+		let spec = TestSpec::from_json_bytes(Cow::Owned(json.into_bytes())).unwrap();
+		assert_eq!(spec.wasm_max_stack_height(), Some(65536));
+	}
+
+	#[test]
+	fn patches_the_genesis_config() {
+		let mut spec = TestSpec::from_genesis(
+			"test",
+			"test_id",
+			ChainType::Development,
+			|| Genesis(vec![("kept".to_string(), "value".to_string())].into_iter().collect()),
+			Vec::new(),
+			None,
+			None,
+			None,
+			None,
+		);
+		spec.patch_genesis(JsonValue::parse(r#"{"patched":"value"}"#).unwrap()).unwrap();
+
+		let storage = spec.build_storage().unwrap();
+		assert_eq!(storage.top.get(&b"kept"[..]), Some(&b"value".to_vec()));
+		assert_eq!(storage.top.get(&b"patched"[..]), Some(&b"value".to_vec()));
+		assert!(spec.patch_genesis(JsonValue::parse("[]").unwrap()).is_err());
+
+		let mut raw = TestSpec::from_json_bytes(spec.as_json(true).unwrap().into_bytes())
+			.unwrap();
+		assert!(raw.patch_genesis(JsonValue::Object(Vec::new())).is_err());
+	}
 }
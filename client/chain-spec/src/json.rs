@@ -0,0 +1,207 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//! JSON values keeping their numbers as written, to patch the genesis configs.
+//!
+//! The numbers of `serde_json::Value` are `u64`, `i64` or `f64`, which can't represent the
+//! balances of most chains, e.g. `u128`s. So the documents are parsed by `serde_json` as raw
+//! values, which keep the numbers as written. Its `arbitrary_precision` feature would do too, but
+//! it changes how the numbers of every crate using `serde_json` are deserialized.
+
+use std::{fmt, marker::PhantomData};
+use serde::{Deserialize, Deserializer, de::{MapAccess, Visitor}};
+use serde_json::value::RawValue;
+
+/// A JSON value, whose numbers are kept as they are written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonValue {
+	/// `null`.
+	Null,
+	/// `true` or `false`.
+	Bool(bool),
+	/// A number, as written.
+	Number(String),
+	/// A string.
+	String(String),
+	/// An array.
+	Array(Vec<JsonValue>),
+	/// An object, whose entries are kept in order.
+	Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+	/// Parse the given JSON document.
+	pub fn parse(json: &str) -> Result<Self, String> {
+		serde_json::from_str::<&RawValue>(json)
+			.and_then(from_raw)
+			.map_err(|e| format!("Invalid JSON: {}", e))
+	}
+
+	/// Returns the value of the given key, if this is an object.
+	pub fn get(&self, key: &str) -> Option<&JsonValue> {
+		match self {
+			JsonValue::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+			_ => None,
+		}
+	}
+
+	/// Apply the given JSON merge patch, see RFC 7396.
+	///
+	/// The entries of the objects of the patch are merged into the entries of the same key,
+	/// or removed if they are `null`, the other values replace the patched ones.
+	pub fn merge(&mut self, patch: JsonValue) {
+		let patch = match patch {
+			JsonValue::Object(patch) => patch,
+			patch => {
+				*self = patch;
+				return;
+			},
+		};
+		if !matches!(self, JsonValue::Object(_)) {
+			*self = JsonValue::Object(Vec::new());
+		}
+		if let JsonValue::Object(entries) = self {
+			for (key, value) in patch {
+				match (entries.iter().position(|(k, _)| *k == key), value) {
+					(Some(index), JsonValue::Null) => {
+						entries.remove(index);
+					},
+					(None, JsonValue::Null) => {},
+					(Some(index), value) => entries[index].1.merge(value),
+					(None, value) => {
+						let mut entry = JsonValue::Null;
+						entry.merge(value);
+						entries.push((key, entry));
+					},
+				}
+			}
+		}
+	}
+}
+
+impl fmt::Display for JsonValue {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			JsonValue::Null => write!(f, "null"),
+			JsonValue::Bool(value) => write!(f, "{}", value),
+			JsonValue::Number(number) => write!(f, "{}", number),
+			JsonValue::String(string) => write!(f, "{}", quote(string)),
+			JsonValue::Array(values) => {
+				write!(f, "[")?;
+				for (index, value) in values.iter().enumerate() {
+					if index > 0 {
+						write!(f, ",")?;
+					}
+					write!(f, "{}", value)?;
+				}
+				write!(f, "]")
+			},
+			JsonValue::Object(entries) => {
+				write!(f, "{{")?;
+				for (index, (key, value)) in entries.iter().enumerate() {
+					if index > 0 {
+						write!(f, ",")?;
+					}
+					write!(f, "{}:{}", quote(key), value)?;
+				}
+				write!(f, "}}")
+			},
+		}
+	}
+}
+
+fn quote(string: &str) -> String {
+	serde_json::to_string(string).expect("Strings are always serializable; qed")
+}
+
+/// The entries of an object, in order, with their values left to parse.
+struct Entries<'a>(Vec<(String, &'a RawValue)>);
+
+impl<'de: 'a, 'a> Deserialize<'de> for Entries<'a> {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		struct EntriesVisitor<'a>(PhantomData<&'a ()>);
+
+		impl<'de: 'a, 'a> Visitor<'de> for EntriesVisitor<'a> {
+			type Value = Entries<'a>;
+
+			fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+				formatter.write_str("a JSON object")
+			}
+
+			fn visit_map<M: MapAccess<'de>>(self, mut map: M) -> Result<Self::Value, M::Error> {
+				let mut entries = Vec::new();
+				while let Some(entry) = map.next_entry()? {
+					entries.push(entry);
+				}
+				Ok(Entries(entries))
+			}
+		}
+
+		deserializer.deserialize_map(EntriesVisitor(PhantomData))
+	}
+}
+
+/// Convert the given JSON value, checked by `serde_json`, keeping its numbers as written.
+fn from_raw(raw: &RawValue) -> Result<JsonValue, serde_json::Error> {
+	let json = raw.get();
+	Ok(match json.as_bytes().first() {
+		Some(b'{') => {
+			let Entries(entries) = serde_json::from_str(json)?;
+			JsonValue::Object(entries.into_iter()
+				.map(|(key, value)| Ok((key, from_raw(value)?)))
+				.collect::<Result<_, _>>()?)
+		},
+		Some(b'[') => {
+			let values: Vec<&RawValue> = serde_json::from_str(json)?;
+			JsonValue::Array(values.into_iter().map(from_raw).collect::<Result<_, _>>()?)
+		},
+		Some(b'"') => JsonValue::String(serde_json::from_str(json)?),
+		Some(b't') | Some(b'f') => JsonValue::Bool(serde_json::from_str(json)?),
+		Some(b'n') => JsonValue::Null,
+		_ => JsonValue::Number(json.into()),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn keeps_the_numbers_as_written() {
+		let json = r#"{"balances":[["5Grw\"a",1000000000000000000000]],"x":-1.5e3,"y":[null]}"#;
+		let value = JsonValue::parse(json).unwrap();
+		assert_eq!(value.to_string(), json);
+		assert_eq!(
+			JsonValue::parse(" [ 1 , {} , [] ] ").unwrap().to_string(),
+			"[1,{},[]]",
+		);
+		assert!(JsonValue::parse(r#"{"a":}"#).is_err());
+		assert!(JsonValue::parse("[1] 2").is_err());
+		assert!(JsonValue::parse("01x").is_err());
+	}
+
+	#[test]
+	fn merges_patches() {
+		let mut value = JsonValue::parse(r#"{"a":"b","c":{"d":"e","f":"g"},"h":[1]}"#).unwrap();
+		let patch = r#"{"a":"z","c":{"f":null},"h":[2,3],"i":{"j":null}}"#;
+		value.merge(JsonValue::parse(patch).unwrap());
+		assert_eq!(value.to_string(), r#"{"a":"z","c":{"d":"e"},"h":[2,3],"i":{}}"#);
+
+		value.merge(JsonValue::parse(r#"["replaced"]"#).unwrap());
+		assert_eq!(value.to_string(), r#"["replaced"]"#);
+	}
+}
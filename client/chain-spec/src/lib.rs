@@ -109,11 +109,13 @@
 
 mod chain_spec;
 mod extension;
+mod json;
 
 pub use chain_spec::{
 	ChainSpec as GenericChainSpec, NoExtension, LightSyncState, SerializableLightSyncState,
 };
 pub use extension::{Group, Fork, Forks, Extension, GetExtension, get_extension};
+pub use json::JsonValue;
 pub use sc_chain_spec_derive::{ChainSpecExtension, ChainSpecGroup};
 pub use sp_chain_spec::{Properties, ChainType};
 
@@ -145,6 +147,10 @@ pub trait ChainSpec: BuildStorage + Send + Sync {
 	///
 	/// Returns an empty JSON object if 'properties' not defined in config
 	fn properties(&self) -> Properties;
+	/// The limit of the stack height of the runtimes of the chain, if any.
+	///
+	/// All the nodes of the chain must use the same limit, since it decides which calls fail.
+	fn wasm_max_stack_height(&self) -> Option<u32>;
 	/// Returns a reference to defined chain spec extensions.
 	fn extensions(&self) -> &dyn GetExtension;
 	/// Add a bootnode to the list.
@@ -161,6 +167,10 @@ pub trait ChainSpec: BuildStorage + Send + Sync {
 	fn set_storage(&mut self, storage: Storage);
 	/// Hardcode infomation to allow light clients to sync quickly into the chain spec.
 	fn set_light_sync_state(&mut self, light_sync_state: SerializableLightSyncState);
+	/// Apply the given JSON merge patch to the runtime genesis config, see RFC 7396.
+	///
+	/// The genesis of a raw chain spec can't be patched.
+	fn patch_genesis(&mut self, patch: JsonValue) -> Result<(), String>;
 }
 
 impl std::fmt::Debug for dyn ChainSpec {
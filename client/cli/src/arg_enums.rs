@@ -50,7 +50,8 @@ impl Into<sc_service::config::WasmExecutionMethod> for WasmExecutionMethod {
 				sc_service::config::WasmExecutionMethod::Interpreted
 			}
 			#[cfg(feature = "wasmtime")]
-			WasmExecutionMethod::Compiled => sc_service::config::WasmExecutionMethod::Compiled,
+			WasmExecutionMethod::Compiled =>
+				sc_service::config::WasmExecutionMethod::Compiled(Default::default()),
 			#[cfg(not(feature = "wasmtime"))]
 			WasmExecutionMethod::Compiled => panic!(
 				"Substrate must be compiled with \"wasmtime\" feature for compiled Wasm execution"
@@ -59,6 +60,72 @@ impl Into<sc_service::config::WasmExecutionMethod> for WasmExecutionMethod {
 	}
 }
 
+arg_enum! {
+	/// How much the compiled Wasm runtime code is optimized
+	#[allow(missing_docs)]
+	#[derive(Debug, Clone, Copy)]
+	pub enum WasmtimeOptLevel {
+		// No optimizations, the fastest to compile.
+		None,
+		// Optimize for speed.
+		Speed,
+		// Optimize for both speed and size.
+		SpeedAndSize,
+	}
+}
+
+#[cfg(feature = "wasmtime")]
+impl Into<sc_service::config::WasmtimeOptLevel> for WasmtimeOptLevel {
+	fn into(self) -> sc_service::config::WasmtimeOptLevel {
+		match self {
+			WasmtimeOptLevel::None => sc_service::config::WasmtimeOptLevel::None,
+			WasmtimeOptLevel::Speed => sc_service::config::WasmtimeOptLevel::Speed,
+			WasmtimeOptLevel::SpeedAndSize => sc_service::config::WasmtimeOptLevel::SpeedAndSize,
+		}
+	}
+}
+
+arg_enum! {
+	/// How the memories of the compiled Wasm runtime are allocated
+	#[allow(missing_docs)]
+	#[derive(Debug, Clone, Copy)]
+	pub enum WasmtimeMemoryStrategy {
+		// Reserve the address space up front, sparing the bounds checks.
+		Static,
+		// Allocate the memory as it grows, checking the bounds of every access.
+		Dynamic,
+	}
+}
+
+#[cfg(feature = "wasmtime")]
+impl Into<sc_service::config::WasmtimeMemoryStrategy> for WasmtimeMemoryStrategy {
+	fn into(self) -> sc_service::config::WasmtimeMemoryStrategy {
+		match self {
+			WasmtimeMemoryStrategy::Static => sc_service::config::WasmtimeMemoryStrategy::Static,
+			WasmtimeMemoryStrategy::Dynamic => sc_service::config::WasmtimeMemoryStrategy::Dynamic,
+		}
+	}
+}
+
+arg_enum! {
+	/// How the informant prints the status of the node.
+	#[allow(missing_docs)]
+	#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+	pub enum InformantFormat {
+		Text,
+		Json,
+	}
+}
+
+impl Into<sc_service::config::InformantOutputStyle> for InformantFormat {
+	fn into(self) -> sc_service::config::InformantOutputStyle {
+		match self {
+			InformantFormat::Text => sc_service::config::InformantOutputStyle::Text,
+			InformantFormat::Json => sc_service::config::InformantOutputStyle::Json,
+		}
+	}
+}
+
 arg_enum! {
 	#[allow(missing_docs)]
 	#[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -77,6 +144,28 @@ impl Into<sc_tracing::TracingReceiver> for TracingReceiver {
 	}
 }
 
+arg_enum! {
+	/// How often the log file is rotated.
+	#[allow(missing_docs)]
+	#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+	pub enum LogRotation {
+		Never,
+		Hourly,
+		Daily,
+	}
+}
+
+impl LogRotation {
+	/// The maximum age of the log file, `None` if it's never rotated.
+	pub fn max_age(self) -> Option<std::time::Duration> {
+		match self {
+			LogRotation::Never => None,
+			LogRotation::Hourly => Some(std::time::Duration::from_secs(60 * 60)),
+			LogRotation::Daily => Some(std::time::Duration::from_secs(24 * 60 * 60)),
+		}
+	}
+}
+
 arg_enum! {
 	#[allow(missing_docs)]
 	#[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -180,7 +269,9 @@ impl std::str::FromStr for Database {
 	fn from_str(s: &str) -> Result<Self, String> {
 		if s.eq_ignore_ascii_case("rocksdb") {
 			Ok(Self::RocksDb)
-		} else if s.eq_ignore_ascii_case("paritydb-experimental") {
+		} else if s.eq_ignore_ascii_case("paritydb")
+			|| s.eq_ignore_ascii_case("paritydb-experimental")
+		{
 			Ok(Self::ParityDb)
 		} else {
 			Err(format!("Unknwon variant `{}`, known variants: {:?}", s, Self::variants()))
@@ -191,7 +282,7 @@ impl std::str::FromStr for Database {
 impl Database {
 	/// Returns all the variants of this enum to be shown in the cli.
 	pub fn variants() -> &'static [&'static str] {
-		&["rocksdb", "paritydb-experimental"]
+		&["rocksdb", "paritydb", "paritydb-experimental"]
 	}
 }
 
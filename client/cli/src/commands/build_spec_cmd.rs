@@ -22,9 +22,9 @@ use crate::params::SharedParams;
 use crate::CliConfiguration;
 use log::info;
 use sc_network::config::build_multiaddr;
-use sc_service::{config::{MultiaddrWithPeerId, NetworkConfiguration}, ChainSpec};
+use sc_service::{config::{MultiaddrWithPeerId, NetworkConfiguration}, ChainSpec, JsonValue};
 use structopt::StructOpt;
-use std::io::Write;
+use std::{fs, io::Write, path::{Path, PathBuf}};
 
 /// The `build-spec` command used to build a specification.
 #[derive(Debug, StructOpt)]
@@ -40,6 +40,33 @@ pub struct BuildSpecCmd {
 	#[structopt(long = "disable-default-bootnode")]
 	pub disable_default_bootnode: bool,
 
+	/// Apply the JSON merge patch (RFC 7396) in the given file to the genesis config.
+	///
+	/// Can be given multiple times, the patches are applied in order.
+	#[structopt(long = "patch", value_name = "FILE", parse(from_os_str))]
+	pub patch: Vec<PathBuf>,
+
+	/// Include the balances of the accounts in the given CSV file in the genesis config.
+	///
+	/// Every line is `ACCOUNT,BALANCE`, blank lines and lines starting with `#` are ignored.
+	/// The balances of accounts already in the genesis config are replaced.
+	#[structopt(long = "balances", value_name = "FILE", parse(from_os_str))]
+	pub balances: Option<PathBuf>,
+
+	/// The key of the genesis config of the balances pallet.
+	#[structopt(long = "balances-pallet", value_name = "KEY", default_value = "palletBalances")]
+	pub balances_pallet: String,
+
+	/// Write the plain specification to the given file rather than to stdout.
+	#[structopt(long = "plain-output", value_name = "PATH", parse(from_os_str))]
+	pub plain_output: Option<PathBuf>,
+
+	/// Write the raw specification to the given file rather than to stdout.
+	///
+	/// Can be combined with `--plain-output` to write both specifications in one run.
+	#[structopt(long = "raw-output", value_name = "PATH", parse(from_os_str))]
+	pub raw_output: Option<PathBuf>,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub shared_params: SharedParams,
@@ -69,12 +96,97 @@ impl BuildSpecCmd {
 			spec.add_boot_node(addr)
 		}
 
-		let json = sc_service::chain_ops::build_spec(&*spec, raw_output)?;
-		if std::io::stdout().write_all(json.as_bytes()).is_err() {
-			let _ = std::io::stderr().write_all(b"Error writing to stdout\n");
+		for path in &self.patch {
+			let patch = JsonValue::parse(&fs::read_to_string(path)?)
+				.map_err(|e| error::Error::Input(format!("{}: {}", path.display(), e)))?;
+			spec.patch_genesis(patch)?;
+		}
+		if let Some(path) = &self.balances {
+			let balances = parse_balances(&fs::read_to_string(path)?)?;
+			let patch = self.balances_patch(&*spec, balances)?;
+			spec.patch_genesis(patch)?;
+		}
+
+		if self.plain_output.is_none() && self.raw_output.is_none() {
+			let json = sc_service::chain_ops::build_spec(&*spec, raw_output)?;
+			if std::io::stdout().write_all(json.as_bytes()).is_err() {
+				let _ = std::io::stderr().write_all(b"Error writing to stdout\n");
+			}
+		}
+		if let Some(path) = &self.plain_output {
+			write_spec(path, &sc_service::chain_ops::build_spec(&*spec, false)?)?;
+		}
+		if let Some(path) = &self.raw_output {
+			write_spec(path, &sc_service::chain_ops::build_spec(&*spec, true)?)?;
 		}
 		Ok(())
 	}
+
+	/// The patch setting the given balances, in addition to those of the genesis config.
+	fn balances_patch(
+		&self,
+		spec: &dyn ChainSpec,
+		balances: Vec<(String, String)>,
+	) -> error::Result<JsonValue> {
+		let plain = JsonValue::parse(&spec.as_json(false)?)?;
+		let existing = plain.get("genesis")
+			.and_then(|genesis| genesis.get("runtime"))
+			.and_then(|runtime| runtime.get(&self.balances_pallet))
+			.and_then(|pallet| pallet.get("balances"));
+		let mut entries = match existing {
+			Some(JsonValue::Array(entries)) => entries.clone(),
+			Some(_) => return Err(error::Error::Input(
+				format!("The balances of `{}` aren't a list", self.balances_pallet)
+			)),
+			None => Vec::new(),
+		};
+
+		for (account, balance) in balances {
+			let account = JsonValue::String(account);
+			let entry = JsonValue::Array(vec![account.clone(), JsonValue::Number(balance)]);
+			let position = entries.iter().position(|entry| match entry {
+				JsonValue::Array(pair) => pair.first() == Some(&account),
+				_ => false,
+			});
+			match position {
+				Some(position) => entries[position] = entry,
+				None => entries.push(entry),
+			}
+		}
+
+		let balances = JsonValue::Object(vec![("balances".into(), JsonValue::Array(entries))]);
+		Ok(JsonValue::Object(vec![(self.balances_pallet.clone(), balances)]))
+	}
+}
+
+/// Parse the `ACCOUNT,BALANCE` lines of a CSV file, skipping a header line.
+fn parse_balances(csv: &str) -> error::Result<Vec<(String, String)>> {
+	let mut balances = Vec::new();
+	let lines = csv.lines()
+		.enumerate()
+		.map(|(n, line)| (n + 1, line.trim()))
+		.filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+	for (i, (n, line)) in lines.enumerate() {
+		let invalid = |reason: &str| error::Error::Input(format!("Line {}: {}", n, reason));
+		let mut fields = line.split(',').map(str::trim);
+		let (account, balance) = match (fields.next(), fields.next(), fields.next()) {
+			(Some(account), Some(balance), None) if !account.is_empty() => (account, balance),
+			_ => return Err(invalid("expected `ACCOUNT,BALANCE`")),
+		};
+		match balance.parse::<u128>() {
+			Ok(balance) => balances.push((account.to_owned(), balance.to_string())),
+			Err(_) if i == 0 => continue,
+			Err(e) => return Err(invalid(&format!("invalid balance `{}`: {}", balance, e))),
+		}
+	}
+	Ok(balances)
+}
+
+fn write_spec(path: &Path, json: &str) -> error::Result<()> {
+	fs::write(path, json)?;
+	info!("Chain spec written to {}", path.display());
+	Ok(())
 }
 
 impl CliConfiguration for BuildSpecCmd {
@@ -86,3 +198,22 @@ impl CliConfiguration for BuildSpecCmd {
 		Some(&self.node_key_params)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_the_balances() {
+		let csv = "account,balance\n\n# faucet\n5Grw,1000000000000000000000\n 5FHn , 42 \n";
+		assert_eq!(
+			parse_balances(csv).unwrap(),
+			vec![
+				("5Grw".to_owned(), "1000000000000000000000".to_owned()),
+				("5FHn".to_owned(), "42".to_owned()),
+			],
+		);
+		assert!(parse_balances("5Grw,1\n5FHn,-1\n").is_err());
+		assert!(parse_balances("5Grw,1,2\n").is_err());
+	}
+}
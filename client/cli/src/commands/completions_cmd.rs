@@ -0,0 +1,76 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::error;
+use crate::SubstrateCli;
+use std::io::{self, Write};
+use structopt::{clap::{App, Shell}, StructOpt};
+
+/// The `completions` command used to generate the shell completion scripts of the node.
+#[derive(Debug, StructOpt)]
+pub struct CompletionsCmd {
+	/// The shell to generate the completion script for.
+	#[structopt(
+		value_name = "SHELL",
+		possible_values = &Shell::variants(),
+		case_insensitive = true,
+	)]
+	pub shell: Shell,
+}
+
+impl CompletionsCmd {
+	/// Run the completions command, printing the completion script of the subcommands and
+	/// options of the given CLI.
+	pub fn run<C: SubstrateCli + StructOpt>(&self) -> error::Result<()> {
+		self.generate(C::clap(), &C::executable_name(), &mut io::stdout());
+		Ok(())
+	}
+
+	/// Write the completion script of the given app, invoked as the given binary.
+	fn generate<W: Write>(&self, mut app: App, bin_name: &str, out: &mut W) {
+		app.gen_completions_to(bin_name, self.shell, out);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[derive(StructOpt)]
+	#[allow(dead_code)]
+	enum Node {
+		/// Purge the chain.
+		PurgeChain {
+			#[structopt(long)]
+			keep_keystore: bool,
+		},
+	}
+
+	#[test]
+	fn generates_completions_of_the_subcommands_and_flags() {
+		for shell in &["bash", "zsh", "fish"] {
+			let cmd = CompletionsCmd::from_iter(&["completions", shell]);
+			let mut script = Vec::new();
+			cmd.generate(Node::clap(), "node", &mut script);
+
+			let script = String::from_utf8(script).unwrap();
+			assert!(script.contains("purge-chain"), "{}", script);
+			assert!(script.contains("keep-keystore"), "{}", script);
+		}
+	}
+}
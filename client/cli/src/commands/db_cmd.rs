@@ -0,0 +1,244 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::error;
+use crate::params::{BlockNumberOrHash, DatabaseParams, PruningParams, SharedParams};
+use crate::CliConfiguration;
+use sc_service::Configuration;
+use sp_core::twox_128;
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
+use std::{fmt::Debug, path::PathBuf, str::FromStr};
+use structopt::StructOpt;
+
+/// The `db` command used to back up, restore, check and inspect the database.
+#[derive(Debug, StructOpt)]
+pub enum DbCmd {
+	/// Write a snapshot of the database to an empty directory.
+	///
+	/// Running nodes take snapshots with the unsafe `system_snapshotDatabase` RPC instead, see
+	/// `--db-snapshot-dir`.
+	Snapshot(DbSnapshotCmd),
+
+	/// Restore a snapshot of the database, once checked against the genesis of the chain.
+	///
+	/// The database of the node must not exist yet.
+	Restore(DbRestoreCmd),
+
+	/// Check the headers, bodies and justifications of the best chain and the state of the best
+	/// block for missing or corrupt entries.
+	Check(DbCheckCmd),
+
+	/// Report the number and size of the keys and values of the state at a block, by pallet
+	/// prefix, the largest ones first.
+	StorageReport(DbStorageReportCmd),
+}
+
+/// The `db snapshot` command.
+#[derive(Debug, StructOpt)]
+pub struct DbSnapshotCmd {
+	/// Directory to write the snapshot to.
+	#[structopt(parse(from_os_str))]
+	pub path: PathBuf,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+}
+
+/// The `db restore` command.
+#[derive(Debug, StructOpt)]
+pub struct DbRestoreCmd {
+	/// Directory of the snapshot to restore.
+	#[structopt(parse(from_os_str))]
+	pub path: PathBuf,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+}
+
+/// The `db check` command.
+#[derive(Debug, StructOpt)]
+pub struct DbCheckCmd {
+	/// Revert the chain to the last block before the first inconsistent one.
+	///
+	/// The blocks past it, including the finalized ones, are downloaded again once the node
+	/// syncs instead of resyncing the whole chain.
+	#[structopt(long)]
+	pub repair: bool,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+}
+
+/// The `db storage-report` command.
+#[derive(Debug, StructOpt)]
+pub struct DbStorageReportCmd {
+	/// Block hash or number, the best block by default.
+	#[structopt(value_name = "HASH or NUMBER")]
+	pub input: Option<BlockNumberOrHash>,
+
+	/// Report the keys under a prefix under the given name.
+	///
+	/// Either `NAME`, the name of a pallet whose keys are prefixed with the `twox_128` hash of
+	/// its name, or `0xPREFIX=NAME`. The prefixes of the FRAME pallets are known already.
+	#[structopt(
+		long = "prefix",
+		value_name = "NAME or 0xPREFIX=NAME",
+		parse(try_from_str = parse_prefix),
+	)]
+	pub prefixes: Vec<(Vec<u8>, String)>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+}
+
+fn parse_prefix(prefix: &str) -> Result<(Vec<u8>, String), String> {
+	if !prefix.starts_with("0x") {
+		return Ok((twox_128(prefix.as_bytes()).to_vec(), prefix.into()));
+	}
+	match prefix[2..].splitn(2, '=').collect::<Vec<_>>()[..] {
+		[hex, name] if !name.is_empty() => hex::decode(hex)
+			.map(|bytes| (bytes, name.into()))
+			.map_err(|e| format!("Invalid prefix {}: {}", hex, e)),
+		_ => Err(format!("Expected `0xPREFIX=NAME`, got {}", prefix)),
+	}
+}
+
+impl DbCmd {
+	/// Run the db command
+	pub fn run<B>(&self, config: Configuration) -> error::Result<()> where
+		B: BlockT,
+		B::Hash: FromStr,
+		<B::Hash as FromStr>::Err: Debug,
+		<<B::Header as HeaderT>::Number as FromStr>::Err: Debug,
+	{
+		match self {
+			DbCmd::Snapshot(cmd) => {
+				sc_service::snapshot_database::<B>(&config, &cmd.path)?;
+				println!("Snapshot written to {:?}.", &cmd.path);
+			},
+			DbCmd::Restore(cmd) => {
+				sc_service::restore_database::<B>(&config, &cmd.path)?;
+				println!("Snapshot {:?} restored.", &cmd.path);
+			},
+			DbCmd::Check(cmd) => {
+				let report = sc_service::check_database::<B>(&config, cmd.repair)?;
+				println!(
+					"Checked {} blocks and {} state keys.",
+					report.blocks,
+					report.state_keys,
+				);
+				for (number, inconsistency) in &report.inconsistencies {
+					println!("#{}: {}", number, inconsistency);
+				}
+				if report.inconsistencies.is_empty() {
+					println!("No inconsistency found.");
+				} else if !cmd.repair {
+					println!("Run with `--repair` to revert the inconsistent blocks.");
+				}
+				if let Some((requested, reverted)) = report.reverted {
+					println!("Reverted {} of the {} blocks to repair.", reverted, requested);
+				}
+				if report.partially_reverted() {
+					return Err(error::Error::Input(
+						"The chain couldn't be reverted past the first inconsistent block".into(),
+					));
+				}
+			},
+			DbCmd::StorageReport(cmd) => {
+				let block = cmd.input.as_ref().map(|b| b.parse::<B>()).transpose()?;
+				let report = sc_service::storage_report::<B>(&config, block, &cmd.prefixes)?;
+				println!(
+					"{:<34} {:>12} {:>16} {:>16}",
+					"Prefix", "Keys", "Key bytes", "Value bytes",
+				);
+				for usage in report.prefixes.iter().chain(std::iter::once(&report.total())) {
+					println!(
+						"{:<34} {:>12} {:>16} {:>16}",
+						usage.name, usage.keys, usage.key_bytes, usage.value_bytes,
+					);
+				}
+			},
+		}
+		Ok(())
+	}
+}
+
+impl CliConfiguration for DbCmd {
+	fn shared_params(&self) -> &SharedParams {
+		match self {
+			DbCmd::Snapshot(cmd) => &cmd.shared_params,
+			DbCmd::Restore(cmd) => &cmd.shared_params,
+			DbCmd::Check(cmd) => &cmd.shared_params,
+			DbCmd::StorageReport(cmd) => &cmd.shared_params,
+		}
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		match self {
+			DbCmd::Snapshot(cmd) => Some(&cmd.database_params),
+			DbCmd::Restore(cmd) => Some(&cmd.database_params),
+			DbCmd::Check(cmd) => Some(&cmd.database_params),
+			DbCmd::StorageReport(cmd) => Some(&cmd.database_params),
+		}
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		match self {
+			DbCmd::Snapshot(cmd) => Some(&cmd.pruning_params),
+			DbCmd::Restore(cmd) => Some(&cmd.pruning_params),
+			DbCmd::Check(cmd) => Some(&cmd.pruning_params),
+			DbCmd::StorageReport(cmd) => Some(&cmd.pruning_params),
+		}
+	}
+}
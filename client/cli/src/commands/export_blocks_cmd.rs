@@ -56,6 +56,10 @@ pub struct ExportBlocksCmd {
 	#[structopt(long)]
 	pub binary: bool,
 
+	/// Don't export the justifications of the blocks.
+	#[structopt(long)]
+	pub no_justifications: bool,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub shared_params: SharedParams,
@@ -95,7 +99,7 @@ impl ExportBlocksCmd {
 			None => Box::new(io::stdout()),
 		};
 
-		export_blocks(client, file, from.into(), to, binary)
+		export_blocks(client, file, from.into(), to, binary, !self.no_justifications)
 			.await
 			.map_err(Into::into)
 	}
@@ -0,0 +1,90 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::config_file::{
+	append_options, config_path, options_of_args, read_config_file, EXPORT_CONFIG,
+};
+use crate::error;
+use crate::params::SharedParams;
+use crate::{CliConfiguration, RunCmd};
+use std::{ffi::OsString, fs, io::Write, path::{Path, PathBuf}};
+use structopt::StructOpt;
+
+/// The `export-config` command used to export the configuration of the node.
+///
+/// It takes the options of the node, from the command line and from the configuration file
+/// given by `--config`, and prints the effective ones as a configuration file. The other options
+/// keep their default values.
+#[derive(Debug, StructOpt)]
+pub struct ExportConfigCmd {
+	/// Write the configuration to the given file rather than to stdout.
+	///
+	/// As YAML if its extension is `yaml` or `yml`, as TOML otherwise.
+	#[structopt(long, value_name = "FILE", parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub run: RunCmd,
+}
+
+impl ExportConfigCmd {
+	/// Run the export-config command with the arguments the node was started with.
+	pub fn run(&self) -> error::Result<()> {
+		self.run_with_args(&std::env::args_os().collect::<Vec<_>>())
+	}
+
+	/// Run the export-config command with the given arguments of the node.
+	pub fn run_with_args(&self, args: &[OsString]) -> error::Result<()> {
+		// the arguments of the subcommand, parsed as those of an app.
+		let start = args.iter().position(|arg| arg == EXPORT_CONFIG).unwrap_or(args.len());
+		let mut args = std::iter::once(EXPORT_CONFIG.into())
+			.chain(args.iter().skip(start + 1).cloned())
+			.collect::<Vec<OsString>>();
+		let app = Self::clap();
+		if let Some(path) = config_path(&args) {
+			let config = read_config_file(Path::new(&path))?;
+			args = append_options(&app, args, &config, None)?;
+		}
+		app.clone()
+			.get_matches_from_safe(&args)
+			.map_err(|e| error::Error::Input(e.to_string()))?;
+		let options = options_of_args(&app, &args, &["config", "output", "help", "version"]);
+
+		let yaml = self.output.as_ref()
+			.and_then(|output| output.extension())
+			.map_or(false, |extension| extension == "yaml" || extension == "yml");
+		let config = if yaml {
+			serde_yaml::to_string(&options).map_err(|e| error::Error::Input(e.to_string()))?
+		} else {
+			toml::to_string(&options).map_err(|e| error::Error::Input(e.to_string()))?
+		};
+
+		match &self.output {
+			Some(output) => fs::write(output, config)?,
+			None => std::io::stdout().write_all(config.as_bytes())?,
+		}
+		Ok(())
+	}
+}
+
+impl CliConfiguration for ExportConfigCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.run.shared_params
+	}
+}
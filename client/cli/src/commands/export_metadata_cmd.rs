@@ -0,0 +1,117 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+	CliConfiguration, error, params::{PruningParams, SharedParams, BlockNumberOrHash},
+};
+use parity_scale_codec::Decode;
+use log::info;
+use sp_runtime::{generic::BlockId, traits::{Block as BlockT, Header as HeaderT}};
+use std::{fmt::Debug, str::FromStr, path::PathBuf, sync::Arc};
+use structopt::StructOpt;
+use sc_client_api::{CallExecutor, ExecutionStrategy, ExecutorProvider, UsageProvider};
+use sp_blockchain::HeaderBackend;
+
+/// The `export-metadata` command used to export the metadata and the version of the
+/// runtime of a given block.
+#[derive(Debug, StructOpt)]
+pub struct ExportMetadataCmd {
+	/// Block hash or number, the best block if not given.
+	#[structopt(value_name = "HASH or NUMBER")]
+	pub input: Option<BlockNumberOrHash>,
+
+	/// Directory the files are written to: `metadata.hex`, the hex-encoded SCALE metadata
+	/// as returned by `state_getMetadata`, and `runtime.json`, the runtime version and
+	/// the genesis hash.
+	#[structopt(long, short = "o", value_name = "DIR", parse(from_os_str), default_value = ".")]
+	pub output: PathBuf,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+}
+
+impl ExportMetadataCmd {
+	/// Run the `export-metadata` command
+	pub async fn run<B, C>(&self, client: Arc<C>) -> error::Result<()>
+	where
+		B: BlockT,
+		C: UsageProvider<B> + HeaderBackend<B> + ExecutorProvider<B>,
+		B::Hash: FromStr,
+		<B::Hash as FromStr>::Err: Debug,
+		<<B::Header as HeaderT>::Number as FromStr>::Err: Debug,
+	{
+		let id = match &self.input {
+			Some(input) => input.parse()?,
+			None => BlockId::Hash(client.usage_info().chain.best_hash),
+		};
+		let unknown_block = || format!("Unknown block {}", id);
+		let hash = client.block_hash_from_id(&id)?.ok_or_else(unknown_block)?;
+		let number = client.block_number_from_id(&id)?.ok_or_else(unknown_block)?;
+		info!("Exporting the metadata of the runtime of #{} ({})...", number, hash);
+
+		let at = BlockId::Hash(hash);
+		let executor = client.executor();
+		let version = executor.runtime_version(&at)?;
+		let encoded = executor.call(
+			&at,
+			"Metadata_metadata",
+			&[],
+			ExecutionStrategy::AlwaysWasm,
+			None,
+		)?;
+		let metadata = Vec::<u8>::decode(&mut &encoded[..])?;
+
+		let runtime = serde_json::json!({
+			"genesisHash": client.usage_info().chain.genesis_hash,
+			"blockHash": hash,
+			"blockNumber": number.to_string(),
+			"runtimeVersion": version,
+		});
+		let runtime = serde_json::to_string_pretty(&runtime).map_err(|e| e.to_string())?;
+
+		std::fs::create_dir_all(&self.output)?;
+		let metadata_path = self.output.join("metadata.hex");
+		std::fs::write(&metadata_path, format!("0x{}", hex::encode(metadata)))?;
+		let runtime_path = self.output.join("runtime.json");
+		std::fs::write(&runtime_path, runtime)?;
+
+		info!(
+			"Exported the metadata of {}-{} to {} and {}",
+			version.spec_name,
+			version.spec_version,
+			metadata_path.display(),
+			runtime_path.display(),
+		);
+		Ok(())
+	}
+}
+
+impl CliConfiguration for ExportMetadataCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+}
@@ -24,6 +24,7 @@ use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
 use std::{fmt::Debug, str::FromStr, io::Write, sync::Arc};
 use structopt::StructOpt;
 use sc_client_api::{StorageProvider, UsageProvider};
+use sp_blockchain::HeaderBackend;
 
 /// The `export-state` command used to export the state of a given block into
 /// a chain spec.
@@ -33,6 +34,11 @@ pub struct ExportStateCmd {
 	#[structopt(value_name = "HASH or NUMBER")]
 	pub input: Option<BlockNumberOrHash>,
 
+	/// Export the state in a compact binary format, which `import-state` reads, instead of a
+	/// chain spec.
+	#[structopt(long)]
+	pub binary: bool,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub shared_params: SharedParams,
@@ -51,14 +57,22 @@ impl ExportStateCmd {
 	) -> error::Result<()>
 	where
 		B: BlockT,
-		C: UsageProvider<B> + StorageProvider<B, BA>,
+		C: UsageProvider<B> + StorageProvider<B, BA> + HeaderBackend<B>,
 		BA: sc_client_api::backend::Backend<B>,
 		B::Hash: FromStr,
 		<B::Hash as FromStr>::Err: Debug,
 		<<B::Header as HeaderT>::Number as FromStr>::Err: Debug,
 	{
-		info!("Exporting raw state...");
 		let block_id = self.input.as_ref().map(|b| b.parse()).transpose()?;
+		if self.binary {
+			info!("Exporting binary state...");
+			let stdout = std::io::stdout();
+			let output = std::io::BufWriter::new(stdout.lock());
+			sc_service::chain_ops::export_binary_state(client, block_id, output)?;
+			return Ok(());
+		}
+
+		info!("Exporting raw state...");
 		let raw_state = sc_service::chain_ops::export_raw_state(client, block_id)?;
 		input_spec.set_storage(raw_state);
 
@@ -19,7 +19,7 @@
 
 use crate::Error;
 use structopt::StructOpt;
-use std::{path::PathBuf, fs};
+use std::{fs, io::{self, Write}, path::{Path, PathBuf}};
 use libp2p::identity::{ed25519 as libp2p_ed25519, PublicKey};
 
 /// The `generate-node-key` command
@@ -32,7 +32,8 @@ use libp2p::identity::{ed25519 as libp2p_ed25519, PublicKey};
 pub struct GenerateNodeKeyCmd {
 	/// Name of file to save secret key to.
 	///
-	/// If not given, the secret key is printed to stdout.
+	/// If not given, the secret key is printed to stdout. The file is readable only by its
+	/// owner, as required by `--node-key-file`.
 	#[structopt(long)]
 	file: Option<PathBuf>,
 }
@@ -46,7 +47,7 @@ impl GenerateNodeKeyCmd {
 		let secret_hex = hex::encode(secret.as_ref());
 
 		match &self.file {
-			Some(file) => fs::write(file, secret_hex)?,
+			Some(file) => write_secret_file(file, secret_hex.as_bytes())?,
 			None => print!("{}", secret_hex),
 		}
 
@@ -56,6 +57,18 @@ impl GenerateNodeKeyCmd {
 	}
 }
 
+/// Write the secret to the given file, readable only by its owner.
+fn write_secret_file(path: &Path, secret: &[u8]) -> io::Result<()> {
+	let mut file = fs::OpenOptions::new().write(true).create(true).truncate(true).open(path)?;
+	// the permissions of an existing file are restricted before the secret is written.
+	#[cfg(unix)]
+	{
+		use std::os::unix::fs::PermissionsExt;
+		file.set_permissions(fs::Permissions::from_mode(0o600))?;
+	}
+	file.write_all(secret)
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -73,4 +86,20 @@ mod tests {
 		assert!(file.read_to_string(&mut buf).is_ok());
 		assert!(hex::decode(buf).is_ok());
 	}
+
+	#[test]
+	fn generate_node_key_file_with_restricted_permissions() {
+		let dir = tempfile::tempdir().unwrap();
+		let file_path = dir.path().join("node-key").display().to_string();
+		let generate =
+			GenerateNodeKeyCmd::from_iter(&["generate-node-key", "--file", &file_path]);
+		assert!(generate.run().is_ok());
+		#[cfg(unix)]
+		{
+			use std::os::unix::fs::PermissionsExt;
+			let mode = fs::metadata(&file_path).unwrap().permissions().mode();
+			assert_eq!(mode & 0o777, 0o600);
+		}
+		assert_eq!(fs::read(&file_path).unwrap().len(), 64);
+	}
 }
@@ -20,7 +20,8 @@ use crate::error;
 use crate::params::ImportParams;
 use crate::params::SharedParams;
 use crate::CliConfiguration;
-use sc_service::chain_ops::import_blocks;
+use log::info;
+use sc_service::chain_ops::{blocks_are_binary, import_blocks};
 use sp_runtime::traits::Block as BlockT;
 use std::fmt::Debug;
 use std::fs;
@@ -29,6 +30,7 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use structopt::StructOpt;
 use sc_client_api::UsageProvider;
+use sp_blockchain::HeaderBackend;
 
 /// The `import-blocks` command used to import blocks.
 #[derive(Debug, StructOpt)]
@@ -44,9 +46,22 @@ pub struct ImportBlocksCmd {
 	pub default_heap_pages: Option<u32>,
 
 	/// Try importing blocks from binary format rather than JSON.
+	///
+	/// The format of the blocks is detected otherwise.
 	#[structopt(long)]
 	pub binary: bool,
 
+	/// The file recording the progress of the import, to resume it once interrupted.
+	///
+	/// By default the progress of the import of a file is recorded to the file with the
+	/// `checkpoint` extension next to it.
+	#[structopt(long, value_name = "PATH", parse(from_os_str))]
+	pub checkpoint: Option<PathBuf>,
+
+	/// Don't record the progress of the import, nor resume it.
+	#[structopt(long, conflicts_with = "checkpoint")]
+	pub no_checkpoint: bool,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub shared_params: SharedParams,
@@ -69,11 +84,11 @@ impl ImportBlocksCmd {
 		import_queue: IQ,
 	) -> error::Result<()>
 	where
-		C: UsageProvider<B> + Send + Sync + 'static,
+		C: UsageProvider<B> + HeaderBackend<B> + Send + Sync + 'static,
 		B: BlockT + for<'de> serde::Deserialize<'de>,
 		IQ: sc_service::ImportQueue<B> + 'static,
 	{
-		let file: Box<dyn ReadPlusSeek + Send> = match &self.input {
+		let mut file: Box<dyn ReadPlusSeek + Send> = match &self.input {
 			Some(filename) => Box::new(fs::File::open(filename)?),
 			None => {
 				let mut buffer = Vec::new();
@@ -82,7 +97,19 @@ impl ImportBlocksCmd {
 			}
 		};
 
-		import_blocks(client, import_queue, file, false, self.binary)
+		let binary = self.binary || blocks_are_binary(&mut file)?;
+		if binary != self.binary {
+			info!("Importing blocks in binary format");
+		}
+
+		let checkpoint = match (&self.checkpoint, &self.input) {
+			_ if self.no_checkpoint => None,
+			(Some(checkpoint), _) => Some(checkpoint.clone()),
+			(None, Some(input)) => Some(input.with_extension("checkpoint")),
+			(None, None) => None,
+		};
+
+		import_blocks(client, import_queue, file, false, binary, checkpoint)
 			.await
 			.map_err(Into::into)
 	}
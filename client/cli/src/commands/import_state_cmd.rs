@@ -0,0 +1,84 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::error;
+use crate::params::{DatabaseParams, PruningParams, SharedParams};
+use crate::CliConfiguration;
+use log::info;
+use sc_service::Configuration;
+use sp_runtime::traits::Block as BlockT;
+use std::{fs, io::BufReader, path::PathBuf};
+use structopt::StructOpt;
+
+/// The `import-state` command used to seed a new database with a state exported by
+/// `export-state --binary`.
+///
+/// The state becomes the genesis state of the new chain, e.g. to test a runtime upgrade against
+/// the state of a live chain.
+#[derive(Debug, StructOpt)]
+pub struct ImportStateCmd {
+	/// Input file of the binary state.
+	#[structopt(parse(from_os_str))]
+	pub input: PathBuf,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+}
+
+impl ImportStateCmd {
+	/// Run the import-state command
+	pub fn run<B: BlockT>(&self, config: Configuration) -> error::Result<()> {
+		let db_exists = config.database.path()
+			.map_or(false, |path| fs::read_dir(path).map_or(false, |mut dir| dir.next().is_some()));
+		if db_exists {
+			return Err(error::Error::Input(
+				"The state can only be imported into a new database, purge the chain first".into(),
+			));
+		}
+
+		let input = BufReader::new(fs::File::open(&self.input)?);
+		let (hash, chunks) = sc_service::chain_ops::read_binary_state::<B, _>(input)?;
+		info!("Importing the state of block {}...", hash);
+		let genesis_hash = sc_service::import_state::<B>(&config, chunks)?;
+		info!("Imported, the genesis block of the new chain is {}", genesis_hash);
+		Ok(())
+	}
+}
+
+impl CliConfiguration for ImportStateCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+}
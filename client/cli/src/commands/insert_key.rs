@@ -21,12 +21,10 @@ use crate::{
 	Error, KeystoreParams, CryptoSchemeFlag, SharedParams, utils, with_crypto_scheme,
 	SubstrateCli,
 };
-use std::{sync::Arc, convert::TryFrom};
+use std::convert::TryFrom;
 use structopt::StructOpt;
 use sp_core::{crypto::KeyTypeId, crypto::SecretString};
-use sp_keystore::{SyncCryptoStorePtr, SyncCryptoStore};
-use sc_keystore::LocalKeystore;
-use sc_service::config::{KeystoreConfig, BasePath};
+use sp_keystore::SyncCryptoStore;
 
 /// The `insert` command
 #[derive(Debug, StructOpt)]
@@ -62,24 +60,9 @@ impl InsertKeyCmd {
 	/// Run the command
 	pub fn run<C: SubstrateCli>(&self, cli: &C) -> Result<(), Error> {
 		let suri = utils::read_uri(self.suri.as_ref())?;
-		let base_path = self.shared_params
-			.base_path()
-			.unwrap_or_else(|| BasePath::from_project("", "", &C::executable_name()));
-		let chain_id = self.shared_params.chain_id(self.shared_params.is_dev());
-		let chain_spec = cli.load_spec(&chain_id)?;
-		let config_dir = base_path.config_dir(chain_spec.id());
-
-		let (keystore, public) = match self.keystore_params.keystore_config(&config_dir)? {
-			(_, KeystoreConfig::Path { path, password }) => {
-				let public = with_crypto_scheme!(
-					self.crypto_scheme.scheme,
-					to_vec(&suri, password.clone())
-				)?;
-				let keystore: SyncCryptoStorePtr = Arc::new(LocalKeystore::open(path, password)?);
-				(keystore, public)
-			},
-			_ => unreachable!("keystore_config always returns path and password; qed")
-		};
+		let (keystore, password) =
+			utils::open_keystore(cli, &self.shared_params, &self.keystore_params)?;
+		let public = with_crypto_scheme!(self.crypto_scheme.scheme, to_vec(&suri, password))?;
 
 		let key_type = KeyTypeId::try_from(self.key_type.as_str()).map_err(|_| Error::KeyTypeInvalid)?;
 
@@ -100,7 +83,8 @@ mod tests {
 	use super::*;
 	use structopt::StructOpt;
 	use tempfile::TempDir;
-	use sp_core::{sr25519::Pair, Pair as _, Public};
+	use sc_keystore::LocalKeystore;
+	use sp_core::{ecdsa, sr25519::Pair, Pair as _, Public};
 	use sc_service::{ChainSpec, GenericChainSpec, ChainType, NoExtension};
 
 	struct Cli;
@@ -170,4 +154,23 @@ mod tests {
 		).unwrap();
 		assert!(keystore.has_keys(&[(key.public().to_raw_vec(), KeyTypeId(*b"test"))]));
 	}
+
+	#[test]
+	fn insert_into_keystore_path() {
+		let path = TempDir::new().unwrap();
+		let path_str = format!("{}", path.path().display());
+		let (key, uri, _) = ecdsa::Pair::generate_with_phrase(None);
+
+		let inspect = InsertKeyCmd::from_iter(&[
+			"insert-key",
+			"--keystore-path", &path_str,
+			"--key-type", "test",
+			"--scheme", "ecdsa",
+			"--suri", &uri,
+		]);
+		assert!(inspect.run(&Cli).is_ok());
+
+		let keystore = LocalKeystore::open(path.path(), None).unwrap();
+		assert!(keystore.has_keys(&[(key.public().to_raw_vec(), KeyTypeId(*b"test"))]));
+	}
 }
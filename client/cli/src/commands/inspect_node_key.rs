@@ -23,6 +23,9 @@ use libp2p::identity::{PublicKey, ed25519};
 use std::path::PathBuf;
 use structopt::StructOpt;
 
+/// Length of the secret of a node key, in bytes.
+const SECRET_LEN: usize = 32;
+
 /// The `inspect-node-key` command
 #[derive(Debug, StructOpt)]
 #[structopt(
@@ -30,7 +33,8 @@ use structopt::StructOpt;
 	about = "Print the peer ID corresponding to the node key in the given file."
 )]
 pub struct InspectNodeKeyCmd {
-	/// Name of file to read the secret key from.
+	/// Name of file to read the secret key from, either hex encoded or in binary, as written
+	/// by the node.
 	#[structopt(long)]
 	file: PathBuf,
 
@@ -42,8 +46,12 @@ pub struct InspectNodeKeyCmd {
 impl InspectNodeKeyCmd {
 	/// runs the command
 	pub fn run(&self) -> Result<(), Error> {
-		let mut file_content = hex::decode(fs::read(&self.file)?)
-			.map_err(|_| "failed to decode secret as hex")?;
+		let mut file_content = fs::read(&self.file)?;
+		// the node writes the secret in binary, `generate-node-key` writes it hex encoded.
+		if file_content.len() != SECRET_LEN {
+			file_content = hex::decode(&file_content)
+				.map_err(|_| "failed to decode secret as hex")?;
+		}
 		let secret = ed25519::SecretKey::from_bytes(&mut file_content)
 			.map_err(|_| "Bad node key file")?;
 
@@ -72,4 +80,14 @@ mod tests {
 		let cmd = InspectNodeKeyCmd::from_iter(&["inspect-node-key", "--file", path]);
 		assert!(cmd.run().is_ok());
 	}
+
+	#[test]
+	fn inspect_binary_node_key() {
+		let path = tempfile::tempdir().unwrap().into_path().join("secret_ed25519");
+		fs::write(&path, ed25519::SecretKey::generate()).unwrap();
+
+		let path = path.to_str().unwrap();
+		let cmd = InspectNodeKeyCmd::from_iter(&["inspect-node-key", "--file", path]);
+		assert!(cmd.run().is_ok());
+	}
 }
@@ -39,6 +39,7 @@ pub enum KeySubcommand {
 	Generate(GenerateCmd),
 
 	/// Gets a public key and a SS58 address from the provided Secret URI
+	#[structopt(name = "inspect", alias = "inspect-key")]
 	InspectKey(InspectKeyCmd),
 
 	/// Print the peer ID corresponding to the node key in the given file
@@ -17,9 +17,14 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 mod build_spec_cmd;
 mod check_block_cmd;
+mod completions_cmd;
+mod db_cmd;
 mod export_blocks_cmd;
+mod export_config_cmd;
+mod export_metadata_cmd;
 mod export_state_cmd;
 mod import_blocks_cmd;
+mod import_state_cmd;
 mod purge_chain_cmd;
 mod sign;
 mod verify;
@@ -37,9 +42,14 @@ pub mod utils;
 pub use self::{
 	build_spec_cmd::BuildSpecCmd,
 	check_block_cmd::CheckBlockCmd,
+	completions_cmd::CompletionsCmd,
+	db_cmd::{DbCmd, DbSnapshotCmd, DbRestoreCmd, DbCheckCmd, DbStorageReportCmd},
 	export_blocks_cmd::ExportBlocksCmd,
+	export_config_cmd::ExportConfigCmd,
+	export_metadata_cmd::ExportMetadataCmd,
 	export_state_cmd::ExportStateCmd,
 	import_blocks_cmd::ImportBlocksCmd,
+	import_state_cmd::ImportStateCmd,
 	purge_chain_cmd::PurgeChainCmd,
 	sign::SignCmd,
 	generate::GenerateCmd,
@@ -50,6 +60,6 @@ pub use self::{
 	key::KeySubcommand,
 	vanity::VanityCmd,
 	verify::VerifyCmd,
-	revert_cmd::RevertCmd,
+	revert_cmd::{AuxRevert, FinishAuxRevert, RevertCmd},
 	run_cmd::RunCmd,
 };
@@ -19,19 +19,44 @@
 use crate::error;
 use crate::params::{DatabaseParams, SharedParams};
 use crate::CliConfiguration;
-use sc_service::DatabaseConfig;
+use sc_network::config::{NodeKeyConfig, Secret};
+use sc_service::{config::KeystoreConfig, Configuration, DatabasePart};
+use sp_runtime::traits::Block as BlockT;
 use std::fmt::Debug;
 use std::fs;
 use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use structopt::StructOpt;
 
 /// The `purge-chain` command used to remove the whole chain.
+///
+/// The database of the node is removed, or only parts of it. The keystore and the network key
+/// of the node are only removed on request.
 #[derive(Debug, StructOpt)]
 pub struct PurgeChainCmd {
 	/// Skip interactive prompt by answering yes automatically.
 	#[structopt(short = "y")]
 	pub yes: bool,
 
+	/// Remove only the state of the blocks, keeping the blocks, e.g. to export them.
+	///
+	/// The node can't import blocks anymore.
+	#[structopt(long, conflicts_with = "only-blocks")]
+	pub only_state: bool,
+
+	/// Remove only the bodies, justifications and indexed transactions of the blocks, keeping
+	/// their headers and the state, as if the blocks were pruned.
+	#[structopt(long)]
+	pub only_blocks: bool,
+
+	/// Remove the keystore of the node too, including the session keys of a validator.
+	#[structopt(long, conflicts_with_all = &["only-state", "only-blocks"])]
+	pub keystore: bool,
+
+	/// Remove the network key of the node too, and so its peer ID.
+	#[structopt(long, conflicts_with_all = &["only-state", "only-blocks"])]
+	pub network_key: bool,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub shared_params: SharedParams,
@@ -43,43 +68,93 @@ pub struct PurgeChainCmd {
 
 impl PurgeChainCmd {
 	/// Run the purge command
-	pub fn run(&self, database_config: DatabaseConfig) -> error::Result<()> {
-		let db_path = database_config.path()
+	pub fn run<B: BlockT>(&self, config: Configuration) -> error::Result<()> {
+		let db_path = config.database.path()
 			.ok_or_else(||
 				error::Error::Input("Cannot purge custom database implementation".into())
 		)?;
 
-		if !self.yes {
-			print!("Are you sure to remove {:?}? [y/N]: ", &db_path);
-			io::stdout().flush().expect("failed to flush stdout");
+		let part = match (self.only_state, self.only_blocks) {
+			(true, _) => Some(DatabasePart::State),
+			(_, true) => Some(DatabasePart::Blocks),
+			_ => None,
+		};
+		if let Some(part) = part {
+			let what = match part {
+				DatabasePart::State => "the state",
+				DatabasePart::Blocks => "the blocks",
+			};
+			if !self.yes && !confirm(&format!("remove {} of {:?}", what, db_path))? {
+				return Ok(());
+			}
+			sc_service::purge_database::<B>(&config, part)?;
+			println!("Removed {} of {:?}.", what, db_path);
+			return Ok(());
+		}
 
-			let mut input = String::new();
-			io::stdin().read_line(&mut input)?;
-			let input = input.trim();
+		let mut paths = vec![db_path.to_path_buf()];
+		paths.extend(
+			config.cold_storage.as_ref().and_then(|cold| cold.source.path()).map(Path::to_path_buf)
+		);
+		paths.extend(self.keystore_path(&config));
+		paths.extend(self.network_key_path(&config));
+		let listed = paths.iter().map(|path| format!("{:?}", path)).collect::<Vec<_>>();
+		if !self.yes && !confirm(&format!("remove {}", listed.join(", ")))? {
+			return Ok(());
+		}
 
-			match input.chars().nth(0) {
-				Some('y') | Some('Y') => {},
-				_ => {
-					println!("Aborted");
-					return Ok(());
-				},
+		for path in &paths {
+			let removed = if path.is_dir() {
+				fs::remove_dir_all(path)
+			} else {
+				fs::remove_file(path)
+			};
+			match removed {
+				Ok(_) => println!("{:?} removed.", path),
+				Err(ref err) if err.kind() == io::ErrorKind::NotFound =>
+					eprintln!("{:?} did not exist.", path),
+				Err(err) => return Err(err.into()),
 			}
 		}
+		Ok(())
+	}
+
+	/// The keystore to remove, if it's requested.
+	fn keystore_path(&self, config: &Configuration) -> Option<PathBuf> {
+		match config.keystore {
+			KeystoreConfig::Path { ref path, .. } if self.keystore => Some(path.clone()),
+			_ => None,
+		}
+	}
 
-		match fs::remove_dir_all(&db_path) {
-			Ok(_) => {
-				println!("{:?} removed.", &db_path);
-				Ok(())
-			},
-			Err(ref err) if err.kind() == io::ErrorKind::NotFound => {
-				eprintln!("{:?} did not exist.", &db_path);
-				Ok(())
-			},
-			Err(err) => Result::Err(err.into()),
+	/// The file of the network key to remove, if it's requested.
+	fn network_key_path(&self, config: &Configuration) -> Option<PathBuf> {
+		match config.network.node_key {
+			NodeKeyConfig::Ed25519(Secret::File(ref path)) if self.network_key =>
+				Some(path.clone()),
+			_ => None,
 		}
 	}
 }
 
+/// Ask for the confirmation of the given action, returns whether it is confirmed.
+fn confirm(action: &str) -> error::Result<bool> {
+	print!("Are you sure to {}? [y/N]: ", action);
+	io::stdout().flush().expect("failed to flush stdout");
+
+	let mut input = String::new();
+	io::stdin().read_line(&mut input)?;
+	let input = input.trim();
+
+	match input.chars().nth(0) {
+		Some('y') | Some('Y') => Ok(true),
+		_ => {
+			println!("Aborted");
+			Ok(false)
+		},
+	}
+}
+
 impl CliConfiguration for PurgeChainCmd {
 	fn shared_params(&self) -> &SharedParams {
 		&self.shared_params
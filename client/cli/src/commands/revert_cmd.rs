@@ -20,13 +20,25 @@ use crate::error;
 use crate::params::{GenericNumber, PruningParams, SharedParams};
 use crate::CliConfiguration;
 use sc_service::chain_ops::revert_chain;
-use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT, NumberFor};
 use std::fmt::Debug;
 use std::str::FromStr;
 use std::sync::Arc;
 use structopt::StructOpt;
 use sc_client_api::{Backend, UsageProvider};
 
+/// Reverts the auxiliary data (e.g. of the consensus) of the blocks reverted from the chain.
+///
+/// It's given the number of best blocks to revert, before they are reverted, and fails if their
+/// auxiliary data can't be reverted. Otherwise it returns the [`FinishAuxRevert`] reverting the
+/// data once the chain is reverted.
+pub type AuxRevert<C, BA, B> =
+	Box<dyn FnOnce(Arc<C>, Arc<BA>, NumberFor<B>) -> error::Result<FinishAuxRevert<B>>>;
+
+/// Reverts the auxiliary data once the chain is reverted, given the number of blocks actually
+/// reverted, which may be less than requested, e.g. if the state of older blocks is pruned.
+pub type FinishAuxRevert<B> = Box<dyn FnOnce(NumberFor<B>) -> error::Result<()>>;
+
 /// The `revert` command used revert the chain to a previous state.
 #[derive(Debug, StructOpt)]
 pub struct RevertCmd {
@@ -34,6 +46,12 @@ pub struct RevertCmd {
 	#[structopt(default_value = "256")]
 	pub num: GenericNumber,
 
+	/// Also revert finalized blocks, the consensus data (e.g. the GRANDPA authority set)
+	/// of the reverted blocks is reverted with them.
+	/// Otherwise the revert fails if it would revert finalized blocks.
+	#[structopt(long)]
+	pub force_finalized: bool,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub shared_params: SharedParams,
@@ -49,6 +67,7 @@ impl RevertCmd {
 		&self,
 		client: Arc<C>,
 		backend: Arc<BA>,
+		aux_revert: Option<AuxRevert<C, BA, B>>,
 	) -> error::Result<()>
 	where
 		B: BlockT,
@@ -56,8 +75,27 @@ impl RevertCmd {
 		C: UsageProvider<B>,
 		<<<B as BlockT>::Header as HeaderT>::Number as FromStr>::Err: Debug,
 	{
-		let blocks = self.num.parse()?;
-		revert_chain(client, backend, blocks)?;
+		let blocks: NumberFor<B> = self.num.parse()?;
+		let info = client.usage_info().chain;
+		let revertible = info.best_number - info.finalized_number;
+		if blocks > revertible && !self.force_finalized {
+			return Err(format!(
+				"Reverting {} blocks would revert finalized blocks, only the {} blocks after \
+				 the finalized block #{} can be reverted without --force-finalized.",
+				blocks,
+				revertible,
+				info.finalized_number,
+			).into());
+		}
+
+		let finish_aux_revert = match aux_revert {
+			Some(aux_revert) => Some(aux_revert(client.clone(), backend.clone(), blocks)?),
+			None => None,
+		};
+		let reverted = revert_chain(client, backend, blocks, self.force_finalized)?;
+		if let Some(finish_aux_revert) = finish_aux_revert {
+			finish_aux_revert(reverted)?;
+		}
 
 		Ok(())
 	}
@@ -16,7 +16,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::arg_enums::RpcMethods;
+use crate::arg_enums::{InformantFormat, RpcMethods};
 use crate::error::{Error, Result};
 use crate::params::ImportParams;
 use crate::params::KeystoreParams;
@@ -27,11 +27,19 @@ use crate::params::TransactionPoolParams;
 use crate::CliConfiguration;
 use regex::Regex;
 use sc_service::{
-	config::{BasePath, PrometheusConfig, TransactionPoolOptions},
+	config::{
+		BasePath, DiskSpaceConfig, InformantOutputFormat, PrometheusAuth, PrometheusConfig,
+		PrometheusTls, RpcAuth, RpcMethodPolicy, RpcRateLimit, RpcRequestLimits, RpcTypeAliases,
+		TransactionPoolOptions,
+	},
 	ChainSpec, Role,
 };
-use sc_telemetry::TelemetryEndpoints;
+use sc_telemetry::{EndpointOptions, TelemetryEndpoints};
+use std::collections::HashMap;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::num::NonZeroU32;
+use std::path::PathBuf;
+use std::time::Duration;
 use structopt::StructOpt;
 
 /// The `run` command used to run a node.
@@ -100,6 +108,19 @@ pub struct RunCmd {
 	#[structopt(long = "unsafe-ws-external")]
 	pub unsafe_ws_external: bool,
 
+	/// Listen to all gRPC gateway interfaces.
+	///
+	/// Default is local. Same caveats as `--ws-external`.
+	/// Use `--unsafe-grpc-external` to suppress the warning if you understand the risks.
+	#[structopt(long = "grpc-external", requires = "grpc-port")]
+	pub grpc_external: bool,
+
+	/// Listen to all gRPC gateway interfaces.
+	///
+	/// Same as `--grpc-external` but doesn't warn you about it.
+	#[structopt(long = "unsafe-grpc-external", requires = "grpc-port")]
+	pub unsafe_grpc_external: bool,
+
 	/// Listen to all Prometheus data source interfaces.
 	///
 	/// Default is local.
@@ -107,9 +128,21 @@ pub struct RunCmd {
 	pub prometheus_external: bool,
 
 	/// Specify IPC RPC server path
+	///
+	/// The unsafe RPC methods are exposed over IPC unless `--rpc-methods` is `Safe`.
 	#[structopt(long = "ipc-path", value_name = "PATH")]
 	pub ipc_path: Option<String>,
 
+	/// File permissions of the IPC RPC socket, in octal (e.g. `660`).
+	///
+	/// Default is `600`, only the user running the node may connect.
+	#[structopt(
+		long = "ipc-permissions",
+		value_name = "MODE",
+		parse(try_from_str = parse_permissions)
+	)]
+	pub ipc_permissions: Option<u32>,
+
 	/// Specify HTTP RPC server TCP port.
 	#[structopt(long = "rpc-port", value_name = "PORT")]
 	pub rpc_port: Option<u16>,
@@ -118,10 +151,84 @@ pub struct RunCmd {
 	#[structopt(long = "ws-port", value_name = "PORT")]
 	pub ws_port: Option<u16>,
 
+	/// Specify the TCP port of the gRPC gateway to the chain, state and author RPC methods.
+	///
+	/// The gateway is disabled unless a port is given.
+	#[structopt(long = "grpc-port", value_name = "PORT")]
+	pub grpc_port: Option<u16>,
+
 	/// Maximum number of WS RPC server connections.
 	#[structopt(long = "ws-max-connections", value_name = "COUNT")]
 	pub ws_max_connections: Option<usize>,
 
+	/// Maximum number of requests per second of every HTTP & WS RPC client.
+	///
+	/// Clients are identified by the IP a `--rpc-trusted-proxy` sets in the `X-Forwarded-For`
+	/// or `X-Real-IP` header, WS clients also by their connection. Requests above the limit
+	/// are rejected with a "429 Too Many Requests" error. Unlimited by default.
+	#[structopt(long = "rpc-rate-limit", value_name = "COUNT")]
+	pub rpc_rate_limit: Option<NonZeroU32>,
+
+	/// Number of requests an RPC client may make in a burst above `--rpc-rate-limit`.
+	///
+	/// Defaults to the rate limit.
+	#[structopt(long = "rpc-rate-limit-burst", value_name = "COUNT", requires = "rpc-rate-limit")]
+	pub rpc_rate_limit_burst: Option<u32>,
+
+	/// IP address of a reverse proxy trusted to report the IP of the RPC clients.
+	///
+	/// May be given multiple times. The `X-Forwarded-For` and `X-Real-IP` headers of the
+	/// requests are ignored unless proxies are trusted: the HTTP & WS servers should then only
+	/// be reachable through them.
+	#[structopt(long = "rpc-trusted-proxy", value_name = "IP")]
+	pub rpc_trusted_proxies: Vec<IpAddr>,
+
+	/// Maximum number of calls in a batch of RPC requests. Unlimited by default.
+	#[structopt(long = "rpc-max-batch-size", value_name = "COUNT")]
+	pub rpc_max_batch_size: Option<usize>,
+
+	/// Process the calls of a batch of RPC requests one after another instead of concurrently.
+	#[structopt(long = "rpc-sequential-batches")]
+	pub rpc_sequential_batches: bool,
+
+	/// Maximum number of RPC calls in flight over a WS connection. Unlimited by default.
+	#[structopt(long = "rpc-max-in-flight", value_name = "COUNT")]
+	pub rpc_max_in_flight: Option<usize>,
+
+	/// Require the given token to call the unsafe RPC methods over HTTP & WS.
+	///
+	/// HTTP clients pass it in the `Authorization: Bearer <TOKEN>` header of their requests,
+	/// WS clients call `rpc_authenticate` with it once per connection. The unsafe methods are
+	/// denied to every other client, regardless of `--rpc-methods`.
+	#[structopt(
+		long = "rpc-auth-token",
+		value_name = "TOKEN",
+		conflicts_with = "rpc-auth-jwt-secret",
+	)]
+	pub rpc_auth_token: Option<String>,
+
+	/// Require a JSON Web Token signed (HS256) with the given hex-encoded secret to call the
+	/// unsafe RPC methods over HTTP & WS.
+	///
+	/// The token is passed as with `--rpc-auth-token`. Its `exp` and `nbf` claims are enforced.
+	#[structopt(long = "rpc-auth-jwt-secret", value_name = "HEX")]
+	pub rpc_auth_jwt_secret: Option<String>,
+
+	/// Apply the access rules of the given JSON file to the HTTP & WS RPC methods.
+	///
+	/// The file maps method names to `allowed`, `denied` or `auth-required`, overriding
+	/// `--rpc-methods`, e.g. `{ "state_getKeysPaged": "denied" }`. Methods requiring
+	/// authentication are denied unless `--rpc-auth-token` or `--rpc-auth-jwt-secret` is set.
+	#[structopt(long = "rpc-method-policy", value_name = "PATH", parse(from_os_str))]
+	pub rpc_method_policy: Option<PathBuf>,
+
+	/// Decode the extrinsics returned over RPC with the type aliases of the given JSON file.
+	///
+	/// The file maps the names of the aliases used by the runtime calls to their types,
+	/// overriding the ones of the Substrate node, e.g. `{ "Balance": "u64" }`.
+	#[structopt(long = "rpc-type-aliases", value_name = "PATH", parse(from_os_str))]
+	pub rpc_type_aliases: Option<PathBuf>,
+
 	/// Specify browser Origins allowed to access the HTTP & WS RPC servers.
 	///
 	/// A comma-separated list of origins (protocol://domain or special `null`
@@ -135,12 +242,79 @@ pub struct RunCmd {
 	#[structopt(long = "prometheus-port", value_name = "PORT")]
 	pub prometheus_port: Option<u16>,
 
+	/// Serve the Prometheus metrics over TLS with the PEM-encoded certificate chain of the
+	/// given file.
+	#[structopt(
+		long = "prometheus-tls-cert",
+		value_name = "PATH",
+		parse(from_os_str),
+		requires = "prometheus-tls-key",
+	)]
+	pub prometheus_tls_cert: Option<PathBuf>,
+
+	/// The PEM-encoded private key of the certificate of `--prometheus-tls-cert`.
+	#[structopt(
+		long = "prometheus-tls-key",
+		value_name = "PATH",
+		parse(from_os_str),
+		requires = "prometheus-tls-cert",
+	)]
+	pub prometheus_tls_key: Option<PathBuf>,
+
+	/// Only serve the Prometheus metrics to the requests authenticated with the username and
+	/// password of the given file, as `USERNAME:PASSWORD`, using HTTP basic authentication.
+	///
+	/// They are read from the `SUBSTRATE_PROMETHEUS_BASIC_AUTH` environment variable if no file
+	/// is given.
+	#[structopt(
+		long = "prometheus-basic-auth-file",
+		value_name = "PATH",
+		parse(from_os_str),
+		conflicts_with = "prometheus-bearer-token-file",
+	)]
+	pub prometheus_basic_auth_file: Option<PathBuf>,
+
+	/// Only serve the Prometheus metrics to the requests passing the token of the given file
+	/// in their `Authorization: Bearer <TOKEN>` header.
+	///
+	/// It's read from the `SUBSTRATE_PROMETHEUS_BEARER_TOKEN` environment variable if no file
+	/// is given.
+	#[structopt(long = "prometheus-bearer-token-file", value_name = "PATH", parse(from_os_str))]
+	pub prometheus_bearer_token_file: Option<PathBuf>,
+
 	/// Do not expose a Prometheus metric endpoint.
 	///
 	/// Prometheus metric endpoint is enabled by default.
 	#[structopt(long = "no-prometheus")]
 	pub no_prometheus: bool,
 
+	/// Serve the `/health`, `/ready` and `/status` HTTP endpoints on the given TCP port.
+	///
+	/// The endpoints report the peer count, the sync state and the age of the last
+	/// imported and finalized blocks, and `/status` the version and roles of the node, without
+	/// exposing the RPC methods. They are disabled by default.
+	#[structopt(long = "health-port", value_name = "PORT")]
+	pub health_port: Option<u16>,
+
+	/// Warn once the free space of the disk of the database falls below this many MiB.
+	///
+	/// Errors are logged once it falls below a quarter of it.
+	#[structopt(long = "db-space-warning", value_name = "MiB", default_value = "1024")]
+	pub db_space_warning: u64,
+
+	/// Pause the offchain workers while the free space of the disk of the database is below
+	/// this many MiB.
+	///
+	/// The blocks are still imported, the node can't follow the chain without writing them.
+	#[structopt(long = "db-space-pause", value_name = "MiB")]
+	pub db_space_pause: Option<u64>,
+
+	/// Listen to all interfaces for the health endpoints.
+	///
+	/// Default is local.
+	#[structopt(long = "health-external")]
+	pub health_external: bool,
+
 	/// The human-readable name for this node.
 	///
 	/// The node name will be reported to the telemetry server, if enabled.
@@ -162,6 +336,52 @@ pub struct RunCmd {
 	#[structopt(long = "telemetry-url", value_name = "URL VERBOSITY", parse(try_from_str = parse_telemetry_endpoints))]
 	pub telemetry_endpoints: Vec<(String, u8)>,
 
+	/// Connect to the telemetry servers with the options of the given JSON file.
+	///
+	/// The file maps the URLs of the servers to their options, e.g.
+	/// `{ "wss://foo/bar": { "headers": { "X-Api-Key": "KEY" }, "clientCertificate":
+	/// { "certificate": "cert.pem", "privateKey": "key.pem" }, "caCertificate": "ca.pem",
+	/// "reconnectBackoff": { "initialSecs": 5, "maxSecs": 300, "jitterSecs": 5 } } }`, all of
+	/// them optional.
+	#[structopt(long = "telemetry-endpoint-options", value_name = "PATH", parse(from_os_str))]
+	pub telemetry_endpoint_options: Option<PathBuf>,
+
+	/// Start the node without the preflight checks of its environment.
+	///
+	/// The file descriptor limit, the permissions of the database and keystore directories, the
+	/// memory used by the caches and, with `--ntp-server`, the clock are checked on start, and
+	/// the node isn't started if one of them failed.
+	#[structopt(long = "skip-preflight-checks")]
+	pub skip_preflight_checks: bool,
+
+	/// Compare the clock with the given NTP server in the preflight checks, e.g.
+	/// `pool.ntp.org:123`.
+	///
+	/// The clock isn't checked by default, so no server is contacted unless asked to.
+	#[structopt(long = "ntp-server", value_name = "HOST:PORT")]
+	pub ntp_server: Option<String>,
+
+	/// How long to wait, in seconds, for the node to shut down once it received `SIGTERM` or
+	/// `SIGINT`.
+	///
+	/// The node stops accepting RPC requests, flushes the transaction pool and telemetry and
+	/// finishes the block being imported before it exits, or once this timeout elapsed.
+	#[structopt(long = "shutdown-timeout", value_name = "SECONDS", default_value = "60")]
+	pub shutdown_timeout: u64,
+
+	/// The format of the status lines printed by the informant.
+	///
+	/// `Json` prints every status line and imported block as a JSON object on its own line of
+	/// the standard output, without the prefix of the logs, so log collectors can parse them.
+	#[structopt(
+		long = "informant-format",
+		value_name = "FORMAT",
+		possible_values = &InformantFormat::variants(),
+		case_insensitive = true,
+		default_value = "Text",
+	)]
+	pub informant_format: InformantFormat,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub offchain_worker_params: OffchainWorkerParams,
@@ -228,6 +448,25 @@ pub struct RunCmd {
 	#[structopt(long)]
 	pub max_runtime_instances: Option<usize>,
 
+	/// The number of instances of each runtime created as soon as it is loaded, before the
+	/// runtime calls need them.
+	///
+	/// The default value is 0, no more than `--max-runtime-instances` are created.
+	#[structopt(long, value_name = "COUNT")]
+	pub preinstantiated_runtime_instances: Option<usize>,
+
+	/// The number of runtimes kept in the cache, e.g. for the RPC calls to historical blocks.
+	///
+	/// The default value is 2, the least recently used runtimes are evicted past it.
+	#[structopt(long, value_name = "COUNT")]
+	pub runtime_cache_size: Option<usize>,
+
+	/// The maximum size of the code of the runtimes kept in the cache, in bytes.
+	///
+	/// The least recently used runtimes are evicted past it, but the last one used.
+	#[structopt(long, value_name = "BYTES")]
+	pub runtime_cache_max_code_size: Option<usize>,
+
 	/// Run a temporary node.
 	///
 	/// A temporary directory will be created to store the configuration and will be deleted
@@ -237,6 +476,10 @@ pub struct RunCmd {
 	/// which includes: database, node key and keystore.
 	#[structopt(long, conflicts_with = "base-path")]
 	pub tmp: bool,
+
+	/// Apply the pending migrations of the database and exit, without starting the node.
+	#[structopt(long)]
+	pub db_migrate_only: bool,
 }
 
 impl RunCmd {
@@ -264,6 +507,32 @@ impl RunCmd {
 			None
 		}
 	}
+
+	fn prometheus_auth(&self) -> Result<Option<PrometheusAuth>> {
+		let basic_auth =
+			read_secret(&self.prometheus_basic_auth_file, PROMETHEUS_BASIC_AUTH_ENV)?;
+		let bearer_token =
+			read_secret(&self.prometheus_bearer_token_file, PROMETHEUS_BEARER_TOKEN_ENV)?;
+
+		match (basic_auth, bearer_token) {
+			(Some(_), Some(_)) => Err(Error::Input(
+				"Expected either a basic auth or a bearer token for Prometheus, not both".into()
+			)),
+			(None, Some(token)) => Ok(Some(PrometheusAuth::Bearer(token))),
+			(Some(credentials), None) => {
+				let mut parts = credentials.splitn(2, ':');
+				match (parts.next(), parts.next()) {
+					(Some(username), Some(password)) if !username.is_empty() =>
+						Ok(Some(PrometheusAuth::Basic {
+							username: username.into(),
+							password: password.into(),
+						})),
+					_ => Err(Error::Input("Expected `USERNAME:PASSWORD` as the basic auth".into())),
+				}
+			},
+			(None, None) => Ok(None),
+		}
+	}
 }
 
 impl CliConfiguration for RunCmd {
@@ -318,7 +587,7 @@ impl CliConfiguration for RunCmd {
 		&self,
 		chain_spec: &Box<dyn ChainSpec>,
 	) -> Result<Option<TelemetryEndpoints>> {
-		Ok(if self.no_telemetry {
+		let mut endpoints = if self.no_telemetry {
 			None
 		} else if !self.telemetry_endpoints.is_empty() {
 			Some(
@@ -327,7 +596,36 @@ impl CliConfiguration for RunCmd {
 			)
 		} else {
 			chain_spec.telemetry_endpoints().clone()
-		})
+		};
+
+		if let (Some(endpoints), Some(path)) = (&mut endpoints, &self.telemetry_endpoint_options) {
+			let file = std::fs::File::open(path)?;
+			let options: HashMap<String, EndpointOptions> =
+				serde_json::from_reader(std::io::BufReader::new(file)).map_err(|e| {
+					Error::Input(format!("Invalid telemetry endpoint options: {}", e))
+				})?;
+			for (url, options) in options {
+				endpoints.set_options(&url, options).map_err(|e| e.to_string())?;
+			}
+		}
+
+		Ok(endpoints)
+	}
+
+	fn skip_preflight_checks(&self) -> Result<bool> {
+		Ok(self.skip_preflight_checks)
+	}
+
+	fn ntp_server(&self) -> Result<Option<String>> {
+		Ok(self.ntp_server.clone())
+	}
+
+	fn shutdown_timeout(&self) -> Result<Option<Duration>> {
+		Ok(Some(Duration::from_secs(self.shutdown_timeout)))
+	}
+
+	fn informant_output_format(&self) -> Result<InformantOutputFormat> {
+		Ok(InformantOutputFormat { style: self.informant_format.into(), ..Default::default() })
 	}
 
 	fn role(&self, is_dev: bool) -> Result<Role> {
@@ -359,15 +657,40 @@ impl CliConfiguration for RunCmd {
 				Ipv4Addr::LOCALHOST
 			};
 
-			Some(PrometheusConfig::new_with_default_registry(
+			let mut config = PrometheusConfig::new_with_default_registry(
 				SocketAddr::new(
 					interface.into(),
 					self.prometheus_port.unwrap_or(default_listen_port),
 				)
-			))
+			);
+			config.security.tls = self.prometheus_tls_cert.clone()
+				.zip(self.prometheus_tls_key.clone())
+				.map(|(certificate, private_key)| PrometheusTls { certificate, private_key });
+			config.security.auth = self.prometheus_auth()?;
+			Some(config)
 		})
 	}
 
+	fn health_endpoint(&self) -> Result<Option<SocketAddr>> {
+		let interface = if self.health_external {
+			Ipv4Addr::UNSPECIFIED
+		} else {
+			Ipv4Addr::LOCALHOST
+		};
+
+		Ok(self.health_port.map(|port| SocketAddr::new(interface.into(), port)))
+	}
+
+	fn disk_space(&self) -> Result<Option<DiskSpaceConfig>> {
+		const MIB: u64 = 1024 * 1024;
+
+		Ok(Some(DiskSpaceConfig {
+			warning_threshold: self.db_space_warning.saturating_mul(MIB),
+			pause_threshold: self.db_space_pause.map(|pause| pause.saturating_mul(MIB)),
+			..Default::default()
+		}))
+	}
+
 	fn disable_grandpa(&self) -> Result<bool> {
 		Ok(self.no_grandpa)
 	}
@@ -376,6 +699,55 @@ impl CliConfiguration for RunCmd {
 		Ok(self.ws_max_connections)
 	}
 
+	fn rpc_rate_limit(&self) -> Result<Option<RpcRateLimit>> {
+		Ok(self.rpc_rate_limit.map(|limit| RpcRateLimit {
+			requests_per_second: limit.get(),
+			burst: self.rpc_rate_limit_burst.unwrap_or_else(|| limit.get()),
+		}))
+	}
+
+	fn rpc_trusted_proxies(&self) -> Result<Vec<IpAddr>> {
+		Ok(self.rpc_trusted_proxies.clone())
+	}
+
+	fn rpc_request_limits(&self) -> Result<RpcRequestLimits> {
+		Ok(RpcRequestLimits {
+			max_batch_size: self.rpc_max_batch_size,
+			sequential_batches: self.rpc_sequential_batches,
+			max_in_flight: self.rpc_max_in_flight,
+		})
+	}
+
+	fn rpc_auth(&self) -> Result<Option<RpcAuth>> {
+		if let Some(token) = &self.rpc_auth_token {
+			return Ok(Some(RpcAuth::Token(token.clone())));
+		}
+
+		self.rpc_auth_jwt_secret.as_ref()
+			.map(|secret| {
+				let secret = hex::decode(secret.trim_start_matches("0x"))?;
+				if secret.is_empty() {
+					return Err(Error::Input("The JWT secret can't be empty".into()));
+				}
+				Ok(RpcAuth::Jwt(secret))
+			})
+			.transpose()
+	}
+
+	fn rpc_type_aliases(&self) -> Result<RpcTypeAliases> {
+		self.rpc_type_aliases.as_ref()
+			.map(|path| RpcTypeAliases::load(path).map_err(Error::Input))
+			.transpose()
+			.map(Option::unwrap_or_default)
+	}
+
+	fn rpc_method_policy(&self) -> Result<RpcMethodPolicy> {
+		self.rpc_method_policy.as_ref()
+			.map(|path| RpcMethodPolicy::load(path).map_err(|e| Error::Input(e.to_string())))
+			.transpose()
+			.map(Option::unwrap_or_default)
+	}
+
 	fn rpc_cors(&self, is_dev: bool) -> Result<Option<Vec<String>>> {
 		Ok(self
 			.rpc_cors
@@ -412,6 +784,10 @@ impl CliConfiguration for RunCmd {
 		Ok(self.ipc_path.clone())
 	}
 
+	fn rpc_ipc_permissions(&self) -> Result<Option<u32>> {
+		Ok(self.ipc_permissions)
+	}
+
 	fn rpc_ws(&self, default_listen_port: u16) -> Result<Option<SocketAddr>> {
 		let interface = rpc_interface(
 			self.ws_external,
@@ -423,6 +799,21 @@ impl CliConfiguration for RunCmd {
 		Ok(Some(SocketAddr::new(interface, self.ws_port.unwrap_or(default_listen_port))))
 	}
 
+	fn rpc_grpc(&self) -> Result<Option<SocketAddr>> {
+		let port = match self.grpc_port {
+			Some(port) => port,
+			None => return Ok(None),
+		};
+		let interface = rpc_interface(
+			self.grpc_external,
+			self.unsafe_grpc_external,
+			self.rpc_methods,
+			self.validator,
+		)?;
+
+		Ok(Some(SocketAddr::new(interface, port)))
+	}
+
 	fn rpc_methods(&self) -> Result<sc_service::config::RpcMethods> {
 		Ok(self.rpc_methods.into())
 	}
@@ -435,6 +826,18 @@ impl CliConfiguration for RunCmd {
 		Ok(self.max_runtime_instances.map(|x| x.min(256)))
 	}
 
+	fn preinstantiated_runtime_instances(&self) -> Result<Option<usize>> {
+		Ok(self.preinstantiated_runtime_instances)
+	}
+
+	fn runtime_cache_size(&self) -> Result<Option<usize>> {
+		Ok(self.runtime_cache_size)
+	}
+
+	fn runtime_cache_max_code_size(&self) -> Result<Option<usize>> {
+		Ok(self.runtime_cache_max_code_size)
+	}
+
 	fn base_path(&self) -> Result<Option<BasePath>> {
 		Ok(if self.tmp {
 			Some(BasePath::new_temp_dir()?)
@@ -569,10 +972,64 @@ fn parse_cors(s: &str) -> std::result::Result<Cors, Box<dyn std::error::Error>>
 	})
 }
 
+/// Parse octal file permissions.
+fn parse_permissions(s: &str) -> std::result::Result<u32, String> {
+	let digits = s.trim_start_matches("0o");
+	match u32::from_str_radix(digits, 8) {
+		Ok(mode) if mode <= 0o777 => Ok(mode),
+		_ => Err(format!("Invalid file permissions `{}`, expected e.g. `660`", s)),
+	}
+}
+
+/// The environment variable with the `USERNAME:PASSWORD` of the Prometheus basic auth.
+const PROMETHEUS_BASIC_AUTH_ENV: &str = "SUBSTRATE_PROMETHEUS_BASIC_AUTH";
+/// The environment variable with the Prometheus bearer token.
+const PROMETHEUS_BEARER_TOKEN_ENV: &str = "SUBSTRATE_PROMETHEUS_BEARER_TOKEN";
+
+/// Read the secret of the given file without its trailing newline, or else of the given
+/// environment variable, so it isn't passed on the command line.
+fn read_secret(file: &Option<PathBuf>, env: &str) -> Result<Option<String>> {
+	match file {
+		Some(file) => std::fs::read_to_string(file)
+			.map(|secret| Some(secret.trim_end_matches(&['\r', '\n'][..]).to_owned()))
+			.map_err(|e| Error::Input(format!("Failed to read {}: {}", file.display(), e))),
+		None => Ok(std::env::var(env).ok()),
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
 
+	#[test]
+	fn parses_octal_permissions() {
+		assert_eq!(parse_permissions("600"), Ok(0o600));
+		assert_eq!(parse_permissions("0o660"), Ok(0o660));
+		assert_eq!(parse_permissions("0660"), Ok(0o660));
+		assert!(parse_permissions("800").is_err());
+		assert!(parse_permissions("1777").is_err());
+	}
+
+	#[test]
+	fn parses_prometheus_basic_auth() {
+		let dir = tempfile::tempdir().unwrap();
+		let auth = |credentials: &str| {
+			let file = dir.path().join("basic-auth");
+			std::fs::write(&file, format!("{}\n", credentials)).unwrap();
+			RunCmd::from_iter(&["run", "--prometheus-basic-auth-file", file.to_str().unwrap()])
+				.prometheus_auth()
+		};
+		match auth("prometheus:pass:word") {
+			Ok(Some(PrometheusAuth::Basic { username, password })) => {
+				assert_eq!(username, "prometheus");
+				assert_eq!(password, "pass:word");
+			},
+			other => panic!("Unexpected basic auth: {:?}", other),
+		}
+		assert!(auth("prometheus").is_err());
+		assert!(auth(":password").is_err());
+	}
+
 	#[test]
 	fn tests_node_name_good() {
 		assert!(is_node_name_valid("short name").is_ok());
@@ -17,15 +17,19 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 //! Implementation of the `sign` subcommand
-use crate::{error, utils, with_crypto_scheme, CryptoSchemeFlag, KeystoreParams};
+use crate::{
+	error, utils, with_crypto_scheme, CryptoSchemeFlag, KeystoreParams, SharedParams, SubstrateCli,
+};
+use std::{convert::TryFrom, path::PathBuf};
 use structopt::StructOpt;
-use sp_core::crypto::SecretString;
+use sp_core::{Public, crypto::{CryptoTypePublicPair, KeyTypeId, SecretString, Ss58Codec}};
+use sp_keystore::{SyncCryptoStore, SyncCryptoStorePtr};
 
 /// The `sign` command
 #[derive(Debug, StructOpt)]
 #[structopt(
 	name = "sign",
-	about = "Sign a message, with a given (secret) key"
+	about = "Sign a message, with a given (secret) key or a key of the keystore of a node"
 )]
 pub struct SignCmd {
 	/// The secret key URI.
@@ -34,6 +38,14 @@ pub struct SignCmd {
 	#[structopt(long)]
 	suri: Option<String>,
 
+	/// Sign with the key of the keystore of this type, examples: "gran", or "imon".
+	#[structopt(long, requires = "public", conflicts_with = "suri")]
+	key_type: Option<String>,
+
+	/// The public key, hex-encoded or SS58, of the key of the keystore to sign with.
+	#[structopt(long, requires = "key-type")]
+	public: Option<String>,
+
 	/// Message to sign, if not provided you will be prompted to
 	/// pass the message via STDIN
 	#[structopt(long)]
@@ -43,6 +55,15 @@ pub struct SignCmd {
 	#[structopt(long)]
 	hex: bool,
 
+	/// The message is the payload of an extrinsic, signed as the runtime does:
+	/// its blake2-256 hash is signed if it's longer than 256 bytes.
+	#[structopt(long)]
+	extrinsic: bool,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub keystore_params: KeystoreParams,
@@ -54,20 +75,53 @@ pub struct SignCmd {
 
 
 impl SignCmd {
-	/// Run the command
+	/// Run the command, the keystore to sign with has to be given with `--keystore-path`.
 	pub fn run(&self) -> error::Result<()> {
-		let message = utils::read_message(self.message.as_ref(), self.hex)?;
-		let suri = utils::read_uri(self.suri.as_ref())?;
-		let password = self.keystore_params.read_password()?;
-
-		let signature = with_crypto_scheme!(
-			self.crypto_scheme.scheme,
-			sign(&suri, password, message)
-		)?;
+		let signature = self.signature(|| match self.keystore_params.keystore_path {
+			Some(_) => Ok(utils::open_keystore_at(&self.keystore_params, &PathBuf::new())?.0),
+			None => Err("The keystore to sign with has to be given with --keystore-path".into()),
+		})?;
+		println!("{}", signature);
+		Ok(())
+	}
 
+	/// Run the command, signing with the keystore of the chain unless `--keystore-path`
+	/// is given.
+	pub fn run_with_chain<C: SubstrateCli>(&self, cli: &C) -> error::Result<()> {
+		let signature = self.signature(|| {
+			Ok(utils::open_keystore(cli, &self.shared_params, &self.keystore_params)?.0)
+		})?;
 		println!("{}", signature);
 		Ok(())
 	}
+
+	/// Returns the hex-encoded signature of the message.
+	fn signature(
+		&self,
+		keystore: impl FnOnce() -> error::Result<SyncCryptoStorePtr>,
+	) -> error::Result<String> {
+		let mut message = utils::read_message(self.message.as_ref(), self.hex)?;
+		if self.extrinsic {
+			message = utils::extrinsic_payload(message);
+		}
+
+		match (&self.key_type, &self.public) {
+			(Some(key_type), Some(public)) => {
+				let id = KeyTypeId::try_from(key_type.as_str())
+					.map_err(|_| error::Error::KeyTypeInvalid)?;
+				let key = with_crypto_scheme!(self.crypto_scheme.scheme, decode_public(public))?;
+				let signature = SyncCryptoStore::sign_with(&*keystore()?, id, &key, &message)
+					.map_err(|_| error::Error::KeyStoreOperation)?
+					.ok_or_else(|| format!("No {} key {} in the keystore", key_type, public))?;
+				Ok(hex::encode(signature))
+			},
+			_ => {
+				let suri = utils::read_uri(self.suri.as_ref())?;
+				let password = self.keystore_params.read_password()?;
+				with_crypto_scheme!(self.crypto_scheme.scheme, sign(&suri, password, message))
+			},
+		}
+	}
 }
 
 fn sign<P: sp_core::Pair>(suri: &str, password: Option<SecretString>, message: Vec<u8>) ->  error::Result<String> {
@@ -75,10 +129,26 @@ fn sign<P: sp_core::Pair>(suri: &str, password: Option<SecretString>, message: V
 	Ok(format!("{}", hex::encode(pair.sign(&message))))
 }
 
+fn decode_public<P: sp_core::Pair>(public: &str) -> error::Result<CryptoTypePublicPair> {
+	let public = match hex::decode(public.trim_start_matches("0x")) {
+		Ok(public) if public.len() == P::Public::default().as_ref().len() =>
+			P::Public::from_slice(&public),
+		Ok(_) => return Err(error::Error::Input("Invalid length of the public key".into())),
+		Err(_) => P::Public::from_string(public)?,
+	};
+	Ok(public.to_public_crypto_pair())
+}
+
 #[cfg(test)]
 mod test {
 	use super::SignCmd;
+	use crate::VerifyCmd;
 	use structopt::StructOpt;
+	use std::sync::Arc;
+	use tempfile::TempDir;
+	use sc_keystore::LocalKeystore;
+	use sp_core::{sr25519, Pair as _, crypto::KeyTypeId};
+	use sp_keystore::{SyncCryptoStore, SyncCryptoStorePtr};
 
 	#[test]
 	fn sign() {
@@ -95,4 +165,26 @@ mod test {
 		]);
 		assert!(sign.run().is_ok());
 	}
+
+	#[test]
+	fn sign_with_keystore() {
+		let path = TempDir::new().unwrap();
+		let (pair, uri, _) = sr25519::Pair::generate_with_phrase(None);
+		let public = hex::encode(pair.public());
+		let keystore: SyncCryptoStorePtr = Arc::new(LocalKeystore::open(path.path(), None).unwrap());
+		let key_type = KeyTypeId(*b"test");
+		SyncCryptoStore::insert_unknown(&*keystore, key_type, &uri, pair.public().as_ref()).unwrap();
+
+		let sign = |public: &str| SignCmd::from_iter(&[
+			"sign",
+			"--key-type", "test",
+			"--public", public,
+			"--message", "0x1234",
+		]).signature(|| Ok(keystore.clone()));
+		assert!(sign(&hex::encode(sr25519::Pair::generate().0.public())).is_err());
+
+		let signature = sign(&public).unwrap();
+		let verify = VerifyCmd::from_iter(&["verify", &signature, &public, "--message", "0x1234"]);
+		assert!(verify.run().is_ok());
+	}
 }
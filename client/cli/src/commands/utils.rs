@@ -17,13 +17,19 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 //! subcommand utilities
-use std::{io::Read, path::PathBuf, convert::TryFrom};
+use std::{io::Read, path::{Path, PathBuf}, convert::TryFrom, sync::Arc};
 use sp_core::{
-	Pair, hexdisplay::HexDisplay,
+	Pair, hexdisplay::HexDisplay, hashing::blake2_256,
 	crypto::{Ss58Codec, Ss58AddressFormat},
 };
+use sp_keystore::SyncCryptoStorePtr;
 use sp_runtime::{MultiSigner, traits::IdentifyAccount};
-use crate::{OutputType, error::{self, Error}};
+use sc_keystore::LocalKeystore;
+use sc_service::config::{BasePath, KeystoreConfig};
+use crate::{
+	OutputType, KeystoreParams, SharedParams, SubstrateCli,
+	error::{self, Error},
+};
 use serde_json::json;
 use sp_core::crypto::{SecretString, Zeroize, ExposeSecret};
 
@@ -266,6 +272,53 @@ pub fn read_message(msg: Option<&String>, should_decode: bool) -> Result<Vec<u8>
 	Ok(message)
 }
 
+/// returns the data signed for the given extrinsic payload, which is hashed if it's
+/// longer than 256 bytes, as the signed extensions of the runtime do.
+pub fn extrinsic_payload(payload: Vec<u8>) -> Vec<u8> {
+	if payload.len() > 256 {
+		blake2_256(&payload).to_vec()
+	} else {
+		payload
+	}
+}
+
+/// opens the keystore of the chain, or the one at `--keystore-path` if it's given,
+/// and returns it with its password.
+pub(crate) fn open_keystore<C: SubstrateCli>(
+	cli: &C,
+	shared_params: &SharedParams,
+	keystore_params: &KeystoreParams,
+) -> Result<(SyncCryptoStorePtr, Option<SecretString>), Error> {
+	// the keystore of the chain isn't looked up if its path is given.
+	let config_dir = match keystore_params.keystore_path {
+		Some(_) => PathBuf::new(),
+		None => {
+			let base_path = shared_params
+				.base_path()
+				.unwrap_or_else(|| BasePath::from_project("", "", &C::executable_name()));
+			let chain_id = shared_params.chain_id(shared_params.is_dev());
+			let chain_spec = cli.load_spec(&chain_id)?;
+			base_path.config_dir(chain_spec.id())
+		},
+	};
+	open_keystore_at(keystore_params, &config_dir)
+}
+
+/// opens the keystore of the given configuration directory, or the one at `--keystore-path`
+/// if it's given, and returns it with its password.
+pub(crate) fn open_keystore_at(
+	keystore_params: &KeystoreParams,
+	config_dir: &Path,
+) -> Result<(SyncCryptoStorePtr, Option<SecretString>), Error> {
+	match keystore_params.keystore_config(config_dir)? {
+		(_, KeystoreConfig::Path { path, password }) => {
+			let keystore: SyncCryptoStorePtr =
+				Arc::new(LocalKeystore::open(path, password.clone())?);
+			Ok((keystore, password))
+		},
+		_ => unreachable!("keystore_config always returns path and password; qed")
+	}
+}
 
 /// Allows for calling $method with appropriate crypto impl.
 #[macro_export]
@@ -46,6 +46,11 @@ pub struct VerifyCmd {
 	#[structopt(long)]
 	hex: bool,
 
+	/// The message is the payload of an extrinsic, signed as the runtime does:
+	/// its blake2-256 hash is signed if it's longer than 256 bytes.
+	#[structopt(long)]
+	extrinsic: bool,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub crypto_scheme: CryptoSchemeFlag,
@@ -54,7 +59,10 @@ pub struct VerifyCmd {
 impl VerifyCmd {
 	/// Run the command
 	pub fn run(&self) -> error::Result<()> {
-		let message = utils::read_message(self.message.as_ref(), self.hex)?;
+		let mut message = utils::read_message(self.message.as_ref(), self.hex)?;
+		if self.extrinsic {
+			message = utils::extrinsic_payload(message);
+		}
 		let sig_data = utils::decode_hex(&self.sig)?;
 		let uri = utils::read_uri(self.uri.as_ref())?;
 		let uri = if uri.starts_with("0x") {
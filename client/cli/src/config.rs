@@ -24,19 +24,21 @@ use crate::{
 	DatabaseParams, ImportParams, KeystoreParams, NetworkParams, NodeKeyParams,
 	OffchainWorkerParams, PruningParams, SharedParams, SubstrateCli,
 };
-use log::warn;
 use names::{Generator, Name};
-use sc_client_api::execution_extensions::ExecutionStrategies;
+use sc_client_api::execution_extensions::{ExecutionStrategies, ExecutionMemoryLimits};
 use sc_service::config::{
-	BasePath, Configuration, DatabaseConfig, ExtTransport, KeystoreConfig, NetworkConfiguration,
-	NodeKeyConfig, OffchainWorkerConfig, PrometheusConfig, PruningMode, Role, RpcMethods,
-	TaskExecutor, TelemetryEndpoints, TransactionPoolOptions, WasmExecutionMethod,
+	BasePath, ColdStorageConfig, Configuration, DatabaseConfig, DiskSpaceConfig, ExtTransport,
+	InformantOutputFormat, KeystoreConfig, NetworkConfiguration, NodeKeyConfig,
+	OffchainWorkerConfig, PrometheusConfig, PruningMode, Role, RpcAuth, RpcMethodPolicy,
+	RpcMethods, RpcRateLimit, RpcRequestLimits, RpcTypeAliases, TaskExecutor, TelemetryEndpoints,
+	TransactionPoolOptions, WasmExecutionMethod, WasmFeatures,
 };
 use sc_service::{ChainSpec, TracingReceiver, KeepBlocks, TransactionStorageMode};
 use sc_telemetry::TelemetryHandle;
-use sc_tracing::logging::LoggerBuilder;
-use std::net::SocketAddr;
+use sc_tracing::logging::{FileLogConfig, LoggerBuilder};
+use std::net::{IpAddr, SocketAddr};
 use std::path::PathBuf;
+use std::time::Duration;
 
 /// The maximum number of characters for a node name.
 pub(crate) const NODE_NAME_MAX_LENGTH: usize = 64;
@@ -44,9 +46,6 @@ pub(crate) const NODE_NAME_MAX_LENGTH: usize = 64;
 /// Default sub directory to store network config.
 pub(crate) const DEFAULT_NETWORK_CONFIG_PATH: &'static str = "network";
 
-/// The recommended open file descriptor limit to be configured for the process.
-const RECOMMENDED_OPEN_FILE_DESCRIPTOR_LIMIT: u64 = 10_000;
-
 /// Default configuration values used by Substrate
 ///
 /// These values will be used by [`CliConfiguration`] to set
@@ -212,6 +211,47 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 			.unwrap_or(TransactionStorageMode::BlockBody))
 	}
 
+	/// Whether the extrinsics should be indexed.
+	///
+	/// By default this is retrieved from `DatabaseParams` if it is available. Otherwise its `false`.
+	fn index_extrinsics(&self) -> Result<bool> {
+		Ok(self.database_params().map(|x| x.index_extrinsics()).unwrap_or(false))
+	}
+
+	/// Whether the block bodies and justifications should be stored compressed.
+	///
+	/// By default this is retrieved from `DatabaseParams` if it is available. Otherwise its `false`.
+	fn compress_blocks(&self) -> Result<bool> {
+		Ok(self.database_params().map(|x| x.compress_blocks()).unwrap_or(false))
+	}
+
+	/// Get the directory the snapshots of the database are written to over RPC.
+	///
+	/// By default this is retrieved from `DatabaseParams` if it is available. Otherwise its `None`.
+	fn database_snapshot_dir(&self) -> Result<Option<PathBuf>> {
+		Ok(self.database_params().and_then(|x| x.snapshot_dir()))
+	}
+
+	/// Get the configuration of the database the ancient blocks are moved to, of the same
+	/// backend as the main database.
+	///
+	/// By default this is retrieved from `DatabaseParams` if it is available. Otherwise its `None`.
+	fn cold_storage(
+		&self,
+		cache_size: usize,
+		database: Database,
+	) -> Result<Option<ColdStorageConfig>> {
+		let (path, hot_blocks) = match self.database_params().and_then(|x| x.cold_storage()) {
+			Some(cold_storage) => cold_storage,
+			None => return Ok(None),
+		};
+		let source = match database {
+			Database::RocksDb => DatabaseConfig::RocksDb { path, cache_size },
+			Database::ParityDb => DatabaseConfig::ParityDb { path, migrate_from: None },
+		};
+		Ok(Some(ColdStorageConfig { source, hot_blocks }))
+	}
+
 	/// Get the database backend variant.
 	///
 	/// By default this is retrieved from `DatabaseParams` if it is available. Otherwise its `None`.
@@ -233,6 +273,11 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 			},
 			Database::ParityDb => DatabaseConfig::ParityDb {
 				path: base_path.join("paritydb"),
+				migrate_from: if self.database_params().map_or(false, |x| x.migrate_rocksdb()) {
+					Some(base_path.join("db"))
+				} else {
+					None
+				},
 			},
 		})
 	}
@@ -246,6 +291,24 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 			.unwrap_or_default())
 	}
 
+	/// Get the size of the trie node cache.
+	///
+	/// By default this is retrieved from `ImportParams` if it is available. Otherwise its `0`.
+	fn trie_cache_size(&self) -> Result<usize> {
+		Ok(self.import_params()
+			.map(|x| x.trie_cache_size())
+			.unwrap_or_default())
+	}
+
+	/// Whether the trie node cache should be warmed up on startup.
+	///
+	/// By default this is retrieved from `ImportParams` if it is available. Otherwise its `false`.
+	fn trie_cache_warm_up(&self) -> Result<bool> {
+		Ok(self.import_params()
+			.map(|x| x.trie_cache_warm_up())
+			.unwrap_or_default())
+	}
+
 	/// Get the state cache child ratio (if any).
 	///
 	/// By default this is `None`.
@@ -273,6 +336,16 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 			.unwrap_or_else(|| Ok(KeepBlocks::All))
 	}
 
+	/// Get the canonicalization delay and the maximum it can be adapted to.
+	///
+	/// By default this is retrieved from `PruningParams` if it is available. Otherwise the delay
+	/// is the default one and isn't adapted.
+	fn canonicalization_delay(&self) -> Result<(Option<u64>, Option<u64>)> {
+		self.pruning_params()
+			.map(|x| x.canonicalization_delay())
+			.unwrap_or(Ok((None, None)))
+	}
+
 	/// Get the chain ID (string).
 	///
 	/// By default this is retrieved from `SharedParams`.
@@ -297,6 +370,16 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 			.unwrap_or_default())
 	}
 
+	/// Get the WebAssembly proposals the runtimes may use.
+	///
+	/// By default this is retrieved from `ImportParams` if it is available. Otherwise no
+	/// proposal is enabled.
+	fn wasm_features(&self) -> Result<WasmFeatures> {
+		Ok(self.import_params()
+			.map(|x| x.wasm_features())
+			.unwrap_or_default())
+	}
+
 	/// Get the path where WASM overrides live.
 	///
 	/// By default this is `None`.
@@ -335,6 +418,13 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		Ok(None)
 	}
 
+	/// Get the file permissions of the RPC IPC socket (`None` if default).
+	///
+	/// By default this is `None`.
+	fn rpc_ipc_permissions(&self) -> Result<Option<u32>> {
+		Ok(None)
+	}
+
 	/// Get the RPC websocket address (`None` if disabled).
 	///
 	/// By default this is `None`.
@@ -342,6 +432,13 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		Ok(None)
 	}
 
+	/// Get the gRPC gateway address (`None` if disabled).
+	///
+	/// By default this is `None`.
+	fn rpc_grpc(&self) -> Result<Option<SocketAddr>> {
+		Ok(None)
+	}
+
 	/// Returns the RPC method set to expose.
 	///
 	/// By default this is `RpcMethods::Auto` (unsafe RPCs are denied iff
@@ -357,6 +454,48 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		Ok(None)
 	}
 
+	/// Get the rate limit of every HTTP & WS RPC client (`None` if unlimited).
+	///
+	/// By default this is `None`.
+	fn rpc_rate_limit(&self) -> Result<Option<RpcRateLimit>> {
+		Ok(None)
+	}
+
+	/// Get the reverse proxies trusted to report the IP of the HTTP & WS RPC clients.
+	///
+	/// By default no proxy is trusted.
+	fn rpc_trusted_proxies(&self) -> Result<Vec<IpAddr>> {
+		Ok(Vec::new())
+	}
+
+	/// Get the limits of the batches and of the calls in flight of every RPC client.
+	///
+	/// By default the requests are unlimited.
+	fn rpc_request_limits(&self) -> Result<RpcRequestLimits> {
+		Ok(Default::default())
+	}
+
+	/// Get the credential required to call the unsafe RPC methods (`None` if not required).
+	///
+	/// By default this is `None`.
+	fn rpc_auth(&self) -> Result<Option<RpcAuth>> {
+		Ok(None)
+	}
+
+	/// Get the access rules of individual RPC methods.
+	///
+	/// By default there are none, the safe or all methods are exposed as per `rpc_methods`.
+	fn rpc_method_policy(&self) -> Result<RpcMethodPolicy> {
+		Ok(Default::default())
+	}
+
+	/// Get the types of the type aliases of the runtime calls.
+	///
+	/// By default these are the types of the Substrate node.
+	fn rpc_type_aliases(&self) -> Result<RpcTypeAliases> {
+		Ok(Default::default())
+	}
+
 	/// Get the RPC cors (`None` if disabled)
 	///
 	/// By default this is `Some(Vec::new())`.
@@ -371,6 +510,21 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		Ok(None)
 	}
 
+	/// Get the address of the health and readiness endpoints (`None` if disabled)
+	///
+	/// By default this is `None`.
+	fn health_endpoint(&self) -> Result<Option<SocketAddr>> {
+		Ok(None)
+	}
+
+	/// Get the configuration of the monitoring of the disk space of the database (`None` if
+	/// disabled)
+	///
+	/// By default this is `None`.
+	fn disk_space(&self) -> Result<Option<DiskSpaceConfig>> {
+		Ok(None)
+	}
+
 	/// Get the telemetry endpoints (if any)
 	///
 	/// By default this is retrieved from the chain spec loaded by `load_spec`.
@@ -388,6 +542,17 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		Ok(None)
 	}
 
+	/// Get the memory limits of the runtime calls.
+	///
+	/// By default this is retrieved from `ImportParams` if it is available. Otherwise the calls
+	/// are not limited.
+	fn execution_memory_limits(&self) -> Result<ExecutionMemoryLimits> {
+		Ok(self
+			.import_params()
+			.map(|x| x.execution_memory_limits())
+			.unwrap_or_default())
+	}
+
 	/// Get the default value for heap pages
 	///
 	/// By default this is `None`.
@@ -458,6 +623,27 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		Ok(Default::default())
 	}
 
+	/// Get the number of runtime instances created as soon as a runtime is loaded
+	///
+	/// By default this is `None`.
+	fn preinstantiated_runtime_instances(&self) -> Result<Option<usize>> {
+		Ok(Default::default())
+	}
+
+	/// Get the number of runtimes kept in the cache
+	///
+	/// By default this is `None`.
+	fn runtime_cache_size(&self) -> Result<Option<usize>> {
+		Ok(Default::default())
+	}
+
+	/// Get the maximum size of the code of the runtimes kept in the cache
+	///
+	/// By default this is `None`.
+	fn runtime_cache_max_code_size(&self) -> Result<Option<usize>> {
+		Ok(Default::default())
+	}
+
 	/// Activate or not the automatic announcing of blocks after import
 	///
 	/// By default this is `false`.
@@ -465,6 +651,36 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		Ok(true)
 	}
 
+	/// Returns `true` if the node should be started without the preflight checks.
+	///
+	/// By default this is `false`.
+	fn skip_preflight_checks(&self) -> Result<bool> {
+		Ok(false)
+	}
+
+	/// Get the NTP server the clock is compared with by the preflight checks, `None` to not
+	/// check the clock.
+	///
+	/// By default this is `None`.
+	fn ntp_server(&self) -> Result<Option<String>> {
+		Ok(None)
+	}
+
+	/// Get how long to wait for the node to shut down once it received a signal to, `None` to
+	/// wait until it is shut down.
+	///
+	/// By default this is `None`.
+	fn shutdown_timeout(&self) -> Result<Option<Duration>> {
+		Ok(None)
+	}
+
+	/// Get the format of the output of the informant.
+	///
+	/// By default this is `InformantOutputFormat::default()`.
+	fn informant_output_format(&self) -> Result<InformantOutputFormat> {
+		Ok(Default::default())
+	}
+
 	/// Create a Configuration object from the current object
 	fn create_configuration<C: SubstrateCli>(
 		&self,
@@ -499,6 +715,7 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 			.import_params()
 			.map(|p| p.unsafe_pruning)
 			.unwrap_or(false);
+		let (canonicalization_delay, max_canonicalization_delay) = self.canonicalization_delay()?;
 
 		Ok(Configuration {
 			impl_name: C::impl_name(),
@@ -519,19 +736,41 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 			database: self.database_config(&config_dir, database_cache_size, database)?,
 			state_cache_size: self.state_cache_size()?,
 			state_cache_child_ratio: self.state_cache_child_ratio()?,
+			trie_cache_size: self.trie_cache_size()?,
+			trie_cache_warm_up: self.trie_cache_warm_up()?,
 			state_pruning: self.state_pruning(unsafe_pruning, &role)?,
 			keep_blocks: self.keep_blocks()?,
 			transaction_storage: self.database_transaction_storage()?,
+			index_extrinsics: self.index_extrinsics()?,
+			database_snapshot_dir: self.database_snapshot_dir()?,
+			compress_blocks: self.compress_blocks()?,
+			cold_storage: self.cold_storage(database_cache_size, database)?,
+			canonicalization_delay,
+			max_canonicalization_delay,
 			wasm_method: self.wasm_method()?,
+			wasm_features: self.wasm_features()?,
 			wasm_runtime_overrides: self.wasm_runtime_overrides(),
 			execution_strategies: self.execution_strategies(is_dev, is_validator)?,
+			execution_memory_limits: self.execution_memory_limits()?,
 			rpc_http: self.rpc_http(DCV::rpc_http_listen_port())?,
 			rpc_ws: self.rpc_ws(DCV::rpc_ws_listen_port())?,
+			rpc_grpc: self.rpc_grpc()?,
 			rpc_ipc: self.rpc_ipc()?,
+			rpc_ipc_permissions: self.rpc_ipc_permissions()?,
 			rpc_methods: self.rpc_methods()?,
 			rpc_ws_max_connections: self.rpc_ws_max_connections()?,
 			rpc_cors: self.rpc_cors(is_dev)?,
+			rpc_rate_limit: self.rpc_rate_limit()?,
+			rpc_trusted_proxies: self.rpc_trusted_proxies()?,
+			rpc_auth: self.rpc_auth()?,
+			rpc_method_policy: self.rpc_method_policy()?,
+			rpc_request_limits: self.rpc_request_limits()?,
+			rpc_interceptors: Default::default(),
+			rpc_aliases: Default::default(),
+			rpc_type_aliases: self.rpc_type_aliases()?,
 			prometheus_config: self.prometheus_config(DCV::prometheus_listen_port())?,
+			health_endpoint: self.health_endpoint()?,
+			disk_space: self.disk_space()?,
 			telemetry_endpoints,
 			telemetry_external_transport: self.telemetry_external_transport()?,
 			default_heap_pages: self.default_heap_pages()?,
@@ -544,10 +783,17 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 			disable_log_reloading: self.is_log_filter_reloading_disabled()?,
 			chain_spec,
 			max_runtime_instances,
+			preinstantiated_runtime_instances: self.preinstantiated_runtime_instances()?
+				.unwrap_or_default(),
+			runtime_cache_size: self.runtime_cache_size()?
+				.unwrap_or(sc_service::config::DEFAULT_MAX_RUNTIMES),
+			runtime_cache_max_code_size: self.runtime_cache_max_code_size()?,
+			host_functions: Default::default(),
 			announce_block: self.announce_block()?,
 			role,
 			base_path: Some(base_path),
-			informant_output_format: Default::default(),
+			informant_output_format: self.informant_output_format()?,
+			inherent_data_providers: Default::default(),
 			telemetry_handle,
 		})
 	}
@@ -572,6 +818,13 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		Ok(self.shared_params().disable_log_color())
 	}
 
+	/// Get the configuration of the file the logs are written to, if any.
+	///
+	/// By default this is retrieved from `SharedParams`.
+	fn log_file(&self) -> Result<Option<FileLogConfig>> {
+		Ok(self.shared_params().log_file())
+	}
+
 	/// Initialize substrate. This must be done only once per process.
 	///
 	/// This method:
@@ -598,17 +851,14 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 			logger.with_colors(false);
 		}
 
+		if let Some(log_file) = self.log_file()? {
+			logger.with_log_file(log_file);
+		}
+
 		let telemetry_worker = logger.init()?;
 
-		if let Some(new_limit) = fdlimit::raise_fd_limit() {
-			if new_limit < RECOMMENDED_OPEN_FILE_DESCRIPTOR_LIMIT {
-				warn!(
-					"Low open file descriptor limit configured for the process. \
-					Current value: {:?}, recommended value: {:?}.",
-					new_limit, RECOMMENDED_OPEN_FILE_DESCRIPTOR_LIMIT,
-				);
-			}
-		}
+		// the limit is checked by the preflight checks, when running a node.
+		fdlimit::raise_fd_limit();
 
 		Ok(telemetry_worker)
 	}
@@ -0,0 +1,367 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Configuration files, where the options of the command line can be specified.
+//!
+//! The files are TOML, or YAML if their extension is `yaml` or `yml`. Their keys are the long
+//! names of the options, e.g. `rpc-port = 9933`, flags are `true` and options given multiple
+//! times are lists. The top-level keys are the options of the node, those of a subcommand are
+//! in the table named after it, e.g. `[purge-chain]`, and the options of the node apply to the
+//! subcommands taking them too. The options given on the command line override those of the
+//! file.
+//!
+//! Clap 2 has no API listing the arguments of an app, so the options are only resolved by
+//! parsing the arguments with the app, and the short names of the options are listed in
+//! `SHORT_NAMES`.
+
+use crate::error::{Error, Result};
+use serde_json::{Map, Value};
+use std::{ffi::OsString, fs, path::Path};
+use structopt::clap::{App, ErrorKind};
+
+/// The option giving the configuration file.
+const CONFIG: &str = "--config";
+
+/// The subcommand exporting the configuration of the node, so it takes the options of the node.
+pub(crate) const EXPORT_CONFIG: &str = "export-config";
+
+/// Returns the given arguments along with the options of the configuration file they give,
+/// if any, which aren't given on the command line.
+pub(crate) fn with_config_file(app: &App, args: Vec<OsString>) -> Result<Vec<OsString>> {
+	let path = match config_path(&args) {
+		Some(path) => path,
+		None => return Ok(args),
+	};
+	let subcommand = app.clone()
+		.get_matches_from_safe(&args)
+		.ok()
+		.and_then(|matches| matches.subcommand_name().map(str::to_owned));
+	let config = read_config_file(Path::new(&path))?;
+	append_options(app, args, &config, subcommand.as_deref())
+}
+
+/// Returns the path of the configuration file the arguments give.
+pub(crate) fn config_path(args: &[OsString]) -> Option<OsString> {
+	let prefix = format!("{}=", CONFIG);
+	args.iter().enumerate().find_map(|(i, arg)| {
+		let arg = arg.to_str()?;
+		if arg == CONFIG {
+			args.get(i + 1).cloned()
+		} else {
+			arg.strip_prefix(prefix.as_str()).map(Into::into)
+		}
+	})
+}
+
+/// Reads the options of the given configuration file.
+pub(crate) fn read_config_file(path: &Path) -> Result<Map<String, Value>> {
+	let content = fs::read_to_string(path)?;
+	let invalid = |e: String| Error::Input(
+		format!("Invalid config file {}: {}", path.display(), e)
+	);
+	let config = match path.extension().and_then(|extension| extension.to_str()) {
+		Some("yaml") | Some("yml") =>
+			serde_yaml::from_str::<Value>(&content).map_err(|e| invalid(e.to_string()))?,
+		_ => toml::from_str::<Value>(&content).map_err(|e| invalid(e.to_string()))?,
+	};
+	match config {
+		Value::Object(config) => Ok(config),
+		Value::Null => Ok(Map::new()),
+		_ => Err(invalid("expected a map of options".into())),
+	}
+}
+
+/// The short names of the options, by long name, as clap 2 has no API listing the arguments of
+/// an app. The options given by their short name on the command line are recognized by them.
+const SHORT_NAMES: &[(char, &str)] = &[('d', "base-path"), ('l', "log"), ('o', "output")];
+
+/// Returns whether the option with the given long name is one of the given arguments, by any of
+/// its names.
+fn given_in(long: &str, args: &[OsString]) -> bool {
+	let short = SHORT_NAMES.iter().find(|(_, name)| *name == long).map(|(short, _)| *short);
+	args.iter().filter_map(|arg| arg.to_str()).any(|arg| {
+		let is_long = arg.strip_prefix("--")
+			.map_or(false, |arg| arg == long || arg.starts_with(&format!("{}=", long)));
+		let is_short = match (short, arg.strip_prefix('-')) {
+			(Some(short), Some(arg)) => !arg.starts_with('-') && arg.starts_with(short),
+			_ => false,
+		};
+		is_long || is_short
+	})
+}
+
+/// Returns the arguments giving the given value of the option with the given long name.
+fn option_args(key: &str, value: &Value) -> Result<Vec<OsString>> {
+	let option = format!("--{}", key.replace('_', "-"));
+	let values = match value {
+		Value::Array(values) => values.iter().collect(),
+		value => vec![value],
+	};
+	let mut args = Vec::new();
+	for value in values {
+		match value {
+			Value::Bool(true) => args.push(option.clone().into()),
+			Value::Bool(false) | Value::Null => {},
+			Value::Number(number) => {
+				args.push(option.clone().into());
+				args.push(number.to_string().into());
+			},
+			Value::String(string) => {
+				args.push(option.clone().into());
+				args.push(string.into());
+			},
+			Value::Array(_) | Value::Object(_) => return Err(Error::Input(
+				format!("Invalid value of `{}` in the config file", key)
+			)),
+		}
+	}
+	Ok(args)
+}
+
+/// Returns the error `app` rejects the given arguments with, once the given option arguments
+/// are appended, if any.
+fn parse_error(app: &App, args: &[OsString], option_args: &[OsString]) -> Option<ErrorKind> {
+	let args = args.iter().chain(option_args).cloned().collect::<Vec<_>>();
+	app.clone().get_matches_from_safe(args).err().map(|e| e.kind)
+}
+
+/// Appends the options of the node, or of the given subcommand, which aren't given yet.
+///
+/// An option given on the command line overrides the option of the configuration file. The
+/// options given by their short name or an alias are recognized as clap rejects them once given
+/// twice, unless they can be given multiple times and aren't one of `SHORT_NAMES`. The options
+/// of the node apply to the subcommands taking them, unless their table sets them.
+pub(crate) fn append_options(
+	app: &App,
+	mut args: Vec<OsString>,
+	config: &Map<String, Value>,
+	subcommand: Option<&str>,
+) -> Result<Vec<OsString>> {
+	let mut options = config.iter()
+		.filter(|(_, value)| !value.is_object())
+		.collect::<Vec<_>>();
+	if let Some(Value::Object(subcommand_options)) = subcommand.and_then(|s| config.get(s)) {
+		options.retain(|(key, _)| !subcommand_options.contains_key(*key));
+		options.extend(subcommand_options);
+	}
+
+	let mut appended = Vec::new();
+	for (key, value) in options {
+		if given_in(&key.replace('_', "-"), &args) {
+			continue;
+		}
+		let option_args = option_args(key, value)?;
+		// the options of the node the subcommand doesn't take are left out, the unknown options
+		// of the node are left for clap to reject.
+		let is_node_option = subcommand.map_or(false, |subcommand| {
+			config.get(subcommand).and_then(|options| options.get(key)).is_none()
+		});
+		match parse_error(app, &args, &option_args) {
+			Some(ErrorKind::UnexpectedMultipleUsage) => continue,
+			Some(ErrorKind::UnknownArgument) if is_node_option => continue,
+			_ => appended.extend(option_args),
+		}
+	}
+
+	args.extend(appended);
+	Ok(args)
+}
+
+/// Returns the options given by the arguments of the given app, as the options of a
+/// configuration file, except for the given ones.
+///
+/// The options given by their short name are recognized if they are one of `SHORT_NAMES`, the
+/// options left to their default value aren't listed.
+pub(crate) fn options_of_args(
+	app: &App,
+	args: &[OsString],
+	except: &[&str],
+) -> Map<String, Value> {
+	let name = args.first().cloned().unwrap_or_default();
+	// the argument following a flag is rejected, unless it is an option.
+	let takes_value = |long: &str, next: &str| !next.starts_with('-') && !matches!(
+		parse_error(app, &[name.clone(), format!("--{}", long).into(), next.into()], &[]),
+		Some(ErrorKind::UnknownArgument) | Some(ErrorKind::UnrecognizedSubcommand),
+	);
+
+	let mut options = Map::new();
+	let mut args = args.iter().skip(1).filter_map(|arg| arg.to_str()).peekable();
+	while let Some(arg) = args.next() {
+		let (long, value) = if let Some(option) = arg.strip_prefix("--") {
+			match option.find('=') {
+				Some(equals) => (&option[..equals], Some(option[equals + 1..].to_owned())),
+				None => (option, None),
+			}
+		} else if let Some(shorts) = arg.strip_prefix('-') {
+			let mut shorts = shorts.chars();
+			let long = shorts.next()
+				.and_then(|short| SHORT_NAMES.iter().find(|(name, _)| *name == short));
+			match long {
+				Some((_, long)) => {
+					let value = shorts.as_str().trim_start_matches('=');
+					(*long, if value.is_empty() { None } else { Some(value.to_owned()) })
+				},
+				None => continue,
+			}
+		} else {
+			continue;
+		};
+		if long.is_empty() || except.contains(&long) {
+			continue;
+		}
+
+		let value = match value {
+			Some(value) => Value::String(value),
+			None => match args.peek() {
+				Some(next) if takes_value(long, next) =>
+					Value::String(args.next().unwrap_or_default().into()),
+				_ => Value::Bool(true),
+			},
+		};
+		match options.get_mut(long) {
+			Some(Value::Array(values)) => values.push(value),
+			Some(previous) => {
+				let previous = previous.take();
+				options.insert(long.into(), Value::Array(vec![previous, value]));
+			},
+			None => {
+				options.insert(long.into(), value);
+			},
+		}
+	}
+	options
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::Write;
+	use structopt::StructOpt;
+
+	#[derive(StructOpt)]
+	#[allow(dead_code)]
+	struct Node {
+		#[structopt(long)]
+		name: Option<String>,
+		#[structopt(long)]
+		validator: bool,
+		#[structopt(long)]
+		rpc_external: bool,
+		#[structopt(long, default_value = "30333")]
+		port: u16,
+		#[structopt(long)]
+		bootnodes: Vec<String>,
+		#[structopt(short = "d", long, alias = "base")]
+		base_path: Option<String>,
+		#[structopt(subcommand)]
+		subcommand: Option<Subcommand>,
+	}
+
+	#[derive(StructOpt)]
+	#[allow(dead_code)]
+	enum Subcommand {
+		PurgeChain {
+			#[structopt(long)]
+			keystore: bool,
+			#[structopt(short = "d", long)]
+			base_path: Option<String>,
+		},
+		Revert {
+			#[structopt(long)]
+			blocks: Option<u32>,
+		},
+	}
+
+	fn args(args: &[&str]) -> Vec<OsString> {
+		args.iter().map(Into::into).collect()
+	}
+
+	#[test]
+	fn appends_the_options_of_the_config_file() {
+		let mut file = tempfile::Builder::new().suffix(".toml").tempfile().unwrap();
+		file.write_all(br#"
+			name = "validator"
+			validator = true
+			rpc-external = false
+			port = 30334
+			bootnodes = ["/ip4/127.0.0.1/tcp/30334", "/ip4/127.0.0.1/tcp/30335"]
+			base-path = "/var/lib/node"
+
+			[purge-chain]
+			keystore = true
+		"#).unwrap();
+		let config = read_config_file(file.path()).unwrap();
+		let app = Node::clap();
+		let append = |given: &[&str], subcommand| {
+			append_options(&app, args(given), &config, subcommand).unwrap()
+		};
+
+		assert_eq!(
+			append(&["node", "--name=alice"], None),
+			args(&[
+				"node", "--name=alice", "--base-path", "/var/lib/node",
+				"--bootnodes", "/ip4/127.0.0.1/tcp/30334",
+				"--bootnodes", "/ip4/127.0.0.1/tcp/30335",
+				"--port", "30334", "--validator",
+			]),
+		);
+		// the options given by their short name or an alias aren't given twice.
+		assert_eq!(
+			append(&["node", "-d", "/tmp/node", "--port", "1"], None),
+			args(&[
+				"node", "-d", "/tmp/node", "--port", "1", "--bootnodes", "/ip4/127.0.0.1/tcp/30334",
+				"--bootnodes", "/ip4/127.0.0.1/tcp/30335", "--name", "validator", "--validator",
+			]),
+		);
+		assert_eq!(
+			append(&["node", "--base", "/tmp/node"], None)[..3],
+			args(&["node", "--base", "/tmp/node"])[..],
+		);
+		assert!(!append(&["node", "--base", "/tmp/node"], None).contains(&"--base-path".into()));
+
+		// the options of the node apply to the subcommands taking them.
+		assert_eq!(
+			append(&["node", "purge-chain"], Some("purge-chain")),
+			args(&["node", "purge-chain", "--base-path", "/var/lib/node", "--keystore"]),
+		);
+		assert_eq!(
+			append(&["node", "purge-chain", "-d", "/tmp/node"], Some("purge-chain")),
+			args(&["node", "purge-chain", "-d", "/tmp/node", "--keystore"]),
+		);
+		assert_eq!(append(&["node", "revert"], Some("revert")), args(&["node", "revert"]));
+	}
+
+	#[test]
+	fn exports_the_effective_options() {
+		let app = Node::clap();
+		let args = args(&[
+			"node", "-d", "/tmp/node", "--validator", "--bootnodes", "a", "--bootnodes=b",
+			"--name", "alice", "--port", "30334",
+		]);
+
+		assert_eq!(
+			Value::Object(options_of_args(&app, &args, &["name"])),
+			serde_json::json!({
+				"base-path": "/tmp/node",
+				"validator": true,
+				"port": "30334",
+				"bootnodes": ["a", "b"],
+			}),
+		);
+	}
+}
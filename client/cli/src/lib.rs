@@ -25,8 +25,10 @@
 pub mod arg_enums;
 mod commands;
 mod config;
+mod config_file;
 mod error;
 mod params;
+mod preflight;
 mod runner;
 
 pub use arg_enums::*;
@@ -34,6 +36,7 @@ pub use commands::*;
 pub use config::*;
 pub use error::*;
 pub use params::*;
+pub use preflight::run_preflight_checks;
 pub use runner::*;
 pub use sc_service::{ChainSpec, Role};
 use sc_service::{Configuration, TaskExecutor};
@@ -115,6 +118,9 @@ pub trait SubstrateCli: Sized {
 	/// To allow running the node without subcommand, it also sets a few more settings:
 	/// `AppSettings::ArgsNegateSubcommands` and `AppSettings::SubcommandsNegateReqs`.
 	///
+	/// The options of the configuration file given by `--config` are added to those of the
+	/// command line, which override them.
+	///
 	/// Gets the struct from any iterator such as a `Vec` of your making.
 	/// Print the error message and quit the program in case of failure.
 	fn from_iter<I>(iter: I) -> Self
@@ -142,7 +148,16 @@ pub trait SubstrateCli: Sized {
 				AppSettings::SubcommandsNegateReqs,
 			]);
 
-		let matches = match app.get_matches_from_safe(iter) {
+		let args = iter.into_iter().map(Into::into).collect();
+		let args = match config_file::with_config_file(&app, args) {
+			Ok(args) => args,
+			Err(e) => {
+				let _ = writeln!(std::io::stderr(), "{}", e);
+				std::process::exit(1);
+			},
+		};
+
+		let matches = match app.get_matches_from_safe(args) {
 			Ok(matches) => matches,
 			Err(mut e) => {
 				// To support pipes, we can not use `writeln!` as any error
@@ -199,7 +214,11 @@ pub trait SubstrateCli: Sized {
 			.about(about.as_str())
 			.version(full_version.as_str());
 
-		let matches = app.get_matches_from_safe(iter)?;
+		let args = iter.into_iter().map(Into::into).collect();
+		let args = config_file::with_config_file(&app, args).map_err(|e| {
+			clap::Error::with_description(&e.to_string(), clap::ErrorKind::InvalidValue)
+		})?;
+		let matches = app.get_matches_from_safe(args)?;
 
 		Ok(<Self as StructOpt>::from_clap(&matches))
 	}
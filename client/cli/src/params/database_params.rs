@@ -17,6 +17,7 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::arg_enums::Database;
+use std::path::PathBuf;
 use structopt::StructOpt;
 use sc_service::TransactionStorageMode;
 
@@ -45,6 +46,45 @@ pub struct DatabaseParams {
 	/// in the block body column.
 	#[structopt(long)]
 	pub storage_chain: bool,
+
+	/// Migrate the RocksDB database of the node when opening ParityDB for the first time.
+	///
+	/// Only archive databases can be migrated. The RocksDB database is left in place and
+	/// can be removed once the node runs on ParityDB.
+	#[structopt(long)]
+	pub migrate_rocksdb: bool,
+
+	/// Maintain an index of the blocks the extrinsics are included in.
+	///
+	/// The index serves `chain_getTransaction`. Only the extrinsics of the blocks imported
+	/// while it is enabled are indexed.
+	#[structopt(long)]
+	pub index_extrinsics: bool,
+
+	/// Store the block bodies and justifications compressed.
+	///
+	/// The blocks stored uncompressed are compressed in the background.
+	#[structopt(long)]
+	pub compress_blocks: bool,
+
+	/// Move the bodies and justifications of the ancient blocks to a database at the given path,
+	/// e.g. on a slower and cheaper disk.
+	///
+	/// The blocks are moved in the background once finalized for `--hot-blocks` blocks, and are
+	/// read from either database. The state stays in the main database.
+	#[structopt(long, value_name = "PATH", parse(from_os_str))]
+	pub cold_database_path: Option<PathBuf>,
+
+	/// Number of finalized blocks whose bodies and justifications stay in the main database
+	/// when `--cold-database-path` is set.
+	#[structopt(long, value_name = "COUNT", default_value = "100000")]
+	pub hot_blocks: u32,
+
+	/// Directory the unsafe `system_snapshotDatabase` RPC writes the snapshots of the database to.
+	///
+	/// The RPC is disabled unless the directory is set.
+	#[structopt(long, value_name = "PATH", parse(from_os_str))]
+	pub db_snapshot_dir: Option<PathBuf>,
 }
 
 impl DatabaseParams {
@@ -58,6 +98,32 @@ impl DatabaseParams {
 		self.database_cache_size
 	}
 
+	/// Whether the RocksDB database should be migrated to ParityDB.
+	pub fn migrate_rocksdb(&self) -> bool {
+		self.migrate_rocksdb
+	}
+
+	/// Whether the extrinsics should be indexed.
+	pub fn index_extrinsics(&self) -> bool {
+		self.index_extrinsics
+	}
+
+	/// Whether the block bodies and justifications should be stored compressed.
+	pub fn compress_blocks(&self) -> bool {
+		self.compress_blocks
+	}
+
+	/// The path of the database of the ancient blocks and the number of finalized blocks kept
+	/// in the main database, if the ancient blocks should be moved.
+	pub fn cold_storage(&self) -> Option<(PathBuf, u32)> {
+		self.cold_database_path.clone().map(|path| (path, self.hot_blocks))
+	}
+
+	/// The directory the snapshots of the database are written to over RPC.
+	pub fn snapshot_dir(&self) -> Option<PathBuf> {
+		self.db_snapshot_dir.clone()
+	}
+
 	/// Transaction storage scheme.
 	pub fn transaction_storage(&self) -> TransactionStorageMode {
 		if self.storage_chain {
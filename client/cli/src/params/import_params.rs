@@ -17,13 +17,15 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::arg_enums::{
-	ExecutionStrategy, WasmExecutionMethod, DEFAULT_EXECUTION_BLOCK_CONSTRUCTION,
+	ExecutionStrategy, WasmExecutionMethod, WasmtimeMemoryStrategy, WasmtimeOptLevel,
+	DEFAULT_EXECUTION_BLOCK_CONSTRUCTION,
 	DEFAULT_EXECUTION_IMPORT_BLOCK, DEFAULT_EXECUTION_IMPORT_BLOCK_VALIDATOR,
 	DEFAULT_EXECUTION_OFFCHAIN_WORKER, DEFAULT_EXECUTION_OTHER, DEFAULT_EXECUTION_SYNCING,
 };
 use crate::params::DatabaseParams;
 use crate::params::PruningParams;
-use sc_client_api::execution_extensions::ExecutionStrategies;
+use sc_client_api::execution_extensions::{ExecutionStrategies, ExecutionMemoryLimits};
+use sp_core::traits::MemoryLimits;
 use structopt::StructOpt;
 use std::path::PathBuf;
 
@@ -56,6 +58,47 @@ pub struct ImportParams {
 	)]
 	pub wasm_method: WasmExecutionMethod,
 
+	/// The optimization level of the compiled Wasm runtime code.
+	///
+	/// Lower levels compile the runtimes faster, but execute them slower.
+	#[structopt(
+		long = "wasmtime-opt-level",
+		value_name = "LEVEL",
+		possible_values = &WasmtimeOptLevel::variants(),
+		case_insensitive = true,
+		default_value = "SpeedAndSize"
+	)]
+	pub wasmtime_opt_level: WasmtimeOptLevel,
+
+	/// The number of threads compiling a Wasm runtime, all the cores by default.
+	#[structopt(long = "wasmtime-compilation-threads", value_name = "COUNT")]
+	pub wasmtime_compilation_threads: Option<usize>,
+
+	/// How the memories of the compiled Wasm runtime are allocated.
+	///
+	/// `Static` reserves 4GiB of virtual memory for every instance, so the compiled code
+	/// doesn't have to check the bounds of the memory accesses.
+	#[structopt(
+		long = "wasmtime-memory-strategy",
+		value_name = "STRATEGY",
+		possible_values = &WasmtimeMemoryStrategy::variants(),
+		case_insensitive = true,
+		default_value = "Static"
+	)]
+	pub wasmtime_memory_strategy: WasmtimeMemoryStrategy,
+
+	/// Let the runtimes use the bulk memory operations of WebAssembly.
+	///
+	/// Requires the compiled Wasm execution. All the nodes of the network must agree on it.
+	#[structopt(long = "wasm-bulk-memory")]
+	pub wasm_bulk_memory: bool,
+
+	/// Let the runtimes use the SIMD instructions of WebAssembly.
+	///
+	/// Requires the compiled Wasm execution. All the nodes of the network must agree on it.
+	#[structopt(long = "wasm-simd")]
+	pub wasm_simd: bool,
+
 	/// Specify the path where local WASM runtimes are stored.
 	///
 	/// These runtimes will override on-chain runtimes when the version matches.
@@ -73,6 +116,40 @@ pub struct ImportParams {
 		default_value = "67108864"
 	)]
 	pub state_cache_size: usize,
+
+	/// Specify the size of the trie node and value cache shared by all the blocks.
+	///
+	/// The trie nodes and the values read by the block import, the RPC and the block authoring
+	/// are kept in this cache, so hot storage keys don't hit the database at every block. A
+	/// quarter of the size is dedicated to the values. `0` disables it.
+	#[structopt(
+		long = "trie-cache-size",
+		value_name = "Bytes",
+		default_value = "67108864"
+	)]
+	pub trie_cache_size: usize,
+
+	/// Read the trie nodes cached when the node was stopped into the cache on startup.
+	///
+	/// Saves a restarted node from reading the trie nodes of the hot storage keys from the
+	/// database while it imports or authors its first blocks.
+	#[structopt(long = "trie-cache-warm-up")]
+	pub trie_cache_warm_up: bool,
+
+	/// The maximum number of heap pages of the runtime calls syncing, importing or
+	/// constructing blocks.
+	///
+	/// The node fails to import the blocks of a runtime requiring more heap pages.
+	#[structopt(long = "max-import-heap-pages", value_name = "COUNT")]
+	pub max_import_heap_pages: Option<u64>,
+
+	/// The maximum number of heap pages of the other runtime calls, e.g. the `state_call` of
+	/// the RPC clients and the offchain workers.
+	///
+	/// The calls run with at most this many heap pages, so the calls allocating more fail and
+	/// the RPC clients can't exhaust the memory of the node.
+	#[structopt(long = "max-call-heap-pages", value_name = "COUNT")]
+	pub max_call_heap_pages: Option<u64>,
 }
 
 impl ImportParams {
@@ -82,9 +159,42 @@ impl ImportParams {
 		self.state_cache_size
 	}
 
+	/// Specify the size of the trie node cache.
+	pub fn trie_cache_size(&self) -> usize {
+		self.trie_cache_size
+	}
+
+	/// Whether the trie node cache should be warmed up on startup.
+	pub fn trie_cache_warm_up(&self) -> bool {
+		self.trie_cache_warm_up
+	}
+
 	/// Get the WASM execution method from the parameters
 	pub fn wasm_method(&self) -> sc_service::config::WasmExecutionMethod {
-		self.wasm_method.into()
+		match self.wasm_method {
+			#[cfg(feature = "wasmtime")]
+			WasmExecutionMethod::Compiled =>
+				sc_service::config::WasmExecutionMethod::Compiled(self.wasmtime_config()),
+			method => method.into(),
+		}
+	}
+
+	/// Get the WebAssembly proposals the runtimes may use from the parameters
+	pub fn wasm_features(&self) -> sc_service::config::WasmFeatures {
+		sc_service::config::WasmFeatures {
+			bulk_memory: self.wasm_bulk_memory,
+			simd: self.wasm_simd,
+		}
+	}
+
+	/// Get the configuration of the compiled WASM execution from the parameters
+	#[cfg(feature = "wasmtime")]
+	pub fn wasmtime_config(&self) -> sc_service::config::WasmtimeConfig {
+		sc_service::config::WasmtimeConfig {
+			opt_level: self.wasmtime_opt_level.into(),
+			compilation_threads: self.wasmtime_compilation_threads,
+			memory_strategy: self.wasmtime_memory_strategy.into(),
+		}
 	}
 
 	/// Enable overriding on-chain WASM with locally-stored WASM
@@ -93,6 +203,17 @@ impl ImportParams {
 		self.wasm_runtime_overrides.clone()
 	}
 
+	/// Get the memory limits of the runtime calls.
+	pub fn execution_memory_limits(&self) -> ExecutionMemoryLimits {
+		ExecutionMemoryLimits {
+			importing: MemoryLimits {
+				max_heap_pages: self.max_import_heap_pages,
+				cap_heap_pages: false,
+			},
+			other: MemoryLimits { max_heap_pages: self.max_call_heap_pages, cap_heap_pages: true },
+		}
+	}
+
 	/// Get execution strategies for the parameters
 	pub fn execution_strategies(&self, is_dev: bool, is_validator: bool) -> ExecutionStrategies {
 		let exec = &self.execution_strategies;
@@ -120,6 +241,9 @@ impl ImportParams {
 			offchain_worker:
 				exec_all_or(exec.execution_offchain_worker, DEFAULT_EXECUTION_OFFCHAIN_WORKER),
 			other: exec_all_or(exec.execution_other, DEFAULT_EXECUTION_OTHER),
+			methods: exec.execution_override.iter()
+				.map(|(method, strategy)| (method.clone(), (*strategy).into()))
+				.collect(),
 		}
 	}
 }
@@ -189,4 +313,61 @@ pub struct ExecutionStrategiesParams {
 		]
 	)]
 	pub execution: Option<ExecutionStrategy>,
+
+	/// The means of execution used when calling the given runtime API method, e.g.
+	/// `Core_execute_block=Wasm`, whatever the execution context.
+	///
+	/// Takes precedence over the other execution parameters and can be passed multiple times.
+	#[structopt(
+		long = "execution-override",
+		value_name = "METHOD=STRATEGY",
+		parse(try_from_str = parse_execution_override),
+	)]
+	pub execution_override: Vec<(String, ExecutionStrategy)>,
+}
+
+fn parse_execution_override(s: &str) -> Result<(String, ExecutionStrategy), String> {
+	let (method, strategy) = match s.find('=') {
+		Some(pos) if pos > 0 => (&s[..pos], &s[pos + 1..]),
+		_ => return Err(format!("Expected `METHOD=STRATEGY`, got `{}`", s)),
+	};
+	let strategy = strategy.parse::<ExecutionStrategy>().map_err(|_| format!(
+		"Unknown execution strategy `{}`, expected one of {:?}",
+		strategy,
+		ExecutionStrategy::variants(),
+	))?;
+	Ok((method.into(), strategy))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn execution_overrides_take_precedence() {
+		let params = ImportParams::from_iter(&[
+			"test",
+			"--execution-other", "Native",
+			"--execution-override", "Core_version=wasm",
+			"--execution-override", "Metadata_metadata=NativeElseWasm",
+		]);
+		let strategies = params.execution_strategies(false, false);
+		let other = strategies.other;
+		assert_eq!(
+			strategies.for_method("Core_version", other),
+			sc_client_api::ExecutionStrategy::AlwaysWasm,
+		);
+		assert_eq!(
+			strategies.for_method("Metadata_metadata", other),
+			sc_client_api::ExecutionStrategy::NativeElseWasm,
+		);
+		assert_eq!(
+			strategies.for_method("Core_execute_block", other),
+			sc_client_api::ExecutionStrategy::NativeWhenPossible,
+		);
+
+		assert!(parse_execution_override("Core_version").is_err());
+		assert!(parse_execution_override("=Wasm").is_err());
+		assert!(parse_execution_override("Core_version=Fast").is_err());
+	}
 }
@@ -149,18 +149,34 @@ pub struct OutputTypeFlag {
 /// Optional flag for specifying network scheme
 #[derive(Debug, StructOpt)]
 pub struct NetworkSchemeFlag {
-	/// network address format
+	/// network address format, the name of a network or a custom SS58 prefix.
+	///
+	/// The prefixes range from 0 to 16383.
 	#[structopt(
 		long,
-		value_name = "NETWORK",
+		value_name = "NETWORK or PREFIX",
 		short = "n",
-		possible_values = &Ss58AddressFormat::all_names()[..],
-		parse(try_from_str = Ss58AddressFormat::try_from),
-		case_insensitive = true,
+		parse(try_from_str = parse_ss58_address_format),
 	)]
 	pub network: Option<Ss58AddressFormat>,
 }
 
+/// The largest SS58 prefix, the prefixes are encoded on 14 bits.
+const MAX_SS58_PREFIX: u16 = 16_383;
+
+fn parse_ss58_address_format(format: &str) -> Result<Ss58AddressFormat, String> {
+	let parsed = Ss58AddressFormat::try_from(format.to_lowercase().as_str())
+		.map_err(|_| format!(
+			"Expected one of {} or a prefix, got {}",
+			Ss58AddressFormat::all_names().join(", "),
+			format,
+		))?;
+	if u16::from(parsed) > MAX_SS58_PREFIX {
+		return Err(format!("The SS58 prefixes range from 0 to {}", MAX_SS58_PREFIX));
+	}
+	Ok(parsed)
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -192,6 +208,17 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn parse_network_names_and_custom_prefixes() {
+		assert_eq!(
+			parse_ss58_address_format("Polkadot"),
+			Ok(Ss58AddressFormat::PolkadotAccount),
+		);
+		assert_eq!(parse_ss58_address_format("1234"), Ok(Ss58AddressFormat::Custom(1234)));
+		assert!(parse_ss58_address_format("16384").is_err());
+		assert!(parse_ss58_address_format("unknown").is_err());
+	}
+
 	#[test]
 	fn parse_block_number_fails() {
 		assert_eq!(
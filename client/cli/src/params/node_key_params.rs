@@ -18,7 +18,7 @@
 
 use sc_network::{config::identity::ed25519, config::NodeKeyConfig};
 use sp_core::H256;
-use std::{path::PathBuf, str::FromStr};
+use std::{path::{Path, PathBuf}, str::FromStr};
 use structopt::StructOpt;
 
 use crate::arg_enums::NodeKeyType;
@@ -86,9 +86,14 @@ pub struct NodeKeyParams {
 	///   The file must contain an unencoded 32 byte or hex encoded Ed25519 secret key.
 	///
 	/// If the file does not exist, it is created with a newly generated secret key of
-	/// the chosen type.
+	/// the chosen type, readable only by its owner. An existing file must not be accessible
+	/// by other users, see `--unsafe-node-key-file-permissions`.
 	#[structopt(long = "node-key-file", value_name = "FILE")]
 	pub node_key_file: Option<PathBuf>,
+
+	/// Use the node key file even if other users can access it.
+	#[structopt(long = "unsafe-node-key-file-permissions")]
+	pub unsafe_node_key_file_permissions: bool,
 }
 
 impl NodeKeyParams {
@@ -100,11 +105,13 @@ impl NodeKeyParams {
 				let secret = if let Some(node_key) = self.node_key.as_ref() {
 					parse_ed25519_secret(node_key)?
 				} else {
-					sc_network::config::Secret::File(
-						self.node_key_file
-							.clone()
-							.unwrap_or_else(|| net_config_dir.join(NODE_KEY_ED25519_FILE))
-					)
+					let file = self.node_key_file
+						.clone()
+						.unwrap_or_else(|| net_config_dir.join(NODE_KEY_ED25519_FILE));
+					if !self.unsafe_node_key_file_permissions {
+						check_node_key_file_permissions(&file)?;
+					}
+					sc_network::config::Secret::File(file)
 				};
 
 				NodeKeyConfig::Ed25519(secret)
@@ -118,6 +125,33 @@ fn invalid_node_key(e: impl std::fmt::Display) -> error::Error {
 	error::Error::Input(format!("Invalid node key: {}", e))
 }
 
+/// Check that other users can't access the given node key file, if it exists.
+#[cfg(unix)]
+fn check_node_key_file_permissions(file: &Path) -> error::Result<()> {
+	use std::os::unix::fs::PermissionsExt;
+
+	let mode = match std::fs::metadata(file) {
+		Ok(metadata) => metadata.permissions().mode(),
+		Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+		Err(e) => return Err(e.into()),
+	};
+	if mode & 0o077 != 0 {
+		return Err(error::Error::Input(format!(
+			"The node key file {} can be accessed by other users (mode {:o}), restrict its \
+			permissions with `chmod 600` or pass `--unsafe-node-key-file-permissions`",
+			file.display(),
+			mode & 0o777,
+		)));
+	}
+	Ok(())
+}
+
+/// Check that other users can't access the given node key file, if it exists.
+#[cfg(not(unix))]
+fn check_node_key_file_permissions(_file: &Path) -> error::Result<()> {
+	Ok(())
+}
+
 /// Parse a Ed25519 secret key from a hex string into a `sc_network::Secret`.
 fn parse_ed25519_secret(hex: &str) -> error::Result<sc_network::config::Ed25519Secret> {
 	H256::from_str(&hex)
@@ -147,6 +181,7 @@ mod tests {
 					node_key_type,
 					node_key: Some(format!("{:x}", H256::from_slice(sk.as_ref()))),
 					node_key_file: None,
+					unsafe_node_key_file_permissions: false,
 				};
 				params.node_key(net_config_dir).and_then(|c| match c {
 					NodeKeyConfig::Ed25519(sc_network::config::Secret::Input(ref ski))
@@ -169,6 +204,7 @@ mod tests {
 				node_key_type: NodeKeyType::Ed25519,
 				node_key: None,
 				node_key_file: Some(file),
+				unsafe_node_key_file_permissions: false,
 			};
 
 			let node_key = params.node_key(&PathBuf::from("not-used"))
@@ -188,12 +224,48 @@ mod tests {
 		let key = ed25519::SecretKey::generate();
 
 		fs::write(&file, hex::encode(key.as_ref())).expect("Writes secret key");
+		restrict_permissions(&file);
 		check_key(file.clone(), &key);
 
 		fs::write(&file, &key).expect("Writes secret key");
 		check_key(file.clone(), &key);
 	}
 
+	#[cfg(unix)]
+	fn restrict_permissions(file: &Path) {
+		use std::os::unix::fs::PermissionsExt;
+		fs::set_permissions(file, fs::Permissions::from_mode(0o600)).unwrap();
+	}
+
+	#[cfg(not(unix))]
+	fn restrict_permissions(_file: &Path) {}
+
+	#[cfg(unix)]
+	#[test]
+	fn test_node_key_file_permissions() {
+		use std::os::unix::fs::PermissionsExt;
+
+		let tmp = tempfile::Builder::new().prefix("alice").tempdir().expect("Creates tempfile");
+		let file = tmp.path().join("mysecret");
+		let params = |unsafe_node_key_file_permissions| NodeKeyParams {
+			node_key_type: NodeKeyType::Ed25519,
+			node_key: None,
+			node_key_file: Some(file.clone()),
+			unsafe_node_key_file_permissions,
+		};
+
+		// a missing file is generated by the node, readable only by its owner.
+		assert!(params(false).node_key(&PathBuf::from("not-used")).is_ok());
+
+		fs::write(&file, hex::encode(ed25519::SecretKey::generate())).unwrap();
+		fs::set_permissions(&file, fs::Permissions::from_mode(0o644)).unwrap();
+		assert!(params(false).node_key(&PathBuf::from("not-used")).is_err());
+		assert!(params(true).node_key(&PathBuf::from("not-used")).is_ok());
+
+		restrict_permissions(&file);
+		assert!(params(false).node_key(&PathBuf::from("not-used")).is_ok());
+	}
+
 	#[test]
 	fn test_node_key_config_default() {
 		fn with_def_params<F>(f: F) -> error::Result<()>
@@ -206,6 +278,7 @@ mod tests {
 					node_key_type,
 					node_key: None,
 					node_key_file: None,
+					unsafe_node_key_file_permissions: false,
 				})
 			})
 		}
@@ -23,11 +23,16 @@ use structopt::StructOpt;
 /// Parameters to define the pruning mode
 #[derive(Debug, StructOpt)]
 pub struct PruningParams {
-	/// Specify the state pruning mode, a number of blocks to keep or 'archive'.
+	/// Specify the state pruning mode, a number of blocks to keep, 'archive' or
+	/// 'archive-canonical'.
+	///
+	/// 'archive' keeps the state of all the blocks, 'archive-canonical' keeps the state
+	/// of the finalized blocks and prunes the state of the discarded forks.
 	///
 	/// Default is to keep all block states if the node is running as a
 	/// validator (i.e. 'archive'), otherwise state is only kept for the last
-	/// 256 blocks.
+	/// 256 blocks. The mode is recorded in the database, it can't be changed
+	/// once the database is created.
 	#[structopt(long = "pruning", value_name = "PRUNING_MODE")]
 	pub pruning: Option<String>,
 	/// Specify the number of finalized blocks to keep in the database.
@@ -35,6 +40,18 @@ pub struct PruningParams {
 	/// Default is to keep all blocks.
 	#[structopt(long, value_name = "COUNT")]
 	pub keep_blocks: Option<u32>,
+	/// Specify the number of blocks behind the best block the blocks not finalized yet are
+	/// canonicalized at, discarding the state of the forks they aren't part of.
+	///
+	/// Default is 4096 blocks.
+	#[structopt(long, value_name = "COUNT")]
+	pub canonicalization_delay: Option<u64>,
+	/// Adapt the canonicalization delay to the number of blocks not finalized yet, up to the
+	/// given number of blocks, so the state of the forks is kept while the finality lags.
+	///
+	/// The state of the blocks not canonicalized yet is kept in memory.
+	#[structopt(long, value_name = "COUNT")]
+	pub max_canonicalization_delay: Option<u64>,
 }
 
 impl PruningParams {
@@ -46,6 +63,7 @@ impl PruningParams {
 		// unless `unsafe_pruning` is set.
 		Ok(match &self.pruning {
 			Some(ref s) if s == "archive" => PruningMode::ArchiveAll,
+			Some(ref s) if s == "archive-canonical" => PruningMode::ArchiveCanonical,
 			None if role.is_authority() => PruningMode::ArchiveAll,
 			None => PruningMode::default(),
 			Some(s) => {
@@ -64,6 +82,18 @@ impl PruningParams {
 		})
 	}
 
+	/// Get the canonicalization delay and its maximum from the parameters
+	pub fn canonicalization_delay(&self) -> error::Result<(Option<u64>, Option<u64>)> {
+		let delay = self.canonicalization_delay.unwrap_or(sc_service::CANONICALIZATION_DELAY);
+		match self.max_canonicalization_delay {
+			Some(max) if max < delay => Err(error::Error::Input(format!(
+				"The maximum canonicalization delay can't be less than the delay of {} blocks",
+				delay,
+			))),
+			max => Ok((self.canonicalization_delay, max)),
+		}
+	}
+
 	/// Get the block pruning value from the parameters
 	pub fn keep_blocks(&self) -> error::Result<KeepBlocks> {
 		Ok(match self.keep_blocks {
@@ -72,3 +102,32 @@ impl PruningParams {
 		})
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_the_state_pruning_modes() {
+		let state_pruning = |pruning: Option<&str>, role| PruningParams {
+			pruning: pruning.map(Into::into),
+			keep_blocks: None,
+			canonicalization_delay: None,
+			max_canonicalization_delay: None,
+		}.state_pruning(false, &role);
+
+		assert_eq!(state_pruning(Some("archive"), Role::Full).unwrap(), PruningMode::ArchiveAll);
+		assert_eq!(
+			state_pruning(Some("archive-canonical"), Role::Authority).unwrap(),
+			PruningMode::ArchiveCanonical,
+		);
+		assert_eq!(
+			state_pruning(Some("1000"), Role::Full).unwrap(),
+			PruningMode::keep_blocks(1000),
+		);
+		assert_eq!(state_pruning(None, Role::Full).unwrap(), PruningMode::default());
+		assert_eq!(state_pruning(None, Role::Authority).unwrap(), PruningMode::ArchiveAll);
+		assert!(state_pruning(Some("1000"), Role::Authority).is_err());
+		assert!(state_pruning(Some("canonical"), Role::Full).is_err());
+	}
+}
@@ -17,13 +17,22 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use sc_service::config::BasePath;
+use sc_tracing::logging::FileLogConfig;
 use std::path::PathBuf;
 use structopt::StructOpt;
-use crate::arg_enums::TracingReceiver;
+use crate::arg_enums::{LogRotation, TracingReceiver};
 
 /// Shared parameters used by all `CoreParams`.
 #[derive(Debug, StructOpt)]
 pub struct SharedParams {
+	/// Read the options from the given configuration file.
+	///
+	/// The file is TOML, or YAML if its extension is `yaml` or `yml`. Its keys are the long names
+	/// of the options, its tables the options of the subcommands named after them. The options
+	/// given on the command line override those of the file.
+	#[structopt(long, value_name = "FILE", parse(from_os_str))]
+	pub config: Option<PathBuf>,
+
 	/// Specify the chain specification.
 	///
 	/// It can be one of the predefined ones (dev, local, or staging) or it can be a path to a file with
@@ -58,6 +67,43 @@ pub struct SharedParams {
 	#[structopt(long = "disable-log-reloading")]
 	pub disable_log_reloading: bool,
 
+	/// Also write the logs to the given file.
+	///
+	/// The logs in the file are always detailed and without colors.
+	#[structopt(long, value_name = "PATH", parse(from_os_str))]
+	pub log_file: Option<PathBuf>,
+
+	/// Sets a custom logging filter of the log file. Syntax is the same as for logging.
+	///
+	/// The file has the logging filter if none is set, this filter replaces it for the file,
+	/// e.g. -lwarn --log-file-filter sync=debug only prints the warnings and errors but keeps
+	/// the debug logs of the sync in the file. Like the logging filter, it is applied after
+	/// `RUST_LOG`, but it isn't changed by the RPCs adding or resetting log filters.
+	#[structopt(long, value_name = "LOG_PATTERN", requires = "log-file")]
+	pub log_file_filter: Vec<String>,
+
+	/// Rotate the log file once it's larger than this, in MiB.
+	#[structopt(long, value_name = "MiB", requires = "log-file")]
+	pub log_file_max_size: Option<u64>,
+
+	/// Rotate the log file every hour or every day, counted since it was opened.
+	///
+	/// By default the log file is never rotated.
+	#[structopt(
+		long,
+		value_name = "ROTATION",
+		possible_values = &LogRotation::variants(),
+		case_insensitive = true,
+		requires = "log-file"
+	)]
+	pub log_file_rotation: Option<LogRotation>,
+
+	/// The number of rotated log files to keep, `<PATH>.1` being the latest.
+	///
+	/// By default 5 rotated log files are kept.
+	#[structopt(long, value_name = "COUNT", requires = "log-file")]
+	pub log_file_keep: Option<usize>,
+
 	/// Sets a custom profiling filter. Syntax is the same as for logging: <target>=<level>
 	#[structopt(long = "tracing-targets", value_name = "TARGETS")]
 	pub tracing_targets: Option<String>,
@@ -108,6 +154,23 @@ impl SharedParams {
 		self.disable_log_color
 	}
 
+	/// Get the configuration of the log file, if the logs should be written to one.
+	pub fn log_file(&self) -> Option<FileLogConfig> {
+		let path = self.log_file.clone()?;
+		let config = FileLogConfig::new(path);
+		Some(FileLogConfig {
+			directives: if self.log_file_filter.is_empty() {
+				None
+			} else {
+				Some(self.log_file_filter.join(","))
+			},
+			max_size: self.log_file_max_size.map(|size| size * 1024 * 1024),
+			max_age: self.log_file_rotation.and_then(LogRotation::max_age),
+			keep: self.log_file_keep.unwrap_or(config.keep),
+			..config
+		})
+	}
+
 	/// Is log reloading disabled
 	pub fn is_log_filter_reloading_disabled(&self) -> bool {
 		self.disable_log_reloading
@@ -16,7 +16,8 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use sc_service::config::TransactionPoolOptions;
+use sc_service::config::{TransactionPoolLane, TransactionPoolLimit, TransactionPoolOptions};
+use sp_runtime::Percent;
 use structopt::StructOpt;
 
 /// Parameters used to create the pool configuration.
@@ -29,6 +30,121 @@ pub struct TransactionPoolParams {
 	/// Maximum number of kilobytes of all transactions stored in the pool.
 	#[structopt(long = "pool-kbytes", value_name = "COUNT", default_value = "20480")]
 	pub pool_kbytes: usize,
+
+	/// Maximum number of future transactions in the transaction pool.
+	///
+	/// Defaults to a tenth of `--pool-limit`.
+	#[structopt(long = "pool-future-limit", value_name = "COUNT")]
+	pub pool_future_limit: Option<usize>,
+
+	/// Maximum number of kilobytes of all future transactions stored in the pool.
+	///
+	/// Defaults to a tenth of `--pool-kbytes`.
+	#[structopt(long = "pool-future-kbytes", value_name = "COUNT")]
+	pub pool_future_kbytes: Option<usize>,
+
+	/// Number of seconds after which transactions still waiting in the future queue for
+	/// their requirements are dropped from the pool.
+	#[structopt(long = "pool-future-max-age", value_name = "SECONDS")]
+	pub pool_future_max_age: Option<u64>,
+
+	/// Minimum priority increase, in percent, over the priority of a ready transaction
+	/// that a transaction with the same sender and nonce needs in order to replace it.
+	#[structopt(
+		long = "pool-replacement-bump",
+		value_name = "PERCENT",
+		default_value = "0",
+		parse(try_from_str = parse_percent),
+	)]
+	pub pool_replacement_bump: u8,
+
+	/// Maximum number of ready and future transactions of a single sender in the pool.
+	///
+	/// The senders are only known if the node extracts them from the transactions, the
+	/// transactions without a known sender aren't limited.
+	#[structopt(long = "pool-sender-limit", value_name = "COUNT")]
+	pub pool_sender_limit: Option<usize>,
+
+	/// Maximum number of kilobytes of the ready and future transactions of a single sender
+	/// stored in the pool.
+	#[structopt(long = "pool-sender-kbytes", value_name = "COUNT")]
+	pub pool_sender_kbytes: Option<usize>,
+
+	/// Persist the transactions of the pool on shutdown and resubmit them on startup.
+	#[structopt(long = "pool-persist")]
+	pub pool_persist: bool,
+
+	/// Number of seconds invalid or manually banned transactions are prevented from
+	/// being reimported into the pool.
+	#[structopt(long = "pool-ban-seconds", value_name = "SECONDS", default_value = "1800")]
+	pub pool_ban_seconds: u64,
+
+	/// Interval in milliseconds between two batches of background revalidation.
+	#[structopt(
+		long = "pool-revalidation-interval",
+		value_name = "MILLISECONDS",
+		default_value = "200",
+		parse(try_from_str = parse_non_zero),
+	)]
+	pub pool_revalidation_interval: u64,
+
+	/// Maximum number of transactions revalidated in a single batch.
+	#[structopt(
+		long = "pool-revalidation-batch",
+		value_name = "COUNT",
+		parse(try_from_str = parse_non_zero),
+	)]
+	pub pool_revalidation_batch: Option<usize>,
+
+	/// Priority added to the transactions submitted locally, e.g. by offchain workers.
+	#[structopt(long = "pool-local-priority-bonus", value_name = "PRIORITY", default_value = "0")]
+	pub pool_local_priority_bonus: u64,
+
+	/// A lane of transactions with a reserved share of the pool, e.g. `0x0102:512:1024`.
+	///
+	/// The lane is made of the transactions providing a tag starting with the hex encoded
+	/// prefix, and reserves the given number of transactions and kilobytes of the ready and of
+	/// the future queue. Can be passed multiple times, a transaction belongs to the first lane
+	/// it matches.
+	#[structopt(
+		long = "pool-lane",
+		value_name = "PREFIX:COUNT:KBYTES",
+		parse(try_from_str = parse_lane),
+	)]
+	pub pool_lane: Vec<(Vec<u8>, usize, usize)>,
+}
+
+fn parse_lane(s: &str) -> Result<(Vec<u8>, usize, usize), String> {
+	let parts = s.split(':').collect::<Vec<_>>();
+	let (prefix, count, kbytes) = match parts[..] {
+		[prefix, count, kbytes] => (prefix, count, kbytes),
+		_ => return Err(format!("Expected `PREFIX:COUNT:KBYTES`, got `{}`", s)),
+	};
+	let prefix = hex::decode(prefix.trim_start_matches("0x"))
+		.map_err(|e| format!("Invalid lane prefix `{}`: {}", prefix, e))?;
+	let count = count.parse().map_err(|e| format!("Invalid lane count `{}`: {}", count, e))?;
+	let kbytes = kbytes.parse()
+		.map_err(|e| format!("Invalid lane kilobytes `{}`: {}", kbytes, e))?;
+	Ok((prefix, count, kbytes))
+}
+
+fn parse_percent(s: &str) -> Result<u8, String> {
+	let percent = s.parse::<u8>().map_err(|e| format!("Invalid percent `{}`: {}", s, e))?;
+	if percent > 100 {
+		return Err(format!("Expected a percent between 0 and 100, got `{}`", percent));
+	}
+	Ok(percent)
+}
+
+fn parse_non_zero<T>(s: &str) -> Result<T, String> where
+	T: std::str::FromStr + Default + PartialEq,
+	T::Err: std::fmt::Display,
+{
+	let value = s.parse::<T>().map_err(|e| format!("Invalid number `{}`: {}", s, e))?;
+	if value == T::default() {
+		return Err("Expected a value greater than 0".into());
+	}
+	Ok(value)
 }
 
 impl TransactionPoolParams {
@@ -42,9 +158,99 @@ impl TransactionPoolParams {
 
 		// future queue
 		let factor = 10;
-		opts.future.count = self.pool_limit / factor;
-		opts.future.total_bytes = self.pool_kbytes * 1024 / factor;
+		opts.future.count = self.pool_future_limit.unwrap_or(self.pool_limit / factor);
+		opts.future.total_bytes = self.pool_future_kbytes
+			.map_or(self.pool_kbytes * 1024 / factor, |kbytes| kbytes * 1024);
+		opts.future_max_age = self.pool_future_max_age.map(std::time::Duration::from_secs);
+
+		opts.min_replacement_bump = Percent::from_percent(self.pool_replacement_bump);
+
+		// per-sender limits
+		if self.pool_sender_limit.is_some() || self.pool_sender_kbytes.is_some() {
+			let mut limit = opts.ready.clone();
+			if let Some(count) = self.pool_sender_limit {
+				limit.count = count;
+			}
+			if let Some(kbytes) = self.pool_sender_kbytes {
+				limit.total_bytes = kbytes * 1024;
+			}
+			opts.per_sender = Some(limit);
+		}
+
+		opts.persist = self.pool_persist;
+		opts.ban_time = std::time::Duration::from_secs(self.pool_ban_seconds);
+
+		opts.local_priority_bonus = self.pool_local_priority_bonus;
+
+		opts.lanes = self.pool_lane.iter()
+			.map(|(prefix, count, kbytes)| TransactionPoolLane {
+				tag_prefix: prefix.clone(),
+				reserved: TransactionPoolLimit { count: *count, total_bytes: kbytes * 1024 },
+			})
+			.collect();
+
+		opts.revalidation.interval =
+			std::time::Duration::from_millis(self.pool_revalidation_interval);
+		if let Some(batch) = self.pool_revalidation_batch {
+			opts.revalidation.max_batch_size = batch;
+		}
 
 		opts
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_the_lanes() {
+		let params = TransactionPoolParams::from_iter(&[
+			"test",
+			"--pool-lane", "0x0102:512:1024",
+			"--pool-lane", "ff:1:2",
+		]);
+		let lanes = params.transaction_pool().lanes;
+		assert_eq!(lanes.len(), 2);
+		assert_eq!(lanes[0].tag_prefix, vec![1, 2]);
+		assert_eq!(lanes[0].reserved.count, 512);
+		assert_eq!(lanes[0].reserved.total_bytes, 1024 * 1024);
+		assert_eq!(lanes[1].tag_prefix, vec![0xff]);
+
+		assert!(parse_lane("0x0102:512").is_err());
+		assert!(parse_lane("zz:1:2").is_err());
+	}
+
+	#[test]
+	fn rejects_replacement_bump_above_hundred_percent() {
+		let params = TransactionPoolParams::from_iter(&["test", "--pool-replacement-bump", "100"]);
+		assert_eq!(params.transaction_pool().min_replacement_bump, Percent::from_percent(100));
+
+		assert!(
+			TransactionPoolParams::from_iter_safe(&["test", "--pool-replacement-bump", "101"])
+				.is_err()
+		);
+		assert!(parse_percent("-1").is_err());
+	}
+
+	#[test]
+	fn rejects_zero_revalidation_interval_and_batch() {
+		let params = TransactionPoolParams::from_iter(&[
+			"test",
+			"--pool-revalidation-interval", "1",
+			"--pool-revalidation-batch", "10",
+		]);
+		let revalidation = params.transaction_pool().revalidation;
+		assert_eq!(revalidation.interval, std::time::Duration::from_millis(1));
+		assert_eq!(revalidation.max_batch_size, 10);
+
+		assert!(
+			TransactionPoolParams::from_iter_safe(&["test", "--pool-revalidation-interval", "0"])
+				.is_err()
+		);
+		assert!(
+			TransactionPoolParams::from_iter_safe(&["test", "--pool-revalidation-batch", "0"])
+				.is_err()
+		);
+	}
+}
@@ -0,0 +1,292 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Checks of the environment run before the node is started, so that a misconfigured host is
+//! reported clearly instead of failing obscurely an hour into the sync.
+
+use crate::error::{Error, Result};
+use log::{debug, error, warn};
+use sc_service::config::{Configuration, DatabaseConfig};
+use std::{
+	fs, io,
+	net::UdpSocket,
+	path::Path,
+	sync::mpsc,
+	thread,
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// The open file descriptor limit below which the database is likely to fail.
+const MINIMUM_OPEN_FILE_DESCRIPTOR_LIMIT: u64 = 1_024;
+
+/// The open file descriptor limit recommended to run a node.
+const RECOMMENDED_OPEN_FILE_DESCRIPTOR_LIMIT: u64 = 10_000;
+
+/// How long to wait for the answer of the NTP server, including the resolution of its name.
+const NTP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The offset of the clock from the NTP server, in seconds, above which a warning is logged.
+const MAX_CLOCK_OFFSET: f64 = 1.0;
+
+/// The seconds between the NTP epoch (1900) and the UNIX epoch (1970).
+const NTP_TO_UNIX_EPOCH: f64 = 2_208_988_800.0;
+
+const MIB: u64 = 1024 * 1024;
+
+/// A problem found by a check.
+#[derive(Debug, PartialEq)]
+enum Problem {
+	/// The node can run, but probably not well.
+	Warning(String),
+	/// The node will most likely fail.
+	Error(String),
+}
+
+/// Run the checks of the environment of the node with the given configuration.
+///
+/// The clock is compared with the given NTP server, unless it is `None`. The warnings are logged.
+/// The errors are logged too and make this function fail.
+pub fn run_preflight_checks(config: &Configuration, ntp_server: Option<&str>) -> Result<()> {
+	let problems = [
+		check_open_file_descriptor_limit(),
+		ntp_server.and_then(check_clock),
+		check_paths(config),
+		check_memory(config),
+	];
+
+	let mut errors = Vec::new();
+	for problem in problems.iter().flatten() {
+		match problem {
+			Problem::Warning(message) => warn!("⚠️  {}", message),
+			Problem::Error(message) => {
+				error!("❌ {}", message);
+				errors.push(message.as_str());
+			},
+		}
+	}
+
+	if errors.is_empty() {
+		Ok(())
+	} else {
+		Err(Error::Input(format!(
+			"The preflight checks failed: {}. Pass `--skip-preflight-checks` to start anyway.",
+			errors.join(" "),
+		)))
+	}
+}
+
+fn check_open_file_descriptor_limit() -> Option<Problem> {
+	// returns the current limit, which was already raised as much as possible.
+	let limit = fdlimit::raise_fd_limit()?;
+	if limit < MINIMUM_OPEN_FILE_DESCRIPTOR_LIMIT {
+		Some(Problem::Error(format!(
+			"The open file descriptor limit of the process is {}, the database needs at least {}. \
+			Raise the hard limit, e.g. with `ulimit -Hn`.",
+			limit, MINIMUM_OPEN_FILE_DESCRIPTOR_LIMIT,
+		)))
+	} else if limit < RECOMMENDED_OPEN_FILE_DESCRIPTOR_LIMIT {
+		Some(Problem::Warning(format!(
+			"Low open file descriptor limit configured for the process. \
+			Current value: {:?}, recommended value: {:?}.",
+			limit, RECOMMENDED_OPEN_FILE_DESCRIPTOR_LIMIT,
+		)))
+	} else {
+		None
+	}
+}
+
+fn check_clock(ntp_server: &str) -> Option<Problem> {
+	let offset = match clock_offset(ntp_server, NTP_TIMEOUT) {
+		Ok(offset) => offset,
+		Err(e) => {
+			debug!("Failed to compare the clock with {}: {}", ntp_server, e);
+			return None;
+		},
+	};
+
+	if offset.abs() > MAX_CLOCK_OFFSET {
+		Some(Problem::Warning(format!(
+			"The system clock is {:.1}s off from {}, blocks may be authored or rejected at the \
+			wrong time. Synchronize it, e.g. with an NTP daemon.",
+			offset, ntp_server,
+		)))
+	} else {
+		None
+	}
+}
+
+/// Returns how many seconds the clock of the given NTP server is ahead of the system clock.
+///
+/// Fails if the server didn't answer within the given timeout.
+fn clock_offset(server: &str, timeout: Duration) -> io::Result<f64> {
+	// the resolution of the name of the server isn't bounded by the timeouts of the socket, so
+	// the query is made from another thread, left behind if it doesn't answer in time.
+	let (sender, receiver) = mpsc::channel();
+	let server = server.to_owned();
+	thread::Builder::new()
+		.name("preflight-ntp".into())
+		.spawn(move || {
+			let _ = sender.send(query_clock_offset(&server, timeout));
+		})?;
+	receiver.recv_timeout(timeout).unwrap_or_else(|_| Err(io::Error::new(
+		io::ErrorKind::TimedOut,
+		"no answer from the NTP server",
+	)))
+}
+
+fn query_clock_offset(server: &str, timeout: Duration) -> io::Result<f64> {
+	let socket = UdpSocket::bind("0.0.0.0:0")?;
+	socket.set_read_timeout(Some(timeout))?;
+	socket.set_write_timeout(Some(timeout))?;
+	socket.connect(server)?;
+
+	let mut packet = [0u8; 48];
+	// leap indicator 0, version 3, client mode.
+	packet[0] = 0x1b;
+	let sent = unix_time(SystemTime::now())?;
+	socket.send(&packet)?;
+	if socket.recv(&mut packet)? < packet.len() {
+		return Err(io::Error::new(io::ErrorKind::InvalidData, "truncated NTP packet"));
+	}
+	let received = unix_time(SystemTime::now())?;
+
+	let transmitted = ntp_to_unix_time(&packet[40..48]);
+	Ok(transmitted - (sent + received) / 2.0)
+}
+
+fn unix_time(time: SystemTime) -> io::Result<f64> {
+	time.duration_since(UNIX_EPOCH)
+		.map(|time| time.as_secs_f64())
+		.map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+}
+
+/// Converts an NTP timestamp to seconds since the UNIX epoch.
+fn ntp_to_unix_time(timestamp: &[u8]) -> f64 {
+	let mut seconds = [0u8; 4];
+	let mut fraction = [0u8; 4];
+	seconds.copy_from_slice(&timestamp[..4]);
+	fraction.copy_from_slice(&timestamp[4..8]);
+	let fraction = u32::from_be_bytes(fraction) as f64 / (1u64 << 32) as f64;
+	u32::from_be_bytes(seconds) as f64 + fraction - NTP_TO_UNIX_EPOCH
+}
+
+fn check_paths(config: &Configuration) -> Option<Problem> {
+	let paths = [("database", config.database.path()), ("keystore", config.keystore.path())];
+	paths.iter()
+		.filter_map(|&(name, path)| path.map(|path| (name, path)))
+		.find_map(|(name, path)| check_writable(path).err().map(|e| Problem::Error(format!(
+			"The {} directory {} is not writable: {}. Check its owner and permissions.",
+			name,
+			path.display(),
+			e,
+		))))
+}
+
+/// Check that files can be created in the given directory, creating it if needed.
+fn check_writable(path: &Path) -> io::Result<()> {
+	fs::create_dir_all(path)?;
+	let probe = path.join(".preflight-check");
+	fs::write(&probe, b"")?;
+	fs::remove_file(&probe)
+}
+
+fn check_memory(config: &Configuration) -> Option<Problem> {
+	let database_cache = match config.database {
+		DatabaseConfig::RocksDb { cache_size, .. } => cache_size as u64 * MIB,
+		_ => 0,
+	};
+	let caches = database_cache + config.state_cache_size as u64 + config.trie_cache_size as u64;
+	let (total, available) = memory()?;
+	compare_memory(caches, total, available)
+}
+
+fn compare_memory(caches: u64, total: u64, available: u64) -> Option<Problem> {
+	let message = |memory: &str, size: u64| format!(
+		"The caches are configured to use {} MiB but the system has {} MiB of {} memory. \
+		Lower `--db-cache`, `--state-cache-size` or `--trie-cache-size`.",
+		caches / MIB,
+		size / MIB,
+		memory,
+	);
+	if caches > total {
+		Some(Problem::Error(message("total", total)))
+	} else if caches > available {
+		Some(Problem::Warning(message("available", available)))
+	} else {
+		None
+	}
+}
+
+/// Returns the total and the available memory, in bytes.
+#[cfg(target_os = "linux")]
+fn memory() -> Option<(u64, u64)> {
+	let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+	let field = |name: &str| meminfo.lines()
+		.find(|line| line.starts_with(name))
+		.and_then(|line| line.split_whitespace().nth(1))
+		.and_then(|kib| kib.parse::<u64>().ok())
+		.map(|kib| kib * 1024);
+	Some((field("MemTotal:")?, field("MemAvailable:")?))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn memory() -> Option<(u64, u64)> {
+	None
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn converts_ntp_timestamps() {
+		// 2021-01-01T00:00:00.5Z
+		let timestamp = [0xe3, 0x98, 0xe4, 0x80, 0x80, 0x00, 0x00, 0x00];
+		assert_eq!(ntp_to_unix_time(&timestamp), 1_609_459_200.5);
+	}
+
+	#[test]
+	fn clock_comparisons_time_out() {
+		let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+		let server = silent.local_addr().unwrap().to_string();
+		let error = clock_offset(&server, Duration::from_millis(100)).unwrap_err();
+		assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+	}
+
+	#[test]
+	fn caches_must_fit_in_memory() {
+		assert_eq!(compare_memory(512 * MIB, 2048 * MIB, 1024 * MIB), None);
+		assert!(matches!(
+			compare_memory(1536 * MIB, 2048 * MIB, 1024 * MIB),
+			Some(Problem::Warning(_))
+		));
+		assert!(matches!(
+			compare_memory(4096 * MIB, 2048 * MIB, 1024 * MIB),
+			Some(Problem::Error(_))
+		));
+	}
+
+	#[test]
+	fn writable_directories_are_created() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("chains").join("db");
+		check_writable(&path).unwrap();
+		assert!(path.is_dir());
+		assert_eq!(fs::read_dir(&path).unwrap().count(), 0);
+	}
+}
@@ -17,17 +17,18 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::CliConfiguration;
+use crate::run_preflight_checks;
 use crate::Result;
 use crate::SubstrateCli;
 use chrono::prelude::*;
 use futures::pin_mut;
 use futures::select;
 use futures::{future, future::FutureExt, Future};
-use log::info;
-use sc_service::{Configuration, TaskType, TaskManager};
+use log::{info, warn};
+use sc_service::{Configuration, RestartPolicy, TaskType, TaskManager};
 use sc_telemetry::{TelemetryHandle, TelemetryWorker};
 use sp_utils::metrics::{TOKIO_THREADS_ALIVE, TOKIO_THREADS_TOTAL};
-use std::marker::PhantomData;
+use std::{marker::PhantomData, time::Duration};
 use sc_service::Error as ServiceError;
 use crate::error::Error as CliError;
 
@@ -111,11 +112,27 @@ where
 	Ok(())
 }
 
+/// Shut the task manager down, waiting at most for the given timeout.
+async fn shutdown(task_manager: TaskManager, timeout: Option<Duration>) {
+	match timeout {
+		Some(timeout) => {
+			info!("Shutting down, waiting at most {:?}", timeout);
+			if tokio::time::timeout(timeout, task_manager.clean_shutdown()).await.is_err() {
+				warn!("The node didn't shut down within {:?}, exiting anyway", timeout);
+			}
+		},
+		None => task_manager.clean_shutdown().await,
+	}
+}
+
 /// A Substrate CLI runtime that can be used to run a node or a command
 pub struct Runner<C: SubstrateCli> {
 	config: Configuration,
 	tokio_runtime: tokio::runtime::Runtime,
 	telemetry_worker: TelemetryWorker,
+	shutdown_timeout: Option<Duration>,
+	skip_preflight_checks: bool,
+	ntp_server: Option<String>,
 	phantom: PhantomData<C>,
 }
 
@@ -148,6 +165,9 @@ impl<C: SubstrateCli> Runner<C> {
 			)?,
 			tokio_runtime,
 			telemetry_worker,
+			shutdown_timeout: command.shutdown_timeout()?,
+			skip_preflight_checks: command.skip_preflight_checks()?,
+			ntp_server: command.ntp_server()?,
 			phantom: PhantomData,
 		})
 	}
@@ -187,6 +207,13 @@ impl<C: SubstrateCli> Runner<C> {
 
 	/// A helper function that runs a node with tokio and stops if the process receives the signal
 	/// `SIGTERM` or `SIGINT`.
+	///
+	/// The environment of the node is checked first, unless the preflight checks are skipped,
+	/// see [`run_preflight_checks`].
+	///
+	/// The telemetry is flushed when the node shuts down, along with what the node registered
+	/// with [`TaskManager::on_shutdown`], e.g. the persistence of its transaction pool. See
+	/// [`TaskManager::clean_shutdown`] for the rest of the shutdown sequence.
 	pub fn run_node_until_exit<F, E>(
 		mut self,
 		initialize: impl FnOnce(Configuration) -> F,
@@ -196,10 +223,20 @@ impl<C: SubstrateCli> Runner<C> {
 		E: std::error::Error + Send + Sync + 'static + From<ServiceError>,
 	{
 		self.print_node_infos();
+		if !self.skip_preflight_checks {
+			run_preflight_checks(&self.config, self.ntp_server.as_deref())
+				.map_err(|e| ServiceError::Other(e.to_string()))?;
+		}
 		let mut task_manager = self.tokio_runtime.block_on(initialize(self.config))?;
-		task_manager.spawn_handle().spawn("telemetry_worker", self.telemetry_worker.run());
+		let telemetry_handle = self.telemetry_worker.handle();
+		task_manager.on_shutdown("telemetry", async move { telemetry_handle.flush().await });
+		task_manager.spawn_essential_handle().spawn_with_restart(
+			"telemetry_worker",
+			RestartPolicy::on_failure(),
+			self.telemetry_worker.restartable(),
+		);
 		let res = self.tokio_runtime.block_on(main(task_manager.future().fuse()));
-		self.tokio_runtime.block_on(task_manager.clean_shutdown());
+		self.tokio_runtime.block_on(shutdown(task_manager, self.shutdown_timeout));
 		Ok(res?)
 	}
 
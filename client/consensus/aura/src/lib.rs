@@ -54,9 +54,11 @@ use sp_api::ProvideRuntimeApi;
 use sp_core::crypto::Pair;
 use sp_keystore::{SyncCryptoStorePtr, SyncCryptoStore};
 use sp_inherents::{InherentDataProviders, InherentData};
+use sp_session::DisabledValidators;
 use sp_timestamp::{TimestampInherentData, InherentType as TimestampInherent};
 use sc_consensus_slots::{SlotInfo, SlotCompatible, StorageChanges, BackoffAuthoringBlocksStrategy};
 use sp_consensus_slots::Slot;
+use prometheus_endpoint::Registry;
 
 mod import_queue;
 
@@ -149,6 +151,8 @@ pub struct StartAuraParams<C, SC, I, PF, SO, BS, CAW> {
 	/// slot. However, the proposing can still take longer when there is some lenience factor applied,
 	/// because there were no blocks produced for some slots.
 	pub block_proposal_slot_portion: SlotProportion,
+	/// The prometheus registry.
+	pub registry: Option<Registry>,
 }
 
 /// Start the aura worker. The returned future should be run in a futures executor.
@@ -166,11 +170,12 @@ pub fn start_aura<P, B, C, SC, PF, I, SO, CAW, BS, Error>(
 		keystore,
 		can_author_with,
 		block_proposal_slot_portion,
+		registry,
 	}: StartAuraParams<C, SC, I, PF, SO, BS, CAW>,
 ) -> Result<impl Future<Output = ()>, sp_consensus::Error> where
 	B: BlockT,
 	C: ProvideRuntimeApi<B> + BlockOf + ProvideCache<B> + AuxStore + HeaderBackend<B> + Send + Sync,
-	C::Api: AuraApi<B, AuthorityId<P>>,
+	C::Api: AuraApi<B, AuthorityId<P>> + DisabledValidators<B>,
 	SC: SelectChain<B>,
 	PF: Environment<B, Error = Error> + Send + Sync + 'static,
 	PF::Proposer: Proposer<B, Error = Error, Transaction = sp_api::TransactionFor<C, B>>,
@@ -193,6 +198,7 @@ pub fn start_aura<P, B, C, SC, PF, I, SO, CAW, BS, Error>(
 		backoff_authoring_blocks,
 		_key_type: PhantomData::<P>,
 		block_proposal_slot_portion,
+		metrics: sc_consensus_slots::Metrics::register_or_warn(registry.as_ref()),
 	};
 	register_aura_inherent_data_provider(
 		&inherent_data_providers,
@@ -218,6 +224,7 @@ struct AuraWorker<C, E, I, P, SO, BS> {
 	force_authoring: bool,
 	backoff_authoring_blocks: Option<BS>,
 	block_proposal_slot_portion: SlotProportion,
+	metrics: Option<sc_consensus_slots::Metrics>,
 	_key_type: PhantomData<P>,
 }
 
@@ -226,7 +233,7 @@ impl<B, C, E, I, P, Error, SO, BS> sc_consensus_slots::SimpleSlotWorker<B>
 where
 	B: BlockT,
 	C: ProvideRuntimeApi<B> + BlockOf + ProvideCache<B> + HeaderBackend<B> + Sync,
-	C::Api: AuraApi<B, AuthorityId<P>>,
+	C::Api: AuraApi<B, AuthorityId<P>> + DisabledValidators<B>,
 	E: Environment<B, Error = Error>,
 	E::Proposer: Proposer<B, Error = Error, Transaction = sp_api::TransactionFor<C, B>>,
 	I: BlockImport<B, Transaction = sp_api::TransactionFor<C, B>> + Send + Sync + 'static,
@@ -361,6 +368,25 @@ where
 		false
 	}
 
+	fn is_authority_disabled(
+		&self,
+		chain_head: &B::Header,
+		claim: &Self::Claim,
+		epoch_data: &Self::EpochData,
+	) -> bool {
+		epoch_data.iter().position(|a| a == claim).map_or(false, |index| {
+			sc_consensus_slots::is_authority_disabled(
+				self.client.as_ref(),
+				&BlockId::Hash(chain_head.hash()),
+				index as u32,
+			)
+		})
+	}
+
+	fn metrics(&self) -> Option<&sc_consensus_slots::Metrics> {
+		self.metrics.as_ref()
+	}
+
 	fn sync_oracle(&mut self) -> &mut Self::SyncOracle {
 		&mut self.sync_oracle
 	}
@@ -595,6 +621,7 @@ mod tests {
 						inherent_data_providers,
 						AlwaysCanAuthor,
 						CheckForEquivocation::Yes,
+						sc_consensus_slots::ClockSkewMonitor::new(slot_duration.get(), None),
 					)
 				},
 				PeersClient::Light(_, _) => unreachable!("No (yet) tests for light client + Aura"),
@@ -670,6 +697,7 @@ mod tests {
 				keystore,
 				can_author_with: sp_consensus::AlwaysCanAuthor,
 				block_proposal_slot_portion: SlotProportion::new(0.5),
+				registry: None,
 			}).expect("Starts aura"));
 		}
 
@@ -731,6 +759,7 @@ mod tests {
 			backoff_authoring_blocks: Some(BackoffAuthoringOnFinalizedHeadLagging::default()),
 			_key_type: PhantomData::<AuthorityPair>,
 			block_proposal_slot_portion: SlotProportion::new(0.5),
+			metrics: None,
 		};
 
 		let head = Header::new(
@@ -779,6 +808,7 @@ mod tests {
 			backoff_authoring_blocks: Option::<()>::None,
 			_key_type: PhantomData::<AuthorityPair>,
 			block_proposal_slot_portion: SlotProportion::new(0.5),
+			metrics: None,
 		};
 
 		let head = client.header(&BlockId::Number(0)).unwrap().unwrap();
@@ -90,6 +90,7 @@ use sp_runtime::{
 use sp_api::{ProvideRuntimeApi, NumberFor};
 use parking_lot::Mutex;
 use sp_inherents::{InherentDataProviders, InherentData};
+use sp_session::DisabledValidators;
 use sc_telemetry::{telemetry, CONSENSUS_TRACE, CONSENSUS_DEBUG};
 use sp_consensus::{
 	BlockImport, Environment, Proposer, BlockCheckParams,
@@ -111,7 +112,7 @@ use log::{debug, info, log, trace, warn};
 use prometheus_endpoint::Registry;
 use sc_consensus_slots::{
 	SlotInfo, SlotCompatible, StorageChanges, CheckedHeader, check_equivocation,
-	BackoffAuthoringBlocksStrategy
+	BackoffAuthoringBlocksStrategy, ClockSkewMonitor,
 };
 use sc_consensus_epochs::{
 	descendent_query, SharedEpochChanges, EpochChangesFor, Epoch as EpochT, ViableEpochDescriptor,
@@ -402,6 +403,9 @@ pub struct BabeParams<B: BlockT, C, E, I, SO, SC, CAW, BS> {
 	/// slot. However, the proposing can still take longer when there is some lenience factor applied,
 	/// because there were no blocks produced for some slots.
 	pub block_proposal_slot_portion: SlotProportion,
+
+	/// The prometheus registry.
+	pub registry: Option<Registry>,
 }
 
 /// Start the babe worker.
@@ -418,6 +422,7 @@ pub fn start_babe<B, C, SC, E, I, SO, CAW, BS, Error>(BabeParams {
 	babe_link,
 	can_author_with,
 	block_proposal_slot_portion,
+	registry,
 }: BabeParams<B, C, E, I, SO, SC, CAW, BS>) -> Result<
 	BabeWorker<B>,
 	sp_consensus::Error,
@@ -425,7 +430,7 @@ pub fn start_babe<B, C, SC, E, I, SO, CAW, BS, Error>(BabeParams {
 	B: BlockT,
 	C: ProvideRuntimeApi<B> + ProvideCache<B> + ProvideUncles<B> + BlockchainEvents<B>
 		+ HeaderBackend<B> + HeaderMetadata<B, Error = ClientError> + Send + Sync + 'static,
-	C::Api: BabeApi<B>,
+	C::Api: BabeApi<B> + DisabledValidators<B>,
 	SC: SelectChain<B> + 'static,
 	E: Environment<B, Error = Error> + Send + Sync + 'static,
 	E::Proposer: Proposer<B, Error = Error, Transaction = sp_api::TransactionFor<C, B>>,
@@ -453,6 +458,7 @@ pub fn start_babe<B, C, SC, E, I, SO, CAW, BS, Error>(BabeParams {
 		slot_notification_sinks: slot_notification_sinks.clone(),
 		config: config.clone(),
 		block_proposal_slot_portion,
+		metrics: sc_consensus_slots::Metrics::register_or_warn(registry.as_ref()),
 	};
 
 	register_babe_inherent_data_provider(&inherent_data_providers, config.slot_duration())?;
@@ -495,38 +501,61 @@ async fn answer_requests<B: BlockT, C>(
 	while let Some(request) = request_rx.next().await {
 		match request {
 			BabeRequest::EpochForChild(parent_hash, parent_number, slot_number, response) => {
-				let lookup = || {
-					let epoch_changes =	epoch_changes.lock();
-					let epoch_descriptor = epoch_changes.epoch_descriptor_for_child_of(
-						descendent_query(&*client),
-						&parent_hash,
-						parent_number,
-						slot_number,
-					)
-						.map_err(|e| Error::<B>::ForkTree(Box::new(e)))?
-						.ok_or_else(|| Error::<B>::FetchEpoch(parent_hash))?;
-
-					let viable_epoch = epoch_changes.viable_epoch(
-						&epoch_descriptor,
-						|slot| Epoch::genesis(&genesis_config, slot)
-					).ok_or_else(|| Error::<B>::FetchEpoch(parent_hash))?;
-
-					Ok(sp_consensus_babe::Epoch {
-						epoch_index: viable_epoch.as_ref().epoch_index,
-						start_slot: viable_epoch.as_ref().start_slot,
-						duration: viable_epoch.as_ref().duration,
-						authorities: viable_epoch.as_ref().authorities.clone(),
-						randomness: viable_epoch.as_ref().randomness,
-						config: viable_epoch.as_ref().config.clone(),
-					})
-				};
-
-				let _ = response.send(lookup());
+				let lookup = epoch_for_child_of(
+					&*client,
+					&epoch_changes,
+					&genesis_config,
+					parent_hash,
+					parent_number,
+					slot_number,
+				).map(|epoch| sp_consensus_babe::Epoch {
+					epoch_index: epoch.epoch_index,
+					start_slot: epoch.start_slot,
+					duration: epoch.duration,
+					authorities: epoch.authorities,
+					randomness: epoch.randomness,
+					config: epoch.config,
+				});
+
+				let _ = response.send(lookup);
 			}
 		}
 	}
 }
 
+/// Returns the epoch that a child of the given block, authored in the given slot, would belong
+/// to, as recorded in the epoch changes tree.
+///
+/// The parent block is identified by its hash and number.
+pub fn epoch_for_child_of<B, C>(
+	client: &C,
+	epoch_changes: &SharedEpochChanges<B, Epoch>,
+	genesis_config: &BabeGenesisConfiguration,
+	parent_hash: B::Hash,
+	parent_number: NumberFor<B>,
+	slot: Slot,
+) -> Result<Epoch, Error<B>> where
+	B: BlockT,
+	C: HeaderBackend<B> + HeaderMetadata<B, Error = ClientError>,
+{
+	let epoch_changes = epoch_changes.lock();
+	let epoch_descriptor = epoch_changes.epoch_descriptor_for_child_of(
+		descendent_query(client),
+		&parent_hash,
+		parent_number,
+		slot,
+	)
+		.map_err(|e| Error::<B>::ForkTree(Box::new(e)))?
+		.ok_or_else(|| Error::<B>::FetchEpoch(parent_hash))?;
+
+	let viable_epoch = epoch_changes.viable_epoch(
+		&epoch_descriptor,
+		|slot| Epoch::genesis(genesis_config, slot)
+	).ok_or_else(|| Error::<B>::FetchEpoch(parent_hash))?;
+
+	Ok(viable_epoch.as_ref().clone())
+}
+
 /// Requests to the BABE service.
 #[non_exhaustive]
 pub enum BabeRequest<B: BlockT> {
@@ -609,6 +638,7 @@ struct BabeSlotWorker<B: BlockT, C, E, I, SO, BS> {
 	slot_notification_sinks: SlotNotificationSinks<B>,
 	config: Config,
 	block_proposal_slot_portion: SlotProportion,
+	metrics: Option<sc_consensus_slots::Metrics>,
 }
 
 impl<B, C, E, I, Error, SO, BS> sc_consensus_slots::SimpleSlotWorker<B>
@@ -619,7 +649,7 @@ where
 		ProvideCache<B> +
 		HeaderBackend<B> +
 		HeaderMetadata<B, Error = ClientError>,
-	C::Api: BabeApi<B>,
+	C::Api: BabeApi<B> + DisabledValidators<B>,
 	E: Environment<B, Error = Error>,
 	E::Proposer: Proposer<B, Error = Error, Transaction = sp_api::TransactionFor<C, B>>,
 	I: BlockImport<B, Transaction = sp_api::TransactionFor<C, B>> + Send + Sync + 'static,
@@ -789,6 +819,23 @@ where
 		false
 	}
 
+	fn is_authority_disabled(
+		&self,
+		chain_head: &B::Header,
+		claim: &Self::Claim,
+		_epoch_data: &Self::EpochData,
+	) -> bool {
+		sc_consensus_slots::is_authority_disabled(
+			self.client.as_ref(),
+			&BlockId::Hash(chain_head.hash()),
+			claim.0.authority_index(),
+		)
+	}
+
+	fn metrics(&self) -> Option<&sc_consensus_slots::Metrics> {
+		self.metrics.as_ref()
+	}
+
 	fn sync_oracle(&mut self) -> &mut Self::SyncOracle {
 		&mut self.sync_oracle
 	}
@@ -947,6 +994,7 @@ pub struct BabeVerifier<Block: BlockT, Client, SelectChain, CAW> {
 	epoch_changes: SharedEpochChanges<Block, Epoch>,
 	time_source: TimeSource,
 	can_author_with: CAW,
+	clock_skew: ClockSkewMonitor,
 }
 
 impl<Block, Client, SelectChain, CAW> BabeVerifier<Block, Client, SelectChain, CAW>
@@ -1113,6 +1161,7 @@ where
 			.map_err(Error::<Block>::FetchParentHeader)?;
 
 		let pre_digest = find_pre_digest::<Block>(&header)?;
+
 		let epoch_changes = self.epoch_changes.lock();
 		let epoch_descriptor = epoch_changes.epoch_descriptor_for_child_of(
 			descendent_query(&*self.client),
@@ -1142,6 +1191,10 @@ where
 					.expect("check_header always returns a pre-digest digest item; qed");
 				let slot = babe_pre_digest.slot();
 
+				// only the slots of blocks with a valid seal are used to estimate the skew of
+				// the local clock, anyone could announce a block with an arbitrary slot.
+				self.clock_skew.note_block(origin, slot);
+
 				// the header is valid but let's check if there was something else already
 				// proposed at the same slot by the given author. if there was, we will
 				// report the equivocation to the runtime.
@@ -1614,6 +1667,7 @@ pub fn import_queue<Block: BlockT, Client, SelectChain, Inner, CAW>(
 		client,
 		select_chain,
 		inherent_data_providers,
+		clock_skew: ClockSkewMonitor::new(babe_link.config.slot_duration, registry),
 		config: babe_link.config,
 		epoch_changes: babe_link.epoch_changes,
 		time_source: babe_link.time_source,
@@ -320,6 +320,10 @@ impl TestNetFactory for BabeTestNet {
 				epoch_changes: data.link.epoch_changes.clone(),
 				time_source: data.link.time_source.clone(),
 				can_author_with: AlwaysCanAuthor,
+				clock_skew: sc_consensus_slots::ClockSkewMonitor::new(
+					data.link.config.slot_duration,
+					None,
+				),
 			},
 			mutator: MUTATOR.with(|m| m.borrow().clone()),
 		}
@@ -432,6 +436,7 @@ fn run_one_test(
 			keystore,
 			can_author_with: sp_consensus::AlwaysCanAuthor,
 			block_proposal_slot_portion: SlotProportion::new(0.5),
+			registry: None,
 		}).expect("Starts babe"));
 	}
 	futures::executor::block_on(future::select(
@@ -0,0 +1,213 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Detection of skew between the local clock and the clock of the network.
+//!
+//! Every block announced to us carries the slot it was authored in, which marks
+//! the time at which the author started building it. A block can't be received
+//! before its slot starts and should normally be received well before the next
+//! slot starts. If the blocks announced by our peers are consistently received
+//! outside of that window, our local clock has drifted away from the network.
+
+use std::{collections::VecDeque, sync::Arc, time::{Duration, Instant, SystemTime, UNIX_EPOCH}};
+
+use log::{debug, warn};
+use parking_lot::Mutex;
+use prometheus_endpoint::{register, Gauge, PrometheusError, Registry, I64};
+use sp_consensus::BlockOrigin;
+use sp_consensus_slots::Slot;
+
+/// The number of most recent block announcements the skew is estimated from.
+const MAX_SAMPLES: usize = 32;
+
+/// The minimum number of samples required before estimating any skew.
+const MIN_SAMPLES: usize = 8;
+
+/// The minimum interval between two clock skew warnings.
+const WARN_INTERVAL: Duration = Duration::from_secs(60);
+
+struct Inner {
+	samples: VecDeque<i64>,
+	last_warning: Option<Instant>,
+}
+
+/// Estimates the skew of the local clock from the slots of the blocks
+/// announced by the network, warning when it exceeds half of a slot.
+#[derive(Clone)]
+pub struct ClockSkewMonitor {
+	slot_duration: u64,
+	inner: Arc<Mutex<Inner>>,
+	skew: Option<Gauge<I64>>,
+}
+
+impl ClockSkewMonitor {
+	/// Create a new monitor for the given slot duration (in milliseconds),
+	/// optionally exposing the estimated skew in the given registry.
+	pub fn new(slot_duration: u64, registry: Option<&Registry>) -> Self {
+		let skew = registry.and_then(|registry|
+			register_skew_gauge(registry)
+				.map_err(|err| warn!(
+					target: "slots",
+					"Failed to register clock skew prometheus metric: {}",
+					err,
+				))
+				.ok()
+		);
+
+		ClockSkewMonitor {
+			slot_duration,
+			inner: Arc::new(Mutex::new(Inner { samples: VecDeque::new(), last_warning: None })),
+			skew,
+		}
+	}
+
+	/// Note that a block authored in the given slot was received with the
+	/// given origin. Only blocks announced by the network are taken into
+	/// account, since blocks received while syncing are expected to be old.
+	pub fn note_block(&self, origin: BlockOrigin, slot: Slot) {
+		if origin != BlockOrigin::NetworkBroadcast {
+			return;
+		}
+
+		let now = match SystemTime::now().duration_since(UNIX_EPOCH) {
+			Ok(now) => now.as_millis() as i64,
+			Err(_) => return,
+		};
+
+		let slot_start = (*slot).saturating_mul(self.slot_duration) as i64;
+		self.note_sample(now.saturating_sub(slot_start));
+	}
+
+	/// Note the delay (in milliseconds, as measured by the local clock) with
+	/// which a block was received after the start of its slot.
+	fn note_sample(&self, delay: i64) {
+		let mut inner = self.inner.lock();
+
+		if inner.samples.len() == MAX_SAMPLES {
+			inner.samples.pop_front();
+		}
+		inner.samples.push_back(delay);
+
+		let skew = match estimate_skew(&inner.samples, self.slot_duration) {
+			Some(skew) => skew,
+			None => return,
+		};
+
+		if let Some(gauge) = self.skew.as_ref() {
+			gauge.set(skew);
+		}
+
+		if (skew.abs() as u64) <= self.slot_duration / 2 {
+			return;
+		}
+
+		debug!(target: "slots", "Estimated local clock skew of {} ms", skew);
+
+		if inner.last_warning.map_or(false, |last| last.elapsed() < WARN_INTERVAL) {
+			return;
+		}
+		inner.last_warning = Some(Instant::now());
+
+		warn!(
+			target: "slots",
+			"⏰ Local clock seems to be {} ms {} the network's. \
+			 Blocks can't be authored or imported reliably, please check the system time \
+			 (e.g. whether NTP is running).",
+			skew.abs(),
+			if skew > 0 { "ahead of" } else { "behind" },
+		);
+	}
+}
+
+/// Estimate the skew of the local clock from the given delays, i.e. the
+/// median delay outside of the window `[0, slot_duration)` during which blocks
+/// are expected to be received. A positive skew means the local clock is ahead.
+fn estimate_skew(samples: &VecDeque<i64>, slot_duration: u64) -> Option<i64> {
+	if samples.len() < MIN_SAMPLES {
+		return None;
+	}
+
+	let mut sorted = samples.iter().copied().collect::<Vec<_>>();
+	sorted.sort_unstable();
+	let median = sorted[sorted.len() / 2];
+
+	let skew = if median < 0 {
+		median
+	} else {
+		median.saturating_sub(slot_duration as i64).max(0)
+	};
+
+	Some(skew)
+}
+
+fn register_skew_gauge(registry: &Registry) -> Result<Gauge<I64>, PrometheusError> {
+	register(
+		Gauge::new(
+			"slots_estimated_clock_skew_milliseconds",
+			"Estimated skew of the local clock compared to the network, positive if ahead",
+		)?,
+		registry,
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn samples(delays: &[i64]) -> VecDeque<i64> {
+		delays.iter().copied().collect()
+	}
+
+	#[test]
+	fn requires_minimum_number_of_samples() {
+		assert_eq!(estimate_skew(&samples(&[-10_000; MIN_SAMPLES - 1]), 6000), None);
+		assert_eq!(estimate_skew(&samples(&[-10_000; MIN_SAMPLES]), 6000), Some(-10_000));
+	}
+
+	#[test]
+	fn no_skew_while_blocks_arrive_within_their_slot() {
+		let delays = [200, 500, 800, 300, 1200, 400, 600, 5900];
+		assert_eq!(estimate_skew(&samples(&delays), 6000), Some(0));
+	}
+
+	#[test]
+	fn estimates_skew_from_median_delay() {
+		// local clock is behind, blocks seem to come from the future.
+		let delays = [-4000, -3500, -3800, 9000, -3900, -3700, -3600, -4100];
+		assert_eq!(estimate_skew(&samples(&delays), 6000), Some(-3700));
+
+		// local clock is ahead, blocks seem to arrive late.
+		let delays = [9000, 9500, 9200, 0, 9100, 9300, 9400, 8900];
+		assert_eq!(estimate_skew(&samples(&delays), 6000), Some(3200));
+	}
+
+	#[test]
+	fn ignores_blocks_not_announced_by_the_network() {
+		let monitor = ClockSkewMonitor::new(6000, None);
+
+		for _ in 0..MAX_SAMPLES {
+			monitor.note_block(BlockOrigin::NetworkInitialSync, 0u64.into());
+		}
+		assert!(monitor.inner.lock().samples.is_empty());
+
+		for _ in 0..MAX_SAMPLES * 2 {
+			monitor.note_block(BlockOrigin::NetworkBroadcast, 0u64.into());
+		}
+		assert_eq!(monitor.inner.lock().samples.len(), MAX_SAMPLES);
+	}
+}
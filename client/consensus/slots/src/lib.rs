@@ -27,10 +27,14 @@
 
 mod slots;
 mod aux_schema;
+mod clock_skew;
+mod metrics;
 
 pub use slots::SlotInfo;
 use slots::Slots;
 pub use aux_schema::{check_equivocation, MAX_SLOT_CAPACITY, PRUNING_BOUND};
+pub use clock_skew::ClockSkewMonitor;
+pub use metrics::Metrics;
 
 use std::{fmt::Debug, ops::Deref, pin::Pin, sync::Arc, time::Duration};
 use codec::{Decode, Encode};
@@ -38,11 +42,12 @@ use futures::{prelude::*, future::{self, Either}};
 use futures_timer::Delay;
 use log::{debug, error, info, warn};
 use parking_lot::Mutex;
-use sp_api::{ProvideRuntimeApi, ApiRef};
+use sp_api::{ApiExt, ProvideRuntimeApi, ApiRef};
 use sp_arithmetic::traits::BaseArithmetic;
 use sp_consensus::{BlockImport, Proposer, SyncOracle, SelectChain, CanAuthorWith, SlotData};
 use sp_consensus_slots::Slot;
 use sp_inherents::{InherentData, InherentDataProviders};
+use sp_session::DisabledValidators;
 use sp_runtime::{
 	generic::BlockId,
 	traits::{Block as BlockT, Header, HashFor, NumberFor}
@@ -174,6 +179,27 @@ pub trait SimpleSlotWorker<B: BlockT> {
 		false
 	}
 
+	/// Returns whether the authority that claimed the slot has been disabled by the runtime.
+	///
+	/// Blocks authored by a disabled authority are rejected by the rest of the network, so
+	/// authoring is skipped when this returns `true`. A helper implementation backed by the
+	/// [`DisabledValidators`] runtime api is provided by [`is_authority_disabled`].
+	///
+	/// By default this function always returns `false`.
+	fn is_authority_disabled(
+		&self,
+		_chain_head: &B::Header,
+		_claim: &Self::Claim,
+		_epoch_data: &Self::EpochData,
+	) -> bool {
+		false
+	}
+
+	/// Returns the prometheus metrics of this worker, if any.
+	fn metrics(&self) -> Option<&Metrics> {
+		None
+	}
+
 	/// Returns a handle to a `SyncOracle`.
 	fn sync_oracle(&mut self) -> &mut Self::SyncOracle;
 
@@ -251,6 +277,32 @@ pub trait SimpleSlotWorker<B: BlockT> {
 			Some(claim) => claim,
 		};
 
+		if self.is_authority_disabled(&chain_head, &claim, &epoch_data) {
+			warn!(
+				target: self.logging_target(),
+				"🔒 Skipping authorship at slot {}: our authority is disabled by the runtime \
+				in the current session, blocks authored by it would be rejected by peers.",
+				slot,
+			);
+
+			telemetry!(
+				CONSENSUS_WARN;
+				"slots.authority_disabled";
+				"slot" => *slot,
+			);
+
+			if let Some(metrics) = self.metrics() {
+				metrics.authority_disabled.set(1);
+				metrics.disabled_slots_skipped.inc();
+			}
+
+			return Box::pin(future::ready(None));
+		}
+
+		if let Some(metrics) = self.metrics() {
+			metrics.authority_disabled.set(0);
+		}
+
 		if self.should_backoff(slot, &chain_head) {
 			return Box::pin(future::ready(None));
 		}
@@ -460,6 +512,42 @@ where
 		})
 }
 
+/// Checks whether the authority with the given index in the current session's validator set
+/// has been disabled by the runtime at the given block.
+///
+/// Uses the [`DisabledValidators`] runtime api and returns `false` if the runtime at the given
+/// block doesn't implement it or the call fails.
+pub fn is_authority_disabled<B, C>(client: &C, at: &BlockId<B>, authority_index: u32) -> bool
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B>,
+	C::Api: DisabledValidators<B>,
+{
+	let runtime_api = client.runtime_api();
+
+	match runtime_api.has_api::<dyn DisabledValidators<B>>(at) {
+		Ok(true) => {},
+		Ok(false) => return false,
+		Err(err) => {
+			debug!(
+				target: "slots",
+				"Unable to check for `DisabledValidators` api at {:?}: {:?}",
+				at,
+				err,
+			);
+			return false
+		},
+	}
+
+	match runtime_api.disabled_validators(at) {
+		Ok(disabled) => disabled.contains(&authority_index),
+		Err(err) => {
+			debug!(target: "slots", "Unable to fetch disabled validators at {:?}: {:?}", at, err);
+			false
+		},
+	}
+}
+
 /// A header which has been checked
 pub enum CheckedHeader<H, S> {
 	/// A header which has slot in the future. this is the full header (not stripped)
@@ -1127,4 +1215,56 @@ mod test {
 		assert_eq!((block_for_max_interval, time_to_reach_limit), expected);
 		assert_eq!((block_for_max_interval, time_to_reach_limit), (250, 60906));
 	}
+
+	#[derive(Clone)]
+	struct TestApi {
+		disabled: Vec<u32>,
+	}
+
+	struct RuntimeApi {
+		inner: TestApi,
+	}
+
+	impl sp_api::ProvideRuntimeApi<Block> for TestApi {
+		type Api = RuntimeApi;
+
+		fn runtime_api<'a>(&'a self) -> sp_api::ApiRef<'a, Self::Api> {
+			RuntimeApi { inner: self.clone() }.into()
+		}
+	}
+
+	sp_api::mock_impl_runtime_apis! {
+		impl sp_session::DisabledValidators<Block> for RuntimeApi {
+			fn disabled_validators(&self) -> Vec<u32> {
+				self.inner.disabled.clone()
+			}
+		}
+	}
+
+	#[test]
+	fn authority_disabled_by_the_runtime_is_detected() {
+		let client = TestApi { disabled: vec![1, 3] };
+		let at = sp_runtime::generic::BlockId::Number(0);
+
+		assert!(!super::is_authority_disabled::<Block, _>(&client, &at, 0));
+		assert!(super::is_authority_disabled::<Block, _>(&client, &at, 1));
+		assert!(!super::is_authority_disabled::<Block, _>(&client, &at, 2));
+		assert!(super::is_authority_disabled::<Block, _>(&client, &at, 3));
+	}
+
+	#[test]
+	fn no_authority_is_disabled_without_disabled_validators() {
+		let client = TestApi { disabled: Vec::new() };
+		let at = sp_runtime::generic::BlockId::Number(0);
+
+		assert!((0..4).all(|i| !super::is_authority_disabled::<Block, _>(&client, &at, i)));
+	}
+
+	#[test]
+	fn no_authority_is_disabled_on_the_test_runtime() {
+		let client = substrate_test_runtime_client::new();
+		let at = sp_runtime::generic::BlockId::Number(0);
+
+		assert!(!super::is_authority_disabled::<Block, _>(&client, &at, 0));
+	}
 }
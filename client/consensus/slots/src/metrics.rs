@@ -0,0 +1,64 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Prometheus metrics of the slot worker.
+
+use prometheus_endpoint::{register, Counter, Gauge, PrometheusError, Registry, U64};
+
+/// Metrics reported by a [`SimpleSlotWorker`](crate::SimpleSlotWorker).
+#[derive(Clone)]
+pub struct Metrics {
+	pub(crate) authority_disabled: Gauge<U64>,
+	pub(crate) disabled_slots_skipped: Counter<U64>,
+}
+
+impl Metrics {
+	/// Register the slot worker metrics in the given registry.
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			authority_disabled: register(
+				Gauge::new(
+					"slots_authority_disabled",
+					"Whether the local authority is currently disabled by the runtime (0 or 1)",
+				)?,
+				registry,
+			)?,
+			disabled_slots_skipped: register(
+				Counter::new(
+					"slots_disabled_slots_skipped_total",
+					"Number of claimed slots skipped because the local authority is disabled",
+				)?,
+				registry,
+			)?,
+		})
+	}
+
+	/// Register the slot worker metrics, logging a warning instead of failing if the
+	/// registration is unsuccessful.
+	pub fn register_or_warn(registry: Option<&Registry>) -> Option<Self> {
+		registry.and_then(|registry|
+			Self::register(registry)
+				.map_err(|err| log::warn!(
+					target: "slots",
+					"Failed to register slot worker prometheus metrics: {}",
+					err,
+				))
+				.ok()
+		)
+	}
+}
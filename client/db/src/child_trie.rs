@@ -0,0 +1,120 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Reclaiming the nodes of the emptied child tries.
+//!
+//! The nodes of a child trie are only deleted with the keys they store. The nodes a block
+//! leaves behind once it deleted all the keys of a child trie are added to the nodes the block
+//! deletes, so the pruning journal reclaims them once the block is pruned.
+
+use std::collections::HashSet;
+use hash_db::{Hasher, HashDBRef, Prefix};
+use log::warn;
+use sp_core::{hexdisplay::HexDisplay, storage::ChildInfo};
+use sp_state_machine::{Backend as StateBackend, ChildStorageCollection, StorageCollection, Storage};
+use sp_trie::{DBValue, KeySpacedDB, Layout, TrieDB, TrieDBNodeIterator, prefixed_key};
+
+/// The nodes of the state, by their prefixed key.
+struct StateNodes<'a, H: Hasher>(&'a dyn Storage<H>);
+
+impl<'a, H: Hasher> HashDBRef<H, DBValue> for StateNodes<'a, H> {
+	fn get(&self, key: &H::Out, prefix: Prefix) -> Option<DBValue> {
+		self.0.get(key, prefix).ok().flatten()
+	}
+
+	fn contains(&self, key: &H::Out, prefix: Prefix) -> bool {
+		self.get(key, prefix).is_some()
+	}
+}
+
+/// Returns the database keys of the nodes of the child tries emptied by `child_changes` on top
+/// of `state`, which aren't already `deleted`.
+pub(crate) fn orphaned_nodes<H: Hasher, S: StateBackend<H>>(
+	nodes: &dyn Storage<H>,
+	state: &S,
+	child_changes: &ChildStorageCollection,
+	deleted: &[Vec<u8>],
+) -> Vec<Vec<u8>> {
+	let mut orphaned = Vec::new();
+	for (storage_key, changes) in child_changes {
+		let child_info = ChildInfo::new_default(storage_key);
+		let result = emptied_trie_nodes(nodes, state, &child_info, changes, deleted, &mut orphaned);
+		if let Err(e) = result {
+			warn!(
+				target: "db",
+				"Failed to read the nodes of the emptied child trie {}: {}",
+				HexDisplay::from(storage_key),
+				e,
+			);
+		}
+	}
+	orphaned
+}
+
+fn emptied_trie_nodes<H: Hasher, S: StateBackend<H>>(
+	nodes: &dyn Storage<H>,
+	state: &S,
+	child_info: &ChildInfo,
+	changes: &StorageCollection,
+	deleted: &[Vec<u8>],
+	orphaned: &mut Vec<Vec<u8>>,
+) -> Result<(), String> {
+	// the child trie is only emptied if all its keys are deleted.
+	if changes.iter().any(|(_, value)| value.is_some()) {
+		return Ok(());
+	}
+	let root = match state.storage(child_info.prefixed_storage_key().as_slice()) {
+		Ok(Some(root)) => root,
+		Ok(None) => return Ok(()),
+		Err(e) => return Err(format!("{:?}", e)),
+	};
+	let removed = changes.iter().map(|(key, _)| &key[..]).collect::<HashSet<_>>();
+	let mut emptied = true;
+	state.apply_to_child_keys_while(child_info, |key| {
+		emptied = removed.contains(key);
+		emptied
+	});
+	if !emptied {
+		return Ok(());
+	}
+
+	let mut root_hash = H::Out::default();
+	if root.len() != root_hash.as_ref().len() {
+		return Err(format!("Invalid root {}", HexDisplay::from(&root)));
+	}
+	root_hash.as_mut().copy_from_slice(&root);
+	let keyspace = child_info.keyspace();
+	let state_nodes = StateNodes(nodes);
+	let db = KeySpacedDB::new(&state_nodes, keyspace);
+	let trie = TrieDB::<Layout<H>>::new(&db, &root_hash).map_err(|e| format!("{:?}", e))?;
+
+	let deleted = deleted.iter().map(|key| &key[..]).collect::<HashSet<_>>();
+	for node in TrieDBNodeIterator::new(&trie).map_err(|e| format!("{:?}", e))? {
+		let (prefix, hash, _) = node.map_err(|e| format!("{:?}", e))?;
+		// the inline nodes are stored in their parent.
+		if let Some(hash) = hash {
+			let (nibbles, padding) = prefix.as_prefix();
+			let key_prefix = [keyspace, nibbles].concat();
+			let key = prefixed_key::<H>(&hash, (&key_prefix, padding));
+			if !deleted.contains(&key[..]) {
+				orphaned.push(key);
+			}
+		}
+	}
+	Ok(())
+}
@@ -0,0 +1,197 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Storage of the bodies and justifications of the ancient blocks in a second database.
+//!
+//! The bodies and justifications of the blocks finalized long enough ago are moved in the
+//! background from the main database to the cold one, e.g. on a slower and cheaper disk.
+//! They are read from the main database first, then from the cold one.
+
+use std::sync::Arc;
+
+use codec::{Decode, Encode};
+use sp_database::{Change, ColumnId, Database, Transaction};
+
+use crate::{columns, DatabaseSettingsSrc, DbHash};
+
+/// Number of the first block whose body and justification may still be in the main database.
+const COLD_BLOCKS: &[u8; 4] = b"cold";
+
+/// The columns moved to the cold database.
+const COLD_COLUMNS: [ColumnId; 2] = [columns::BODY, columns::JUSTIFICATION];
+
+fn is_cold(col: ColumnId) -> bool {
+	COLD_COLUMNS.contains(&col)
+}
+
+/// Open the cold database.
+pub(crate) fn open(
+	source: &DatabaseSettingsSrc,
+) -> sp_blockchain::Result<Arc<dyn Database<DbHash>>> {
+	Ok(match source {
+		#[cfg(any(feature = "with-kvdb-rocksdb", test))]
+		DatabaseSettingsSrc::RocksDb { path, cache_size } => {
+			let mut config = kvdb_rocksdb::DatabaseConfig::with_columns(crate::utils::NUM_COLUMNS);
+			let budget = cache_size / COLD_COLUMNS.len();
+			config.memory_budget = COLD_COLUMNS.iter().map(|col| (*col, budget)).collect();
+			let path = path.to_str()
+				.ok_or_else(|| sp_blockchain::Error::Backend("Invalid database path".into()))?;
+			let db = kvdb_rocksdb::Database::open(&config, path)
+				.map_err(|err| sp_blockchain::Error::Backend(format!("{}", err)))?;
+			sp_database::as_database(db)
+		},
+		#[cfg(feature = "with-parity-db")]
+		DatabaseSettingsSrc::ParityDb { path, .. } =>
+			crate::parity_db::open(path, crate::utils::DatabaseType::Full)
+				.map_err(|e| sp_blockchain::Error::Backend(format!("{:?}", e)))?,
+		DatabaseSettingsSrc::Custom(db) => db.clone(),
+		#[allow(unreachable_patterns)]
+		source => return Err(sp_blockchain::Error::Backend(
+			format!("The cold database {} can't be opened, its feature isn't enabled", source),
+		)),
+	})
+}
+
+/// The main database, falling back to the cold database for the block bodies and
+/// justifications moved there.
+///
+/// The removals of bodies and justifications, when blocks are pruned or reverted, apply to
+/// both databases.
+pub(crate) struct TieredDatabase {
+	hot: Arc<dyn Database<DbHash>>,
+	cold: Arc<dyn Database<DbHash>>,
+	hot_blocks: u32,
+}
+
+impl TieredDatabase {
+	/// Keep the bodies and justifications of the last `hot_blocks` finalized blocks in the main
+	/// database.
+	pub(crate) fn new(
+		hot: Arc<dyn Database<DbHash>>,
+		cold: Arc<dyn Database<DbHash>>,
+		hot_blocks: u32,
+	) -> Self {
+		TieredDatabase { hot, cold, hot_blocks }
+	}
+
+	/// Returns the number of finalized blocks whose bodies and justifications stay in the main
+	/// database.
+	pub(crate) fn hot_blocks(&self) -> u32 {
+		self.hot_blocks
+	}
+
+	/// Returns the number of the first block whose body and justification may not have been
+	/// moved yet.
+	pub(crate) fn next_block(&self) -> Result<u64, codec::Error> {
+		match self.cold.get(columns::META, COLD_BLOCKS) {
+			Some(next) => u64::decode(&mut &next[..]),
+			None => Ok(0),
+		}
+	}
+
+	/// Move the bodies and justifications with the given lookup keys to the cold database,
+	/// noting that the blocks before `next` were moved.
+	///
+	/// They are written to the cold database before being removed from the main one, so they
+	/// are in either of them even if the node stops in between.
+	pub(crate) fn move_to_cold(
+		&self,
+		keys: &[Vec<u8>],
+		next: u64,
+	) -> sp_database::error::Result<()> {
+		let mut cold = Transaction::new();
+		let mut hot = Transaction::new();
+		for key in keys {
+			for col in &COLD_COLUMNS {
+				if let Some(value) = self.hot.get(*col, key) {
+					cold.set_from_vec(*col, key, value);
+					hot.remove(*col, key);
+				}
+			}
+		}
+		cold.set_from_vec(columns::META, COLD_BLOCKS, next.encode());
+		self.cold.commit(cold)?;
+		self.hot.commit(hot)
+	}
+
+	/// Returns `true` if the entry of the given column and key was moved to the cold database.
+	pub(crate) fn is_moved(&self, col: ColumnId, key: &[u8]) -> bool {
+		is_cold(col) && !self.hot.contains(col, key) && self.cold.contains(col, key)
+	}
+
+	/// Write the given changes of the entries moved to the cold database.
+	pub(crate) fn commit_cold(
+		&self,
+		transaction: Transaction<DbHash>,
+	) -> sp_database::error::Result<()> {
+		self.cold.commit(transaction)
+	}
+}
+
+impl Database<DbHash> for TieredDatabase {
+	fn commit(&self, transaction: Transaction<DbHash>) -> sp_database::error::Result<()> {
+		let cold = transaction.0.iter().filter_map(|change| match change {
+			Change::Remove(col, key) if is_cold(*col) => Some(Change::Remove(*col, key.clone())),
+			_ => None,
+		}).collect::<Vec<_>>();
+
+		// an entry removed from the main database but not from the cold one, if the node stops
+		// in between, is only left behind, while an entry removed from the cold database of a
+		// transaction that isn't committed would be lost.
+		self.hot.commit(transaction)?;
+		if cold.is_empty() {
+			return Ok(());
+		}
+		self.cold.commit(Transaction(cold))
+	}
+
+	fn get(&self, col: ColumnId, key: &[u8]) -> Option<Vec<u8>> {
+		match self.hot.get(col, key) {
+			None if is_cold(col) => self.cold.get(col, key),
+			value => value,
+		}
+	}
+
+	fn contains(&self, col: ColumnId, key: &[u8]) -> bool {
+		self.hot.contains(col, key) || (is_cold(col) && self.cold.contains(col, key))
+	}
+
+	fn with_get(&self, col: ColumnId, key: &[u8], f: &mut dyn FnMut(&[u8])) {
+		if !is_cold(col) || self.hot.contains(col, key) {
+			self.hot.with_get(col, key, f)
+		} else {
+			self.cold.with_get(col, key, f)
+		}
+	}
+
+	fn lookup(&self, hash: &DbHash) -> Option<Vec<u8>> {
+		self.hot.lookup(hash)
+	}
+
+	fn with_lookup(&self, hash: &DbHash, f: &mut dyn FnMut(&[u8])) {
+		self.hot.with_lookup(hash, f)
+	}
+
+	fn iter(&self, col: ColumnId) -> Option<Box<dyn Iterator<Item = (Vec<u8>, Vec<u8>)> + '_>> {
+		let hot = self.hot.iter(col)?;
+		if !is_cold(col) {
+			return Some(hot);
+		}
+		Some(Box::new(hot.chain(self.cold.iter(col)?)))
+	}
+}
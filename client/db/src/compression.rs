@@ -0,0 +1,99 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Compression of the block bodies and justifications.
+//!
+//! Compressed values are prefixed with `COMPRESSED`, so the values written uncompressed, e.g.
+//! before compression was enabled, keep being read as they are.
+
+/// Prefix of the compressed values.
+///
+/// Bodies and justifications are SCALE encoded vectors: the compact encoding of their length
+/// never starts with this byte.
+const COMPRESSED: u8 = 0xff;
+
+/// The zstd compression level.
+#[cfg(any(feature = "with-zstd", test))]
+const LEVEL: i32 = 3;
+
+fn err(e: impl std::fmt::Display) -> sp_blockchain::Error {
+	sp_blockchain::Error::Backend(format!("Error decompressing block data: {}", e))
+}
+
+/// Returns `true` if the value is compressed.
+pub(crate) fn is_compressed(value: &[u8]) -> bool {
+	value.first() == Some(&COMPRESSED)
+}
+
+/// Compress the value, unless it doesn't get any smaller.
+#[cfg(any(feature = "with-zstd", test))]
+pub(crate) fn compress(value: Vec<u8>) -> Vec<u8> {
+	let mut compressed = vec![COMPRESSED];
+	match zstd::stream::copy_encode(&value[..], &mut compressed, LEVEL) {
+		Ok(()) if compressed.len() < value.len() => compressed,
+		_ => value,
+	}
+}
+
+/// Values are stored uncompressed without zstd support.
+#[cfg(not(any(feature = "with-zstd", test)))]
+pub(crate) fn compress(value: Vec<u8>) -> Vec<u8> {
+	value
+}
+
+/// Decompress the value, if it is compressed.
+pub(crate) fn decompress(value: Vec<u8>) -> sp_blockchain::Result<Vec<u8>> {
+	if !is_compressed(&value) {
+		return Ok(value);
+	}
+	decode(&value[1..])
+}
+
+#[cfg(any(feature = "with-zstd", test))]
+fn decode(compressed: &[u8]) -> sp_blockchain::Result<Vec<u8>> {
+	zstd::stream::decode_all(compressed).map_err(err)
+}
+
+#[cfg(not(any(feature = "with-zstd", test)))]
+fn decode(_compressed: &[u8]) -> sp_blockchain::Result<Vec<u8>> {
+	Err(err("the node is built without zstd support"))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::{Compact, Encode};
+
+	#[test]
+	fn decompresses_compressed_values_only() {
+		let body = vec![vec![7u8; 64]; 16].encode();
+		let compressed = compress(body.clone());
+		assert!(is_compressed(&compressed));
+		assert!(compressed.len() < body.len());
+		assert_eq!(decompress(compressed).unwrap(), body);
+
+		// incompressible values are stored as they are.
+		let empty = Vec::<Vec<u8>>::new().encode();
+		assert_eq!(compress(empty.clone()), empty);
+		assert_eq!(decompress(empty.clone()).unwrap(), empty);
+
+		for len in &[0u32, 63, 64, 16383, 16384, u32::max_value()] {
+			assert!(!is_compressed(&Compact(*len).encode()));
+		}
+	}
+}
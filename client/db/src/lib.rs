@@ -35,6 +35,7 @@ pub mod offchain;
 pub mod bench;
 
 mod children;
+mod child_trie;
 mod cache;
 mod changes_tries_storage;
 mod storage_cache;
@@ -42,19 +43,26 @@ mod storage_cache;
 mod upgrade;
 mod utils;
 mod stats;
+mod migration;
+mod trie_cache;
+mod compression;
+mod cold_storage;
+mod metrics;
+#[cfg(any(feature = "with-kvdb-rocksdb", test))]
+mod snapshot;
 #[cfg(feature = "with-parity-db")]
 mod parity_db;
 
-use std::sync::Arc;
+use std::sync::{Arc, atomic::{AtomicU64, Ordering}};
 use std::path::{Path, PathBuf};
 use std::io;
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, hash_map::Entry};
 use parking_lot::{Mutex, RwLock};
 use linked_hash_map::LinkedHashMap;
 use log::{trace, debug, warn};
 
 use sc_client_api::{
-	UsageInfo, MemoryInfo, IoInfo, MemorySize,
+	UsageInfo, MemoryInfo, IoInfo, MemorySize, AuxKeySpaces,
 	backend::{NewBlockState, PrunableStateChangesTrieStorage, ProvideChtRoots},
 	leaves::{LeafSet, FinalizationDisplaced}, cht,
 };
@@ -79,19 +87,24 @@ use sp_state_machine::{
 	StorageCollection, ChildStorageCollection, OffchainChangesCollection,
 	backend::Backend as StateBackend, StateMachineStats,
 };
-use crate::utils::{DatabaseType, Meta, meta_keys, read_db, read_meta};
+use crate::utils::{Meta, meta_keys, read_db, read_meta};
+use crate::trie_cache::{TrieCacheStats, TrieNodeCache};
 use crate::changes_tries_storage::{DbChangesTrieStorage, DbChangesTrieStorageTransaction};
 use sc_state_db::StateDb;
 use sp_blockchain::{CachedHeaderMetadata, HeaderMetadata, HeaderMetadataCache};
 use crate::storage_cache::{CachingState, SyncingCachingState, SharedCache, new_shared_cache};
 use crate::stats::StateUsageStats;
+use prometheus_endpoint::Registry;
 
 // Re-export the Database trait so that one can pass an implementation of it.
 pub use sp_database::Database;
 pub use sc_state_db::PruningMode;
+pub use crate::utils::DatabaseType;
 
 #[cfg(any(feature = "with-kvdb-rocksdb", test))]
 pub use bench::BenchmarkingState;
+#[cfg(any(feature = "with-kvdb-rocksdb", test))]
+pub use snapshot::{restore_snapshot, snapshot_database, snapshot_settings};
 
 const MIN_BLOCKS_TO_KEEP_CHANGES_TRIES_FOR: u32 = 32768;
 const CACHE_HEADERS: usize = 8;
@@ -148,7 +161,18 @@ impl<B: BlockT> StateBackend<HashFor<B>> for RefTrackingState<B> {
 	type TrieBackendStorage = <DbState<B> as StateBackend<HashFor<B>>>::TrieBackendStorage;
 
 	fn storage(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
-		self.state.storage(key)
+		let cache = match self.storage.trie_cache.as_ref() {
+			Some(cache) => cache,
+			None => return self.state.storage(key),
+		};
+		// the state at a root never changes, so the values read at it stay valid.
+		let root = *self.state.root();
+		if let Some(value) = cache.get_value(&root, key) {
+			return Ok(value);
+		}
+		let value = self.state.storage(key)?;
+		cache.insert_value(root, key.to_vec(), value.clone());
+		Ok(value)
 	}
 
 	fn storage_hash(&self, key: &[u8]) -> Result<Option<B::Hash>, Self::Error> {
@@ -264,6 +288,8 @@ pub struct DatabaseSettings {
 	pub state_cache_size: usize,
 	/// Ratio of cache size dedicated to child tries.
 	pub state_cache_child_ratio: Option<(usize, usize)>,
+	/// Size of the trie node cache shared by all the blocks, in bytes. `0` disables it.
+	pub trie_cache_size: usize,
 	/// State pruning mode.
 	pub state_pruning: PruningMode,
 	/// Where to find the database.
@@ -272,6 +298,29 @@ pub struct DatabaseSettings {
 	pub keep_blocks: KeepBlocks,
 	/// Block body/Transaction storage scheme.
 	pub transaction_storage: TransactionStorageMode,
+	/// Maintain an index of the blocks the extrinsics of the best chain are included in.
+	pub index_extrinsics: bool,
+	/// Store the block bodies and justifications compressed.
+	///
+	/// They are read whether they are compressed or not.
+	pub compress_blocks: bool,
+	/// Save the keys of the cached trie nodes when the backend is dropped and read the nodes
+	/// into the cache again when the database is opened.
+	pub trie_cache_warm_up: bool,
+	/// Move the bodies and justifications of the ancient blocks to a second database.
+	pub cold_storage: Option<ColdStorageSettings>,
+	/// Adapt the canonicalization delay to the number of blocks not finalized yet, up to the
+	/// given number of blocks, so the state of the forks is kept while the finality lags.
+	pub max_canonicalization_delay: Option<u64>,
+}
+
+/// Settings of the database the bodies and justifications of the ancient blocks are moved to.
+#[derive(Debug, Clone)]
+pub struct ColdStorageSettings {
+	/// Where to find the cold database.
+	pub source: DatabaseSettingsSrc,
+	/// Number of finalized blocks whose bodies and justifications stay in the main database.
+	pub hot_blocks: u32,
 }
 
 /// Block pruning settings.
@@ -308,6 +357,9 @@ pub enum DatabaseSettingsSrc {
 	ParityDb {
 		/// Path to the database.
 		path: PathBuf,
+		/// Path to a RocksDB database to migrate to ParityDb, if the ParityDb database
+		/// doesn't exist yet.
+		migrate_from: Option<PathBuf>,
 	},
 
 	/// Use a custom already-open database.
@@ -343,6 +395,103 @@ impl std::fmt::Display for DatabaseSettingsSrc {
 	}
 }
 
+/// Open the database and apply its pending upgrades and schema migrations, without opening
+/// a backend on top of it.
+pub fn migrate_database<Block: BlockT>(
+	settings: &DatabaseSettings,
+	db_type: DatabaseType,
+) -> ClientResult<()> {
+	utils::open_database::<Block>(settings, db_type).map(|_| ())
+}
+
+/// A part of the database of a full node, see `purge_database`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatabasePart {
+	/// The state of the blocks.
+	State,
+	/// The bodies, justifications and indexed transactions of the blocks, but their headers.
+	Blocks,
+}
+
+/// Number of entries removed at once by `purge_database`.
+const PURGE_BATCH: usize = 10_000;
+
+/// Remove a part of the database of a full node, keeping the rest of it.
+///
+/// Without their bodies, the blocks are left as if they were pruned. Without the state, the
+/// blocks can be exported but not imported on top of.
+pub fn purge_database<Block: BlockT>(
+	settings: &DatabaseSettings,
+	part: DatabasePart,
+) -> ClientResult<()> {
+	let db = utils::open_database::<Block>(settings, DatabaseType::Full)?;
+	match part {
+		DatabasePart::State =>
+			purge_columns(&*db, &[columns::STATE, columns::STATE_META, columns::CHANGES_TRIE]),
+		DatabasePart::Blocks => {
+			let cols = [
+				columns::BODY,
+				columns::JUSTIFICATION,
+				columns::TRANSACTION,
+				columns::EXTRINSIC_INDEX,
+			];
+			purge_columns(&*db, &cols)?;
+			match settings.cold_storage {
+				Some(ref cold) => purge_columns(
+					&*cold_storage::open(&cold.source)?,
+					&[columns::BODY, columns::JUSTIFICATION],
+				),
+				None => Ok(()),
+			}
+		},
+	}
+}
+
+fn purge_columns(db: &dyn Database<DbHash>, cols: &[u32]) -> ClientResult<()> {
+	for &col in cols {
+		let entries = db.iter(col).ok_or_else(|| sp_blockchain::Error::Backend(
+			"The entries of the database can't be iterated over".into(),
+		))?;
+		let mut removed = 0;
+		let mut transaction = Transaction::new();
+		for (key, _) in entries {
+			transaction.remove(col, &key);
+			removed += 1;
+			if removed % PURGE_BATCH == 0 {
+				db.commit(std::mem::replace(&mut transaction, Transaction::new()))?;
+			}
+		}
+		db.commit(transaction)?;
+		debug!(target: "db", "Removed {} entries of column {}", removed, col);
+	}
+	Ok(())
+}
+
+/// Snapshots are RocksDB databases, they can't be taken without RocksDB support.
+#[cfg(not(any(feature = "with-kvdb-rocksdb", test)))]
+pub fn snapshot_database(_settings: &DatabaseSettings, _path: &Path) -> ClientResult<()> {
+	Err(sp_blockchain::Error::Backend("Snapshots require RocksDb support".into()))
+}
+
+/// Snapshots are RocksDB databases, they can't be opened without RocksDB support.
+#[cfg(not(any(feature = "with-kvdb-rocksdb", test)))]
+pub fn snapshot_settings(
+	_settings: DatabaseSettings,
+	_snapshot: &Path,
+) -> ClientResult<DatabaseSettings> {
+	Err(sp_blockchain::Error::Backend("Snapshots require RocksDb support".into()))
+}
+
+/// Snapshots are RocksDB databases, they can't be restored without RocksDB support.
+#[cfg(not(any(feature = "with-kvdb-rocksdb", test)))]
+pub fn restore_snapshot<Block: BlockT>(
+	_snapshot: &std::path::Path,
+	_settings: &DatabaseSettings,
+	_genesis_hash: Block::Hash,
+) -> ClientResult<()> {
+	Err(sp_blockchain::Error::Backend("Snapshots require RocksDb support".into()))
+}
+
 pub(crate) mod columns {
 	pub const META: u32 = crate::utils::COLUMN_META;
 	pub const STATE: u32 = 1;
@@ -359,6 +508,40 @@ pub(crate) mod columns {
 	pub const CACHE: u32 = 10;
 	/// Transactions
 	pub const TRANSACTION: u32 = 11;
+	/// maps extrinsic hashes to the hash of their block and their index in it.
+	pub const EXTRINSIC_INDEX: u32 = 12;
+}
+
+/// Number of blocks behind the best block the blocks not finalized yet are canonicalized at.
+struct CanonicalizationDelay {
+	min: u64,
+	max: u64,
+	current: AtomicU64,
+}
+
+impl CanonicalizationDelay {
+	fn new(min: u64, max: Option<u64>) -> Self {
+		CanonicalizationDelay {
+			min,
+			max: max.map_or(min, |max| std::cmp::max(min, max)),
+			current: AtomicU64::new(min),
+		}
+	}
+
+	/// Adapt the delay to the number of imported blocks not finalized yet, and return it.
+	///
+	/// The delay is twice the finality lag, within the bounds. It grows at once, but shrinks
+	/// by one block at a time, so no more than two blocks are canonicalized per import.
+	fn adapt(&self, finality_lag: u64) -> u64 {
+		let target = finality_lag.saturating_mul(2).max(self.min).min(self.max);
+		let current = self.current.load(Ordering::Relaxed);
+		let delay = if target >= current { target } else { current - 1 };
+		if delay != current {
+			self.current.store(delay, Ordering::Relaxed);
+			trace!(target: "db", "Canonicalization delay: {} blocks", delay);
+		}
+		delay
+	}
 }
 
 struct PendingBlock<Block: BlockT> {
@@ -507,6 +690,7 @@ impl<Block: BlockT> sc_client_api::blockchain::Backend<Block> for BlockchainDb<B
 	fn body(&self, id: BlockId<Block>) -> ClientResult<Option<Vec<Block::Extrinsic>>> {
 		match read_db(&*self.db, columns::KEY_LOOKUP, columns::BODY, id)? {
 			Some(body) => {
+				let body = compression::decompress(body)?;
 				match self.transaction_storage {
 					TransactionStorageMode::BlockBody => match Decode::decode(&mut &body[..]) {
 						Ok(body) => Ok(Some(body)),
@@ -537,12 +721,15 @@ impl<Block: BlockT> sc_client_api::blockchain::Backend<Block> for BlockchainDb<B
 
 	fn justification(&self, id: BlockId<Block>) -> ClientResult<Option<Justification>> {
 		match read_db(&*self.db, columns::KEY_LOOKUP, columns::JUSTIFICATION, id)? {
-			Some(justification) => match Decode::decode(&mut &justification[..]) {
-				Ok(justification) => Ok(Some(justification)),
-				Err(err) => return Err(sp_blockchain::Error::Backend(
-					format!("Error decoding justification: {}", err)
-				)),
-			}
+			Some(justification) => {
+				let justification = compression::decompress(justification)?;
+				match Decode::decode(&mut &justification[..]) {
+					Ok(justification) => Ok(Some(justification)),
+					Err(err) => return Err(sp_blockchain::Error::Backend(
+						format!("Error decoding justification: {}", err)
+					)),
+				}
+			},
 			None => Ok(None),
 		}
 	}
@@ -580,6 +767,22 @@ impl<Block: BlockT> sc_client_api::blockchain::Backend<Block> for BlockchainDb<B
 	fn have_extrinsic(&self, hash: &Block::Hash) -> ClientResult<bool> {
 		Ok(self.db.contains(columns::TRANSACTION, hash.as_ref()))
 	}
+
+	fn extrinsic_location(&self, hash: &Block::Hash) -> ClientResult<Option<(Block::Hash, u32)>> {
+		let location = match self.db.get(columns::EXTRINSIC_INDEX, hash.as_ref()) {
+			Some(location) => location,
+			None => return Ok(None),
+		};
+		let (block_hash, index) = <(Block::Hash, u32)>::decode(&mut &location[..])
+			.map_err(|err| sp_blockchain::Error::Backend(
+				format!("Error decoding location of extrinsic {}: {}", hash, err)
+			))?;
+		// the entries of the blocks retracted from the best chain are left in the index.
+		match self.number(block_hash)? {
+			Some(number) if self.hash(number)? == Some(block_hash) => Ok(Some((block_hash, index))),
+			_ => Ok(None),
+		}
+	}
 }
 
 impl<Block: BlockT> sc_client_api::blockchain::ProvideCache<Block> for BlockchainDb<Block> {
@@ -828,17 +1031,76 @@ struct StorageDb<Block: BlockT> {
 	pub db: Arc<dyn Database<DbHash>>,
 	pub state_db: StateDb<Block::Hash, Vec<u8>>,
 	prefix_keys: bool,
+	trie_cache: Option<TrieNodeCache<Block::Hash>>,
 }
 
 impl<Block: BlockT> sp_state_machine::Storage<HashFor<Block>> for StorageDb<Block> {
 	fn get(&self, key: &Block::Hash, prefix: Prefix) -> Result<Option<DBValue>, String> {
-		if self.prefix_keys {
-			let key = prefixed_key::<HashFor<Block>>(key, prefix);
-			self.state_db.get(&key, self)
+		if let Some(node) = self.trie_cache.as_ref().and_then(|cache| cache.get(key)) {
+			return Ok(Some(node));
+		}
+		let db_key;
+		let (node, prefix) = if self.prefix_keys {
+			db_key = prefixed_key::<HashFor<Block>>(key, prefix);
+			let node = self.state_db.get(&db_key, self);
+			(node, &db_key[..db_key.len() - key.as_ref().len()])
 		} else {
-			self.state_db.get(key.as_ref(), self)
+			(self.state_db.get(key.as_ref(), self), &[][..])
+		};
+		let node = node.map_err(|e| format!("Database backend error: {:?}", e))?;
+		if let (Some(cache), Some(node)) = (self.trie_cache.as_ref(), node.as_ref()) {
+			cache.insert(*key, prefix.to_vec(), node.clone());
+		}
+		Ok(node)
+	}
+}
+
+impl<Block: BlockT> StorageDb<Block> {
+	/// Read the trie nodes whose keys were saved by `save_trie_cache` into the cache.
+	///
+	/// Returns the number of nodes read, the nodes pruned since are skipped.
+	fn warm_up_trie_cache(&self) -> ClientResult<usize> {
+		let cache = match self.trie_cache.as_ref() {
+			Some(cache) => cache,
+			None => return Ok(0),
+		};
+		let keys = match self.db.get(columns::META, meta_keys::TRIE_CACHE_KEYS) {
+			Some(keys) => Vec::<Vec<u8>>::decode(&mut &keys[..]).map_err(|err| {
+				let msg = format!("Error decoding the trie cache keys: {}", err);
+				sp_blockchain::Error::Backend(msg)
+			})?,
+			None => return Ok(0),
+		};
+
+		let mut read = 0;
+		for key in keys {
+			let mut hash = Block::Hash::default();
+			let len = hash.as_ref().len();
+			if key.len() < len {
+				continue;
+			}
+			let node = self.state_db.get(&key[..], self)
+				.map_err(sp_blockchain::Error::from_state_db)?;
+			if let Some(node) = node {
+				hash.as_mut().copy_from_slice(&key[key.len() - len..]);
+				cache.insert(hash, key[..key.len() - len].to_vec(), node);
+				read += 1;
+			}
 		}
-		.map_err(|e| format!("Database backend error: {:?}", e))
+		Ok(read)
+	}
+
+	/// Save the keys of the cached trie nodes, the most recently used last, so they are read
+	/// into the cache again when the database is reopened.
+	fn save_trie_cache(&self) -> ClientResult<()> {
+		let keys = match self.trie_cache.as_ref() {
+			Some(cache) => cache.keys(),
+			None => return Ok(()),
+		};
+		let mut transaction = Transaction::new();
+		transaction.set_from_vec(columns::META, meta_keys::TRIE_CACHE_KEYS, keys.encode());
+		self.db.commit(transaction)?;
+		Ok(())
 	}
 }
 
@@ -916,14 +1178,27 @@ pub struct Backend<Block: BlockT> {
 	offchain_storage: offchain::LocalStorage,
 	changes_tries_storage: DbChangesTrieStorage<Block>,
 	blockchain: BlockchainDb<Block>,
-	canonicalization_delay: u64,
+	canonicalization_delay: CanonicalizationDelay,
 	shared_cache: SharedCache<Block>,
 	import_lock: Arc<RwLock<()>>,
 	is_archive: bool,
 	keep_blocks: KeepBlocks,
 	transaction_storage: TransactionStorageMode,
-	io_stats: FrozenForDuration<(kvdb::IoStats, StateUsageInfo)>,
+	index_extrinsics: bool,
+	compress_blocks: bool,
+	trie_cache_warm_up: bool,
+	io_stats: FrozenForDuration<(kvdb::IoStats, StateUsageInfo, TrieCacheStats)>,
 	state_usage: Arc<StateUsageStats>,
+	aux_key_spaces: AuxKeySpaces,
+	/// The number of times the blocks were pinned, see `pin_block`.
+	pinned_blocks: Mutex<HashMap<Block::Hash, u32>>,
+	cold_storage: Option<Arc<cold_storage::TieredDatabase>>,
+	/// The path of the database if it is a RocksDB database, the snapshots are taken from.
+	#[cfg(any(feature = "with-kvdb-rocksdb", test))]
+	rocksdb_path: Option<PathBuf>,
+	/// Where to find the cold database, if any, copied along in the snapshots.
+	#[cfg(any(feature = "with-kvdb-rocksdb", test))]
+	cold_source: Option<DatabaseSettingsSrc>,
 }
 
 impl<Block: BlockT> Backend<Block> {
@@ -931,8 +1206,43 @@ impl<Block: BlockT> Backend<Block> {
 	///
 	/// The pruning window is how old a block must be before the state is pruned.
 	pub fn new(config: DatabaseSettings, canonicalization_delay: u64) -> ClientResult<Self> {
+		Self::new_with_registry(config, canonicalization_delay, None)
+	}
+
+	/// Create a new instance of database backend, registering the metrics of the database
+	/// in the given registry.
+	pub fn new_with_registry(
+		config: DatabaseSettings,
+		canonicalization_delay: u64,
+		registry: Option<&Registry>,
+	) -> ClientResult<Self> {
 		let db = crate::utils::open_database::<Block>(&config, DatabaseType::Full)?;
-		Self::from_database(db as Arc<_>, canonicalization_delay, &config)
+		let cold_storage = match config.cold_storage {
+			Some(ref cold) => Some(Arc::new(cold_storage::TieredDatabase::new(
+				db.clone(),
+				cold_storage::open(&cold.source)?,
+				cold.hot_blocks,
+			))),
+			None => None,
+		};
+		let db: Arc<dyn Database<DbHash>> = match cold_storage {
+			Some(ref cold_storage) => cold_storage.clone(),
+			None => db,
+		};
+		let metrics = registry
+			.map(|registry| metrics::DatabaseMetrics::register(registry, config.source.path()))
+			.transpose()
+			.map_err(|e| sp_blockchain::Error::Backend(
+				format!("Failed to register the database metrics: {}", e)
+			))?;
+		let db: Arc<dyn Database<DbHash>> = match metrics {
+			Some(metrics) => Arc::new(metrics::MeteredDatabase::new(db, metrics)),
+			None => db,
+		};
+
+		let mut backend = Self::from_database(db, canonicalization_delay, &config)?;
+		backend.cold_storage = cold_storage;
+		Ok(backend)
 	}
 
 	/// Create new memory-backed client backend for tests.
@@ -957,10 +1267,16 @@ impl<Block: BlockT> Backend<Block> {
 		let db_setting = DatabaseSettings {
 			state_cache_size: 16777216,
 			state_cache_child_ratio: Some((50, 100)),
+			trie_cache_size: 16777216,
 			state_pruning: PruningMode::keep_blocks(keep_blocks),
 			source: DatabaseSettingsSrc::Custom(db),
 			keep_blocks: KeepBlocks::Some(keep_blocks),
 			transaction_storage,
+			index_extrinsics: true,
+			compress_blocks: true,
+			trie_cache_warm_up: false,
+			cold_storage: None,
+			max_canonicalization_delay: None,
 		};
 
 		Self::new(db_setting, canonicalization_delay).expect("failed to create test-db")
@@ -984,7 +1300,18 @@ impl<Block: BlockT> Backend<Block> {
 			db: db.clone(),
 			state_db,
 			prefix_keys: !config.source.supports_ref_counting(),
+			trie_cache: if config.trie_cache_size > 0 {
+				Some(TrieNodeCache::new(config.trie_cache_size))
+			} else {
+				None
+			},
 		};
+		if config.trie_cache_warm_up {
+			let start = std::time::Instant::now();
+			let read = storage_db.warm_up_trie_cache()?;
+			let elapsed = start.elapsed();
+			debug!(target: "db", "Read {} trie nodes into the cache in {:?}", read, elapsed);
+		}
 		let offchain_storage = offchain::LocalStorage::new(db.clone());
 		let changes_tries_storage = DbChangesTrieStorage::new(
 			db,
@@ -1007,7 +1334,10 @@ impl<Block: BlockT> Backend<Block> {
 			offchain_storage,
 			changes_tries_storage,
 			blockchain,
-			canonicalization_delay,
+			canonicalization_delay: CanonicalizationDelay::new(
+				canonicalization_delay,
+				config.max_canonicalization_delay,
+			),
 			shared_cache: new_shared_cache(
 				config.state_cache_size,
 				config.state_cache_child_ratio.unwrap_or(DEFAULT_CHILD_RATIO),
@@ -1018,9 +1348,146 @@ impl<Block: BlockT> Backend<Block> {
 			state_usage: Arc::new(StateUsageStats::new()),
 			keep_blocks: config.keep_blocks.clone(),
 			transaction_storage: config.transaction_storage.clone(),
+			index_extrinsics: config.index_extrinsics,
+			compress_blocks: config.compress_blocks,
+			trie_cache_warm_up: config.trie_cache_warm_up,
+			aux_key_spaces: Default::default(),
+			pinned_blocks: Default::default(),
+			cold_storage: None,
+			#[cfg(any(feature = "with-kvdb-rocksdb", test))]
+			rocksdb_path: match &config.source {
+				DatabaseSettingsSrc::RocksDb { path, .. } => Some(path.clone()),
+				_ => None,
+			},
+			#[cfg(any(feature = "with-kvdb-rocksdb", test))]
+			cold_source: config.cold_storage.as_ref().map(|cold| cold.source.clone()),
 		})
 	}
 
+	/// Compress the value of a block body or justification, if they are stored compressed.
+	fn block_data(&self, value: Vec<u8>) -> Vec<u8> {
+		if self.compress_blocks {
+			compression::compress(value)
+		} else {
+			value
+		}
+	}
+
+	/// Compress the bodies and justifications of up to `max_blocks` finalized blocks stored
+	/// uncompressed, e.g. before compression was enabled.
+	///
+	/// The bodies and justifications moved to the cold database are compressed there. The
+	/// progress is saved, so the compression resumes where it stopped. Returns `false` once
+	/// all the finalized blocks are compressed.
+	pub fn compress_stored_blocks(&self, max_blocks: u32) -> ClientResult<bool> {
+		let db = &*self.storage.db;
+		let next = match db.get(columns::META, meta_keys::COMPRESSED_BLOCKS) {
+			Some(next) => u64::decode(&mut &next[..]).map_err(|err| sp_blockchain::Error::Backend(
+				format!("Error decoding the compression progress: {}", err)
+			))?,
+			None => 0,
+		};
+
+		// keeps blocks from being imported, finalized or pruned while they are compressed.
+		let _lock = self.import_lock.write();
+		let finalized = self.blockchain.meta.read().finalized_number.saturated_into::<u64>();
+		if next > finalized {
+			return Ok(false);
+		}
+		let end = std::cmp::min(next + max_blocks as u64, finalized + 1);
+
+		let mut transaction = Transaction::new();
+		let mut cold_transaction = Transaction::new();
+		for number in next..end {
+			let id = BlockId::<Block>::number(number.saturated_into());
+			let key = match utils::block_id_to_lookup_key(db, columns::KEY_LOOKUP, id)? {
+				Some(key) => key,
+				None => continue,
+			};
+			for column in &[columns::BODY, columns::JUSTIFICATION] {
+				if let Some(value) = db.get(*column, &key) {
+					if compression::is_compressed(&value) {
+						continue;
+					}
+					let compressed = compression::compress(value);
+					if !compression::is_compressed(&compressed) {
+						continue;
+					}
+					match self.cold_storage {
+						Some(ref cold_storage) if cold_storage.is_moved(*column, &key) =>
+							cold_transaction.set_from_vec(*column, &key, compressed),
+						_ => transaction.set_from_vec(*column, &key, compressed),
+					}
+				}
+			}
+		}
+		// the compressed values are written before the progress, so they are only
+		// compressed again if the node stops in between.
+		if let Some(ref cold_storage) = self.cold_storage {
+			if !cold_transaction.0.is_empty() {
+				cold_storage.commit_cold(cold_transaction)?;
+			}
+		}
+		transaction.set_from_vec(columns::META, meta_keys::COMPRESSED_BLOCKS, end.encode());
+		db.commit(transaction)?;
+		Ok(end <= finalized)
+	}
+
+	/// Move the bodies and justifications of up to `max_blocks` finalized blocks, older than the
+	/// blocks kept in the main database, to the cold database.
+	///
+	/// The progress is saved, so the blocks are moved from where it stopped. Returns `false` once
+	/// all the blocks old enough are moved, or if there is no cold database.
+	pub fn migrate_cold_blocks(&self, max_blocks: u32) -> ClientResult<bool> {
+		let cold_storage = match self.cold_storage {
+			Some(ref cold_storage) => cold_storage,
+			None => return Ok(false),
+		};
+		let next = cold_storage.next_block().map_err(|err| sp_blockchain::Error::Backend(
+			format!("Error decoding the cold storage progress: {}", err)
+		))?;
+
+		// keeps blocks from being imported, finalized or pruned while they are moved.
+		let _lock = self.import_lock.write();
+		let finalized = self.blockchain.meta.read().finalized_number.saturated_into::<u64>();
+		let last = match finalized.checked_sub(cold_storage.hot_blocks() as u64) {
+			Some(last) if next <= last => last,
+			_ => return Ok(false),
+		};
+		let end = std::cmp::min(next + max_blocks as u64, last + 1);
+
+		let db = &*self.storage.db;
+		let mut keys = Vec::new();
+		for number in next..end {
+			let id = BlockId::<Block>::number(number.saturated_into());
+			if let Some(key) = utils::block_id_to_lookup_key(db, columns::KEY_LOOKUP, id)? {
+				keys.push(key);
+			}
+		}
+		cold_storage.move_to_cold(&keys, end)?;
+		Ok(end <= last)
+	}
+
+	/// Note the location of the extrinsics of the given block in the index, if it is maintained.
+	fn index_extrinsics(
+		&self,
+		transaction: &mut Transaction<DbHash>,
+		hash: Block::Hash,
+		body: &[Block::Extrinsic],
+	) {
+		if !self.index_extrinsics {
+			return;
+		}
+		for (index, extrinsic) in body.iter().enumerate() {
+			let extrinsic_hash = HashFor::<Block>::hash(&extrinsic.encode());
+			transaction.set_from_vec(
+				columns::EXTRINSIC_INDEX,
+				extrinsic_hash.as_ref(),
+				(hash, index as u32).encode(),
+			);
+		}
+	}
+
 	/// Handle setting head within a transaction. `route_to` should be the last
 	/// block that existed in the database. `best_to` should be the best block
 	/// to be set.
@@ -1076,6 +1543,15 @@ impl<Block: BlockT> Backend<Block> {
 					e.number,
 					e.hash
 				)?;
+				if self.index_extrinsics {
+					let body = sc_client_api::blockchain::Backend::body(
+						&self.blockchain,
+						BlockId::Hash(e.hash),
+					)?;
+					if let Some(body) = body {
+						self.index_extrinsics(transaction, e.hash, &body);
+					}
+				}
 			}
 		}
 
@@ -1132,7 +1608,7 @@ impl<Block: BlockT> Backend<Block> {
 			transaction.set_from_vec(
 				columns::JUSTIFICATION,
 				&utils::number_and_hash_to_lookup_key(number, hash)?,
-				justification.encode(),
+				self.block_data(justification.encode()),
 			);
 		}
 		Ok((*hash, number, false, true))
@@ -1148,25 +1624,34 @@ impl<Block: BlockT> Backend<Block> {
 		-> ClientResult<()>
 	{
 		let number_u64 = number.saturated_into::<u64>();
-		if number_u64 > self.canonicalization_delay {
-			let new_canonical = number_u64 - self.canonicalization_delay;
-
-			if new_canonical <= self.storage.state_db.best_canonical().unwrap_or(0) {
-				return Ok(())
-			}
-
-			let hash = if new_canonical == number_u64 {
-				hash
-			} else {
-				::sc_client_api::blockchain::HeaderBackend::hash(&self.blockchain, new_canonical.saturated_into())?
-					.expect("existence of block with number `new_canonical` \
-						implies existence of blocks with all numbers before it; qed")
+		let finalized = self.blockchain.meta.read().finalized_number.saturated_into::<u64>();
+		let delay = self.canonicalization_delay.adapt(number_u64.saturating_sub(finalized));
+		if number_u64 > delay {
+			let new_canonical = number_u64 - delay;
+
+			let first = match self.storage.state_db.best_canonical() {
+				Some(best_canonical) if new_canonical <= best_canonical => return Ok(()),
+				Some(best_canonical) => best_canonical + 1,
+				None => new_canonical,
 			};
 
-			trace!(target: "db", "Canonicalize block #{} ({:?})", new_canonical, hash);
-			let commit = self.storage.state_db.canonicalize_block(&hash)
-				.map_err(|e: sc_state_db::Error<io::Error>| sp_blockchain::Error::from_state_db(e))?;
-			apply_state_commit(transaction, commit);
+			// more than one block is canonicalized once the delay shrinks.
+			for canonical in first..=new_canonical {
+				let hash = if canonical == number_u64 {
+					hash
+				} else {
+					let number = canonical.saturated_into();
+					::sc_client_api::blockchain::HeaderBackend::hash(&self.blockchain, number)?
+						.expect("existence of block with number `canonical` \
+							implies existence of blocks with all numbers before it; qed")
+				};
+
+				trace!(target: "db", "Canonicalize block #{} ({:?})", canonical, hash);
+				let commit = self.storage.state_db.canonicalize_block(&hash).map_err(
+					|e: sc_state_db::Error<io::Error>| sp_blockchain::Error::from_state_db(e)
+				)?;
+				apply_state_commit(transaction, commit);
+			}
 		};
 
 		Ok(())
@@ -1227,7 +1712,8 @@ impl<Block: BlockT> Backend<Block> {
 			if let Some(body) = &pending_block.body {
 				match self.transaction_storage {
 					TransactionStorageMode::BlockBody => {
-						transaction.set_from_vec(columns::BODY, &lookup_key, body.encode());
+						let body = self.block_data(body.encode());
+						transaction.set_from_vec(columns::BODY, &lookup_key, body);
 					},
 					TransactionStorageMode::StorageChain => {
 						let mut hashes = Vec::with_capacity(body.len());
@@ -1237,12 +1723,17 @@ impl<Block: BlockT> Backend<Block> {
 							transaction.set(columns::TRANSACTION, &hash.as_ref(), &extrinsic);
 							hashes.push(hash);
 						}
-						transaction.set_from_vec(columns::BODY, &lookup_key, hashes.encode());
+						let hashes = self.block_data(hashes.encode());
+						transaction.set_from_vec(columns::BODY, &lookup_key, hashes);
 					},
 				}
+				if pending_block.leaf_state.is_best() {
+					self.index_extrinsics(&mut transaction, hash, body);
+				}
 			}
 			if let Some(justification) = pending_block.justification {
-				transaction.set_from_vec(columns::JUSTIFICATION, &lookup_key, justification.encode());
+				let justification = self.block_data(justification.encode());
+				transaction.set_from_vec(columns::JUSTIFICATION, &lookup_key, justification);
 			}
 
 			if number.is_zero() {
@@ -1289,6 +1780,17 @@ impl<Block: BlockT> Backend<Block> {
 						}
 					}
 				}
+				if self.storage.prefix_keys && !self.is_archive {
+					let orphaned = child_trie::orphaned_nodes(
+						&*self.storage as &dyn sp_state_machine::Storage<HashFor<Block>>,
+						&operation.old_state,
+						&operation.child_storage_updates,
+						&changeset.deleted,
+					);
+					removal += orphaned.len() as u64;
+					bytes_removal += orphaned.iter().map(|key| key.len() as u64).sum::<u64>();
+					changeset.deleted.extend(orphaned);
+				}
 				self.state_usage.tally_writes_nodes(ops, bytes);
 				self.state_usage.tally_removed_nodes(removal, bytes_removal);
 
@@ -1512,37 +2014,166 @@ impl<Block: BlockT> Backend<Block> {
 				return Ok(())
 			}
 			let number = finalized.saturating_sub(keep.into());
-			match read_db(&*self.storage.db, columns::KEY_LOOKUP, columns::BODY, BlockId::<Block>::number(number))? {
-				Some(body) => {
-					debug!(target: "db", "Removing block #{}", number);
-					utils::remove_from_db(
-						transaction,
-						&*self.storage.db,
-						columns::KEY_LOOKUP,
-						columns::BODY,
-						BlockId::<Block>::number(number),
-					)?;
-					match self.transaction_storage {
-						TransactionStorageMode::BlockBody => {},
-						TransactionStorageMode::StorageChain => {
-							match Vec::<Block::Hash>::decode(&mut &body[..]) {
-								Ok(hashes) => {
-									for h in hashes {
-										transaction.remove(columns::TRANSACTION, h.as_ref());
-									}
-								}
+			if let Some(hash) = self.blockchain.hash(number)? {
+				if self.pinned_blocks.lock().contains_key(&hash) {
+					debug!(target: "db", "Keeping pinned block #{}", number);
+					return Ok(())
+				}
+			}
+			self.prune_block_body(transaction, number)?;
+		}
+		Ok(())
+	}
+
+	/// Remove the body of the canonical block with the given number, if it's still stored.
+	fn prune_block_body(
+		&self,
+		transaction: &mut Transaction<DbHash>,
+		number: NumberFor<Block>,
+	) -> ClientResult<()> {
+		let id = BlockId::<Block>::number(number);
+		match read_db(&*self.storage.db, columns::KEY_LOOKUP, columns::BODY, id.clone())? {
+			Some(body) => {
+				debug!(target: "db", "Removing block #{}", number);
+				let body = compression::decompress(body)?;
+				utils::remove_from_db(
+					transaction,
+					&*self.storage.db,
+					columns::KEY_LOOKUP,
+					columns::BODY,
+					id,
+				)?;
+				match self.transaction_storage {
+					TransactionStorageMode::BlockBody => {
+						if self.index_extrinsics {
+							match Vec::<Block::Extrinsic>::decode(&mut &body[..]) {
+								Ok(extrinsics) => for extrinsic in extrinsics {
+									let h = HashFor::<Block>::hash(&extrinsic.encode());
+									transaction.remove(columns::EXTRINSIC_INDEX, h.as_ref());
+								},
 								Err(err) => return Err(sp_blockchain::Error::Backend(
-									format!("Error decoding body list: {}", err)
+									format!("Error decoding body: {}", err)
 								)),
 							}
 						}
+					},
+					TransactionStorageMode::StorageChain => {
+						match Vec::<Block::Hash>::decode(&mut &body[..]) {
+							Ok(hashes) => {
+								for h in hashes {
+									transaction.remove(columns::TRANSACTION, h.as_ref());
+									if self.index_extrinsics {
+										let key = h.as_ref();
+										transaction.remove(columns::EXTRINSIC_INDEX, key);
+									}
+								}
+							}
+							Err(err) => return Err(sp_blockchain::Error::Backend(
+								format!("Error decoding body list: {}", err)
+							)),
+						}
 					}
 				}
-				None => return Ok(()),
 			}
+			None => return Ok(()),
 		}
 		Ok(())
 	}
+
+	/// Remove the body of a block that was kept while it was pinned, if it's out of the
+	/// blocks to keep by now.
+	fn prune_unpinned_body(&self, hash: &Block::Hash) -> ClientResult<()> {
+		let keep = match self.keep_blocks {
+			KeepBlocks::Some(keep_blocks) => std::cmp::max(keep_blocks, 1),
+			KeepBlocks::All => return Ok(()),
+		};
+		let number = match self.blockchain.number(*hash)? {
+			Some(number) => number,
+			None => return Ok(()),
+		};
+		let finalized = self.blockchain.meta.read().finalized_number;
+		if finalized < keep.into() || number > finalized.saturating_sub(keep.into()) {
+			return Ok(())
+		}
+		// only the bodies of the canonical blocks are pruned.
+		if self.blockchain.hash(number)? != Some(*hash) {
+			return Ok(())
+		}
+
+		let mut transaction = Transaction::new();
+		self.prune_block_body(&mut transaction, number)?;
+		self.storage.db.commit(transaction)?;
+		Ok(())
+	}
+
+	/// Seed the empty database with the given state, as the genesis state of a new chain, and
+	/// commit the genesis block `genesis_header` builds from the root of the state.
+	///
+	/// The state is imported chunk by chunk: the trie nodes of each chunk are written before the
+	/// next chunk is read, so only one chunk is held in memory. Returns the hash of the genesis
+	/// block.
+	pub fn import_genesis_state(
+		&self,
+		chunks: impl IntoIterator<Item = ClientResult<Storage>>,
+		genesis_header: impl FnOnce(Block::Hash) -> Block::Header,
+	) -> ClientResult<Block::Hash> {
+		use sc_client_api::backend::{Backend as _, BlockImportOperation as _};
+
+		if self.blockchain.header(BlockId::Number(Zero::zero()))?.is_some() {
+			return Err(sp_blockchain::Error::Backend("The database is not empty".into()));
+		}
+
+		let mut root = DbGenesisStorage::<Block>::new().0;
+		let mut changes_trie_config: Option<ChangesTrieConfiguration> = None;
+		for chunk in chunks {
+			let chunk = chunk?;
+			if chunk.top.keys().any(|k| well_known_keys::is_child_storage_key(&k)) {
+				return Err(sp_blockchain::Error::GenesisInvalid.into());
+			}
+			if let Some(config) = chunk.top.get(well_known_keys::CHANGES_TRIE_CONFIG) {
+				changes_trie_config = Some(Decode::decode(&mut &config[..]).map_err(|e| {
+					sp_blockchain::Error::Backend(
+						format!("Invalid changes trie configuration: {}", e),
+					)
+				})?);
+			}
+
+			let state = DbState::<Block>::new(self.storage.clone(), root);
+			let (chunk_root, mut updates) = state.full_storage_root(
+				chunk.top.iter().map(|(k, v)| (&k[..], Some(&v[..]))),
+				chunk.children_default.values().map(|child| (
+					&child.child_info,
+					child.data.iter().map(|(k, v)| (&k[..], Some(&v[..]))),
+				)),
+			);
+			let mut transaction = Transaction::new();
+			for (mut key, (value, rc)) in updates.drain() {
+				if !self.storage.prefix_keys {
+					key.drain(0 .. key.len() - DB_HASH_LEN);
+				}
+				if rc > 0 {
+					transaction.set_from_vec(columns::STATE, &key, value);
+				} else if rc < 0 && self.storage.prefix_keys {
+					// the nodes replaced by the chunk belong to no other trie, unless nodes are
+					// shared between tries when their keys aren't prefixed.
+					transaction.remove(columns::STATE, &key);
+				}
+			}
+			self.storage.db.commit(transaction)?;
+			root = chunk_root;
+		}
+
+		let header = genesis_header(root);
+		let hash = header.hash();
+		let mut operation = self.begin_operation()?;
+		self.begin_state_operation(&mut operation, BlockId::Hash(Default::default()))?;
+		// the nodes of the state are written already.
+		operation.commit_state = true;
+		operation.changes_trie_config_update = Some(changes_trie_config);
+		operation.set_block_data(header, Some(Vec::new()), None, NewBlockState::Final)?;
+		self.commit_operation(operation)?;
+		Ok(hash)
+	}
 }
 
 fn apply_state_commit(transaction: &mut Transaction<DbHash>, commit: sc_state_db::CommitSet<Vec<u8>>) {
@@ -1584,6 +2215,16 @@ impl<Block> sc_client_api::backend::AuxStore for Backend<Block> where Block: Blo
 	}
 }
 
+impl<Block: BlockT> Drop for Backend<Block> {
+	fn drop(&mut self) {
+		if self.trie_cache_warm_up {
+			if let Err(e) = self.storage.save_trie_cache() {
+				warn!(target: "db", "Failed to save the trie cache keys: {:?}", e);
+			}
+		}
+	}
+}
+
 impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 	type BlockImportOperation = BlockImportOperation<Block>;
 	type Blockchain = BlockchainDb<Block>;
@@ -1677,22 +2318,26 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 	}
 
 	fn usage_info(&self) -> Option<UsageInfo> {
-		let (io_stats, state_stats) = self.io_stats.take_or_else(||
+		let trie_cache = self.storage.trie_cache.as_ref();
+		let (io_stats, state_stats, trie_cache_stats) = self.io_stats.take_or_else(||
 			(
 				// TODO: implement DB stats and cache size retrieval
 				kvdb::IoStats::empty(),
 				self.state_usage.take(),
+				trie_cache.map(|cache| cache.take_stats()).unwrap_or_default(),
 			)
 		);
 		let database_cache = MemorySize::from_bytes(0);
 		let state_cache = MemorySize::from_bytes(
 			(*&self.shared_cache).lock().used_storage_cache_size(),
 		);
+		let trie_cache = MemorySize::from_bytes(trie_cache.map_or(0, |cache| cache.used_size()));
 		let state_db = self.storage.state_db.memory_info();
 
 		Some(UsageInfo {
 			memory: MemoryInfo {
 				state_cache,
+				trie_cache,
 				database_cache,
 				state_db,
 			},
@@ -1708,6 +2353,10 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 				state_writes_cache: state_stats.overlay_writes.ops,
 				state_reads_cache: state_stats.cache_reads.ops,
 				state_writes_nodes: state_stats.nodes_writes.ops,
+				trie_cache_hits: trie_cache_stats.hits,
+				trie_cache_misses: trie_cache_stats.misses,
+				trie_value_cache_hits: trie_cache_stats.value_hits,
+				trie_value_cache_misses: trie_cache_stats.value_misses,
 			},
 		})
 	}
@@ -1753,7 +2402,20 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 					return Ok(c.saturated_into::<NumberFor<Block>>())
 				}
 
-				match self.storage.state_db.revert_one() {
+				// the canonicalized blocks are reverted once no non-canonical block is left.
+				let commit = match self.storage.state_db.revert_one() {
+					None if revert_finalized => self.storage.state_db
+						.revert_canonical(
+							&removed_hash,
+							&prev_hash,
+							&StateMetaDb(&*self.storage.db),
+						)
+						.map_err(|e: sc_state_db::Error<io::Error>| {
+							sp_blockchain::Error::from_state_db(e)
+						})?,
+					commit => commit,
+				};
+				match commit {
 					Some(commit) => {
 						apply_state_commit(&mut transaction, commit);
 
@@ -1906,6 +2568,77 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 	fn get_import_lock(&self) -> &RwLock<()> {
 		&*self.import_lock
 	}
+
+	fn aux_key_spaces(&self) -> &AuxKeySpaces {
+		&self.aux_key_spaces
+	}
+
+	fn pin_block(&self, hash: &Block::Hash) -> ClientResult<()> {
+		let number = self.blockchain.number(*hash)?.ok_or_else(||
+			sp_blockchain::Error::UnknownBlock(format!("Unknown block {:?}", hash))
+		)?;
+		let discarded = || sp_blockchain::Error::UnknownBlock(
+			format!("State already discarded for {:?}", hash)
+		);
+		if !self.have_state_at(hash, number) {
+			return Err(discarded());
+		}
+
+		let mut pinned = self.pinned_blocks.lock();
+		match self.storage.state_db.pin(hash) {
+			Ok(()) => {},
+			// the canonical states are never pruned in archive mode.
+			Err(_) if self.is_archive => {},
+			Err(_) => return Err(discarded()),
+		}
+		*pinned.entry(*hash).or_default() += 1;
+		Ok(())
+	}
+
+	fn unpin_block(&self, hash: &Block::Hash) {
+		let mut pinned = self.pinned_blocks.lock();
+		let unpinned = match pinned.entry(*hash) {
+			Entry::Occupied(mut entry) => {
+				*entry.get_mut() -= 1;
+				if *entry.get() == 0 {
+					entry.remove();
+					true
+				} else {
+					false
+				}
+			},
+			Entry::Vacant(_) => return,
+		};
+		self.storage.state_db.unpin(hash);
+
+		if unpinned {
+			if let Err(e) = self.prune_unpinned_body(hash) {
+				warn!(
+					target: "db",
+					"Failed to prune the body of unpinned block {:?}: {:?}",
+					hash,
+					e,
+				);
+			}
+		}
+	}
+
+	#[cfg(any(feature = "with-kvdb-rocksdb", test))]
+	fn snapshot(&self, path: &std::path::Path) -> ClientResult<()> {
+		let cold_db_path = match &self.cold_source {
+			Some(DatabaseSettingsSrc::RocksDb { path, .. }) => Some(path.as_path()),
+			Some(_) => return Err(sp_blockchain::Error::Backend(
+				"Snapshots can only be taken of RocksDb cold databases".into(),
+			)),
+			None => None,
+		};
+		match self.rocksdb_path {
+			Some(ref db_path) => snapshot::write_snapshot(db_path, cold_db_path, path),
+			None => Err(sp_blockchain::Error::Backend(
+				"Snapshots can only be taken of RocksDb databases".into(),
+			)),
+		}
+	}
 }
 
 impl<Block: BlockT> sc_client_api::backend::LocalBackend<Block> for Backend<Block> {}
@@ -2037,10 +2770,16 @@ pub(crate) mod tests {
 		let backend = Backend::<Block>::new(DatabaseSettings {
 			state_cache_size: 16777216,
 			state_cache_child_ratio: Some((50, 100)),
+			trie_cache_size: 16777216,
 			state_pruning: PruningMode::keep_blocks(1),
 			source: DatabaseSettingsSrc::Custom(backing),
 			keep_blocks: KeepBlocks::All,
 			transaction_storage: TransactionStorageMode::BlockBody,
+			index_extrinsics: false,
+			compress_blocks: false,
+			trie_cache_warm_up: false,
+			cold_storage: None,
+			max_canonicalization_delay: None,
 		}, 0).unwrap();
 		assert_eq!(backend.blockchain().info().best_number, 9);
 		for i in 0..10 {
@@ -2048,6 +2787,61 @@ pub(crate) mod tests {
 		}
 	}
 
+	#[test]
+	fn warms_up_the_trie_cache() {
+		let db = sp_database::as_database(kvdb_memorydb::create(crate::utils::NUM_COLUMNS));
+		let settings = || DatabaseSettings {
+			state_cache_size: 16777216,
+			state_cache_child_ratio: Some((50, 100)),
+			trie_cache_size: 16777216,
+			state_pruning: PruningMode::ArchiveAll,
+			source: DatabaseSettingsSrc::Custom(db.clone()),
+			keep_blocks: KeepBlocks::All,
+			transaction_storage: TransactionStorageMode::BlockBody,
+			index_extrinsics: false,
+			compress_blocks: false,
+			trie_cache_warm_up: true,
+			cold_storage: None,
+			max_canonicalization_delay: None,
+		};
+
+		let keys = {
+			let backend = Backend::<Block>::new(settings(), 0).unwrap();
+			let mut op = backend.begin_operation().unwrap();
+			backend.begin_state_operation(&mut op, BlockId::Hash(Default::default())).unwrap();
+			let storage = vec![
+				(vec![1, 3, 5], vec![2, 4, 6]),
+				(vec![1, 2, 3], vec![9, 9, 9]),
+			];
+			let mut header = Header {
+				number: 0,
+				parent_hash: Default::default(),
+				state_root: Default::default(),
+				digest: Default::default(),
+				extrinsics_root: Default::default(),
+			};
+			header.state_root = op.old_state.storage_root(storage
+				.iter()
+				.map(|(x, y)| (&x[..], Some(&y[..])))
+			).0.into();
+			op.reset_storage(Storage {
+				top: storage.into_iter().collect(),
+				children_default: Default::default(),
+			}).unwrap();
+			op.set_block_data(header, Some(vec![]), None, NewBlockState::Best).unwrap();
+			backend.commit_operation(op).unwrap();
+
+			let state = backend.state_at(BlockId::Number(0)).unwrap();
+			assert_eq!(state.storage(&[1, 3, 5]).unwrap(), Some(vec![2, 4, 6]));
+			let keys = backend.storage.trie_cache.as_ref().unwrap().keys();
+			assert!(!keys.is_empty());
+			keys
+		};
+
+		let backend = Backend::<Block>::new(settings(), 0).unwrap();
+		assert_eq!(backend.storage.trie_cache.as_ref().unwrap().keys(), keys);
+	}
+
 	#[test]
 	fn set_state_data() {
 		let db = Backend::<Block>::new_test(2, 0);
@@ -2293,6 +3087,106 @@ pub(crate) mod tests {
 		).is_none());
 	}
 
+	#[test]
+	fn prunes_the_nodes_of_emptied_child_tries() {
+		let backend = Backend::<Block>::new_test(1, 0);
+		let child_info = ChildInfo::new_default(b"child");
+		// values too large to be inlined, so the child trie has a branch and two leaf nodes.
+		let (value1, value2) = (vec![1u8; 40], vec![2u8; 40]);
+
+		let (hash, child_root) = {
+			let mut op = backend.begin_operation().unwrap();
+			backend.begin_state_operation(&mut op, BlockId::Hash(Default::default())).unwrap();
+			let child = sp_core::storage::StorageChild {
+				data: vec![
+					(b"key1".to_vec(), value1.clone()),
+					(b"key2".to_vec(), value2.clone()),
+				].into_iter().collect(),
+				child_info: child_info.clone(),
+			};
+			let root = op.reset_storage(Storage {
+				top: Default::default(),
+				children_default: vec![(b"child".to_vec(), child)].into_iter().collect(),
+			}).unwrap();
+			let child_root = op.old_state.child_storage_root(
+				&child_info,
+				vec![(&b"key1"[..], Some(&value1[..])), (&b"key2"[..], Some(&value2[..]))]
+					.into_iter(),
+			).0;
+			let header = Header {
+				number: 0,
+				parent_hash: Default::default(),
+				state_root: root,
+				digest: Default::default(),
+				extrinsics_root: Default::default(),
+			};
+			let hash = header.hash();
+			op.set_block_data(header, Some(vec![]), None, NewBlockState::Best).unwrap();
+			backend.commit_operation(op).unwrap();
+			(hash, child_root)
+		};
+		let child_root_key = sp_trie::prefixed_key::<BlakeTwo256>(&child_root, (&b"child"[..], None));
+		assert!(backend.storage.db.get(columns::STATE, &child_root_key).is_some());
+
+		// delete all the keys of the child trie, with the transaction of the state backend.
+		let child_changes = vec![
+			(b"child".to_vec(), vec![(b"key1".to_vec(), None), (b"key2".to_vec(), None)]),
+		];
+		let hash = {
+			let mut op = backend.begin_operation().unwrap();
+			backend.begin_state_operation(&mut op, BlockId::Number(0)).unwrap();
+			let (root, transaction) = op.old_state.full_storage_root(
+				std::iter::empty(),
+				vec![(&child_info, vec![(&b"key1"[..], None), (&b"key2"[..], None)].into_iter())]
+					.into_iter(),
+			);
+			let deleted = transaction.clone().drain().into_iter()
+				.filter_map(|(key, (_, rc))| if rc < 0 { Some(key) } else { None })
+				.collect::<Vec<_>>();
+
+			let orphaned = |deleted: &[Vec<u8>]| child_trie::orphaned_nodes(
+				&*backend.storage as &dyn sp_state_machine::Storage<BlakeTwo256>,
+				&op.old_state,
+				&child_changes,
+				deleted,
+			);
+			let nodes = orphaned(&[]);
+			assert_eq!(nodes.len(), 3);
+			assert!(nodes.contains(&child_root_key));
+			// only the nodes the transaction leaves behind are added.
+			let mut left_behind = nodes.iter()
+				.filter(|key| !deleted.contains(key))
+				.cloned()
+				.collect::<Vec<_>>();
+			left_behind.sort();
+			let mut orphaned_nodes = orphaned(&deleted);
+			orphaned_nodes.sort();
+			assert_eq!(orphaned_nodes, left_behind);
+			assert!(orphaned(&nodes).is_empty());
+
+			op.update_db_storage(transaction).unwrap();
+			op.update_storage(Vec::new(), child_changes.clone()).unwrap();
+			let header = Header {
+				number: 1,
+				parent_hash: hash,
+				state_root: root,
+				digest: Default::default(),
+				extrinsics_root: Default::default(),
+			};
+			let hash = header.hash();
+			op.set_block_data(header, Some(vec![]), None, NewBlockState::Best).unwrap();
+			backend.commit_operation(op).unwrap();
+			hash
+		};
+		assert!(backend.storage.db.get(columns::STATE, &child_root_key).is_some());
+
+		let hash = insert_header(&backend, 2, hash, None, Default::default());
+		insert_header(&backend, 3, hash, None, Default::default());
+		assert!(backend.storage.db.get(columns::STATE, &child_root_key).is_none());
+		assert!(backend.state_at(BlockId::Number(3)).unwrap()
+			.child_storage(&child_info, b"key1").unwrap().is_none());
+	}
+
 	#[test]
 	fn tree_route_works() {
 		let backend = Backend::<Block>::new_test(1000, 100);
@@ -2613,4 +3507,265 @@ pub(crate) mod tests {
 			assert_eq!(Some(vec![4.into()]), bc.body(BlockId::hash(blocks[4])).unwrap());
 		}
 	}
+
+	#[test]
+	fn pinned_blocks_are_not_pruned() {
+		let backend = Backend::<Block>::new_test(2, 0);
+		let mut blocks = Vec::new();
+		let mut prev_hash = Default::default();
+		for i in 0 .. 5 {
+			let body = vec![i.into()];
+			let hash = insert_block(&backend, i, prev_hash, None, Default::default(), body);
+			blocks.push(hash);
+			prev_hash = hash;
+		}
+		assert!(backend.pin_block(&Default::default()).is_err());
+		backend.pin_block(&blocks[1]).unwrap();
+
+		{
+			let mut op = backend.begin_operation().unwrap();
+			backend.begin_state_operation(&mut op, BlockId::Hash(blocks[4])).unwrap();
+			for i in 1 .. 5 {
+				op.mark_finalized(BlockId::Hash(blocks[i]), None).unwrap();
+			}
+			backend.commit_operation(op).unwrap();
+		}
+		let bc = backend.blockchain();
+		assert_eq!(None, bc.body(BlockId::hash(blocks[0])).unwrap());
+		assert_eq!(Some(vec![1.into()]), bc.body(BlockId::hash(blocks[1])).unwrap());
+		assert_eq!(None, bc.body(BlockId::hash(blocks[2])).unwrap());
+		assert!(backend.have_state_at(&blocks[1], 1));
+
+		backend.unpin_block(&blocks[1]);
+		assert_eq!(None, bc.body(BlockId::hash(blocks[1])).unwrap());
+	}
+
+	#[test]
+	fn compresses_stored_blocks() {
+		let db = sp_database::as_database(kvdb_memorydb::create(crate::utils::NUM_COLUMNS));
+		let settings = |compress_blocks| DatabaseSettings {
+			state_cache_size: 16777216,
+			state_cache_child_ratio: Some((50, 100)),
+			trie_cache_size: 16777216,
+			state_pruning: PruningMode::ArchiveAll,
+			source: DatabaseSettingsSrc::Custom(db.clone()),
+			keep_blocks: KeepBlocks::All,
+			transaction_storage: TransactionStorageMode::BlockBody,
+			index_extrinsics: false,
+			compress_blocks,
+			trie_cache_warm_up: false,
+			cold_storage: None,
+			max_canonicalization_delay: None,
+		};
+		let body = vec![ExtrinsicWrapper::from(7u64); 64];
+		let is_compressed = |hash| {
+			let id = BlockId::<Block>::Hash(hash);
+			let key = utils::block_id_to_lookup_key(&*db, columns::KEY_LOOKUP, id).unwrap();
+			let key = key.unwrap();
+			compression::is_compressed(&db.get(columns::BODY, &key).unwrap())
+		};
+
+		let blocks = {
+			let backend = Backend::<Block>::new(settings(false), 0).unwrap();
+			let mut blocks = Vec::new();
+			let mut prev_hash = Default::default();
+			for i in 0 .. 4 {
+				let hash = insert_block(&backend, i, prev_hash, None, H256::zero(), body.clone());
+				blocks.push(hash);
+				prev_hash = hash;
+			}
+			let mut op = backend.begin_operation().unwrap();
+			backend.begin_state_operation(&mut op, BlockId::Hash(blocks[3])).unwrap();
+			for i in 1 .. 4 {
+				op.mark_finalized(BlockId::Hash(blocks[i]), None).unwrap();
+			}
+			backend.commit_operation(op).unwrap();
+			blocks
+		};
+		assert!(!is_compressed(blocks[0]));
+
+		let backend = Backend::<Block>::new(settings(true), 0).unwrap();
+		let block = insert_block(&backend, 4, blocks[3], None, Default::default(), body.clone());
+		assert!(is_compressed(block));
+
+		assert!(backend.compress_stored_blocks(2).unwrap());
+		assert!(is_compressed(blocks[1]));
+		assert!(!is_compressed(blocks[2]));
+		assert!(!backend.compress_stored_blocks(10).unwrap());
+		assert!(!backend.compress_stored_blocks(10).unwrap());
+		for hash in blocks.into_iter().chain(Some(block)) {
+			assert!(is_compressed(hash));
+			assert_eq!(backend.blockchain().body(BlockId::Hash(hash)).unwrap(), Some(body.clone()));
+		}
+	}
+
+	#[test]
+	fn moves_ancient_blocks_to_cold_storage() {
+		let hot = sp_database::as_database(kvdb_memorydb::create(crate::utils::NUM_COLUMNS));
+		let cold = sp_database::as_database(kvdb_memorydb::create(crate::utils::NUM_COLUMNS));
+		let backend = Backend::<Block>::new(DatabaseSettings {
+			state_cache_size: 16777216,
+			state_cache_child_ratio: Some((50, 100)),
+			trie_cache_size: 16777216,
+			state_pruning: PruningMode::ArchiveAll,
+			source: DatabaseSettingsSrc::Custom(hot.clone()),
+			keep_blocks: KeepBlocks::All,
+			transaction_storage: TransactionStorageMode::BlockBody,
+			index_extrinsics: false,
+			compress_blocks: false,
+			trie_cache_warm_up: false,
+			cold_storage: Some(ColdStorageSettings {
+				source: DatabaseSettingsSrc::Custom(cold.clone()),
+				hot_blocks: 2,
+			}),
+			max_canonicalization_delay: None,
+		}, 0).unwrap();
+		let is_hot = |hash| {
+			let id = BlockId::<Block>::Hash(hash);
+			let key = utils::block_id_to_lookup_key(&*hot, columns::KEY_LOOKUP, id).unwrap();
+			hot.contains(columns::BODY, &key.unwrap())
+		};
+
+		let mut blocks = Vec::new();
+		let mut prev_hash = Default::default();
+		for i in 0 .. 6 {
+			let body = vec![ExtrinsicWrapper::from(i)];
+			let hash = insert_block(&backend, i, prev_hash, None, H256::zero(), body);
+			blocks.push(hash);
+			prev_hash = hash;
+		}
+		let mut op = backend.begin_operation().unwrap();
+		backend.begin_state_operation(&mut op, BlockId::Hash(blocks[5])).unwrap();
+		for i in 1 .. 5 {
+			op.mark_finalized(BlockId::Hash(blocks[i]), None).unwrap();
+		}
+		backend.commit_operation(op).unwrap();
+
+		// blocks #0 to #2 are older than the last 2 finalized blocks.
+		assert!(backend.migrate_cold_blocks(2).unwrap());
+		assert!(!backend.migrate_cold_blocks(2).unwrap());
+		assert!(!backend.migrate_cold_blocks(2).unwrap());
+		for (number, hash) in blocks.iter().enumerate() {
+			assert_eq!(is_hot(*hash), number > 2);
+			assert_eq!(
+				backend.blockchain().body(BlockId::Hash(*hash)).unwrap(),
+				Some(vec![ExtrinsicWrapper::from(number as u64)]),
+			);
+		}
+
+		// the bodies moved to the cold database are compressed there.
+		let body = vec![ExtrinsicWrapper::from(0u64); 64];
+		let id = BlockId::<Block>::Hash(blocks[0]);
+		let key = utils::block_id_to_lookup_key(&*hot, columns::KEY_LOOKUP, id).unwrap().unwrap();
+		let mut transaction = Transaction::new();
+		transaction.set_from_vec(columns::BODY, &key, body.encode());
+		cold.commit(transaction).unwrap();
+		while backend.compress_stored_blocks(2).unwrap() {}
+		assert!(!is_hot(blocks[0]));
+		assert!(compression::is_compressed(&cold.get(columns::BODY, &key).unwrap()));
+		assert_eq!(backend.blockchain().body(id).unwrap(), Some(body));
+
+		// the bodies are removed from the cold database too once pruned.
+		let key = utils::number_and_hash_to_lookup_key(1u64, blocks[1]).unwrap();
+		assert!(cold.contains(columns::BODY, &key));
+		let mut transaction = Transaction::new();
+		transaction.remove(columns::BODY, &key);
+		backend.storage.db.commit(transaction).unwrap();
+		assert!(!cold.contains(columns::BODY, &key));
+		assert_eq!(backend.blockchain().body(BlockId::Hash(blocks[1])).unwrap(), None);
+	}
+
+	#[test]
+	fn purges_parts_of_the_database() {
+		let db = sp_database::as_database(kvdb_memorydb::create(crate::utils::NUM_COLUMNS));
+		let settings = || DatabaseSettings {
+			state_cache_size: 16777216,
+			state_cache_child_ratio: Some((50, 100)),
+			trie_cache_size: 16777216,
+			state_pruning: PruningMode::ArchiveAll,
+			source: DatabaseSettingsSrc::Custom(db.clone()),
+			keep_blocks: KeepBlocks::All,
+			transaction_storage: TransactionStorageMode::BlockBody,
+			index_extrinsics: false,
+			compress_blocks: false,
+			trie_cache_warm_up: false,
+			cold_storage: None,
+			max_canonicalization_delay: None,
+		};
+		let backend = Backend::<Block>::new(settings(), 0).unwrap();
+		let mut prev_hash = Default::default();
+		for i in 0 .. 3 {
+			prev_hash = insert_block(&backend, i, prev_hash, None, H256::zero(), vec![i.into()]);
+		}
+		let count = |col| db.iter(col).unwrap().count();
+		assert_eq!(count(columns::BODY), 3);
+
+		purge_database::<Block>(&settings(), DatabasePart::Blocks).unwrap();
+		assert_eq!(count(columns::BODY), 0);
+		assert_eq!(backend.blockchain().body(BlockId::Hash(prev_hash)).unwrap(), None);
+		assert!(backend.blockchain().header(BlockId::Hash(prev_hash)).unwrap().is_some());
+		assert!(count(columns::STATE_META) > 0);
+
+		purge_database::<Block>(&settings(), DatabasePart::State).unwrap();
+		assert_eq!(count(columns::STATE) + count(columns::STATE_META), 0);
+		assert_eq!(count(columns::HEADER), 3);
+	}
+
+	#[test]
+	fn adapts_the_canonicalization_delay_to_the_finality_lag() {
+		let delay = CanonicalizationDelay::new(2, Some(8));
+		assert_eq!(delay.adapt(1), 2);
+		assert_eq!(delay.adapt(3), 6);
+		assert_eq!(delay.adapt(10), 8);
+		assert_eq!(delay.adapt(0), 7);
+		assert_eq!(delay.adapt(3), 6);
+		assert_eq!(delay.adapt(3), 6);
+
+		let mut backend = Backend::<Block>::new_test(1000, 2);
+		backend.canonicalization_delay = CanonicalizationDelay::new(2, Some(8));
+		let mut prev_hash = Default::default();
+		for i in 0 ..= 10 {
+			prev_hash = insert_header(&backend, i, prev_hash, None, Default::default());
+		}
+		// block #8 would be canonicalized with a fixed delay.
+		assert_eq!(backend.storage.state_db.best_canonical(), Some(2));
+	}
+
+	#[test]
+	fn indexes_extrinsics_of_the_best_chain() {
+		let backend = Backend::<Block>::new_test(1000, 100);
+		let location = |extrinsic: u64| {
+			let hash = BlakeTwo256::hash(&ExtrinsicWrapper::from(extrinsic).encode());
+			backend.blockchain().extrinsic_location(&hash).unwrap()
+		};
+
+		let genesis = insert_header(&backend, 0, Default::default(), None, Default::default());
+		let block1 = insert_block(&backend, 1, genesis, None, Default::default(), vec![1.into()]);
+		let block2 = insert_block(&backend, 2, block1, None, Default::default(), vec![2.into()]);
+		assert_eq!(location(1), Some((block1, 0)));
+		assert_eq!(location(2), Some((block2, 0)));
+		assert_eq!(location(3), None);
+
+		// the fork becomes the best chain.
+		let fork1 = insert_block(
+			&backend,
+			1,
+			genesis,
+			None,
+			H256::from_low_u64_be(1),
+			vec![1.into(), 3.into()],
+		);
+		assert_eq!(location(1), Some((fork1, 0)));
+		assert_eq!(location(2), None);
+		assert_eq!(location(3), Some((fork1, 1)));
+
+		// and is retracted.
+		let mut op = backend.begin_operation().unwrap();
+		backend.begin_state_operation(&mut op, BlockId::Hash(block2)).unwrap();
+		op.mark_head(BlockId::Hash(block2)).unwrap();
+		backend.commit_operation(op).unwrap();
+		assert_eq!(location(1), Some((block1, 0)));
+		assert_eq!(location(2), Some((block2, 0)));
+		assert_eq!(location(3), None);
+	}
 }
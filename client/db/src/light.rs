@@ -571,6 +571,7 @@ impl<Block> Storage<Block> for LightStorage<Block>
 			memory: MemoryInfo {
 				database_cache,
 				state_cache: Default::default(),
+				trie_cache: Default::default(),
 				state_db: Default::default(),
 			},
 			io: IoInfo {
@@ -586,6 +587,10 @@ impl<Block> Storage<Block> for LightStorage<Block>
 				state_reads_cache: 0,
 				state_writes_cache: 0,
 				state_writes_nodes: 0,
+				trie_cache_hits: 0,
+				trie_cache_misses: 0,
+				trie_value_cache_hits: 0,
+				trie_value_cache_misses: 0,
 			}
 		})
 	}
@@ -0,0 +1,328 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Metrics of the database reads and commits and of its size on disk.
+
+use std::{
+	fs,
+	io,
+	path::{Path, PathBuf},
+	sync::{Arc, Weak},
+	thread,
+	time::{Duration, Instant},
+};
+
+use prometheus_endpoint::{
+	exponential_buckets, register, Counter, CounterVec, Gauge, Histogram, HistogramOpts,
+	HistogramVec, Opts, PrometheusError, Registry, U64,
+};
+use sp_database::{Change, ChangeRef, ColumnId, Database, Transaction};
+
+use crate::{columns, DbHash};
+
+/// How often the size of the database on disk is computed.
+const DISK_SIZE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Returns the label of the given column.
+fn column_name(col: ColumnId) -> &'static str {
+	match col {
+		columns::META => "meta",
+		columns::STATE => "state",
+		columns::STATE_META => "state_meta",
+		columns::KEY_LOOKUP => "key_lookup",
+		columns::HEADER => "header",
+		columns::BODY => "body",
+		columns::JUSTIFICATION => "justification",
+		columns::CHANGES_TRIE => "changes_trie",
+		columns::AUX => "aux",
+		columns::OFFCHAIN => "offchain",
+		columns::CACHE => "cache",
+		columns::TRANSACTION => "transaction",
+		columns::EXTRINSIC_INDEX => "extrinsic_index",
+		_ => "unknown",
+	}
+}
+
+/// The metrics of the database.
+///
+/// The commits write to all their columns at once, so only their number of keys is recorded
+/// by column, their duration isn't.
+#[derive(Clone)]
+pub(crate) struct DatabaseMetrics {
+	reads: HistogramVec,
+	written_keys: CounterVec<U64>,
+	commits: Histogram,
+	commit_size: Histogram,
+	commit_errors: Counter<U64>,
+	disk_size: Gauge<U64>,
+	/// Keeps the size of the database on disk updated, until the last clone is dropped.
+	_disk_size_updates: Arc<()>,
+}
+
+impl DatabaseMetrics {
+	/// Register the metrics of the database stored in the given directory, if any.
+	///
+	/// The size of the directory is computed periodically by a background thread.
+	pub(crate) fn register(
+		registry: &Registry,
+		path: Option<&Path>,
+	) -> Result<Self, PrometheusError> {
+		let metrics = DatabaseMetrics {
+			reads: register(HistogramVec::new(
+				HistogramOpts {
+					common_opts: Opts::new(
+						"database_read_duration_seconds",
+						"Time taken by the database reads, by column",
+					),
+					buckets: exponential_buckets(0.000_001, 4.0, 12)?,
+				},
+				&["column"],
+			)?, registry)?,
+			written_keys: register(CounterVec::new(
+				Opts::new("database_written_keys_total", "Keys set or removed, by column"),
+				&["column"],
+			)?, registry)?,
+			commits: register(Histogram::with_opts(HistogramOpts {
+				common_opts: Opts::new(
+					"database_commit_duration_seconds",
+					"Time taken by the database commits",
+				),
+				buckets: exponential_buckets(0.000_01, 4.0, 12)?,
+			})?, registry)?,
+			commit_size: register(Histogram::with_opts(HistogramOpts {
+				common_opts: Opts::new(
+					"database_commit_changes",
+					"Number of changes of the database commits",
+				),
+				buckets: exponential_buckets(1.0, 4.0, 12)?,
+			})?, registry)?,
+			commit_errors: register(Counter::new(
+				"database_commit_errors_total", "Number of failed database commits",
+			)?, registry)?,
+			disk_size: register(Gauge::new(
+				"database_disk_bytes", "Size of the database files on disk, in bytes",
+			)?, registry)?,
+			_disk_size_updates: Arc::new(()),
+		};
+		if let Some(path) = path {
+			spawn_disk_size_updates(
+				path.into(),
+				metrics.disk_size.clone(),
+				Arc::downgrade(&metrics._disk_size_updates),
+			);
+		}
+		Ok(metrics)
+	}
+
+	/// Note a commit of the given columns and number of changes that took `duration`.
+	fn observe_commit(
+		&self,
+		written: &[ColumnId],
+		changes: usize,
+		duration: Duration,
+		success: bool,
+	) {
+		if !success {
+			self.commit_errors.inc();
+		}
+		self.commits.observe(duration.as_secs_f64());
+		self.commit_size.observe(changes as f64);
+		for col in written {
+			self.written_keys.with_label_values(&[column_name(*col)]).inc();
+		}
+	}
+
+	fn observe_read(&self, col: ColumnId, start: Instant) {
+		self.reads.with_label_values(&[column_name(col)]).observe(start.elapsed().as_secs_f64());
+	}
+}
+
+/// Compute the size of the database in the given directory every [`DISK_SIZE_INTERVAL`],
+/// until `alive` is dropped.
+fn spawn_disk_size_updates(path: PathBuf, disk_size: Gauge<U64>, alive: Weak<()>) {
+	let updates = thread::Builder::new().name("db-disk-size".into()).spawn(move || {
+		while alive.strong_count() > 0 {
+			update_disk_size(&disk_size, &path);
+			thread::sleep(DISK_SIZE_INTERVAL);
+		}
+	});
+	if let Err(e) = updates {
+		log::warn!(target: "db", "Failed to start the updates of the database size: {}", e);
+	}
+}
+
+fn update_disk_size(disk_size: &Gauge<U64>, path: &Path) {
+	match dir_size(path) {
+		Ok(size) => disk_size.set(size),
+		Err(e) => log::debug!(
+			target: "db",
+			"Failed to compute the size of the database at {}: {}",
+			path.display(),
+			e,
+		),
+	}
+}
+
+/// Returns the total size of the files in a directory and its subdirectories.
+///
+/// The files removed while they are listed, e.g. by a compaction of the database, are skipped.
+fn dir_size(path: &Path) -> io::Result<u64> {
+	let mut size = 0;
+	for entry in fs::read_dir(path)? {
+		let entry = entry?;
+		let metadata = match entry.metadata() {
+			Ok(metadata) => metadata,
+			Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+			Err(e) => return Err(e),
+		};
+		size += if metadata.is_dir() {
+			match dir_size(&entry.path()) {
+				Ok(size) => size,
+				Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+				Err(e) => return Err(e),
+			}
+		} else {
+			metadata.len()
+		};
+	}
+	Ok(size)
+}
+
+/// A database recording the metrics of its reads and commits.
+pub(crate) struct MeteredDatabase {
+	db: Arc<dyn Database<DbHash>>,
+	metrics: DatabaseMetrics,
+}
+
+impl MeteredDatabase {
+	pub(crate) fn new(db: Arc<dyn Database<DbHash>>, metrics: DatabaseMetrics) -> Self {
+		MeteredDatabase { db, metrics }
+	}
+}
+
+impl Database<DbHash> for MeteredDatabase {
+	fn commit(&self, transaction: Transaction<DbHash>) -> sp_database::error::Result<()> {
+		let changes = transaction.0.len();
+		let written = transaction.0.iter().filter_map(|change| match change {
+			Change::Set(col, ..) | Change::Remove(col, ..) => Some(*col),
+			Change::Store(..) | Change::Release(..) => None,
+		}).collect::<Vec<_>>();
+
+		let start = Instant::now();
+		let result = self.db.commit(transaction);
+		self.metrics.observe_commit(&written, changes, start.elapsed(), result.is_ok());
+		result
+	}
+
+	fn commit_ref<'a>(
+		&self,
+		transaction: &mut dyn Iterator<Item = ChangeRef<'a, DbHash>>,
+	) -> sp_database::error::Result<()> {
+		let mut changes = 0;
+		let mut written = Vec::new();
+		let mut transaction = transaction.inspect(|change| {
+			changes += 1;
+			match change {
+				ChangeRef::Set(col, ..) | ChangeRef::Remove(col, ..) => written.push(*col),
+				ChangeRef::Store(..) | ChangeRef::Release(..) => {},
+			}
+		});
+
+		let start = Instant::now();
+		let result = self.db.commit_ref(&mut transaction);
+		drop(transaction);
+		self.metrics.observe_commit(&written, changes, start.elapsed(), result.is_ok());
+		result
+	}
+
+	fn get(&self, col: ColumnId, key: &[u8]) -> Option<Vec<u8>> {
+		let start = Instant::now();
+		let value = self.db.get(col, key);
+		self.metrics.observe_read(col, start);
+		value
+	}
+
+	fn contains(&self, col: ColumnId, key: &[u8]) -> bool {
+		let start = Instant::now();
+		let contains = self.db.contains(col, key);
+		self.metrics.observe_read(col, start);
+		contains
+	}
+
+	fn with_get(&self, col: ColumnId, key: &[u8], f: &mut dyn FnMut(&[u8])) {
+		let start = Instant::now();
+		self.db.with_get(col, key, f);
+		self.metrics.observe_read(col, start);
+	}
+
+	fn lookup(&self, hash: &DbHash) -> Option<Vec<u8>> {
+		let start = Instant::now();
+		let value = self.db.lookup(hash);
+		self.metrics.observe_read(columns::STATE, start);
+		value
+	}
+
+	fn with_lookup(&self, hash: &DbHash, f: &mut dyn FnMut(&[u8])) {
+		let start = Instant::now();
+		self.db.with_lookup(hash, f);
+		self.metrics.observe_read(columns::STATE, start);
+	}
+
+	fn iter(&self, col: ColumnId) -> Option<Box<dyn Iterator<Item = (Vec<u8>, Vec<u8>)> + '_>> {
+		self.db.iter(col)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn records_reads_commits_and_disk_size() {
+		let dir = tempfile::tempdir().unwrap();
+		fs::write(dir.path().join("a"), [0u8; 10]).unwrap();
+		fs::create_dir(dir.path().join("b")).unwrap();
+		fs::write(dir.path().join("b").join("c"), [0u8; 5]).unwrap();
+
+		let registry = Registry::new();
+		let metrics = DatabaseMetrics::register(&registry, None).unwrap();
+		let db = MeteredDatabase::new(
+			sp_database::as_database(kvdb_memorydb::create(crate::utils::NUM_COLUMNS)),
+			metrics.clone(),
+		);
+
+		let mut transaction = Transaction::new();
+		transaction.set(columns::HEADER, b"key", b"value");
+		transaction.set(columns::HEADER, b"other", b"value");
+		transaction.remove(columns::BODY, b"key");
+		db.commit(transaction).unwrap();
+		assert_eq!(db.get(columns::HEADER, b"key"), Some(b"value".to_vec()));
+
+		assert_eq!(metrics.commits.get_sample_count(), 1);
+		assert_eq!(metrics.commit_size.get_sample_sum(), 3.0);
+		assert_eq!(metrics.written_keys.with_label_values(&["header"]).get(), 2);
+		assert_eq!(metrics.written_keys.with_label_values(&["body"]).get(), 1);
+		assert_eq!(metrics.reads.with_label_values(&["header"]).get_sample_count(), 1);
+
+		update_disk_size(&metrics.disk_size, dir.path());
+		assert_eq!(metrics.disk_size.get(), 15);
+		// a missing directory leaves the last size.
+		update_disk_size(&metrics.disk_size, &dir.path().join("missing"));
+		assert_eq!(metrics.disk_size.get(), 15);
+	}
+}
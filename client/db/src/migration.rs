@@ -0,0 +1,168 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Migrations of the database schema.
+//!
+//! The version of the schema is stored in the meta column. When the database is opened, the
+//! migrations from its version to the current one are applied in order, so changes of the
+//! columns or of the encoding of their values don't require to resync the chain. Unlike the
+//! upgrades of RocksDB databases, which change their layout before they can be opened, the
+//! migrations apply to every database backend.
+
+use std::time::Instant;
+
+use codec::{Decode, Encode};
+use log::info;
+use sp_database::Transaction;
+use crate::{Database, DbHash, utils::{COLUMN_META, DatabaseType, meta_keys}};
+
+/// Returns the changes migrating the database, committed along with its new version.
+type Apply = fn(&dyn Database<DbHash>, DatabaseType) -> sp_blockchain::Result<Transaction<DbHash>>;
+
+/// A migration of the database schema from the previous version.
+pub(crate) struct Migration {
+	/// Short description of the migration, for the logs.
+	name: &'static str,
+	apply: Apply,
+}
+
+/// The migrations of the schema, in order: the migration at index `i` migrates the schema from
+/// version `i` to version `i + 1`.
+const MIGRATIONS: &[Migration] = &[];
+
+/// Apply the pending migrations of the database.
+pub(crate) fn migrate(
+	db: &dyn Database<DbHash>,
+	db_type: DatabaseType,
+) -> sp_blockchain::Result<()> {
+	migrate_with(db, db_type, MIGRATIONS)
+}
+
+fn migrate_with(
+	db: &dyn Database<DbHash>,
+	db_type: DatabaseType,
+	migrations: &[Migration],
+) -> sp_blockchain::Result<()> {
+	let current = migrations.len() as u32;
+	let version = match db.get(COLUMN_META, meta_keys::SCHEMA_VERSION) {
+		Some(version) => u32::decode(&mut &version[..]).map_err(|e| sp_blockchain::Error::Backend(
+			format!("Invalid database schema version: {}", e),
+		))?,
+		// new databases use the current schema, the ones created before the versions the first.
+		None if db.get(COLUMN_META, meta_keys::TYPE).is_none() => current,
+		None => 0,
+	};
+	if version > current {
+		return Err(sp_blockchain::Error::Backend(
+			format!("Future database schema version: {}", version),
+		));
+	}
+
+	if version < current {
+		info!(
+			target: "db",
+			"Migrating the database schema from version {} to {}",
+			version,
+			current,
+		);
+	}
+	for (index, migration) in migrations.iter().enumerate().skip(version as usize) {
+		let version = index as u32 + 1;
+		info!(target: "db", "Applying migration {}/{}: {}", version, current, migration.name);
+		let started = Instant::now();
+		let mut transaction = (migration.apply)(db, db_type)?;
+		transaction.set_from_vec(COLUMN_META, meta_keys::SCHEMA_VERSION, version.encode());
+		db.commit(transaction)?;
+		info!(
+			target: "db",
+			"Applied migration {}/{} in {} ms",
+			version,
+			current,
+			started.elapsed().as_millis(),
+		);
+	}
+
+	if db.get(COLUMN_META, meta_keys::SCHEMA_VERSION).is_none() {
+		let mut transaction = Transaction::new();
+		transaction.set_from_vec(COLUMN_META, meta_keys::SCHEMA_VERSION, current.encode());
+		db.commit(transaction)?;
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_blockchain::Result as ClientResult;
+
+	const KEY: &[u8] = b"key";
+
+	fn append(db: &dyn Database<DbHash>, value: u8) -> Transaction<DbHash> {
+		let mut values = db.get(COLUMN_META, KEY).unwrap_or_default();
+		values.push(value);
+		let mut transaction = Transaction::new();
+		transaction.set_from_vec(COLUMN_META, KEY, values);
+		transaction
+	}
+
+	fn first(db: &dyn Database<DbHash>, _: DatabaseType) -> ClientResult<Transaction<DbHash>> {
+		Ok(append(db, 1))
+	}
+
+	fn second(db: &dyn Database<DbHash>, _: DatabaseType) -> ClientResult<Transaction<DbHash>> {
+		Ok(append(db, 2))
+	}
+
+	const TEST_MIGRATIONS: &[Migration] = &[
+		Migration { name: "first", apply: first },
+		Migration { name: "second", apply: second },
+	];
+
+	fn version(db: &dyn Database<DbHash>) -> Option<u32> {
+		db.get(COLUMN_META, meta_keys::SCHEMA_VERSION)
+			.map(|version| u32::decode(&mut &version[..]).unwrap())
+	}
+
+	#[test]
+	fn applies_the_pending_migrations_in_order() {
+		// databases created before the versions start at the first migration.
+		let db = sp_database::MemDb::default();
+		let mut transaction = Transaction::new();
+		transaction.set(COLUMN_META, meta_keys::TYPE, b"full");
+		db.commit(transaction).unwrap();
+
+		migrate_with(&db, DatabaseType::Full, &TEST_MIGRATIONS[..1]).unwrap();
+		assert_eq!(version(&db), Some(1));
+		assert_eq!(db.get(COLUMN_META, KEY), Some(vec![1]));
+
+		migrate_with(&db, DatabaseType::Full, TEST_MIGRATIONS).unwrap();
+		migrate_with(&db, DatabaseType::Full, TEST_MIGRATIONS).unwrap();
+		assert_eq!(version(&db), Some(2));
+		assert_eq!(db.get(COLUMN_META, KEY), Some(vec![1, 2]));
+
+		assert!(migrate_with(&db, DatabaseType::Full, &TEST_MIGRATIONS[..1]).is_err());
+	}
+
+	#[test]
+	fn new_databases_use_the_current_schema() {
+		let db = sp_database::MemDb::default();
+		migrate_with(&db, DatabaseType::Full, TEST_MIGRATIONS).unwrap();
+		assert_eq!(version(&db), Some(2));
+		assert_eq!(db.get(COLUMN_META, KEY), None);
+	}
+}
@@ -17,10 +17,21 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 /// A `Database` adapter for parity-db.
 
+use std::{fs, io, path::Path};
 use sp_database::{Database, Change, ColumnId, Transaction, error::DatabaseError};
 use crate::utils::{DatabaseType, NUM_COLUMNS};
 use crate::columns;
 
+/// Number of entries copied to parity-db per commit when migrating a database.
+#[cfg(any(feature = "with-kvdb-rocksdb", test))]
+const MIGRATION_BATCH: usize = 10_000;
+
+/// Number of columns of the databases created before the extrinsic index column was added.
+const V1_NUM_COLUMNS: u32 = 12;
+
+/// The file parity-db describes the columns of a database in.
+const METADATA_FILE: &str = "metadata";
+
 struct DbAdapter(parity_db::Db);
 
 fn handle_err<T>(result: parity_db::Result<T>) -> T {
@@ -32,10 +43,7 @@ fn handle_err<T>(result: parity_db::Result<T>) -> T {
 	}
 }
 
-/// Wrap parity-db database into a trait object that implements `sp_database::Database`
-pub fn open<H: Clone>(path: &std::path::Path, db_type: DatabaseType)
-	-> parity_db::Result<std::sync::Arc<dyn Database<H>>>
-{
+fn options(path: &Path, db_type: DatabaseType) -> parity_db::Options {
 	let mut config = parity_db::Options::with_columns(path, NUM_COLUMNS as u8);
 	config.sync = true; // Flush each commit
 	if db_type == DatabaseType::Full {
@@ -44,10 +52,160 @@ pub fn open<H: Clone>(path: &std::path::Path, db_type: DatabaseType)
 		state_col.preimage = true;
 		state_col.uniform = true;
 	}
-	let db = parity_db::Db::open(&config)?;
+	config
+}
+
+/// Wrap parity-db database into a trait object that implements `sp_database::Database`
+pub fn open<H: Clone>(path: &Path, db_type: DatabaseType)
+	-> parity_db::Result<std::sync::Arc<dyn Database<H>>>
+{
+	add_missing_columns(path)?;
+	let db = parity_db::Db::open(&options(path, db_type))?;
 	Ok(std::sync::Arc::new(DbAdapter(db)))
 }
 
+/// Add the extrinsic index column to a database created before it, which parity-db would
+/// refuse to open with another number of columns than it was created with.
+///
+/// The column is added the way parity-db adds columns: it is described in the metadata of the
+/// database, with the options of the meta column as both use the default ones, and its files are
+/// created on its first use. Databases without metadata or with another number of columns are
+/// left to parity-db.
+fn add_missing_columns(path: &Path) -> io::Result<()> {
+	let metadata_path = path.join(METADATA_FILE);
+	let metadata = match fs::read_to_string(&metadata_path) {
+		Ok(metadata) => metadata,
+		Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+		Err(e) => return Err(e),
+	};
+	let column = |col: u32| format!("col{}=", col);
+	let num_columns = (0..NUM_COLUMNS)
+		.take_while(|col| metadata.lines().any(|line| line.starts_with(&column(*col))))
+		.count() as u32;
+	if num_columns != V1_NUM_COLUMNS {
+		return Ok(());
+	}
+	let meta_options = metadata.lines()
+		.find_map(|line| line.strip_prefix(&column(columns::META)))
+		.expect("the database has the meta column; qed")
+		.to_owned();
+
+	let mut upgraded = metadata;
+	if !upgraded.ends_with('\n') {
+		upgraded.push('\n');
+	}
+	upgraded.push_str(&format!("{}{}\n", column(columns::EXTRINSIC_INDEX), meta_options));
+	// replace the metadata at once, so an interrupted upgrade leaves the database as it was.
+	let upgrading = path.join(format!("{}.upgrading", METADATA_FILE));
+	fs::write(&upgrading, upgraded)?;
+	fs::rename(&upgrading, &metadata_path)?;
+	log::info!(
+		target: "db",
+		"Added the extrinsic index column to the ParityDb database at {}",
+		path.display(),
+	);
+	Ok(())
+}
+
+/// Copy the RocksDB database at `from` into a new parity-db database at `to`.
+///
+/// The state nodes are stored by hash in parity-db and by prefixed key in RocksDB, so the
+/// keys of the state are stripped of their prefix. The journals of the state database refer to
+/// the prefixed keys, hence only archive databases whose blocks are all canonicalized can be
+/// migrated. The RocksDB database is left untouched, apart from its upgrades.
+#[cfg(any(feature = "with-kvdb-rocksdb", test))]
+pub fn migrate_from_rocksdb<Block: sp_runtime::traits::Block>(
+	from: &Path,
+	to: &Path,
+	db_type: DatabaseType,
+	state_pruning: &sc_state_db::PruningMode,
+) -> sp_blockchain::Result<()> {
+	use kvdb::KeyValueDB;
+
+	let backend_err = |e: &dyn std::fmt::Debug| sp_blockchain::Error::Backend(format!("{:?}", e));
+
+	if !state_pruning.is_archive() {
+		return Err(sp_blockchain::Error::Backend(
+			"Only archive databases can be migrated to ParityDb".into(),
+		));
+	}
+
+	crate::upgrade::upgrade_db::<Block>(from, db_type)?;
+	let path = from.to_str()
+		.ok_or_else(|| sp_blockchain::Error::Backend("Invalid database path".into()))?;
+	let source = kvdb_rocksdb::Database::open(
+		&kvdb_rocksdb::DatabaseConfig::with_columns(NUM_COLUMNS),
+		path,
+	).map_err(|e| backend_err(&e))?;
+
+	check_migrated_state(&source, state_pruning)?;
+	let stored_type = source.get(columns::META, crate::utils::meta_keys::TYPE)
+		.map_err(|e| backend_err(&e))?;
+	if stored_type.map_or(false, |stored| stored != db_type.as_str().as_bytes()) {
+		return Err(sp_blockchain::Error::Backend(
+			format!("Unexpected database type. Expected: {}", db_type.as_str()),
+		));
+	}
+
+	// copy into a temporary directory first, so an interrupted migration is started over.
+	let migrating = to.with_extension("migrating");
+	if migrating.exists() {
+		std::fs::remove_dir_all(&migrating).map_err(|e| backend_err(&e))?;
+	}
+	{
+		let target = parity_db::Db::open(&options(&migrating, db_type))
+			.map_err(|e| backend_err(&e))?;
+		for col in 0..NUM_COLUMNS {
+			let strip_prefix = col == columns::STATE && db_type == DatabaseType::Full;
+			let mut batch = Vec::with_capacity(MIGRATION_BATCH);
+			for (key, value) in source.iter(col) {
+				let key = if strip_prefix {
+					key[key.len().saturating_sub(crate::DB_HASH_LEN)..].to_vec()
+				} else {
+					key.into_vec()
+				};
+				batch.push((col as u8, key, Some(value.into_vec())));
+				if batch.len() == MIGRATION_BATCH {
+					target.commit(batch.drain(..)).map_err(|e| backend_err(&e))?;
+				}
+			}
+			target.commit(batch).map_err(|e| backend_err(&e))?;
+		}
+	}
+	std::fs::rename(&migrating, to).map_err(|e| backend_err(&e))?;
+
+	log::info!(target: "db", "Migrated the RocksDb database at {} to ParityDb", from.display());
+	Ok(())
+}
+
+/// Check the state of the RocksDB database can be stored by hash.
+#[cfg(any(feature = "with-kvdb-rocksdb", test))]
+fn check_migrated_state(
+	source: &kvdb_rocksdb::Database,
+	state_pruning: &sc_state_db::PruningMode,
+) -> sp_blockchain::Result<()> {
+	use kvdb::KeyValueDB;
+
+	// the meta keys of `sc-state-db`.
+	const PRUNING_MODE: &[u8] = b"mode";
+	const NON_CANONICAL_JOURNAL: &[u8] = b"noncanonical_journal";
+
+	let mode = source.get(columns::STATE_META, PRUNING_MODE)
+		.map_err(|e| sp_blockchain::Error::Backend(format!("{}", e)))?;
+	if mode.map_or(false, |mode| mode != state_pruning.id()) {
+		return Err(sp_blockchain::Error::Backend(
+			"Only archive databases can be migrated to ParityDb".into(),
+		));
+	}
+	if source.iter(columns::STATE_META).any(|(key, _)| key.ends_with(NON_CANONICAL_JOURNAL)) {
+		return Err(sp_blockchain::Error::Backend(
+			"The database has blocks that are not canonicalized yet, \
+			they must be finalized before migrating to ParityDb".into(),
+		));
+	}
+	Ok(())
+}
+
 impl<H: Clone> Database<H> for DbAdapter {
 	fn commit(&self, transaction: Transaction<H>) -> Result<(), DatabaseError> {
 		handle_err(self.0.commit(transaction.0.into_iter().map(|change|
@@ -69,3 +227,108 @@ impl<H: Clone> Database<H> for DbAdapter {
 		unimplemented!();
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use sc_client_api::backend::{Backend as _, BlockImportOperation as _, NewBlockState};
+	use sp_core::storage::Storage;
+	use sp_blockchain::HeaderBackend as _;
+	use sp_runtime::{generic::BlockId, testing::Header, traits::Header as _};
+	use sp_state_machine::Backend as _;
+	use sc_state_db::PruningMode;
+	use crate::{Backend, DatabaseSettings, DatabaseSettingsSrc, KeepBlocks, TransactionStorageMode};
+	use crate::tests::Block;
+	use super::{METADATA_FILE, add_missing_columns};
+
+	fn backend(
+		source: DatabaseSettingsSrc,
+		state_pruning: PruningMode,
+	) -> sp_blockchain::Result<Backend<Block>> {
+		Backend::new(DatabaseSettings {
+			state_cache_size: 0,
+			state_cache_child_ratio: None,
+			trie_cache_size: 0,
+			state_pruning,
+			source,
+			keep_blocks: KeepBlocks::All,
+			transaction_storage: TransactionStorageMode::BlockBody,
+			index_extrinsics: false,
+			compress_blocks: false,
+			trie_cache_warm_up: false,
+			cold_storage: None,
+			max_canonicalization_delay: None,
+		}, 0)
+	}
+
+	fn import_genesis(backend: &Backend<Block>, storage: Vec<(Vec<u8>, Vec<u8>)>) -> sp_core::H256 {
+		let mut op = backend.begin_operation().unwrap();
+		backend.begin_state_operation(&mut op, BlockId::Hash(Default::default())).unwrap();
+		let mut header = Header {
+			number: 0,
+			parent_hash: Default::default(),
+			state_root: Default::default(),
+			digest: Default::default(),
+			extrinsics_root: Default::default(),
+		};
+		header.state_root = op.old_state.storage_root(
+			storage.iter().map(|(key, value)| (&key[..], Some(&value[..])))
+		).0.into();
+		op.reset_storage(Storage {
+			top: storage.into_iter().collect(),
+			children_default: Default::default(),
+		}).unwrap();
+		op.set_block_data(header.clone(), Some(vec![]), None, NewBlockState::Final).unwrap();
+		backend.commit_operation(op).unwrap();
+		header.hash()
+	}
+
+	#[test]
+	fn migrates_archive_rocksdb_database() {
+		let dir = tempfile::TempDir::new().unwrap();
+		let rocksdb = |dir: &tempfile::TempDir| DatabaseSettingsSrc::RocksDb {
+			path: dir.path().join("db"),
+			cache_size: 16,
+		};
+		let parity_db = |dir: &tempfile::TempDir| DatabaseSettingsSrc::ParityDb {
+			path: dir.path().join("paritydb"),
+			migrate_from: Some(dir.path().join("db")),
+		};
+		let storage = vec![(vec![1, 3, 5], vec![2, 4, 6]), (vec![1, 2, 3], vec![9, 9, 9])];
+		let archive = backend(rocksdb(&dir), PruningMode::ArchiveAll).unwrap();
+		let hash = import_genesis(&archive, storage);
+		drop(archive);
+
+		let parity_db = backend(parity_db(&dir), PruningMode::ArchiveAll).unwrap();
+		assert_eq!(parity_db.blockchain().hash(0).unwrap(), Some(hash));
+		let state = parity_db.state_at(BlockId::Hash(hash)).unwrap();
+		assert_eq!(state.storage(&[1, 3, 5]).unwrap(), Some(vec![2, 4, 6]));
+		assert_eq!(state.storage(&[1, 2, 3]).unwrap(), Some(vec![9, 9, 9]));
+		assert_eq!(state.storage(&[5, 5, 5]).unwrap(), None);
+		assert!(!dir.path().join("paritydb.migrating").exists());
+
+		let pruned = tempfile::TempDir::new().unwrap();
+		let pruned_backend = backend(rocksdb(&pruned), PruningMode::keep_blocks(256)).unwrap();
+		import_genesis(&pruned_backend, vec![(vec![1], vec![1])]);
+		drop(pruned_backend);
+		assert!(backend(parity_db(&pruned), PruningMode::ArchiveAll).is_err());
+	}
+
+	#[test]
+	fn adds_the_extrinsic_index_column() {
+		let dir = tempfile::TempDir::new().unwrap();
+		let metadata = |columns: u32| (0..columns)
+			.map(|col| format!("col{}=options{}\n", col, if col == 1 { "[state]" } else { "" }))
+			.fold("version=4\nsalt=00\n".to_owned(), |metadata, column| metadata + &column);
+		let read = || std::fs::read_to_string(dir.path().join(METADATA_FILE)).unwrap();
+
+		add_missing_columns(dir.path()).unwrap();
+		assert!(!dir.path().join(METADATA_FILE).exists());
+
+		std::fs::write(dir.path().join(METADATA_FILE), metadata(12)).unwrap();
+		add_missing_columns(dir.path()).unwrap();
+		assert_eq!(read(), metadata(13));
+
+		add_missing_columns(dir.path()).unwrap();
+		assert_eq!(read(), metadata(13));
+	}
+}
@@ -0,0 +1,328 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Snapshots of the database, taken while the node keeps running.
+//!
+//! A snapshot is a RocksDB database holding a copy of every column of a RocksDB database. The
+//! columns are read from a secondary instance of the database, a consistent view of the database
+//! as of the time it is opened that the node keeps writing to meanwhile. A snapshot is restored
+//! by replacing the RocksDB database of a node with it, once its genesis is checked against the
+//! one of the chain.
+//!
+//! The cold database of the node, if any, is copied along in the `cold` directory of the
+//! snapshot, and restored as the cold database of the node.
+
+use std::{fs, path::Path};
+
+use kvdb::{DBTransaction, KeyValueDB};
+use kvdb_rocksdb::DatabaseConfig;
+use sp_runtime::traits::Block as BlockT;
+use crate::{
+	ColdStorageSettings, DatabaseSettings, DatabaseSettingsSrc,
+	utils::{COLUMN_META, NUM_COLUMNS, meta_keys},
+};
+
+/// Number of entries written to the snapshot per transaction.
+const SNAPSHOT_BATCH: usize = 10_000;
+
+/// The directory of the copy of the cold database, in the snapshot.
+const COLD_SNAPSHOT: &str = "cold";
+
+fn backend_err(e: impl std::fmt::Display) -> sp_blockchain::Error {
+	sp_blockchain::Error::Backend(format!("{}", e))
+}
+
+/// Returns `true` if there is nothing at the given path.
+fn is_empty(path: &Path) -> bool {
+	path.read_dir().map_or(true, |mut entries| entries.next().is_none())
+}
+
+fn path_str(path: &Path) -> sp_blockchain::Result<&str> {
+	path.to_str().ok_or_else(|| backend_err(format!("Invalid path {}", path.display())))
+}
+
+/// Write a snapshot of the database of `settings`, and of its cold database if any, to the
+/// directory at `path`, which must be empty. Both must be RocksDB databases.
+///
+/// The databases are read through secondary instances, so they may be in use by a running node.
+pub fn snapshot_database(settings: &DatabaseSettings, path: &Path) -> sp_blockchain::Result<()> {
+	let db_path = match &settings.source {
+		DatabaseSettingsSrc::RocksDb { path, .. } => path,
+		_ => return Err(backend_err("Snapshots can only be taken of RocksDb databases")),
+	};
+	let cold_db_path = match settings.cold_storage.as_ref().map(|cold| &cold.source) {
+		Some(DatabaseSettingsSrc::RocksDb { path, .. }) => Some(path.as_path()),
+		Some(_) => return Err(backend_err("Snapshots can only be taken of RocksDb cold databases")),
+		None => None,
+	};
+	write_snapshot(db_path, cold_db_path, path)
+}
+
+/// Returns the settings to open the snapshot at `snapshot` of the database of `settings` as a
+/// database on its own, e.g. to write to it without changing the database of the node.
+pub fn snapshot_settings(
+	settings: DatabaseSettings,
+	snapshot: &Path,
+) -> sp_blockchain::Result<DatabaseSettings> {
+	let cache_size = match settings.source {
+		DatabaseSettingsSrc::RocksDb { cache_size, .. } => cache_size,
+		_ => return Err(backend_err("Snapshots can only be taken of RocksDb databases")),
+	};
+	let cold_storage = settings.cold_storage.map(|cold| ColdStorageSettings {
+		source: DatabaseSettingsSrc::RocksDb {
+			path: snapshot.join(COLD_SNAPSHOT),
+			cache_size: match cold.source {
+				DatabaseSettingsSrc::RocksDb { cache_size, .. } => cache_size,
+				_ => cache_size,
+			},
+		},
+		hot_blocks: cold.hot_blocks,
+	});
+	Ok(DatabaseSettings {
+		source: DatabaseSettingsSrc::RocksDb { path: snapshot.to_owned(), cache_size },
+		cold_storage,
+		..settings
+	})
+}
+
+/// Write a snapshot of the RocksDB database at `db_path`, and of the RocksDB cold database at
+/// `cold_db_path` if any, to the directory at `path`, which must be empty.
+///
+/// The databases may keep being written to while the snapshot is taken.
+pub(crate) fn write_snapshot(
+	db_path: &Path,
+	cold_db_path: Option<&Path>,
+	path: &Path,
+) -> sp_blockchain::Result<()> {
+	if !is_empty(path) {
+		return Err(backend_err(format!("Snapshot directory {} is not empty", path.display())));
+	}
+
+	// write to a temporary directory first, so interrupted snapshots aren't mistaken for
+	// complete ones.
+	let partial = path.with_extension("partial");
+	let secondary = path.with_extension("secondary");
+	for leftover in &[&partial, &secondary] {
+		if leftover.exists() {
+			fs::remove_dir_all(leftover).map_err(backend_err)?;
+		}
+	}
+	copy_database(db_path, &secondary, &partial)?;
+	// the blocks are written to the cold database before being removed from the main one, so
+	// the blocks moved while the main database is copied are in the later copy of the cold one.
+	if let Some(cold_db_path) = cold_db_path {
+		copy_database(cold_db_path, &secondary, &partial.join(COLD_SNAPSHOT))?;
+	}
+	crate::upgrade::update_version(&partial)?;
+	if path.exists() {
+		fs::remove_dir(path).map_err(backend_err)?;
+	}
+	fs::rename(&partial, path).map_err(backend_err)
+}
+
+/// Copy every column of the RocksDB database at `db_path` to a new database at `target`, through
+/// a secondary instance at `secondary`.
+fn copy_database(db_path: &Path, secondary: &Path, target: &Path) -> sp_blockchain::Result<()> {
+	{
+		let source = kvdb_rocksdb::Database::open(
+			&DatabaseConfig {
+				secondary: Some(path_str(secondary)?.to_owned()),
+				..DatabaseConfig::with_columns(NUM_COLUMNS)
+			},
+			path_str(db_path)?,
+		).map_err(backend_err)?;
+		let copy = kvdb_rocksdb::Database::open(
+			&DatabaseConfig::with_columns(NUM_COLUMNS),
+			path_str(target)?,
+		).map_err(backend_err)?;
+		for col in 0..NUM_COLUMNS {
+			let mut transaction = DBTransaction::new();
+			for (key, value) in source.iter(col) {
+				transaction.put_vec(col, &key, value.into_vec());
+				if transaction.ops.len() == SNAPSHOT_BATCH {
+					copy.write(std::mem::take(&mut transaction)).map_err(backend_err)?;
+				}
+			}
+			copy.write(transaction).map_err(backend_err)?;
+		}
+	}
+	fs::remove_dir_all(secondary).map_err(backend_err)
+}
+
+/// Restore the snapshot at `snapshot` as the database of `settings`, which must be an empty
+/// RocksDB database. The cold database of the snapshot, if any, is restored as the cold database
+/// of `settings`, which must be an empty RocksDB database too.
+///
+/// The snapshot is rejected unless its genesis block is `genesis_hash`. The immutable files of
+/// the snapshot are hard linked when possible, the others are copied.
+pub fn restore_snapshot<Block: BlockT>(
+	snapshot: &Path,
+	settings: &DatabaseSettings,
+	genesis_hash: Block::Hash,
+) -> sp_blockchain::Result<()> {
+	let path = match &settings.source {
+		DatabaseSettingsSrc::RocksDb { path, .. } => path,
+		_ => return Err(backend_err("Snapshots can only be restored to RocksDb databases")),
+	};
+	if !is_empty(path) {
+		return Err(backend_err(format!("Database directory {} is not empty", path.display())));
+	}
+	let cold_snapshot = snapshot.join(COLD_SNAPSHOT);
+	let cold_path = match settings.cold_storage.as_ref().map(|cold| &cold.source) {
+		_ if !cold_snapshot.exists() => None,
+		Some(DatabaseSettingsSrc::RocksDb { path, .. }) if is_empty(path) => Some(path),
+		Some(DatabaseSettingsSrc::RocksDb { path, .. }) => return Err(backend_err(
+			format!("Cold database directory {} is not empty", path.display()),
+		)),
+		Some(_) => return Err(backend_err(
+			"Snapshots can only be restored to RocksDb cold databases",
+		)),
+		None => return Err(backend_err(
+			"The snapshot has a cold database, but no cold database is set for the node",
+		)),
+	};
+
+	{
+		let source = kvdb_rocksdb::Database::open(
+			&DatabaseConfig::with_columns(NUM_COLUMNS),
+			path_str(snapshot)?,
+		).map_err(backend_err)?;
+		let genesis = source.get(COLUMN_META, meta_keys::GENESIS_HASH).map_err(backend_err)?;
+		if genesis.as_deref() != Some(genesis_hash.as_ref()) {
+			return Err(backend_err("The snapshot is not a snapshot of this chain"));
+		}
+	}
+
+	restore_files(snapshot, path)?;
+	if let Some(cold_path) = cold_path {
+		restore_files(&cold_snapshot, cold_path)?;
+	}
+	Ok(())
+}
+
+/// Link or copy the files of the database at `snapshot` to `path`, leaving out the directories.
+fn restore_files(snapshot: &Path, path: &Path) -> sp_blockchain::Result<()> {
+	fs::create_dir_all(path).map_err(backend_err)?;
+	for entry in fs::read_dir(snapshot).map_err(backend_err)? {
+		let entry = entry.map_err(backend_err)?;
+		if entry.file_type().map_err(backend_err)?.is_dir() {
+			continue;
+		}
+		let target = path.join(entry.file_name());
+		let is_table = entry.path().extension().map_or(false, |extension| extension == "sst");
+		if !is_table || fs::hard_link(entry.path(), &target).is_err() {
+			fs::copy(entry.path(), &target).map_err(backend_err)?;
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc_client_api::backend::Backend as _;
+	use sp_blockchain::{Backend as _, HeaderBackend as _};
+	use sc_state_db::PruningMode;
+	use sp_runtime::{generic::BlockId, testing::ExtrinsicWrapper};
+	use crate::{Backend, ColdStorageSettings, KeepBlocks, TransactionStorageMode};
+	use crate::tests::{Block, insert_block, insert_header};
+
+	fn settings(path: &Path) -> DatabaseSettings {
+		DatabaseSettings {
+			state_cache_size: 0,
+			state_cache_child_ratio: None,
+			trie_cache_size: 0,
+			state_pruning: PruningMode::ArchiveAll,
+			source: DatabaseSettingsSrc::RocksDb { path: path.into(), cache_size: 16 },
+			keep_blocks: KeepBlocks::All,
+			transaction_storage: TransactionStorageMode::BlockBody,
+			index_extrinsics: false,
+			compress_blocks: false,
+			trie_cache_warm_up: false,
+			cold_storage: None,
+			max_canonicalization_delay: None,
+		}
+	}
+
+	#[test]
+	fn restores_snapshots_of_the_chain() {
+		let dir = tempfile::TempDir::new().unwrap();
+		let (genesis, best) = {
+			let backend = Backend::<Block>::new(settings(&dir.path().join("db")), 0).unwrap();
+			let genesis = insert_header(&backend, 0, Default::default(), None, Default::default());
+			let best = insert_header(&backend, 1, genesis, None, Default::default());
+			backend.snapshot(&dir.path().join("snapshot")).unwrap();
+			insert_header(&backend, 2, best, None, Default::default());
+			(genesis, best)
+		};
+
+		let restored = settings(&dir.path().join("restored"));
+		let snapshot = dir.path().join("snapshot");
+		assert!(restore_snapshot::<Block>(&snapshot, &restored, Default::default()).is_err());
+		restore_snapshot::<Block>(&snapshot, &restored, genesis).unwrap();
+		assert!(restore_snapshot::<Block>(&snapshot, &restored, genesis).is_err());
+
+		let backend = Backend::<Block>::new(restored, 0).unwrap();
+		assert_eq!(backend.blockchain().info().best_hash, best);
+		assert_eq!(backend.blockchain().info().genesis_hash, genesis);
+	}
+
+	#[test]
+	fn snapshots_include_the_cold_database() {
+		let dir = tempfile::TempDir::new().unwrap();
+		let settings_with_cold = |name: &str| DatabaseSettings {
+			cold_storage: Some(ColdStorageSettings {
+				source: DatabaseSettingsSrc::RocksDb {
+					path: dir.path().join(name).join("cold"),
+					cache_size: 16,
+				},
+				hot_blocks: 0,
+			}),
+			..settings(&dir.path().join(name).join("db"))
+		};
+		let (genesis, block) = {
+			let backend = Backend::<Block>::new(settings_with_cold("node"), 0).unwrap();
+			let body = vec![ExtrinsicWrapper::from(0u64)];
+			let genesis = insert_block(
+				&backend, 0, Default::default(), None, Default::default(), body,
+			);
+			let body = vec![ExtrinsicWrapper::from(1u64)];
+			let block = insert_block(&backend, 1, genesis, None, Default::default(), body);
+			backend.finalize_block(BlockId::Hash(block), None).unwrap();
+			while backend.migrate_cold_blocks(1).unwrap() {}
+			backend.snapshot(&dir.path().join("snapshot")).unwrap();
+			(genesis, block)
+		};
+		let snapshot = dir.path().join("snapshot");
+		assert!(snapshot.join(COLD_SNAPSHOT).exists());
+
+		// the cold database of the snapshot can only be restored as a cold database.
+		let restored = settings(&dir.path().join("restored").join("db"));
+		assert!(restore_snapshot::<Block>(&snapshot, &restored, genesis).is_err());
+
+		restore_snapshot::<Block>(&snapshot, &settings_with_cold("restored"), genesis).unwrap();
+		let backend = Backend::<Block>::new(settings_with_cold("restored"), 0).unwrap();
+		for (hash, number) in &[(genesis, 0u64), (block, 1)] {
+			assert_eq!(
+				backend.blockchain().body(BlockId::Hash(*hash)).unwrap(),
+				Some(vec![ExtrinsicWrapper::from(*number)]),
+			);
+		}
+		assert!(!dir.path().join("restored").join("db").join(COLD_SNAPSHOT).exists());
+	}
+}
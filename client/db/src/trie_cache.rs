@@ -0,0 +1,258 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Shared cache of the trie nodes of the state.
+//!
+//! The nodes are stored by hash, so they are the same in every block that references them
+//! and stay in the cache across blocks, unlike the values of the state cache that have to be
+//! invalidated when they are modified.
+//!
+//! The database keys of the cached nodes can be saved, so the nodes are read into the cache
+//! again when the database is reopened instead of on their first use.
+//!
+//! The values read from the state are cached as well, by the state root they were read at, so
+//! the value of a hot key is only looked up in the trie once for all the reads of the block
+//! import, the RPC and the block authoring at the same state. A quarter of the size of the cache
+//! is dedicated to the values.
+
+use std::{hash::Hash, sync::atomic::{AtomicU64, Ordering}};
+use linked_hash_map::LinkedHashMap;
+use parking_lot::Mutex;
+use sp_trie::DBValue;
+
+/// The number of reads of the trie node cache since they were last taken.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TrieCacheStats {
+	/// Reads of nodes found in the cache.
+	pub hits: u64,
+	/// Reads of nodes missing from the cache.
+	pub misses: u64,
+	/// Reads of values found in the cache.
+	pub value_hits: u64,
+	/// Reads of values missing from the cache.
+	pub value_misses: u64,
+}
+
+/// Divisor of the cache size giving the size dedicated to the values.
+const VALUE_CACHE_DIVISOR: usize = 4;
+
+/// A cached node, with the prefix of its database key.
+struct Node {
+	value: DBValue,
+	prefix: Vec<u8>,
+}
+
+impl Node {
+	fn size<H: AsRef<[u8]>>(&self, hash: &H) -> usize {
+		hash.as_ref().len() + self.prefix.len() + self.value.len()
+	}
+}
+
+struct Nodes<H: Hash + Eq> {
+	lru: LinkedHashMap<H, Node>,
+	used: usize,
+}
+
+/// The key of a cached value, the state root it was read at and the storage key.
+type ValueKey<H> = (H, Vec<u8>);
+
+struct Values<H: Hash + Eq> {
+	lru: LinkedHashMap<ValueKey<H>, Option<Vec<u8>>>,
+	used: usize,
+}
+
+fn value_size<H: AsRef<[u8]>>((root, key): &ValueKey<H>, value: &Option<Vec<u8>>) -> usize {
+	root.as_ref().len() + key.len() + value.as_ref().map_or(0, |value| value.len())
+}
+
+/// Size-bounded LRU cache of trie nodes and values, shared by all the blocks.
+pub struct TrieNodeCache<H: Hash + Eq> {
+	nodes: Mutex<Nodes<H>>,
+	values: Mutex<Values<H>>,
+	limit: usize,
+	value_limit: usize,
+	hits: AtomicU64,
+	misses: AtomicU64,
+	value_hits: AtomicU64,
+	value_misses: AtomicU64,
+}
+
+impl<H: Hash + Eq + AsRef<[u8]>> TrieNodeCache<H> {
+	/// New cache of at most `limit` bytes of nodes and values.
+	pub fn new(limit: usize) -> Self {
+		let value_limit = limit / VALUE_CACHE_DIVISOR;
+		TrieNodeCache {
+			nodes: Mutex::new(Nodes { lru: LinkedHashMap::new(), used: 0 }),
+			values: Mutex::new(Values { lru: LinkedHashMap::new(), used: 0 }),
+			limit: limit - value_limit,
+			value_limit,
+			hits: AtomicU64::new(0),
+			misses: AtomicU64::new(0),
+			value_hits: AtomicU64::new(0),
+			value_misses: AtomicU64::new(0),
+		}
+	}
+
+	/// Returns the node with the given hash, if it is cached.
+	pub fn get(&self, hash: &H) -> Option<DBValue> {
+		let node = self.nodes.lock().lru.get_refresh(hash).map(|node| node.value.clone());
+		let counter = if node.is_some() { &self.hits } else { &self.misses };
+		counter.fetch_add(1, Ordering::Relaxed);
+		node
+	}
+
+	/// Cache the node with the given hash, evicting the least recently used ones if the
+	/// cache is full.
+	///
+	/// The database key of the node is `prefix` followed by the hash.
+	pub fn insert(&self, hash: H, prefix: Vec<u8>, value: DBValue) {
+		let node = Node { value, prefix };
+		let size = node.size(&hash);
+		if size > self.limit {
+			return;
+		}
+		let mut nodes = self.nodes.lock();
+		if nodes.lru.contains_key(&hash) {
+			return;
+		}
+		nodes.used += size;
+		nodes.lru.insert(hash, node);
+		while nodes.used > self.limit {
+			match nodes.lru.pop_front() {
+				Some((hash, node)) => nodes.used -= node.size(&hash),
+				None => break,
+			}
+		}
+	}
+
+	/// Returns the value of the given key at the given state root, if it is cached.
+	///
+	/// `Some(None)` is returned for keys cached as missing from the state.
+	pub fn get_value(&self, root: &H, key: &[u8]) -> Option<Option<Vec<u8>>>
+		where H: Clone
+	{
+		let value = self.values.lock().lru.get_refresh(&(root.clone(), key.to_vec()))
+			.map(|value| value.clone());
+		let counter = if value.is_some() { &self.value_hits } else { &self.value_misses };
+		counter.fetch_add(1, Ordering::Relaxed);
+		value
+	}
+
+	/// Cache the value of the given key at the given state root, `None` if the key is missing
+	/// from the state, evicting the least recently used values if the cache is full.
+	pub fn insert_value(&self, root: H, key: Vec<u8>, value: Option<Vec<u8>>) {
+		let key = (root, key);
+		let size = value_size(&key, &value);
+		if size > self.value_limit {
+			return;
+		}
+		let mut values = self.values.lock();
+		if values.lru.contains_key(&key) {
+			return;
+		}
+		values.used += size;
+		values.lru.insert(key, value);
+		while values.used > self.value_limit {
+			match values.lru.pop_front() {
+				Some((key, value)) => values.used -= value_size(&key, &value),
+				None => break,
+			}
+		}
+	}
+
+	/// Returns the database keys of the cached nodes, the most recently used last.
+	pub fn keys(&self) -> Vec<Vec<u8>> {
+		self.nodes.lock().lru.iter()
+			.map(|(hash, node)| [&node.prefix[..], hash.as_ref()].concat())
+			.collect()
+	}
+
+	/// Returns the size of the cached nodes and values in bytes.
+	pub fn used_size(&self) -> usize {
+		self.nodes.lock().used + self.values.lock().used
+	}
+
+	/// Returns the number of reads since the last call and resets them.
+	pub fn take_stats(&self) -> TrieCacheStats {
+		TrieCacheStats {
+			hits: self.hits.swap(0, Ordering::Relaxed),
+			misses: self.misses.swap(0, Ordering::Relaxed),
+			value_hits: self.value_hits.swap(0, Ordering::Relaxed),
+			value_misses: self.value_misses.swap(0, Ordering::Relaxed),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn evicts_the_least_recently_used_nodes() {
+		// room for two nodes of 4 bytes with their 4 bytes hash, the quarter left is for values.
+		let cache = TrieNodeCache::new(21);
+		cache.insert([1u8; 4], Vec::new(), vec![1; 4]);
+		cache.insert([2u8; 4], Vec::new(), vec![2; 4]);
+		assert_eq!(cache.get(&[1; 4]), Some(vec![1; 4]));
+
+		cache.insert([3u8; 4], Vec::new(), vec![3; 4]);
+		assert_eq!(cache.get(&[2; 4]), None);
+		assert_eq!(cache.get(&[1; 4]), Some(vec![1; 4]));
+		assert_eq!(cache.get(&[3; 4]), Some(vec![3; 4]));
+		assert_eq!(cache.used_size(), 16);
+
+		cache.insert([4u8; 4], Vec::new(), vec![4; 20]);
+		assert_eq!(cache.get(&[4; 4]), None);
+		assert_eq!(
+			cache.take_stats(),
+			TrieCacheStats { hits: 3, misses: 2, ..Default::default() },
+		);
+		assert_eq!(cache.take_stats(), TrieCacheStats::default());
+	}
+
+	#[test]
+	fn caches_the_values_by_state_root() {
+		// room for 16 bytes of values, the keys of 2 bytes at roots of 4 bytes included.
+		let cache = TrieNodeCache::new(64);
+		cache.insert_value([1u8; 4], vec![1; 2], Some(vec![1; 2]));
+		cache.insert_value([1u8; 4], vec![2; 2], None);
+		assert_eq!(cache.get_value(&[1; 4], &[2; 2]), Some(None));
+		assert_eq!(cache.get_value(&[1; 4], &[1; 2]), Some(Some(vec![1; 2])));
+		assert_eq!(cache.get_value(&[2; 4], &[1; 2]), None);
+
+		cache.insert_value([2u8; 4], vec![1; 2], Some(vec![3; 2]));
+		assert_eq!(cache.get_value(&[2; 4], &[1; 2]), Some(Some(vec![3; 2])));
+		assert_eq!(cache.get_value(&[1; 4], &[1; 2]), Some(Some(vec![1; 2])));
+		assert_eq!(cache.get_value(&[1; 4], &[2; 2]), None);
+		assert_eq!(cache.used_size(), 16);
+		assert_eq!(
+			cache.take_stats(),
+			TrieCacheStats { value_hits: 4, value_misses: 2, ..Default::default() },
+		);
+	}
+
+	#[test]
+	fn returns_the_keys_of_the_nodes_by_use() {
+		let cache = TrieNodeCache::new(1024);
+		cache.insert([1u8; 4], vec![9], vec![1; 4]);
+		cache.insert([2u8; 4], Vec::new(), vec![2; 4]);
+		cache.get(&[1; 4]);
+
+		assert_eq!(cache.keys(), vec![vec![2; 4], vec![9, 1, 1, 1, 1]]);
+	}
+}
@@ -30,10 +30,12 @@ use kvdb_rocksdb::{Database, DatabaseConfig};
 const VERSION_FILE_NAME: &'static str = "db_version";
 
 /// Current db version.
-const CURRENT_VERSION: u32 = 2;
+const CURRENT_VERSION: u32 = 3;
 
 /// Number of columns in v1.
 const V1_NUM_COLUMNS: u32 = 11;
+/// Number of columns in v2.
+const V2_NUM_COLUMNS: u32 = 12;
 
 /// Upgrade database to current version.
 pub fn upgrade_db<Block: BlockT>(db_path: &Path, db_type: DatabaseType) -> sp_blockchain::Result<()> {
@@ -42,7 +44,11 @@ pub fn upgrade_db<Block: BlockT>(db_path: &Path, db_type: DatabaseType) -> sp_bl
 		let db_version = current_version(db_path)?;
 		match db_version {
 			0 => Err(sp_blockchain::Error::Backend(format!("Unsupported database version: {}", db_version)))?,
-			1 => migrate_1_to_2::<Block>(db_path, db_type)?,
+			1 => {
+				migrate_1_to_2::<Block>(db_path, db_type)?;
+				migrate_2_to_3::<Block>(db_path, db_type)?
+			},
+			2 => migrate_2_to_3::<Block>(db_path, db_type)?,
 			CURRENT_VERSION => (),
 			_ => Err(sp_blockchain::Error::Backend(format!("Future database version: {}", db_version)))?,
 		}
@@ -62,6 +68,17 @@ fn migrate_1_to_2<Block: BlockT>(db_path: &Path, _db_type: DatabaseType) -> sp_b
 	db.add_column().map_err(db_err)
 }
 
+/// Migration from version2 to version3:
+/// 1) the number of columns has changed from 12 to 13;
+/// 2) extrinsic index column is added;
+fn migrate_2_to_3<Block: BlockT>(db_path: &Path, _db_type: DatabaseType) -> sp_blockchain::Result<()> {
+	let db_path = db_path.to_str()
+		.ok_or_else(|| sp_blockchain::Error::Backend("Invalid database path".into()))?;
+	let db_cfg = DatabaseConfig::with_columns(V2_NUM_COLUMNS);
+	let db = Database::open(&db_cfg, db_path).map_err(db_err)?;
+	db.add_column().map_err(db_err)
+}
+
 /// Reads current database version from the file at given path.
 /// If the file does not exist returns 0.
 fn current_version(path: &Path) -> sp_blockchain::Result<u32> {
@@ -85,7 +102,7 @@ fn db_err(err: std::io::Error) -> sp_blockchain::Error {
 
 /// Writes current database version to the file.
 /// Creates a new file if the version file does not exist yet.
-fn update_version(path: &Path) -> sp_blockchain::Result<()> {
+pub(crate) fn update_version(path: &Path) -> sp_blockchain::Result<()> {
 	fs::create_dir_all(path).map_err(db_err)?;
 	let mut file = fs::File::create(version_file_path(path)).map_err(db_err)?;
 	file.write_all(format!("{}", CURRENT_VERSION).as_bytes()).map_err(db_err)?;
@@ -118,10 +135,16 @@ mod tests {
 		crate::utils::open_database::<Block>(&DatabaseSettings {
 			state_cache_size: 0,
 			state_cache_child_ratio: None,
+			trie_cache_size: 0,
 			state_pruning: PruningMode::ArchiveAll,
 			source: DatabaseSettingsSrc::RocksDb { path: db_path.to_owned(), cache_size: 128 },
 			keep_blocks: KeepBlocks::All,
 			transaction_storage: TransactionStorageMode::BlockBody,
+			index_extrinsics: false,
+			compress_blocks: false,
+			trie_cache_warm_up: false,
+			cold_storage: None,
+			max_canonicalization_delay: None,
 		}, DatabaseType::Full).map(|_| ())
 	}
 
@@ -150,4 +173,13 @@ mod tests {
 			assert_eq!(current_version(db_path).unwrap(), CURRENT_VERSION);
 		}
 	}
+
+	#[test]
+	fn upgrade_from_2_to_3_works() {
+		let db_dir = tempfile::TempDir::new().unwrap();
+		let db_path = db_dir.path();
+		create_db(db_path, Some(2));
+		open_database(db_path).unwrap();
+		assert_eq!(current_version(db_path).unwrap(), CURRENT_VERSION);
+	}
 }
@@ -37,7 +37,7 @@ use crate::{DatabaseSettings, DatabaseSettingsSrc, Database, DbHash};
 /// Number of columns in the db. Must be the same for both full && light dbs.
 /// Otherwise RocksDb will fail to open database && check its type.
 #[cfg(any(feature = "with-kvdb-rocksdb", feature = "with-parity-db", feature = "test-helpers", test))]
-pub const NUM_COLUMNS: u32 = 12;
+pub const NUM_COLUMNS: u32 = 13;
 /// Meta column. The set of keys in the column is shared by full && light storages.
 pub const COLUMN_META: u32 = 0;
 
@@ -59,6 +59,12 @@ pub mod meta_keys {
 	pub const LEAF_PREFIX: &[u8; 4] = b"leaf";
 	/// Children prefix list key.
 	pub const CHILDREN_PREFIX: &[u8; 8] = b"children";
+	/// Version of the database schema.
+	pub const SCHEMA_VERSION: &[u8; 6] = b"schema";
+	/// Number of the first block whose body and justification may be stored uncompressed.
+	pub const COMPRESSED_BLOCKS: &[u8; 10] = b"compressed";
+	/// Database keys of the cached trie nodes, to read them into the cache on startup.
+	pub const TRIE_CACHE_KEYS: &[u8; 9] = b"triecache";
 }
 
 /// Database metadata.
@@ -276,7 +282,20 @@ pub fn open_database<Block: BlockT>(
 			return Err(db_open_error("with-kvdb-rocksdb"));
 		},
 		#[cfg(feature = "with-parity-db")]
-		DatabaseSettingsSrc::ParityDb { path } => {
+		DatabaseSettingsSrc::ParityDb { path, migrate_from } => {
+			let is_empty = path.read_dir().map_or(true, |mut d| d.next().is_none());
+			match migrate_from {
+				#[cfg(any(feature = "with-kvdb-rocksdb", test))]
+				Some(from) if is_empty => crate::parity_db::migrate_from_rocksdb::<Block>(
+					from,
+					path,
+					db_type,
+					&config.state_pruning,
+				)?,
+				#[cfg(not(any(feature = "with-kvdb-rocksdb", test)))]
+				Some(_) if is_empty => return Err(db_open_error("with-kvdb-rocksdb")),
+				_ => {},
+			}
 			crate::parity_db::open(&path, db_type)
 				.map_err(|e| sp_blockchain::Error::Backend(format!("{:?}", e)))?
 		},
@@ -287,6 +306,7 @@ pub fn open_database<Block: BlockT>(
 		DatabaseSettingsSrc::Custom(db) => db.clone(),
 	};
 
+	crate::migration::migrate(&*db, db_type)?;
 	check_database_type(&*db, db_type)?;
 
 	Ok(db)
@@ -100,6 +100,9 @@ pub enum Error {
 	#[error("Shared memory is not supported")]
 	SharedMemUnsupported,
 
+	#[error("Passive data segments are not supported")]
+	PassiveDataSegmentsUnsupported,
+
 	#[error("Imported globals are not supported yet")]
 	ImportedGlobalsUnsupported,
 
@@ -108,6 +111,64 @@ pub enum Error {
 
 	#[error("Invalid initializer expression provided {0}")]
 	InvalidInitializerExpression(String),
+
+	#[error("The runtime requires {0} heap pages, more than the {1} allowed for the call")]
+	HeapPagesLimitExceeded(u64, u64),
+
+	#[error("Host function {0} is provided by both `{1}` and `{2}`")]
+	ConflictingHostFunction(String, String, String),
+
+	#[error("Wasm execution trapped: {0}")]
+	AbortedDueToTrap(MessageWithBacktrace),
+
+	#[error("Runtime panicked: {0}")]
+	AbortedDueToPanic(MessageWithBacktrace),
+}
+
+/// The message a runtime call was aborted with, along with the wasm backtrace of the abort if
+/// the executor could capture it.
+#[derive(Debug)]
+pub struct MessageWithBacktrace {
+	/// The message of the trap or panic.
+	pub message: String,
+	/// The backtrace of the abort.
+	pub backtrace: Option<Backtrace>,
+}
+
+impl std::fmt::Display for MessageWithBacktrace {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "{}", self.message)?;
+		if let Some(ref backtrace) = self.backtrace {
+			write!(f, "\nWASM backtrace:\n{}", backtrace)?;
+		}
+		Ok(())
+	}
+}
+
+/// A wasm backtrace, innermost frame first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backtrace {
+	/// The frames of the backtrace.
+	pub frames: Vec<BacktraceFrame>,
+}
+
+/// A frame of a wasm backtrace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BacktraceFrame {
+	/// The index of the function in the module.
+	pub func_index: u32,
+	/// The name of the function, if the module embeds a name section.
+	pub func_name: Option<String>,
+}
+
+impl std::fmt::Display for Backtrace {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		for (i, frame) in self.frames.iter().enumerate() {
+			let name = frame.func_name.as_deref().unwrap_or("<unknown>");
+			writeln!(f, "{:>4}: {} (function #{})", i, name, frame.func_index)?;
+		}
+		Ok(())
+	}
 }
 
 impl wasmi::HostError for Error {}
@@ -145,6 +206,9 @@ pub enum WasmError {
 	InvalidHeapPages,
 	/// Instantiation error.
 	Instantiation(String),
+	/// The module uses WebAssembly proposals which are disabled, see `WasmFeatures`.
+	#[display(fmt = "The runtime uses the disabled WebAssembly proposals: {}", _0)]
+	DisabledFeatures(String),
 	/// Other error happenend.
 	Other(String),
 }
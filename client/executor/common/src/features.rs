@@ -0,0 +1,116 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! The WebAssembly proposals the runtimes may use beyond the MVP.
+
+use crate::error::WasmError;
+
+/// The WebAssembly proposals enabled for the runtimes.
+///
+/// A runtime using a disabled proposal fails to be created, so all the nodes of a network must
+/// enable the same proposals to agree on the blocks they can execute. The interpreter supports
+/// none of them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct WasmFeatures {
+	/// The bulk memory operations, e.g. `memory.copy` and `memory.fill`.
+	pub bulk_memory: bool,
+	/// The 128-bit SIMD instructions.
+	pub simd: bool,
+}
+
+impl WasmFeatures {
+	/// Every proposal.
+	pub const ALL: WasmFeatures = WasmFeatures { bulk_memory: true, simd: true };
+
+	/// Returns the names of the enabled proposals.
+	pub fn names(&self) -> Vec<&'static str> {
+		let mut names = Vec::new();
+		if self.bulk_memory {
+			names.push("bulk-memory");
+		}
+		if self.simd {
+			names.push("simd");
+		}
+		names
+	}
+
+	/// Returns `true` if no proposal is enabled.
+	pub fn is_empty(&self) -> bool {
+		*self == WasmFeatures::default()
+	}
+
+	/// Check that the given code only uses the enabled proposals.
+	///
+	/// Returns `WasmError::DisabledFeatures` naming the disabled proposals it uses otherwise.
+	/// The code that is invalid whatever the proposals is left to the execution method to reject.
+	pub fn check(&self, code: &[u8]) -> Result<(), WasmError> {
+		if validate(code, *self) || !validate(code, WasmFeatures::ALL) {
+			return Ok(());
+		}
+
+		let required = WasmFeatures {
+			bulk_memory: !validate(code, WasmFeatures { bulk_memory: false, ..WasmFeatures::ALL }),
+			simd: !validate(code, WasmFeatures { simd: false, ..WasmFeatures::ALL }),
+		};
+		let disabled = WasmFeatures {
+			bulk_memory: required.bulk_memory && !self.bulk_memory,
+			simd: required.simd && !self.simd,
+		};
+		Err(WasmError::DisabledFeatures(disabled.names().join(", ")))
+	}
+}
+
+/// Returns `true` if the code is valid with the given proposals.
+fn validate(code: &[u8], features: WasmFeatures) -> bool {
+	let mut validator = wasmparser::Validator::new();
+	validator.wasm_features(wasmparser::WasmFeatures {
+		bulk_memory: features.bulk_memory,
+		simd: features.simd,
+		..Default::default()
+	});
+	validator.validate_all(code).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn rejects_the_code_using_disabled_proposals() {
+		let code = wat::parse_str(r#"
+			(module
+				(memory 1)
+				(func (export "fill")
+					(memory.fill (i32.const 0) (i32.const 1) (i32.const 8))
+				)
+			)
+		"#).unwrap();
+
+		let bulk_memory = WasmFeatures { bulk_memory: true, simd: false };
+		assert!(bulk_memory.check(&code).is_ok());
+		assert!(WasmFeatures::ALL.check(&code).is_ok());
+		assert!(matches!(
+			WasmFeatures::default().check(&code),
+			Err(WasmError::DisabledFeatures(ref names)) if names == "bulk-memory"
+		));
+
+		let mvp = wat::parse_str("(module (memory 1))").unwrap();
+		assert!(WasmFeatures::default().check(&mvp).is_ok());
+		assert_eq!(bulk_memory.names(), vec!["bulk-memory"]);
+	}
+}
@@ -0,0 +1,80 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Versioning of the host functions.
+//!
+//! A runtime interface provides every version `n` of its function `foo` as a host function
+//! named `ext_<interface>_foo_version_<n>`, so the host can provide several versions of the same
+//! function side by side and every runtime imports the version it was built against.
+
+use sp_wasm_interface::Function;
+
+/// Split the name of a host function into the name of the function and its version, e.g.
+/// `("ext_misc_print_num", 1)` for `ext_misc_print_num_version_1`.
+///
+/// Returns `None` if the name doesn't carry any version.
+pub fn split_version(name: &str) -> Option<(&str, u32)> {
+	let index = name.rfind("_version_")?;
+	let version = name[index + "_version_".len()..].parse().ok()?;
+	Some((&name[..index], version))
+}
+
+/// Returns the versions of the function named like the host function `name` that the given
+/// host functions provide, in ascending order.
+pub fn provided_versions(name: &str, host_functions: &[&'static dyn Function]) -> Vec<u32> {
+	let function = match split_version(name) {
+		Some((function, _)) => function,
+		None => return Vec::new(),
+	};
+	let mut versions: Vec<_> = host_functions.iter()
+		.filter_map(|host_function| split_version(host_function.name()))
+		.filter(|(name, _)| *name == function)
+		.map(|(_, version)| version)
+		.collect();
+	versions.sort();
+	versions.dedup();
+	versions
+}
+
+/// Describe the host function `name` the runtime imports but the host doesn't provide, along
+/// with the versions of it the host provides, if any.
+pub fn describe_missing(name: &str, host_functions: &[&'static dyn Function]) -> String {
+	let versions = provided_versions(name, host_functions);
+	if versions.is_empty() {
+		name.into()
+	} else {
+		let versions = versions.iter().map(ToString::to_string).collect::<Vec<_>>();
+		format!("{} (the host provides the versions {})", name, versions.join(", "))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn splits_the_version_of_host_functions() {
+		assert_eq!(split_version("ext_misc_print_num_version_1"), Some(("ext_misc_print_num", 1)));
+		assert_eq!(
+			split_version("ext_hashing_twox_64_version_12"),
+			Some(("ext_hashing_twox_64", 12)),
+		);
+		assert_eq!(split_version("ext_misc_print_num"), None);
+		assert_eq!(split_version("ext_misc_print_num_version_"), None);
+	}
+}
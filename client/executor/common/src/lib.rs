@@ -22,6 +22,8 @@
 #![deny(unused_crate_dependencies)]
 
 pub mod error;
+pub mod features;
+pub mod host_functions;
 pub mod sandbox;
 pub mod util;
 pub mod wasm_runtime;
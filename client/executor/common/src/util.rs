@@ -17,14 +17,37 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 //! A set of utilities for resetting a wasm instance to its initial state.
+//!
+//! The modules are decoded with `wasmparser`, so the utilities work whatever the WebAssembly
+//! proposals the modules use.
 
 use crate::error::{self, Error};
-use std::mem;
-use parity_wasm::elements::{deserialize_buffer, DataSegment, Instruction, Module as RawModule};
+use std::ops::Range;
+use wasmparser::{
+	BinaryReader, BinaryReaderError, DataKind, DataSectionReader, GlobalSectionReader,
+	ImportSectionEntryType, ImportSectionReader, Operator,
+};
+
+/// The magic number and version of a module.
+const HEADER_SIZE: usize = 8;
+const CUSTOM_SECTION: u8 = 0;
+const IMPORT_SECTION: u8 = 2;
+const GLOBAL_SECTION: u8 = 6;
+const EXPORT_SECTION: u8 = 7;
+const DATA_SECTION: u8 = 11;
+/// The kind of the exports of globals.
+const GLOBAL_EXPORT: u8 = 3;
 
 /// A bunch of information collected from a WebAssembly module.
 pub struct WasmModuleInfo {
-	raw_module: RawModule,
+	/// The sections of the module.
+	sections: Vec<Section>,
+	/// The contents of the data section, if any.
+	data_section: Option<Vec<u8>>,
+	/// Whether the globals defined in the module are mutable.
+	declared_globals: Vec<bool>,
+	/// The number of imports of globals.
+	imported_globals: u32,
 }
 
 impl WasmModuleInfo {
@@ -32,36 +55,201 @@ impl WasmModuleInfo {
 	///
 	/// Returns `None` if the wasm code cannot be deserialized.
 	pub fn new(wasm_code: &[u8]) -> Option<Self> {
-		let raw_module: RawModule = deserialize_buffer(wasm_code).ok()?;
-		Some(Self { raw_module })
+		let mut info = WasmModuleInfo {
+			sections: sections(wasm_code)?,
+			data_section: None,
+			declared_globals: Vec::new(),
+			imported_globals: 0,
+		};
+		for section in &info.sections {
+			let data = &wasm_code[section.range.clone()];
+			match section.id {
+				IMPORT_SECTION => info.imported_globals = imported_globals(data).ok()?,
+				GLOBAL_SECTION => info.declared_globals = mutable_globals(data).ok()?,
+				DATA_SECTION => info.data_section = Some(data.to_vec()),
+				_ => {},
+			}
+		}
+		Some(info)
 	}
 
-	/// Extract the data segments from the given wasm code.
-	///
-	/// Returns `Err` if the given wasm code cannot be deserialized.
-	fn data_segments(&self) -> Vec<DataSegment> {
-		self.raw_module
-			.data_section()
-			.map(|ds| ds.entries())
-			.unwrap_or(&[])
-			.to_vec()
+	/// Extract the data segments from the given wasm code, by offset and contents.
+	fn data_segments(&self) -> error::Result<Vec<(u32, Vec<u8>)>> {
+		let section = match self.data_section {
+			Some(ref section) => section,
+			None => return Ok(Vec::new()),
+		};
+		DataSectionReader::new(section, 0)
+			.map_err(invalid_data)?
+			.into_iter()
+			.map(|segment| {
+				let segment = segment.map_err(invalid_data)?;
+				let init_expr = match segment.kind {
+					DataKind::Active { init_expr, .. } => init_expr,
+					// The passive segments are only copied by the runtime, and a dropped segment
+					// couldn't be restored for the next call.
+					DataKind::Passive => return Err(Error::PassiveDataSegmentsUnsupported),
+				};
+
+				let mut operators = init_expr.get_operators_reader();
+				let offset = match operators.read().map_err(invalid_data)? {
+					Operator::I32Const { value } => value as u32,
+					Operator::GlobalGet { .. } => {
+						// In a valid wasm file, initializer expressions can only refer imported
+						// globals.
+						//
+						// At the moment of writing the Substrate Runtime Interface does not provide
+						// any globals. There is nothing that prevents us from supporting this
+						// if/when we gain those.
+						return Err(Error::ImportedGlobalsUnsupported);
+					}
+					operator => {
+						return Err(Error::InvalidInitializerExpression(format!("{:?}", operator)))
+					}
+				};
+				// [op, End]
+				match operators.read().map_err(invalid_data)? {
+					Operator::End if operators.eof() => {},
+					_ => return Err(Error::InitializerHasTooManyExpressions),
+				}
+
+				Ok((offset, segment.data.to_vec()))
+			})
+			.collect()
 	}
 
 	/// The number of globals defined in locally in this module.
 	pub fn declared_globals_count(&self) -> u32 {
-		self.raw_module
-			.global_section()
-			.map(|gs| gs.entries().len() as u32)
-			.unwrap_or(0)
+		self.declared_globals.len() as u32
 	}
 
 	/// The number of imports of globals.
 	pub fn imported_globals_count(&self) -> u32 {
-		self.raw_module
-			.import_section()
-			.map(|is| is.globals() as u32)
-			.unwrap_or(0)
+		self.imported_globals
 	}
+
+	/// Export the mutable globals defined in the given wasm code, which this information was
+	/// collected from, as `{prefix}{index}`, so their values can be saved and restored.
+	///
+	/// Returns `None` if the export section of the wasm code cannot be deserialized.
+	pub fn export_mutable_globals(&self, wasm_code: &[u8], prefix: &str) -> Option<Vec<u8>> {
+		let mut exports = Vec::new();
+		let mut count = 0;
+		let mutable_globals = self.declared_globals.iter()
+			.enumerate()
+			.filter(|(_, mutable)| **mutable);
+		for (index, _) in mutable_globals {
+			let index = self.imported_globals + index as u32;
+			let name = format!("{}{}", prefix, index);
+			write_u32(&mut exports, name.len() as u32);
+			exports.extend_from_slice(name.as_bytes());
+			exports.push(GLOBAL_EXPORT);
+			write_u32(&mut exports, index);
+			count += 1;
+		}
+		if count == 0 {
+			return Some(wasm_code.to_vec());
+		}
+
+		// the export section follows the sections with lower ids, which are all known.
+		let position = self.sections.iter()
+			.position(|section| section.id >= EXPORT_SECTION)
+			.unwrap_or(self.sections.len());
+		let (before, after) = self.sections.split_at(position);
+		let ((existing, entries), after) = match after.first() {
+			Some(section) if section.id == EXPORT_SECTION => {
+				let data = &wasm_code[section.range.clone()];
+				let mut reader = BinaryReader::new(data);
+				let existing = reader.read_var_u32().ok()?;
+				((existing, &data[reader.current_position()..]), &after[1..])
+			},
+			_ => ((0, &[][..]), after),
+		};
+		let mut export_section = Vec::new();
+		write_u32(&mut export_section, existing + count);
+		export_section.extend_from_slice(entries);
+		export_section.extend_from_slice(&exports);
+
+		let mut code = wasm_code[..HEADER_SIZE].to_vec();
+		for section in before {
+			write_section(&mut code, section.id, &wasm_code[section.range.clone()]);
+		}
+		write_section(&mut code, EXPORT_SECTION, &export_section);
+		for section in after {
+			write_section(&mut code, section.id, &wasm_code[section.range.clone()]);
+		}
+		Some(code)
+	}
+}
+
+/// Returns the contents of the custom section of the given wasm code with the given name.
+pub fn custom_section<'a>(wasm_code: &'a [u8], name: &str) -> Option<&'a [u8]> {
+	sections(wasm_code)?
+		.into_iter()
+		.filter(|section| section.id == CUSTOM_SECTION)
+		.find_map(|section| {
+			let data = &wasm_code[section.range];
+			let mut reader = BinaryReader::new(data);
+			match reader.read_string() {
+				Ok(section_name) if section_name == name =>
+					Some(&data[reader.current_position()..]),
+				_ => None,
+			}
+		})
+}
+
+/// A section of a module, by id and range of its contents in the code of the module.
+struct Section {
+	id: u8,
+	range: Range<usize>,
+}
+
+/// Split the given wasm code into its sections, without decoding them.
+fn sections(code: &[u8]) -> Option<Vec<Section>> {
+	if code.len() < HEADER_SIZE || code[..4] != *b"\0asm" {
+		return None;
+	}
+	let mut reader = BinaryReader::new_with_offset(&code[HEADER_SIZE..], HEADER_SIZE);
+	let mut sections = Vec::new();
+	while !reader.eof() {
+		let id = reader.read_u8().ok()? as u8;
+		let size = reader.read_var_u32().ok()? as usize;
+		let start = reader.original_position();
+		reader.skip_bytes(size).ok()?;
+		sections.push(Section { id, range: start..start + size });
+	}
+	Some(sections)
+}
+
+fn imported_globals(section: &[u8]) -> Result<u32, BinaryReaderError> {
+	let mut count = 0;
+	for import in ImportSectionReader::new(section, 0)? {
+		if let ImportSectionEntryType::Global(_) = import?.ty {
+			count += 1;
+		}
+	}
+	Ok(count)
+}
+
+fn mutable_globals(section: &[u8]) -> Result<Vec<bool>, BinaryReaderError> {
+	GlobalSectionReader::new(section, 0)?
+		.into_iter()
+		.map(|global| global.map(|global| global.ty.mutable))
+		.collect()
+}
+
+fn invalid_data(error: BinaryReaderError) -> Error {
+	Error::Other(format!("cannot decode the data segments: {}", error))
+}
+
+fn write_u32(out: &mut Vec<u8>, value: u32) {
+	leb128::write::unsigned(out, value.into()).expect("writing to a `Vec` can't fail; qed");
+}
+
+fn write_section(code: &mut Vec<u8>, id: u8, data: &[u8]) {
+	code.push(id);
+	write_u32(code, data.len() as u32);
+	code.extend_from_slice(data);
 }
 
 /// This is a snapshot of data segments specialzied for a particular instantiation.
@@ -76,45 +264,7 @@ pub struct DataSegmentsSnapshot {
 impl DataSegmentsSnapshot {
 	/// Create a snapshot from the data segments from the module.
 	pub fn take(module: &WasmModuleInfo) -> error::Result<Self> {
-		let data_segments = module
-			.data_segments()
-			.into_iter()
-			.map(|mut segment| {
-				// Just replace contents of the segment since the segments will be discarded later
-				// anyway.
-				let contents = mem::replace(segment.value_mut(), vec![]);
-
-				let init_expr = match segment.offset() {
-					Some(offset) => offset.code(),
-					// Return if the segment is passive
-					None => return Err(Error::SharedMemUnsupported),
-				};
-
-				// [op, End]
-				if init_expr.len() != 2 {
-					return Err(Error::InitializerHasTooManyExpressions);
-				}
-				let offset = match &init_expr[0] {
-					Instruction::I32Const(v) => *v as u32,
-					Instruction::GetGlobal(_) => {
-						// In a valid wasm file, initializer expressions can only refer imported
-						// globals.
-						//
-						// At the moment of writing the Substrate Runtime Interface does not provide
-						// any globals. There is nothing that prevents us from supporting this
-						// if/when we gain those.
-						return Err(Error::ImportedGlobalsUnsupported);
-					}
-					insn => {
-						return Err(Error::InvalidInitializerExpression(format!("{:?}", insn)))
-					}
-				};
-
-				Ok((offset, contents))
-			})
-			.collect::<error::Result<Vec<_>>>()?;
-
-		Ok(Self { data_segments })
+		Ok(Self { data_segments: module.data_segments()? })
 	}
 
 	/// Apply the given snapshot to a linear memory.
@@ -130,3 +280,66 @@ impl DataSegmentsSnapshot {
 		Ok(())
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn decodes_the_modules_using_proposals() {
+		let code = wat::parse_str(r#"
+			(module
+				(import "env" "imported" (global i32))
+				(memory (export "memory") 1)
+				(global $constant i32 (i32.const 0))
+				(global $calls (mut i32) (i32.const 0))
+				(data (i32.const 16) "data")
+				(func (export "fill")
+					(memory.fill (i32.const 0) (i32.const 7) (i32.const 8))
+				)
+				(@custom "test" "contents")
+			)
+		"#).unwrap();
+
+		let info = WasmModuleInfo::new(&code).unwrap();
+		assert_eq!(info.imported_globals_count(), 1);
+		assert_eq!(info.declared_globals_count(), 2);
+		assert_eq!(info.data_segments().unwrap(), vec![(16, b"data".to_vec())]);
+		assert_eq!(custom_section(&code, "test"), Some(&b"contents"[..]));
+		assert_eq!(custom_section(&code, "other"), None);
+
+		let exported = info.export_mutable_globals(&code, "exported_global").unwrap();
+		let mut validator = wasmparser::Validator::new();
+		validator.wasm_features(wasmparser::WasmFeatures {
+			bulk_memory: true,
+			..Default::default()
+		});
+		validator.validate_all(&exported).unwrap();
+		let exports = sections(&exported).unwrap()
+			.into_iter()
+			.find(|section| section.id == EXPORT_SECTION)
+			.map(|section| {
+				wasmparser::ExportSectionReader::new(&exported[section.range], 0).unwrap()
+			})
+			.unwrap()
+			.into_iter()
+			.map(|export| export.unwrap().field.to_owned())
+			.collect::<Vec<_>>();
+		assert_eq!(exports, vec!["memory", "fill", "exported_global2"]);
+
+		assert!(WasmModuleInfo::new(b"\0asm\x01").is_none());
+	}
+
+	#[test]
+	fn rejects_the_passive_data_segments() {
+		let code = wat::parse_str(r#"
+			(module
+				(memory (export "memory") 1)
+				(data "passive")
+			)
+		"#).unwrap();
+
+		let info = WasmModuleInfo::new(&code).unwrap();
+		assert!(matches!(info.data_segments(), Err(Error::PassiveDataSegmentsUnsupported)));
+	}
+}
@@ -0,0 +1,131 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Sets of host functions registered with the executor by the node.
+//!
+//! Besides `SubstrateHostFunctions` and the `ExtendHostFunctions` of its
+//! `NativeExecutionDispatch`, a node can provide its runtimes with more sets of host functions,
+//! e.g. custom crypto, without changing the executor.
+//!
+//! The host functions are versioned by name (see `sc_executor_common::host_functions`), so a set
+//! can provide new versions of the functions of another one, every runtime importing the version
+//! it was built against. A set providing a version which is already provided is rejected,
+//! unless it was registered to replace it.
+
+use std::{collections::HashMap, fmt};
+use sc_executor_common::error::{Error, Result};
+use sp_wasm_interface::{Function, HostFunctions};
+
+#[derive(Clone)]
+struct HostFunctionSet {
+	name: String,
+	functions: Vec<&'static dyn Function>,
+	replaces: bool,
+}
+
+/// Sets of host functions provided to the runtimes on top of those of the executor.
+#[derive(Clone, Default)]
+pub struct HostFunctionSets(Vec<HostFunctionSet>);
+
+impl HostFunctionSets {
+	/// Register the host functions `H` under the given name, which the conflicts are reported
+	/// with.
+	pub fn register<H: HostFunctions>(self, name: impl Into<String>) -> Self {
+		self.push::<H>(name.into(), false)
+	}
+
+	/// Register the host functions `H`, replacing the functions of the same names and versions,
+	/// e.g. with a different implementation.
+	pub fn register_replacing<H: HostFunctions>(self, name: impl Into<String>) -> Self {
+		self.push::<H>(name.into(), true)
+	}
+
+	fn push<H: HostFunctions>(mut self, name: String, replaces: bool) -> Self {
+		self.0.push(HostFunctionSet { name, functions: H::host_functions(), replaces });
+		self
+	}
+
+	/// Returns `true` if no set is registered.
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+
+	/// Extend the host functions of the executor with the registered sets.
+	///
+	/// Returns an error if a set provides a function which is already provided and that it
+	/// doesn't replace.
+	pub(crate) fn extend(
+		&self,
+		mut functions: Vec<&'static dyn Function>,
+	) -> Result<Vec<&'static dyn Function>> {
+		// The runtimes are given the first function of every name.
+		let mut provided = HashMap::new();
+		for (index, function) in functions.iter().enumerate() {
+			provided.entry(function.name().to_owned()).or_insert((index, "executor"));
+		}
+
+		for set in &self.0 {
+			for function in &set.functions {
+				match provided.get(function.name()) {
+					Some(&(index, _)) if set.replaces => functions[index] = *function,
+					Some((_, provider)) => return Err(Error::ConflictingHostFunction(
+						function.name().into(),
+						provider.to_string(),
+						set.name.clone(),
+					)),
+					None => {
+						let index = functions.len();
+						provided.insert(function.name().to_owned(), (index, set.name.as_str()));
+						functions.push(*function);
+					},
+				}
+			}
+		}
+		Ok(functions)
+	}
+}
+
+impl fmt::Debug for HostFunctionSets {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_list().entries(self.0.iter().map(|set| &set.name)).finish()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn registers_host_functions_unless_they_conflict() {
+		let executor = sp_io::SubstrateHostFunctions::host_functions();
+		let hashing = sp_io::hashing::HostFunctions::host_functions();
+
+		let sets = HostFunctionSets::default().register::<sp_io::hashing::HostFunctions>("hashing");
+		let functions = sets.extend(Vec::new()).unwrap();
+		assert_eq!(functions.len(), hashing.len());
+		assert!(matches!(
+			sets.extend(executor.clone()),
+			Err(Error::ConflictingHostFunction(_, ref provider, ref set))
+				if provider == "executor" && set == "hashing"
+		));
+
+		let sets = HostFunctionSets::default()
+			.register_replacing::<sp_io::hashing::HostFunctions>("hashing");
+		assert_eq!(sets.extend(executor.clone()).unwrap().len(), executor.len());
+	}
+}
@@ -49,7 +49,7 @@ macro_rules! test_wasm_execution {
 			#[test]
 			#[cfg(feature = "wasmtime")]
 			fn [<$method_name _compiled>]() {
-				$method_name(WasmExecutionMethod::Compiled);
+				$method_name(WasmExecutionMethod::Compiled(Default::default()));
 			}
 		}
 	};
@@ -120,7 +120,7 @@ fn call_not_existing_function(wasm_method: WasmExecutionMethod) {
 					"\"Trap: Trap { kind: Host(Other(\\\"Function `missing_external` is only a stub. Calling a stub is not allowed.\\\")) }\""
 				),
 				#[cfg(feature = "wasmtime")]
-				WasmExecutionMethod::Compiled => assert!(
+				WasmExecutionMethod::Compiled(_) => assert!(
 					format!("{:?}", e).contains("Wasm execution trapped: call to a missing function env:missing_external")
 				),
 			}
@@ -147,7 +147,7 @@ fn call_yet_another_not_existing_function(wasm_method: WasmExecutionMethod) {
 					"\"Trap: Trap { kind: Host(Other(\\\"Function `yet_another_missing_external` is only a stub. Calling a stub is not allowed.\\\")) }\""
 				),
 				#[cfg(feature = "wasmtime")]
-				WasmExecutionMethod::Compiled => assert!(
+				WasmExecutionMethod::Compiled(_) => assert!(
 					format!("{:?}", e).contains("Wasm execution trapped: call to a missing function env:yet_another_missing_external")
 				),
 			}
@@ -185,6 +185,35 @@ fn panicking_should_work(wasm_method: WasmExecutionMethod) {
 	assert!(output.is_err());
 }
 
+test_wasm_execution!(panics_are_reported_with_their_message);
+fn panics_are_reported_with_their_message(wasm_method: WasmExecutionMethod) {
+	let mut ext = TestExternalities::default();
+	let mut ext = ext.ext();
+
+	let error = call_in_wasm(
+		"test_panic",
+		&[],
+		wasm_method,
+		&mut ext,
+	).unwrap_err();
+	assert!(error.starts_with("Runtime panicked: panicked at 'test panic'"), "{}", error);
+
+	match wasm_method {
+		WasmExecutionMethod::Interpreted => assert!(!error.contains("WASM backtrace")),
+		#[cfg(feature = "wasmtime")]
+		WasmExecutionMethod::Compiled(_) => assert!(error.contains("WASM backtrace"), "{}", error),
+	}
+
+	// the message is only reported for the call which panicked.
+	let output = call_in_wasm(
+		"test_conditional_panic",
+		&[0],
+		wasm_method,
+		&mut ext,
+	);
+	assert!(output.is_ok());
+}
+
 test_wasm_execution!(storage_should_work);
 fn storage_should_work(wasm_method: WasmExecutionMethod) {
 	let mut ext = TestExternalities::default();
@@ -552,6 +581,67 @@ fn should_trap_when_heap_exhausted(wasm_method: WasmExecutionMethod) {
 	assert!(err.contains("Allocator ran out of space"));
 }
 
+test_wasm_execution!(calls_exceeding_the_heap_pages_limit_fail);
+fn calls_exceeding_the_heap_pages_limit_fail(wasm_method: WasmExecutionMethod) {
+	let mut ext = TestExternalities::default();
+	ext.register_extension(sp_core::traits::MemoryLimitsExt(sp_core::traits::MemoryLimits {
+		max_heap_pages: Some(8),
+		cap_heap_pages: false,
+	}));
+
+	let executor = crate::WasmExecutor::new(
+		wasm_method,
+		Some(1024),
+		HostFunctions::host_functions(),
+		8,
+		None,
+	);
+	let err = executor.call_in_wasm(
+		&wasm_binary_unwrap()[..],
+		Some(vec![1]),
+		"test_empty_return",
+		&[],
+		&mut ext.ext(),
+		sp_core::traits::MissingHostFunctions::Allow,
+	).unwrap_err();
+
+	assert!(err.contains("requires 1024 heap pages, more than the 8 allowed"));
+}
+
+test_wasm_execution!(calls_capping_the_heap_pages_fail_past_the_limit);
+fn calls_capping_the_heap_pages_fail_past_the_limit(wasm_method: WasmExecutionMethod) {
+	let executor = crate::WasmExecutor::new(
+		wasm_method,
+		Some(1024),
+		HostFunctions::host_functions(),
+		8,
+		None,
+	);
+	let call = |ext: &mut TestExternalities, method| executor.call_in_wasm(
+		&wasm_binary_unwrap()[..],
+		Some(vec![1]),
+		method,
+		&[0],
+		&mut ext.ext(),
+		sp_core::traits::MissingHostFunctions::Allow,
+	);
+
+	let mut unlimited = TestExternalities::default();
+	call(&mut unlimited, "test_exhaust_heap").unwrap();
+
+	let mut capped = TestExternalities::default();
+	capped.register_extension(sp_core::traits::MemoryLimitsExt(sp_core::traits::MemoryLimits {
+		max_heap_pages: Some(64),
+		cap_heap_pages: true,
+	}));
+	call(&mut capped, "test_empty_return").unwrap();
+	let err = call(&mut capped, "test_exhaust_heap").unwrap_err();
+	assert!(err.contains("Allocator ran out of space"));
+
+	// the calls without limits still get the heap pages of the runtime.
+	call(&mut unlimited, "test_exhaust_heap").unwrap();
+}
+
 test_wasm_execution!(returns_mutable_static);
 fn returns_mutable_static(wasm_method: WasmExecutionMethod) {
 	let runtime = crate::wasm_runtime::create_wasm_runtime_with_code(
@@ -561,6 +651,8 @@ fn returns_mutable_static(wasm_method: WasmExecutionMethod) {
 		HostFunctions::host_functions(),
 		true,
 		None,
+		Default::default(),
+		None,
 	).expect("Creates runtime");
 
 	let instance = runtime.new_instance().unwrap();
@@ -595,6 +687,8 @@ fn restoration_of_globals(wasm_method: WasmExecutionMethod) {
 		HostFunctions::host_functions(),
 		true,
 		None,
+		Default::default(),
+		None,
 	).expect("Creates runtime");
 	let instance = runtime.new_instance().unwrap();
 
@@ -616,6 +710,8 @@ fn heap_is_reset_between_calls(wasm_method: WasmExecutionMethod) {
 		HostFunctions::host_functions(),
 		true,
 		None,
+		Default::default(),
+		None,
 	).expect("Creates runtime");
 	let instance = runtime.new_instance().unwrap();
 
@@ -781,3 +877,79 @@ fn panic_in_spawned_instance_panics_on_joining_its_result(wasm_method: WasmExecu
 	dbg!(&error_result);
 	assert!(format!("{}", error_result).contains("Spawned task"));
 }
+
+test_wasm_execution!(stack_height_is_limited_deterministically);
+fn stack_height_is_limited_deterministically(wasm_method: WasmExecutionMethod) {
+	// `recurse` calls itself as many times as the length of its input.
+	let code = wat::parse_str(r#"
+		(module
+			(memory (export "memory") 1)
+			(global (export "__heap_base") i32 (i32.const 1024))
+			(func $recurse (param $depth i32)
+				(if (local.get $depth)
+					(then (call $recurse (i32.sub (local.get $depth) (i32.const 1))))
+				)
+			)
+			(func (export "recurse") (param $data i32) (param $len i32) (result i64)
+				(call $recurse (local.get $len))
+				(i64.const 0)
+			)
+		)
+	"#).unwrap();
+
+	let runtime = crate::wasm_runtime::create_wasm_runtime_with_code(
+		wasm_method,
+		1,
+		&code,
+		HostFunctions::host_functions(),
+		true,
+		Some(1024),
+		Default::default(),
+		None,
+	).expect("Creates runtime");
+	let instance = runtime.new_instance().unwrap();
+
+	assert!(instance.call_export("recurse", &[0; 100]).is_ok());
+	assert!(instance.call_export("recurse", &[0; 1000]).is_err());
+	// the stack height is reset after a trap.
+	assert!(instance.call_export("recurse", &[0; 100]).is_ok());
+}
+
+#[test]
+#[cfg(feature = "wasmtime")]
+fn runs_the_runtimes_using_the_enabled_proposals() {
+	// `fill` returns 8 bytes filled by `memory.fill` followed by 8 bytes of a SIMD store, and
+	// counts its calls in a mutable global, which is restored between them.
+	let code = wat::parse_str(r#"
+		(module
+			(memory (export "memory") 1)
+			(global (export "__heap_base") i32 (i32.const 1024))
+			(global $calls (mut i32) (i32.const 0))
+			(data (i32.const 16) "unused")
+			(func (export "fill") (param $data i32) (param $len i32) (result i64)
+				(global.set $calls (i32.add (global.get $calls) (i32.const 1)))
+				(memory.fill (i32.const 0) (i32.const 7) (i32.const 8))
+				(v128.store (i32.const 8) (i8x16.splat (i32.const 9)))
+				(i64.const 68719476736)
+			)
+		)
+	"#).unwrap();
+	let create_runtime = |max_stack_height| crate::wasm_runtime::create_wasm_runtime_with_code(
+		WasmExecutionMethod::Compiled(Default::default()),
+		1,
+		&code,
+		HostFunctions::host_functions(),
+		true,
+		max_stack_height,
+		sc_executor_common::features::WasmFeatures::ALL,
+		None,
+	);
+
+	let instance = create_runtime(None).expect("Creates runtime").new_instance().unwrap();
+	let mut expected = vec![7; 8];
+	expected.extend_from_slice(&[9; 8]);
+	assert_eq!(instance.call_export("fill", &[]).unwrap(), expected);
+	assert_eq!(instance.call_export("fill", &[]).unwrap(), expected);
+
+	assert!(create_runtime(Some(1024)).is_err());
+}
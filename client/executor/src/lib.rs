@@ -31,23 +31,34 @@
 #![warn(missing_docs)]
 #![recursion_limit="128"]
 
+mod host_functions;
 #[macro_use]
 mod native_executor;
 mod wasm_runtime;
+mod metrics;
 #[cfg(test)]
 mod integration_tests;
 
 pub use wasmi;
-pub use native_executor::{with_externalities_safe, NativeExecutor, WasmExecutor, NativeExecutionDispatch};
+pub use native_executor::{
+	with_externalities_safe, NativeExecutor, WasmExecutor, NativeExecutionDispatch,
+	DEFAULT_MAX_STACK_HEIGHT,
+};
+pub use host_functions::HostFunctionSets;
 pub use sp_version::{RuntimeVersion, NativeVersion};
 pub use codec::Codec;
 #[doc(hidden)]
 pub use sp_core::traits::{Externalities, CallInWasm};
 #[doc(hidden)]
 pub use sp_wasm_interface;
-pub use wasm_runtime::WasmExecutionMethod;
+pub use wasm_runtime::{WasmExecutionMethod, DEFAULT_MAX_RUNTIMES};
+#[cfg(feature = "wasmtime")]
+pub use sc_executor_wasmtime::{
+	Config as WasmtimeConfig, MemoryStrategy as WasmtimeMemoryStrategy,
+	OptLevel as WasmtimeOptLevel,
+};
 
-pub use sc_executor_common::{error, sandbox};
+pub use sc_executor_common::{error, sandbox, features::WasmFeatures};
 
 /// Provides runtime information.
 pub trait RuntimeInfo {
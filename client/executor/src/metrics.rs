@@ -0,0 +1,306 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Metrics of the runtime instances.
+
+use prometheus_endpoint::{
+	register, CounterVec, Gauge, HistogramOpts, HistogramVec, Opts, PrometheusError, Registry,
+	U64, exponential_buckets,
+};
+
+use sp_version::RuntimeVersion;
+
+use crate::wasm_runtime::{Eviction, WasmExecutionMethod};
+
+/// Label of the calls to entry points that don't belong to any API of the runtime, so the
+/// callers, e.g. of `state_call`, can't create labels.
+const OTHER_ENTRY_POINT: &str = "other";
+
+/// The entry points the node calls itself, labelled by their name.
+const KNOWN_ENTRY_POINTS: &[&str] = &[
+	"Core_version",
+	"Core_execute_block",
+	"Core_initialize_block",
+	"BlockBuilder_apply_extrinsic",
+	"BlockBuilder_finalize_block",
+	"BlockBuilder_inherent_extrinsics",
+	"BlockBuilder_check_inherents",
+	"TaggedTransactionQueue_validate_transaction",
+	"OffchainWorkerApi_offchain_worker",
+	"Metadata_metadata",
+];
+
+/// Why a runtime instance was created.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Instantiation {
+	/// Created along with the runtime, before it is called.
+	Preinstantiated,
+	/// Created for a free slot of the pool.
+	Pooled,
+	/// Created for a single call, since all the instances of the pool are in use.
+	PoolExhausted,
+}
+
+impl Instantiation {
+	fn label(&self) -> &'static str {
+		match self {
+			Instantiation::Preinstantiated => "preinstantiated",
+			Instantiation::Pooled => "pooled",
+			Instantiation::PoolExhausted => "pool_exhausted",
+		}
+	}
+}
+
+impl Eviction {
+	fn label(&self) -> &'static str {
+		match self {
+			Eviction::Count => "count",
+			Eviction::Size => "size",
+		}
+	}
+}
+
+/// Returns the label of the given executor backend.
+pub(crate) fn method_label(method: WasmExecutionMethod) -> &'static str {
+	match method {
+		WasmExecutionMethod::Interpreted => "interpreted",
+		#[cfg(feature = "wasmtime")]
+		WasmExecutionMethod::Compiled(_) => "compiled",
+	}
+}
+
+/// Returns the label of the given entry point of the runtime of the given version.
+///
+/// The known entry points are labelled by their name, e.g. `Core_execute_block`, and the other
+/// entry points of the APIs of the runtime by the name of their API, e.g. `Core`.
+pub(crate) fn entry_point_label<'a>(entry_point: &'a str, version: &RuntimeVersion) -> &'a str {
+	if let Some(known) = KNOWN_ENTRY_POINTS.iter().find(|known| **known == entry_point) {
+		return known;
+	}
+	let api = entry_point.split('_').next().unwrap_or_default();
+	let api_id = sp_core::hashing::blake2_64(api.as_bytes());
+	if version.apis.iter().any(|(id, _)| *id == api_id) {
+		api
+	} else {
+		OTHER_ENTRY_POINT
+	}
+}
+
+/// The metrics of the executor.
+#[derive(Debug, Clone)]
+pub(crate) struct ExecutorMetrics {
+	pool_hits: CounterVec<U64>,
+	instantiations: CounterVec<U64>,
+	instantiation_time: HistogramVec,
+	call_time: HistogramVec,
+	evictions: CounterVec<U64>,
+	cached_runtimes: Gauge<U64>,
+	cached_code_size: Gauge<U64>,
+}
+
+impl ExecutorMetrics {
+	pub(crate) fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(ExecutorMetrics {
+			pool_hits: register(CounterVec::new(
+				Opts::new(
+					"executor_instance_pool_hits_total",
+					"Runtime calls served by an instance of the pool, by executor backend",
+				),
+				&["method"],
+			)?, registry)?,
+			instantiations: register(CounterVec::new(
+				Opts::new(
+					"executor_instantiations_total",
+					"Runtime instances created, by executor backend and reason",
+				),
+				&["method", "reason"],
+			)?, registry)?,
+			instantiation_time: register(HistogramVec::new(
+				HistogramOpts {
+					common_opts: Opts::new(
+						"executor_instantiation_duration_seconds",
+						"Time taken to create the runtime instances, by executor backend",
+					),
+					buckets: exponential_buckets(0.000_1, 4.0, 10)?,
+				},
+				&["method"],
+			)?, registry)?,
+			call_time: register(HistogramVec::new(
+				HistogramOpts {
+					common_opts: Opts::new(
+						"executor_call_duration_seconds",
+						"Time taken by the runtime calls, by entry point and executor backend",
+					),
+					buckets: exponential_buckets(0.000_1, 4.0, 10)?,
+				},
+				&["entry_point", "backend"],
+			)?, registry)?,
+			evictions: register(CounterVec::new(
+				Opts::new(
+					"executor_runtime_cache_evictions_total",
+					"Runtimes evicted from the cache, by the limit they exceeded",
+				),
+				&["reason"],
+			)?, registry)?,
+			cached_runtimes: register(Gauge::new(
+				"executor_runtime_cache_runtimes",
+				"Number of runtimes in the cache",
+			)?, registry)?,
+			cached_code_size: register(Gauge::new(
+				"executor_runtime_cache_code_bytes",
+				"Size of the code of the runtimes in the cache",
+			)?, registry)?,
+		})
+	}
+
+	pub(crate) fn observe_pool_hit(&self, method: WasmExecutionMethod) {
+		self.pool_hits.with_label_values(&[method_label(method)]).inc();
+	}
+
+	pub(crate) fn observe_instantiation(
+		&self,
+		method: WasmExecutionMethod,
+		instantiation: Instantiation,
+		duration: std::time::Duration,
+	) {
+		let method = method_label(method);
+		self.instantiations.with_label_values(&[method, instantiation.label()]).inc();
+		self.instantiation_time.with_label_values(&[method]).observe(duration.as_secs_f64());
+	}
+
+	/// Note a call to the given entry point of the runtime, executed by the given backend, e.g.
+	/// `native`.
+	pub(crate) fn observe_call(
+		&self,
+		entry_point: &str,
+		backend: &str,
+		duration: std::time::Duration,
+	) {
+		self.call_time.with_label_values(&[entry_point, backend]).observe(duration.as_secs_f64());
+	}
+
+	pub(crate) fn observe_eviction(&self, eviction: Eviction) {
+		self.evictions.with_label_values(&[eviction.label()]).inc();
+	}
+
+	pub(crate) fn observe_cached_runtimes(&self, runtimes: usize, code_size: usize) {
+		self.cached_runtimes.set(runtimes as u64);
+		self.cached_code_size.set(code_size as u64);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::wasm_runtime::RuntimeCache;
+	use sc_runtime_test::wasm_binary_unwrap;
+	use sp_core::traits::{Externalities, RuntimeCode, WrappedRuntimeCode};
+	use sp_wasm_interface::HostFunctions;
+
+	#[test]
+	fn counts_pool_hits_and_instantiations() {
+		let registry = Registry::new();
+		let metrics = ExecutorMetrics::register(&registry).unwrap();
+		let cache = RuntimeCache::new(2, 1, None, None, Some(metrics.clone()));
+		let code = WrappedRuntimeCode(wasm_binary_unwrap().into());
+		let runtime_code = RuntimeCode { code_fetcher: &code, hash: vec![1], heap_pages: None };
+		let host_functions = sp_io::SubstrateHostFunctions::host_functions();
+		let call = |ext: &mut dyn Externalities, f: &dyn Fn(&mut dyn Externalities)| cache
+			.with_instance(
+				&runtime_code,
+				ext,
+				WasmExecutionMethod::Interpreted,
+				8,
+				&host_functions,
+				false,
+				|_, _, _, ext| Ok(f(ext)),
+			)
+			.unwrap()
+			.unwrap();
+
+		let mut ext = sp_io::TestExternalities::default();
+		let mut ext = ext.ext();
+		// the calls in progress keep their instances, until the pool is exhausted.
+		call(&mut ext, &|ext| call(ext, &|ext| call(ext, &|_| ())));
+		call(&mut ext, &|_| ());
+
+		let instantiations = |reason| metrics.instantiations
+			.with_label_values(&["interpreted", reason])
+			.get();
+		assert_eq!(metrics.pool_hits.with_label_values(&["interpreted"]).get(), 2);
+		assert_eq!(instantiations("preinstantiated"), 1);
+		assert_eq!(instantiations("pooled"), 1);
+		assert_eq!(instantiations("pool_exhausted"), 1);
+	}
+
+	#[test]
+	fn evicts_the_least_recently_used_runtimes() {
+		let registry = Registry::new();
+		let metrics = ExecutorMetrics::register(&registry).unwrap();
+		let code = WrappedRuntimeCode(wasm_binary_unwrap().into());
+		let cache = RuntimeCache::new(1, 0, None, None, Some(metrics.clone()))
+			.with_limits(2, Some(2 * code.0.len()));
+		let host_functions = sp_io::SubstrateHostFunctions::host_functions();
+		let mut ext = sp_io::TestExternalities::default();
+		let mut ext = ext.ext();
+		let mut call = |hash| {
+			let runtime_code = RuntimeCode { code_fetcher: &code, hash, heap_pages: None };
+			cache
+				.with_instance(
+					&runtime_code,
+					&mut ext,
+					WasmExecutionMethod::Interpreted,
+					8,
+					&host_functions,
+					false,
+					|_, _, _, _| Ok(()),
+				)
+				.unwrap()
+				.unwrap();
+		};
+
+		call(vec![1]);
+		call(vec![2]);
+		call(vec![1]);
+		call(vec![3]);
+		assert_eq!(metrics.evictions.with_label_values(&["count"]).get(), 1);
+		assert_eq!(metrics.cached_runtimes.get(), 2);
+		assert_eq!(metrics.cached_code_size.get(), 2 * code.0.len() as u64);
+
+		// the runtime `1` was used more recently than `2`, so it is still cached.
+		call(vec![1]);
+		assert_eq!(metrics.evictions.with_label_values(&["count"]).get(), 1);
+		assert_eq!(metrics.instantiations.with_label_values(&["interpreted", "pooled"]).get(), 3);
+	}
+
+	#[test]
+	fn labels_the_entry_points_of_the_apis_of_the_runtime() {
+		let version = RuntimeVersion {
+			apis: vec![(sp_core::hashing::blake2_64(b"Core"), 3)].into(),
+			..Default::default()
+		};
+
+		assert_eq!(entry_point_label("Core_execute_block", &version), "Core_execute_block");
+		assert_eq!(entry_point_label("Core_anything", &version), "Core");
+		assert_eq!(entry_point_label("Core_anything_else", &version), "Core");
+		assert_eq!(entry_point_label("Metadata_metadata", &version), "Metadata_metadata");
+		assert_eq!(entry_point_label("Metadata_anything", &version), OTHER_ENTRY_POINT);
+		assert_eq!(entry_point_label("Core", &version), "Core");
+		assert_eq!(entry_point_label("", &version), OTHER_ENTRY_POINT);
+	}
+}
@@ -17,16 +17,20 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
-	RuntimeInfo, error::{Error, Result},
-	wasm_runtime::{RuntimeCache, WasmExecutionMethod},
+	RuntimeInfo, error::{Error, MessageWithBacktrace, Result},
+	host_functions::HostFunctionSets,
+	metrics::{ExecutorMetrics, entry_point_label, method_label},
+	wasm_runtime::{RuntimeCache, WasmExecutionMethod, DEFAULT_MAX_RUNTIMES},
 };
 
 use std::{
+	any::TypeId,
 	collections::HashMap,
 	panic::{UnwindSafe, AssertUnwindSafe},
 	result,
 	sync::{Arc, atomic::{AtomicU64, Ordering}, mpsc},
 	path::PathBuf,
+	time::Instant,
 };
 
 use sp_version::{NativeVersion, RuntimeVersion};
@@ -35,18 +39,27 @@ use sp_core::{
 	NativeOrEncoded,
 	traits::{
 		CodeExecutor, Externalities, RuntimeCode, MissingHostFunctions,
-		RuntimeSpawnExt, RuntimeSpawn,
+		RuntimeSpawnExt, RuntimeSpawn, PanicMessageExt,
 	},
 };
 use log::trace;
+use prometheus_endpoint::{PrometheusError, Registry};
 use sp_wasm_interface::{HostFunctions, Function};
-use sc_executor_common::wasm_runtime::{WasmInstance, WasmModule, InvokeMethod};
+use sc_executor_common::{
+	features::WasmFeatures,
+	wasm_runtime::{WasmInstance, WasmModule, InvokeMethod},
+};
 use sp_externalities::ExternalitiesExt as _;
 use sp_tasks::new_async_externalities;
 
 /// Default num of pages for the heap
 const DEFAULT_HEAP_PAGES: u64 = 1024;
 
+/// A limit of the height of the stack of the runtimes, counted in values and frames of the wasm
+/// stack machine, see `WasmExecutor::with_max_stack_height`. The stack height isn't limited
+/// unless the chain spec sets a limit.
+pub const DEFAULT_MAX_STACK_HEIGHT: u32 = 65536;
+
 /// Set up the externalities and safe calling environment to execute runtime calls.
 ///
 /// If the inner closure panics, it will be caught and return an error.
@@ -72,6 +85,25 @@ pub fn with_externalities_safe<F, U>(ext: &mut dyn Externalities, f: F) -> Resul
 	)
 }
 
+/// Returns the error of a failed runtime call, with the message of the panic it aborted with if
+/// the runtime reported one.
+fn with_panic_message(ext: &mut dyn Externalities, error: Error) -> Error {
+	let message = ext.extension_by_type_id(TypeId::of::<PanicMessageExt>())
+		.and_then(|message| message.downcast_mut::<PanicMessageExt>())
+		.map(|message| std::mem::take(&mut message.0));
+	let message = match message {
+		Some(message) => message,
+		None => return error,
+	};
+	let _ = ext.deregister_extension_by_type_id(TypeId::of::<PanicMessageExt>());
+
+	let backtrace = match error {
+		Error::AbortedDueToTrap(trap) => trap.backtrace,
+		_ => None,
+	};
+	Error::AbortedDueToPanic(MessageWithBacktrace { message, backtrace })
+}
+
 /// Delegate for dispatching a CodeExecutor call.
 ///
 /// By dispatching we mean that we execute a runtime function specified by it's name.
@@ -103,9 +135,20 @@ pub struct WasmExecutor {
 	cache: Arc<RuntimeCache>,
 	/// The size of the instances cache.
 	max_runtime_instances: usize,
+	/// The number of instances created along with each runtime.
+	preinstantiated_instances: usize,
+	/// The deterministic limit of the stack height of the runtimes.
+	max_stack_height: Option<u32>,
+	/// The WebAssembly proposals the runtimes may use.
+	wasm_features: WasmFeatures,
+	/// The maximum number of runtimes of the cache.
+	max_runtimes: usize,
+	/// The maximum size of the code of the runtimes of the cache.
+	max_runtimes_size: Option<usize>,
 	/// The path to a directory which the executor can leverage for a file cache, e.g. put there
 	/// compiled artifacts.
 	cache_path: Option<PathBuf>,
+	metrics: Option<ExecutorMetrics>,
 }
 
 impl WasmExecutor {
@@ -137,12 +180,124 @@ impl WasmExecutor {
 			method,
 			default_heap_pages: default_heap_pages.unwrap_or(DEFAULT_HEAP_PAGES),
 			host_functions: Arc::new(host_functions),
-			cache: Arc::new(RuntimeCache::new(max_runtime_instances, cache_path.clone())),
+			cache: Arc::new(RuntimeCache::new(
+				max_runtime_instances,
+				0,
+				None,
+				cache_path.clone(),
+				None,
+			)),
 			max_runtime_instances,
+			preinstantiated_instances: 0,
+			max_stack_height: None,
+			wasm_features: Default::default(),
+			max_runtimes: DEFAULT_MAX_RUNTIMES,
+			max_runtimes_size: None,
 			cache_path,
+			metrics: None,
 		}
 	}
 
+	/// Create `count` instances of the runtimes ready for reuse as soon as they are loaded, so
+	/// the first calls don't have to instantiate them.
+	///
+	/// No more than `max_runtime_instances` instances are created.
+	pub fn with_preinstantiated_instances(mut self, count: usize) -> Self {
+		self.preinstantiated_instances = count;
+		self.reset_cache();
+		self
+	}
+
+	/// Register the metrics of the runtime instances in the given registry.
+	pub fn with_prometheus_registry(
+		mut self,
+		registry: Option<&Registry>,
+	) -> result::Result<Self, PrometheusError> {
+		self.metrics = registry.map(ExecutorMetrics::register).transpose()?;
+		self.reset_cache();
+		Ok(self)
+	}
+
+	/// Let the executor put its files, e.g. the compiled runtimes, in the given directory.
+	pub fn with_cache_path(mut self, cache_path: Option<PathBuf>) -> Self {
+		self.cache_path = cache_path;
+		self.reset_cache();
+		self
+	}
+
+	/// Keep up to `max_runtimes` runtimes in the cache, and up to `max_runtimes_size` bytes of
+	/// their code if given, evicting the least recently used ones past them.
+	///
+	/// Defaults to `DEFAULT_MAX_RUNTIMES` runtimes of any size.
+	pub fn with_runtime_cache_limits(
+		mut self,
+		max_runtimes: usize,
+		max_runtimes_size: Option<usize>,
+	) -> Self {
+		self.max_runtimes = max_runtimes;
+		self.max_runtimes_size = max_runtimes_size;
+		self.reset_cache();
+		self
+	}
+
+	/// Provide the runtimes with the given sets of host functions too.
+	///
+	/// Returns an error if a set provides a function which is already provided, see
+	/// `HostFunctionSets`.
+	pub fn with_host_function_sets(mut self, sets: &HostFunctionSets) -> Result<Self> {
+		if !sets.is_empty() {
+			self.host_functions = Arc::new(sets.extend(self.host_functions.to_vec())?);
+			self.reset_cache();
+		}
+		Ok(self)
+	}
+
+	/// Make the runtimes trap once the height of their stack exceeds `max_stack_height`, e.g.
+	/// `DEFAULT_MAX_STACK_HEIGHT`, instead of once they exhaust the native stack.
+	///
+	/// The height is counted the same way by every execution method, so all the nodes agree
+	/// on which calls exceed it. `None` lifts the limit.
+	pub fn with_max_stack_height(mut self, max_stack_height: Option<u32>) -> Self {
+		self.max_stack_height = max_stack_height;
+		self.reset_cache();
+		self
+	}
+
+	/// Let the runtimes use the given WebAssembly proposals, see `WasmFeatures`.
+	///
+	/// Returns an error if the proposals aren't supported by the execution method, as the
+	/// interpreter supports none of them.
+	pub fn with_wasm_features(mut self, wasm_features: WasmFeatures) -> Result<Self> {
+		if !wasm_features.is_empty() {
+			if matches!(self.method, WasmExecutionMethod::Interpreted) {
+				return Err(Error::Other(format!(
+					"The interpreter doesn't support the WebAssembly proposals: {}",
+					wasm_features.names().join(", "),
+				)));
+			}
+			log::info!(
+				target: "executor",
+				"Enabled the WebAssembly proposals: {}",
+				wasm_features.names().join(", "),
+			);
+		}
+		self.wasm_features = wasm_features;
+		self.reset_cache();
+		Ok(self)
+	}
+
+	fn reset_cache(&mut self) {
+		self.cache = Arc::new(RuntimeCache::new(
+			self.max_runtime_instances,
+			self.preinstantiated_instances,
+			self.max_stack_height,
+			self.cache_path.clone(),
+			self.metrics.clone(),
+		)
+			.with_limits(self.max_runtimes, self.max_runtimes_size)
+			.with_wasm_features(self.wasm_features));
+	}
+
 	/// Execute the given closure `f` with the latest runtime (based on `runtime_code`).
 	///
 	/// The closure `f` is expected to return `Err(_)` when there happened a `panic!` in native code
@@ -180,8 +335,12 @@ impl WasmExecutor {
 			|module, instance, version, ext| {
 				let module = AssertUnwindSafe(module);
 				let instance = AssertUnwindSafe(instance);
-				let ext = AssertUnwindSafe(ext);
-				f(module, instance, version, ext)
+				// a runtime error logged by an earlier call isn't the panic of this one.
+				let _ = ext.deregister_extension_by_type_id(TypeId::of::<PanicMessageExt>());
+				match f(module, instance, version, AssertUnwindSafe(&mut *ext)) {
+					Ok(Err(error)) => Ok(Err(with_panic_message(ext, error))),
+					result => result,
+				}
 			}
 		)? {
 			Ok(r) => r,
@@ -221,10 +380,12 @@ impl sp_core::traits::CallInWasm for WasmExecutor {
 		} else {
 			let module = crate::wasm_runtime::create_wasm_runtime_with_code(
 				self.method,
-				self.default_heap_pages,
+				crate::wasm_runtime::heap_pages(None, wasm_code, self.default_heap_pages),
 				&wasm_code,
 				self.host_functions.to_vec(),
 				allow_missing_host_functions,
+				self.max_stack_height,
+				self.wasm_features,
 				self.cache_path.as_deref(),
 			)
 				.map_err(|e| format!("Failed to create module: {:?}", e))?;
@@ -233,6 +394,7 @@ impl sp_core::traits::CallInWasm for WasmExecutor {
 				.map_err(|e| format!("Failed to create instance: {:?}", e))?;
 
 			let instance = AssertUnwindSafe(instance);
+			let _ = ext.deregister_extension_by_type_id(TypeId::of::<PanicMessageExt>());
 			let mut ext = AssertUnwindSafe(ext);
 			let module = AssertUnwindSafe(module);
 
@@ -244,7 +406,7 @@ impl sp_core::traits::CallInWasm for WasmExecutor {
 				}
 			)
 			.and_then(|r| r)
-			.map_err(|e| e.to_string())
+			.map_err(|e| with_panic_message(&mut **ext, e).to_string())
 		}
 	}
 }
@@ -292,6 +454,58 @@ impl<D: NativeExecutionDispatch> NativeExecutor<D> {
 			wasm: wasm_executor,
 		}
 	}
+
+	/// Create `count` instances of the runtimes ready for reuse as soon as they are loaded, see
+	/// `WasmExecutor::with_preinstantiated_instances`.
+	pub fn with_preinstantiated_instances(mut self, count: usize) -> Self {
+		self.wasm = self.wasm.with_preinstantiated_instances(count);
+		self
+	}
+
+	/// Let the executor put its files, e.g. the compiled runtimes, in the given directory.
+	pub fn with_cache_path(mut self, cache_path: Option<PathBuf>) -> Self {
+		self.wasm = self.wasm.with_cache_path(cache_path);
+		self
+	}
+
+	/// Provide the runtimes with the given sets of host functions too, see
+	/// `WasmExecutor::with_host_function_sets`.
+	pub fn with_host_function_sets(mut self, sets: &HostFunctionSets) -> Result<Self> {
+		self.wasm = self.wasm.with_host_function_sets(sets)?;
+		Ok(self)
+	}
+
+	/// Limit the runtimes of the cache, see `WasmExecutor::with_runtime_cache_limits`.
+	pub fn with_runtime_cache_limits(
+		mut self,
+		max_runtimes: usize,
+		max_runtimes_size: Option<usize>,
+	) -> Self {
+		self.wasm = self.wasm.with_runtime_cache_limits(max_runtimes, max_runtimes_size);
+		self
+	}
+
+	/// Limit the height of the stack of the runtimes, see `WasmExecutor::with_max_stack_height`.
+	pub fn with_max_stack_height(mut self, max_stack_height: Option<u32>) -> Self {
+		self.wasm = self.wasm.with_max_stack_height(max_stack_height);
+		self
+	}
+
+	/// Let the runtimes use the given WebAssembly proposals, see
+	/// `WasmExecutor::with_wasm_features`.
+	pub fn with_wasm_features(mut self, wasm_features: WasmFeatures) -> Result<Self> {
+		self.wasm = self.wasm.with_wasm_features(wasm_features)?;
+		Ok(self)
+	}
+
+	/// Register the metrics of the runtime instances in the given registry.
+	pub fn with_prometheus_registry(
+		mut self,
+		registry: Option<&Registry>,
+	) -> result::Result<Self, PrometheusError> {
+		self.wasm = self.wasm.with_prometheus_registry(registry)?;
+		Ok(self)
+	}
 }
 
 impl<D: NativeExecutionDispatch> RuntimeInfo for NativeExecutor<D> {
@@ -477,8 +691,11 @@ impl<D: NativeExecutionDispatch + 'static> CodeExecutor for NativeExecutor<D> {
 				)?;
 
 				let can_call_with = onchain_version.can_call_with(&self.native_version.runtime_version);
+				let entry_point = entry_point_label(method, onchain_version);
+				// the calls are only timed if they are measured, as there is no time in browsers.
+				let started = self.wasm.metrics.as_ref().map(|_| Instant::now());
 
-				match (
+				let (backend, result) = match (
 					use_native,
 					can_call_with,
 					native_call,
@@ -493,13 +710,14 @@ impl<D: NativeExecutionDispatch + 'static> CodeExecutor for NativeExecutor<D> {
 							);
 						}
 
-						with_externalities_safe(
+						let result = with_externalities_safe(
 							&mut **ext,
 							move || {
 								RuntimeInstanceSpawn::register_on_externalities(module.clone());
 								instance.call_export(method, data).map(NativeOrEncoded::Encoded)
 							}
-						)
+						);
+						(method_label(self.wasm.method), result)
 					},
 					(true, true, Some(call)) => {
 						trace!(
@@ -517,7 +735,7 @@ impl<D: NativeExecutionDispatch + 'static> CodeExecutor for NativeExecutor<D> {
 								.map_err(Error::ApiError)
 							);
 
-						Ok(res)
+						("native", Ok(res))
 					}
 					_ => {
 						trace!(
@@ -528,9 +746,16 @@ impl<D: NativeExecutionDispatch + 'static> CodeExecutor for NativeExecutor<D> {
 						);
 
 						used_native = true;
-						Ok(D::dispatch(&mut **ext, method, data).map(NativeOrEncoded::Encoded))
+						let result = D::dispatch(&mut **ext, method, data)
+							.map(NativeOrEncoded::Encoded);
+						("native", Ok(result))
 					}
+				};
+
+				if let (Some(metrics), Some(started)) = (&self.wasm.metrics, started) {
+					metrics.observe_call(entry_point, backend, started.elapsed());
 				}
+				result
 			}
 		);
 		(result, used_native)
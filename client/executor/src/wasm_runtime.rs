@@ -21,15 +21,15 @@
 //! The primary means of accessing the runtimes is through a cache which saves the reusable
 //! components of the runtime that are expensive to initialize.
 
-use std::sync::Arc;
-use crate::error::{Error, WasmError};
+use std::{any::TypeId, borrow::Cow, sync::Arc, time::Instant};
+use crate::{error::{Error, WasmError}, metrics::{ExecutorMetrics, Instantiation}};
 use parking_lot::Mutex;
 use codec::Decode;
-use sp_core::traits::{Externalities, RuntimeCode, FetchRuntimeCode};
-use sp_version::RuntimeVersion;
+use sp_core::traits::{Externalities, RuntimeCode, FetchRuntimeCode, MemoryLimitsExt};
+use sp_version::{RuntimeVersion, HEAP_PAGES_SECTION};
 use std::panic::AssertUnwindSafe;
 use std::path::{Path, PathBuf};
-use sc_executor_common::wasm_runtime::{WasmModule, WasmInstance};
+use sc_executor_common::{features::WasmFeatures, wasm_runtime::{WasmModule, WasmInstance}};
 
 use sp_wasm_interface::Function;
 
@@ -38,9 +38,9 @@ use sp_wasm_interface::Function;
 pub enum WasmExecutionMethod {
 	/// Uses the Wasmi interpreter.
 	Interpreted,
-	/// Uses the Wasmtime compiled runtime.
+	/// Uses the Wasmtime compiled runtime, with the given configuration.
 	#[cfg(feature = "wasmtime")]
-	Compiled,
+	Compiled(sc_executor_wasmtime::Config),
 }
 
 impl Default for WasmExecutionMethod {
@@ -57,15 +57,35 @@ struct VersionedRuntime {
 	wasm_method: WasmExecutionMethod,
 	/// Shared runtime that can spawn instances.
 	module: Arc<dyn WasmModule>,
+	/// The size of the code of the runtime, in bytes.
+	code_size: usize,
 	/// The number of WebAssembly heap pages this instance was created with.
 	heap_pages: u64,
+	/// The number of heap pages the runtime was requested with, see `RuntimeCode::heap_pages`.
+	requested_heap_pages: Option<u64>,
+	/// Whether `heap_pages` were capped to the `MemoryLimits` of the calls it was created for.
+	capped: bool,
 	/// Runtime version according to `Core_version` if any.
 	version: Option<RuntimeVersion>,
 	/// Cached instance pool.
 	instances: Vec<Mutex<Option<Box<dyn WasmInstance>>>>,
+	metrics: Option<ExecutorMetrics>,
 }
 
 impl VersionedRuntime {
+	/// Create a new instance of this runtime.
+	fn new_instance(&self, instantiation: Instantiation) -> Result<Box<dyn WasmInstance>, Error> {
+		// the time is only measured along with the metrics, as it isn't available in browsers.
+		let metrics = match self.metrics {
+			Some(ref metrics) => metrics,
+			None => return self.module.new_instance(),
+		};
+		let start = Instant::now();
+		let instance = self.module.new_instance()?;
+		metrics.observe_instantiation(self.wasm_method, instantiation, start.elapsed());
+		Ok(instance)
+	}
+
 	/// Run the given closure `f` with an instance of this runtime.
 	fn with_instance<'c, R, F>(
 		&self,
@@ -87,9 +107,15 @@ impl VersionedRuntime {
 
 		match instance {
 			Some((index, mut locked)) => {
-				let (instance, new_inst) = locked.take()
-					.map(|r| Ok((r, false)))
-					.unwrap_or_else(|| self.module.new_instance().map(|i| (i, true)))?;
+				let (instance, new_inst) = match locked.take() {
+					Some(instance) => {
+						if let Some(ref metrics) = self.metrics {
+							metrics.observe_pool_hit(self.wasm_method);
+						}
+						(instance, false)
+					},
+					None => (self.new_instance(Instantiation::Pooled)?, true),
+				};
 
 				let result = f(&self.module, &*instance, self.version.as_ref(), ext);
 				if let Err(e) = &result {
@@ -125,7 +151,7 @@ impl VersionedRuntime {
 				log::warn!(target: "wasm-runtime", "Ran out of free WASM instances");
 
 				// Allocate a new instance
-				let instance = self.module.new_instance()?;
+				let instance = self.new_instance(Instantiation::PoolExhausted)?;
 
 				f(&self.module, &*instance, self.version.as_ref(), ext)
 			}
@@ -133,7 +159,17 @@ impl VersionedRuntime {
 	}
 }
 
-const MAX_RUNTIMES: usize = 2;
+/// The default number of runtimes kept in the cache.
+pub const DEFAULT_MAX_RUNTIMES: usize = 2;
+
+/// Why a runtime was evicted from the cache.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Eviction {
+	/// The cache holds as many runtimes as it can.
+	Count,
+	/// The code of the runtimes of the cache exceeds its size limit.
+	Size,
+}
 
 /// Cache for the runtimes.
 ///
@@ -145,15 +181,31 @@ const MAX_RUNTIMES: usize = 2;
 /// the memory reset to the initial memory. So, one runtime instance is reused for every fetch
 /// request.
 ///
-/// The size of cache is equal to `MAX_RUNTIMES`.
+/// The cache holds up to `max_runtimes` runtimes, `DEFAULT_MAX_RUNTIMES` by default, and
+/// optionally up to `max_runtimes_size` bytes of their code: the least recently used runtimes are
+/// evicted past them, except the runtime of the last call.
+///
+/// The instances of every runtime are pooled: an instance is only dropped if its call fails,
+/// otherwise it is reset before its next call.
 pub struct RuntimeCache {
 	/// A cache of runtimes along with metadata.
 	///
 	/// Runtimes sorted by recent usage. The most recently used is at the front.
-	runtimes: Mutex<[Option<Arc<VersionedRuntime>>; MAX_RUNTIMES]>,
+	runtimes: Mutex<Vec<Arc<VersionedRuntime>>>,
+	/// The maximum number of runtimes of the cache.
+	max_runtimes: usize,
+	/// The maximum size of the code of the runtimes of the cache, in bytes.
+	max_runtimes_size: Option<usize>,
 	/// The size of the instances cache for each runtime.
 	max_runtime_instances: usize,
+	/// The number of instances created along with each runtime.
+	preinstantiated_instances: usize,
+	/// The deterministic limit of the stack height of the runtimes.
+	max_stack_height: Option<u32>,
+	/// The WebAssembly proposals the runtimes may use.
+	wasm_features: WasmFeatures,
 	cache_path: Option<PathBuf>,
+	metrics: Option<ExecutorMetrics>,
 }
 
 impl RuntimeCache {
@@ -162,16 +214,52 @@ impl RuntimeCache {
 	/// `max_runtime_instances` specifies the number of runtime instances preserved in an in-memory
 	/// cache.
 	///
+	/// `preinstantiated_instances` specifies the number of these instances created along with
+	/// the runtime, instead of on the first calls.
+	///
+	/// `max_stack_height` specifies the deterministic limit of the stack height of the runtimes,
+	/// see `create_wasm_runtime_with_code`.
+	///
 	/// `cache_path` allows to specify an optional directory where the executor can store files
 	/// for caching.
-	pub fn new(max_runtime_instances: usize, cache_path: Option<PathBuf>) -> RuntimeCache {
+	pub(crate) fn new(
+		max_runtime_instances: usize,
+		preinstantiated_instances: usize,
+		max_stack_height: Option<u32>,
+		cache_path: Option<PathBuf>,
+		metrics: Option<ExecutorMetrics>,
+	) -> RuntimeCache {
 		RuntimeCache {
 			runtimes: Default::default(),
+			max_runtimes: DEFAULT_MAX_RUNTIMES,
+			max_runtimes_size: None,
 			max_runtime_instances,
+			preinstantiated_instances,
+			max_stack_height,
+			wasm_features: Default::default(),
 			cache_path,
+			metrics,
 		}
 	}
 
+	/// Let the runtimes use the given WebAssembly proposals.
+	pub(crate) fn with_wasm_features(mut self, wasm_features: WasmFeatures) -> Self {
+		self.wasm_features = wasm_features;
+		self
+	}
+
+	/// Keep up to `max_runtimes` runtimes, and up to `max_runtimes_size` bytes of their code if
+	/// given, in the cache.
+	pub(crate) fn with_limits(
+		mut self,
+		max_runtimes: usize,
+		max_runtimes_size: Option<usize>,
+	) -> Self {
+		self.max_runtimes = max_runtimes.max(1);
+		self.max_runtimes_size = max_runtimes_size;
+		self
+	}
+
 	/// Prepares a WASM module instance and executes given function for it.
 	///
 	/// This uses internal cache to find avaiable instance or create a new one.
@@ -181,7 +269,8 @@ impl RuntimeCache {
 	///
 	/// `runtime_code` - The runtime wasm code used setup the runtime.
 	///
-	/// `default_heap_pages` - Number of 64KB pages to allocate for Wasm execution.
+	/// `default_heap_pages` - Number of 64KB pages to allocate for Wasm execution, unless the
+	/// runtime code requests or advertises the heap pages it requires, see `heap_pages`.
 	///
 	/// `wasm_method` - Type of WASM backend to use.
 	///
@@ -200,6 +289,10 @@ impl RuntimeCache {
 	///
 	/// `Error::InvalidMemoryReference` is returned if no memory export with the
 	/// identifier `memory` can be found in the runtime.
+	///
+	/// `Error::HeapPagesLimitExceeded` is returned if the runtime requires more heap pages than
+	/// the `MemoryLimitsExt` registered with `ext` allows, unless the limits cap the heap pages of
+	/// the call instead.
 	pub fn with_instance<'c, R, F>(
 		&self,
 		runtime_code: &'c RuntimeCode<'c>,
@@ -218,22 +311,50 @@ impl RuntimeCache {
 		-> Result<R, Error>,
 	{
 		let code_hash = &runtime_code.hash;
-		let heap_pages = runtime_code.heap_pages.unwrap_or(default_heap_pages);
+		let limits = ext.extension_by_type_id(TypeId::of::<MemoryLimitsExt>())
+			.and_then(|limits| limits.downcast_ref::<MemoryLimitsExt>())
+			.map(|limits| **limits)
+			.unwrap_or_default();
+		// the heap pages to run the calls with, given the heap pages the runtime requires.
+		let limit_heap_pages = |heap_pages: u64| match limits.max_heap_pages {
+			Some(max_heap_pages) if heap_pages > max_heap_pages && limits.cap_heap_pages =>
+				Ok(max_heap_pages),
+			Some(max_heap_pages) if heap_pages > max_heap_pages =>
+				Err(Error::HeapPagesLimitExceeded(heap_pages, max_heap_pages)),
+			_ => Ok(heap_pages),
+		};
 
 		let mut runtimes = self.runtimes.lock(); // this must be released prior to calling f
-		let pos = runtimes.iter().position(|r| r.as_ref().map_or(
-			false,
-			|r| r.wasm_method == wasm_method &&
+		let pos = runtimes.iter().position(|r|
+			r.wasm_method == wasm_method &&
 				r.code_hash == *code_hash &&
-				r.heap_pages == heap_pages
-		));
+				r.requested_heap_pages == runtime_code.heap_pages &&
+				if r.capped {
+					limits.cap_heap_pages && limits.max_heap_pages == Some(r.heap_pages)
+				} else {
+					// the calls capping the heap pages of this runtime need a capped runtime.
+					limit_heap_pages(r.heap_pages).map_or(true, |pages| pages == r.heap_pages)
+				}
+		);
 
 		let runtime = match pos {
-			Some(n) => runtimes[n]
-				.clone()
-				.expect("`position` only returns `Some` for entries that are `Some`"),
+			Some(n) => {
+				limit_heap_pages(runtimes[n].heap_pages)?;
+				runtimes.remove(n)
+			},
 			None =>  {
 				let code = runtime_code.fetch_runtime_code().ok_or(WasmError::CodeNotFound)?;
+				let required_heap_pages =
+					heap_pages(runtime_code.heap_pages, &code, default_heap_pages);
+				let heap_pages = limit_heap_pages(required_heap_pages)?;
+				if heap_pages != required_heap_pages {
+					log::debug!(
+						target: "wasm-runtime",
+						"Capping the {} heap pages of the runtime to {}.",
+						required_heap_pages,
+						heap_pages,
+					);
+				}
 
 				let result = create_versioned_wasm_runtime(
 					&code,
@@ -241,10 +362,16 @@ impl RuntimeCache {
 					ext,
 					wasm_method,
 					heap_pages,
+					runtime_code.heap_pages,
+					heap_pages != required_heap_pages,
 					host_functions.into(),
 					allow_missing_func_imports,
 					self.max_runtime_instances,
+					self.preinstantiated_instances,
+					self.max_stack_height,
+					self.wasm_features,
 					self.cache_path.as_deref(),
+					self.metrics.clone(),
 				);
 				if let Err(ref err) = result {
 					log::warn!(target: "wasm-runtime", "Cannot create a runtime: {:?}", err);
@@ -254,35 +381,119 @@ impl RuntimeCache {
 		};
 
 		// Rearrange runtimes by last recently used.
-		match pos {
-			Some(0) => {},
-			Some(n) => {
-				for i in (1 .. n + 1).rev() {
-					runtimes.swap(i, i - 1);
-				}
-			}
-			None => {
-				runtimes[MAX_RUNTIMES-1] = Some(runtime.clone());
-				for i in (1 .. MAX_RUNTIMES).rev() {
-					runtimes.swap(i, i - 1);
-				}
-			}
-		}
+		runtimes.insert(0, runtime.clone());
+		self.evict(&mut runtimes);
 		drop(runtimes);
 
 		Ok(runtime.with_instance(ext, f))
 	}
+
+	/// Evict the least recently used runtimes past the limits of the cache.
+	fn evict(&self, runtimes: &mut Vec<Arc<VersionedRuntime>>) {
+		let mut size: usize = runtimes.iter().map(|runtime| runtime.code_size).sum();
+		while runtimes.len() > 1 {
+			let eviction = if runtimes.len() > self.max_runtimes {
+				Eviction::Count
+			} else if self.max_runtimes_size.map_or(false, |max_size| size > max_size) {
+				Eviction::Size
+			} else {
+				break;
+			};
+
+			let runtime = runtimes.pop().expect("there are more than one runtime; qed");
+			size -= runtime.code_size;
+			log::debug!(
+				target: "wasm-runtime",
+				"Evicting runtime version {:?} from the cache ({:?}).",
+				runtime.version,
+				eviction,
+			);
+			if let Some(ref metrics) = self.metrics {
+				metrics.observe_eviction(eviction);
+			}
+		}
+		if let Some(ref metrics) = self.metrics {
+			metrics.observe_cached_runtimes(runtimes.len(), size);
+		}
+	}
+}
+
+/// Returns the number of heap pages to create the runtime of the given `code` with.
+///
+/// The heap pages requested by the chain, see `RuntimeCode::heap_pages`, take precedence over the
+/// heap pages the runtime advertises in its `HEAP_PAGES_SECTION`, which take precedence over the
+/// default heap pages of the node. So a runtime upgrade requiring more memory gets it as soon as
+/// it is enacted, on every node.
+pub(crate) fn heap_pages(requested: Option<u64>, code: &[u8], default: u64) -> u64 {
+	requested.or_else(|| advertised_heap_pages(code)).unwrap_or(default)
+}
+
+/// Returns the number of heap pages the runtime advertises in its `HEAP_PAGES_SECTION`, if any.
+fn advertised_heap_pages(code: &[u8]) -> Option<u64> {
+	let section = sc_executor_common::util::custom_section(code, HEAP_PAGES_SECTION)?;
+	let mut heap_pages = [0; 8];
+	if section.len() != heap_pages.len() {
+		log::warn!(target: "wasm-runtime", "Ignoring the invalid heap pages of the runtime.");
+		return None;
+	}
+	heap_pages.copy_from_slice(section);
+	Some(u64::from_le_bytes(heap_pages))
 }
 
 /// Create a wasm runtime with the given `code`.
+///
+/// If `max_stack_height` is given, the code is instrumented to count the height of its stack and
+/// trap once it exceeds the limit. The height is counted in values and frames of the wasm stack
+/// machine rather than in bytes of the native stack, so the runtime traps at the same point
+/// whichever method executes it, and before it could exhaust the native stack.
+///
+/// Fails with `WasmError::DisabledFeatures` if the code uses WebAssembly proposals missing from
+/// `wasm_features`.
 pub fn create_wasm_runtime_with_code(
 	wasm_method: WasmExecutionMethod,
 	heap_pages: u64,
 	code: &[u8],
 	host_functions: Vec<&'static dyn Function>,
 	allow_missing_func_imports: bool,
+	max_stack_height: Option<u32>,
+	wasm_features: WasmFeatures,
 	cache_path: Option<&Path>,
 ) -> Result<Arc<dyn WasmModule>, WasmError> {
+	create_wasm_runtime(
+		wasm_method,
+		heap_pages,
+		code,
+		host_functions,
+		allow_missing_func_imports,
+		max_stack_height,
+		wasm_features,
+		cache_path,
+		false,
+	)
+}
+
+/// Create a wasm runtime with the given `code`, see `create_wasm_runtime_with_code`.
+///
+/// If `capped`, the memory of the compiled runtime can't grow past its heap pages, as with the
+/// interpreter, so the calls limited by `MemoryLimits` can't allocate more.
+fn create_wasm_runtime(
+	wasm_method: WasmExecutionMethod,
+	heap_pages: u64,
+	code: &[u8],
+	host_functions: Vec<&'static dyn Function>,
+	allow_missing_func_imports: bool,
+	max_stack_height: Option<u32>,
+	wasm_features: WasmFeatures,
+	cache_path: Option<&Path>,
+	capped: bool,
+) -> Result<Arc<dyn WasmModule>, WasmError> {
+	wasm_features.check(code)?;
+	let code = match max_stack_height {
+		Some(max_stack_height) => Cow::Owned(inject_stack_limiter(code, max_stack_height)?),
+		None => Cow::Borrowed(code),
+	};
+	let code = &code[..];
+
 	match wasm_method {
 		WasmExecutionMethod::Interpreted => {
 			// Wasmi doesn't have any need in a cache directory.
@@ -290,6 +501,8 @@ pub fn create_wasm_runtime_with_code(
 			// We drop the cache_path here to silence warnings that cache_path is not used if compiling
 			// without the `wasmtime` flag.
 			drop(cache_path);
+			// The memory of the interpreted runtimes can't grow past their heap pages anyway.
+			drop(capped);
 
 			sc_executor_wasmi::create_runtime(
 				code,
@@ -300,17 +513,37 @@ pub fn create_wasm_runtime_with_code(
 			.map(|runtime| -> Arc<dyn WasmModule> { Arc::new(runtime) })
 		}
 		#[cfg(feature = "wasmtime")]
-		WasmExecutionMethod::Compiled =>
+		WasmExecutionMethod::Compiled(config) =>
 			sc_executor_wasmtime::create_runtime(
 				code,
 				heap_pages,
 				host_functions,
 				allow_missing_func_imports,
 				cache_path,
+				config,
+				wasm_features,
+				capped,
 			).map(|runtime| -> Arc<dyn WasmModule> { Arc::new(runtime) }),
 	}
 }
 
+/// Instrument the code to trap once the height of its stack exceeds `max_stack_height`.
+///
+/// The code is instrumented with `parity-wasm`, which supports none of the WebAssembly proposals,
+/// so it fails for the runtimes using them.
+fn inject_stack_limiter(code: &[u8], max_stack_height: u32) -> Result<Vec<u8>, WasmError> {
+	if let Err(WasmError::DisabledFeatures(names)) = WasmFeatures::default().check(code) {
+		return Err(WasmError::Other(
+			format!("cannot limit the stack height of a runtime using {}", names),
+		));
+	}
+	let module = parity_wasm::deserialize_buffer(code).map_err(|_| WasmError::InvalidModule)?;
+	let module = pwasm_utils::stack_height::inject_limiter(module, max_stack_height)
+		.map_err(|e| WasmError::Other(format!("cannot limit the stack height: {:?}", e)))?;
+	parity_wasm::serialize(module)
+		.map_err(|e| WasmError::Other(format!("cannot encode module: {}", e)))
+}
+
 fn decode_version(version: &[u8]) -> Result<RuntimeVersion, WasmError> {
 	let v: RuntimeVersion = sp_api::OldRuntimeVersion::decode(&mut &version[..])
 		.map_err(|_|
@@ -336,20 +569,29 @@ fn create_versioned_wasm_runtime(
 	ext: &mut dyn Externalities,
 	wasm_method: WasmExecutionMethod,
 	heap_pages: u64,
+	requested_heap_pages: Option<u64>,
+	capped: bool,
 	host_functions: Vec<&'static dyn Function>,
 	allow_missing_func_imports: bool,
 	max_instances: usize,
+	preinstantiated_instances: usize,
+	max_stack_height: Option<u32>,
+	wasm_features: WasmFeatures,
 	cache_path: Option<&Path>,
+	metrics: Option<ExecutorMetrics>,
 ) -> Result<VersionedRuntime, WasmError> {
 	#[cfg(not(target_os = "unknown"))]
 	let time = std::time::Instant::now();
-	let runtime = create_wasm_runtime_with_code(
+	let runtime = create_wasm_runtime(
 		wasm_method,
 		heap_pages,
 		&code,
 		host_functions,
 		allow_missing_func_imports,
+		max_stack_height,
+		wasm_features,
 		cache_path,
+		capped,
 	)?;
 
 	// Call to determine runtime version.
@@ -380,14 +622,24 @@ fn create_versioned_wasm_runtime(
 	let mut instances = Vec::with_capacity(max_instances);
 	instances.resize_with(max_instances, || Mutex::new(None));
 
-	Ok(VersionedRuntime {
+	let runtime = VersionedRuntime {
 		code_hash,
 		module: runtime,
+		code_size: code.len(),
 		version,
 		heap_pages,
+		requested_heap_pages,
+		capped,
 		wasm_method,
 		instances,
-	})
+		metrics,
+	};
+	for instance in runtime.instances.iter().take(preinstantiated_instances) {
+		let new_instance = runtime.new_instance(Instantiation::Preinstantiated)
+			.map_err(|e| WasmError::Instantiation(format!("{:?}", e)))?;
+		*instance.lock() = Some(new_instance);
+	}
+	Ok(runtime)
 }
 
 #[cfg(test)]
@@ -435,6 +687,27 @@ mod tests {
 		decode_version(&old_runtime_version.encode()).unwrap_err();
 	}
 
+	#[test]
+	fn runtimes_get_the_heap_pages_they_advertise() {
+		use parity_wasm::elements::{CustomSection, Module, Section};
+
+		let code = |section: Option<Vec<u8>>| {
+			let mut module = Module::default();
+			if let Some(payload) = section {
+				module.sections_mut().push(Section::Custom(
+					CustomSection::new(HEAP_PAGES_SECTION.into(), payload),
+				));
+			}
+			parity_wasm::serialize(module).unwrap()
+		};
+		let advertising = code(Some(2048u64.to_le_bytes().to_vec()));
+
+		assert_eq!(heap_pages(None, &advertising, 1024), 2048);
+		assert_eq!(heap_pages(Some(4096), &advertising, 1024), 4096);
+		assert_eq!(heap_pages(None, &code(None), 1024), 1024);
+		assert_eq!(heap_pages(None, &code(Some(vec![1, 2])), 1024), 1024);
+	}
+
 	#[test]
 	fn new_runtime_version_decodes() {
 		let old_runtime_version = sp_api::RuntimeVersion {
@@ -36,6 +36,7 @@ use sc_executor_common::{
 	error::{Error, WasmError},
 	sandbox,
 };
+use sc_executor_common::host_functions;
 use sc_executor_common::util::{DataSegmentsSnapshot, WasmModuleInfo};
 
 struct FunctionExecutor<'a> {
@@ -334,7 +335,10 @@ impl<'a> wasmi::ModuleImportResolver for Resolver<'a> {
 			Ok(wasmi::FuncInstance::alloc_host(signature.into(), id))
 		} else {
 			Err(wasmi::Error::Instantiation(
-				format!("Export {} not found", name),
+				format!(
+					"Export {} not found",
+					host_functions::describe_missing(name, self.host_functions),
+				),
 			))
 		}
 	}
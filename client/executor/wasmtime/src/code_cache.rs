@@ -0,0 +1,163 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! An on-disk cache of the compiled runtimes.
+//!
+//! The compiled modules are stored by hash of the runtime code, under a directory specific to
+//! the version of the executor and by the settings they are compiled with, so neither
+//! restarting the node nor switching back and forth between runtimes pays for their
+//! compilation again. Every entry is stored along with its checksum and any entry that doesn't
+//! match it is removed and compiled again. Once the entries exceed `SIZE_LIMIT`, the least
+//! recently used ones are removed.
+
+use std::{fs, io, path::{Path, PathBuf}, time::SystemTime};
+use sp_core::{hashing::blake2_256, hexdisplay::HexDisplay};
+
+/// The size of the cache past which the least recently used entries are removed, in bytes.
+pub const SIZE_LIMIT: u64 = 512 * 1024 * 1024;
+
+/// Length of the checksum stored ahead of every compiled module.
+const CHECKSUM_LEN: usize = 32;
+
+/// A directory of compiled modules, by hash of their code.
+pub struct CodeCache {
+	directory: PathBuf,
+	flavour: String,
+	size_limit: u64,
+}
+
+impl CodeCache {
+	/// Open the cache under the given path, creating its directory if needed.
+	///
+	/// The `flavour` names the settings the modules are compiled with.
+	pub fn open(cache_path: &Path, flavour: String, size_limit: u64) -> io::Result<Self> {
+		let directory = cache_path.join("wasmtime").join(env!("CARGO_PKG_VERSION"));
+		fs::create_dir_all(&directory)?;
+		Ok(CodeCache { directory, flavour, size_limit })
+	}
+
+	fn entry_path(&self, code: &[u8]) -> PathBuf {
+		self.directory.join(format!("{}-{}.bin", HexDisplay::from(&blake2_256(code)), self.flavour))
+	}
+
+	/// Returns the compiled module of the given code, if it is cached and intact.
+	pub fn load(&self, code: &[u8]) -> Option<Vec<u8>> {
+		let path = self.entry_path(code);
+		let mut entry = fs::read(&path).ok()?;
+		let intact = entry.len() >= CHECKSUM_LEN &&
+			entry[..CHECKSUM_LEN] == blake2_256(&entry[CHECKSUM_LEN..]);
+		if !intact {
+			log::warn!(
+				target: "wasmtime",
+				"Removing corrupted compiled runtime {}",
+				path.display(),
+			);
+			self.remove(&path);
+			return None;
+		}
+		// note when the entry was last used.
+		let _ = filetime::set_file_mtime(&path, filetime::FileTime::now());
+		Some(entry.split_off(CHECKSUM_LEN))
+	}
+
+	/// Remove an entry which couldn't be used, e.g. since it was compiled by a different
+	/// version of wasmtime.
+	pub fn invalidate(&self, code: &[u8]) {
+		self.remove(&self.entry_path(code));
+	}
+
+	/// Store the compiled module of the given code, removing the least recently used entries
+	/// once the cache exceeds its size limit.
+	pub fn store(&self, code: &[u8], compiled: &[u8]) -> io::Result<()> {
+		let path = self.entry_path(code);
+		let mut entry = Vec::with_capacity(CHECKSUM_LEN + compiled.len());
+		entry.extend_from_slice(&blake2_256(compiled));
+		entry.extend_from_slice(compiled);
+
+		// Write to a temporary file first, so a partially written entry is never loaded.
+		let temporary = path.with_extension("tmp");
+		fs::write(&temporary, &entry)?;
+		fs::rename(&temporary, &path)?;
+
+		self.enforce_size_limit()
+	}
+
+	fn enforce_size_limit(&self) -> io::Result<()> {
+		let mut entries = Vec::new();
+		for entry in fs::read_dir(&self.directory)? {
+			let entry = entry?;
+			let metadata = entry.metadata()?;
+			if metadata.is_file() {
+				let used = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+				entries.push((used, metadata.len(), entry.path()));
+			}
+		}
+
+		let mut size: u64 = entries.iter().map(|(_, len, _)| len).sum();
+		entries.sort();
+		for (_, len, path) in entries {
+			if size <= self.size_limit {
+				break;
+			}
+			self.remove(&path);
+			size -= len;
+		}
+		Ok(())
+	}
+
+	fn remove(&self, path: &Path) {
+		if let Err(e) = fs::remove_file(path) {
+			log::warn!(target: "wasmtime", "Failed to remove {}: {}", path.display(), e);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn stores_verifies_and_evicts_compiled_modules() {
+		let path = tempfile::tempdir().unwrap();
+		let cache = CodeCache::open(path.path(), "test".into(), 150).unwrap();
+
+		cache.store(b"first", &[1; 40]).unwrap();
+		assert_eq!(cache.load(b"first"), Some(vec![1; 40]));
+		assert_eq!(cache.load(b"second"), None);
+
+		// a corrupted entry is removed.
+		let entry = cache.entry_path(b"first");
+		let mut corrupted = fs::read(&entry).unwrap();
+		corrupted[CHECKSUM_LEN] = 2;
+		fs::write(&entry, corrupted).unwrap();
+		assert_eq!(cache.load(b"first"), None);
+		assert!(!entry.exists());
+
+		// the entries are removed, least recently used first, past the size limit.
+		cache.store(b"first", &[1; 40]).unwrap();
+		std::thread::sleep(std::time::Duration::from_millis(10));
+		cache.store(b"second", &[2; 40]).unwrap();
+		std::thread::sleep(std::time::Duration::from_millis(10));
+		assert!(cache.load(b"first").is_some());
+		std::thread::sleep(std::time::Duration::from_millis(10));
+		cache.store(b"third", &[3; 40]).unwrap();
+		assert_eq!(cache.load(b"second"), None);
+		assert_eq!(cache.load(b"first"), Some(vec![1; 40]));
+		assert_eq!(cache.load(b"third"), Some(vec![3; 40]));
+	}
+}
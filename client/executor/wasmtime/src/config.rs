@@ -0,0 +1,108 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Tuning of the compilation and execution of the runtimes by wasmtime.
+
+use sc_executor_common::features::WasmFeatures;
+
+/// How much cranelift optimizes the compiled code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OptLevel {
+	/// No optimizations, the fastest to compile.
+	None,
+	/// Optimize for the speed of the compiled code.
+	Speed,
+	/// Optimize for both the speed and the size of the compiled code.
+	SpeedAndSize,
+}
+
+/// How the memories of the runtimes are allocated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MemoryStrategy {
+	/// Reserve the whole 4GiB address space of a memory up front, so the compiled code doesn't
+	/// have to check the bounds of the accesses.
+	Static,
+	/// Allocate the memories as they grow, checking the bounds of every access.
+	///
+	/// Slower, but reserves a lot less virtual memory for every instance.
+	Dynamic,
+}
+
+/// The configuration of wasmtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Config {
+	/// The optimization level of the compiled code.
+	pub opt_level: OptLevel,
+	/// The number of threads compiling a runtime, all the cores if `None`.
+	pub compilation_threads: Option<usize>,
+	/// How the memories of the runtimes are allocated.
+	pub memory_strategy: MemoryStrategy,
+}
+
+impl Default for Config {
+	fn default() -> Self {
+		Config {
+			opt_level: OptLevel::SpeedAndSize,
+			compilation_threads: None,
+			memory_strategy: MemoryStrategy::Static,
+		}
+	}
+}
+
+impl Config {
+	/// Returns the wasmtime configuration of the engine, enabling the given proposals.
+	pub(crate) fn engine_config(&self, features: WasmFeatures) -> wasmtime::Config {
+		let mut config = wasmtime::Config::new();
+		config.wasm_bulk_memory(features.bulk_memory);
+		config.wasm_simd(features.simd);
+		config.cranelift_opt_level(match self.opt_level {
+			OptLevel::None => wasmtime::OptLevel::None,
+			OptLevel::Speed => wasmtime::OptLevel::Speed,
+			OptLevel::SpeedAndSize => wasmtime::OptLevel::SpeedAndSize,
+		});
+		if let MemoryStrategy::Dynamic = self.memory_strategy {
+			config.static_memory_maximum_size(0);
+		}
+		config
+	}
+
+	/// Returns the name of the settings the compiled code depends on, so the code compiled with
+	/// different settings isn't loaded from the code cache.
+	pub(crate) fn code_flavour(&self) -> String {
+		format!("{:?}-{:?}", self.opt_level, self.memory_strategy).to_lowercase()
+	}
+
+	/// Run the compilation on the configured number of threads.
+	pub(crate) fn compile<R: Send>(&self, compile: impl FnOnce() -> R + Send) -> R {
+		let pool = self.compilation_threads.and_then(|threads| {
+			rayon::ThreadPoolBuilder::new()
+				.num_threads(threads)
+				.build()
+				.map_err(|e| log::warn!(
+					target: "wasmtime",
+					"Failed to start the compilation threads: {}",
+					e,
+				))
+				.ok()
+		});
+		match pool {
+			Some(pool) => pool.install(compile),
+			None => compile(),
+		}
+	}
+}
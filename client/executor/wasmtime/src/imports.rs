@@ -34,11 +34,14 @@ pub struct Imports {
 
 /// Goes over all imports of a module and prepares a vector of `Extern`s that can be used for
 /// instantiation of the module. Returns an error if there are imports that cannot be satisfied.
+///
+/// If `capped`, the memory can't grow past the `heap_pages` added to the memory of the module.
 pub fn resolve_imports(
 	store: &Store,
 	module: &Module,
 	host_functions: &[&'static dyn Function],
 	heap_pages: u32,
+	capped: bool,
 	allow_missing_func_imports: bool,
 ) -> Result<Imports, WasmError> {
 	let mut externs = vec![];
@@ -57,7 +60,7 @@ pub fn resolve_imports(
 		let resolved = match name {
 			"memory" => {
 				memory_import_index = Some(externs.len());
-				resolve_memory_import(store, &import_ty, heap_pages)?
+				resolve_memory_import(store, &import_ty, heap_pages, capped)?
 			}
 			_ => resolve_func_import(
 				store,
@@ -88,6 +91,7 @@ fn resolve_memory_import(
 	store: &Store,
 	import_ty: &ImportType,
 	heap_pages: u32,
+	capped: bool,
 ) -> Result<Extern, WasmError> {
 	let requested_memory_ty = match import_ty.ty() {
 		ExternType::Memory(memory_ty) => memory_ty,
@@ -117,7 +121,9 @@ fn resolve_memory_import(
 		}
 	}
 
-	let memory_ty = MemoryType::new(Limits::new(initial, requested_memory_ty.limits().max()));
+	// The calls with capped heap pages can't grow the memory past them, as with the interpreter.
+	let max = if capped { Some(initial) } else { requested_memory_ty.limits().max() };
+	let memory_ty = MemoryType::new(Limits::new(initial, max));
 	let memory = Memory::new(store, memory_ty);
 	Ok(Extern::Memory(memory))
 }
@@ -153,7 +159,7 @@ fn resolve_func_import(
 			return Err(WasmError::Other(format!(
 				"host doesn't provide such function: {}:{}",
 				import_ty.module(),
-				name,
+				sc_executor_common::host_functions::describe_missing(name, host_functions),
 			)));
 		}
 	};
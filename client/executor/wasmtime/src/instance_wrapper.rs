@@ -21,16 +21,16 @@
 
 use crate::util;
 use crate::imports::Imports;
+use crate::code_cache::CodeCache;
 
 use std::{slice, marker};
 use sc_executor_common::{
-	error::{Error, Result},
+	error::{Backtrace, BacktraceFrame, Error, MessageWithBacktrace, Result},
 	util::{WasmModuleInfo, DataSegmentsSnapshot},
 	wasm_runtime::InvokeMethod,
 };
 use sp_wasm_interface::{Pointer, WordSize, Value};
 use wasmtime::{Engine, Instance, Module, Memory, Table, Val, Func, Extern, Global, Store};
-use parity_wasm::elements;
 
 mod globals_snapshot;
 
@@ -42,18 +42,36 @@ pub struct ModuleWrapper {
 }
 
 impl ModuleWrapper {
-	pub fn new(engine: &Engine, code: &[u8]) -> Result<Self> {
-		let mut raw_module: elements::Module = elements::deserialize_buffer(code)
-			.map_err(|e| Error::from(format!("cannot decode module: {}", e)))?;
-		pwasm_utils::export_mutable_globals(&mut raw_module, "exported_internal_global");
-		let instrumented_code = elements::serialize(raw_module)
-			.map_err(|e| Error::from(format!("cannot encode module: {}", e)))?;
-
-		let module = Module::new(engine, &instrumented_code)
-			.map_err(|e| Error::from(format!("cannot create module: {}", e)))?;
-
+	/// Compile the given code, unless it is found in the given cache.
+	pub fn new(engine: &Engine, code: &[u8], cache: Option<&CodeCache>) -> Result<Self> {
 		let module_info = WasmModuleInfo::new(code)
 			.ok_or_else(|| Error::from("cannot deserialize module".to_string()))?;
+		let instrumented_code = module_info
+			.export_mutable_globals(code, "exported_internal_global")
+			.ok_or_else(|| Error::from("cannot decode module".to_string()))?;
+
+		let module = match cache.and_then(|cache| load_module(engine, cache, code)) {
+			Some(module) => module,
+			None => {
+				let module = Module::new(engine, &instrumented_code)
+					.map_err(|e| Error::from(format!("cannot create module: {}", e)))?;
+				if let Some(cache) = cache {
+					let stored = module.serialize()
+						.map_err(|e| e.to_string())
+						.and_then(|compiled| {
+							cache.store(code, &compiled).map_err(|e| e.to_string())
+						});
+					if let Err(e) = stored {
+						log::warn!(
+							target: "wasmtime",
+							"Failed to cache the compiled runtime: {}",
+							e,
+						);
+					}
+				}
+				module
+			},
+		};
 
 		let data_segments_snapshot = DataSegmentsSnapshot::take(&module_info)
 			.map_err(|e| Error::from(format!("cannot take data segments snapshot: {}", e)))?;
@@ -73,6 +91,35 @@ impl ModuleWrapper {
 	}
 }
 
+/// Load the compiled module of the given code from the cache, removing it if it can't be used.
+fn load_module(engine: &Engine, cache: &CodeCache, code: &[u8]) -> Option<Module> {
+	let compiled = cache.load(code)?;
+	match Module::deserialize(engine, &compiled) {
+		Ok(module) => Some(module),
+		Err(e) => {
+			log::debug!(target: "wasmtime", "Discarding the cached compiled runtime: {}", e);
+			cache.invalidate(code);
+			None
+		},
+	}
+}
+
+/// Returns the error of a call aborted by the given trap, with its wasm backtrace.
+fn trap_error(trap: wasmtime::Trap) -> Error {
+	// the display of the trap ends with its backtrace, which is reported on its own.
+	let message = trap.to_string();
+	let message = message.split("\nwasm backtrace:").next().unwrap_or_default().to_owned();
+	let frames = trap.trace().iter()
+		.map(|frame| BacktraceFrame {
+			func_index: frame.func_index(),
+			func_name: frame.func_name()
+				.map(|name| rustc_demangle::demangle(name).to_string()),
+		})
+		.collect::<Vec<_>>();
+	let backtrace = if frames.is_empty() { None } else { Some(Backtrace { frames }) };
+	Error::AbortedDueToTrap(MessageWithBacktrace { message, backtrace })
+}
+
 /// Invoked entrypoint format.
 pub enum EntryPointType {
 	/// Direct call.
@@ -117,10 +164,10 @@ impl EntryPoint {
 				// the signature is checked to have i64 return type
 				results[0].unwrap_i64() as u64
 			)
-			.map_err(|err| Error::from(format!(
-				"Wasm execution trapped: {}",
-				err
-			)))
+			.map_err(|error| match error.downcast::<wasmtime::Trap>() {
+				Ok(trap) => trap_error(trap),
+				Err(error) => Error::from(format!("Wasm execution failed: {}", error)),
+			})
 	}
 
 	pub fn direct(func: wasmtime::Func) -> std::result::Result<Self, &'static str> {
@@ -18,6 +18,8 @@
 
 ///! Defines a `WasmRuntime` that uses the Wasmtime JIT to execute.
 
+mod code_cache;
+mod config;
 mod host;
 mod runtime;
 mod state_holder;
@@ -25,4 +27,5 @@ mod imports;
 mod instance_wrapper;
 mod util;
 
+pub use config::{Config, MemoryStrategy, OptLevel};
 pub use runtime::create_runtime;
@@ -18,6 +18,8 @@
 
 //! Defines the compiled Wasm runtime that uses Wasmtime internally.
 
+use crate::code_cache::{self, CodeCache};
+use crate::config::Config;
 use crate::host::HostState;
 use crate::imports::{Imports, resolve_imports};
 use crate::instance_wrapper::{ModuleWrapper, InstanceWrapper, GlobalsSnapshot, EntryPoint};
@@ -28,18 +30,20 @@ use std::sync::Arc;
 use std::path::Path;
 use sc_executor_common::{
 	error::{Result, WasmError},
+	features::WasmFeatures,
 	wasm_runtime::{WasmModule, WasmInstance, InvokeMethod},
 };
 use sp_allocator::FreeingBumpHeapAllocator;
 use sp_runtime_interface::unpack_ptr_and_len;
 use sp_wasm_interface::{Function, Pointer, WordSize, Value};
-use wasmtime::{Config, Engine, Store};
+use wasmtime::{Engine, Store};
 
 /// A `WasmModule` implementation using wasmtime to compile the runtime module to machine code
 /// and execute the compiled code.
 pub struct WasmtimeRuntime {
 	module_wrapper: Arc<ModuleWrapper>,
 	heap_pages: u32,
+	capped: bool,
 	allow_missing_func_imports: bool,
 	host_functions: Vec<&'static dyn Function>,
 	engine: Engine,
@@ -56,6 +60,7 @@ impl WasmModule for WasmtimeRuntime {
 			self.module_wrapper.module(),
 			&self.host_functions,
 			self.heap_pages,
+			self.capped,
 			self.allow_missing_func_imports,
 		)?;
 
@@ -120,74 +125,41 @@ impl WasmInstance for WasmtimeInstance {
 	}
 }
 
-/// Prepare a directory structure and a config file to enable wasmtime caching.
-///
-/// In case of an error the caching will not be enabled.
-fn setup_wasmtime_caching(
-	cache_path: &Path,
-	config: &mut Config,
-) -> std::result::Result<(), String> {
-	use std::fs;
-
-	let wasmtime_cache_root = cache_path.join("wasmtime");
-	fs::create_dir_all(&wasmtime_cache_root)
-		.map_err(|err| format!("cannot create the dirs to cache: {:?}", err))?;
-
-	// Canonicalize the path after creating the directories.
-	let wasmtime_cache_root = wasmtime_cache_root
-		.canonicalize()
-		.map_err(|err| format!("failed to canonicalize the path: {:?}", err))?;
-
-	// Write the cache config file
-	let cache_config_path = wasmtime_cache_root.join("cache-config.toml");
-	let config_content = format!(
-		"\
-[cache]
-enabled = true
-directory = \"{cache_dir}\"
-",
-		cache_dir = wasmtime_cache_root.display()
-	);
-	fs::write(&cache_config_path, config_content)
-		.map_err(|err| format!("cannot write the cache config: {:?}", err))?;
-
-	config
-		.cache_config_load(cache_config_path)
-		.map_err(|err| format!("failed to parse the config: {:?}", err))?;
-
-	Ok(())
-}
-
 /// Create a new `WasmtimeRuntime` given the code. This function performs translation from Wasm to
 /// machine code, which can be computationally heavy.
 ///
-/// The `cache_path` designates where this executor implementation can put compiled artifacts.
+/// The `cache_path` designates where this executor implementation can put compiled artifacts,
+/// so the same code doesn't have to be compiled again, see `code_cache`.
+///
+/// If `capped`, the memory of the runtime can't grow past its `heap_pages`.
 pub fn create_runtime(
 	code: &[u8],
 	heap_pages: u64,
 	host_functions: Vec<&'static dyn Function>,
 	allow_missing_func_imports: bool,
 	cache_path: Option<&Path>,
+	config: Config,
+	features: WasmFeatures,
+	capped: bool,
 ) -> std::result::Result<WasmtimeRuntime, WasmError> {
 	// Create the engine, store and finally the module from the given code.
-	let mut config = Config::new();
-	config.cranelift_opt_level(wasmtime::OptLevel::SpeedAndSize);
-	if let Some(cache_path) = cache_path {
-		if let Err(reason) = setup_wasmtime_caching(cache_path, &mut config) {
-			log::warn!(
+	let cache = cache_path.and_then(|cache_path| {
+		CodeCache::open(cache_path, config.code_flavour(), code_cache::SIZE_LIMIT)
+			.map_err(|e| log::warn!(
 				"failed to setup wasmtime cache. Performance may degrade significantly: {}.",
-				reason,
-			);
-		}
-	}
+				e,
+			))
+			.ok()
+	});
 
-	let engine = Engine::new(&config);
-	let module_wrapper = ModuleWrapper::new(&engine, code)
+	let engine = Engine::new(&config.engine_config(features));
+	let module_wrapper = config.compile(|| ModuleWrapper::new(&engine, code, cache.as_ref()))
 		.map_err(|e| WasmError::Other(format!("cannot create module: {}", e)))?;
 
 	Ok(WasmtimeRuntime {
 		module_wrapper: Arc::new(module_wrapper),
 		heap_pages: heap_pages as u32,
+		capped,
 		allow_missing_func_imports,
 		host_functions,
 		engine,
@@ -50,6 +50,8 @@ pub enum Error<N, E> {
 	ForcedAuthoritySetChangeDependencyUnsatisfied(N),
 	#[display(fmt = "Invalid operation in the pending changes tree: {}", _0)]
 	ForkTree(fork_tree::Error<E>),
+	#[display(fmt = "The authority set changed at #{}, after the block to revert to", _0)]
+	RevertPastAuthoritySetChange(N),
 }
 
 impl<N, E> From<fork_tree::Error<E>> for Error<N, E> {
@@ -220,6 +222,46 @@ where
 	N: Add<Output = N> + Ord + Clone + Debug,
 	H: Clone + Debug,
 {
+	/// Revert the pending changes signaled after the given block, which becomes the best
+	/// block (and the last finalized one, if it's below it). The given function
+	/// `is_descendent_of` should return `true` if the second hash (target) is a
+	/// descendent of the first hash (base).
+	///
+	/// The current set can't be reverted if it was enacted after the given block,
+	/// since the previous sets aren't kept.
+	pub(crate) fn revert<F, E>(
+		&mut self,
+		number: N,
+		is_descendent_of: &F,
+	) -> Result<(), Error<N, E>>
+	where
+		F: Fn(&H, &H) -> Result<bool, E>,
+		E: std::error::Error,
+	{
+		if let Some((_, last_change)) = self.authority_set_changes.0.last() {
+			if *last_change > number {
+				return Err(Error::RevertPastAuthoritySetChange(last_change.clone()));
+			}
+		}
+
+		// the changes are traversed in pre-order, so they are imported after their ancestors.
+		let pending_changes =
+			std::mem::replace(&mut self.pending_standard_changes, ForkTree::new());
+		for (hash, canon_height, change) in pending_changes.iter() {
+			if *canon_height <= number {
+				self.pending_standard_changes.import(
+					hash.clone(),
+					canon_height.clone(),
+					change.clone(),
+					is_descendent_of,
+				)?;
+			}
+		}
+		self.pending_forced_changes.retain(|change| change.canon_height <= number);
+
+		Ok(())
+	}
+
 	/// Returns the block hash and height at which the next pending change in
 	/// the given chain (i.e. it includes `best_hash`) was signalled, `None` if
 	/// there are no pending changes for the given chain.
@@ -846,6 +888,50 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn revert_removes_later_changes() {
+		let current_authorities = vec![(AuthorityId::from_slice(&[1; 32]), 1)];
+
+		let mut authorities = AuthoritySet {
+			current_authorities: current_authorities.clone(),
+			set_id: 1,
+			pending_standard_changes: ForkTree::new(),
+			pending_forced_changes: Vec::new(),
+			authority_set_changes: AuthoritySetChanges(vec![(0, 3)]),
+		};
+
+		let change = |canon_height, canon_hash, delay_kind| PendingChange {
+			next_authorities: current_authorities.clone(),
+			delay: 1,
+			canon_height,
+			canon_hash,
+			delay_kind,
+		};
+		let change_a = change(5, "hash_a", DelayKind::Finalized);
+		let change_b = change(10, "hash_b", DelayKind::Finalized);
+		let change_c = change(7, "hash_c", DelayKind::Best { median_last_finalized: 4 });
+
+		let is_descendent_of = is_descendent_of(|base, hash| match (*base, *hash) {
+			("hash_a", "hash_b") => true,
+			_ => false,
+		});
+		authorities.add_pending_change(change_a.clone(), &is_descendent_of).unwrap();
+		authorities.add_pending_change(change_b.clone(), &is_descendent_of).unwrap();
+		authorities.add_pending_change(change_c.clone(), &is_descendent_of).unwrap();
+
+		authorities.revert(7, &is_descendent_of).unwrap();
+		assert_eq!(authorities.pending_changes().collect::<Vec<_>>(), vec![&change_a, &change_c]);
+
+		authorities.revert(6, &is_descendent_of).unwrap();
+		assert_eq!(authorities.pending_changes().collect::<Vec<_>>(), vec![&change_a]);
+
+		// the current set was enacted at #3.
+		assert!(matches!(
+			authorities.revert(2, &is_descendent_of),
+			Err(Error::RevertPastAuthoritySetChange(3)),
+		));
+	}
+
 	#[test]
 	fn apply_change() {
 		let mut authorities = AuthoritySet {
@@ -32,14 +32,16 @@ use crate::authorities::{
 	AuthoritySet, AuthoritySetChanges, SharedAuthoritySet, PendingChange, DelayKind,
 };
 use crate::environment::{
-	CompletedRound, CompletedRounds, CurrentRounds, HasVoted, SharedVoterSetState, VoterSetState,
+	CompletedRound, CompletedRounds, CurrentRounds, HasVoted, RoundVotes, SharedVoterSetState,
+	VoterSetState,
 };
-use crate::NewAuthoritySet;
+use crate::{NewAuthoritySet, SignedMessage};
 
 const VERSION_KEY: &[u8] = b"grandpa_schema_version";
 const SET_STATE_KEY: &[u8] = b"grandpa_completed_round";
 const CONCLUDED_ROUNDS: &[u8] = b"grandpa_concluded_rounds";
 const AUTHORITY_SET_KEY: &[u8] = b"grandpa_voters";
+const ROUND_VOTES_KEY: &[u8] = b"grandpa_round_votes";
 
 const CURRENT_VERSION: u32 = 3;
 
@@ -492,6 +494,30 @@ where
 	}
 }
 
+/// Write the authority set on disk after the chain was reverted.
+///
+/// If finalized blocks were reverted, the voter set state restarts from the given new last
+/// finalized block (see `VoterSetState::restarted`) and the votes of its live round are
+/// forgotten. Otherwise the voter set state is kept, along with the votes it cast.
+pub(crate) fn write_reverted_authority_set<Block: BlockT, B: AuxStore>(
+	backend: &B,
+	set: &AuthoritySet<Block::Hash, NumberFor<Block>>,
+	set_state: &VoterSetState<Block>,
+	finalized: Option<(Block::Hash, NumberFor<Block>)>,
+) -> ClientResult<()> {
+	let encoded_set = set.encode();
+	match finalized {
+		Some(finalized) => {
+			let encoded_state = set_state.restarted(set, finalized).encode();
+			backend.insert_aux(
+				&[(AUTHORITY_SET_KEY, &encoded_set[..]), (SET_STATE_KEY, &encoded_state[..])],
+				&[ROUND_VOTES_KEY],
+			)
+		},
+		None => backend.insert_aux(&[(AUTHORITY_SET_KEY, &encoded_set[..])], &[]),
+	}
+}
+
 /// Write voter set state.
 pub(crate) fn write_voter_set_state<Block: BlockT, B: AuxStore>(
 	backend: &B,
@@ -515,6 +541,29 @@ pub(crate) fn write_concluded_round<Block: BlockT, B: AuxStore>(
 	backend.insert_aux(&[(&key[..], round_data.encode().as_slice())], &[])
 }
 
+/// Write a snapshot of the votes observed in a live round, replacing any
+/// previously written snapshot.
+pub(crate) fn write_round_votes<Block: BlockT, B: AuxStore>(
+	backend: &B,
+	round_votes: &RoundVotes<Block>,
+) -> ClientResult<()> {
+	backend.insert_aux(&[(ROUND_VOTES_KEY, round_votes.encode().as_slice())], &[])
+}
+
+/// Load the votes of the persisted live round snapshot, if it matches the
+/// given set id and round number.
+pub(crate) fn load_round_votes<Block: BlockT, B: AuxStore>(
+	backend: &B,
+	set_id: SetId,
+	round: RoundNumber,
+) -> ClientResult<Vec<SignedMessage<Block>>> {
+	match load_decode::<_, RoundVotes<Block>>(backend, ROUND_VOTES_KEY)? {
+		Some(snapshot) if snapshot.set_id == set_id && snapshot.round == round =>
+			Ok(snapshot.votes),
+		_ => Ok(Vec::new()),
+	}
+}
+
 #[cfg(test)]
 pub(crate) fn load_authorities<B: AuxStore, H: Decode, N: Decode + Clone + Ord>(
 	backend: &B
@@ -818,4 +867,79 @@ mod test {
 			Some(completed_round),
 		);
 	}
+
+	#[test]
+	fn write_read_round_votes() {
+		type Block = substrate_test_runtime_client::runtime::Block;
+
+		let client = substrate_test_runtime_client::new();
+		let vote = SignedMessage::<Block> {
+			message: finality_grandpa::Message::Prevote(finality_grandpa::Prevote {
+				target_hash: H256::random(),
+				target_number: 10,
+			}),
+			signature: Default::default(),
+			id: AuthorityId::default(),
+		};
+
+		let round_votes = RoundVotes::<Block> {
+			set_id: 1,
+			round: 42,
+			votes: vec![vote.clone()],
+		};
+
+		assert!(load_round_votes::<Block, _>(&client, 1, 42).unwrap().is_empty());
+		assert!(write_round_votes(&client, &round_votes).is_ok());
+
+		assert_eq!(load_round_votes::<Block, _>(&client, 1, 42).unwrap(), vec![vote]);
+
+		// snapshots of other rounds or sets are ignored
+		assert!(load_round_votes::<Block, _>(&client, 1, 43).unwrap().is_empty());
+		assert!(load_round_votes::<Block, _>(&client, 2, 42).unwrap().is_empty());
+	}
+
+	#[test]
+	fn write_reverted_authority_set_restarts_in_a_later_round() {
+		type Block = substrate_test_runtime_client::runtime::Block;
+
+		let client = substrate_test_runtime_client::new();
+		let authority_set = AuthoritySet::<H256, u64>::genesis(
+			vec![(AuthorityId::default(), 100)],
+		).unwrap();
+
+		let mut set_state = VoterSetState::<Block>::live(0, &authority_set, (H256::random(), 0));
+		if let VoterSetState::Live { current_rounds, .. } = &mut set_state {
+			current_rounds.insert(5, HasVoted::No);
+		}
+		write_voter_set_state(&client, &set_state).unwrap();
+		let round_votes = RoundVotes::<Block> { set_id: 0, round: 5, votes: vec![] };
+		write_round_votes(&client, &round_votes).unwrap();
+
+		// the voter set state is kept if no finalized blocks were reverted
+		write_reverted_authority_set::<Block, _>(&client, &authority_set, &set_state, None)
+			.unwrap();
+		assert_eq!(
+			load_decode::<_, VoterSetState<Block>>(&client, SET_STATE_KEY).unwrap().as_ref(),
+			Some(&set_state),
+		);
+		assert!(load_decode::<_, RoundVotes<Block>>(&client, ROUND_VOTES_KEY).unwrap().is_some());
+
+		// otherwise the voter restarts from the new last finalized block after its last round
+		let finalized = (H256::random(), 3);
+		write_reverted_authority_set::<Block, _>(
+			&client,
+			&authority_set,
+			&set_state,
+			Some(finalized),
+		).unwrap();
+		let restarted = load_decode::<_, VoterSetState<Block>>(&client, SET_STATE_KEY)
+			.unwrap()
+			.unwrap();
+		let last_completed_round = restarted.last_completed_round();
+		assert_eq!(last_completed_round.number, 5);
+		assert_eq!(last_completed_round.base, finalized);
+		assert!(restarted.with_current_round(6).is_ok());
+		assert!(load_decode::<_, RoundVotes<Block>>(&client, ROUND_VOTES_KEY).unwrap().is_none());
+		assert_eq!(load_authorities::<_, H256, u64>(&client), Some(authority_set));
+	}
 }
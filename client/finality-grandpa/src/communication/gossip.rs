@@ -73,7 +73,10 @@
 //! The logic for issuing and tracking pending catch up requests is implemented
 //! in the `GossipValidator`. A catch up request is issued anytime we see a
 //! neighbor packet from a peer at a round `CATCH_UP_THRESHOLD` higher than at
-//! we are.
+//! we are. Right after the validator is created (i.e. when the voter has just
+//! been (re)started) the lower `RESTART_CATCH_UP_THRESHOLD` is used instead, so
+//! that a restarted voter doesn't have to wait for several rounds to time out
+//! before rejoining the rest of the network.
 //!
 //! ## Expiration
 //!
@@ -108,6 +111,14 @@ const CATCH_UP_PROCESS_TIMEOUT: Duration = Duration::from_secs(30);
 /// Maximum number of rounds we are behind a peer before issuing a
 /// catch up request.
 const CATCH_UP_THRESHOLD: u64 = 2;
+/// Maximum number of rounds we are behind a peer before issuing a catch up
+/// request, during the `RESTART_CATCH_UP_PERIOD` following startup.
+///
+/// NOTE: this can't be lower than 1, since we can't request a catch up for
+/// our current round.
+const RESTART_CATCH_UP_THRESHOLD: u64 = 1;
+/// Duration after startup during which the `RESTART_CATCH_UP_THRESHOLD` is used.
+const RESTART_CATCH_UP_PERIOD: Duration = Duration::from_secs(60);
 
 const PROPAGATION_ALL: u32 = 4; //in rounds;
 const PROPAGATION_ALL_AUTHORITIES: u32 = 2; //in rounds;
@@ -679,6 +690,7 @@ struct Inner<Block: BlockT> {
 	next_rebroadcast: Instant,
 	pending_catch_up: PendingCatchUp,
 	catch_up_config: CatchUpConfig,
+	started: Instant,
 }
 
 type MaybeMessage<Block> = Option<(Vec<PeerId>, NeighborPacket<NumberFor<Block>>)>;
@@ -711,6 +723,16 @@ impl<Block: BlockT> Inner<Block> {
 			pending_catch_up: PendingCatchUp::None,
 			catch_up_config,
 			config,
+			started: Instant::now(),
+		}
+	}
+
+	/// The number of rounds we can be behind a peer before issuing a catch up request.
+	fn catch_up_threshold(&self) -> u64 {
+		if self.started.elapsed() < RESTART_CATCH_UP_PERIOD {
+			RESTART_CATCH_UP_THRESHOLD
+		} else {
+			CATCH_UP_THRESHOLD
 		}
 	}
 
@@ -1023,14 +1045,15 @@ impl<Block: BlockT> Inner<Block> {
 		let mut report = None;
 
 		// if the peer is on the same set and ahead of us by a margin bigger
-		// than `CATCH_UP_THRESHOLD` then we should ask it for a catch up
+		// than the catch up threshold then we should ask it for a catch up
 		// message. we only send catch-up requests to authorities, observers
 		// won't be able to reply since they don't follow the full GRANDPA
 		// protocol and therefore might not have the vote data available.
+		let catch_up_threshold = self.catch_up_threshold();
 		if let (Some(peer), Some(local_view)) = (self.peers.peer(who), &self.local_view) {
 			if self.catch_up_config.request_allowed(&peer) &&
 				peer.view.set_id == local_view.set_id &&
-				peer.view.round.0.saturating_sub(CATCH_UP_THRESHOLD) > local_view.round.0
+				peer.view.round.0.saturating_sub(catch_up_threshold) > local_view.round.0
 			{
 				// send catch up request if allowed
 				let round = peer.view.round.0 - 1; // peer.view.round is > 0
@@ -2138,6 +2161,65 @@ mod tests {
 		}
 	}
 
+	#[test]
+	fn uses_lower_catch_up_threshold_after_restart() {
+		let (val, _) = GossipValidator::<Block>::new(
+			config(),
+			voter_set_state(),
+			None,
+		);
+
+		val.note_set(SetId(1), Vec::new(), |_, _| {});
+		val.note_round(Round(41), |_, _| {});
+
+		let peer = PeerId::random();
+		val.inner.write().peers.new_peer(peer.clone(), ObservedRole::Authority);
+
+		let import_neighbor_message = |round| {
+			let (_, _, catch_up_request, _) = val.inner.write().import_neighbor_message(
+				&peer,
+				NeighborPacket {
+					round: Round(round),
+					set_id: SetId(1),
+					commit_finalized_height: 42,
+				},
+			);
+
+			catch_up_request
+		};
+
+		// right after startup, a peer two rounds ahead of us is enough to
+		// trigger a catch up request.
+		match import_neighbor_message(43) {
+			Some(GossipMessage::CatchUpRequest(request)) => {
+				assert_eq!(request.set_id, SetId(1));
+				assert_eq!(request.round, Round(42));
+			},
+			_ => panic!("expected catch up message"),
+		}
+
+		// once the restart period is over we go back to the default threshold.
+		{
+			let mut inner = val.inner.write();
+			inner.started = Instant::now()
+				.checked_sub(RESTART_CATCH_UP_PERIOD + Duration::from_secs(1))
+				.expect("system uptime is higher than the restart catch up period; qed");
+			inner.pending_catch_up = PendingCatchUp::None;
+		}
+
+		match import_neighbor_message(43) {
+			None => {},
+			_ => panic!("expected no catch up message"),
+		}
+
+		match import_neighbor_message(44) {
+			Some(GossipMessage::CatchUpRequest(request)) => {
+				assert_eq!(request.round, Round(43));
+			},
+			_ => panic!("expected catch up message"),
+		}
+	}
+
 	#[test]
 	fn doesnt_send_catch_up_requests_when_disabled() {
 		// we create a gossip validator with catch up requests disabled.
@@ -16,12 +16,13 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::iter::FromIterator;
 use std::marker::PhantomData;
 use std::pin::Pin;
 use std::sync::Arc;
-use std::time::Duration;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 
 use futures::prelude::*;
 use futures_timer::Delay;
@@ -29,7 +30,7 @@ use log::{debug, warn};
 use parity_scale_codec::{Decode, Encode};
 use parking_lot::RwLock;
 
-use sc_client_api::{backend::{Backend, apply_aux}, utils::is_descendent_of};
+use sc_client_api::{backend::{AuxStore, Backend, apply_aux}, utils::is_descendent_of};
 use finality_grandpa::{
 	BlockNumberOps, Error as GrandpaError, round::State as RoundState,
 	voter, voter_set::VoterSet,
@@ -81,6 +82,18 @@ pub struct CompletedRound<Block: BlockT> {
 	pub votes: Vec<SignedMessage<Block>>,
 }
 
+/// Snapshot of the votes observed in a round that is still live. The snapshot
+/// is minimal, i.e. at most one vote of each kind is stored per voter.
+#[derive(Debug, Clone, Decode, Encode, PartialEq)]
+pub struct RoundVotes<Block: BlockT> {
+	/// The voter set id of the round.
+	pub set_id: SetId,
+	/// The round number.
+	pub round: RoundNumber,
+	/// The votes observed in the round so far.
+	pub votes: Vec<SignedMessage<Block>>,
+}
+
 // Data about last completed rounds within a single voter set. Stores
 // NUM_LAST_COMPLETED_ROUNDS and always contains data about at least one round
 // (genesis).
@@ -222,6 +235,42 @@ impl<Block: BlockT> VoterSetState<Block> {
 		}
 	}
 
+	/// Returns the voter set state restarting from the given block, e.g. once the chain was
+	/// reverted below the blocks finalized by the voter. The voter restarts in the round after
+	/// the last one it took part in, so it never votes again in a round of the set.
+	pub(crate) fn restarted(
+		&self,
+		authority_set: &AuthoritySet<Block::Hash, NumberFor<Block>>,
+		base: (Block::Hash, NumberFor<Block>),
+	) -> VoterSetState<Block> {
+		let last_completed_round = self.last_completed_round().number;
+		let last_round = match self {
+			VoterSetState::Live { current_rounds, .. } => current_rounds.keys()
+				.next_back()
+				.map_or(last_completed_round, |round| (*round).max(last_completed_round)),
+			VoterSetState::Paused { .. } => last_completed_round,
+		};
+		let completed_rounds = CompletedRounds::new(
+			CompletedRound {
+				number: last_round,
+				state: RoundState::genesis(base),
+				base,
+				votes: Vec::new(),
+			},
+			authority_set.set_id,
+			authority_set,
+		);
+
+		match self {
+			VoterSetState::Live { .. } => {
+				let mut current_rounds = CurrentRounds::new();
+				current_rounds.insert(last_round + 1, HasVoted::No);
+				VoterSetState::Live { completed_rounds, current_rounds }
+			},
+			VoterSetState::Paused { .. } => VoterSetState::Paused { completed_rounds },
+		}
+	}
+
 	/// Returns the last completed rounds.
 	pub(crate) fn completed_rounds(&self) -> CompletedRounds<Block> {
 		match self {
@@ -374,6 +423,15 @@ impl<Block: BlockT> SharedVoterSetState<Block> {
 		self.voting.write().remove(&round);
 	}
 
+	/// Whether the given round is the latest round that is currently live.
+	pub(crate) fn is_latest_live_round(&self, round: RoundNumber) -> bool {
+		match &*self.inner.read() {
+			VoterSetState::Live { current_rounds, .. } =>
+				current_rounds.keys().next_back() == Some(&round),
+			VoterSetState::Paused { .. } => false,
+		}
+	}
+
 	/// Return vote status information for the current round.
 	pub(crate) fn has_voted(&self, round: RoundNumber) -> HasVoted<Block> {
 		match &*self.inner.read() {
@@ -397,6 +455,132 @@ impl<Block: BlockT> SharedVoterSetState<Block> {
 	}
 }
 
+/// Minimum interval between two consecutive writes of a round votes snapshot.
+const ROUND_VOTES_PERSIST_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A stream of incoming round messages that keeps a snapshot of the votes
+/// observed in the round persisted in the aux-db. When the stream is created
+/// for a round that has a persisted snapshot (e.g. after a restart) the
+/// snapshot votes are yielded first, so that the voter doesn't need to wait
+/// for them to be gossiped again.
+///
+/// The snapshot is only written while the round is the latest live round,
+/// since only one snapshot is kept at a time.
+struct RecordRoundVotes<Block: BlockT, C: AuxStore, S> {
+	client: Arc<C>,
+	voter_set_state: SharedVoterSetState<Block>,
+	snapshot: RoundVotes<Block>,
+	seen: HashSet<(AuthorityId, u8)>,
+	restored: VecDeque<SignedMessage<Block>>,
+	dirty: bool,
+	last_persisted: Instant,
+	inner: S,
+}
+
+impl<Block: BlockT, C: AuxStore, S> RecordRoundVotes<Block, C, S> {
+	fn new(
+		client: Arc<C>,
+		voter_set_state: SharedVoterSetState<Block>,
+		set_id: SetId,
+		round: RoundNumber,
+		inner: S,
+	) -> Self {
+		let restored = crate::aux_schema::load_round_votes::<Block, _>(&*client, set_id, round)
+			.unwrap_or_else(|e| {
+				debug!(target: "afg", "Failed to load votes snapshot of round {}: {:?}", round, e);
+				Vec::new()
+			});
+
+		if !restored.is_empty() {
+			debug!(
+				target: "afg",
+				"Restoring {} votes observed in round {} before restart",
+				restored.len(),
+				round,
+			);
+		}
+
+		RecordRoundVotes {
+			client,
+			voter_set_state,
+			snapshot: RoundVotes { set_id, round, votes: Vec::new() },
+			seen: HashSet::new(),
+			restored: restored.into(),
+			dirty: false,
+			last_persisted: Instant::now(),
+			inner,
+		}
+	}
+
+	fn note_vote(&mut self, vote: &SignedMessage<Block>) {
+		let kind = match vote.message {
+			finality_grandpa::Message::Prevote(_) => 0,
+			finality_grandpa::Message::Precommit(_) => 1,
+			finality_grandpa::Message::PrimaryPropose(_) => 2,
+		};
+
+		// only the first vote of each kind is kept per voter, equivocations
+		// are not needed to resume the round.
+		if self.seen.insert((vote.id.clone(), kind)) {
+			self.snapshot.votes.push(vote.clone());
+			self.dirty = true;
+		}
+
+		if self.last_persisted.elapsed() >= ROUND_VOTES_PERSIST_INTERVAL {
+			self.persist();
+		}
+	}
+
+	fn persist(&mut self) {
+		if !self.dirty || !self.voter_set_state.is_latest_live_round(self.snapshot.round) {
+			return;
+		}
+
+		if let Err(e) = crate::aux_schema::write_round_votes(&*self.client, &self.snapshot) {
+			debug!(
+				target: "afg",
+				"Failed to write votes snapshot of round {}: {:?}",
+				self.snapshot.round,
+				e,
+			);
+		}
+
+		self.dirty = false;
+		self.last_persisted = Instant::now();
+	}
+}
+
+impl<Block: BlockT, C: AuxStore, S: Unpin> Unpin for RecordRoundVotes<Block, C, S> {}
+
+impl<Block, C, S> Stream for RecordRoundVotes<Block, C, S> where
+	Block: BlockT,
+	C: AuxStore,
+	S: Stream<Item = SignedMessage<Block>> + Unpin,
+{
+	type Item = SignedMessage<Block>;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+		let this = &mut *self;
+
+		let next = match this.restored.pop_front() {
+			Some(vote) => Poll::Ready(Some(vote)),
+			None => this.inner.poll_next_unpin(cx),
+		};
+
+		if let Poll::Ready(Some(ref vote)) = next {
+			this.note_vote(vote);
+		}
+
+		next
+	}
+}
+
+impl<Block: BlockT, C: AuxStore, S> Drop for RecordRoundVotes<Block, C, S> {
+	fn drop(&mut self) {
+		self.persist();
+	}
+}
+
 /// Prometheus metrics for GRANDPA.
 #[derive(Clone)]
 pub(crate) struct Metrics {
@@ -820,6 +1004,16 @@ where
 			has_voted,
 		);
 
+		// replay the votes we had already observed in this round before a restart
+		// and keep a snapshot of the round votes persisted while the round is live.
+		let incoming = RecordRoundVotes::new(
+			self.client.clone(),
+			self.voter_set_state.clone(),
+			self.set_id,
+			round,
+			incoming,
+		);
+
 		// schedule incoming messages from the network to be held until
 		// corresponding blocks are imported.
 		let incoming = Box::pin(UntilVoteTargetImported::new(
@@ -495,6 +495,79 @@ impl<Block: BlockT, E> GenesisAuthoritySetProvider<Block> for Arc<dyn ExecutorPr
 	}
 }
 
+/// Prepare the revert of the GRANDPA data of the given number of best blocks, before they are
+/// reverted from the chain. The returned [`GrandpaRevert`] reverts the data once they are.
+///
+/// Fails if the current authority set was enacted by one of the blocks, since the previous
+/// sets aren't kept.
+pub fn revert<Block, Client>(
+	client: Arc<Client>,
+	blocks: NumberFor<Block>,
+) -> Result<GrandpaRevert<Block, Client>, ClientError>
+where
+	Block: BlockT,
+	Client: AuxStore + HeaderBackend<Block> + HeaderMetadata<Block, Error = ClientError>,
+{
+	let info = client.info();
+	let number = info.best_number - blocks.min(info.best_number);
+	let revert = GrandpaRevert { client, finalized_number: info.finalized_number };
+	revert.reverted_authority_set(number)?;
+	Ok(revert)
+}
+
+/// Reverts the GRANDPA data of the blocks reverted from the chain, see [`revert`].
+pub struct GrandpaRevert<Block: BlockT, Client> {
+	client: Arc<Client>,
+	/// The last finalized block before the revert.
+	finalized_number: NumberFor<Block>,
+}
+
+impl<Block, Client> GrandpaRevert<Block, Client>
+where
+	Block: BlockT,
+	Client: AuxStore + HeaderBackend<Block> + HeaderMetadata<Block, Error = ClientError>,
+{
+	/// Revert the GRANDPA data to the best block, once the chain is reverted: the authority set
+	/// changes signaled by the reverted blocks are removed and, if finalized blocks were
+	/// reverted, the voter restarts from the new last finalized block in a later round.
+	pub fn finish(self) -> Result<(), ClientError> {
+		let info = self.client.info();
+		let (authority_set, persistent_data) = self.reverted_authority_set(info.best_number)?;
+		let finalized = if info.finalized_number < self.finalized_number {
+			Some((info.finalized_hash, info.finalized_number))
+		} else {
+			None
+		};
+		let set_state = persistent_data.set_state.read();
+		aux_schema::write_reverted_authority_set::<Block, _>(
+			&*self.client,
+			&authority_set,
+			&set_state,
+			finalized,
+		)
+	}
+
+	/// Returns the authority set without the changes signaled after the given block, along
+	/// with the persisted data it was loaded from.
+	fn reverted_authority_set(
+		&self,
+		number: NumberFor<Block>,
+	) -> Result<(AuthoritySet<Block::Hash, NumberFor<Block>>, PersistentData<Block>), ClientError> {
+		let persistent_data = aux_schema::load_persistent::<Block, _, _>(
+			&*self.client,
+			self.client.info().genesis_hash,
+			Zero::zero(),
+			|| Err(ClientError::Backend("There is no GRANDPA data to revert".into())),
+		)?;
+		let mut authority_set = persistent_data.authority_set.clone_inner();
+		let is_descendent_of = sc_client_api::utils::is_descendent_of(&*self.client, None);
+		authority_set.revert(number, &is_descendent_of)
+			.map_err(|e| ClientError::Backend(e.to_string()))?;
+
+		Ok((authority_set, persistent_data))
+	}
+}
+
 /// Make block importer and link half necessary to tie the background voter
 /// to it.
 pub fn block_import<BE, Block: BlockT, Client, SC>(
@@ -586,6 +586,7 @@ mod tests {
 				header,
 				is_new_best: false,
 				tree_route: None,
+				unpin_handle: None,
 			}).unwrap();
 		}
 	}
@@ -16,15 +16,19 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::OutputFormat;
+use crate::{OutputFormat, OutputStyle};
 use ansi_term::Colour;
 use log::info;
 use sc_client_api::ClientInfo;
 use sc_network::{NetworkStatus, SyncState};
-use sp_runtime::traits::{Block as BlockT, CheckedDiv, NumberFor, Saturating, Zero};
+use sp_runtime::{
+	traits::{Block as BlockT, CheckedDiv, NumberFor, Saturating, Zero},
+	SaturatedConversion,
+};
 use std::{
 	convert::{TryFrom, TryInto},
 	fmt,
+	io::{self, Write},
 };
 use wasm_timer::Instant;
 
@@ -53,6 +57,8 @@ pub struct InformantDisplay<B: BlockT> {
 	last_total_bytes_outbound: u64,
 	/// The format to print output in.
 	format: OutputFormat,
+	/// Where the status lines are written in the JSON format.
+	json_output: Box<dyn Write + Send>,
 }
 
 impl<B: BlockT> InformantDisplay<B> {
@@ -64,16 +70,19 @@ impl<B: BlockT> InformantDisplay<B> {
 			last_total_bytes_inbound: 0,
 			last_total_bytes_outbound: 0,
 			format,
+			json_output: Box::new(io::stdout()),
 		}
 	}
 
-	/// Displays the informant by calling `info!`.
+	/// Displays the informant by calling `info!`, or by writing a line to the standard output
+	/// in the JSON format.
 	pub fn display(&mut self, info: &ClientInfo<B>, net_status: NetworkStatus<B>) {
 		let best_number = info.chain.best_number;
 		let best_hash = info.chain.best_hash;
 		let finalized_number = info.chain.finalized_number;
 		let num_connected_peers = net_status.num_connected_peers;
 		let speed = speed::<B>(best_number, self.last_number, self.last_update);
+		let speed_text = speed.as_ref().map(ToString::to_string).unwrap_or_default();
 		let total_bytes_inbound = net_status.total_bytes_inbound;
 		let total_bytes_outbound = net_status.total_bytes_outbound;
 
@@ -93,12 +102,39 @@ impl<B: BlockT> InformantDisplay<B> {
 				(diff_bytes_inbound, diff_bytes_outbound)
 			};
 
+		let statuses = self.format.status_providers.statuses();
+
+		if self.format.style == OutputStyle::Json {
+			let (status, target) = match (net_status.sync_state, net_status.best_seen_block) {
+				(SyncState::Idle, _) => ("idle", None),
+				(SyncState::Downloading, None) => ("preparing", None),
+				(SyncState::Downloading, Some(n)) => ("syncing", Some(n.saturated_into::<u64>())),
+			};
+			let status = serde_json::json!({
+				"status": status,
+				"targetNumber": target,
+				"blocksPerSecond": speed.map(|speed| speed.to_json()),
+				"peers": num_connected_peers,
+				"bestNumber": best_number.saturated_into::<u64>(),
+				"bestHash": format!("{:?}", best_hash),
+				"finalizedNumber": finalized_number.saturated_into::<u64>(),
+				"finalizedHash": format!("{:?}", info.chain.finalized_hash),
+				"bytesPerSecondInbound": avg_bytes_per_sec_inbound,
+				"bytesPerSecondOutbound": avg_bytes_per_sec_outbound,
+				"statuses": statuses.into_iter()
+					.map(|(name, status)| (name, status.into()))
+					.collect::<serde_json::Map<_, _>>(),
+			});
+			let _ = write_json(&mut self.json_output, &status);
+			return;
+		}
+
 		let (level, status, target) = match (net_status.sync_state, net_status.best_seen_block) {
 			(SyncState::Idle, _) => ("💤", "Idle".into(), "".into()),
-			(SyncState::Downloading, None) => ("⚙️ ", format!("Preparing{}", speed), "".into()),
+			(SyncState::Downloading, None) => ("⚙️ ", format!("Preparing{}", speed_text), "".into()),
 			(SyncState::Downloading, Some(n)) => (
 				"⚙️ ",
-				format!("Syncing{}", speed),
+				format!("Syncing{}", speed_text),
 				format!(", target=#{}", n),
 			),
 		};
@@ -106,7 +142,7 @@ impl<B: BlockT> InformantDisplay<B> {
 		if self.format.enable_color {
 			info!(
 				target: "substrate",
-				"{} {}{} ({} peers), best: #{} ({}), finalized #{} ({}), {} {}",
+				"{} {}{} ({} peers), best: #{} ({}), finalized #{} ({}), {} {}{}",
 				level,
 				Colour::White.bold().paint(&status),
 				target,
@@ -117,11 +153,12 @@ impl<B: BlockT> InformantDisplay<B> {
 				info.chain.finalized_hash,
 				Colour::Green.paint(format!("⬇ {}", TransferRateFormat(avg_bytes_per_sec_inbound))),
 				Colour::Red.paint(format!("⬆ {}", TransferRateFormat(avg_bytes_per_sec_outbound))),
+				StatusesFormat(&statuses),
 			)
 		} else {
 			info!(
 				target: "substrate",
-				"{} {}{} ({} peers), best: #{} ({}), finalized #{} ({}), ⬇ {} ⬆ {}",
+				"{} {}{} ({} peers), best: #{} ({}), finalized #{} ({}), ⬇ {} ⬆ {}{}",
 				level,
 				status,
 				target,
@@ -132,18 +169,60 @@ impl<B: BlockT> InformantDisplay<B> {
 				info.chain.finalized_hash,
 				TransferRateFormat(avg_bytes_per_sec_inbound),
 				TransferRateFormat(avg_bytes_per_sec_outbound),
+				StatusesFormat(&statuses),
 			)
 		}
 	}
 }
 
-/// Calculates `(best_number - last_number) / (now - last_update)` and returns a `String`
-/// representing the speed of import.
+/// Writes the given JSON object as one line of the standard output.
+///
+/// The line doesn't go through the logger, which would prefix it with e.g. the time and the
+/// level, so it can be parsed as is.
+pub(crate) fn print_json(value: &serde_json::Value) {
+	let _ = write_json(&mut io::stdout(), value);
+}
+
+fn write_json(output: &mut impl Write, value: &serde_json::Value) -> io::Result<()> {
+	let mut line = serde_json::to_vec(value)?;
+	line.push(b'\n');
+	output.write_all(&line)?;
+	output.flush()
+}
+
+/// Speed of import, in blocks per second.
+enum Speed<B: BlockT> {
+	/// The number of imported blocks could be converted to a regular integer.
+	Float(f64),
+	/// Otherwise, the speed is an integer.
+	Number(NumberFor<B>),
+}
+
+impl<B: BlockT> Speed<B> {
+	fn to_json(&self) -> serde_json::Value {
+		match self {
+			Speed::Float(speed) => (*speed).into(),
+			Speed::Number(speed) => (*speed).saturated_into::<u64>().into(),
+		}
+	}
+}
+
+impl<B: BlockT> fmt::Display for Speed<B> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Speed::Float(speed) => write!(f, " {:4.1} bps", speed),
+			Speed::Number(speed) => write!(f, " {} bps", speed),
+		}
+	}
+}
+
+/// Calculates `(best_number - last_number) / (now - last_update)`, `None` if the informant
+/// is displayed for the first time.
 fn speed<B: BlockT>(
 	best_number: NumberFor<B>,
 	last_number: Option<NumberFor<B>>,
 	last_update: Instant
-) -> String {
+) -> Option<Speed<B>> {
 	// Number of milliseconds elapsed since last time.
 	let elapsed_ms = {
 		let elapsed = last_update.elapsed();
@@ -154,7 +233,7 @@ fn speed<B: BlockT>(
 
 	// Number of blocks that have been imported since last time.
 	let diff = match last_number {
-		None => return String::new(),
+		None => return None,
 		Some(n) => best_number.saturating_sub(n)
 	};
 
@@ -163,7 +242,7 @@ fn speed<B: BlockT>(
 		// do the math and turn it into a `f64`.
 		let speed = diff.saturating_mul(10_000).checked_div(u128::from(elapsed_ms))
 			.map_or(0.0, |s| s as f64) / 10.0;
-		format!(" {:4.1} bps", speed)
+		Some(Speed::Float(speed))
 
 	} else {
 		// If the number of blocks can't be converted to a regular integer, then we need a more
@@ -175,7 +254,7 @@ fn speed<B: BlockT>(
 
 		let speed = diff.saturating_mul(one_thousand).checked_div(&elapsed)
 			.unwrap_or_else(Zero::zero);
-		format!(" {} bps", speed)
+		Some(Speed::Number(speed))
 	}
 }
 
@@ -202,3 +281,96 @@ impl fmt::Display for TransferRateFormat {
 		write!(f, "{:.1}MiB/s", self.0 as f64 / (1024.0 * 1024.0))
 	}
 }
+
+/// Contains the statuses of the status providers. Implements `fmt::Display` and shows them as
+/// `, name: status` each.
+struct StatusesFormat<'a>(&'a [(String, String)]);
+impl<'a> fmt::Display for StatusesFormat<'a> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		for (name, status) in self.0 {
+			write!(f, ", {}: {}", name, status)?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::status::StatusProvider;
+	use parking_lot::Mutex;
+	use sp_runtime::testing::{Block as RawBlock, ExtrinsicWrapper, H256};
+	use std::sync::Arc;
+
+	type Block = RawBlock<ExtrinsicWrapper<u64>>;
+
+	#[derive(Clone, Default)]
+	struct Output(Arc<Mutex<Vec<u8>>>);
+
+	impl Write for Output {
+		fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+			self.0.lock().write(buf)
+		}
+
+		fn flush(&mut self) -> io::Result<()> {
+			Ok(())
+		}
+	}
+
+	struct Collator;
+
+	impl StatusProvider for Collator {
+		fn name(&self) -> &str {
+			"collator"
+		}
+
+		fn status(&self) -> Option<String> {
+			Some("collating".into())
+		}
+	}
+
+	#[test]
+	fn json_status_line_can_be_parsed() {
+		let format = OutputFormat { style: OutputStyle::Json, ..Default::default() };
+		format.status_providers.register(Arc::new(Collator)).unwrap();
+		let mut display = InformantDisplay::<Block>::new(format);
+		let output = Output::default();
+		display.json_output = Box::new(output.clone());
+
+		let info = ClientInfo {
+			chain: sp_blockchain::Info {
+				best_hash: H256::repeat_byte(1),
+				best_number: 10,
+				genesis_hash: H256::repeat_byte(0),
+				finalized_hash: H256::repeat_byte(2),
+				finalized_number: 8,
+				number_leaves: 1,
+			},
+			usage: None,
+		};
+		let net_status = NetworkStatus {
+			sync_state: SyncState::Downloading,
+			best_seen_block: Some(20),
+			num_sync_peers: 3,
+			num_connected_peers: 4,
+			num_active_peers: 3,
+			total_bytes_inbound: 0,
+			total_bytes_outbound: 0,
+		};
+		display.display(&info, net_status);
+
+		let line = output.0.lock().clone();
+		assert_eq!(line.iter().filter(|byte| **byte == b'\n').count(), 1);
+		assert_eq!(line.last(), Some(&b'\n'));
+		let status: serde_json::Value = serde_json::from_slice(&line).unwrap();
+		assert_eq!(status["status"], "syncing");
+		assert_eq!(status["targetNumber"], 20);
+		assert_eq!(status["blocksPerSecond"], serde_json::Value::Null);
+		assert_eq!(status["peers"], 4);
+		assert_eq!(status["bestNumber"], 10);
+		assert_eq!(status["bestHash"], format!("{:?}", H256::repeat_byte(1)));
+		assert_eq!(status["finalizedNumber"], 8);
+		assert_eq!(status["finalizedHash"], format!("{:?}", H256::repeat_byte(2)));
+		assert_eq!(status["statuses"]["collator"], "collating");
+	}
+}
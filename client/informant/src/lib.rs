@@ -25,12 +25,15 @@ use parity_util_mem::MallocSizeOf;
 use sc_client_api::{BlockchainEvents, UsageProvider};
 use sc_network::NetworkStatus;
 use sp_blockchain::HeaderMetadata;
-use sp_runtime::traits::{Block as BlockT, Header};
+use sp_runtime::{traits::{Block as BlockT, Header}, SaturatedConversion};
 use sp_transaction_pool::TransactionPool;
 use sp_utils::{status_sinks, mpsc::tracing_unbounded};
 use std::{fmt::Display, sync::Arc, time::Duration, collections::VecDeque};
 
 mod display;
+mod status;
+
+pub use status::{DuplicateName, StatusProvider, StatusProviders};
 
 /// The format to print telemetry output in.
 #[derive(Clone, Debug)]
@@ -39,16 +42,33 @@ pub struct OutputFormat {
 	///
 	/// Is enabled by default.
 	pub enable_color: bool,
+	/// How the status lines are printed.
+	///
+	/// Is `OutputStyle::Text` by default.
+	pub style: OutputStyle,
+	/// The providers of the statuses appended to the status line.
+	pub status_providers: StatusProviders,
 }
 
 impl Default for OutputFormat {
 	fn default() -> Self {
 		Self {
 			enable_color: true,
+			style: OutputStyle::Text,
+			status_providers: Default::default(),
 		}
 	}
 }
 
+/// How the status lines are printed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputStyle {
+	/// Human readable text, colored if `enable_color` is set.
+	Text,
+	/// One JSON object per line, e.g. to be parsed by log pipelines.
+	Json,
+}
+
 /// Marker trait for a type that implements `TransactionPool` and `MallocSizeOf` on `not(target_os = "unknown")`.
 #[cfg(target_os = "unknown")]
 pub trait TransactionPoolAndMaybeMallogSizeOf: TransactionPool {}
@@ -103,11 +123,14 @@ where
 
 	future::join(
 		display_notifications,
-		display_block_import(client),
+		display_block_import(client, format.style),
 	).map(|_| ())
 }
 
-fn display_block_import<B: BlockT, C>(client: Arc<C>) -> impl Future<Output = ()>
+fn display_block_import<B: BlockT, C>(
+	client: Arc<C>,
+	style: OutputStyle,
+) -> impl Future<Output = ()>
 where
 	C: UsageProvider<B> + HeaderMetadata<B> + BlockchainEvents<B>,
 	<C as HeaderMetadata<B>>::Error: Display,
@@ -132,6 +155,16 @@ where
 				);
 
 				match maybe_ancestor {
+					Ok(ref ancestor) if ancestor.hash != *last_hash && style == OutputStyle::Json =>
+						display::print_json(&serde_json::json!({
+							"event": "reorg",
+							"fromNumber": (*last_num).saturated_into::<u64>(),
+							"fromHash": format!("{:?}", last_hash),
+							"toNumber": (*n.header.number()).saturated_into::<u64>(),
+							"toHash": format!("{:?}", n.hash),
+							"ancestorNumber": ancestor.number.saturated_into::<u64>(),
+							"ancestorHash": format!("{:?}", ancestor.hash),
+						})),
 					Ok(ref ancestor) if ancestor.hash != *last_hash => info!(
 						"♻️  Reorg on #{},{} to #{},{}, common ancestor #{},{}",
 						Colour::Red.bold().paint(format!("{}", last_num)), last_hash,
@@ -158,12 +191,20 @@ where
 				last_blocks.pop_front();
 			}
 
-			info!(
-				target: "substrate",
-				"✨ Imported #{} ({})",
-				Colour::White.bold().paint(format!("{}", n.header.number())),
-				n.hash,
-			);
+			if style == OutputStyle::Json {
+				display::print_json(&serde_json::json!({
+					"event": "imported",
+					"number": (*n.header.number()).saturated_into::<u64>(),
+					"hash": format!("{:?}", n.hash),
+				}));
+			} else {
+				info!(
+					target: "substrate",
+					"✨ Imported #{} ({})",
+					Colour::White.bold().paint(format!("{}", n.header.number())),
+					n.hash,
+				);
+			}
 		}
 
 		future::ready(())
@@ -0,0 +1,138 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2019-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Additional statuses appended to the status line of the informant.
+//!
+//! Nodes can report the status of their own components, e.g. a collator or a bridge,
+//! on the line the informant regularly prints instead of logging it from separate tasks.
+
+use parking_lot::RwLock;
+use std::{fmt, sync::Arc};
+
+/// Provides a status appended to the status line of the informant.
+pub trait StatusProvider: Send + Sync {
+	/// The name the status is printed with, e.g. `collator`.
+	fn name(&self) -> &str;
+
+	/// Returns the current status, or `None` if there is nothing to print.
+	///
+	/// Called every time the status line is printed, so it should be cheap.
+	fn status(&self) -> Option<String>;
+}
+
+/// The providers of the statuses appended to the status line.
+///
+/// The clones share the providers, so they can be registered after the informant
+/// is started.
+#[derive(Clone, Default)]
+pub struct StatusProviders(Arc<RwLock<Vec<Arc<dyn StatusProvider>>>>);
+
+impl StatusProviders {
+	/// Append the status of the given provider to the status line.
+	///
+	/// Fails if a provider with the same name is already registered, as their statuses
+	/// couldn't be told apart.
+	pub fn register(&self, provider: Arc<dyn StatusProvider>) -> Result<(), DuplicateName> {
+		let mut providers = self.0.write();
+		if providers.iter().any(|registered| registered.name() == provider.name()) {
+			return Err(DuplicateName(provider.name().into()));
+		}
+		providers.push(provider);
+		Ok(())
+	}
+
+	/// Returns the names and current statuses of the providers, in the order they were
+	/// registered in.
+	pub(crate) fn statuses(&self) -> Vec<(String, String)> {
+		self.0.read()
+			.iter()
+			.filter_map(|provider| provider.status().map(|status| (provider.name().into(), status)))
+			.collect()
+	}
+}
+
+impl fmt::Debug for StatusProviders {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_list().entries(self.0.read().iter().map(|provider| provider.name())).finish()
+	}
+}
+
+/// A status provider was registered with the name of another one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateName(pub String);
+
+impl fmt::Display for DuplicateName {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "a status provider named `{}` is already registered", self.0)
+	}
+}
+
+impl std::error::Error for DuplicateName {}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	struct Provider(&'static str, Option<&'static str>);
+
+	impl StatusProvider for Provider {
+		fn name(&self) -> &str {
+			self.0
+		}
+
+		fn status(&self) -> Option<String> {
+			self.1.map(Into::into)
+		}
+	}
+
+	#[test]
+	fn statuses_are_listed_in_registration_order() {
+		let providers = StatusProviders::default();
+		providers.register(Arc::new(Provider("collator", Some("collating")))).unwrap();
+		providers.register(Arc::new(Provider("idle", None))).unwrap();
+		providers.register(Arc::new(Provider("bridge", Some("2 blocks behind")))).unwrap();
+
+		assert_eq!(
+			providers.statuses(),
+			vec![
+				("collator".into(), "collating".into()),
+				("bridge".into(), "2 blocks behind".into()),
+			],
+		);
+	}
+
+	#[test]
+	fn providers_registered_on_a_clone_are_shared() {
+		let providers = StatusProviders::default();
+		providers.clone().register(Arc::new(Provider("collator", Some("collating")))).unwrap();
+
+		assert_eq!(providers.statuses(), vec![("collator".into(), "collating".into())]);
+	}
+
+	#[test]
+	fn providers_with_the_same_name_are_rejected() {
+		let providers = StatusProviders::default();
+		providers.register(Arc::new(Provider("collator", Some("collating")))).unwrap();
+
+		assert_eq!(
+			providers.register(Arc::new(Provider("collator", Some("idle")))),
+			Err(DuplicateName("collator".into())),
+		);
+		assert_eq!(providers.statuses(), vec![("collator".into(), "collating".into())]);
+	}
+}
@@ -45,6 +45,7 @@ use sc_client_api::{
 	},
 	light::Storage as BlockchainStorage,
 	in_mem::check_genesis_storage,
+	AuxKeySpaces,
 	UsageInfo,
 };
 use super::blockchain::Blockchain;
@@ -57,6 +58,7 @@ pub struct Backend<S, H: Hasher> {
 	blockchain: Arc<Blockchain<S>>,
 	genesis_state: RwLock<Option<InMemoryBackend<H>>>,
 	import_lock: RwLock<()>,
+	aux_key_spaces: AuxKeySpaces,
 }
 
 /// Light block (header and justification) import operation.
@@ -88,6 +90,7 @@ impl<S, H: Hasher> Backend<S, H> {
 			blockchain,
 			genesis_state: RwLock::new(None),
 			import_lock: Default::default(),
+			aux_key_spaces: Default::default(),
 		}
 	}
 
@@ -241,6 +244,10 @@ impl<S, Block> ClientBackend<Block> for Backend<S, HashFor<Block>>
 	fn get_import_lock(&self) -> &RwLock<()> {
 		&self.import_lock
 	}
+
+	fn aux_key_spaces(&self) -> &AuxKeySpaces {
+		&self.aux_key_spaces
+	}
 }
 
 impl<S, Block> RemoteBackend<Block> for Backend<S, HashFor<Block>>
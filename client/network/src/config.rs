@@ -173,13 +173,18 @@ pub type TransactionImportFuture = Pin<Box<dyn Future<Output=TransactionImport>
 pub trait TransactionPool<H: ExHashT, B: BlockT>: Send + Sync {
 	/// Get transactions from the pool that are ready to be propagated.
 	fn transactions(&self) -> Vec<(H, B::Extrinsic)>;
+	/// Get the transactions that are ready to be propagated and were submitted locally.
+	///
+	/// These are periodically propagated again, even to the peers that already know them.
+	fn local_transactions(&self) -> Vec<(H, B::Extrinsic)>;
 	/// Get hash of transaction.
 	fn hash_of(&self, transaction: &B::Extrinsic) -> H;
-	/// Import a transaction into the pool.
+	/// Import a transaction received from the given peer into the pool.
 	///
 	/// This will return future.
 	fn import(
 		&self,
+		peer: &PeerId,
 		transaction: B::Extrinsic,
 	) -> TransactionImportFuture;
 	/// Notify the pool about transactions broadcast.
@@ -201,12 +206,17 @@ impl<H: ExHashT + Default, B: BlockT> TransactionPool<H, B> for EmptyTransaction
 		Vec::new()
 	}
 
+	fn local_transactions(&self) -> Vec<(H, B::Extrinsic)> {
+		Vec::new()
+	}
+
 	fn hash_of(&self, _transaction: &B::Extrinsic) -> H {
 		Default::default()
 	}
 
 	fn import(
 		&self,
+		_peer: &PeerId,
 		_transaction: B::Extrinsic
 	) -> TransactionImportFuture {
 		Box::pin(future::ready(TransactionImport::KnownGood))
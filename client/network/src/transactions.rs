@@ -49,6 +49,10 @@ use std::{iter, num::NonZeroUsize, pin::Pin, task::Poll, time};
 /// Interval at which we propagate transactions;
 const PROPAGATE_TIMEOUT: time::Duration = time::Duration::from_millis(2900);
 
+/// Interval at which we propagate the locally submitted transactions again, even to the peers
+/// that already know them.
+const LOCAL_REPROPAGATE_TIMEOUT: time::Duration = time::Duration::from_secs(30);
+
 /// Maximum number of known transaction hashes to keep for a peer.
 ///
 /// This should be approx. 2 blocks full of transactions for the network to function properly.
@@ -165,6 +169,7 @@ impl TransactionsHandlerPrototype {
 		let handler = TransactionsHandler {
 			protocol_name: self.protocol_name,
 			propagate_timeout: Box::pin(interval(PROPAGATE_TIMEOUT)),
+			local_repropagate_timeout: Box::pin(interval(LOCAL_REPROPAGATE_TIMEOUT)),
 			pending_transactions: FuturesUnordered::new(),
 			pending_transactions_peers: HashMap::new(),
 			gossip_enabled: gossip_enabled.clone(),
@@ -229,6 +234,8 @@ pub struct TransactionsHandler<B: BlockT + 'static, H: ExHashT> {
 	protocol_name: Cow<'static, str>,
 	/// Interval at which we call `propagate_transactions`.
 	propagate_timeout: Pin<Box<dyn Stream<Item = ()> + Send>>,
+	/// Interval at which we call `repropagate_local_transactions`.
+	local_repropagate_timeout: Pin<Box<dyn Stream<Item = ()> + Send>>,
 	/// Pending transactions verification tasks.
 	pending_transactions: FuturesUnordered<PendingTransaction<H>>,
 	/// As multiple peers can send us the same transaction, we group
@@ -267,6 +274,9 @@ impl<B: BlockT + 'static, H: ExHashT> TransactionsHandler<B, H> {
 				_ = self.propagate_timeout.next().fuse() => {
 					self.propagate_transactions();
 				},
+				_ = self.local_repropagate_timeout.next().fuse() => {
+					self.repropagate_local_transactions();
+				},
 				(tx_hash, result) = self.pending_transactions.select_next_some() => {
 					if let Some(peers) = self.pending_transactions_peers.remove(&tx_hash) {
 						peers.into_iter().for_each(|p| self.on_handle_transaction_import(p, result));
@@ -392,7 +402,7 @@ impl<B: BlockT + 'static, H: ExHashT> TransactionsHandler<B, H> {
 				match self.pending_transactions_peers.entry(hash.clone()) {
 					Entry::Vacant(entry) => {
 						self.pending_transactions.push(PendingTransaction {
-							validation: self.transaction_pool.import(t),
+							validation: self.transaction_pool.import(&who, t),
 							tx_hash: hash,
 						});
 						entry.insert(vec![who.clone()]);
@@ -425,14 +435,18 @@ impl<B: BlockT + 'static, H: ExHashT> TransactionsHandler<B, H> {
 			return;
 		}
 		if let Some(transaction) = self.transaction_pool.transaction(hash) {
-			let propagated_to = self.do_propagate_transactions(&[(hash.clone(), transaction)]);
+			let propagated_to =
+				self.do_propagate_transactions(&[(hash.clone(), transaction)], false);
 			self.transaction_pool.on_broadcasted(propagated_to);
 		}
 	}
 
+	/// Send the given transactions to all peers that don't know them yet, or to all peers
+	/// if `resend` is true.
 	fn do_propagate_transactions(
 		&mut self,
 		transactions: &[(H, B::Extrinsic)],
+		resend: bool,
 	) -> HashMap<H, Vec<String>> {
 		let mut propagated_to = HashMap::<_, Vec<_>>::new();
 		let mut propagated_transactions = 0;
@@ -445,7 +459,7 @@ impl<B: BlockT + 'static, H: ExHashT> TransactionsHandler<B, H> {
 
 			let (hashes, to_send): (Vec<_>, Vec<_>) = transactions
 				.iter()
-				.filter(|&(ref hash, _)| peer.known_transactions.insert(hash.clone()))
+				.filter(|&(ref hash, _)| peer.known_transactions.insert(hash.clone()) || resend)
 				.cloned()
 				.unzip();
 
@@ -482,7 +496,22 @@ impl<B: BlockT + 'static, H: ExHashT> TransactionsHandler<B, H> {
 		}
 		debug!(target: "sync", "Propagating transactions");
 		let transactions = self.transaction_pool.transactions();
-		let propagated_to = self.do_propagate_transactions(&transactions);
+		let propagated_to = self.do_propagate_transactions(&transactions, false);
+		self.transaction_pool.on_broadcasted(propagated_to);
+	}
+
+	/// Call when we must propagate the locally submitted transactions again, in case they
+	/// were dropped by our peers.
+	fn repropagate_local_transactions(&mut self) {
+		if !self.gossip_enabled.load(Ordering::Relaxed) {
+			return;
+		}
+		let transactions = self.transaction_pool.local_transactions();
+		if transactions.is_empty() {
+			return;
+		}
+		debug!(target: "sync", "Propagating {} local transactions again", transactions.len());
+		let propagated_to = self.do_propagate_transactions(&transactions, true);
 		self.transaction_pool.on_broadcasted(propagated_to);
 	}
 }
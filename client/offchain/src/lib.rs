@@ -86,6 +86,7 @@ pub struct OffchainWorkers<Client, Storage, Block: traits::Block> {
 	_block: PhantomData<Block>,
 	thread_pool: Mutex<ThreadPool>,
 	shared_client: SharedClient,
+	paused: Option<Box<dyn Fn() -> bool + Send + Sync>>,
 }
 
 impl<Client, Storage, Block: traits::Block> OffchainWorkers<Client, Storage, Block> {
@@ -98,8 +99,16 @@ impl<Client, Storage, Block: traits::Block> OffchainWorkers<Client, Storage, Blo
 			_block: PhantomData,
 			thread_pool: Mutex::new(ThreadPool::new(num_cpus::get())),
 			shared_client,
+			paused: None,
 		}
 	}
+
+	/// Don't start the offchain workers while the given function returns `true`, e.g. while
+	/// the disk is almost full.
+	pub fn pause_when(mut self, paused: impl Fn() -> bool + Send + Sync + 'static) -> Self {
+		self.paused = Some(Box::new(paused));
+		self
+	}
 }
 
 impl<Client, Storage, Block: traits::Block> fmt::Debug for OffchainWorkers<
@@ -130,8 +139,12 @@ impl<Client, Storage, Block> OffchainWorkers<
 		network_provider: Arc<dyn NetworkProvider + Send + Sync>,
 		is_validator: bool,
 	) -> impl Future<Output = ()> {
-		let runtime = self.client.runtime_api();
 		let at = BlockId::hash(header.hash());
+		if self.paused.as_ref().map_or(false, |paused| paused()) {
+			debug!("Offchain workers are paused, skipping them at {:?}", at);
+			return futures::future::Either::Right(futures::future::ready(()));
+		}
+		let runtime = self.client.runtime_api();
 		let has_api_v1 = runtime.has_api_with::<dyn OffchainWorkerApi<Block>, _>(
 			&at, |v| v == 1
 		);
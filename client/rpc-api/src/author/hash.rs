@@ -18,6 +18,8 @@
 
 //! Extrinsic helpers for author RPC module.
 
+use std::collections::BTreeMap;
+
 use sp_core::Bytes;
 use serde::{Serialize, Deserialize};
 
@@ -32,3 +34,45 @@ pub enum ExtrinsicOrHash<Hash> {
 	/// Raw extrinsic bytes.
 	Extrinsic(Bytes),
 }
+
+/// RPC representation of a transaction in the pool.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolExtrinsic<Hash> {
+	/// The hash of the extrinsic.
+	pub hash: Hash,
+	/// Priority of the extrinsic.
+	pub priority: u64,
+	/// Number of blocks the extrinsic is valid for.
+	pub longevity: u64,
+	/// Tags provided by the extrinsic.
+	pub provides: Vec<Bytes>,
+	/// Tags required by the extrinsic.
+	pub requires: Vec<Bytes>,
+	/// Raw extrinsic bytes.
+	pub bytes: Bytes,
+}
+
+/// RPC representation of the ready or future part of the pool.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolExtrinsics<Hash> {
+	/// The extrinsics in this part of the pool.
+	pub extrinsics: Vec<PoolExtrinsic<Hash>>,
+	/// Number of extrinsics providing each tag.
+	pub provided_tags: BTreeMap<Bytes, u32>,
+	/// Number of extrinsics requiring each tag.
+	pub required_tags: BTreeMap<Bytes, u32>,
+}
+
+/// RPC representation of the outcome of a dry run of an extrinsic.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunResult<Hash> {
+	/// The hash of the extrinsic.
+	pub hash: Hash,
+	/// SCALE encoded result of applying the extrinsic.
+	pub result: Bytes,
+	/// SCALE encoded events deposited in the block the extrinsic was applied in.
+	pub events: Bytes,
+}
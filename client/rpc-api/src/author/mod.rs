@@ -39,6 +39,22 @@ pub trait AuthorApi<Hash, BlockHash> {
 	#[rpc(name = "author_submitExtrinsic")]
 	fn submit_extrinsic(&self, extrinsic: Bytes) -> FutureResult<Hash>;
 
+	/// Submit hex-encoded extrinsic for inclusion in block as a local extrinsic.
+	///
+	/// Local extrinsics are prioritized, never evicted by the pool limits and propagated
+	/// to the network more often. This is an unsafe RPC method: it is denied unless unsafe
+	/// RPC methods are allowed, so that remote callers cannot claim the local privileges.
+	#[rpc(name = "author_submitLocalExtrinsic")]
+	fn submit_local_extrinsic(&self, extrinsic: Bytes) -> FutureResult<Hash>;
+
+	/// Apply hex-encoded extrinsic in a block initialized on top of the best block without
+	/// submitting it.
+	///
+	/// Returns the hash of the extrinsic, the SCALE encoded `ApplyExtrinsicResult` and the
+	/// SCALE encoded events deposited in the block.
+	#[rpc(name = "author_dryRunExtrinsic")]
+	fn dry_run_extrinsic(&self, extrinsic: Bytes) -> FutureResult<hash::DryRunResult<Hash>>;
+
 	/// Insert a key into the keystore.
 	#[rpc(name = "author_insertKey")]
 	fn insert_key(
@@ -76,6 +92,32 @@ pub trait AuthorApi<Hash, BlockHash> {
 		bytes_or_hash: Vec<hash::ExtrinsicOrHash<Hash>>
 	) -> Result<Vec<Hash>>;
 
+	/// Remove given extrinsic from the pool and ban it from being reimported for the given
+	/// number of seconds, or the default ban time of the pool if not given.
+	///
+	/// Returns the hash of the banned extrinsic.
+	#[rpc(name = "author_banExtrinsic")]
+	fn ban_extrinsic(
+		&self,
+		bytes_or_hash: hash::ExtrinsicOrHash<Hash>,
+		duration: Option<u64>,
+	) -> Result<Hash>;
+
+	/// Lift the ban of the extrinsic with given hash, allowing it to be imported again.
+	///
+	/// Returns `true` if the extrinsic was banned.
+	#[rpc(name = "author_unbanExtrinsic")]
+	fn unban_extrinsic(&self, hash: Hash) -> Result<bool>;
+
+	/// Returns the details of all ready extrinsics and the number of extrinsics per tag.
+	#[rpc(name = "author_readyExtrinsics")]
+	fn ready_extrinsics(&self) -> Result<hash::PoolExtrinsics<Hash>>;
+
+	/// Returns the details of all future extrinsics, i.e. extrinsics waiting for some
+	/// of their required tags to be provided, and the number of extrinsics per tag.
+	#[rpc(name = "author_futureExtrinsics")]
+	fn future_extrinsics(&self) -> Result<hash::PoolExtrinsics<Hash>>;
+
 	/// Submit an extrinsic to watch.
 	///
 	/// See [`TransactionStatus`](sp_transaction_pool::TransactionStatus) for details on transaction
@@ -0,0 +1,94 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Substrate blockchain API helpers.
+
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use sp_core::Bytes;
+
+/// A block with its extrinsics decoded using the runtime metadata.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedBlock<Header> {
+	/// The block header.
+	pub header: Header,
+	/// The decoded extrinsics of the block.
+	pub extrinsics: Vec<DecodedExtrinsic>,
+}
+
+/// An extrinsic decoded using the runtime metadata.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedExtrinsic {
+	/// The SCALE encoded extrinsic.
+	pub raw: Bytes,
+	/// The signature of the extrinsic. `None` if unsigned or undecodable.
+	pub signature: Option<DecodedSignature>,
+	/// The call of the extrinsic. `None` if undecodable.
+	pub call: Option<DecodedCall>,
+}
+
+/// The signature of a decoded extrinsic.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedSignature {
+	/// Address of the signer.
+	pub address: Value,
+	/// The signature.
+	pub signature: Value,
+	/// The data of the signed extensions, by identifier.
+	pub extensions: Vec<(String, Value)>,
+}
+
+/// A call decoded using the runtime metadata.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedCall {
+	/// Name of the pallet.
+	pub pallet: String,
+	/// Name of the call.
+	pub name: String,
+	/// The arguments of the call, in order.
+	pub args: Vec<DecodedArg>,
+	/// The encoded arguments starting with the first one which couldn't be decoded, if any.
+	pub remainder: Option<Bytes>,
+}
+
+/// An argument of a decoded call.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedArg {
+	/// Name of the argument.
+	pub name: String,
+	/// Type of the argument, as named by the metadata.
+	#[serde(rename = "type")]
+	pub ty: String,
+	/// Value of the argument. `None` if its type is unknown or it follows such an argument.
+	pub value: Option<Value>,
+}
+
+/// The location of an extrinsic in the best chain.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtrinsicLocation<Hash> {
+	/// Hash of the block the extrinsic is included in.
+	pub block_hash: Hash,
+	/// Index of the extrinsic in the block.
+	pub index: u32,
+}
@@ -19,6 +19,7 @@
 //! Substrate blockchain API.
 
 pub mod error;
+pub mod helpers;
 
 use jsonrpc_core::Result as RpcResult;
 use jsonrpc_derive::rpc;
@@ -27,6 +28,9 @@ use sp_rpc::{number::NumberOrHex, list::ListOrValue};
 use self::error::{FutureResult, Result};
 
 pub use self::gen_client::Client as ChainClient;
+pub use self::helpers::{
+	DecodedArg, DecodedBlock, DecodedCall, DecodedExtrinsic, DecodedSignature, ExtrinsicLocation,
+};
 
 /// Substrate blockchain API
 #[rpc]
@@ -42,6 +46,18 @@ pub trait ChainApi<Number, Hash, Header, SignedBlock> {
 	#[rpc(name = "chain_getBlock")]
 	fn block(&self, hash: Option<Hash>) -> FutureResult<Option<SignedBlock>>;
 
+	/// Get header and body of a relay chain block, with the extrinsics decoded using
+	/// the runtime metadata at that block.
+	#[rpc(name = "chain_getBlockDecoded")]
+	fn block_decoded(&self, hash: Option<Hash>) -> FutureResult<Option<DecodedBlock<Header>>>;
+
+	/// Get the block of the best chain the extrinsic with the given hash is included in, and its
+	/// index in the block.
+	///
+	/// Only available on nodes indexing extrinsics, `null` otherwise.
+	#[rpc(name = "chain_getTransaction")]
+	fn transaction(&self, hash: Hash) -> FutureResult<Option<ExtrinsicLocation<Hash>>>;
+
 	/// Get hash of the n-th block in the canon chain.
 	///
 	/// By default returns latest block hash.
@@ -68,13 +84,21 @@ pub trait ChainApi<Number, Hash, Header, SignedBlock> {
 	) -> RpcResult<bool>;
 
 	/// New head subscription
+	///
+	/// Passing the hash of the last head received before reconnecting as `resume_from`
+	/// resumes the subscription: every head since is sent first, instead of the current one.
 	#[pubsub(
 		subscription = "chain_newHead",
 		subscribe,
 		name = "chain_subscribeNewHeads",
 		alias("subscribe_newHead", "chain_subscribeNewHead")
 	)]
-	fn subscribe_new_heads(&self, metadata: Self::Metadata, subscriber: Subscriber<Header>);
+	fn subscribe_new_heads(
+		&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<Header>,
+		resume_from: Option<Hash>,
+	);
 
 	/// Unsubscribe from new head subscription.
 	#[pubsub(
@@ -90,13 +114,21 @@ pub trait ChainApi<Number, Hash, Header, SignedBlock> {
 	) -> RpcResult<bool>;
 
 	/// Finalized head subscription
+	///
+	/// Passing the hash of the last head received before reconnecting as `resume_from`
+	/// resumes the subscription: every head finalized since is sent first.
 	#[pubsub(
 		subscription = "chain_finalizedHead",
 		subscribe,
 		name = "chain_subscribeFinalizedHeads",
 		alias("chain_subscribeFinalisedHeads")
 	)]
-	fn subscribe_finalized_heads(&self, metadata: Self::Metadata, subscriber: Subscriber<Header>);
+	fn subscribe_finalized_heads(
+		&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<Header>,
+		resume_from: Option<Hash>,
+	);
 
 	/// Unsubscribe from finalized head subscription.
 	#[pubsub(
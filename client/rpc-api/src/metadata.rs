@@ -30,6 +30,24 @@ use jsonrpc_pubsub::{Session, PubSubMetadata};
 #[derive(Default, Clone)]
 pub struct Metadata {
 	session: Option<Arc<Session>>,
+	credential: Option<String>,
+	client_ip: Option<String>,
+}
+
+/// Metadata identifying the client: the credential it authenticated its requests
+/// with and its IP address.
+pub trait AuthMetadata {
+	/// Returns the credential of the client, if any.
+	fn credential(&self) -> Option<&str>;
+
+	/// Set the credential of the client, e.g. extracted from the request headers.
+	fn set_credential(&mut self, credential: String);
+
+	/// Returns the IP address of the client, if known.
+	fn client_ip(&self) -> Option<&str>;
+
+	/// Set the IP address of the client, e.g. extracted from the request headers.
+	fn set_client_ip(&mut self, client_ip: String);
 }
 
 impl jsonrpc_core::Metadata for Metadata {}
@@ -39,11 +57,31 @@ impl PubSubMetadata for Metadata {
 	}
 }
 
+impl AuthMetadata for Metadata {
+	fn credential(&self) -> Option<&str> {
+		self.credential.as_deref()
+	}
+
+	fn set_credential(&mut self, credential: String) {
+		self.credential = Some(credential);
+	}
+
+	fn client_ip(&self) -> Option<&str> {
+		self.client_ip.as_deref()
+	}
+
+	fn set_client_ip(&mut self, client_ip: String) {
+		self.client_ip = Some(client_ip);
+	}
+}
+
 impl Metadata {
 	/// Create new `Metadata` with session (Pub/Sub) support.
 	pub fn new(transport: mpsc::Sender<String>) -> Self {
 		Metadata {
 			session: Some(Arc::new(Session::new(transport))),
+			credential: None,
+			client_ip: None,
 		}
 	}
 
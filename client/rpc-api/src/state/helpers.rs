@@ -18,7 +18,7 @@
 
 //! Substrate state API helpers.
 
-use sp_core::Bytes;
+use sp_core::{Bytes, storage::StorageChangeSet};
 use serde::{Serialize, Deserialize};
 
 /// ReadProof struct returned by the RPC
@@ -30,3 +30,13 @@ pub struct ReadProof<Hash> {
 	/// A proof used to prove that storage entries are included in the storage trie
 	pub proof: Vec<Bytes>,
 }
+
+/// Changes of storage entries over a range of blocks, returned by the RPC
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageChangesPage<Hash> {
+	/// Change sets of the blocks of the range, in ascending order
+	pub changes: Vec<StorageChangeSet<Hash>>,
+	/// Block to continue the query from if the number of change sets exceeded the limit
+	pub next: Option<Hash>,
+}
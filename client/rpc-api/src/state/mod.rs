@@ -27,10 +27,11 @@ use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
 use sp_core::Bytes;
 use sp_core::storage::{StorageKey, StorageData, StorageChangeSet};
 use sp_version::RuntimeVersion;
+use sp_rpc::tracing::BlockTrace;
 use self::error::FutureResult;
 
 pub use self::gen_client::Client as StateClient;
-pub use self::helpers::ReadProof;
+pub use self::helpers::{ReadProof, StorageChangesPage};
 
 /// Substrate state API
 #[rpc]
@@ -103,10 +104,37 @@ pub trait StateApi<Hash> {
 		at: Option<Hash>,
 	) -> FutureResult<Vec<StorageChangeSet<Hash>>>;
 
+	/// Query the changes of storage entries (by key) over a range of blocks, from the block
+	/// given as the second parameter up to the one given as the third parameter (best block
+	/// if not provided).
+	///
+	/// NOTE As with `state_queryStorage`, the first change set contains the initial state of
+	/// storage for all keys. The number of keys and the length of the range are limited, and a
+	/// limited number of change sets is returned at once. If there are more, `next` is the block
+	/// to continue the query from.
+	#[rpc(name = "state_queryStorageRange")]
+	fn query_storage_range(
+		&self,
+		keys: Vec<StorageKey>,
+		from: Hash,
+		to: Option<Hash>,
+	) -> FutureResult<StorageChangesPage<Hash>>;
+
 	/// Returns proof of storage entries at a specific block's state.
 	#[rpc(name = "state_getReadProof")]
 	fn read_proof(&self, keys: Vec<StorageKey>, hash: Option<Hash>) -> FutureResult<ReadProof<Hash>>;
 
+	/// Re-execute the given block on the state of its parent and return the spans and events
+	/// of the given comma separated tracing targets (`pallet,frame,state` if not provided).
+	///
+	/// This method is unsafe: re-executing blocks is expensive.
+	#[rpc(name = "state_traceBlock")]
+	fn trace_block(
+		&self,
+		block: Hash,
+		targets: Option<String>,
+	) -> FutureResult<BlockTrace<Hash>>;
+
 	/// New runtime version subscription
 	#[pubsub(
 		subscription = "state_runtimeVersion",
@@ -126,9 +154,22 @@ pub trait StateApi<Hash> {
 	fn unsubscribe_runtime_version(&self, metadata: Option<Self::Metadata>, id: SubscriptionId) -> RpcResult<bool>;
 
 	/// New storage subscription
+	///
+	/// Notifies the changes of the given `keys` and of the keys starting with any of the
+	/// given `prefixes`, or of all the keys if neither is given. The initial values are
+	/// only notified for the `keys`.
+	///
+	/// Passing the block of the last change set received before reconnecting as
+	/// `resume_from` resumes the subscription of the `keys`: the changes of every block
+	/// since are sent first, instead of the initial values.
 	#[pubsub(subscription = "state_storage", subscribe, name = "state_subscribeStorage")]
 	fn subscribe_storage(
-		&self, metadata: Self::Metadata, subscriber: Subscriber<StorageChangeSet<Hash>>, keys: Option<Vec<StorageKey>>
+		&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<StorageChangeSet<Hash>>,
+		keys: Option<Vec<StorageKey>>,
+		prefixes: Option<Vec<StorageKey>>,
+		resume_from: Option<Hash>,
 	);
 
 	/// Unsubscribe from storage subscription
@@ -136,4 +177,31 @@ pub trait StateApi<Hash> {
 	fn unsubscribe_storage(
 		&self, metadata: Option<Self::Metadata>, id: SubscriptionId
 	) -> RpcResult<bool>;
+
+	/// New storage subscription notified of finalized changes only.
+	///
+	/// Notifies the values of the given keys at the last finalized block first, then
+	/// every time blocks are finalized the keys whose value changed since the previously
+	/// finalized block, so the changes undone by reorgs are never notified.
+	#[pubsub(
+		subscription = "state_storageFinalized",
+		subscribe,
+		name = "state_subscribeStorageFinalized"
+	)]
+	fn subscribe_storage_finalized(
+		&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<StorageChangeSet<Hash>>,
+		keys: Vec<StorageKey>,
+	);
+
+	/// Unsubscribe from finalized storage subscription
+	#[pubsub(
+		subscription = "state_storageFinalized",
+		unsubscribe,
+		name = "state_unsubscribeStorageFinalized"
+	)]
+	fn unsubscribe_storage_finalized(
+		&self, metadata: Option<Self::Metadata>, id: SubscriptionId
+	) -> RpcResult<bool>;
 }
@@ -32,6 +32,10 @@ pub enum Error {
 	NotHealthy(Health),
 	/// Peer argument is malformatted.
 	MalformattedPeerArg(String),
+	/// The snapshot of the database failed.
+	#[display(fmt = "Database snapshot failed: {}", _0)]
+	#[from(ignore)]
+	DatabaseSnapshot(String),
 }
 
 impl std::error::Error for Error {}
@@ -51,7 +55,12 @@ impl From<Error> for rpc::Error {
 				code :rpc::ErrorCode::ServerError(BASE_ERROR + 2),
 				message: e.clone(),
 				data: None,
-			}
+			},
+			Error::DatabaseSnapshot(_) => rpc::Error {
+				code: rpc::ErrorCode::ServerError(BASE_ERROR + 3),
+				message: format!("{}", e),
+				data: None,
+			},
 		}
 	}
 }
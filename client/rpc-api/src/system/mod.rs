@@ -124,4 +124,12 @@ pub trait SystemApi<Hash, Number> {
 	#[rpc(name = "system_resetLogFilter", returns = "()")]
 	fn system_reset_log_filter(&self)
 		-> Result<(), jsonrpc_core::Error>;
+
+	/// Writes a consistent copy of the database to a new directory of the snapshot directory
+	/// set by the operator of the node, while the node keeps running.
+	///
+	/// Returns the name of the directory of the snapshot.
+	#[rpc(name = "system_snapshotDatabase", returns = "String")]
+	fn system_snapshot_database(&self)
+		-> Compat<BoxFuture<'static, Result<String, jsonrpc_core::Error>>>;
 }
@@ -0,0 +1,11 @@
+const PROTOS: &[&str] = &[
+	"src/schema/rpc.v1.proto",
+];
+
+fn main() {
+	tonic_build::configure()
+		.build_client(false)
+		.format(false)
+		.compile(PROTOS, &["src/schema"])
+		.unwrap();
+}
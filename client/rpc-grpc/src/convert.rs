@@ -0,0 +1,218 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Conversions between the JSON values of the RPC methods and the protobuf messages.
+
+use jsonrpc_core::Value;
+use sp_core::bytes;
+use tonic::Status;
+
+use crate::schema;
+
+/// The JSON value of the given bytes, as a hex string.
+pub(crate) fn to_json(data: &[u8]) -> Value {
+	Value::String(bytes::to_hex(data, false))
+}
+
+/// The JSON value of the given block hash, `null` for the best block if it is empty.
+pub(crate) fn at(hash: &[u8]) -> Value {
+	if hash.is_empty() {
+		Value::Null
+	} else {
+		to_json(hash)
+	}
+}
+
+fn invalid(what: &str, value: &Value) -> Status {
+	Status::internal(format!("Invalid {} in response: {}", what, value))
+}
+
+/// The bytes of the given hex string, or of the given array of bytes.
+pub(crate) fn data(value: &Value) -> Result<Vec<u8>, Status> {
+	match value {
+		Value::String(hex) => bytes::from_hex(hex).map_err(|_| invalid("bytes", value)),
+		Value::Array(items) => items.iter()
+			.map(|item| item.as_u64().filter(|byte| *byte <= 0xff).map(|byte| byte as u8))
+			.collect::<Option<_>>()
+			.ok_or_else(|| invalid("bytes", value)),
+		_ => Err(invalid("bytes", value)),
+	}
+}
+
+/// The number of the given hex string or JSON number.
+fn number(value: &Value) -> Result<u64, Status> {
+	match value {
+		Value::String(hex) => u64::from_str_radix(hex.trim_start_matches("0x"), 16).ok(),
+		value => value.as_u64(),
+	}.ok_or_else(|| invalid("number", value))
+}
+
+fn string(value: &Value) -> Result<String, Status> {
+	value.as_str().map(Into::into).ok_or_else(|| invalid("string", value))
+}
+
+fn u32_field(value: &Value) -> Result<u32, Status> {
+	value.as_u64()
+		.and_then(|n| std::convert::TryFrom::try_from(n).ok())
+		.ok_or_else(|| invalid("number", value))
+}
+
+/// Fails with `NotFound` for the `null` results of missing blocks.
+pub(crate) fn found<'a>(value: &'a Value, what: &str) -> Result<&'a Value, Status> {
+	match value {
+		Value::Null => Err(Status::not_found(format!("No such {}", what))),
+		value => Ok(value),
+	}
+}
+
+pub(crate) fn hash(value: &Value) -> Result<schema::Hash, Status> {
+	Ok(schema::Hash { hash: data(found(value, "block")?)? })
+}
+
+pub(crate) fn header(value: &Value) -> Result<schema::Header, Status> {
+	Ok(schema::Header {
+		parent_hash: data(&value["parentHash"])?,
+		number: number(&value["number"])?,
+		state_root: data(&value["stateRoot"])?,
+		extrinsics_root: data(&value["extrinsicsRoot"])?,
+		digest_logs: match &value["digest"]["logs"] {
+			Value::Array(logs) => logs.iter().map(data).collect::<Result<_, _>>()?,
+			logs => return Err(invalid("digest", logs)),
+		},
+	})
+}
+
+pub(crate) fn block(value: &Value) -> Result<schema::Block, Status> {
+	let block = &value["block"];
+	Ok(schema::Block {
+		header: Some(header(&block["header"])?),
+		extrinsics: match &block["extrinsics"] {
+			Value::Array(extrinsics) => extrinsics.iter().map(data).collect::<Result<_, _>>()?,
+			extrinsics => return Err(invalid("extrinsics", extrinsics)),
+		},
+		justification: match &value["justification"] {
+			Value::Null => Vec::new(),
+			justification => data(justification)?,
+		},
+	})
+}
+
+pub(crate) fn storage_value(value: &Value) -> Result<schema::StorageValue, Status> {
+	Ok(match value {
+		Value::Null => schema::StorageValue { exists: false, value: Vec::new() },
+		value => schema::StorageValue { exists: true, value: data(value)? },
+	})
+}
+
+pub(crate) fn runtime_version(value: &Value) -> Result<schema::RuntimeVersion, Status> {
+	Ok(schema::RuntimeVersion {
+		spec_name: string(&value["specName"])?,
+		impl_name: string(&value["implName"])?,
+		authoring_version: u32_field(&value["authoringVersion"])?,
+		spec_version: u32_field(&value["specVersion"])?,
+		impl_version: u32_field(&value["implVersion"])?,
+		transaction_version: u32_field(&value["transactionVersion"])?,
+	})
+}
+
+/// Convert a status of a watched transaction, e.g. `"ready"` or `{ "inBlock": "0x..." }`.
+pub(crate) fn transaction_status(value: &Value) -> Result<schema::TransactionStatus, Status> {
+	let mut status = schema::TransactionStatus::default();
+	match value {
+		Value::String(name) => status.status = name.clone(),
+		Value::Object(fields) if fields.len() == 1 => {
+			let (name, value) = fields.iter().next().expect("the object has one field; qed");
+			status.status = name.clone();
+			match value {
+				Value::Array(peers) =>
+					status.peers = peers.iter().map(string).collect::<Result<_, _>>()?,
+				value => status.hash = data(value)?,
+			}
+		},
+		value => return Err(invalid("transaction status", value)),
+	}
+	Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::json;
+
+	#[test]
+	fn converts_headers_and_blocks() {
+		let header_json = json!({
+			"parentHash": "0x01",
+			"number": "0x1a",
+			"stateRoot": "0x02",
+			"extrinsicsRoot": "0x03",
+			"digest": { "logs": ["0x0400"] },
+		});
+		let expected = schema::Header {
+			parent_hash: vec![1],
+			number: 26,
+			state_root: vec![2],
+			extrinsics_root: vec![3],
+			digest_logs: vec![vec![4, 0]],
+		};
+		assert_eq!(header(&header_json).unwrap(), expected);
+
+		let block_json = json!({
+			"block": { "header": header_json, "extrinsics": ["0x0506"] },
+			"justification": [7, 8],
+		});
+		assert_eq!(
+			block(&block_json).unwrap(),
+			schema::Block {
+				header: Some(expected),
+				extrinsics: vec![vec![5, 6]],
+				justification: vec![7, 8],
+			},
+		);
+		assert_eq!(header(&json!({ "number": "0x1" })).unwrap_err().code(), tonic::Code::Internal);
+		assert_eq!(hash(&Value::Null).unwrap_err().code(), tonic::Code::NotFound);
+	}
+
+	#[test]
+	fn converts_storage_values_and_transaction_statuses() {
+		assert_eq!(
+			storage_value(&Value::Null).unwrap(),
+			schema::StorageValue { exists: false, value: vec![] },
+		);
+		assert_eq!(
+			storage_value(&json!("0x")).unwrap(),
+			schema::StorageValue { exists: true, value: vec![] },
+		);
+
+		let status = |name: &str, hash: Vec<u8>, peers: Vec<String>| schema::TransactionStatus {
+			status: name.into(),
+			hash,
+			peers,
+		};
+		assert_eq!(transaction_status(&json!("ready")).unwrap(), status("ready", vec![], vec![]));
+		assert_eq!(
+			transaction_status(&json!({ "inBlock": "0x0a" })).unwrap(),
+			status("inBlock", vec![10], vec![]),
+		);
+		assert_eq!(
+			transaction_status(&json!({ "broadcast": ["peer"] })).unwrap(),
+			status("broadcast", vec![], vec!["peer".into()]),
+		);
+		assert!(transaction_status(&json!(1)).is_err());
+	}
+}
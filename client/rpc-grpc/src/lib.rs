@@ -0,0 +1,245 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! gRPC gateway to the RPC handlers.
+//!
+//! The chain, state and author APIs are exposed as the typed, streaming gRPC services of
+//! `src/schema/rpc.v1.proto`. Every gRPC method is dispatched to the JSON-RPC method of
+//! the same API through the [`RpcHandler`] of the node, so the gateway is subject to the
+//! same middleware (metrics, method policy and unsafe methods) as the other transports,
+//! and the subscriptions of the streaming methods last as long as their gRPC stream.
+//! The requests of every client IP are limited by the [`RateLimiter`] of the gateway, if
+//! any, and rejected with the `RESOURCE_EXHAUSTED` status.
+
+#![warn(missing_docs)]
+
+mod convert;
+mod services;
+
+/// The sources generated from the protobuf definitions.
+#[allow(missing_docs)]
+pub mod schema {
+	tonic::include_proto!("rpc.v1");
+}
+
+use std::{net::{IpAddr, SocketAddr}, sync::Arc};
+
+use futures::{compat::{Future01CompatExt, Stream01CompatExt}, StreamExt};
+use jsonrpc_core::{
+	Call, Error, ErrorCode, Id, MetaIoHandler, MethodCall, Output, Params, Request, Response,
+	Value, Version,
+};
+use log::{debug, error, info};
+use sc_rpc_api::{AuthMetadata, Metadata};
+use sc_rpc_server::{RateLimiter, RpcHandler, RpcMiddleware};
+use tonic::{Code, Request as GrpcRequest, Status};
+
+use schema::{
+	author_server::AuthorServer, chain_server::ChainServer, state_server::StateServer,
+};
+
+/// Number of notifications of a subscription buffered for its stream.
+const SUBSCRIPTION_BUFFER: usize = 16;
+
+/// Dispatches the calls of the gRPC services to the JSON-RPC methods.
+#[derive(Clone)]
+pub struct Gateway {
+	io: Arc<MetaIoHandler<Metadata, RpcMiddleware>>,
+	rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+impl Gateway {
+	/// Create a gateway to the given handler, limiting the rate of the requests of every
+	/// client IP with the given limiter, if any.
+	pub fn new(io: RpcHandler<Metadata>, rate_limiter: Option<Arc<RateLimiter>>) -> Self {
+		Gateway { io: Arc::new(io.into()), rate_limiter }
+	}
+
+	/// Returns the IP of the client of the given request, failing if the client exceeded
+	/// its rate limit.
+	fn admit<T>(&self, request: &GrpcRequest<T>) -> Result<Option<IpAddr>, Status> {
+		let ip = request.remote_addr().map(|address| address.ip());
+		match (&self.rate_limiter, ip) {
+			(Some(rate_limiter), Some(ip)) if !rate_limiter.check_ip(&ip) =>
+				Err(Status::resource_exhausted("Too many requests, rate limit exceeded")),
+			_ => Ok(ip),
+		}
+	}
+
+	/// Call the given JSON-RPC method on behalf of the given client, returning its result.
+	async fn dispatch(
+		&self,
+		client: Option<IpAddr>,
+		method: &str,
+		params: Vec<Value>,
+	) -> Result<Value, Status> {
+		self.call(with_client(Default::default(), client), method, params).await
+	}
+
+	/// Call the given JSON-RPC method with the given metadata, returning its result.
+	async fn call(
+		&self,
+		meta: Metadata,
+		method: &str,
+		params: Vec<Value>,
+	) -> Result<Value, Status> {
+		let request = Request::Single(Call::MethodCall(MethodCall {
+			jsonrpc: Some(Version::V2),
+			method: method.into(),
+			params: Params::Array(params),
+			id: Id::Num(1),
+		}));
+
+		match self.io.handle_rpc_request(request, meta).compat().await {
+			Ok(Some(Response::Single(Output::Success(success)))) => Ok(success.result),
+			Ok(Some(Response::Single(Output::Failure(failure)))) => Err(status(failure.error)),
+			_ => Err(Status::internal(format!("No response to `{}`", method))),
+		}
+	}
+
+	/// Subscribe with the given JSON-RPC method, returning the stream of the results of
+	/// its notifications converted with `convert`.
+	///
+	/// The subscription is cancelled once the stream is dropped.
+	async fn subscribe<T, F>(
+		&self,
+		client: Option<IpAddr>,
+		method: &str,
+		params: Vec<Value>,
+		convert: F,
+	) -> Result<tokio::sync::mpsc::Receiver<Result<T, Status>>, Status> where
+		T: Send + 'static,
+		F: Fn(&Value) -> Result<T, Status> + Send + 'static,
+	{
+		let (sender, notifications) = futures01::sync::mpsc::channel(SUBSCRIPTION_BUFFER);
+		let meta = with_client(Metadata::new(sender), client);
+		self.call(meta.clone(), method, params).await?;
+
+		let (mut sink, stream) = tokio::sync::mpsc::channel(SUBSCRIPTION_BUFFER);
+		let method = method.to_owned();
+		tokio::spawn(async move {
+			let mut notifications = notifications.compat();
+			while let Some(Ok(notification)) = notifications.next().await {
+				let item = notification_result(&notification).and_then(|result| convert(&result));
+				if sink.send(item).await.is_err() {
+					break;
+				}
+			}
+			debug!("gRPC stream of `{}` closed", method);
+			// the session ends with the last copy of the metadata, cancelling the subscription.
+			drop(meta);
+		});
+
+		Ok(stream)
+	}
+}
+
+/// Attach the IP of the given client, if known, to the given metadata.
+fn with_client(mut meta: Metadata, client: Option<IpAddr>) -> Metadata {
+	if let Some(ip) = client {
+		meta.set_client_ip(ip.to_string());
+	}
+	meta
+}
+
+/// Returns the result carried by the given JSON-RPC notification.
+fn notification_result(notification: &str) -> Result<Value, Status> {
+	let mut notification: Value = serde_json::from_str(notification)
+		.map_err(|e| Status::internal(format!("Invalid notification: {}", e)))?;
+	match notification["params"]["result"].take() {
+		Value::Null => Err(Status::internal("Notification without result")),
+		result => Ok(result),
+	}
+}
+
+/// Convert the given JSON-RPC error to a gRPC status.
+fn status(error: Error) -> Status {
+	let code = match error.code {
+		ErrorCode::MethodNotFound => Code::Unimplemented,
+		ErrorCode::ParseError | ErrorCode::InvalidRequest | ErrorCode::InvalidParams =>
+			Code::InvalidArgument,
+		ErrorCode::InternalError => Code::Internal,
+		ErrorCode::ServerError(_) => Code::Unknown,
+	};
+	Status::new(code, error.message)
+}
+
+/// Serve the gRPC services on the given address, dispatching to the given handler.
+///
+/// The requests of every client IP are limited by the given `rate_limiter`, if any.
+pub async fn run(
+	address: SocketAddr,
+	io: RpcHandler<Metadata>,
+	rate_limiter: Option<Arc<RateLimiter>>,
+) {
+	let gateway = Gateway::new(io, rate_limiter);
+	let server = tonic::transport::Server::builder()
+		.add_service(ChainServer::new(gateway.clone()))
+		.add_service(StateServer::new(gateway.clone()))
+		.add_service(AuthorServer::new(gateway));
+
+	info!("gRPC gateway started at {}", address);
+	if let Err(e) = server.serve(address).await {
+		error!("gRPC gateway at {} failed: {}", address, e);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::executor::block_on;
+	use sc_rpc_server::{rpc_handler, RpcMetrics};
+
+	fn gateway() -> Gateway {
+		let mut io = jsonrpc_core::IoHandler::default();
+		io.add_method("chain_getFinalizedHead", |_| Ok(Value::String("0x0102".into())));
+		io.add_method("state_call", |params: Params| {
+			let params: Vec<Value> = params.parse()?;
+			Ok(params[1].clone())
+		});
+		let middleware = RpcMiddleware::new(RpcMetrics::new(None).unwrap(), "grpc");
+		Gateway::new(rpc_handler(io, middleware), None)
+	}
+
+	#[test]
+	fn dispatches_to_the_json_rpc_methods() {
+		let gateway = gateway();
+		let call = |method, params| block_on(gateway.dispatch(Default::default(), method, params));
+
+		assert_eq!(call("chain_getFinalizedHead", vec![]).unwrap(), Value::String("0x0102".into()));
+		assert_eq!(
+			call("state_call", vec!["Core_version".into(), "0x03".into()]).unwrap(),
+			Value::String("0x03".into()),
+		);
+		assert_eq!(call("state_getStorage", vec![]).unwrap_err().code(), Code::Unimplemented);
+	}
+
+	#[test]
+	fn extracts_the_results_of_notifications() {
+		let notification = r#"{
+			"jsonrpc": "2.0",
+			"method": "chain_newHead",
+			"params": { "subscription": 1, "result": { "number": "0x1" } }
+		}"#;
+		assert_eq!(
+			notification_result(notification).unwrap(),
+			serde_json::json!({ "number": "0x1" }),
+		);
+		assert!(notification_result(r#"{ "params": {} }"#).is_err());
+	}
+}
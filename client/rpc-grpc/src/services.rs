@@ -0,0 +1,162 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! The gRPC services, dispatching to the JSON-RPC methods of the same APIs.
+
+use jsonrpc_core::Value;
+use tokio::sync::mpsc::Receiver;
+use tonic::{Request, Response, Status};
+
+use crate::{
+	Gateway,
+	convert::{self, at, to_json},
+	schema::{self, author_server::Author, chain_server::Chain, state_server::State},
+};
+
+type Stream<T> = Receiver<Result<T, Status>>;
+
+#[tonic::async_trait]
+impl Chain for Gateway {
+	async fn get_header(
+		&self,
+		request: Request<schema::At>,
+	) -> Result<Response<schema::Header>, Status> {
+		let client = self.admit(&request)?;
+		let params = vec![at(&request.into_inner().hash)];
+		let header = self.dispatch(client, "chain_getHeader", params).await?;
+		convert::header(convert::found(&header, "block")?).map(Response::new)
+	}
+
+	async fn get_block(
+		&self,
+		request: Request<schema::At>,
+	) -> Result<Response<schema::Block>, Status> {
+		let client = self.admit(&request)?;
+		let params = vec![at(&request.into_inner().hash)];
+		let block = self.dispatch(client, "chain_getBlock", params).await?;
+		convert::block(convert::found(&block, "block")?).map(Response::new)
+	}
+
+	async fn get_block_hash(
+		&self,
+		request: Request<schema::BlockNumber>,
+	) -> Result<Response<schema::Hash>, Status> {
+		let client = self.admit(&request)?;
+		let number = match request.into_inner().block {
+			Some(schema::block_number::Block::Number(number)) => Value::from(number),
+			None => Value::Null,
+		};
+		let hash = self.dispatch(client, "chain_getBlockHash", vec![number]).await?;
+		convert::hash(&hash).map(Response::new)
+	}
+
+	async fn get_finalized_head(
+		&self,
+		request: Request<schema::Empty>,
+	) -> Result<Response<schema::Hash>, Status> {
+		let client = self.admit(&request)?;
+		let hash = self.dispatch(client, "chain_getFinalizedHead", vec![]).await?;
+		convert::hash(&hash).map(Response::new)
+	}
+
+	type SubscribeNewHeadsStream = Stream<schema::Header>;
+
+	async fn subscribe_new_heads(
+		&self,
+		request: Request<schema::Empty>,
+	) -> Result<Response<Self::SubscribeNewHeadsStream>, Status> {
+		let client = self.admit(&request)?;
+		self.subscribe(client, "chain_subscribeNewHeads", vec![], convert::header)
+			.await
+			.map(Response::new)
+	}
+
+	type SubscribeFinalizedHeadsStream = Stream<schema::Header>;
+
+	async fn subscribe_finalized_heads(
+		&self,
+		request: Request<schema::Empty>,
+	) -> Result<Response<Self::SubscribeFinalizedHeadsStream>, Status> {
+		let client = self.admit(&request)?;
+		self.subscribe(client, "chain_subscribeFinalizedHeads", vec![], convert::header)
+			.await
+			.map(Response::new)
+	}
+}
+
+#[tonic::async_trait]
+impl State for Gateway {
+	async fn get_storage(
+		&self,
+		request: Request<schema::StorageRequest>,
+	) -> Result<Response<schema::StorageValue>, Status> {
+		let client = self.admit(&request)?;
+		let request = request.into_inner();
+		let params = vec![to_json(&request.key), at(&request.at)];
+		let value = self.dispatch(client, "state_getStorage", params).await?;
+		convert::storage_value(&value).map(Response::new)
+	}
+
+	async fn call(
+		&self,
+		request: Request<schema::CallRequest>,
+	) -> Result<Response<schema::Bytes>, Status> {
+		let client = self.admit(&request)?;
+		let request = request.into_inner();
+		let params = vec![Value::String(request.method), to_json(&request.data), at(&request.at)];
+		let data = self.dispatch(client, "state_call", params).await?;
+		Ok(Response::new(schema::Bytes { data: convert::data(&data)? }))
+	}
+
+	async fn get_runtime_version(
+		&self,
+		request: Request<schema::At>,
+	) -> Result<Response<schema::RuntimeVersion>, Status> {
+		let client = self.admit(&request)?;
+		let params = vec![at(&request.into_inner().hash)];
+		let version = self.dispatch(client, "state_getRuntimeVersion", params).await?;
+		convert::runtime_version(&version).map(Response::new)
+	}
+}
+
+#[tonic::async_trait]
+impl Author for Gateway {
+	async fn submit_extrinsic(
+		&self,
+		request: Request<schema::Extrinsic>,
+	) -> Result<Response<schema::Hash>, Status> {
+		let client = self.admit(&request)?;
+		let params = vec![to_json(&request.into_inner().extrinsic)];
+		let hash = self.dispatch(client, "author_submitExtrinsic", params).await?;
+		convert::hash(&hash).map(Response::new)
+	}
+
+	type SubmitAndWatchExtrinsicStream = Stream<schema::TransactionStatus>;
+
+	async fn submit_and_watch_extrinsic(
+		&self,
+		request: Request<schema::Extrinsic>,
+	) -> Result<Response<Self::SubmitAndWatchExtrinsicStream>, Status> {
+		let client = self.admit(&request)?;
+		let params = vec![to_json(&request.into_inner().extrinsic)];
+		let method = "author_submitAndWatchExtrinsic";
+		self.subscribe(client, method, params, convert::transaction_status)
+			.await
+			.map(Response::new)
+	}
+}
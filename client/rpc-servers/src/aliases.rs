@@ -0,0 +1,233 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Additional names of the RPC methods.
+//!
+//! A renamed method can keep being exposed under its previous name, marked as
+//! deprecated, until its clients migrated. The calls of deprecated names are
+//! logged, at most once a minute per name, counted in the metrics and listed by
+//! `rpc_methods`. Their failures carry
+//! a deprecation warning in the data of the error, as the results of successful calls
+//! have to be left as they are.
+
+use std::{collections::HashMap, time::{Duration, Instant}};
+
+use jsonrpc_core::{Output, Value};
+use parking_lot::Mutex;
+use serde_json::json;
+
+/// Minimum interval between the warnings logged about the calls of a deprecated name.
+const WARNING_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Alias {
+	alias: String,
+	method: String,
+	deprecated: bool,
+}
+
+/// The additional names of the RPC methods.
+#[derive(Debug, Clone, Default)]
+pub struct RpcAliases(Vec<Alias>);
+
+impl RpcAliases {
+	/// Expose `method` under the additional name `alias`.
+	pub fn alias(mut self, alias: impl Into<String>, method: impl Into<String>) -> Self {
+		self.0.push(Alias { alias: alias.into(), method: method.into(), deprecated: false });
+		self
+	}
+
+	/// Keep exposing `method` under the deprecated name `old`, e.g. its name before
+	/// it was renamed.
+	pub fn deprecated(mut self, old: impl Into<String>, method: impl Into<String>) -> Self {
+		self.0.push(Alias { alias: old.into(), method: method.into(), deprecated: true });
+		self
+	}
+
+	/// Returns the method replacing the given name, if it is deprecated.
+	pub fn replacement(&self, name: &str) -> Option<&str> {
+		self.0.iter()
+			.find(|alias| alias.deprecated && alias.alias == name)
+			.map(|alias| alias.method.as_str())
+	}
+
+	/// Returns the method the given name refers to, the name itself if it isn't an alias.
+	pub(crate) fn resolve<'a>(&'a self, name: &'a str) -> &'a str {
+		self.0.iter()
+			.find(|alias| alias.alias == name)
+			.map_or(name, |alias| alias.method.as_str())
+	}
+
+	/// Returns the additional names and the methods they refer to.
+	pub(crate) fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+		self.0.iter().map(|alias| (alias.alias.as_str(), alias.method.as_str()))
+	}
+
+	/// Returns the warning for the calls of the given name, if it is deprecated.
+	pub(crate) fn deprecation(&self, name: &str) -> Option<Deprecation> {
+		self.replacement(name).map(|replacement| Deprecation {
+			method: name.to_owned(),
+			replacement: replacement.to_owned(),
+		})
+	}
+
+	/// Returns the deprecated names and the methods replacing them.
+	pub(crate) fn deprecated_names(&self) -> impl Iterator<Item = (&str, &str)> {
+		self.0.iter()
+			.filter(|alias| alias.deprecated)
+			.map(|alias| (alias.alias.as_str(), alias.method.as_str()))
+	}
+}
+
+/// The warning for the calls of a deprecated name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Deprecation {
+	method: String,
+	replacement: String,
+}
+
+impl Deprecation {
+	/// Add the warning to the data of the error, if the call failed.
+	///
+	/// The warning is added as the `deprecated` member of the data, if it is an object or
+	/// missing.
+	pub(crate) fn attach(&self, output: Option<Output>) -> Option<Output> {
+		let mut failure = match output {
+			Some(Output::Failure(failure)) => failure,
+			output => return output,
+		};
+
+		let warning = json!({ "method": self.method, "replacement": self.replacement });
+		match failure.error.data {
+			None => failure.error.data = Some(json!({ "deprecated": warning })),
+			Some(Value::Object(ref mut data)) => {
+				data.insert("deprecated".into(), warning);
+			},
+			Some(_) => {},
+		}
+		Some(Output::Failure(failure))
+	}
+}
+
+/// The calls of the deprecated names since the last warning about them.
+#[derive(Debug, Default)]
+pub(crate) struct DeprecationWarnings(Mutex<HashMap<String, (Instant, u64)>>);
+
+impl DeprecationWarnings {
+	/// Note a call of the given deprecated name, returning the number of calls to warn about
+	/// if the last warning about it is older than [`WARNING_INTERVAL`].
+	pub(crate) fn note(&self, name: &str, now: Instant) -> Option<u64> {
+		let mut calls = self.0.lock();
+		match calls.get_mut(name) {
+			Some((warned, count)) if now.saturating_duration_since(*warned) < WARNING_INTERVAL => {
+				*count += 1;
+				None
+			},
+			Some((warned, count)) => {
+				let calls = *count + 1;
+				*warned = now;
+				*count = 0;
+				Some(calls)
+			},
+			None => {
+				calls.insert(name.to_owned(), (now, 0));
+				Some(1)
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use jsonrpc_core::{Error, IoHandler};
+	use crate::{rpc_handler, RpcMetrics, RpcMiddleware};
+
+	#[test]
+	fn exposes_methods_under_their_aliases() {
+		let mut extension = IoHandler::default();
+		extension.add_method("chain_getHeader", |_| Ok(Value::Bool(true)));
+		extension.add_method("chain_getBlock", |_| Err(Error::invalid_request()));
+		let aliases = RpcAliases::default()
+			.alias("chain_header", "chain_getHeader")
+			.deprecated("chain_getHead", "chain_getHeader")
+			.deprecated("chain_block", "chain_getBlock");
+		assert_eq!(aliases.replacement("chain_getHead"), Some("chain_getHeader"));
+		assert_eq!(aliases.replacement("chain_header"), None);
+
+		let middleware = RpcMiddleware::new(RpcMetrics::new(None).unwrap(), "test")
+			.with_aliases(aliases);
+		let io = rpc_handler(extension, middleware);
+		let call = |method| io.handle_request_sync(
+			&format!(r#"{{"jsonrpc":"2.0","method":"{}","id":1}}"#, method),
+			sc_rpc_api::Metadata::default(),
+		);
+
+		let success = Some(r#"{"jsonrpc":"2.0","result":true,"id":1}"#.to_owned());
+		assert_eq!(call("chain_getHeader"), success);
+		assert_eq!(call("chain_header"), success);
+		assert_eq!(call("chain_getHead"), success);
+
+		// the failures of deprecated names carry the warning.
+		let failure: Value = serde_json::from_str(&call("chain_block").unwrap()).unwrap();
+		assert_eq!(
+			failure["error"]["data"],
+			serde_json::json!({
+				"deprecated": { "method": "chain_block", "replacement": "chain_getBlock" },
+			}),
+		);
+		let failure: Value = serde_json::from_str(&call("chain_getBlock").unwrap()).unwrap();
+		assert_eq!(failure["error"].get("data"), None);
+
+		let methods: Value = serde_json::from_str(&call("rpc_methods").unwrap()).unwrap();
+		assert_eq!(
+			methods["result"]["methods"],
+			serde_json::json!([
+				"chain_block",
+				"chain_getBlock",
+				"chain_getHead",
+				"chain_getHeader",
+				"chain_header",
+			]),
+		);
+		assert_eq!(
+			methods["result"]["deprecated"],
+			serde_json::json!({
+				"chain_block": "chain_getBlock",
+				"chain_getHead": "chain_getHeader",
+			}),
+		);
+	}
+
+	#[test]
+	fn warns_about_the_calls_of_deprecated_names_once_per_interval() {
+		let warnings = DeprecationWarnings::default();
+		let now = Instant::now();
+
+		assert_eq!(warnings.note("chain_getHead", now), Some(1));
+		assert_eq!(warnings.note("chain_getHead", now + Duration::from_secs(1)), None);
+		assert_eq!(warnings.note("chain_getHead", now + Duration::from_secs(2)), None);
+		// every name is warned about on its own.
+		assert_eq!(warnings.note("chain_block", now), Some(1));
+
+		// the next warning reports the calls made since the previous one.
+		let later = now + WARNING_INTERVAL;
+		assert_eq!(warnings.note("chain_getHead", later), Some(3));
+		assert_eq!(warnings.note("chain_getHead", later), None);
+	}
+}
@@ -0,0 +1,284 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Authentication of the clients calling unsafe RPC methods.
+//!
+//! The requests of authenticated clients are handled by a separate handler which
+//! allows the unsafe RPC methods. HTTP clients authenticate every request with the
+//! `Authorization: Bearer <credential>` header, WS clients authenticate their
+//! connection once by calling [`AUTHENTICATE_METHOD`].
+
+use std::{
+	any::Any, collections::HashSet, fmt, sync::Arc,
+	time::{SystemTime, UNIX_EPOCH},
+};
+
+use hmac::{Hmac, Mac, NewMac};
+use jsonrpc_core::{Call, Error, ErrorCode, Output, Request, Response};
+use parking_lot::Mutex;
+use pubsub::PubSubMetadata;
+use sc_rpc_api::AuthMetadata;
+use serde_json::Value;
+use sha2::Sha256;
+
+use crate::{middleware::session_key, RpcHandler};
+
+/// Method authenticating the WS connection it is called over.
+pub const AUTHENTICATE_METHOD: &str = "rpc_authenticate";
+
+/// JSON-RPC error code returned to clients presenting an invalid credential.
+pub const AUTH_FAILED_ERROR_CODE: i64 = -32030;
+
+/// Credential required to call the unsafe RPC methods.
+#[derive(Clone)]
+pub enum RpcAuth {
+	/// A static token.
+	Token(String),
+	/// A JSON Web Token signed with HMAC-SHA256 using the given secret.
+	///
+	/// The `exp` and `nbf` claims of the token are enforced if present.
+	Jwt(Vec<u8>),
+}
+
+impl fmt::Debug for RpcAuth {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		// never print the secrets.
+		match self {
+			RpcAuth::Token(_) => write!(f, "Token(..)"),
+			RpcAuth::Jwt(_) => write!(f, "Jwt(..)"),
+		}
+	}
+}
+
+impl RpcAuth {
+	/// Returns `true` if the given credential is valid.
+	pub fn verify(&self, credential: &str) -> bool {
+		match self {
+			RpcAuth::Token(token) => constant_time_eq(token.as_bytes(), credential.as_bytes()),
+			RpcAuth::Jwt(secret) => {
+				let now = SystemTime::now()
+					.duration_since(UNIX_EPOCH)
+					.map(|now| now.as_secs())
+					.unwrap_or_default();
+				verify_jwt(secret, credential, now)
+			},
+		}
+	}
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+	a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+fn verify_jwt(secret: &[u8], jwt: &str, now: u64) -> bool {
+	let parts = jwt.split('.').collect::<Vec<_>>();
+	let (header, payload, signature) = match parts[..] {
+		[header, payload, signature] => (header, payload, signature),
+		_ => return false,
+	};
+
+	let decode = |part| base64::decode_config(part, base64::URL_SAFE_NO_PAD).ok();
+	let json = |part| decode(part)
+		.and_then(|bytes| serde_json::from_slice::<Value>(&bytes).ok());
+
+	let alg = json(header).and_then(|header| header.get("alg").cloned());
+	if alg.as_ref().and_then(Value::as_str) != Some("HS256") {
+		return false;
+	}
+
+	let signature = match decode(signature) {
+		Some(signature) => signature,
+		None => return false,
+	};
+	let mut mac = match Hmac::<Sha256>::new_varkey(secret) {
+		Ok(mac) => mac,
+		Err(_) => return false,
+	};
+	mac.update(header.as_bytes());
+	mac.update(b".");
+	mac.update(payload.as_bytes());
+	if mac.verify(&signature).is_err() {
+		return false;
+	}
+
+	let claims = match json(payload) {
+		Some(claims) => claims,
+		None => return false,
+	};
+	let claim = |name| claims.get(name).map(Value::as_u64);
+
+	let not_expired = match claim("exp") {
+		Some(Some(exp)) => now < exp,
+		Some(None) => false,
+		None => true,
+	};
+	let not_before = match claim("nbf") {
+		Some(Some(nbf)) => nbf <= now,
+		Some(None) => false,
+		None => true,
+	};
+	not_expired && not_before
+}
+
+/// Extract the credential from the value of an `Authorization` header.
+pub(crate) fn bearer(value: &[u8]) -> Option<String> {
+	let value = std::str::from_utf8(value).ok()?.trim();
+	let (scheme, credential) = value.split_at(value.find(' ')?);
+	if scheme.eq_ignore_ascii_case("bearer") {
+		Some(credential.trim().to_owned())
+	} else {
+		None
+	}
+}
+
+/// Access granted to a request.
+pub(crate) enum Access {
+	/// The client didn't authenticate, only the safe methods may be called.
+	Public,
+	/// The client authenticated, every method may be called.
+	Privileged,
+	/// The client presented an invalid credential.
+	Denied,
+}
+
+/// Authenticates the clients of a server.
+pub(crate) struct Authenticator {
+	auth: RpcAuth,
+	/// The `RpcHandler` allowing the unsafe methods.
+	privileged: Box<dyn Any + Send + Sync>,
+	/// The authenticated sessions.
+	sessions: Mutex<HashSet<String>>,
+}
+
+impl Authenticator {
+	pub(crate) fn new<M: PubSubMetadata + AuthMetadata>(
+		auth: RpcAuth,
+		privileged: RpcHandler<M>,
+	) -> Self {
+		Authenticator {
+			auth,
+			privileged: Box::new(privileged),
+			sessions: Mutex::new(HashSet::new()),
+		}
+	}
+
+	/// Returns the handler of the requests of authenticated clients.
+	pub(crate) fn privileged<M: PubSubMetadata + AuthMetadata>(&self) -> Option<&RpcHandler<M>> {
+		self.privileged.downcast_ref()
+	}
+
+	/// Returns the access granted to the request with the given metadata.
+	pub(crate) fn access<M: PubSubMetadata + AuthMetadata>(&self, meta: &M) -> Access {
+		if let Some(credential) = meta.credential() {
+			return if self.auth.verify(credential) { Access::Privileged } else { Access::Denied };
+		}
+
+		match meta.session() {
+			Some(session) if self.sessions.lock().contains(&session_key(&session)) =>
+				Access::Privileged,
+			_ => Access::Public,
+		}
+	}
+
+	/// Handle the request if it is a call of [`AUTHENTICATE_METHOD`].
+	pub(crate) fn authenticate<M: PubSubMetadata>(
+		self: &Arc<Self>,
+		request: &Request,
+		meta: &M,
+	) -> Option<Response> {
+		let call = match request {
+			Request::Single(Call::MethodCall(call)) if call.method == AUTHENTICATE_METHOD => call,
+			_ => return None,
+		};
+
+		let result = call.params.clone().parse::<(String,)>().and_then(|(credential,)| {
+			let session = meta.session().ok_or_else(|| auth_error(
+				"Only WS connections can be authenticated, use the `Authorization` header instead"
+			))?;
+			if !self.auth.verify(&credential) {
+				return Err(auth_error("Invalid credential"));
+			}
+
+			let key = session_key(&session);
+			if self.sessions.lock().insert(key.clone()) {
+				let authenticator = self.clone();
+				session.on_drop(move || { authenticator.sessions.lock().remove(&key); });
+			}
+			Ok(Value::Bool(true))
+		});
+
+		Some(Response::Single(Output::from(result, call.id.clone(), call.jsonrpc)))
+	}
+}
+
+/// The error returned to clients presenting an invalid credential.
+pub(crate) fn auth_error(message: &str) -> Error {
+	Error {
+		code: ErrorCode::ServerError(AUTH_FAILED_ERROR_CODE),
+		message: message.into(),
+		data: None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// header `{"alg":"HS256","typ":"JWT"}` signed with the secret `secret`.
+	fn jwt(payload: &str) -> String {
+		let encode = |part: &[u8]| base64::encode_config(part, base64::URL_SAFE_NO_PAD);
+		let message = format!(
+			"{}.{}",
+			encode(br#"{"alg":"HS256","typ":"JWT"}"#),
+			encode(payload.as_bytes()),
+		);
+		let mut mac = Hmac::<Sha256>::new_varkey(b"secret").unwrap();
+		mac.update(message.as_bytes());
+		format!("{}.{}", message, encode(&mac.finalize().into_bytes()))
+	}
+
+	#[test]
+	fn verifies_static_tokens() {
+		let auth = RpcAuth::Token("token".into());
+		assert!(auth.verify("token"));
+		assert!(!auth.verify("other"));
+		assert!(!auth.verify(""));
+	}
+
+	#[test]
+	fn verifies_jwt_signature_and_claims() {
+		assert!(verify_jwt(b"secret", &jwt(r#"{"sub":"node"}"#), 100));
+		assert!(!verify_jwt(b"other", &jwt(r#"{"sub":"node"}"#), 100));
+		assert!(!verify_jwt(b"secret", "not.a.jwt", 100));
+
+		assert!(verify_jwt(b"secret", &jwt(r#"{"exp":101}"#), 100));
+		assert!(!verify_jwt(b"secret", &jwt(r#"{"exp":100}"#), 100));
+		assert!(!verify_jwt(b"secret", &jwt(r#"{"exp":"never"}"#), 100));
+
+		assert!(verify_jwt(b"secret", &jwt(r#"{"nbf":100}"#), 100));
+		assert!(!verify_jwt(b"secret", &jwt(r#"{"nbf":101}"#), 100));
+	}
+
+	#[test]
+	fn extracts_bearer_credential() {
+		assert_eq!(bearer(b"Bearer token"), Some("token".into()));
+		assert_eq!(bearer(b"bearer  token "), Some("token".into()));
+		assert_eq!(bearer(b"Basic dXNlcjpwYXNz"), None);
+		assert_eq!(bearer(b"token"), None);
+	}
+}
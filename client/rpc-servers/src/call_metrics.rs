@@ -0,0 +1,254 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Metrics of the calls of every RPC method and of the active subscriptions.
+
+use std::{
+	collections::{HashMap, HashSet, hash_map::Entry},
+	sync::Arc,
+	time::Duration,
+};
+
+use jsonrpc_core::{Call, Output, Params, Value};
+use parking_lot::{Mutex, RwLock};
+use prometheus_endpoint::{CounterVec, GaugeVec, HistogramVec, U64};
+use pubsub::Session;
+
+use crate::middleware::session_key;
+
+/// Label of the calls of methods that aren't registered, so clients can't create labels.
+const UNKNOWN_METHOD: &str = "unknown";
+
+/// The metrics of the calls of every method.
+#[derive(Debug, Clone)]
+pub(crate) struct MethodMetrics {
+	pub(crate) calls: CounterVec<U64>,
+	pub(crate) errors: CounterVec<U64>,
+	pub(crate) durations: HistogramVec,
+	pub(crate) subscriptions: GaugeVec<U64>,
+	/// The names of the registered methods, the only ones calls are labelled with.
+	pub(crate) registered: Arc<RwLock<HashSet<String>>>,
+}
+
+impl MethodMetrics {
+	/// Note the names of the methods served by a handler.
+	pub(crate) fn register_methods(&self, methods: impl IntoIterator<Item = String>) {
+		self.registered.write().extend(methods);
+	}
+}
+
+/// The active subscriptions of every session, by id.
+#[derive(Clone)]
+pub(crate) struct ActiveSubscriptions {
+	gauge: GaugeVec<U64>,
+	transport: String,
+	sessions: Arc<Mutex<HashMap<String, HashMap<String, String>>>>,
+}
+
+impl ActiveSubscriptions {
+	pub(crate) fn new(gauge: GaugeVec<U64>, transport: &str) -> Self {
+		ActiveSubscriptions { gauge, transport: transport.into(), sessions: Default::default() }
+	}
+
+	/// Note the subscription of the session with the given id, until it is removed or
+	/// the session is dropped.
+	fn add(&self, session: &Arc<Session>, id: String, method: &str) {
+		let key = session_key(session);
+		let mut sessions = self.sessions.lock();
+		let subscriptions = match sessions.entry(key.clone()) {
+			Entry::Occupied(entry) => entry.into_mut(),
+			Entry::Vacant(entry) => {
+				let this = self.clone();
+				session.on_drop(move || this.remove_session(&key));
+				entry.insert(HashMap::new())
+			},
+		};
+		if subscriptions.insert(id, method.into()).is_none() {
+			self.gauge.with_label_values(&[self.transport.as_str(), method]).inc();
+		}
+	}
+
+	/// Forget the subscription of the session with the given id.
+	fn remove(&self, session: &Arc<Session>, id: &str) {
+		let method = self.sessions.lock()
+			.get_mut(&session_key(session))
+			.and_then(|subscriptions| subscriptions.remove(id));
+		if let Some(method) = method {
+			self.gauge.with_label_values(&[self.transport.as_str(), &method]).dec();
+		}
+	}
+
+	fn remove_session(&self, key: &str) {
+		let subscriptions = self.sessions.lock().remove(key).unwrap_or_default();
+		for method in subscriptions.values() {
+			self.gauge.with_label_values(&[self.transport.as_str(), method]).dec();
+		}
+	}
+}
+
+/// Records the metrics of a call once it is handled.
+pub(crate) struct CallObserver {
+	metrics: MethodMetrics,
+	subscriptions: ActiveSubscriptions,
+	session: Option<Arc<Session>>,
+	method: String,
+	/// The id of the subscription, if the call is an unsubscription.
+	unsubscribed: Option<String>,
+}
+
+impl CallObserver {
+	/// Observe the given call, unless it's invalid.
+	pub(crate) fn new(
+		metrics: &MethodMetrics,
+		subscriptions: &ActiveSubscriptions,
+		call: &Call,
+		session: Option<Arc<Session>>,
+	) -> Option<Self> {
+		let (method, params) = match call {
+			Call::MethodCall(call) => (&call.method, &call.params),
+			Call::Notification(notification) => (&notification.method, &notification.params),
+			Call::Invalid { .. } => return None,
+		};
+		let unsubscribed = match params {
+			Params::Array(params) if is_unsubscription(method) =>
+				params.first().map(Value::to_string),
+			_ => None,
+		};
+
+		Some(CallObserver {
+			metrics: metrics.clone(),
+			subscriptions: subscriptions.clone(),
+			session,
+			method: method.clone(),
+			unsubscribed,
+		})
+	}
+
+	/// Record the output of the call and the time it took.
+	pub(crate) fn on_output(self, output: Option<&Output>, duration: Duration) {
+		let registered = self.metrics.registered.read().contains(&self.method);
+		let method = if registered { self.method.as_str() } else { UNKNOWN_METHOD };
+		let transport = self.subscriptions.transport.as_str();
+
+		self.metrics.calls.with_label_values(&[transport, method]).inc();
+		self.metrics.durations.with_label_values(&[transport, method])
+			.observe(duration.as_secs_f64());
+
+		let success = match output {
+			Some(Output::Success(success)) => success,
+			Some(Output::Failure(_)) => {
+				self.metrics.errors.with_label_values(&[transport, method]).inc();
+				return;
+			},
+			None => return,
+		};
+		let session = match self.session {
+			Some(ref session) => session,
+			None => return,
+		};
+		match self.unsubscribed {
+			Some(ref id) if success.result == Value::Bool(true) =>
+				self.subscriptions.remove(session, id),
+			Some(_) => {},
+			None if is_subscription(method) =>
+				self.subscriptions.add(session, success.result.to_string(), method),
+			None => {},
+		}
+	}
+}
+
+fn is_subscription(method: &str) -> bool {
+	method.contains("_subscribe")
+}
+
+fn is_unsubscription(method: &str) -> bool {
+	method.contains("_unsubscribe")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use jsonrpc_core::futures::sync::mpsc;
+	use prometheus_endpoint::Registry;
+	use crate::RpcMetrics;
+
+	#[test]
+	fn counts_active_subscriptions() {
+		let registry = Registry::new();
+		let metrics = RpcMetrics::new(Some(&registry)).unwrap().methods.unwrap();
+		metrics.register_methods(
+			vec!["chain_subscribeNewHead".into(), "chain_unsubscribeNewHead".into()],
+		);
+		let subscriptions = ActiveSubscriptions::new(metrics.subscriptions.clone(), "ws");
+		let active = || metrics.subscriptions
+			.with_label_values(&["ws", "chain_subscribeNewHead"])
+			.get();
+
+		let (sender, _receiver) = mpsc::channel(1);
+		let session = Arc::new(Session::new(sender));
+		let call = |method: &str, params: &str| {
+			let call = serde_json::from_str::<Call>(&format!(
+				r#"{{"jsonrpc":"2.0","method":"{}","params":{},"id":1}}"#,
+				method,
+				params,
+			)).unwrap();
+			CallObserver::new(&metrics, &subscriptions, &call, Some(session.clone())).unwrap()
+		};
+		let output = |result: &str| serde_json::from_str::<Output>(&format!(
+			r#"{{"jsonrpc":"2.0","result":{},"id":1}}"#,
+			result,
+		)).unwrap();
+
+		let done = Duration::default();
+		call("chain_subscribeNewHead", "[]").on_output(Some(&output(r#""a""#)), done);
+		call("chain_subscribeNewHead", "[]").on_output(Some(&output(r#""b""#)), done);
+		assert_eq!(active(), 2);
+
+		call("chain_unsubscribeNewHead", r#"["a"]"#).on_output(Some(&output("true")), done);
+		call("chain_unsubscribeNewHead", r#"["c"]"#).on_output(Some(&output("false")), done);
+		assert_eq!(active(), 1);
+		assert_eq!(metrics.calls.with_label_values(&["ws", "chain_unsubscribeNewHead"]).get(), 2);
+
+		drop(session);
+		assert_eq!(active(), 0);
+	}
+
+	#[test]
+	fn labels_the_calls_of_unregistered_methods_as_unknown() {
+		let registry = Registry::new();
+		let metrics = RpcMetrics::new(Some(&registry)).unwrap().methods.unwrap();
+		metrics.register_methods(vec!["system_health".into()]);
+		let subscriptions = ActiveSubscriptions::new(metrics.subscriptions.clone(), "http");
+		let calls = |method| metrics.calls.with_label_values(&["http", method]).get();
+
+		for method in &["system_health", "made_up", "made_up_subscribe"] {
+			// notifications of unregistered methods aren't answered at all.
+			let call = serde_json::from_str::<Call>(&format!(
+				r#"{{"jsonrpc":"2.0","method":"{}","params":[]}}"#,
+				method,
+			)).unwrap();
+			CallObserver::new(&metrics, &subscriptions, &call, None)
+				.unwrap()
+				.on_output(None, Duration::default());
+		}
+
+		assert_eq!(calls("system_health"), 1);
+		assert_eq!(calls(UNKNOWN_METHOD), 2);
+		assert_eq!(calls("made_up"), 0);
+	}
+}
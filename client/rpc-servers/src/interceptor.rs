@@ -0,0 +1,191 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Interception of the calls handled by the RPC servers.
+//!
+//! Interceptors see every call before it is handled and may reject it, e.g. to
+//! implement custom access rules, and are notified of the outcome of the call,
+//! e.g. to write an audit log.
+
+use std::{fmt, sync::Arc, time::Duration};
+
+use jsonrpc_core::{Call, Error, ErrorCode, Output};
+use sha2::{Digest, Sha256};
+
+/// An RPC call seen by the interceptors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpcCall {
+	/// Name of the called method.
+	pub method: String,
+	/// SHA-256 hash of the JSON encoded parameters of the call.
+	pub params_hash: [u8; 32],
+	/// IP address of the client, as reported by a reverse proxy. `None` if unknown.
+	pub client_ip: Option<String>,
+	/// Label of the transport the call was received over.
+	pub transport: String,
+}
+
+/// Outcome of an RPC call.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RpcOutcome {
+	/// The call succeeded.
+	Success,
+	/// The call failed, or was rejected, with the given error code.
+	Failure(ErrorCode),
+	/// The call was a notification, nothing was returned to the client.
+	Notification,
+}
+
+/// Intercepts the calls handled by an RPC server.
+pub trait RpcInterceptor: Send + Sync {
+	/// Called before the call is handled. Returning an error rejects the call with it.
+	fn on_call(&self, _call: &RpcCall) -> Result<(), Error> {
+		Ok(())
+	}
+
+	/// Called once the call was handled (or rejected) with its outcome and the time
+	/// it took.
+	fn on_response(&self, _call: &RpcCall, _outcome: &RpcOutcome, _duration: Duration) {}
+}
+
+/// The interceptors of the calls of an RPC server, called in the order they were added.
+#[derive(Clone, Default)]
+pub struct RpcInterceptors(Vec<Arc<dyn RpcInterceptor>>);
+
+impl fmt::Debug for RpcInterceptors {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "RpcInterceptors({})", self.0.len())
+	}
+}
+
+impl RpcInterceptors {
+	/// Add an interceptor, called after the ones already added.
+	pub fn with(mut self, interceptor: Arc<dyn RpcInterceptor>) -> Self {
+		self.0.push(interceptor);
+		self
+	}
+
+	/// Returns `true` if there are no interceptors.
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+
+	/// Pass the call to every interceptor, stopping at the first one rejecting it.
+	pub(crate) fn on_call(&self, call: &RpcCall) -> Result<(), Error> {
+		self.0.iter().try_for_each(|interceptor| interceptor.on_call(call))
+	}
+
+	/// Notify every interceptor of the output of the call.
+	pub(crate) fn on_response(&self, call: &RpcCall, output: Option<&Output>, duration: Duration) {
+		let outcome = match output {
+			Some(Output::Success(_)) => RpcOutcome::Success,
+			Some(Output::Failure(failure)) => RpcOutcome::Failure(failure.error.code.clone()),
+			None => RpcOutcome::Notification,
+		};
+		for interceptor in &self.0 {
+			interceptor.on_response(call, &outcome, duration);
+		}
+	}
+}
+
+/// Describe the given call to the interceptors. Invalid calls aren't intercepted.
+pub(crate) fn rpc_call(call: &Call, client_ip: Option<&str>, transport: &str) -> Option<RpcCall> {
+	let (method, params) = match call {
+		Call::MethodCall(call) => (&call.method, &call.params),
+		Call::Notification(notification) => (&notification.method, &notification.params),
+		Call::Invalid { .. } => return None,
+	};
+
+	let params = serde_json::to_vec(params).unwrap_or_default();
+	let mut params_hash = [0; 32];
+	params_hash.copy_from_slice(&Sha256::digest(&params));
+
+	Some(RpcCall {
+		method: method.clone(),
+		params_hash,
+		client_ip: client_ip.map(Into::into),
+		transport: transport.into(),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use jsonrpc_core::{MetaIoHandler, Value};
+	use parking_lot::Mutex;
+	use crate::{RpcMetrics, RpcMiddleware};
+
+	#[derive(Default)]
+	struct Audit(Mutex<Vec<(String, RpcOutcome)>>);
+
+	impl RpcInterceptor for Audit {
+		fn on_call(&self, call: &RpcCall) -> Result<(), Error> {
+			if call.method == "blocked" {
+				return Err(Error::invalid_request());
+			}
+			Ok(())
+		}
+
+		fn on_response(&self, call: &RpcCall, outcome: &RpcOutcome, _duration: Duration) {
+			self.0.lock().push((call.method.clone(), outcome.clone()));
+		}
+	}
+
+	#[test]
+	fn intercepts_calls() {
+		let audit = Arc::new(Audit::default());
+		let middleware = RpcMiddleware::new(RpcMetrics::new(None).unwrap(), "test")
+			.with_interceptors(RpcInterceptors::default().with(audit.clone()));
+		let mut io = MetaIoHandler::with_middleware(middleware);
+		io.add_method("allowed", |_| Ok(Value::Bool(true)));
+		io.add_method("blocked", |_| Ok(Value::Bool(true)));
+
+		let call = |method| io.handle_request_sync(
+			&format!(r#"{{"jsonrpc":"2.0","method":"{}","id":1}}"#, method),
+			sc_rpc_api::Metadata::default(),
+		);
+
+		assert_eq!(call("allowed"), Some(r#"{"jsonrpc":"2.0","result":true,"id":1}"#.into()));
+		assert_eq!(
+			call("blocked"),
+			Some(r#"{"jsonrpc":"2.0","error":{"code":-32600,"message":"Invalid request"},"id":1}"#
+				.into()),
+		);
+		assert_eq!(
+			*audit.0.lock(),
+			vec![
+				("allowed".into(), RpcOutcome::Success),
+				("blocked".into(), RpcOutcome::Failure(ErrorCode::InvalidRequest)),
+			],
+		);
+	}
+
+	#[test]
+	fn hashes_call_params() {
+		let call = |params| serde_json::from_str::<Call>(&format!(
+			r#"{{"jsonrpc":"2.0","method":"method","params":{},"id":1}}"#,
+			params,
+		)).unwrap();
+
+		let first = rpc_call(&call("[1]"), Some("1.2.3.4"), "http").unwrap();
+		assert_eq!(first.method, "method");
+		assert_eq!(first.client_ip.as_deref(), Some("1.2.3.4"));
+		assert_eq!(first.params_hash, rpc_call(&call("[1]"), None, "ws").unwrap().params_hash);
+		assert_ne!(first.params_hash, rpc_call(&call("[2]"), None, "ws").unwrap().params_hash);
+	}
+}
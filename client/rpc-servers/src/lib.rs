@@ -20,12 +20,20 @@
 
 #![warn(missing_docs)]
 
+mod aliases;
+mod auth;
+mod call_metrics;
+mod interceptor;
+mod limits;
+mod method_policy;
 mod middleware;
+mod rate_limit;
 
 use std::io;
 use jsonrpc_core::{IoHandlerExtension, MetaIoHandler};
-use log::error;
+use log::{error, warn};
 use pubsub::PubSubMetadata;
+use sc_rpc_api::AuthMetadata;
 
 /// Maximal payload accepted by RPC servers.
 const MAX_PAYLOAD: usize = 15 * 1024 * 1024;
@@ -33,96 +41,345 @@ const MAX_PAYLOAD: usize = 15 * 1024 * 1024;
 /// Default maximum number of connections for WS RPC servers.
 const WS_MAX_CONNECTIONS: usize = 100;
 
+/// Default permissions of the IPC socket: only its owner may connect.
+pub const DEFAULT_IPC_PERMISSIONS: u32 = 0o600;
+
 /// The RPC IoHandler containing all requested APIs.
 pub type RpcHandler<T> = pubsub::PubSubHandler<T, RpcMiddleware>;
 
 pub use self::inner::*;
+pub use aliases::RpcAliases;
+pub use auth::{RpcAuth, AUTHENTICATE_METHOD, AUTH_FAILED_ERROR_CODE};
+pub use interceptor::{RpcCall, RpcInterceptor, RpcInterceptors, RpcOutcome};
+pub use limits::{RequestLimits, REQUEST_LIMIT_ERROR_CODE};
+pub use method_policy::{MethodAccess, PolicyError, RpcMethodPolicy};
 pub use middleware::{RpcMiddleware, RpcMetrics};
+pub use rate_limit::{RateLimit, RateLimiter, RATE_LIMITED_ERROR_CODE};
 
 /// Construct rpc `IoHandler`
-pub fn rpc_handler<M: PubSubMetadata>(
+pub fn rpc_handler<M: PubSubMetadata + AuthMetadata>(
 	extension: impl IoHandlerExtension<M>,
 	rpc_middleware: RpcMiddleware,
 ) -> RpcHandler<M> {
+	let aliases = rpc_middleware.aliases().clone();
+	let method_metrics = rpc_middleware.method_metrics().cloned();
 	let io_handler = MetaIoHandler::with_middleware(rpc_middleware);
 	let mut io = pubsub::PubSubHandler::new(io_handler);
 	extension.augment(&mut io);
 
+	for (alias, method) in aliases.iter() {
+		if io.iter().any(|(name, _)| name == method) {
+			io.add_alias(alias, method);
+		} else {
+			warn!("Not exposing `{}` as `{}`: no such RPC method", method, alias);
+		}
+	}
+
 	// add an endpoint to list all available methods.
 	let mut methods = io.iter().map(|x| x.0.clone()).collect::<Vec<String>>();
+	let deprecated = aliases.deprecated_names()
+		.filter(|(alias, _)| methods.iter().any(|name| name == alias))
+		.map(|(alias, method)| (alias.to_owned(), method.to_owned()))
+		.collect::<std::collections::BTreeMap<_, _>>();
 	io.add_method("rpc_methods", {
 		methods.sort();
 		let methods = serde_json::to_value(&methods)
 			.expect("Serialization of Vec<String> is infallible; qed");
+		let deprecated = serde_json::to_value(&deprecated)
+			.expect("Serialization of BTreeMap<String, String> is infallible; qed");
 
 		move |_| Ok(serde_json::json!({
 			"version": 1,
 			"methods": methods.clone(),
+			"deprecated": deprecated.clone(),
 		}))
 	});
+
+	if let Some(method_metrics) = method_metrics {
+		method_metrics.register_methods(io.iter().map(|(name, _)| name.clone()));
+	}
 	io
 }
 
 #[cfg(not(target_os = "unknown"))]
 mod inner {
 	use super::*;
+	use std::{cell::Cell, net::{IpAddr, SocketAddr}, sync::Arc};
+	use futures::{sync::oneshot, Future, Stream};
+	use http::{hyper, tokio};
+	use auth::bearer;
+	use rate_limit::{client_ip, ip_bucket, rate_limited_body};
+
+	/// Number of threads of the HTTP server.
+	const HTTP_THREADS: usize = 4;
+
+	/// Identifies the client of an HTTP request.
+	type RequestIp = Arc<dyn Fn(&hyper::Request<hyper::Body>) -> Option<IpAddr> + Send + Sync>;
 
 	/// Type alias for ipc server
 	pub type IpcServer = ipc::Server;
-	/// Type alias for http server
-	pub type HttpServer = http::Server;
 	/// Type alias for ws server
 	pub type WsServer = ws::Server;
 
+	thread_local! {
+		/// The IP of the client of the WS connection being opened.
+		///
+		/// `jsonrpc-ws-server` only passes the handshake request to the request middleware,
+		/// which then calls the metadata extractor of the connection on the same thread.
+		static WS_CLIENT_IP: Cell<Option<IpAddr>> = Cell::new(None);
+	}
+
+	/// HTTP server, closed when dropped.
+	///
+	/// **Note**: Only available if `not(target_os = "unknown")`.
+	pub struct HttpServer {
+		address: SocketAddr,
+		close: Option<oneshot::Sender<()>>,
+		runtime: Option<tokio::runtime::Runtime>,
+	}
+
+	impl HttpServer {
+		/// Returns the address the server is listening on.
+		pub fn address(&self) -> &SocketAddr {
+			&self.address
+		}
+
+		/// Close the server, waiting for its threads to stop.
+		pub fn close(self) {}
+	}
+
+	impl Drop for HttpServer {
+		fn drop(&mut self) {
+			if let Some(close) = self.close.take() {
+				let _ = close.send(());
+			}
+			if let Some(runtime) = self.runtime.take() {
+				let _ = runtime.shutdown_now().wait();
+			}
+		}
+	}
+
 	/// Start HTTP server listening on given address.
 	///
+	/// Clients are identified by the address of the peer of their connection, or the
+	/// address forwarded by the `trusted_proxies`, see [`RateLimiter`]. The requests of
+	/// every client IP are limited by the given `rate_limiter`, if any, and rejected with
+	/// a `429 Too Many Requests` response. The credential of the `Authorization` header
+	/// of the requests and the client IP are attached to their metadata.
+	///
+	/// The connections are accepted by the server itself rather than by
+	/// `jsonrpc-http-server`, which doesn't report the address of their peer.
+	///
 	/// **Note**: Only available if `not(target_os = "unknown")`.
-	pub fn start_http<M: pubsub::PubSubMetadata + Default>(
-		addr: &std::net::SocketAddr,
+	pub fn start_http<M: pubsub::PubSubMetadata + AuthMetadata + Default>(
+		addr: &SocketAddr,
 		cors: Option<&Vec<String>>,
 		io: RpcHandler<M>,
-	) -> io::Result<http::Server> {
-		http::ServerBuilder::new(io)
-			.threads(4)
-			.health_api(("/health", "system_health"))
-			.allowed_hosts(hosts_filtering(cors.is_some()))
-			.rest_api(if cors.is_some() {
-				http::RestApi::Secure
+		rate_limiter: Option<Arc<RateLimiter>>,
+		trusted_proxies: &[IpAddr],
+	) -> io::Result<HttpServer> {
+		let listener = std::net::TcpListener::bind(addr)?;
+		let address = listener.local_addr()?;
+		let runtime = tokio::runtime::Builder::new()
+			.name_prefix("http.worker")
+			.core_threads(HTTP_THREADS)
+			.build()?;
+
+		let handler: Arc<MetaIoHandler<M, RpcMiddleware>> = Arc::new(io.into());
+		let trusted_proxies: Arc<[IpAddr]> = trusted_proxies.into();
+		let cors: Option<Vec<http::AccessControlAllowOrigin>> =
+			map_cors::<http::AccessControlAllowOrigin>(cors).into();
+		let allowed_hosts = jsonrpc_server_utils::hosts::update(
+			hosts_filtering(cors.is_some()).into(),
+			&address,
+		);
+		let rest_api = if cors.is_some() {
+			http::RestApi::Secure
+		} else {
+			http::RestApi::Unsecure
+		};
+
+		let (close, closed) = oneshot::channel::<()>();
+		let server = futures::future::lazy(move || {
+			let listener = tokio::net::TcpListener::from_std(
+				listener,
+				&tokio::reactor::Handle::default(),
+			).map_err(|e| error!("Failed to listen on {}: {}", address, e))?;
+			let connection = hyper::server::conn::Http::new();
+
+			let connections = http::SuspendableStream::new(listener.incoming())
+				.for_each(move |socket| {
+					let peer = socket.peer_addr().ok().map(|peer| peer.ip());
+					let trusted_proxies = trusted_proxies.clone();
+					let request_ip: RequestIp = Arc::new(move |request| client_ip(
+						peer,
+						&trusted_proxies,
+						|name| request.headers().get(name).map(|value| value.as_bytes()),
+					));
+
+					// the handler only holds the extractor weakly.
+					let rpc = http::Rpc {
+						handler: handler.clone(),
+						extractor: Arc::new(extractor::<M>(request_ip.clone())),
+					};
+					let service = http::ServerHandler::new(
+						rpc.downgrade(),
+						cors.clone(),
+						None,
+						http::cors::AccessControlAllowHeaders::Any,
+						allowed_hosts.clone(),
+						Arc::new(rate_limit_middleware(rate_limiter.clone(), request_ip)),
+						rest_api,
+						Some(("/health".into(), "system_health".into())),
+						MAX_PAYLOAD,
+						true,
+					);
+
+					tokio::spawn(connection.serve_connection(socket, service).then(move |result| {
+						if let Err(e) = result {
+							error!("Error serving connection: {:?}", e);
+						}
+						drop(rpc);
+						Ok(())
+					}));
+					Ok(())
+				});
+
+			Ok::<_, ()>(connections)
+		})
+			.flatten()
+			.select(closed.map_err(|_| ()))
+			.then(|_| Ok(()));
+		runtime.executor().spawn(server);
+
+		Ok(HttpServer { address, close: Some(close), runtime: Some(runtime) })
+	}
+
+	/// Attach the credential of the `Authorization` header and the client IP to the metadata
+	/// of the requests.
+	fn extractor<M: pubsub::PubSubMetadata + AuthMetadata + Default>(
+		request_ip: RequestIp,
+	) -> impl http::MetaExtractor<M> {
+		move |request: &hyper::Request<hyper::Body>| {
+			let mut meta = M::default();
+			let authorization = request.headers().get(hyper::header::AUTHORIZATION);
+			if let Some(credential) = authorization.and_then(|value| bearer(value.as_bytes())) {
+				meta.set_credential(credential);
+			}
+			if let Some(ip) = request_ip(request) {
+				meta.set_client_ip(ip.to_string());
+			}
+			meta
+		}
+	}
+
+	/// Reject the requests of the clients exceeding their rate limit, if any.
+	fn rate_limit_middleware(
+		rate_limiter: Option<Arc<RateLimiter>>,
+		request_ip: RequestIp,
+	) -> impl http::RequestMiddleware {
+		move |request: hyper::Request<hyper::Body>| {
+			let allowed = match (&rate_limiter, request_ip(&request)) {
+				(Some(rate_limiter), Some(ip)) => rate_limiter.check(&ip_bucket(&ip)),
+				_ => true,
+			};
+			if allowed {
+				http::RequestMiddlewareAction::Proceed {
+					should_continue_on_invalid_cors: false,
+					request,
+				}
 			} else {
-				http::RestApi::Unsecure
-			})
-			.cors(map_cors::<http::AccessControlAllowOrigin>(cors))
-			.max_request_body_size(MAX_PAYLOAD)
-			.start_http(addr)
+				http::RequestMiddlewareAction::Respond {
+					should_validate_hosts: true,
+					response: Box::new(futures::future::ok(http_rate_limited())),
+				}
+			}
+		}
 	}
 
 	/// Start IPC server listening on given path.
 	///
+	/// On Unix the server listens on a domain socket created with the given file
+	/// `permissions`, e.g. [`DEFAULT_IPC_PERMISSIONS`], before any connection is accepted.
+	/// Named pipes of Windows don't have permissions.
+	///
 	/// **Note**: Only available if `not(target_os = "unknown")`.
-	pub fn start_ipc<M: pubsub::PubSubMetadata + Default>(
+	pub fn start_ipc<M: pubsub::PubSubMetadata + AuthMetadata + Default>(
 		addr: &str,
 		io: RpcHandler<M>,
+		permissions: u32,
 	) -> io::Result<ipc::Server> {
 		let builder = ipc::ServerBuilder::new(io);
-		#[cfg(target_os = "unix")]
-		builder.set_security_attributes({
-			let security_attributes = ipc::SecurityAttributes::empty();
-			security_attributes.set_mode(0o600)?;
-			security_attributes
-		});
+
+		#[cfg(unix)]
+		let builder = {
+			let mode = std::convert::TryFrom::try_from(permissions).map_err(|_| io::Error::new(
+				io::ErrorKind::InvalidInput,
+				format!("Invalid IPC socket permissions: {:o}", permissions),
+			))?;
+			builder.set_security_attributes(ipc::SecurityAttributes::empty().set_mode(mode)?)
+		};
+		#[cfg(not(unix))]
+		let _ = permissions;
+
 		builder.start(addr)
 	}
 
 	/// Start WS server listening on given address.
 	///
+	/// The connection attempts of every client IP are limited by the given
+	/// `rate_limiter`, if any, and rejected with a `429 Too Many Requests` response.
+	/// The requests made over the connections are limited by the [`RpcMiddleware`].
+	/// Clients are identified by the address forwarded by the `trusted_proxies`, which is
+	/// attached to the metadata of their session: `jsonrpc-ws-server` doesn't report the
+	/// address of the peer of connections.
+	///
 	/// **Note**: Only available if `not(target_os = "unknown")`.
-	pub fn start_ws<M: pubsub::PubSubMetadata + From<jsonrpc_core::futures::sync::mpsc::Sender<String>>> (
-		addr: &std::net::SocketAddr,
+	pub fn start_ws<
+		M: PubSubMetadata + AuthMetadata + From<jsonrpc_core::futures::sync::mpsc::Sender<String>>,
+	>(
+		addr: &SocketAddr,
 		max_connections: Option<usize>,
 		cors: Option<&Vec<String>>,
 		io: RpcHandler<M>,
+		rate_limiter: Option<Arc<RateLimiter>>,
+		trusted_proxies: &[IpAddr],
 	) -> io::Result<ws::Server> {
-		ws::ServerBuilder::with_meta_extractor(io, |context: &ws::RequestContext| context.sender().into())
+		let trusted_proxies: Arc<[IpAddr]> = trusted_proxies.into();
+		let builder = ws::ServerBuilder::with_meta_extractor(
+			io,
+			|context: &ws::RequestContext| {
+				let mut meta = M::from(context.sender());
+				if let Some(ip) = WS_CLIENT_IP.with(|ip| ip.take()) {
+					meta.set_client_ip(ip.to_string());
+				}
+				meta
+			},
+		);
+
+		builder
+			.request_middleware(move |request: &ws::ws::Request| {
+				let ip = client_ip(
+					None,
+					&trusted_proxies,
+					|name| request.header(name).map(|v| v.as_slice()),
+				);
+				WS_CLIENT_IP.with(|client_ip| client_ip.set(ip));
+
+				let allowed = match (&rate_limiter, ip) {
+					(Some(rate_limiter), Some(ip)) => rate_limiter.check(&ip_bucket(&ip)),
+					_ => true,
+				};
+				if allowed {
+					None
+				} else {
+					Some(ws::ws::Response::new(
+						429,
+						"Too Many Requests",
+						rate_limited_body().into_bytes(),
+					))
+				}
+			})
 			.max_payload(MAX_PAYLOAD)
 			.max_connections(max_connections.unwrap_or(WS_MAX_CONNECTIONS))
 			.allowed_origins(map_cors(cors))
@@ -138,6 +395,14 @@ mod inner {
 			})
 	}
 
+	fn http_rate_limited() -> hyper::Response<hyper::Body> {
+		hyper::Response::builder()
+			.status(hyper::StatusCode::TOO_MANY_REQUESTS)
+			.header(hyper::header::CONTENT_TYPE, "application/json; charset=utf-8")
+			.body(rate_limited_body().into())
+			.expect("Status code and header are valid; qed")
+	}
+
 	fn map_cors<T: for<'a> From<&'a str>>(
 		cors: Option<&Vec<String>>
 	) -> http::DomainsValidation<T> {
@@ -159,3 +424,61 @@ mod inner {
 #[cfg(target_os = "unknown")]
 mod inner {
 }
+
+#[cfg(all(test, not(target_os = "unknown")))]
+mod tests {
+	use super::*;
+	use std::{io::{Read, Write}, net::TcpStream, sync::Arc};
+	use sc_rpc_api::Metadata;
+
+	fn post(address: &std::net::SocketAddr, body: &str) -> String {
+		let mut stream = TcpStream::connect(address).unwrap();
+		write!(
+			stream,
+			"POST / HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\n\
+				Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+			address,
+			body.len(),
+			body,
+		).unwrap();
+		let mut response = String::new();
+		stream.read_to_string(&mut response).unwrap();
+		response
+	}
+
+	#[test]
+	fn limits_the_rate_of_http_clients_by_their_peer_address() {
+		let metrics = RpcMetrics::new(None).unwrap();
+		let limit = RateLimit { requests_per_second: 1, burst: 2 };
+		let rate_limiter = Arc::new(RateLimiter::new(limit, &metrics, "http"));
+		let middleware = RpcMiddleware::new(metrics, "http")
+			.with_rate_limiter(Some(rate_limiter.clone()));
+		let mut io = jsonrpc_core::MetaIoHandler::<Metadata>::default();
+		io.add_method_with_meta("client_ip", |_, meta: Metadata| {
+			futures::future::ok(serde_json::json!(meta.client_ip()))
+		});
+		let server = start_http(
+			&"127.0.0.1:0".parse().unwrap(),
+			None,
+			rpc_handler(io, middleware),
+			Some(rate_limiter),
+			&[],
+		).unwrap();
+
+		let response = post(server.address(), r#"{"jsonrpc":"2.0","method":"client_ip","id":1}"#);
+		assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+		assert!(response.contains(r#"{"jsonrpc":"2.0","result":"127.0.0.1","id":1}"#));
+
+		// every call of a batch takes a token.
+		let batch = r#"[
+			{"jsonrpc":"2.0","method":"client_ip","id":1},
+			{"jsonrpc":"2.0","method":"client_ip","id":2}
+		]"#;
+		let response = post(server.address(), batch);
+		assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+		assert!(response.contains(&RATE_LIMITED_ERROR_CODE.to_string()), "{}", response);
+
+		let response = post(server.address(), r#"{"jsonrpc":"2.0","method":"client_ip","id":1}"#);
+		assert!(response.starts_with("HTTP/1.1 429"), "{}", response);
+	}
+}
@@ -0,0 +1,120 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Limits of the batches and of the calls in flight of RPC clients.
+
+use std::{collections::HashMap, sync::Arc};
+
+use jsonrpc_core::{Error, ErrorCode};
+use parking_lot::Mutex;
+
+/// JSON-RPC error code returned for requests exceeding the limits.
+pub const REQUEST_LIMIT_ERROR_CODE: i64 = -32031;
+
+/// Limits of the requests of every RPC client.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RequestLimits {
+	/// Maximum number of calls in a batch. `None` if unlimited.
+	pub max_batch_size: Option<usize>,
+	/// Whether the calls of a batch are processed one after another instead of
+	/// concurrently.
+	///
+	/// Every call is only dispatched once the previous call completed.
+	pub sequential_batches: bool,
+	/// Maximum number of calls in flight over a connection (session). `None` if
+	/// unlimited. Requests made outside of a session aren't limited.
+	pub max_in_flight: Option<usize>,
+}
+
+/// The error returned for requests exceeding the limits.
+pub(crate) fn limit_error(message: String) -> Error {
+	Error {
+		code: ErrorCode::ServerError(REQUEST_LIMIT_ERROR_CODE),
+		message,
+		data: None,
+	}
+}
+
+/// Number of calls in flight of every session.
+#[derive(Default)]
+pub(crate) struct InFlight {
+	calls: Arc<Mutex<HashMap<String, usize>>>,
+}
+
+impl InFlight {
+	/// Note that the given number of calls of the session are in flight until the
+	/// returned guard is dropped, unless that exceeds the limit.
+	pub(crate) fn acquire(
+		&self,
+		session: String,
+		calls: usize,
+		limit: usize,
+	) -> Option<InFlightGuard> {
+		let mut in_flight = self.calls.lock();
+		let count = in_flight.entry(session.clone()).or_default();
+		if *count + calls > limit {
+			if *count == 0 {
+				in_flight.remove(&session);
+			}
+			return None;
+		}
+		*count += calls;
+
+		Some(InFlightGuard { calls: self.calls.clone(), session, count: calls })
+	}
+}
+
+/// Releases calls in flight when dropped.
+pub(crate) struct InFlightGuard {
+	calls: Arc<Mutex<HashMap<String, usize>>>,
+	session: String,
+	count: usize,
+}
+
+impl Drop for InFlightGuard {
+	fn drop(&mut self) {
+		let mut in_flight = self.calls.lock();
+		if let Some(count) = in_flight.get_mut(&self.session) {
+			*count = count.saturating_sub(self.count);
+			if *count == 0 {
+				in_flight.remove(&self.session);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn limits_calls_in_flight_per_session() {
+		let in_flight = InFlight::default();
+
+		let first = in_flight.acquire("a".into(), 2, 3).unwrap();
+		assert!(in_flight.acquire("a".into(), 2, 3).is_none());
+		let second = in_flight.acquire("a".into(), 1, 3).unwrap();
+		assert!(in_flight.acquire("b".into(), 3, 3).is_some());
+
+		drop(first);
+		assert!(in_flight.acquire("a".into(), 2, 3).is_some());
+
+		drop(second);
+		assert!(in_flight.calls.lock().is_empty());
+	}
+}
@@ -0,0 +1,229 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Access rules of individual RPC methods.
+//!
+//! The rules override the split of the methods into safe and unsafe ones for the clients of
+//! the HTTP & WS servers, e.g. to disable an expensive safe method on a public node. They are
+//! read from a JSON file mapping method names to one of `allowed`, `denied` or `auth-required`:
+//!
+//! ```json
+//! { "state_getKeysPaged": "denied", "author_rotateKeys": "auth-required" }
+//! ```
+
+use std::{collections::HashMap, fmt, fs, io, path::Path, str::FromStr};
+
+use jsonrpc_core::Call;
+use serde_json::Value;
+
+use crate::aliases::RpcAliases;
+
+/// Access to an RPC method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MethodAccess {
+	/// Every client may call the method, even if it is unsafe.
+	Allowed,
+	/// No client may call the method, it is reported as not found.
+	Denied,
+	/// Only the authenticated clients may call the method. Without an [`RpcAuth`] no client
+	/// can be authenticated, so the method is denied.
+	///
+	/// [`RpcAuth`]: crate::RpcAuth
+	AuthRequired,
+}
+
+impl FromStr for MethodAccess {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"allowed" => Ok(MethodAccess::Allowed),
+			"denied" => Ok(MethodAccess::Denied),
+			"auth-required" => Ok(MethodAccess::AuthRequired),
+			_ => Err(format!(
+				"Invalid method access `{}`, expected `allowed`, `denied` or `auth-required`",
+				s,
+			)),
+		}
+	}
+}
+
+/// Error loading a method policy.
+#[derive(Debug)]
+pub enum PolicyError {
+	/// The file couldn't be read.
+	Io(io::Error),
+	/// The file isn't valid JSON.
+	Json(serde_json::Error),
+	/// The file isn't a map of method names to valid accesses.
+	Invalid(String),
+}
+
+impl fmt::Display for PolicyError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			PolicyError::Io(e) => write!(f, "Failed to read the RPC method policy: {}", e),
+			PolicyError::Json(e) => write!(f, "Invalid RPC method policy: {}", e),
+			PolicyError::Invalid(e) => write!(f, "Invalid RPC method policy: {}", e),
+		}
+	}
+}
+
+impl std::error::Error for PolicyError {}
+
+/// The access rules of the RPC methods, by name.
+#[derive(Debug, Clone, Default)]
+pub struct RpcMethodPolicy(HashMap<String, MethodAccess>);
+
+impl RpcMethodPolicy {
+	/// Read the policy from the JSON file at the given path.
+	pub fn load(path: &Path) -> Result<Self, PolicyError> {
+		let json = fs::read_to_string(path).map_err(PolicyError::Io)?;
+		Self::from_json(&json)
+	}
+
+	/// Parse the policy from a JSON object mapping method names to accesses.
+	pub fn from_json(json: &str) -> Result<Self, PolicyError> {
+		let value: Value = serde_json::from_str(json).map_err(PolicyError::Json)?;
+		let rules = value.as_object()
+			.ok_or_else(|| PolicyError::Invalid("expected an object".into()))?;
+
+		rules.iter().try_fold(Self::default(), |policy, (method, access)| {
+			let access = access.as_str()
+				.ok_or_else(|| format!("the access to `{}` isn't a string", method))
+				.and_then(MethodAccess::from_str)
+				.map_err(PolicyError::Invalid)?;
+			Ok(policy.with(method.clone(), access))
+		})
+	}
+
+	/// Set the access to the given method.
+	pub fn with(mut self, method: impl Into<String>, access: MethodAccess) -> Self {
+		self.0.insert(method.into(), access);
+		self
+	}
+
+	/// Returns the access to the given method, if it has a rule.
+	pub fn access(&self, method: &str) -> Option<MethodAccess> {
+		self.0.get(method).copied()
+	}
+
+	/// Returns `true` if there are no rules.
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+
+	/// Returns `true` if some methods are allowed to every client.
+	pub fn allows_any(&self) -> bool {
+		self.0.values().any(|access| *access == MethodAccess::Allowed)
+	}
+
+	/// Returns the policy applying to the authenticated clients, who may call every method
+	/// that isn't denied.
+	pub fn authenticated(&self) -> Self {
+		RpcMethodPolicy(
+			self.0.iter()
+				.filter(|(_, access)| **access == MethodAccess::Denied)
+				.map(|(method, access)| (method.clone(), *access))
+				.collect()
+		)
+	}
+
+	/// Returns the access to the called method, if it has a rule.
+	///
+	/// The rules apply to the methods whichever of their names they are called by, so the
+	/// aliases are resolved first.
+	pub(crate) fn access_to(&self, call: &Call, aliases: &RpcAliases) -> Option<MethodAccess> {
+		match call {
+			Call::MethodCall(call) => self.access(aliases.resolve(&call.method)),
+			Call::Notification(notification) => self.access(aliases.resolve(&notification.method)),
+			Call::Invalid { .. } => None,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use jsonrpc_core::IoHandler;
+	use crate::{rpc_handler, RpcAliases, RpcMetrics, RpcMiddleware};
+
+	#[test]
+	fn parses_the_access_of_every_method() {
+		let policy = RpcMethodPolicy::from_json(r#"{
+			"state_getKeysPaged": "denied",
+			"author_rotateKeys": "auth-required",
+			"system_addReservedPeer": "allowed"
+		}"#).unwrap();
+
+		assert_eq!(policy.access("state_getKeysPaged"), Some(MethodAccess::Denied));
+		assert_eq!(policy.access("author_rotateKeys"), Some(MethodAccess::AuthRequired));
+		assert_eq!(policy.access("system_addReservedPeer"), Some(MethodAccess::Allowed));
+		assert_eq!(policy.access("system_health"), None);
+		assert!(policy.allows_any());
+
+		let authenticated = policy.authenticated();
+		assert_eq!(authenticated.access("state_getKeysPaged"), Some(MethodAccess::Denied));
+		assert_eq!(authenticated.access("author_rotateKeys"), None);
+		assert!(!authenticated.allows_any());
+
+		assert!(RpcMethodPolicy::from_json(r#"{ "system_health": "maybe" }"#).is_err());
+		assert!(RpcMethodPolicy::from_json(r#"["system_health"]"#).is_err());
+	}
+
+	#[test]
+	fn applies_the_policy_to_the_calls() {
+		let handler = |unsafe_result| {
+			let mut extension = IoHandler::default();
+			extension.add_method("state_getKeysPaged", |_| Ok(Value::Bool(true)));
+			extension.add_method("author_rotateKeys", |_| Ok(Value::Bool(true)));
+			extension.add_method("system_addReservedPeer", move |_| Ok(Value::Bool(unsafe_result)));
+			extension
+		};
+		let policy = RpcMethodPolicy::default()
+			.with("state_getKeysPaged", MethodAccess::Denied)
+			.with("author_rotateKeys", MethodAccess::AuthRequired)
+			.with("system_addReservedPeer", MethodAccess::Allowed);
+		let aliases = RpcAliases::default()
+			.alias("state_getKeysPagedAlias", "state_getKeysPaged")
+			.deprecated("author_rotateKeysOld", "author_rotateKeys");
+		let middleware = || RpcMiddleware::new(RpcMetrics::new(None).unwrap(), "test")
+			.with_aliases(aliases.clone());
+		let unrestricted = rpc_handler(handler(true), middleware());
+		let io = rpc_handler(
+			handler(false),
+			middleware().with_method_policy(policy, Some(unrestricted)),
+		);
+		let call = |method| {
+			let response = io.handle_request_sync(
+				&format!(r#"{{"jsonrpc":"2.0","method":"{}","id":1}}"#, method),
+				sc_rpc_api::Metadata::default(),
+			).unwrap();
+			serde_json::from_str::<Value>(&response).unwrap()
+		};
+
+		assert_eq!(call("state_getKeysPaged")["error"]["code"], -32601);
+		assert_eq!(call("author_rotateKeys")["error"]["code"], crate::AUTH_FAILED_ERROR_CODE);
+		assert_eq!(call("system_addReservedPeer")["result"], true);
+		assert_eq!(call("state_getKeysPagedAlias")["error"]["code"], -32601);
+		assert_eq!(
+			call("author_rotateKeysOld")["error"]["code"],
+			crate::AUTH_FAILED_ERROR_CODE,
+		);
+	}
+}
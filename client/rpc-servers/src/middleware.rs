@@ -18,21 +18,40 @@
 
 //! Middleware for RPC requests.
 
+use std::{any::Any, sync::Arc, time::{Duration, Instant}};
+
 use jsonrpc_core::{
-	Middleware as RequestMiddleware, Metadata,
-	Request, Response, FutureResponse, FutureOutput
+	Middleware as RequestMiddleware, Call, Error, ErrorCode, Id, Output,
+	Request, Response, FutureResponse, FutureOutput, Version,
 };
+use pubsub::{PubSubMetadata, Session};
+use sc_rpc_api::AuthMetadata;
 use prometheus_endpoint::{
-	Registry, CounterVec, PrometheusError,
-	Opts, register, U64
+	Registry, CounterVec, GaugeVec, HistogramOpts, HistogramVec, PrometheusError,
+	Opts, exponential_buckets, register, U64
 };
 
-use futures::{future::Either, Future};
+use futures::{future::{self, Either}, stream, Future, Stream};
+use log::{debug, warn};
+
+use crate::{
+	RpcHandler,
+	aliases::{Deprecation, DeprecationWarnings, RpcAliases},
+	call_metrics::{ActiveSubscriptions, CallObserver, MethodMetrics},
+	auth::{Access, Authenticator, RpcAuth, auth_error},
+	interceptor::{RpcInterceptors, rpc_call},
+	limits::{InFlight, InFlightGuard, RequestLimits, limit_error},
+	method_policy::{MethodAccess, RpcMethodPolicy},
+	rate_limit::{ip_bucket, RateLimiter, RATE_LIMITED_ERROR_CODE, RATE_LIMITED_MESSAGE},
+};
 
 /// Metrics for RPC middleware
 #[derive(Debug, Clone)]
 pub struct RpcMetrics {
 	rpc_calls: Option<CounterVec<U64>>,
+	pub(crate) rate_limited: Option<CounterVec<U64>>,
+	deprecated_calls: Option<CounterVec<U64>>,
+	pub(crate) methods: Option<MethodMetrics>,
 }
 
 impl RpcMetrics {
@@ -51,6 +70,77 @@ impl RpcMetrics {
 					r,
 				)
 			).transpose()?,
+			rate_limited: metrics_registry.map(|r|
+				register(
+					CounterVec::new(
+						Opts::new(
+							"rpc_requests_rate_limited_total",
+							"Number of rpc requests rejected because of the rate limit",
+						),
+						&["protocol"]
+					)?,
+					r,
+				)
+			).transpose()?,
+			deprecated_calls: metrics_registry.map(|r|
+				register(
+					CounterVec::new(
+						Opts::new(
+							"rpc_deprecated_calls_total",
+							"Number of calls of deprecated rpc method names",
+						),
+						&["protocol", "method"]
+					)?,
+					r,
+				)
+			).transpose()?,
+			methods: metrics_registry.map(|r| Ok::<_, PrometheusError>(MethodMetrics {
+				calls: register(
+					CounterVec::new(
+						Opts::new(
+							"rpc_method_calls_total",
+							"Number of calls of every rpc method",
+						),
+						&["protocol", "method"]
+					)?,
+					r,
+				)?,
+				errors: register(
+					CounterVec::new(
+						Opts::new(
+							"rpc_method_errors_total",
+							"Number of calls of every rpc method that failed",
+						),
+						&["protocol", "method"]
+					)?,
+					r,
+				)?,
+				durations: register(
+					HistogramVec::new(
+						HistogramOpts {
+							common_opts: Opts::new(
+								"rpc_method_call_duration_seconds",
+								"Time taken to handle the calls of every rpc method",
+							),
+							buckets: exponential_buckets(0.0001, 4.0, 10)
+								.expect("parameters are always valid values; qed"),
+						},
+						&["protocol", "method"]
+					)?,
+					r,
+				)?,
+				subscriptions: register(
+					GaugeVec::new(
+						Opts::new(
+							"rpc_subscriptions_active",
+							"Number of active rpc subscriptions of every kind",
+						),
+						&["protocol", "subscription"]
+					)?,
+					r,
+				)?,
+				registered: Default::default(),
+			})).transpose()?,
 		})
 	}
 }
@@ -59,6 +149,20 @@ impl RpcMetrics {
 pub struct RpcMiddleware {
 	metrics: RpcMetrics,
 	transport_label: String,
+	rate_limiter: Option<Arc<RateLimiter>>,
+	authenticator: Option<Arc<Authenticator>>,
+	limits: RequestLimits,
+	in_flight: InFlight,
+	interceptors: RpcInterceptors,
+	aliases: RpcAliases,
+	/// The calls of the deprecated names since the last warning about them.
+	warnings: Arc<DeprecationWarnings>,
+	subscriptions: Option<ActiveSubscriptions>,
+	method_policy: RpcMethodPolicy,
+	/// The `RpcHandler` allowing the unsafe methods, for the methods allowed by the policy.
+	unrestricted: Option<Arc<dyn Any + Send + Sync>>,
+	/// The `RpcHandler` the calls of sequential batches are passed to one after another.
+	batch_handler: Option<Arc<dyn Any + Send + Sync>>,
 }
 
 impl RpcMiddleware {
@@ -67,14 +171,323 @@ impl RpcMiddleware {
 	/// - `metrics`: Will be used to report statistics.
 	/// - `transport_label`: The label that is used when reporting the statistics.
 	pub fn new(metrics: RpcMetrics, transport_label: &str) -> Self {
+		let subscriptions = metrics.methods.as_ref().map(|methods| {
+			ActiveSubscriptions::new(methods.subscriptions.clone(), transport_label)
+		});
 		RpcMiddleware {
 			metrics,
 			transport_label: String::from(transport_label),
+			rate_limiter: None,
+			authenticator: None,
+			limits: Default::default(),
+			in_flight: Default::default(),
+			interceptors: Default::default(),
+			aliases: Default::default(),
+			warnings: Default::default(),
+			subscriptions,
+			method_policy: Default::default(),
+			unrestricted: None,
+			batch_handler: None,
+		}
+	}
+
+	/// Returns a middleware handling the calls the way this one does, for the handler
+	/// of the sequential batches.
+	///
+	/// The requests themselves are limited by this middleware, so they aren't by the copy.
+	pub fn for_calls(&self) -> Self {
+		RpcMiddleware {
+			metrics: self.metrics.clone(),
+			transport_label: self.transport_label.clone(),
+			rate_limiter: None,
+			authenticator: None,
+			limits: Default::default(),
+			in_flight: Default::default(),
+			interceptors: self.interceptors.clone(),
+			aliases: self.aliases.clone(),
+			warnings: self.warnings.clone(),
+			subscriptions: self.subscriptions.clone(),
+			method_policy: self.method_policy.clone(),
+			unrestricted: self.unrestricted.clone(),
+			batch_handler: None,
 		}
 	}
+
+	/// Limit the rate of the requests made over every connection (session) with
+	/// the given limiter, if any, every call of a batch taking a token.
+	///
+	/// Requests made outside of a session are charged to the bucket of their client IP,
+	/// if known, for all of their calls but the first one: the HTTP server takes a token
+	/// for every request before reading it.
+	pub fn with_rate_limiter(mut self, rate_limiter: Option<Arc<RateLimiter>>) -> Self {
+		self.rate_limiter = rate_limiter;
+		self
+	}
+
+	/// Require the given credential to call the unsafe methods.
+	///
+	/// The requests of authenticated clients are passed to the `privileged` handler,
+	/// which should allow the unsafe methods, instead of the handler of this middleware.
+	pub fn with_auth<M: PubSubMetadata + AuthMetadata>(
+		mut self,
+		auth: RpcAuth,
+		privileged: RpcHandler<M>,
+	) -> Self {
+		self.authenticator = Some(Arc::new(Authenticator::new(auth, privileged)));
+		self
+	}
+
+	/// Limit the size of the batches and the number of calls in flight of every client.
+	///
+	/// The calls of batches are only handled one after another if a batch handler is set
+	/// with [`RpcMiddleware::with_batch_handler`].
+	pub fn with_request_limits(mut self, limits: RequestLimits) -> Self {
+		self.limits = limits;
+		self
+	}
+
+	/// Returns `true` if the calls of batches should be handled one after another.
+	pub fn sequential_batches(&self) -> bool {
+		self.limits.sequential_batches
+	}
+
+	/// Pass the calls of sequential batches to the given handler, each of them once the
+	/// previous one completed.
+	///
+	/// The handler should be built with the middleware returned by
+	/// [`RpcMiddleware::for_calls`], to handle the calls the way this middleware does.
+	pub fn with_batch_handler<M: PubSubMetadata + AuthMetadata>(
+		mut self,
+		handler: RpcHandler<M>,
+	) -> Self {
+		self.batch_handler = Some(Arc::new(handler));
+		self
+	}
+
+	/// Pass every call to the given interceptors before handling it.
+	pub fn with_interceptors(mut self, interceptors: RpcInterceptors) -> Self {
+		self.interceptors = interceptors;
+		self
+	}
+
+	/// Expose the methods under the given additional names.
+	///
+	/// The calls of deprecated names are counted in the metrics, and a warning is
+	/// logged about them at most once a minute per name.
+	pub fn with_aliases(mut self, aliases: RpcAliases) -> Self {
+		self.aliases = aliases;
+		self
+	}
+
+	/// Apply the given access rules to the calls.
+	///
+	/// The calls of the methods the policy allows to every client are passed to the
+	/// `unrestricted` handler, if any, which should allow the unsafe methods.
+	pub fn with_method_policy<M: PubSubMetadata + AuthMetadata>(
+		mut self,
+		policy: RpcMethodPolicy,
+		unrestricted: Option<RpcHandler<M>>,
+	) -> Self {
+		self.method_policy = policy;
+		self.unrestricted = unrestricted.map(|handler| Arc::new(handler) as Arc<_>);
+		self
+	}
+
+	/// Returns the additional names of the methods.
+	pub(crate) fn aliases(&self) -> &RpcAliases {
+		&self.aliases
+	}
+
+	/// Returns the metrics of the calls of every method.
+	pub(crate) fn method_metrics(&self) -> Option<&MethodMetrics> {
+		self.metrics.methods.as_ref()
+	}
+
+	/// Report the call if it is made under a deprecated name, returning the warning for
+	/// its response.
+	fn note_deprecated(&self, call: &Call) -> Option<Deprecation> {
+		let method = match call {
+			Call::MethodCall(call) => &call.method,
+			Call::Notification(notification) => &notification.method,
+			Call::Invalid { .. } => return None,
+		};
+		let replacement = self.aliases.replacement(method)?;
+
+		if let Some(ref deprecated_calls) = self.metrics.deprecated_calls {
+			deprecated_calls.with_label_values(&[self.transport_label.as_str(), method]).inc();
+		}
+		match self.warnings.note(method, Instant::now()) {
+			Some(calls) => warn!(
+				"RPC method `{}` is deprecated, use `{}` instead ({} calls since the last warning)",
+				method,
+				replacement,
+				calls,
+			),
+			None => debug!("RPC method `{}` is deprecated, use `{}` instead", method, replacement),
+		}
+		self.aliases.deprecation(method)
+	}
+
+	/// Report the metrics of the call.
+	fn observe<M, F, X>(&self, call: Call, meta: M, next: F) -> Either<FutureOutput, X>
+	where
+		M: PubSubMetadata + AuthMetadata,
+		F: Fn(Call, M) -> X + Send + Sync,
+		X: Future<Item = Option<Output>, Error = ()> + Send + 'static,
+	{
+		let observer = match (&self.metrics.methods, &self.subscriptions) {
+			(Some(methods), Some(subscriptions)) =>
+				CallObserver::new(methods, subscriptions, &call, meta.session()),
+			_ => None,
+		};
+		let observer = match observer {
+			Some(observer) => observer,
+			None => return self.intercept(call, meta, next),
+		};
+
+		let start = Instant::now();
+		Either::A(Box::new(self.intercept(call, meta, next).map(move |output| {
+			observer.on_output(output.as_ref(), start.elapsed());
+			output
+		})))
+	}
+
+	/// Pass the call to the interceptors, if any, before handling it.
+	fn intercept<M, F, X>(&self, call: Call, meta: M, next: F) -> Either<FutureOutput, X>
+	where
+		M: PubSubMetadata + AuthMetadata,
+		F: Fn(Call, M) -> X + Send + Sync,
+		X: Future<Item = Option<Output>, Error = ()> + Send + 'static,
+	{
+		if self.interceptors.is_empty() {
+			return Either::B(next(call, meta));
+		}
+		let rpc_call = match rpc_call(&call, meta.client_ip(), &self.transport_label) {
+			Some(rpc_call) => rpc_call,
+			None => return Either::B(next(call, meta)),
+		};
+
+		if let Err(error) = self.interceptors.on_call(&rpc_call) {
+			let output = match call {
+				Call::MethodCall(call) => Some(Output::from(Err(error), call.id, call.jsonrpc)),
+				_ => None,
+			};
+			self.interceptors.on_response(&rpc_call, output.as_ref(), Duration::default());
+			return Either::A(Box::new(future::ok(output)));
+		}
+
+		let interceptors = self.interceptors.clone();
+		let start = Instant::now();
+		Either::A(Box::new(next(call, meta).map(move |output| {
+			interceptors.on_response(&rpc_call, output.as_ref(), start.elapsed());
+			output
+		})))
+	}
+
+	/// Returns `false` if the client making a request of the given number of calls
+	/// exceeded its rate limit.
+	fn check_rate_limit<M: PubSubMetadata + AuthMetadata>(&self, meta: &M, calls: usize) -> bool {
+		let rate_limiter = match self.rate_limiter.as_ref() {
+			Some(rate_limiter) => rate_limiter,
+			None => return true,
+		};
+		let session = match meta.session() {
+			Some(session) => session,
+			None => {
+				let ip = meta.client_ip().and_then(|ip| ip.parse().ok());
+				return match ip {
+					Some(ip) if calls > 1 => rate_limiter.check_tokens(&ip_bucket(&ip), calls - 1),
+					_ => true,
+				};
+			},
+		};
+
+		let client = session_key(&session);
+		if !rate_limiter.is_tracked(&client) {
+			let rate_limiter = rate_limiter.clone();
+			let client = client.clone();
+			session.on_drop(move || rate_limiter.remove(&client));
+		}
+
+		rate_limiter.check_tokens(&client, calls)
+	}
+
+	/// Note that the given number of calls of the session of the request are in flight,
+	/// failing if that exceeds the limit.
+	fn acquire_in_flight<M: PubSubMetadata>(
+		&self,
+		meta: &M,
+		calls: usize,
+	) -> Result<Option<InFlightGuard>, Error> {
+		match (self.limits.max_in_flight, meta.session()) {
+			(Some(max), Some(session)) => self.in_flight.acquire(session_key(&session), calls, max)
+				.map(Some)
+				.ok_or_else(|| limit_error(format!("More than {} calls in flight", max))),
+			_ => Ok(None),
+		}
+	}
+}
+
+/// Identify the given session.
+pub(crate) fn session_key(session: &Arc<Session>) -> String {
+	format!("session:{:p}", Arc::as_ptr(session))
+}
+
+/// Handle the calls of a batch one after another, every call only being passed to the
+/// handler once the previous one completed.
+fn sequential<M: PubSubMetadata + AuthMetadata>(
+	handler: Arc<RpcHandler<M>>,
+	calls: Vec<Call>,
+	meta: M,
+) -> FutureResponse {
+	let outputs = stream::iter_ok::<_, ()>(calls)
+		.and_then(move |call| handler.handle_call(call, meta.clone()))
+		.filter_map(|output| output)
+		.collect();
+
+	Box::new(outputs.map(|outputs| {
+		if outputs.is_empty() {
+			None
+		} else {
+			Some(Response::Batch(outputs))
+		}
+	}))
+}
+
+/// Reject the given call, without a response to notifications.
+fn reject_call(call: Call, error: Error) -> FutureOutput {
+	let output = match call {
+		Call::MethodCall(call) => Some(Output::from(Err(error), call.id, call.jsonrpc)),
+		_ => None,
+	};
+	Box::new(future::ok(output))
+}
+
+/// Reject every call of the given request, without a response to notifications.
+fn reject(request: Request, error: Error) -> Option<Response> {
+	let output = |call: Call| {
+		let (id, jsonrpc) = match call {
+			Call::MethodCall(call) => (call.id, call.jsonrpc),
+			Call::Notification(_) => return None,
+			Call::Invalid { id } => (id, None),
+		};
+		Some(Output::from(Err(error.clone()), id, jsonrpc))
+	};
+
+	match request {
+		Request::Single(call) => output(call).map(Response::Single),
+		Request::Batch(calls) => {
+			let outputs = calls.into_iter().filter_map(output).collect::<Vec<_>>();
+			if outputs.is_empty() {
+				None
+			} else {
+				Some(Response::Batch(outputs))
+			}
+		},
+	}
 }
 
-impl<M: Metadata> RequestMiddleware<M> for RpcMiddleware {
+impl<M: PubSubMetadata + AuthMetadata> RequestMiddleware<M> for RpcMiddleware {
 	type Future = FutureResponse;
 	type CallFuture = FutureOutput;
 
@@ -83,10 +496,146 @@ impl<M: Metadata> RequestMiddleware<M> for RpcMiddleware {
 		F: Fn(Request, M) -> X + Send + Sync,
 		X: Future<Item = Option<Response>, Error = ()> + Send + 'static,
 	{
+		let access = self.authenticator.as_ref().map(|authenticator| authenticator.access(&meta));
+		if let (Some(Access::Privileged), Some(authenticator)) = (&access, &self.authenticator) {
+			// the privileged handler reports the metrics and limits the rate itself.
+			if let Some(privileged) = authenticator.privileged::<M>() {
+				return Either::A(Box::new(privileged.handle_rpc_request(request, meta)));
+			}
+		}
+
 		if let Some(ref rpc_calls) = self.metrics.rpc_calls {
 			rpc_calls.with_label_values(&[self.transport_label.as_str()]).inc();
 		}
 
-		Either::B(next(request, meta))
+		let calls = match &request {
+			Request::Single(_) => 1,
+			Request::Batch(calls) => calls.len(),
+		};
+		if !self.check_rate_limit(&meta, calls) {
+			let error = Error {
+				code: ErrorCode::ServerError(RATE_LIMITED_ERROR_CODE),
+				message: RATE_LIMITED_MESSAGE.into(),
+				data: None,
+			};
+			return Either::A(Box::new(future::ok(reject(request, error))));
+		}
+
+		if let Some(authenticator) = self.authenticator.as_ref() {
+			if let Some(Access::Denied) = access {
+				let error = auth_error("Invalid credential");
+				return Either::A(Box::new(future::ok(reject(request, error))));
+			}
+			if let Some(response) = authenticator.authenticate(&request, &meta) {
+				return Either::A(Box::new(future::ok(Some(response))));
+			}
+		}
+
+		if let (Request::Batch(_), Some(max)) = (&request, self.limits.max_batch_size) {
+			if calls > max {
+				let error = limit_error(format!("Batch exceeds the limit of {} calls", max));
+				let output = Output::from(Err(error), Id::Null, Some(Version::V2));
+				return Either::A(Box::new(future::ok(Some(Response::Single(output)))));
+			}
+		}
+
+		let guard = match self.acquire_in_flight(&meta, calls) {
+			Ok(guard) => guard,
+			Err(error) => return Either::A(Box::new(future::ok(reject(request, error)))),
+		};
+
+		let batch_handler = self.batch_handler.clone()
+			.filter(|_| self.limits.sequential_batches)
+			.and_then(|handler| handler.downcast::<RpcHandler<M>>().ok());
+		let response: FutureResponse = match (request, batch_handler) {
+			(Request::Batch(calls), Some(handler)) => sequential(handler, calls, meta),
+			(request, _) if guard.is_some() => Box::new(next(request, meta)),
+			(request, _) => return Either::B(next(request, meta)),
+		};
+
+		// the calls are in flight until the response is sent.
+		Either::A(Box::new(response.then(move |response| {
+			drop(guard);
+			response
+		})))
+	}
+
+	fn on_call<F, X>(&self, call: Call, meta: M, next: F) -> Either<FutureOutput, X>
+	where
+		F: Fn(Call, M) -> X + Send + Sync,
+		X: Future<Item = Option<Output>, Error = ()> + Send + 'static,
+	{
+		match self.method_policy.access_to(&call, &self.aliases) {
+			Some(MethodAccess::Allowed) => {
+				let unrestricted = self.unrestricted.as_ref()
+					.and_then(|handler| handler.downcast_ref::<RpcHandler<M>>());
+				if let Some(unrestricted) = unrestricted {
+					return Either::A(Box::new(unrestricted.handle_call(call, meta)));
+				}
+			},
+			Some(MethodAccess::Denied) =>
+				return Either::A(reject_call(call, Error::new(ErrorCode::MethodNotFound))),
+			Some(MethodAccess::AuthRequired) => {
+				let error = auth_error("Method requires authentication");
+				return Either::A(reject_call(call, error));
+			},
+			None => {},
+		}
+
+		let deprecation = match self.note_deprecated(&call) {
+			Some(deprecation) => deprecation,
+			None => return self.observe(call, meta, next),
+		};
+		Either::A(Box::new(self.observe(call, meta, next).map(move |output| {
+			deprecation.attach(output)
+		})))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use jsonrpc_core::{MetaIoHandler, Value};
+	use parking_lot::Mutex;
+	use pubsub::PubSubHandler;
+	use sc_rpc_api::Metadata;
+
+	fn handler(log: Arc<Mutex<Vec<String>>>, middleware: RpcMiddleware) -> RpcHandler<Metadata> {
+		let mut io = PubSubHandler::new(MetaIoHandler::with_middleware(middleware));
+		for name in &["first", "second"] {
+			let log = log.clone();
+			io.add_method(name, move |_| {
+				log.lock().push(format!("{} called", name));
+				let log = log.clone();
+				future::lazy(move || {
+					log.lock().push(format!("{} completed", name));
+					Ok::<_, Error>(Value::Null)
+				})
+			});
+		}
+		io
+	}
+
+	#[test]
+	fn handles_the_calls_of_sequential_batches_one_after_another() {
+		let log = Arc::new(Mutex::new(Vec::new()));
+		let middleware = RpcMiddleware::new(RpcMetrics::new(None).unwrap(), "test")
+			.with_request_limits(RequestLimits { sequential_batches: true, ..Default::default() });
+		let batch_handler = handler(log.clone(), middleware.for_calls());
+		let io = handler(log.clone(), middleware.with_batch_handler(batch_handler));
+
+		let request = r#"[
+			{"jsonrpc":"2.0","method":"first","id":1},
+			{"jsonrpc":"2.0","method":"second","id":2}
+		]"#;
+		let response = concat!(
+			r#"[{"jsonrpc":"2.0","result":null,"id":1},"#,
+			r#"{"jsonrpc":"2.0","result":null,"id":2}]"#,
+		);
+		assert_eq!(io.handle_request_sync(request, Default::default()), Some(response.into()));
+		assert_eq!(
+			*log.lock(),
+			vec!["first called", "first completed", "second called", "second completed"],
+		);
 	}
 }
@@ -0,0 +1,301 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Rate limiting of RPC clients.
+//!
+//! Every client (a WS connection or a client IP) gets a token bucket holding up
+//! to `burst` tokens which is refilled at `requests_per_second`. Each request
+//! consumes a token per call and is rejected if the bucket doesn't hold enough of them.
+//! Requests of clients whose IP is unknown are only limited by the bucket of their
+//! connection, if any.
+
+use std::{collections::HashMap, net::IpAddr, time::Instant};
+
+use parking_lot::Mutex;
+use prometheus_endpoint::{Counter, U64};
+
+use crate::middleware::RpcMetrics;
+
+/// JSON-RPC error code returned to clients exceeding their rate limit.
+pub const RATE_LIMITED_ERROR_CODE: i64 = -32029;
+
+/// Error message returned to clients exceeding their rate limit.
+pub(crate) const RATE_LIMITED_MESSAGE: &str = "Too many requests, rate limit exceeded";
+
+/// Number of tracked clients above which the buckets of idle clients are dropped.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// Rate limit applied to every RPC client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+	/// Sustained number of requests per second a client may make.
+	pub requests_per_second: u32,
+	/// Number of requests a client may make in a burst above the sustained rate.
+	pub burst: u32,
+}
+
+struct Bucket {
+	tokens: f64,
+	updated: Instant,
+}
+
+impl Bucket {
+	fn refill(&mut self, limit: &RateLimit, now: Instant) {
+		let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+		self.tokens = (self.tokens + elapsed * limit.requests_per_second as f64)
+			.min(capacity(limit));
+		self.updated = now;
+	}
+
+	fn is_full(&self, limit: &RateLimit, now: Instant) -> bool {
+		let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+		self.tokens + elapsed * limit.requests_per_second as f64 >= capacity(limit)
+	}
+}
+
+fn capacity(limit: &RateLimit) -> f64 {
+	limit.burst.max(1) as f64
+}
+
+/// Token bucket rate limiter of the clients of a single RPC server.
+pub struct RateLimiter {
+	limit: RateLimit,
+	buckets: Mutex<HashMap<String, Bucket>>,
+	rate_limited: Option<Counter<U64>>,
+}
+
+impl RateLimiter {
+	/// Create a new rate limiter, reporting rejected requests with the given
+	/// transport label.
+	pub fn new(limit: RateLimit, metrics: &RpcMetrics, transport_label: &str) -> Self {
+		RateLimiter {
+			limit,
+			buckets: Mutex::new(HashMap::new()),
+			rate_limited: metrics.rate_limited.as_ref()
+				.map(|counter| counter.with_label_values(&[transport_label])),
+		}
+	}
+
+	/// Take a token from the bucket of the given client, returning `false` if
+	/// the client exceeded its rate limit.
+	pub fn check(&self, client: &str) -> bool {
+		self.check_at(client, 1, Instant::now())
+	}
+
+	/// Take the given number of tokens from the bucket of the given client, e.g. one for
+	/// every call of a batch, returning `false` if the client exceeded its rate limit.
+	///
+	/// No token is taken if the bucket doesn't hold enough of them. Requests of more calls
+	/// than the burst take the whole burst, so they are only accepted from a full bucket.
+	pub fn check_tokens(&self, client: &str, tokens: usize) -> bool {
+		self.check_at(client, tokens, Instant::now())
+	}
+
+	/// Take a token from the bucket of the client with the given IP, returning `false` if
+	/// the client exceeded its rate limit.
+	pub fn check_ip(&self, ip: &IpAddr) -> bool {
+		self.check(&ip_bucket(ip))
+	}
+
+	/// Returns `true` if the given client made a request recently.
+	pub fn is_tracked(&self, client: &str) -> bool {
+		self.buckets.lock().contains_key(client)
+	}
+
+	/// Forget about the given client, e.g. when its connection is closed.
+	pub fn remove(&self, client: &str) {
+		self.buckets.lock().remove(client);
+	}
+
+	fn check_at(&self, client: &str, tokens: usize, now: Instant) -> bool {
+		let limit = &self.limit;
+		let mut buckets = self.buckets.lock();
+
+		if buckets.len() >= MAX_TRACKED_CLIENTS && !buckets.contains_key(client) {
+			// a full bucket is no different from a new one.
+			buckets.retain(|_, bucket| !bucket.is_full(limit, now));
+		}
+
+		let bucket = buckets.entry(client.to_owned())
+			.or_insert_with(|| Bucket { tokens: capacity(limit), updated: now });
+		bucket.refill(limit, now);
+
+		let tokens = (tokens as f64).min(capacity(limit));
+		if bucket.tokens >= tokens {
+			bucket.tokens -= tokens;
+			return true;
+		}
+
+		if let Some(rate_limited) = self.rate_limited.as_ref() {
+			rate_limited.inc();
+		}
+		false
+	}
+}
+
+/// The key of the bucket of the client with the given IP.
+pub(crate) fn ip_bucket(ip: &IpAddr) -> String {
+	format!("ip:{}", ip)
+}
+
+/// Identify the client of an HTTP request or WS handshake.
+///
+/// The client is identified by the address of its `peer`, unless the peer is one of the
+/// `trusted_proxies`: the client is then the last address of the `X-Forwarded-For` header
+/// that isn't a trusted proxy, or the address of the `X-Real-IP` header. Forwarded headers
+/// received from any other peer are ignored.
+///
+/// If the address of the peer is unknown, the headers are only used if trusted proxies are
+/// configured: the operator vouches that the server is only reachable through them.
+pub(crate) fn client_ip<'a>(
+	peer: Option<IpAddr>,
+	trusted_proxies: &[IpAddr],
+	header: impl Fn(&str) -> Option<&'a [u8]>,
+) -> Option<IpAddr> {
+	match peer {
+		Some(peer) if !trusted_proxies.contains(&peer) => return Some(peer),
+		None if trusted_proxies.is_empty() => return None,
+		_ => {},
+	}
+
+	let header = |name| header(name).and_then(|value| std::str::from_utf8(value).ok());
+	let forwarded = header("x-forwarded-for")
+		.and_then(|value| value.rsplit(',')
+			.map(|ip| ip.trim().parse::<IpAddr>().ok())
+			.find(|ip| ip.map_or(true, |ip| !trusted_proxies.contains(&ip)))
+			.flatten()
+		);
+	let real = || header("x-real-ip").and_then(|value| value.trim().parse().ok());
+
+	forwarded.or_else(real).or(peer)
+}
+
+/// The body of a response rejecting a rate limited request.
+pub(crate) fn rate_limited_body() -> String {
+	serde_json::json!({
+		"jsonrpc": "2.0",
+		"error": { "code": RATE_LIMITED_ERROR_CODE, "message": RATE_LIMITED_MESSAGE },
+		"id": null,
+	}).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::Duration;
+
+	fn limiter(requests_per_second: u32, burst: u32) -> RateLimiter {
+		let metrics = RpcMetrics::new(None).unwrap();
+		RateLimiter::new(RateLimit { requests_per_second, burst }, &metrics, "test")
+	}
+
+	#[test]
+	fn rejects_requests_above_the_burst() {
+		let limiter = limiter(10, 3);
+		let now = Instant::now();
+
+		assert!(limiter.check_at("a", 1, now));
+		assert!(limiter.check_at("a", 1, now));
+		assert!(limiter.check_at("a", 1, now));
+		assert!(!limiter.check_at("a", 1, now));
+
+		// other clients have their own bucket.
+		assert!(limiter.check_at("b", 1, now));
+	}
+
+	#[test]
+	fn refills_at_the_sustained_rate() {
+		let limiter = limiter(10, 1);
+		let now = Instant::now();
+
+		assert!(limiter.check_at("a", 1, now));
+		assert!(!limiter.check_at("a", 1, now + Duration::from_millis(50)));
+		assert!(limiter.check_at("a", 1, now + Duration::from_millis(100)));
+		assert!(!limiter.check_at("a", 1, now + Duration::from_millis(100)));
+
+		// tokens don't accumulate above the burst.
+		let later = now + Duration::from_secs(60);
+		assert!(limiter.check_at("a", 1, later));
+		assert!(!limiter.check_at("a", 1, later));
+	}
+
+	#[test]
+	fn charges_every_call_of_a_batch() {
+		let limiter = limiter(10, 3);
+		let now = Instant::now();
+
+		assert!(limiter.check_at("a", 2, now));
+		// a batch exceeding the remaining tokens doesn't consume them.
+		assert!(!limiter.check_at("a", 2, now));
+		assert!(limiter.check_at("a", 1, now));
+		assert!(!limiter.check_at("a", 1, now));
+
+		// batches above the burst take the whole of the full bucket.
+		assert!(limiter.check_at("b", 5, now));
+		assert!(!limiter.check_at("b", 1, now));
+	}
+
+	fn ip(ip: &str) -> IpAddr {
+		ip.parse().unwrap()
+	}
+
+	fn client(
+		peer: Option<&str>,
+		trusted_proxies: &[IpAddr],
+		forwarded: Option<&'static str>,
+		real: Option<&'static str>,
+	) -> Option<String> {
+		client_ip(peer.map(ip), trusted_proxies, move |name| match name {
+			"x-forwarded-for" => forwarded.map(str::as_bytes),
+			"x-real-ip" => real.map(str::as_bytes),
+			_ => None,
+		}).map(|ip| ip.to_string())
+	}
+
+	#[test]
+	fn identifies_clients_by_their_peer_address() {
+		let proxies = [ip("10.0.0.1")];
+
+		assert_eq!(client(Some("1.2.3.4"), &[], None, None).as_deref(), Some("1.2.3.4"));
+		// headers of untrusted peers are ignored.
+		assert_eq!(
+			client(Some("1.2.3.4"), &proxies, Some("5.6.7.8"), Some("5.6.7.8")).as_deref(),
+			Some("1.2.3.4"),
+		);
+		assert_eq!(client(None, &[], Some("5.6.7.8"), Some("5.6.7.8")), None);
+	}
+
+	#[test]
+	fn identifies_clients_by_the_address_forwarded_by_trusted_proxies() {
+		let proxies = [ip("10.0.0.1"), ip("10.0.0.2")];
+
+		// the client can't spoof the addresses appended by the trusted proxies.
+		assert_eq!(
+			client(Some("10.0.0.1"), &proxies, Some("6.6.6.6, 1.2.3.4, 10.0.0.2"), None).as_deref(),
+			Some("1.2.3.4"),
+		);
+		assert_eq!(
+			client(Some("10.0.0.1"), &proxies, None, Some("5.6.7.8")).as_deref(),
+			Some("5.6.7.8"),
+		);
+		assert_eq!(client(None, &proxies, Some("1.2.3.4"), None).as_deref(), Some("1.2.3.4"));
+		// the proxy itself is the client if it doesn't forward an address.
+		assert_eq!(client(Some("10.0.0.1"), &proxies, None, None).as_deref(), Some("10.0.0.1"));
+		assert_eq!(client(None, &proxies, Some("garbage"), None), None);
+	}
+}
@@ -21,7 +21,7 @@
 #[cfg(test)]
 mod tests;
 
-use std::{sync::Arc, convert::TryInto};
+use std::{sync::Arc, convert::TryInto, time::Duration};
 use log::warn;
 
 use sp_blockchain::HeaderBackend;
@@ -79,6 +79,39 @@ impl<P, Client> Author<P, Client> {
 	}
 }
 
+impl<P, Client> Author<P, Client>
+	where
+		P: TransactionPool + Sync + Send + 'static,
+		Client: HeaderBackend<P::Block> + Send + Sync + 'static,
+{
+	/// Submit the given extrinsic from the given source at the best block.
+	fn submit(&self, ext: Bytes, source: TransactionSource) -> FutureResult<TxHash<P>> {
+		let xt = match Decode::decode(&mut &ext[..]) {
+			Ok(xt) => xt,
+			Err(err) => return Box::new(result(Err(err.into()))),
+		};
+		let best_block_hash = self.client.info().best_hash;
+		Box::new(self.pool
+			.submit_one(&generic::BlockId::hash(best_block_hash), source, xt)
+			.compat()
+			.map_err(|e| e.into_pool_error()
+				.map(Into::into)
+				.unwrap_or_else(|e| error::Error::Verification(Box::new(e)).into()))
+		)
+	}
+
+	/// Decode the given extrinsic, if needed, and return its hash.
+	fn extrinsic_hash(&self, bytes_or_hash: hash::ExtrinsicOrHash<TxHash<P>>) -> Result<TxHash<P>> {
+		match bytes_or_hash {
+			hash::ExtrinsicOrHash::Hash(h) => Ok(h),
+			hash::ExtrinsicOrHash::Extrinsic(bytes) => {
+				let xt = Decode::decode(&mut &bytes[..])?;
+				Ok(self.pool.hash_of(&xt))
+			},
+		}
+	}
+}
+
 /// Currently we treat all RPC transactions as externals.
 ///
 /// Possibly in the future we could allow opt-in for special treatment
@@ -139,14 +172,35 @@ impl<P, Client> AuthorApi<TxHash<P>, BlockHash<P>> for Author<P, Client>
 	}
 
 	fn submit_extrinsic(&self, ext: Bytes) -> FutureResult<TxHash<P>> {
+		self.submit(ext, TX_SOURCE)
+	}
+
+	fn submit_local_extrinsic(&self, ext: Bytes) -> FutureResult<TxHash<P>> {
+		if let Err(err) = self.deny_unsafe.check_if_safe() {
+			return Box::new(result(Err(err.into())));
+		}
+
+		self.submit(ext, TransactionSource::Local)
+	}
+
+	fn dry_run_extrinsic(&self, ext: Bytes) -> FutureResult<hash::DryRunResult<TxHash<P>>> {
+		if let Err(err) = self.deny_unsafe.check_if_safe() {
+			return Box::new(result(Err(err.into())));
+		}
+
 		let xt = match Decode::decode(&mut &ext[..]) {
 			Ok(xt) => xt,
 			Err(err) => return Box::new(result(Err(err.into()))),
 		};
 		let best_block_hash = self.client.info().best_hash;
 		Box::new(self.pool
-			.submit_one(&generic::BlockId::hash(best_block_hash), TX_SOURCE, xt)
+			.submit_and_simulate(&generic::BlockId::hash(best_block_hash), xt)
 			.compat()
+			.map(|simulation| hash::DryRunResult {
+				hash: simulation.hash,
+				result: simulation.result.encode().into(),
+				events: simulation.events.into(),
+			})
 			.map_err(|e| e.into_pool_error()
 				.map(Into::into)
 				.unwrap_or_else(|e| error::Error::Verification(Box::new(e)).into()))
@@ -164,13 +218,7 @@ impl<P, Client> AuthorApi<TxHash<P>, BlockHash<P>> for Author<P, Client>
 		self.deny_unsafe.check_if_safe()?;
 
 		let hashes = bytes_or_hash.into_iter()
-			.map(|x| match x {
-				hash::ExtrinsicOrHash::Hash(h) => Ok(h),
-				hash::ExtrinsicOrHash::Extrinsic(bytes) => {
-					let xt = Decode::decode(&mut &bytes[..])?;
-					Ok(self.pool.hash_of(&xt))
-				},
-			})
+			.map(|x| self.extrinsic_hash(x))
 			.collect::<Result<Vec<_>>>()?;
 
 		Ok(
@@ -182,6 +230,37 @@ impl<P, Client> AuthorApi<TxHash<P>, BlockHash<P>> for Author<P, Client>
 		)
 	}
 
+	fn ban_extrinsic(
+		&self,
+		bytes_or_hash: hash::ExtrinsicOrHash<TxHash<P>>,
+		duration: Option<u64>,
+	) -> Result<TxHash<P>> {
+		self.deny_unsafe.check_if_safe()?;
+
+		let hash = self.extrinsic_hash(bytes_or_hash)?;
+		self.pool.ban(&[hash.clone()], duration.map(Duration::from_secs));
+
+		Ok(hash)
+	}
+
+	fn unban_extrinsic(&self, hash: TxHash<P>) -> Result<bool> {
+		self.deny_unsafe.check_if_safe()?;
+
+		Ok(self.pool.unban(&hash))
+	}
+
+	fn ready_extrinsics(&self) -> Result<hash::PoolExtrinsics<TxHash<P>>> {
+		self.deny_unsafe.check_if_safe()?;
+
+		Ok(pool_extrinsics::<P>(self.pool.ready()))
+	}
+
+	fn future_extrinsics(&self) -> Result<hash::PoolExtrinsics<TxHash<P>>> {
+		self.deny_unsafe.check_if_safe()?;
+
+		Ok(pool_extrinsics::<P>(self.pool.futures()))
+	}
+
 	fn watch_extrinsic(&self,
 		_metadata: Self::Metadata,
 		subscriber: Subscriber<TransactionStatus<TxHash<P>, BlockHash<P>>>,
@@ -235,3 +314,34 @@ impl<P, Client> AuthorApi<TxHash<P>, BlockHash<P>> for Author<P, Client>
 		Ok(self.subscriptions.cancel(id))
 	}
 }
+
+/// Collect the RPC representation of the given pool transactions.
+fn pool_extrinsics<P: TransactionPool>(
+	transactions: impl IntoIterator<Item = Arc<P::InPoolTransaction>>,
+) -> hash::PoolExtrinsics<TxHash<P>> {
+	let mut result = hash::PoolExtrinsics {
+		extrinsics: Vec::new(),
+		provided_tags: Default::default(),
+		required_tags: Default::default(),
+	};
+
+	for tx in transactions {
+		for tag in tx.provides() {
+			*result.provided_tags.entry(tag.clone().into()).or_default() += 1;
+		}
+		for tag in tx.requires() {
+			*result.required_tags.entry(tag.clone().into()).or_default() += 1;
+		}
+
+		result.extrinsics.push(hash::PoolExtrinsic {
+			hash: tx.hash().clone(),
+			priority: *tx.priority(),
+			longevity: *tx.longevity(),
+			provides: tx.provides().iter().cloned().map(Into::into).collect(),
+			requires: tx.requires().iter().cloned().map(Into::into).collect(),
+			bytes: tx.data().encode().into(),
+		});
+	}
+
+	result
+}
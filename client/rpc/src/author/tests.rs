@@ -80,12 +80,16 @@ impl Default for TestSetup {
 
 impl TestSetup {
 	fn author(&self) -> Author<FullTransactionPool, Client<Backend>> {
+		self.author_with(DenyUnsafe::No)
+	}
+
+	fn author_with(&self, deny_unsafe: DenyUnsafe) -> Author<FullTransactionPool, Client<Backend>> {
 		Author {
 			client: self.client.clone(),
 			pool: self.pool.clone(),
 			subscriptions: SubscriptionManager::new(Arc::new(crate::testing::TaskExecutor)),
 			keystore: self.keystore.clone(),
-			deny_unsafe: DenyUnsafe::No,
+			deny_unsafe,
 		}
 	}
 }
@@ -189,6 +193,21 @@ fn should_return_watch_validation_error() {
 	assert!(res.is_err(), "Expected the transaction to be rejected as invalid.");
 }
 
+#[test]
+fn should_submit_local_extrinsic_only_if_unsafe_is_allowed() {
+	let setup = TestSetup::default();
+	let xt = uxt(AccountKeyring::Alice, 0).encode();
+
+	assert_matches!(
+		setup.author_with(DenyUnsafe::Yes).submit_local_extrinsic(xt.clone().into()).wait(),
+		Err(_)
+	);
+	assert_eq!(setup.pool.status().ready, 0);
+
+	let hash = setup.author().submit_local_extrinsic(xt.into()).wait().unwrap();
+	assert!(setup.pool.ready_transaction(&hash).unwrap().is_local());
+}
+
 #[test]
 fn should_return_pending_extrinsics() {
 	let p = TestSetup::default().author();
@@ -224,6 +243,77 @@ fn should_remove_extrinsics() {
 	assert_eq!(removed.len(), 3);
 }
 
+#[test]
+fn should_ban_and_unban_extrinsic() {
+	let setup = TestSetup::default();
+	let p = setup.author();
+
+	let ex = uxt(AccountKeyring::Alice, 0);
+	let hash = p.submit_extrinsic(ex.encode().into()).wait().unwrap();
+	assert_eq!(setup.pool.status().ready, 1);
+
+	// ban it and make sure it can't be reimported
+	let banned = p.ban_extrinsic(hash::ExtrinsicOrHash::Extrinsic(ex.encode().into()), Some(60))
+		.unwrap();
+	assert_eq!(banned, hash);
+	assert_eq!(setup.pool.status().ready, 0);
+	assert!(p.submit_extrinsic(ex.encode().into()).wait().is_err());
+
+	// lift the ban and import it again
+	assert_matches!(p.unban_extrinsic(hash), Ok(true));
+	assert_matches!(p.unban_extrinsic(hash), Ok(false));
+	p.submit_extrinsic(ex.encode().into()).wait().unwrap();
+	assert_eq!(setup.pool.status().ready, 1);
+}
+
+#[test]
+fn should_dry_run_extrinsic_without_submitting_it() {
+	use sp_runtime::{
+		ApplyExtrinsicResult,
+		transaction_validity::{InvalidTransaction, TransactionValidityError},
+	};
+
+	let setup = TestSetup::default();
+	let p = setup.author();
+
+	let ex = uxt(AccountKeyring::Alice, 0);
+	let dry_run = p.dry_run_extrinsic(ex.encode().into()).wait().unwrap();
+	assert_eq!(dry_run.hash, setup.pool.hash_of(&ex));
+	let result: ApplyExtrinsicResult = Decode::decode(&mut &dry_run.result[..]).unwrap();
+	assert_eq!(result, Ok(Ok(())));
+	// the test runtime doesn't deposit any event
+	assert!(dry_run.events.is_empty());
+	assert_eq!(setup.pool.status().ready, 0);
+
+	let ex = uxt(AccountKeyring::Alice, 100);
+	let dry_run = p.dry_run_extrinsic(ex.encode().into()).wait().unwrap();
+	let result: ApplyExtrinsicResult = Decode::decode(&mut &dry_run.result[..]).unwrap();
+	assert_eq!(result, Err(TransactionValidityError::Invalid(InvalidTransaction::Stale)));
+}
+
+#[test]
+fn should_return_ready_and_future_extrinsics() {
+	let setup = TestSetup::default();
+	let p = setup.author();
+
+	let ex0 = uxt(AccountKeyring::Alice, 0);
+	let hash0 = p.submit_extrinsic(ex0.encode().into()).wait().unwrap();
+	let ex2 = uxt(AccountKeyring::Alice, 2);
+	let hash2 = p.submit_extrinsic(ex2.encode().into()).wait().unwrap();
+
+	let ready = p.ready_extrinsics().unwrap();
+	assert_eq!(ready.extrinsics.len(), 1);
+	assert_eq!(ready.extrinsics[0].hash, hash0);
+	assert_eq!(ready.extrinsics[0].bytes, Bytes(ex0.encode()));
+	assert_eq!(ready.provided_tags.len(), 1);
+
+	let future = p.future_extrinsics().unwrap();
+	assert_eq!(future.extrinsics.len(), 1);
+	assert_eq!(future.extrinsics[0].hash, hash2);
+	assert_eq!(future.required_tags.len(), 1);
+	assert_eq!(future.required_tags.values().sum::<u32>(), 1);
+}
+
 #[test]
 fn should_insert_key() {
 	let setup = TestSetup::default();
@@ -22,10 +22,18 @@ use std::sync::Arc;
 use rpc::futures::future::result;
 use jsonrpc_pubsub::manager::SubscriptionManager;
 
+use codec::Encode;
 use sc_client_api::{BlockchainEvents, BlockBackend};
-use sp_runtime::{generic::{BlockId, SignedBlock}, traits::{Block as BlockT}};
+use sp_api::{Metadata, ProvideRuntimeApi};
+use sp_runtime::{
+	generic::{BlockId, SignedBlock},
+	traits::{Block as BlockT, Header, Zero},
+};
 
-use super::{ChainBackend, client_err, error::FutureResult};
+use super::{
+	ChainBackend, DecodedBlock, ExtrinsicLocation, TypeAliases, client_err,
+	decode::ExtrinsicDecoder, error::{Error, FutureResult},
+};
 use std::marker::PhantomData;
 use sp_blockchain::HeaderBackend;
 
@@ -52,7 +60,9 @@ impl<Block: BlockT, Client> FullChain<Block, Client> {
 
 impl<Block, Client> ChainBackend<Client, Block> for FullChain<Block, Client> where
 	Block: BlockT + 'static,
-	Client: BlockBackend<Block> + HeaderBackend<Block> + BlockchainEvents<Block> + 'static,
+	Client: BlockBackend<Block> + HeaderBackend<Block> + BlockchainEvents<Block>
+		+ ProvideRuntimeApi<Block> + 'static,
+	Client::Api: Metadata<Block>,
 {
 	fn client(&self) -> &Arc<Client> {
 		&self.client
@@ -77,4 +87,56 @@ impl<Block, Client> ChainBackend<Client, Block> for FullChain<Block, Client> whe
 			.map_err(client_err)
 		))
 	}
+
+	fn block_decoded(
+		&self,
+		hash: Option<Block::Hash>,
+		aliases: &TypeAliases,
+	) -> FutureResult<Option<DecodedBlock<Block::Header>>> {
+		let decode = || {
+			let block = match self.client
+				.block(&BlockId::Hash(self.unwrap_or_best(hash)))
+				.map_err(client_err)?
+			{
+				Some(block) => block.block,
+				None => return Ok(None),
+			};
+			let (header, extrinsics) = block.deconstruct();
+
+			// the extrinsics are executed by the runtime of the parent block, the genesis
+			// block has none.
+			let at = if header.number().is_zero() {
+				header.hash()
+			} else {
+				*header.parent_hash()
+			};
+			let metadata = self.client.runtime_api()
+				.metadata(&BlockId::Hash(at))
+				.map_err(|e| Error::Client(Box::new(e)))?;
+			let decoder = ExtrinsicDecoder::new(&*metadata, aliases.clone())
+				.map_err(Error::Other)?;
+
+			Ok(Some(DecodedBlock {
+				header,
+				extrinsics: extrinsics.iter()
+					.map(|extrinsic| decoder.decode_extrinsic(extrinsic.encode()))
+					.collect(),
+			}))
+		};
+		Box::new(result(decode()))
+	}
+
+	fn transaction(
+		&self,
+		hash: Block::Hash,
+	) -> FutureResult<Option<ExtrinsicLocation<Block::Hash>>> {
+		Box::new(result(self.client
+			.extrinsic_location(&hash)
+			.map(|location| location.map(|(block_hash, index)| ExtrinsicLocation {
+				block_hash,
+				index,
+			}))
+			.map_err(client_err)
+		))
+	}
 }
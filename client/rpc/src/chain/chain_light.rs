@@ -29,7 +29,9 @@ use sp_runtime::{
 	traits::{Block as BlockT},
 };
 
-use super::{ChainBackend, client_err, error::FutureResult};
+use super::{
+	ChainBackend, DecodedBlock, ExtrinsicLocation, TypeAliases, client_err, error::FutureResult,
+};
 use sp_blockchain::HeaderBackend;
 use sc_client_api::BlockchainEvents;
 
@@ -115,4 +117,19 @@ impl<Block, Client, F> ChainBackend<Client, Block> for LightChain<Block, Client,
 
 		Box::new(block)
 	}
+
+	fn block_decoded(
+		&self,
+		_hash: Option<Block::Hash>,
+		_aliases: &TypeAliases,
+	) -> FutureResult<Option<DecodedBlock<Block::Header>>> {
+		Box::new(result(Err(client_err(sp_blockchain::Error::NotAvailableOnLightClient))))
+	}
+
+	fn transaction(
+		&self,
+		_hash: Block::Hash,
+	) -> FutureResult<Option<ExtrinsicLocation<Block::Hash>>> {
+		Box::new(result(Err(client_err(sp_blockchain::Error::NotAvailableOnLightClient))))
+	}
 }
@@ -0,0 +1,642 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Decoding of extrinsics using the runtime metadata.
+//!
+//! The metadata only names the types of the call arguments, so only the well-known
+//! types used by the FRAME pallets are decoded: primitives, compact numbers, accounts,
+//! hashes, balances, fixed-size arrays, vectors, options, tuples and nested calls. The
+//! types of the aliases (e.g. `BlockNumber` or `Balance`) are configured with
+//! [`TypeAliases`], by default the types of the Substrate node. An argument of an
+//! unknown type is left undecoded along with the following ones. Signed extrinsics are
+//! decoded if their address and signature are a `MultiAddress` and a `MultiSignature`
+//! and all their signed extensions are known.
+
+use std::{cell::Cell, collections::HashMap, fs, path::Path};
+
+use codec::{Compact, Decode};
+use frame_metadata::{DecodeDifferent, RuntimeMetadata, RuntimeMetadataPrefixed};
+use serde_json::{json, Value};
+use sp_core::Bytes;
+use sp_runtime::generic::Era;
+
+use super::{DecodedArg, DecodedCall, DecodedExtrinsic, DecodedSignature};
+
+/// Bit of the version byte of signed extrinsics.
+const SIGNED_BIT: u8 = 0b1000_0000;
+
+/// Maximum number of nested types of a value, e.g. through aliases of aliases or the
+/// arguments of nested calls.
+const MAX_TYPE_DEPTH: usize = 32;
+
+/// The types of the aliases of the Substrate node.
+const NODE_TYPE_ALIASES: &[(&str, &str)] = &[
+	("BlockNumber", "u32"),
+	("Index", "u32"),
+	("SessionIndex", "u32"),
+	("Moment", "u64"),
+	("Weight", "u64"),
+	("Balance", "u128"),
+	("BalanceOf", "u128"),
+	("AccountId", "AccountId32"),
+	("Hash", "H256"),
+	("Source", "MultiAddress"),
+	("LookupSource", "MultiAddress"),
+	("Address", "MultiAddress"),
+];
+
+/// The types of the type aliases used by the calls of a runtime, e.g. `u128` for
+/// `Balance`, by alias name.
+///
+/// The default aliases are the ones of the Substrate node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeAliases(HashMap<String, String>);
+
+impl Default for TypeAliases {
+	fn default() -> Self {
+		TypeAliases(NODE_TYPE_ALIASES.iter()
+			.map(|(alias, ty)| (alias.to_string(), ty.to_string()))
+			.collect())
+	}
+}
+
+impl TypeAliases {
+	/// Read the aliases from the JSON file at the given path, overriding the default ones.
+	pub fn load(path: &Path) -> Result<Self, String> {
+		let json = fs::read_to_string(path)
+			.map_err(|e| format!("Failed to read `{}`: {}", path.display(), e))?;
+		Self::from_json(&json)
+	}
+
+	/// Parse the aliases from a JSON object mapping alias names to types, overriding the
+	/// default ones, e.g. `{ "Balance": "u64" }`.
+	pub fn from_json(json: &str) -> Result<Self, String> {
+		let aliases: HashMap<String, String> = serde_json::from_str(json)
+			.map_err(|e| format!("Invalid type aliases: {}", e))?;
+		Ok(aliases.into_iter()
+			.fold(Self::default(), |aliases, (alias, ty)| aliases.with(alias, ty)))
+	}
+
+	/// Set the type of the given alias.
+	pub fn with(mut self, alias: String, ty: String) -> Self {
+		self.0.insert(alias, ty);
+		self
+	}
+}
+
+struct CallMetadata {
+	name: String,
+	/// Names and types of the arguments.
+	args: Vec<(String, String)>,
+}
+
+struct PalletMetadata {
+	name: String,
+	calls: Vec<CallMetadata>,
+}
+
+/// Decodes the extrinsics of the blocks executed by a runtime, using its metadata.
+pub(crate) struct ExtrinsicDecoder {
+	/// The pallets with calls, by index.
+	pallets: HashMap<u8, PalletMetadata>,
+	extrinsic_version: u8,
+	signed_extensions: Vec<String>,
+	aliases: TypeAliases,
+	/// Number of types the value being decoded is nested in, across nested calls.
+	depth: Cell<usize>,
+}
+
+/// Leaves a nested type when dropped.
+struct DepthGuard<'a>(&'a Cell<usize>);
+
+impl<'a> Drop for DepthGuard<'a> {
+	fn drop(&mut self) {
+		self.0.set(self.0.get() - 1);
+	}
+}
+
+fn decoded<B, O: Default>(value: DecodeDifferent<B, O>) -> O {
+	match value {
+		DecodeDifferent::Decoded(value) => value,
+		DecodeDifferent::Encode(_) => O::default(),
+	}
+}
+
+impl ExtrinsicDecoder {
+	/// Create a decoder from the SCALE encoded metadata of a runtime, whose calls use the
+	/// given type aliases.
+	pub(crate) fn new(mut metadata: &[u8], aliases: TypeAliases) -> Result<Self, String> {
+		let metadata = RuntimeMetadataPrefixed::decode(&mut metadata)
+			.map_err(|e| format!("Failed to decode the runtime metadata: {}", e))?;
+		let metadata = match metadata.1 {
+			RuntimeMetadata::V12(metadata) => metadata,
+			_ => return Err("Unsupported runtime metadata version".into()),
+		};
+
+		let pallets = decoded(metadata.modules).into_iter()
+			.map(|module| (module.index, PalletMetadata {
+				name: decoded(module.name),
+				calls: module.calls.map(decoded).unwrap_or_default().into_iter()
+					.map(|call| CallMetadata {
+						name: decoded(call.name),
+						args: decoded(call.arguments).into_iter()
+							.map(|arg| (decoded(arg.name), decoded(arg.ty)))
+							.collect(),
+					})
+					.collect(),
+			}))
+			.collect();
+
+		Ok(ExtrinsicDecoder {
+			pallets,
+			extrinsic_version: metadata.extrinsic.version,
+			signed_extensions: metadata.extrinsic.signed_extensions.into_iter()
+				.map(decoded)
+				.collect(),
+			aliases,
+			depth: Cell::new(0),
+		})
+	}
+
+	/// Decode the given SCALE encoded extrinsic, as much as possible.
+	pub(crate) fn decode_extrinsic(&self, raw: Vec<u8>) -> DecodedExtrinsic {
+		let (signature, call) = self.decode_parts(&raw).unwrap_or_default();
+		DecodedExtrinsic { raw: raw.into(), signature, call }
+	}
+
+	fn decode_parts(&self, raw: &[u8]) -> Option<(Option<DecodedSignature>, Option<DecodedCall>)> {
+		let mut input = raw;
+		let len = Compact::<u32>::decode(&mut input).ok()?.0;
+		if len as usize != input.len() {
+			return None;
+		}
+
+		let version = u8::decode(&mut input).ok()?;
+		if version & !SIGNED_BIT != self.extrinsic_version {
+			return None;
+		}
+
+		let signature = if version & SIGNED_BIT != 0 {
+			Some(self.decode_signature(&mut input)?)
+		} else {
+			None
+		};
+
+		let mut call = self.decode_call(&mut input);
+		if let Some(call) = call.as_mut() {
+			// the arguments don't match their types if anything is left.
+			if call.remainder.is_none() && !input.is_empty() {
+				call.remainder = Some(input.to_vec().into());
+			}
+		}
+
+		Some((signature, call))
+	}
+
+	fn decode_signature(&self, input: &mut &[u8]) -> Option<DecodedSignature> {
+		let address = multi_address(input)?;
+		let signature = match u8::decode(input).ok()? {
+			0 => json!({ "ed25519": fixed(input, 64)? }),
+			1 => json!({ "sr25519": fixed(input, 64)? }),
+			2 => json!({ "ecdsa": fixed(input, 65)? }),
+			_ => return None,
+		};
+		let extensions = self.signed_extensions.iter()
+			.map(|identifier| Some((identifier.clone(), signed_extension(identifier, input)?)))
+			.collect::<Option<_>>()?;
+
+		Some(DecodedSignature { address, signature, extensions })
+	}
+
+	/// Decode a call, leaving the input where the first undecodable argument starts.
+	fn decode_call(&self, input: &mut &[u8]) -> Option<DecodedCall> {
+		let pallet = self.pallets.get(&u8::decode(input).ok()?)?;
+		let call = pallet.calls.get(u8::decode(input).ok()? as usize)?;
+
+		let mut remainder = None;
+		let args = call.args.iter()
+			.map(|(name, ty)| {
+				let value = if remainder.is_none() {
+					let start = *input;
+					let value = self.decode_value(ty, input);
+					if value.is_none() {
+						*input = start;
+						remainder = Some(start.to_vec().into());
+					}
+					value
+				} else {
+					None
+				};
+				DecodedArg { name: name.clone(), ty: ty.clone(), value }
+			})
+			.collect();
+
+		Some(DecodedCall {
+			pallet: pallet.name.clone(),
+			name: call.name.clone(),
+			args,
+			remainder,
+		})
+	}
+
+	/// Decode a value of the type with the given name, `None` if the type is unknown or
+	/// nested in too many other types.
+	fn decode_value(&self, ty: &str, input: &mut &[u8]) -> Option<Value> {
+		let ty = ty.trim();
+		if self.depth.get() >= MAX_TYPE_DEPTH {
+			return None;
+		}
+		self.depth.set(self.depth.get() + 1);
+		let _guard = DepthGuard(&self.depth);
+
+		if let Some(inner) = generic(ty, "Box") {
+			return self.decode_value(inner, input);
+		}
+		if generic(ty, "Compact").is_some() {
+			return Some(number(Compact::<u128>::decode(input).ok()?.0));
+		}
+		if let Some(inner) = generic(ty, "Option") {
+			return match u8::decode(input).ok()? {
+				0 => Some(Value::Null),
+				1 => self.decode_value(inner, input),
+				_ => None,
+			};
+		}
+		if let Some(inner) = generic(ty, "Vec") {
+			if inner.trim() == "u8" {
+				return bytes(input);
+			}
+			let len = Compact::<u32>::decode(input).ok()?.0;
+			return (0..len)
+				.map(|_| self.decode_value(inner, input))
+				.collect::<Option<_>>()
+				.map(Value::Array);
+		}
+		if ty.starts_with('(') && ty.ends_with(')') {
+			return split_top_level(&ty[1..ty.len() - 1], ',').into_iter()
+				.filter(|ty| !ty.is_empty())
+				.map(|ty| self.decode_value(ty, input))
+				.collect::<Option<_>>()
+				.map(Value::Array);
+		}
+		if ty.starts_with('[') && ty.ends_with(']') {
+			let parts = split_top_level(&ty[1..ty.len() - 1], ';');
+			let (inner, len) = match parts[..] {
+				[inner, len] => (inner, len.parse::<usize>().ok()?),
+				_ => return None,
+			};
+			if inner == "u8" {
+				return fixed(input, len);
+			}
+			return (0..len)
+				.map(|_| self.decode_value(inner, input))
+				.collect::<Option<_>>()
+				.map(Value::Array);
+		}
+
+		let name = type_name(ty);
+		if let Some(aliased) = self.aliases.0.get(name) {
+			return self.decode_value(aliased, input);
+		}
+
+		match name {
+			"bool" => bool::decode(input).ok().map(Value::Bool),
+			"u8" | "Percent" => u8::decode(input).ok().map(Into::into),
+			"u16" => u16::decode(input).ok().map(Into::into),
+			"u32" | "Perbill" | "Permill" => u32::decode(input).ok().map(Into::into),
+			"u64" | "Perquintill" => u64::decode(input).ok().map(Into::into),
+			"u128" => u128::decode(input).ok().map(number),
+			"i8" => i8::decode(input).ok().map(Into::into),
+			"i16" => i16::decode(input).ok().map(Into::into),
+			"i32" => i32::decode(input).ok().map(Into::into),
+			"i64" => i64::decode(input).ok().map(Into::into),
+			"i128" => i128::decode(input).ok().map(|n| match n {
+				n if n >= i64::min_value() as i128 && n <= i64::max_value() as i128 =>
+					(n as i64).into(),
+				n => n.to_string().into(),
+			}),
+			"Bytes" => bytes(input),
+			"H160" => fixed(input, 20),
+			"AccountId32" | "H256" => fixed(input, 32),
+			"H512" => fixed(input, 64),
+			"MultiAddress" => multi_address(input),
+			"Call" => self.decode_call(input)
+				.filter(|call| call.remainder.is_none())
+				.and_then(|call| serde_json::to_value(call).ok()),
+			_ => None,
+		}
+	}
+}
+
+/// Decode the data of the signed extension with the given identifier.
+fn signed_extension(identifier: &str, input: &mut &[u8]) -> Option<Value> {
+	match identifier {
+		"CheckSpecVersion" | "CheckTxVersion" | "CheckGenesis" | "CheckWeight" => Some(Value::Null),
+		"CheckMortality" | "CheckEra" => serde_json::to_value(Era::decode(input).ok()?).ok(),
+		"CheckNonce" | "ChargeTransactionPayment" =>
+			Some(number(Compact::<u128>::decode(input).ok()?.0)),
+		_ => None,
+	}
+}
+
+fn multi_address(input: &mut &[u8]) -> Option<Value> {
+	Some(match u8::decode(input).ok()? {
+		0 => json!({ "id": fixed(input, 32)? }),
+		1 => json!({ "index": number(Compact::<u128>::decode(input).ok()?.0) }),
+		2 => json!({ "raw": bytes(input)? }),
+		3 => json!({ "address32": fixed(input, 32)? }),
+		4 => json!({ "address20": fixed(input, 20)? }),
+		_ => return None,
+	})
+}
+
+/// Numbers that don't fit in a JSON number are returned as decimal strings.
+fn number(number: u128) -> Value {
+	if number <= u64::max_value() as u128 {
+		(number as u64).into()
+	} else {
+		number.to_string().into()
+	}
+}
+
+fn bytes(input: &mut &[u8]) -> Option<Value> {
+	serde_json::to_value(Bytes(Vec::<u8>::decode(input).ok()?)).ok()
+}
+
+fn fixed(input: &mut &[u8], len: usize) -> Option<Value> {
+	if input.len() < len {
+		return None;
+	}
+	let (bytes, rest) = input.split_at(len);
+	*input = rest;
+	serde_json::to_value(Bytes(bytes.to_vec())).ok()
+}
+
+/// Returns the parameter of the given generic type, e.g. `T` for `Vec<T>`.
+fn generic<'a>(ty: &'a str, name: &str) -> Option<&'a str> {
+	if ty.len() > name.len() + 1 && ty.starts_with(name) && ty.ends_with('>') {
+		let rest = &ty[name.len()..];
+		if rest.starts_with('<') {
+			return Some(&rest[1..rest.len() - 1]);
+		}
+	}
+	None
+}
+
+/// Returns the name of the type without its path and parameters, e.g. `Source` for
+/// `<T::Lookup as StaticLookup>::Source` or `BalanceOf` for `BalanceOf<T, I>`.
+fn type_name(ty: &str) -> &str {
+	let name = split_top_level(ty, ':').into_iter().last().unwrap_or(ty);
+	name.split('<').next().unwrap_or(name).trim()
+}
+
+/// Split the given string at the separators which aren't nested in brackets.
+fn split_top_level(s: &str, separator: char) -> Vec<&str> {
+	let mut parts = Vec::new();
+	let mut depth = 0i32;
+	let mut start = 0;
+	for (i, c) in s.char_indices() {
+		match c {
+			'<' | '(' | '[' => depth += 1,
+			'>' | ')' | ']' => depth -= 1,
+			c if c == separator && depth == 0 => {
+				parts.push(s[start..i].trim());
+				start = i + c.len_utf8();
+			},
+			_ => {},
+		}
+	}
+	parts.push(s[start..].trim());
+	parts
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+	use frame_metadata::{
+		ExtrinsicMetadata, FunctionArgumentMetadata, FunctionMetadata, ModuleMetadata,
+		RuntimeMetadataV12,
+	};
+
+	fn call(name: &str, args: &[(&str, &str)]) -> FunctionMetadata {
+		FunctionMetadata {
+			name: DecodeDifferent::Decoded(name.into()),
+			arguments: DecodeDifferent::Decoded(args.iter()
+				.map(|(name, ty)| FunctionArgumentMetadata {
+					name: DecodeDifferent::Decoded((*name).into()),
+					ty: DecodeDifferent::Decoded((*ty).into()),
+				})
+				.collect()),
+			documentation: DecodeDifferent::Decoded(Vec::new()),
+		}
+	}
+
+	fn pallet(name: &str, index: u8, calls: Vec<FunctionMetadata>) -> ModuleMetadata {
+		ModuleMetadata {
+			name: DecodeDifferent::Decoded(name.into()),
+			storage: None,
+			calls: Some(DecodeDifferent::Decoded(calls)),
+			event: None,
+			constants: DecodeDifferent::Decoded(Vec::new()),
+			errors: DecodeDifferent::Decoded(Vec::new()),
+			index,
+		}
+	}
+
+	fn decoder() -> ExtrinsicDecoder {
+		decoder_with(TypeAliases::default())
+	}
+
+	fn decoder_with(aliases: TypeAliases) -> ExtrinsicDecoder {
+		let metadata: RuntimeMetadataPrefixed = RuntimeMetadataV12 {
+			modules: DecodeDifferent::Decoded(vec![
+				pallet("Utility", 1, vec![call("batch", &[("calls", "Vec<<T as Config>::Call>")])]),
+				pallet("Balances", 5, vec![
+					call("transfer", &[
+						("dest", "<T::Lookup as StaticLookup>::Source"),
+						("value", "Compact<T::Balance>"),
+					]),
+					call("custom", &[("first", "u32"), ("second", "T::Custom"), ("third", "u8")]),
+				]),
+			]),
+			extrinsic: ExtrinsicMetadata {
+				version: 4,
+				signed_extensions: vec![
+					DecodeDifferent::Decoded("CheckSpecVersion".into()),
+					DecodeDifferent::Decoded("CheckMortality".into()),
+					DecodeDifferent::Decoded("CheckNonce".into()),
+					DecodeDifferent::Decoded("ChargeTransactionPayment".into()),
+				],
+			},
+		}.into();
+		ExtrinsicDecoder::new(&metadata.encode(), aliases).unwrap()
+	}
+
+	fn transfer() -> Vec<u8> {
+		let mut call = vec![5, 0, 0];
+		call.extend_from_slice(&[1; 32]);
+		call.extend(Compact(100u128).encode());
+		call
+	}
+
+	fn arg(name: &str, ty: &str, value: Option<Value>) -> DecodedArg {
+		DecodedArg { name: name.into(), ty: ty.into(), value }
+	}
+
+	fn transfer_call() -> DecodedCall {
+		DecodedCall {
+			pallet: "Balances".into(),
+			name: "transfer".into(),
+			args: vec![
+				arg(
+					"dest",
+					"<T::Lookup as StaticLookup>::Source",
+					Some(json!({ "id": format!("0x{}", "01".repeat(32)) })),
+				),
+				arg("value", "Compact<T::Balance>", Some(json!(100))),
+			],
+			remainder: None,
+		}
+	}
+
+	#[test]
+	fn decodes_unsigned_extrinsic_with_nested_calls() {
+		let mut payload = vec![4, 1, 0];
+		payload.extend(Compact(2u32).encode());
+		payload.extend(transfer());
+		payload.extend(transfer());
+
+		let decoded = decoder().decode_extrinsic(payload.encode());
+		assert_eq!(decoded.signature, None);
+		let call = decoded.call.unwrap();
+		assert_eq!((call.pallet.as_str(), call.name.as_str()), ("Utility", "batch"));
+		assert_eq!(call.remainder, None);
+		let nested = serde_json::to_value(transfer_call()).unwrap();
+		assert_eq!(call.args[0].value, Some(json!([nested, nested])));
+	}
+
+	#[test]
+	fn limits_the_depth_of_nested_calls() {
+		let batch = |calls: Vec<Vec<u8>>| {
+			let mut call = vec![1, 0];
+			call.extend(Compact(calls.len() as u32).encode());
+			calls.into_iter().for_each(|nested| call.extend(nested));
+			call
+		};
+		let nested = |depth| (0..depth).fold(transfer(), |call, _| batch(vec![call]));
+
+		let decoded = decoder().decode_extrinsic([&[4][..], &nested(3)].concat().encode());
+		assert_eq!(decoded.call.unwrap().remainder, None);
+
+		// every nested call counts towards the depth of the outer one.
+		let payload = [&[4][..], &nested(MAX_TYPE_DEPTH)].concat();
+		let call = decoder().decode_extrinsic(payload.encode()).call.unwrap();
+		assert_eq!(call.args[0].value, None);
+		assert_eq!(call.remainder.map(|remainder| remainder.0), Some(payload[3..].to_vec()));
+
+		// the depth is left once a value is decoded, even if that failed.
+		let decoder = decoder();
+		decoder.decode_extrinsic(payload.encode());
+		assert_eq!(decoder.depth.get(), 0);
+	}
+
+	#[test]
+	fn decodes_signed_extrinsic() {
+		let mut payload = vec![0x84, 0];
+		payload.extend_from_slice(&[2; 32]);
+		payload.push(1);
+		payload.extend_from_slice(&[3; 64]);
+		payload.extend(Era::Immortal.encode());
+		payload.extend(Compact(7u32).encode());
+		payload.extend(Compact(0u128).encode());
+		payload.extend(transfer());
+
+		let decoded = decoder().decode_extrinsic(payload.encode());
+		assert_eq!(decoded.call, Some(transfer_call()));
+		let signature = decoded.signature.unwrap();
+		assert_eq!(signature.address, json!({ "id": format!("0x{}", "02".repeat(32)) }));
+		assert_eq!(signature.signature, json!({ "sr25519": format!("0x{}", "03".repeat(64)) }));
+		assert_eq!(
+			signature.extensions,
+			vec![
+				("CheckSpecVersion".into(), Value::Null),
+				("CheckMortality".into(), serde_json::to_value(Era::Immortal).unwrap()),
+				("CheckNonce".into(), json!(7)),
+				("ChargeTransactionPayment".into(), json!(0)),
+			],
+		);
+	}
+
+	#[test]
+	fn leaves_arguments_of_unknown_types_undecoded() {
+		let payload = vec![4, 5, 1, 42, 0, 0, 0, 9, 9];
+
+		let call = decoder().decode_extrinsic(payload.encode()).call.unwrap();
+		assert_eq!(
+			call.args,
+			vec![
+				arg("first", "u32", Some(json!(42))),
+				arg("second", "T::Custom", None),
+				arg("third", "u8", None),
+			],
+		);
+		assert_eq!(call.remainder, Some(vec![9, 9].into()));
+
+		// unknown calls can't be decoded at all.
+		let decoded = decoder().decode_extrinsic(vec![4u8, 5, 2].encode());
+		assert_eq!(decoded.call, None);
+	}
+
+	#[test]
+	fn decodes_arguments_of_configured_aliases() {
+		let aliases = TypeAliases::from_json(r#"{ "Custom": "(u8, Index)" }"#).unwrap();
+		let payload = vec![4, 5, 1, 42, 0, 0, 0, 9, 1, 0, 0, 0, 7];
+
+		let call = decoder_with(aliases).decode_extrinsic(payload.encode()).call.unwrap();
+		assert_eq!(
+			call.args,
+			vec![
+				arg("first", "u32", Some(json!(42))),
+				arg("second", "T::Custom", Some(json!([9, 1]))),
+				arg("third", "u8", Some(json!(7))),
+			],
+		);
+		assert_eq!(call.remainder, None);
+
+		// the aliases of aliases are bounded.
+		let aliases = TypeAliases::from_json(r#"{ "Custom": "Custom" }"#).unwrap();
+		let call = decoder_with(aliases).decode_extrinsic(payload.encode()).call.unwrap();
+		assert_eq!(call.args[1], arg("second", "T::Custom", None));
+		assert!(TypeAliases::from_json(r#"{ "Custom": 1 }"#).is_err());
+	}
+
+	#[test]
+	fn extracts_type_names() {
+		assert_eq!(type_name("<T::Lookup as StaticLookup>::Source"), "Source");
+		assert_eq!(type_name("BalanceOf<T, I>"), "BalanceOf");
+		assert_eq!(type_name("T::AccountId"), "AccountId");
+		assert_eq!(type_name("u32"), "u32");
+		assert_eq!(generic("Vec<(u32, Vec<u8>)>", "Vec"), Some("(u32, Vec<u8>)"));
+		assert_eq!(generic("Vector<u8>", "Vec"), None);
+		assert_eq!(
+			split_top_level("u32, (u8, u16), Vec<u8>", ','),
+			vec!["u32", "(u8, u16)", "Vec<u8>"],
+		);
+	}
+}
@@ -20,6 +20,7 @@
 
 mod chain_full;
 mod chain_light;
+mod decode;
 
 #[cfg(test)]
 mod tests;
@@ -43,8 +44,11 @@ use sp_runtime::{
 use self::error::{Result, Error, FutureResult};
 
 pub use sc_rpc_api::chain::*;
+pub use self::decode::TypeAliases;
+use sp_api::{Metadata, ProvideRuntimeApi};
 use sp_blockchain::HeaderBackend;
 use sc_client_api::BlockBackend;
+use crate::resume::blocks_since;
 
 /// Blockchain backend API
 trait ChainBackend<Client, Block: BlockT>: Send + Sync + 'static
@@ -72,6 +76,20 @@ trait ChainBackend<Client, Block: BlockT>: Send + Sync + 'static
 	/// Get header and body of a relay chain block.
 	fn block(&self, hash: Option<Block::Hash>) -> FutureResult<Option<SignedBlock<Block>>>;
 
+	/// Get header and body of a relay chain block, with the extrinsics decoded using
+	/// the given type aliases.
+	fn block_decoded(
+		&self,
+		hash: Option<Block::Hash>,
+		aliases: &TypeAliases,
+	) -> FutureResult<Option<DecodedBlock<Block::Header>>>;
+
+	/// Get the location of an extrinsic in the best chain.
+	fn transaction(
+		&self,
+		hash: Block::Hash,
+	) -> FutureResult<Option<ExtrinsicLocation<Block::Hash>>>;
+
 	/// Get hash of the n-th block in the canon chain.
 	///
 	/// By default returns latest block hash.
@@ -113,10 +131,18 @@ trait ChainBackend<Client, Block: BlockT>: Send + Sync + 'static
 			self.client(),
 			self.subscriptions(),
 			subscriber,
+			None,
 			|| self.client().info().best_hash,
-			|| self.client().import_notification_stream()
-				.map(|notification| Ok::<_, ()>(notification.header))
-				.compat(),
+			|| {
+				let mut pinned = None;
+				self.client().import_notification_stream()
+					.map(move |notification| {
+						// keeps the latest block sent to the subscriber from being pruned.
+						drop(std::mem::replace(&mut pinned, notification.unpin_handle));
+						Ok::<_, ()>(notification.header)
+					})
+					.compat()
+			},
 		)
 	}
 
@@ -129,21 +155,30 @@ trait ChainBackend<Client, Block: BlockT>: Send + Sync + 'static
 		Ok(self.subscriptions().cancel(id))
 	}
 
-	/// New best head subscription
+	/// New best head subscription, resumed after the given block if any.
 	fn subscribe_new_heads(
 		&self,
 		_metadata: crate::Metadata,
 		subscriber: Subscriber<Block::Header>,
+		resume_from: Option<Block::Hash>,
 	) {
 		subscribe_headers(
 			self.client(),
 			self.subscriptions(),
 			subscriber,
+			resume_from,
 			|| self.client().info().best_hash,
-			|| self.client().import_notification_stream()
-				.filter(|notification| future::ready(notification.is_new_best))
-				.map(|notification| Ok::<_, ()>(notification.header))
-				.compat(),
+			|| {
+				let mut pinned = None;
+				self.client().import_notification_stream()
+					.filter(|notification| future::ready(notification.is_new_best))
+					.map(move |notification| {
+						// keeps the latest block sent to the subscriber from being pruned.
+						drop(std::mem::replace(&mut pinned, notification.unpin_handle));
+						Ok::<_, ()>(notification.header)
+					})
+					.compat()
+			},
 		)
 	}
 
@@ -156,20 +191,29 @@ trait ChainBackend<Client, Block: BlockT>: Send + Sync + 'static
 		Ok(self.subscriptions().cancel(id))
 	}
 
-	/// Finalized head subscription
+	/// Finalized head subscription, resumed after the given block if any.
 	fn subscribe_finalized_heads(
 		&self,
 		_metadata: crate::Metadata,
 		subscriber: Subscriber<Block::Header>,
+		resume_from: Option<Block::Hash>,
 	) {
 		subscribe_headers(
 			self.client(),
 			self.subscriptions(),
 			subscriber,
+			resume_from,
 			|| self.client().info().finalized_hash,
-			|| self.client().finality_notification_stream()
-				.map(|notification| Ok::<_, ()>(notification.header))
-				.compat(),
+			|| {
+				let mut pinned = None;
+				self.client().finality_notification_stream()
+					.map(move |notification| {
+						// keeps the latest block sent to the subscriber from being pruned.
+						drop(std::mem::replace(&mut pinned, notification.unpin_handle));
+						Ok::<_, ()>(notification.header)
+					})
+					.compat()
+			},
 		)
 	}
 
@@ -190,10 +234,13 @@ pub fn new_full<Block: BlockT, Client>(
 ) -> Chain<Block, Client>
 	where
 		Block: BlockT + 'static,
-		Client: BlockBackend<Block> + HeaderBackend<Block> + BlockchainEvents<Block> + 'static,
+		Client: BlockBackend<Block> + HeaderBackend<Block> + BlockchainEvents<Block>
+			+ ProvideRuntimeApi<Block> + 'static,
+		Client::Api: Metadata<Block>,
 {
 	Chain {
 		backend: Box::new(self::chain_full::FullChain::new(client, subscriptions)),
+		type_aliases: Default::default(),
 	}
 }
 
@@ -216,12 +263,23 @@ pub fn new_light<Block: BlockT, Client, F: Fetcher<Block>>(
 			remote_blockchain,
 			fetcher,
 		)),
+		type_aliases: Default::default(),
 	}
 }
 
 /// Chain API with subscriptions support.
 pub struct Chain<Block: BlockT, Client> {
 	backend: Box<dyn ChainBackend<Client, Block>>,
+	type_aliases: TypeAliases,
+}
+
+impl<Block: BlockT, Client> Chain<Block, Client> {
+	/// Decode the extrinsics of `chain_getBlockDecoded` with the given type aliases,
+	/// instead of the ones of the Substrate node.
+	pub fn with_type_aliases(mut self, type_aliases: TypeAliases) -> Self {
+		self.type_aliases = type_aliases;
+		self
+	}
 }
 
 impl<Block, Client> ChainApi<NumberFor<Block>, Block::Hash, Block::Header, SignedBlock<Block>> for
@@ -241,6 +299,20 @@ impl<Block, Client> ChainApi<NumberFor<Block>, Block::Hash, Block::Header, Signe
 		self.backend.block(hash)
 	}
 
+	fn block_decoded(
+		&self,
+		hash: Option<Block::Hash>,
+	) -> FutureResult<Option<DecodedBlock<Block::Header>>> {
+		self.backend.block_decoded(hash, &self.type_aliases)
+	}
+
+	fn transaction(
+		&self,
+		hash: Block::Hash,
+	) -> FutureResult<Option<ExtrinsicLocation<Block::Hash>>> {
+		self.backend.transaction(hash)
+	}
+
 	fn block_hash(
 		&self,
 		number: Option<ListOrValue<NumberOrHex>>,
@@ -268,16 +340,26 @@ impl<Block, Client> ChainApi<NumberFor<Block>, Block::Hash, Block::Header, Signe
 		self.backend.unsubscribe_all_heads(metadata, id)
 	}
 
-	fn subscribe_new_heads(&self, metadata: Self::Metadata, subscriber: Subscriber<Block::Header>) {
-		self.backend.subscribe_new_heads(metadata, subscriber)
+	fn subscribe_new_heads(
+		&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<Block::Header>,
+		resume_from: Option<Block::Hash>,
+	) {
+		self.backend.subscribe_new_heads(metadata, subscriber, resume_from)
 	}
 
 	fn unsubscribe_new_heads(&self, metadata: Option<Self::Metadata>, id: SubscriptionId) -> RpcResult<bool> {
 		self.backend.unsubscribe_new_heads(metadata, id)
 	}
 
-	fn subscribe_finalized_heads(&self, metadata: Self::Metadata, subscriber: Subscriber<Block::Header>) {
-		self.backend.subscribe_finalized_heads(metadata, subscriber)
+	fn subscribe_finalized_heads(
+		&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<Block::Header>,
+		resume_from: Option<Block::Hash>,
+	) {
+		self.backend.subscribe_finalized_heads(metadata, subscriber, resume_from)
 	}
 
 	fn unsubscribe_finalized_heads(&self, metadata: Option<Self::Metadata>, id: SubscriptionId) -> RpcResult<bool> {
@@ -285,11 +367,12 @@ impl<Block, Client> ChainApi<NumberFor<Block>, Block::Hash, Block::Header, Signe
 	}
 }
 
-/// Subscribe to new headers.
+/// Subscribe to new headers, sending the headers since `resume_from` first if given.
 fn subscribe_headers<Block, Client, F, G, S, ERR>(
 	client: &Arc<Client>,
 	subscriptions: &SubscriptionManager,
 	subscriber: Subscriber<Block::Header>,
+	resume_from: Option<Block::Hash>,
 	best_block_hash: G,
 	stream: F,
 ) where
@@ -301,13 +384,22 @@ fn subscribe_headers<Block, Client, F, G, S, ERR>(
 	S: Stream<Item=Block::Header, Error=ERR> + Send + 'static,
 {
 	subscriptions.add(subscriber, |sink| {
-		// send current head right at the start.
-		let header = client.header(BlockId::Hash(best_block_hash()))
-			.map_err(client_err)
-			.and_then(|header| {
-				header.ok_or_else(|| "Best header missing.".to_owned().into())
-			})
-			.map_err(Into::into);
+		// send current head (or the heads since the resumed one) right at the start.
+		let best_block_hash = best_block_hash();
+		let headers = match resume_from {
+			Some(resume_from) => blocks_since(&**client, resume_from, best_block_hash)
+				.map_err(client_err),
+			None => client.header(BlockId::Hash(best_block_hash))
+				.map_err(client_err)
+				.and_then(|header| {
+					header.ok_or_else(|| "Best header missing.".to_owned().into())
+				})
+				.map(|header| vec![header]),
+		};
+		let headers: Vec<std::result::Result<_, rpc::Error>> = match headers {
+			Ok(headers) => headers.into_iter().map(Ok).collect(),
+			Err(error) => vec![Err(error.into())],
+		};
 
 		// send further subscriptions
 		let stream = stream()
@@ -317,7 +409,7 @@ fn subscribe_headers<Block, Client, F, G, S, ERR>(
 		sink
 			.sink_map_err(|e| warn!("Error sending notifications: {:?}", e))
 			.send_all(
-				stream::iter_result(vec![Ok(header)])
+				stream::iter_ok(headers)
 					.chain(stream)
 			)
 			// we ignore the resulting Stream (if the first stream is over we are unsubscribed)
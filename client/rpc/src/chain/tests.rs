@@ -109,6 +109,10 @@ fn should_return_a_block() {
 		api.block(Some(H256::from_low_u64_be(5)).into()).wait(),
 		Ok(None)
 	);
+	assert_matches!(
+		api.block_decoded(Some(H256::from_low_u64_be(5)).into()).wait(),
+		Ok(None)
+	);
 }
 
 #[test]
@@ -220,7 +224,7 @@ fn should_notify_about_best_block() {
 		let mut client = Arc::new(substrate_test_runtime_client::new());
 		let api = new_full(client.clone(), SubscriptionManager::new(Arc::new(TaskExecutor)));
 
-		api.subscribe_new_heads(Default::default(), subscriber);
+		api.subscribe_new_heads(Default::default(), subscriber, None);
 
 		// assert id assigned
 		assert!(matches!(
@@ -250,7 +254,7 @@ fn should_notify_about_finalized_block() {
 		let mut client = Arc::new(substrate_test_runtime_client::new());
 		let api = new_full(client.clone(), SubscriptionManager::new(Arc::new(TaskExecutor)));
 
-		api.subscribe_finalized_heads(Default::default(), subscriber);
+		api.subscribe_finalized_heads(Default::default(), subscriber, None);
 
 		// assert id assigned
 		assert!(matches!(
@@ -272,3 +276,38 @@ fn should_notify_about_finalized_block() {
 	// no more notifications on this channel
 	assert_eq!(executor::block_on(next.into_future().compat()).unwrap().0, None);
 }
+
+#[test]
+fn should_resume_best_block_subscription() {
+	let (subscriber, id, transport) = Subscriber::new_test("test");
+
+	{
+		let mut client = Arc::new(substrate_test_runtime_client::new());
+		let api = new_full(client.clone(), SubscriptionManager::new(Arc::new(TaskExecutor)));
+
+		// the blocks imported while the client was disconnected.
+		for _ in 0..2 {
+			let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+			client.import(BlockOrigin::Own, block).unwrap();
+		}
+
+		api.subscribe_new_heads(Default::default(), subscriber, Some(client.genesis_hash()));
+
+		// assert id assigned
+		assert!(matches!(
+			executor::block_on(id.compat()),
+			Ok(Ok(SubscriptionId::String(_)))
+		));
+	}
+
+	// assert the heads since the resumed one are sent, oldest first.
+	let mut next = transport;
+	for number in &["0x1", "0x2"] {
+		let (notification, rest) = executor::block_on(next.into_future().compat()).unwrap();
+		let notification: serde_json::Value = serde_json::from_str(&notification.unwrap()).unwrap();
+		assert_eq!(notification["params"]["result"]["number"], *number);
+		next = rest;
+	}
+	// no more notifications on this channel
+	assert_eq!(executor::block_on(next.into_future().compat()).unwrap().0, None);
+}
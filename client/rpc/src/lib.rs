@@ -35,6 +35,9 @@ pub mod chain;
 pub mod offchain;
 pub mod state;
 pub mod system;
+mod resume;
+
+pub use resume::MAX_RESUMED_BLOCKS;
 
 #[cfg(any(test, feature = "test-helpers"))]
 pub mod testing;
@@ -0,0 +1,116 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Resumption of the subscriptions of reconnecting clients.
+//!
+//! A client resumes a subscription by passing the hash of the last block it was notified of,
+//! the resume token, when subscribing again. It is then notified of every block since, up to
+//! [`MAX_RESUMED_BLOCKS`], instead of only the current one, so it doesn't miss the blocks
+//! imported while it was disconnected.
+
+use sp_blockchain::{Error as ClientError, HeaderBackend, Result as ClientResult};
+use sp_runtime::{generic::BlockId, traits::{Block as BlockT, Header}};
+
+/// Maximum number of blocks a subscription can be resumed over.
+pub const MAX_RESUMED_BLOCKS: usize = 256;
+
+/// Returns the headers of the blocks after `from` up to and including `to`, oldest first.
+///
+/// If `from` isn't an ancestor of `to`, e.g. it was retracted in a reorg, the headers after
+/// their common ancestor are returned.
+pub(crate) fn blocks_since<Block, Client>(
+	client: &Client,
+	from: Block::Hash,
+	to: Block::Hash,
+) -> ClientResult<Vec<Block::Header>> where
+	Block: BlockT,
+	Client: HeaderBackend<Block>,
+{
+	let header = |hash: Block::Hash| client.header(BlockId::Hash(hash))?
+		.ok_or_else(|| ClientError::UnknownBlock(format!("{}", hash)));
+	let mut from = header(from)?;
+	let mut to = header(to)?;
+
+	let mut headers = Vec::new();
+	let mut steps = 0;
+	while from.hash() != to.hash() {
+		steps += 1;
+		if steps > MAX_RESUMED_BLOCKS {
+			return Err(ClientError::Application(format!(
+				"Cannot resume over more than {} blocks",
+				MAX_RESUMED_BLOCKS,
+			).into()));
+		}
+
+		// the blocks only on the chain of `from` were retracted, they aren't notified.
+		if from.number() >= to.number() {
+			from = header(*from.parent_hash())?;
+		}
+		if to.number() > from.number() {
+			let parent = *to.parent_hash();
+			headers.push(std::mem::replace(&mut to, header(parent)?));
+		}
+	}
+
+	headers.reverse();
+	Ok(headers)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc_block_builder::BlockBuilderProvider;
+	use substrate_test_runtime_client::{
+		prelude::*,
+		runtime::{Block, Transfer},
+		sp_consensus::BlockOrigin,
+	};
+
+	#[test]
+	fn returns_the_blocks_since_the_common_ancestor() {
+		let mut client = substrate_test_runtime_client::new();
+		let genesis = client.genesis_hash();
+
+		let a1 = client.new_block(Default::default()).unwrap().build().unwrap().block;
+		client.import(BlockOrigin::Own, a1.clone()).unwrap();
+		let a2 = client.new_block(Default::default()).unwrap().build().unwrap().block;
+		client.import(BlockOrigin::Own, a2.clone()).unwrap();
+
+		// a fork of `a1`, different thanks to a transfer.
+		let mut builder = client.new_block_at(&BlockId::Hash(genesis), Default::default(), false)
+			.unwrap();
+		builder.push_transfer(Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Ferdie.into(),
+			amount: 42,
+			nonce: 0,
+		}).unwrap();
+		let b1 = builder.build().unwrap().block;
+		client.import(BlockOrigin::Own, b1.clone()).unwrap();
+
+		let hashes = |from, to| blocks_since::<Block, _>(&client, from, to)
+			.unwrap()
+			.iter()
+			.map(|header| header.hash())
+			.collect::<Vec<_>>();
+		assert_eq!(hashes(genesis, a2.hash()), vec![a1.hash(), a2.hash()]);
+		assert_eq!(hashes(a2.hash(), a2.hash()), vec![]);
+		assert_eq!(hashes(a2.hash(), b1.hash()), vec![b1.hash()]);
+		assert_eq!(hashes(b1.hash(), a2.hash()), vec![a1.hash(), a2.hash()]);
+	}
+}
@@ -26,22 +26,31 @@ use log::warn;
 use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId, manager::SubscriptionManager};
 use rpc::{Result as RpcResult, futures::{stream, Future, Sink, Stream, future::result}};
 
-use sc_rpc_api::state::ReadProof;
+use sc_rpc_api::state::{ReadProof, StorageChangesPage};
 use sc_client_api::backend::Backend;
 use sp_blockchain::{Result as ClientResult, Error as ClientError, HeaderMetadata, CachedHeaderMetadata, HeaderBackend};
-use sc_client_api::BlockchainEvents;
+use sc_client_api::{BlockBackend, BlockchainEvents};
+use sc_tracing::block as block_tracing;
+use crate::resume::blocks_since;
 use sp_core::{
 	Bytes, storage::{well_known_keys, StorageKey, StorageData, StorageChangeSet,
 	ChildInfo, ChildType, PrefixedStorageKey},
+	traits::MemoryLimitsExt,
 };
+use sp_externalities::Extensions;
 use sp_version::RuntimeVersion;
+use sp_rpc::tracing::BlockTrace;
 use sp_runtime::{
-	generic::BlockId, traits::{Block as BlockT, NumberFor, SaturatedConversion, CheckedSub},
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT, NumberFor, SaturatedConversion, CheckedSub},
 };
 
-use sp_api::{Metadata, ProvideRuntimeApi, CallApiAt};
+use sp_api::{Core, Metadata, ProvideRuntimeApi, CallApiAt};
 
-use super::{StateBackend, ChildStateBackend, error::{FutureResult, Error, Result}, client_err};
+use super::{
+	StateBackend, ChildStateBackend, error::{FutureResult, Error, Result}, client_err,
+	QUERY_STORAGE_RANGE_MAX_BLOCKS, QUERY_STORAGE_RANGE_MAX_CHANGES,
+};
 use std::marker::PhantomData;
 use sc_client_api::{CallExecutor, StorageProvider, ExecutorProvider, ProofProvider};
 
@@ -78,6 +87,40 @@ impl<BE, Block: BlockT, Client> FullState<BE, Block, Client>
 		Self { client, subscriptions, _phantom: PhantomData }
 	}
 
+	/// Returns the changes of the given keys in every block since the given one, up to the
+	/// best block. The blocks not changing any of the keys are skipped.
+	fn changes_since(
+		&self,
+		from: Block::Hash,
+		keys: &[StorageKey],
+	) -> Result<Vec<StorageChangeSet<Block::Hash>>> {
+		let headers = blocks_since(&*self.client, from, self.client.info().best_hash)
+			.map_err(client_err)?;
+		let read = |block: Block::Hash| keys.iter()
+			.map(|key| self.client.storage(&BlockId::Hash(block), key))
+			.collect::<ClientResult<Vec<_>>>()
+			.map_err(client_err);
+
+		let mut changes = Vec::new();
+		let mut previous = match headers.first() {
+			Some(first) => read(*first.parent_hash())?,
+			None => return Ok(changes),
+		};
+		for header in headers {
+			let block = header.hash();
+			let values = read(block)?;
+			let changed = keys.iter().zip(&values).zip(&previous)
+				.filter(|((_, value), previous)| value != previous)
+				.map(|((key, value), _)| (key.clone(), value.clone()))
+				.collect::<Vec<_>>();
+			if !changed.is_empty() {
+				changes.push(StorageChangeSet { block, changes: changed });
+			}
+			previous = values;
+		}
+		Ok(changes)
+	}
+
 	/// Returns given block hash or best block hash if None is passed.
 	fn block_or_best(&self, hash: Option<Block::Hash>) -> ClientResult<Block::Hash> {
 		Ok(hash.unwrap_or_else(|| self.client.info().best_hash))
@@ -86,10 +129,12 @@ impl<BE, Block: BlockT, Client> FullState<BE, Block, Client>
 	/// Splits the `query_storage` block range into 'filtered' and 'unfiltered' subranges.
 	/// Blocks that contain changes within filtered subrange could be filtered using changes tries.
 	/// Blocks that contain changes within unfiltered subrange must be filtered manually.
+	/// Ranges longer than `max_blocks` are rejected.
 	fn split_query_storage_range(
 		&self,
 		from: Block::Hash,
-		to: Option<Block::Hash>
+		to: Option<Block::Hash>,
+		max_blocks: Option<u32>,
 	) -> Result<QueryStorageRange<Block>> {
 		let to = self.block_or_best(to).map_err(|e| invalid_block::<Block>(from, to, e.to_string()))?;
 
@@ -101,6 +146,14 @@ impl<BE, Block: BlockT, Client> FullState<BE, Block, Client>
 			return Err(invalid_block_range(&from_meta, &to_meta, "from number > to number".to_owned()))
 		}
 
+		if let Some(max_blocks) = max_blocks {
+			let blocks = (to_meta.number - from_meta.number).saturated_into::<u64>() + 1;
+			if blocks > max_blocks as u64 {
+				let details = format!("range exceeds the limit of {} blocks", max_blocks);
+				return Err(invalid_block_range(&from_meta, &to_meta, details))
+			}
+		}
+
 		// check if we can get from `to` to `from` by going through parent_hashes.
 		let from_number = from_meta.number;
 		let hashes = {
@@ -140,14 +193,19 @@ impl<BE, Block: BlockT, Client> FullState<BE, Block, Client>
 	}
 
 	/// Iterates through range.unfiltered_range and check each block for changes of keys' values.
+	/// Stops once `changes` holds `max_changes` change sets.
 	fn query_storage_unfiltered(
 		&self,
 		range: &QueryStorageRange<Block>,
 		keys: &[StorageKey],
 		last_values: &mut HashMap<StorageKey, Option<StorageData>>,
 		changes: &mut Vec<StorageChangeSet<Block::Hash>>,
+		max_changes: Option<usize>,
 	) -> Result<()> {
 		for block in range.unfiltered_range.start..range.unfiltered_range.end {
+			if max_changes.map_or(false, |max| changes.len() >= max) {
+				break;
+			}
 			let block_hash = range.hashes[block].clone();
 			let mut block_changes = StorageChangeSet { block: block_hash.clone(), changes: Vec::new() };
 			let id = BlockId::hash(block_hash);
@@ -172,13 +230,19 @@ impl<BE, Block: BlockT, Client> FullState<BE, Block, Client>
 	}
 
 	/// Iterates through all blocks that are changing keys within range.filtered_range and collects these changes.
+	/// Only the change sets of the first blocks are collected, until `changes` holds `max_changes`.
 	fn query_storage_filtered(
 		&self,
 		range: &QueryStorageRange<Block>,
 		keys: &[StorageKey],
 		last_values: &HashMap<StorageKey, Option<StorageData>>,
 		changes: &mut Vec<StorageChangeSet<Block::Hash>>,
+		max_changes: Option<usize>,
 	) -> Result<()> {
+		let remaining = max_changes.map(|max| max.saturating_sub(changes.len()));
+		if remaining == Some(0) {
+			return Ok(());
+		}
 		let (begin, end) = match range.filtered_range {
 			Some(ref filtered_range) => (
 				range.first_number + filtered_range.start.saturated_into(),
@@ -195,6 +259,11 @@ impl<BE, Block: BlockT, Client> FullState<BE, Block, Client>
 				if last_block == Some(block) {
 					continue;
 				}
+				// the blocks after the last one that is collected are not needed.
+				let is_full = remaining.map_or(false, |remaining| changes_map.len() >= remaining);
+				if is_full && changes_map.keys().next_back().map_or(false, |last| block > *last) {
+					break;
+				}
 
 				let block_hash = range.hashes[(block - range.first_number).saturated_into::<usize>()].clone();
 				let id = BlockId::Hash(block_hash);
@@ -209,6 +278,13 @@ impl<BE, Block: BlockT, Client> FullState<BE, Block, Client>
 				last_block = Some(block);
 				last_value = value_at_block;
 			}
+
+			if let Some(remaining) = remaining {
+				while changes_map.len() > remaining {
+					let last = *changes_map.keys().next_back().expect("the map is not empty; qed");
+					changes_map.remove(&last);
+				}
+			}
 		}
 		if let Some(additional_capacity) = changes_map.len().checked_sub(changes.len()) {
 			changes.reserve(additional_capacity);
@@ -223,7 +299,7 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 	BE: Backend<Block> + 'static,
 	Client: ExecutorProvider<Block> + StorageProvider<Block, BE> + ProofProvider<Block> + HeaderBackend<Block>
 		+ HeaderMetadata<Block, Error = sp_blockchain::Error> + BlockchainEvents<Block>
-		+ CallApiAt<Block> + ProvideRuntimeApi<Block>
+		+ CallApiAt<Block> + ProvideRuntimeApi<Block> + BlockBackend<Block>
 		+ Send + Sync + 'static,
 	Client::Api: Metadata<Block>,
 {
@@ -233,6 +309,10 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 		method: String,
 		call_data: Bytes,
 	) -> FutureResult<Bytes> {
+		let execution_extensions = self.client.execution_extensions();
+		// the calls are limited as the other calls of the runtime.
+		let mut extensions = Extensions::new();
+		extensions.register(MemoryLimitsExt(execution_extensions.memory_limits().other));
 		let r = self.block_or_best(block)
 			.and_then(|block| self
 				.client
@@ -241,8 +321,11 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 					&BlockId::Hash(block),
 					&method,
 					&*call_data,
-					self.client.execution_extensions().strategies().other,
-					None,
+					execution_extensions.strategies().for_method(
+						&method,
+						execution_extensions.strategies().other,
+					),
+					Some(extensions),
 				)
 				.map(Into::into)
 			).map_err(client_err);
@@ -370,16 +453,49 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 		keys: Vec<StorageKey>,
 	) -> FutureResult<Vec<StorageChangeSet<Block::Hash>>> {
 		let call_fn = move || {
-			let range = self.split_query_storage_range(from, to)?;
+			let range = self.split_query_storage_range(from, to, None)?;
 			let mut changes = Vec::new();
 			let mut last_values = HashMap::new();
-			self.query_storage_unfiltered(&range, &keys, &mut last_values, &mut changes)?;
-			self.query_storage_filtered(&range, &keys, &last_values, &mut changes)?;
+			self.query_storage_unfiltered(&range, &keys, &mut last_values, &mut changes, None)?;
+			self.query_storage_filtered(&range, &keys, &last_values, &mut changes, None)?;
 			Ok(changes)
 		};
 		Box::new(result(call_fn()))
 	}
 
+	fn query_storage_range(
+		&self,
+		from: Block::Hash,
+		to: Option<Block::Hash>,
+		keys: Vec<StorageKey>,
+	) -> FutureResult<StorageChangesPage<Block::Hash>> {
+		let call_fn = move || {
+			let range = self.split_query_storage_range(
+				from,
+				to,
+				Some(QUERY_STORAGE_RANGE_MAX_BLOCKS),
+			)?;
+			// one more change set than returned tells where to query the next page from.
+			let max_changes = Some(QUERY_STORAGE_RANGE_MAX_CHANGES + 1);
+			let mut changes = Vec::new();
+			let mut last_values = HashMap::new();
+			self.query_storage_unfiltered(
+				&range,
+				&keys,
+				&mut last_values,
+				&mut changes,
+				max_changes,
+			)?;
+			self.query_storage_filtered(&range, &keys, &last_values, &mut changes, max_changes)?;
+
+			// querying again from the first omitted block returns its complete state first.
+			let next = changes.get(QUERY_STORAGE_RANGE_MAX_CHANGES).map(|changes| changes.block);
+			changes.truncate(QUERY_STORAGE_RANGE_MAX_CHANGES);
+			Ok(StorageChangesPage { changes, next })
+		};
+		Box::new(result(call_fn()))
+	}
+
 	fn query_storage_at(
 		&self,
 		keys: Vec<StorageKey>,
@@ -409,6 +525,36 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 		))
 	}
 
+	fn trace_block(
+		&self,
+		block: Block::Hash,
+		targets: Option<String>,
+	) -> FutureResult<BlockTrace<Block::Hash>> {
+		let trace = || {
+			let id = BlockId::Hash(block);
+			let unknown_block = || client_err(ClientError::UnknownBlock(format!("{}", block)));
+			let mut header = self.client.header(id).map_err(client_err)?
+				.ok_or_else(unknown_block)?;
+			let extrinsics = self.client.block_body(&id).map_err(client_err)?
+				.ok_or_else(unknown_block)?;
+			let parent_hash = *header.parent_hash();
+			// The seals are added by the consensus engines once the block is built and
+			// removed again on import, before the block is executed.
+			header.digest_mut().logs.retain(|item| item.as_seal().is_none());
+
+			let tracing_targets = targets.unwrap_or_else(|| block_tracing::DEFAULT_TARGETS.into());
+			let (executed, spans, events) = block_tracing::trace(&tracing_targets, || {
+				self.client.runtime_api()
+					.execute_block(&BlockId::Hash(parent_hash), Block::new(header, extrinsics))
+			});
+			executed.map_err(|e| client_err(e.into()))?;
+
+			Ok(BlockTrace { block_hash: block, parent_hash, tracing_targets, spans, events })
+		};
+
+		Box::new(result(trace()))
+	}
+
 	fn subscribe_runtime_version(
 		&self,
 		_meta: crate::Metadata,
@@ -473,10 +619,36 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 		_meta: crate::Metadata,
 		subscriber: Subscriber<StorageChangeSet<Block::Hash>>,
 		keys: Option<Vec<StorageKey>>,
+		prefixes: Option<Vec<StorageKey>>,
+		resume_from: Option<Block::Hash>,
 	) {
 		let keys = Into::<Option<Vec<_>>>::into(keys);
+		let prefixes = prefixes.unwrap_or_default();
+		// the changes since the resumed block are only known for the given keys.
+		let resumed = match (resume_from, &keys) {
+			(Some(resume_from), Some(keys)) if prefixes.is_empty() =>
+				self.changes_since(resume_from, keys).map(Some),
+			(Some(_), _) => Err(client_err(ClientError::Application(
+				"Only subscriptions of keys can be resumed".into(),
+			))),
+			(None, _) => Ok(None),
+		};
+		let resumed = match resumed {
+			Ok(resumed) => resumed,
+			Err(err) => {
+				let _ = subscriber.reject(err.into());
+				return;
+			},
+		};
+
+		// the changes of all the keys are filtered below if prefixes are given.
+		let (filter_keys, matching) = if prefixes.is_empty() {
+			(keys.as_ref().map(|x| &**x), None)
+		} else {
+			(None, Some((keys.clone().unwrap_or_default(), prefixes)))
+		};
 		let stream = match self.client.storage_changes_notification_stream(
-			keys.as_ref().map(|x| &**x),
+			filter_keys,
 			None
 		) {
 			Ok(stream) => stream,
@@ -486,9 +658,10 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 			},
 		};
 
-		// initial values
-		let initial = stream::iter_result(keys
-			.map(|keys| {
+		// initial values, or the changes since the resumed block
+		let initial = stream::iter_result(resumed
+			.map(|changes| changes.into_iter().map(|changes| Ok(Ok(changes))).collect())
+			.or_else(|| keys.map(|keys| {
 				let block = self.client.info().best_hash;
 				let changes = keys
 					.into_iter()
@@ -499,17 +672,28 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 					)
 					.collect();
 				vec![Ok(Ok(StorageChangeSet { block, changes }))]
-			}).unwrap_or_default());
+			}))
+			.unwrap_or_default());
 
 		self.subscriptions.add(subscriber, |sink| {
 			let stream = stream
-				.map(|(block, changes)| Ok::<_, ()>(Ok(StorageChangeSet {
-					block,
-					changes: changes.iter()
+				.filter_map(move |(block, changes)| {
+					let changes = changes.iter()
+						.filter(|&(_, k, _)| matching.as_ref().map_or(true, |(keys, prefixes)| {
+							keys.contains(k) ||
+								prefixes.iter().any(|prefix| k.0.starts_with(&prefix.0))
+						}))
 						.filter_map(|(o_sk, k, v)| if o_sk.is_none() {
 							Some((k.clone(),v.cloned()))
-						} else { None }).collect(),
-				})))
+						} else { None }).collect::<Vec<_>>();
+
+					// blocks not changing any key matching the prefixes aren't notified.
+					future::ready(if matching.is_some() && changes.is_empty() {
+						None
+					} else {
+						Some(Ok::<_, ()>(Ok(StorageChangeSet { block, changes })))
+					})
+				})
 				.compat();
 
 			sink
@@ -527,6 +711,81 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 	) -> RpcResult<bool> {
 		Ok(self.subscriptions.cancel(id))
 	}
+
+	fn subscribe_storage_finalized(
+		&self,
+		_meta: crate::Metadata,
+		subscriber: Subscriber<StorageChangeSet<Block::Hash>>,
+		keys: Vec<StorageKey>,
+	) {
+		let client = self.client.clone();
+		let read = move |block: Block::Hash, key: &StorageKey| {
+			client.storage(&BlockId::Hash(block), key).map_err(client_err)
+		};
+		let read_all = move |block, keys: &[StorageKey]| keys.iter()
+			.map(|key| read(block, key))
+			.collect::<Result<Vec<_>>>();
+
+		// initial values
+		let block = self.client.info().finalized_hash;
+		let mut values = match read_all(block, &keys) {
+			Ok(values) => values,
+			Err(err) => {
+				let _ = subscriber.reject(err.into());
+				return;
+			},
+		};
+		let initial = stream::iter_result(vec![Ok(Ok(StorageChangeSet {
+			block,
+			changes: keys.iter().cloned().zip(values.iter().cloned()).collect(),
+		}))]);
+
+		// the changes of the blocks finalized at once are notified together, compared to
+		// the values at the previously finalized block.
+		let stream = self.client.finality_notification_stream()
+			.filter_map(move |notification| {
+				let block = notification.hash;
+				let changes = match read_all(block, &keys) {
+					Ok(new_values) => keys.iter()
+						.zip(values.iter_mut())
+						.zip(new_values)
+						.filter_map(|((key, value), new_value)| if *value != new_value {
+							*value = new_value.clone();
+							Some((key.clone(), new_value))
+						} else {
+							None
+						})
+						.collect::<Vec<_>>(),
+					Err(err) => {
+						warn!("Failed to read the storage of finalized block {}: {}", block, err);
+						Vec::new()
+					},
+				};
+
+				future::ready(if changes.is_empty() {
+					None
+				} else {
+					Some(Ok::<_, ()>(Ok(StorageChangeSet { block, changes })))
+				})
+			})
+			.compat();
+
+		self.subscriptions.add(subscriber, |sink| {
+			sink
+				.sink_map_err(|e| warn!("Error sending notifications: {:?}", e))
+				.send_all(initial.chain(stream))
+				// we ignore the resulting Stream (if the first stream is over we are unsubscribed)
+				.map(|_| ())
+		});
+	}
+
+	fn unsubscribe_storage_finalized(
+		&self,
+		_meta: Option<crate::Metadata>,
+		id: SubscriptionId,
+	) -> RpcResult<bool> {
+		Ok(self.subscriptions.cancel(id))
+	}
 }
 
 impl<BE, Block, Client> ChildStateBackend<Block, Client> for FullState<BE, Block, Client> where
@@ -547,10 +806,7 @@ impl<BE, Block, Client> ChildStateBackend<Block, Client> for FullState<BE, Block
 		Box::new(result(
 			self.block_or_best(block)
 				.and_then(|block| {
-					let child_info = match ChildType::from_prefixed_key(&storage_key) {
-						Some((ChildType::ParentKeyId, storage_key)) => ChildInfo::new_default(storage_key),
-						None => return Err(sp_blockchain::Error::InvalidChildStorageKey),
-					};
+					let child_info = child_info(&storage_key)?;
 					self.client.child_storage_keys(
 						&BlockId::Hash(block),
 						&child_info,
@@ -560,6 +816,29 @@ impl<BE, Block, Client> ChildStateBackend<Block, Client> for FullState<BE, Block
 				.map_err(client_err)))
 	}
 
+	fn storage_keys_paged(
+		&self,
+		block: Option<Block::Hash>,
+		storage_key: PrefixedStorageKey,
+		prefix: Option<StorageKey>,
+		count: u32,
+		start_key: Option<StorageKey>,
+	) -> FutureResult<Vec<StorageKey>> {
+		Box::new(result(
+			self.block_or_best(block)
+				.and_then(|block| {
+					let child_info = child_info(&storage_key)?;
+					self.client.child_storage_keys_iter(
+						&BlockId::Hash(block),
+						child_info,
+						prefix.as_ref(),
+						start_key.as_ref(),
+					)
+				})
+				.map(|v| v.take(count as usize).collect())
+				.map_err(client_err)))
+	}
+
 	fn storage(
 		&self,
 		block: Option<Block::Hash>,
@@ -569,10 +848,7 @@ impl<BE, Block, Client> ChildStateBackend<Block, Client> for FullState<BE, Block
 		Box::new(result(
 			self.block_or_best(block)
 				.and_then(|block| {
-					let child_info = match ChildType::from_prefixed_key(&storage_key) {
-						Some((ChildType::ParentKeyId, storage_key)) => ChildInfo::new_default(storage_key),
-						None => return Err(sp_blockchain::Error::InvalidChildStorageKey),
-					};
+					let child_info = child_info(&storage_key)?;
 					self.client.child_storage(
 						&BlockId::Hash(block),
 						&child_info,
@@ -582,6 +858,24 @@ impl<BE, Block, Client> ChildStateBackend<Block, Client> for FullState<BE, Block
 				.map_err(client_err)))
 	}
 
+	fn storage_entries(
+		&self,
+		block: Option<Block::Hash>,
+		storage_key: PrefixedStorageKey,
+		keys: Vec<StorageKey>,
+	) -> FutureResult<Vec<Option<StorageData>>> {
+		Box::new(result(
+			self.block_or_best(block)
+				.and_then(|block| {
+					let child_info = child_info(&storage_key)?;
+					let block = BlockId::Hash(block);
+					keys.iter()
+						.map(|key| self.client.child_storage(&block, &child_info, key))
+						.collect()
+				})
+				.map_err(client_err)))
+	}
+
 	fn storage_hash(
 		&self,
 		block: Option<Block::Hash>,
@@ -591,10 +885,7 @@ impl<BE, Block, Client> ChildStateBackend<Block, Client> for FullState<BE, Block
 		Box::new(result(
 			self.block_or_best(block)
 				.and_then(|block| {
-					let child_info = match ChildType::from_prefixed_key(&storage_key) {
-						Some((ChildType::ParentKeyId, storage_key)) => ChildInfo::new_default(storage_key),
-						None => return Err(sp_blockchain::Error::InvalidChildStorageKey),
-					};
+					let child_info = child_info(&storage_key)?;
 					self.client.child_storage_hash(
 						&BlockId::Hash(block),
 						&child_info,
@@ -603,6 +894,88 @@ impl<BE, Block, Client> ChildStateBackend<Block, Client> for FullState<BE, Block
 				})
 				.map_err(client_err)))
 	}
+
+	fn subscribe_storage(
+		&self,
+		_meta: crate::Metadata,
+		subscriber: Subscriber<StorageChangeSet<Block::Hash>>,
+		storage_key: PrefixedStorageKey,
+		keys: Option<Vec<StorageKey>>,
+	) {
+		let child_key = match child_info(&storage_key) {
+			Ok(child_info) => StorageKey(child_info.storage_key().to_vec()),
+			Err(err) => {
+				let _ = subscriber.reject(client_err(err).into());
+				return;
+			},
+		};
+
+		let stream = match self.client.storage_changes_notification_stream(
+			Some(&[]),
+			Some(&[(child_key.clone(), keys.clone())]),
+		) {
+			Ok(stream) => stream,
+			Err(err) => {
+				let _ = subscriber.reject(client_err(err).into());
+				return;
+			},
+		};
+
+		// initial values
+		let initial = stream::iter_result(keys
+			.map(|keys| {
+				let block = self.client.info().best_hash;
+				let changes = keys
+					.into_iter()
+					.map(|key| {
+						let value = ChildStateBackend::storage(
+							self,
+							Some(block),
+							storage_key.clone(),
+							key.clone(),
+						);
+						value.map(|val| (key.clone(), val))
+							.wait()
+							.unwrap_or_else(|_| (key, None))
+					})
+					.collect();
+				vec![Ok(Ok(StorageChangeSet { block, changes }))]
+			}).unwrap_or_default());
+
+		self.subscriptions.add(subscriber, |sink| {
+			let stream = stream
+				.map(move |(block, changes)| Ok::<_, ()>(Ok(StorageChangeSet {
+					block,
+					changes: changes.iter()
+						.filter_map(|(o_sk, k, v)| if o_sk == Some(&child_key) {
+							Some((k.clone(), v.cloned()))
+						} else { None }).collect(),
+				})))
+				.compat();
+
+			sink
+				.sink_map_err(|e| warn!("Error sending notifications: {:?}", e))
+				.send_all(initial.chain(stream))
+				// we ignore the resulting Stream (if the first stream is over we are unsubscribed)
+				.map(|_| ())
+		});
+	}
+
+	fn unsubscribe_storage(
+		&self,
+		_meta: Option<crate::Metadata>,
+		id: SubscriptionId,
+	) -> RpcResult<bool> {
+		Ok(self.subscriptions.cancel(id))
+	}
+}
+
+/// Resolve the child trie of the given prefixed storage key.
+fn child_info(storage_key: &PrefixedStorageKey) -> ClientResult<ChildInfo> {
+	match ChildType::from_prefixed_key(storage_key) {
+		Some((ChildType::ParentKeyId, storage_key)) => Ok(ChildInfo::new_default(storage_key)),
+		None => Err(sp_blockchain::Error::InvalidChildStorageKey),
+	}
 }
 
 /// Splits passed range into two subranges where:
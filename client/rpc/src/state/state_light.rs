@@ -40,7 +40,7 @@ use rpc::{
 	futures::stream::Stream,
 };
 
-use sc_rpc_api::state::ReadProof;
+use sc_rpc_api::state::{ReadProof, StorageChangesPage};
 use sp_blockchain::{Error as ClientError, HeaderBackend};
 use sc_client_api::{
 	BlockchainEvents,
@@ -53,6 +53,7 @@ use sp_core::{
 	Bytes, OpaqueMetadata,
 	storage::{StorageKey, PrefixedStorageKey, StorageData, StorageChangeSet},
 };
+use sp_rpc::tracing::BlockTrace;
 use sp_version::RuntimeVersion;
 use sp_runtime::{generic::BlockId, traits::{Block as BlockT, HashFor}};
 
@@ -289,6 +290,15 @@ impl<Block, F, Client> StateBackend<Block, Client> for LightState<Block, F, Clie
 		Box::new(result(Err(client_err(ClientError::NotAvailableOnLightClient))))
 	}
 
+	fn query_storage_range(
+		&self,
+		_from: Block::Hash,
+		_to: Option<Block::Hash>,
+		_keys: Vec<StorageKey>,
+	) -> FutureResult<StorageChangesPage<Block::Hash>> {
+		Box::new(result(Err(client_err(ClientError::NotAvailableOnLightClient))))
+	}
+
 	fn read_proof(
 		&self,
 		_block: Option<Block::Hash>,
@@ -297,12 +307,26 @@ impl<Block, F, Client> StateBackend<Block, Client> for LightState<Block, F, Clie
 		Box::new(result(Err(client_err(ClientError::NotAvailableOnLightClient))))
 	}
 
+	fn trace_block(
+		&self,
+		_block: Block::Hash,
+		_targets: Option<String>,
+	) -> FutureResult<BlockTrace<Block::Hash>> {
+		Box::new(result(Err(client_err(ClientError::NotAvailableOnLightClient))))
+	}
+
 	fn subscribe_storage(
 		&self,
 		_meta: crate::Metadata,
 		subscriber: Subscriber<StorageChangeSet<Block::Hash>>,
-		keys: Option<Vec<StorageKey>>
+		keys: Option<Vec<StorageKey>>,
+		prefixes: Option<Vec<StorageKey>>,
+		resume_from: Option<Block::Hash>,
 	) {
+		if prefixes.map_or(false, |prefixes| !prefixes.is_empty()) || resume_from.is_some() {
+			let _ = subscriber.reject(client_err(ClientError::NotAvailableOnLightClient).into());
+			return;
+		}
 		let keys = match keys {
 			Some(keys) if !keys.is_empty() => keys,
 			_ => {
@@ -420,6 +444,23 @@ impl<Block, F, Client> StateBackend<Block, Client> for LightState<Block, F, Clie
 		Ok(true)
 	}
 
+	fn subscribe_storage_finalized(
+		&self,
+		_meta: crate::Metadata,
+		subscriber: Subscriber<StorageChangeSet<Block::Hash>>,
+		_keys: Vec<StorageKey>,
+	) {
+		let _ = subscriber.reject(client_err(ClientError::NotAvailableOnLightClient).into());
+	}
+
+	fn unsubscribe_storage_finalized(
+		&self,
+		_meta: Option<crate::Metadata>,
+		_id: SubscriptionId,
+	) -> RpcResult<bool> {
+		Ok(false)
+	}
+
 	fn subscribe_runtime_version(
 		&self,
 		_meta: crate::Metadata,
@@ -491,12 +532,33 @@ impl<Block, F, Client> ChildStateBackend<Block, Client> for LightState<Block, F,
 		Box::new(result(Err(client_err(ClientError::NotAvailableOnLightClient))))
 	}
 
+	fn storage_keys_paged(
+		&self,
+		_block: Option<Block::Hash>,
+		_storage_key: PrefixedStorageKey,
+		_prefix: Option<StorageKey>,
+		_count: u32,
+		_start_key: Option<StorageKey>,
+	) -> FutureResult<Vec<StorageKey>> {
+		Box::new(result(Err(client_err(ClientError::NotAvailableOnLightClient))))
+	}
+
 	fn storage(
 		&self,
 		block: Option<Block::Hash>,
 		storage_key: PrefixedStorageKey,
 		key: StorageKey,
 	) -> FutureResult<Option<StorageData>> {
+		Box::new(ChildStateBackend::storage_entries(self, block, storage_key, vec![key])
+			.map(|mut entries| entries.pop().flatten()))
+	}
+
+	fn storage_entries(
+		&self,
+		block: Option<Block::Hash>,
+		storage_key: PrefixedStorageKey,
+		keys: Vec<StorageKey>,
+	) -> FutureResult<Vec<Option<StorageData>>> {
 		let block = self.block_or_best(block);
 		let fetcher = self.fetcher.clone();
 		let child_storage = resolve_header(&*self.remote_blockchain, &*self.fetcher, block)
@@ -505,13 +567,19 @@ impl<Block, F, Client> ChildStateBackend<Block, Client> for LightState<Block, F,
 					block,
 					header,
 					storage_key,
-					keys: vec![key.0.clone()],
+					keys: keys.iter().map(|key| key.0.clone()).collect(),
 					retry_count: Default::default(),
 				}).then(move |result| ready(result
-					.map(|mut data| data
-						.remove(&key.0)
-						.expect("successful result has entry for all keys; qed")
-						.map(StorageData)
+					.map(|data| keys
+						.iter()
+						// the keys may be requested more than once.
+						.map(|key| data
+							.get(&key.0)
+							.expect("successful result has entry for all keys; qed")
+							.clone()
+							.map(StorageData)
+						)
+						.collect()
 					)
 					.map_err(client_err)
 				))),
@@ -533,6 +601,24 @@ impl<Block, F, Client> ChildStateBackend<Block, Client> for LightState<Block, F,
 			)
 		)
 	}
+
+	fn subscribe_storage(
+		&self,
+		_meta: crate::Metadata,
+		subscriber: Subscriber<StorageChangeSet<Block::Hash>>,
+		_storage_key: PrefixedStorageKey,
+		_keys: Option<Vec<StorageKey>>,
+	) {
+		let _ = subscriber.reject(client_err(ClientError::NotAvailableOnLightClient).into());
+	}
+
+	fn unsubscribe_storage(
+		&self,
+		_meta: Option<crate::Metadata>,
+		_id: SubscriptionId,
+	) -> RpcResult<bool> {
+		Ok(false)
+	}
 }
 
 /// Resolve header by hash.
@@ -25,6 +25,7 @@ use assert_matches::assert_matches;
 use futures01::stream::Stream;
 use sp_core::{storage::ChildInfo, ChangesTrieConfiguration};
 use sp_core::hash::H256;
+use sp_core::hexdisplay::HexDisplay;
 use sc_block_builder::BlockBuilderProvider;
 use sp_io::hashing::blake2_256;
 use substrate_test_runtime_client::{
@@ -136,6 +137,87 @@ fn should_return_child_storage() {
 	);
 }
 
+#[test]
+fn should_return_child_storage_keys_paged_and_entries() {
+	let child_info = ChildInfo::new_default(STORAGE_KEY);
+	let client = Arc::new(substrate_test_runtime_client::TestClientBuilder::new()
+		.add_child_storage(&child_info, "key1", vec![1_u8])
+		.add_child_storage(&child_info, "key2", vec![2_u8])
+		.add_child_storage(&child_info, "other", vec![3_u8])
+		.build());
+	let genesis_hash = client.genesis_hash();
+	let (_client, child) = new_full(
+		client,
+		SubscriptionManager::new(Arc::new(TaskExecutor)),
+		DenyUnsafe::No,
+	);
+	let child_key = prefixed_storage_key();
+
+	assert_eq!(
+		child.storage_keys_paged(
+			child_key.clone(),
+			Some(StorageKey(b"key".to_vec())),
+			10,
+			None,
+			Some(genesis_hash),
+		).wait().unwrap(),
+		vec![StorageKey(b"key1".to_vec()), StorageKey(b"key2".to_vec())],
+	);
+	assert_eq!(
+		child.storage_keys_paged(
+			child_key.clone(),
+			None,
+			1,
+			Some(StorageKey(b"key1".to_vec())),
+			None,
+		).wait().unwrap(),
+		vec![StorageKey(b"key2".to_vec())],
+	);
+	assert_matches!(
+		child.storage_keys_paged(child_key.clone(), None, 1001, None, None).wait(),
+		Err(Error::InvalidCount { value: 1001, max: 1000 })
+	);
+
+	assert_eq!(
+		child.storage_entries(
+			child_key,
+			vec![StorageKey(b"key2".to_vec()), StorageKey(b"missing".to_vec())],
+			Some(genesis_hash),
+		).wait().unwrap(),
+		vec![Some(StorageData(vec![2])), None],
+	);
+}
+
+#[test]
+fn should_notify_on_child_storage_subscription() {
+	let child_info = ChildInfo::new_default(STORAGE_KEY);
+	let client = Arc::new(substrate_test_runtime_client::TestClientBuilder::new()
+		.add_child_storage(&child_info, "key", vec![42_u8])
+		.build());
+	let (subscriber, id, transport) = Subscriber::new_test("test");
+
+	{
+		let (_api, child) = new_full(
+			client,
+			SubscriptionManager::new(Arc::new(TaskExecutor)),
+			DenyUnsafe::No,
+		);
+		child.subscribe_storage(
+			Default::default(),
+			subscriber,
+			prefixed_storage_key(),
+			Some(vec![StorageKey(b"key".to_vec())]),
+		);
+
+		// assert id assigned
+		assert!(matches!(executor::block_on(id.compat()), Ok(Ok(SubscriptionId::String(_)))));
+	}
+
+	// the initial value is sent right away
+	let (notification, _next) = executor::block_on(transport.into_future().compat()).unwrap();
+	assert!(notification.is_some());
+}
+
 #[test]
 fn should_call_contract() {
 	let client = Arc::new(substrate_test_runtime_client::new());
@@ -164,7 +246,7 @@ fn should_notify_about_storage_changes() {
 			DenyUnsafe::No,
 		);
 
-		api.subscribe_storage(Default::default(), subscriber, None.into());
+		api.subscribe_storage(Default::default(), subscriber, None.into(), None, None);
 
 		// assert id assigned
 		assert!(matches!(
@@ -206,7 +288,7 @@ fn should_send_initial_storage_changes_and_notifications() {
 
 		api.subscribe_storage(Default::default(), subscriber, Some(vec![
 			StorageKey(alice_balance_key.to_vec()),
-		]).into());
+		]).into(), None, None);
 
 		// assert id assigned
 		assert!(matches!(
@@ -235,6 +317,152 @@ fn should_send_initial_storage_changes_and_notifications() {
 	assert_eq!(executor::block_on(next.into_future().compat()).unwrap().0, None);
 }
 
+#[test]
+fn should_resume_storage_subscription() {
+	let (subscriber, id, transport) = Subscriber::new_test("test");
+
+	let changing = {
+		let mut client = Arc::new(substrate_test_runtime_client::new());
+		let (api, _child) = new_full(
+			client.clone(),
+			SubscriptionManager::new(Arc::new(TaskExecutor)),
+			DenyUnsafe::No,
+		);
+
+		// a block changing the balance of Alice and one not changing it, imported while
+		// the client was disconnected.
+		let mut builder = client.new_block(Default::default()).unwrap();
+		builder.push_transfer(runtime::Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Ferdie.into(),
+			amount: 42,
+			nonce: 0,
+		}).unwrap();
+		let block = builder.build().unwrap().block;
+		let changing = block.hash();
+		client.import(BlockOrigin::Own, block).unwrap();
+		let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+		client.import(BlockOrigin::Own, block).unwrap();
+
+		let alice_balance_key = blake2_256(&runtime::system::balance_of_key(AccountKeyring::Alice.into()));
+
+		api.subscribe_storage(Default::default(), subscriber, Some(vec![
+			StorageKey(alice_balance_key.to_vec()),
+		]), None, Some(client.genesis_hash()));
+
+		// assert id assigned
+		assert!(matches!(
+			executor::block_on(id.compat()),
+			Ok(Ok(SubscriptionId::String(_)))
+		));
+		changing
+	};
+
+	// assert only the changes since the resumed block are sent.
+	let (notification, next) = executor::block_on(transport.into_future().compat()).unwrap();
+	let notification: serde_json::Value = serde_json::from_str(&notification.unwrap()).unwrap();
+	assert_eq!(
+		notification["params"]["result"]["block"],
+		serde_json::to_value(changing).unwrap(),
+	);
+	// no more notifications on this channel
+	assert_eq!(executor::block_on(next.into_future().compat()).unwrap().0, None);
+}
+
+#[test]
+fn should_notify_about_storage_changes_by_prefix() {
+	let (subscriber, id, transport) = Subscriber::new_test("test");
+	let alice_balance_key = blake2_256(&runtime::system::balance_of_key(AccountKeyring::Alice.into()));
+
+	{
+		let mut client = Arc::new(substrate_test_runtime_client::new());
+		let (api, _child) = new_full(
+			client.clone(),
+			SubscriptionManager::new(Arc::new(TaskExecutor)),
+			DenyUnsafe::No,
+		);
+
+		api.subscribe_storage(Default::default(), subscriber, None, Some(vec![
+			StorageKey(alice_balance_key[..16].to_vec()),
+		]), None);
+
+		// assert id assigned
+		assert!(matches!(
+			executor::block_on(id.compat()),
+			Ok(Ok(SubscriptionId::String(_)))
+		));
+
+		// the first block changes the balance of Alice, the second one doesn't.
+		let mut builder = client.new_block(Default::default()).unwrap();
+		builder.push_transfer(runtime::Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Ferdie.into(),
+			amount: 42,
+			nonce: 0,
+		}).unwrap();
+		let block = builder.build().unwrap().block;
+		client.import(BlockOrigin::Own, block).unwrap();
+		let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+		client.import(BlockOrigin::Own, block).unwrap();
+	}
+
+	// assert notification of the first block sent to transport
+	let (notification, next) = executor::block_on(transport.into_future().compat()).unwrap();
+	let alice_balance_key = format!("0x{}", HexDisplay::from(&alice_balance_key.to_vec()));
+	assert!(notification.unwrap().contains(&alice_balance_key));
+	// no more notifications on this channel
+	assert_eq!(executor::block_on(next.into_future().compat()).unwrap().0, None);
+}
+
+#[test]
+fn should_notify_about_finalized_storage_changes() {
+	let (subscriber, id, transport) = Subscriber::new_test("test");
+
+	{
+		let mut client = Arc::new(substrate_test_runtime_client::new());
+		let (api, _child) = new_full(
+			client.clone(),
+			SubscriptionManager::new(Arc::new(TaskExecutor)),
+			DenyUnsafe::No,
+		);
+
+		let alice_balance_key = blake2_256(&runtime::system::balance_of_key(AccountKeyring::Alice.into()));
+
+		api.subscribe_storage_finalized(Default::default(), subscriber, vec![
+			StorageKey(alice_balance_key.to_vec()),
+		]);
+
+		// assert id assigned
+		assert!(matches!(
+			executor::block_on(id.compat()),
+			Ok(Ok(SubscriptionId::String(_)))
+		));
+
+		// the balance of Alice changes in the first block, not in the second one.
+		let mut builder = client.new_block(Default::default()).unwrap();
+		builder.push_transfer(runtime::Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Ferdie.into(),
+			amount: 42,
+			nonce: 0,
+		}).unwrap();
+		let block = builder.build().unwrap().block;
+		client.import(BlockOrigin::Own, block).unwrap();
+		let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+		client.import(BlockOrigin::Own, block).unwrap();
+		client.finalize_block(BlockId::number(2), None).unwrap();
+	}
+
+	// assert initial values sent to transport
+	let (notification, next) = executor::block_on(transport.into_future().compat()).unwrap();
+	assert!(notification.is_some());
+	// assert the finalized change sent to transport
+	let (notification, next) = executor::block_on(next.into_future().compat()).unwrap();
+	assert!(notification.is_some());
+	// no more notifications on this channel
+	assert_eq!(executor::block_on(next.into_future().compat()).unwrap().0, None);
+}
+
 #[test]
 fn should_query_storage() {
 	fn run_tests(mut client: Arc<TestClient>, has_changes_trie_config: bool) {
@@ -329,6 +557,30 @@ fn should_query_storage() {
 
 		assert_eq!(result.wait().unwrap(), expected);
 
+		// Query all changes with the limited range query.
+		let result = api.query_storage_range(
+			keys.clone(),
+			genesis_hash,
+			None,
+		);
+
+		assert_eq!(
+			result.wait().unwrap(),
+			StorageChangesPage { changes: expected, next: None },
+		);
+
+		// Too many keys for the limited range query.
+		let result = api.query_storage_range(
+			(0..=1000).map(|k: u32| StorageKey(k.to_le_bytes().to_vec())).collect(),
+			genesis_hash,
+			None,
+		);
+
+		assert_matches!(
+			result.wait(),
+			Err(Error::InvalidCount { value: 1001, max: 1000 })
+		);
+
 		// Inverted range.
 		let result = api.query_storage(
 			keys.clone(),
@@ -480,6 +732,73 @@ fn should_return_runtime_version() {
 	assert_eq!(deserialized, runtime_version);
 }
 
+#[test]
+fn should_trace_block() {
+	let mut client = Arc::new(substrate_test_runtime_client::new());
+	let (api, _child) = new_full(
+		client.clone(),
+		SubscriptionManager::new(Arc::new(TaskExecutor)),
+		DenyUnsafe::No,
+	);
+
+	let mut builder = client.new_block(Default::default()).unwrap();
+	builder.push_transfer(runtime::Transfer {
+		from: AccountKeyring::Alice.into(),
+		to: AccountKeyring::Ferdie.into(),
+		amount: 42,
+		nonce: 0,
+	}).unwrap();
+	let block = builder.build().unwrap().block;
+	let block_hash = block.hash();
+	client.import(BlockOrigin::Own, block).unwrap();
+
+	let trace = api.trace_block(block_hash, None).wait().unwrap();
+	assert_eq!(trace.block_hash, block_hash);
+	assert_eq!(trace.parent_hash, client.genesis_hash());
+	assert_eq!(trace.tracing_targets, "pallet,frame,state");
+
+	// the test runtime is executed natively, entering a span on every host function call.
+	let trace = api.trace_block(block_hash, Some("sp_io::storage".into())).wait().unwrap();
+	assert!(trace.spans.iter().any(|span| span.name == "get"), "{:?}", trace.spans);
+	assert!(trace.spans.iter().all(|span| span.target == "sp_io::storage" && !span.wasm));
+	assert!(trace.events.is_empty());
+
+	assert_matches!(
+		api.trace_block(H256::repeat_byte(1), None).wait(),
+		Err(Error::Client(_))
+	);
+}
+
+#[test]
+fn should_deny_tracing_blocks_if_unsafe() {
+	let client = Arc::new(substrate_test_runtime_client::new());
+	let (api, _child) = new_full(
+		client.clone(),
+		SubscriptionManager::new(Arc::new(TaskExecutor)),
+		DenyUnsafe::Yes,
+	);
+
+	assert_matches!(
+		api.trace_block(client.genesis_hash(), None).wait(),
+		Err(Error::UnsafeRpcCalled(_))
+	);
+}
+
+#[test]
+fn should_deny_querying_storage_ranges_if_unsafe() {
+	let client = Arc::new(substrate_test_runtime_client::new());
+	let (api, _child) = new_full(
+		client.clone(),
+		SubscriptionManager::new(Arc::new(TaskExecutor)),
+		DenyUnsafe::Yes,
+	);
+
+	assert_matches!(
+		api.query_storage_range(vec![StorageKey(vec![1])], client.genesis_hash(), None).wait(),
+		Err(Error::UnsafeRpcCalled(_))
+	);
+}
+
 #[test]
 fn should_notify_on_runtime_version_initially() {
 	let (subscriber, id, transport) = Subscriber::new_test("test");
@@ -27,6 +27,7 @@ use sc_rpc_api::{DenyUnsafe, Receiver};
 use sc_tracing::logging;
 use sp_utils::mpsc::TracingUnboundedSender;
 use sp_runtime::traits::{self, Header as HeaderT};
+use std::sync::Arc;
 
 use self::error::Result;
 
@@ -42,11 +43,16 @@ macro_rules! bail_if_unsafe {
 	};
 }
 
+/// Writes a snapshot of the database, returning the name of its directory.
+pub type DatabaseSnapshot =
+	Arc<dyn Fn() -> BoxFuture<'static, std::result::Result<String, String>> + Send + Sync>;
+
 /// System API implementation
 pub struct System<B: traits::Block> {
 	info: SystemInfo,
 	send_back: TracingUnboundedSender<Request<B>>,
 	deny_unsafe: DenyUnsafe,
+	database_snapshot: Option<DatabaseSnapshot>,
 }
 
 /// Request to be processed.
@@ -86,8 +92,15 @@ impl<B: traits::Block> System<B> {
 			info,
 			send_back,
 			deny_unsafe,
+			database_snapshot: None,
 		}
 	}
+
+	/// Serve `system_snapshotDatabase` with the given snapshot function.
+	pub fn with_database_snapshot(mut self, database_snapshot: DatabaseSnapshot) -> Self {
+		self.database_snapshot = Some(database_snapshot);
+		self
+	}
 }
 
 impl<B: traits::Block> SystemApi<B::Hash, <B::Header as HeaderT>::Number> for System<B> {
@@ -209,4 +222,20 @@ impl<B: traits::Block> SystemApi<B::Hash, <B::Header as HeaderT>::Number> for Sy
 		self.deny_unsafe.check_if_safe()?;
 		logging::reset_log_filter().map_err(|_e| rpc::Error::internal_error())
 	}
+
+	fn system_snapshot_database(&self)
+		-> Compat<BoxFuture<'static, std::result::Result<String, rpc::Error>>>
+	{
+		bail_if_unsafe!(self.deny_unsafe);
+
+		let snapshot = match &self.database_snapshot {
+			Some(database_snapshot) => database_snapshot(),
+			None => async {
+				Err("No snapshot directory is set for the node".to_owned())
+			}.boxed(),
+		};
+		async move {
+			snapshot.await.map_err(|e| rpc::Error::from(error::Error::DatabaseSnapshot(e)))
+		}.boxed().compat()
+	}
 }
@@ -337,6 +337,20 @@ fn system_network_remove_reserved() {
 	assert!(runtime.block_on(bad_fut).is_err());
 }
 
+#[test]
+fn system_snapshot_database() {
+	let mut runtime = tokio::runtime::current_thread::Runtime::new().unwrap();
+	let snapshot: DatabaseSnapshot = Arc::new(|| async { Ok("snapshot-1".into()) }.boxed());
+	let failing: DatabaseSnapshot = Arc::new(|| async { Err("Disk full".into()) }.boxed());
+
+	let unsupported_fut = api(None).system_snapshot_database();
+	let good_fut = api(None).with_database_snapshot(snapshot).system_snapshot_database();
+	let bad_fut = api(None).with_database_snapshot(failing).system_snapshot_database();
+	assert!(runtime.block_on(unsupported_fut).is_err());
+	assert_eq!(runtime.block_on(good_fut), Ok("snapshot-1".to_owned()));
+	assert!(runtime.block_on(bad_fut).is_err());
+}
+
 #[test]
 fn test_add_reset_log_filter() {
 	const EXPECTED_BEFORE_ADD: &'static str = "EXPECTED_BEFORE_ADD";
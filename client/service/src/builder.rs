@@ -19,9 +19,10 @@
 use crate::{
 	error::Error, MallocSizeOfWasm, RpcHandlers, NetworkStatusSinks,
 	start_rpc_servers, build_network_future, TransactionPoolAdapter, TaskManager, SpawnTaskHandle,
+	TaskGroup, BuilderHooks, HookRpcExtensions,
 	metrics::MetricsService,
 	client::{light, Client, ClientConfig},
-	config::{Configuration, KeystoreConfig, PrometheusConfig},
+	config::{Configuration, DatabasePart, KeystoreConfig, PrometheusConfig, TransactionPoolOptions},
 };
 use sc_client_api::{
 	light::RemoteBlockchain, ForkBlocks, BadBlocks, UsageProvider, ExecutorProvider,
@@ -46,11 +47,11 @@ use sc_network::block_request_handler::{self, BlockRequestHandler};
 use sc_network::light_client_requests::{self, handler::LightClientRequestHandler};
 use sp_runtime::generic::BlockId;
 use sp_runtime::traits::{
-	Block as BlockT, HashFor, Zero, BlockIdTo,
+	Block as BlockT, Header as HeaderT, HashFor, Zero, One, BlockIdTo,
 };
 use sp_api::{ProvideRuntimeApi, CallApiAt};
 use sc_executor::{NativeExecutor, NativeExecutionDispatch, RuntimeInfo};
-use std::sync::Arc;
+use std::{path::Path, sync::{Arc, atomic::{AtomicU64, Ordering}}};
 use wasm_timer::SystemTime;
 use sc_telemetry::{
 	telemetry,
@@ -67,7 +68,7 @@ use sp_core::traits::{
 	SpawnNamed,
 };
 use sp_keystore::{CryptoStore, SyncCryptoStore, SyncCryptoStorePtr};
-use sp_runtime::BuildStorage;
+use sp_runtime::{BuildStorage, Storage};
 use sc_client_api::{
 	BlockBackend, BlockchainEvents,
 	backend::StorageProvider,
@@ -288,6 +289,297 @@ impl KeystoreContainer {
 	}
 }
 
+/// Returns the options of the transaction pool of the given configuration, with the senders
+/// of the transactions extracted from FRAME's signed extrinsics, whose signer is encoded as
+/// `Address`.
+///
+/// The per-sender count and kilobytes limits of the pool are only enforced on the transactions
+/// whose sender is known, so the options should be used to create the pool of any FRAME based
+/// chain that configures them.
+pub fn new_transaction_pool_options<Address>(config: &Configuration) -> TransactionPoolOptions
+where
+	Address: codec::Decode + codec::Encode + 'static,
+{
+	let mut options = config.transaction_pool.clone();
+	if options.per_sender.is_some() && options.sender_extractor.is_none() {
+		options.sender_extractor = Some(Arc::new(
+			sc_transaction_pool::SignedExtrinsicSender::<Address>::default(),
+		));
+	}
+	options
+}
+
+/// Creates the registry of inherent data providers of a service, with the additional
+/// providers of the configured [`InherentDataProvidersHooks`](crate::InherentDataProvidersHooks)
+/// already registered.
+///
+/// The consensus specific providers (e.g. timestamp or slot) are registered by the
+/// import queue and the authoring worker once the registry is handed to them.
+pub fn new_inherent_data_providers(
+	config: &Configuration,
+) -> Result<sp_inherents::InherentDataProviders, Error> {
+	let inherent_data_providers = sp_inherents::InherentDataProviders::new();
+
+	config.inherent_data_providers.register(&inherent_data_providers)
+		.map_err(|e| Error::Other(e.into_string()))?;
+
+	Ok(inherent_data_providers)
+}
+
+/// Number of blocks the state of non-canonical blocks is kept for, by default.
+pub const CANONICALIZATION_DELAY: u64 = 4096;
+
+/// Number of stored blocks compressed at once, in between the imports.
+const COMPRESSION_BATCH: u32 = 256;
+
+/// Pause in between the batches of stored blocks compressed.
+const COMPRESSION_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Number of blocks moved to the cold database at once, in between the imports.
+const COLD_STORAGE_BATCH: u32 = 256;
+
+/// Pause in between the batches of blocks moved to the cold database.
+const COLD_STORAGE_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Pause once the blocks old enough are moved to the cold database, until more are finalized.
+const COLD_STORAGE_IDLE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+fn database_settings(config: &Configuration) -> DatabaseSettings {
+	DatabaseSettings {
+		state_cache_size: config.state_cache_size,
+		state_cache_child_ratio: config.state_cache_child_ratio.map(|v| (v, 100)),
+		trie_cache_size: config.trie_cache_size,
+		state_pruning: config.state_pruning.clone(),
+		source: config.database.clone(),
+		keep_blocks: config.keep_blocks.clone(),
+		transaction_storage: config.transaction_storage.clone(),
+		index_extrinsics: config.index_extrinsics,
+		compress_blocks: config.compress_blocks,
+		trie_cache_warm_up: config.trie_cache_warm_up,
+		cold_storage: config.cold_storage.clone(),
+		max_canonicalization_delay: config.max_canonicalization_delay,
+	}
+}
+
+fn canonicalization_delay(config: &Configuration) -> u64 {
+	config.canonicalization_delay.unwrap_or(CANONICALIZATION_DELAY)
+}
+
+/// Apply the pending upgrades and migrations of the database of the node, and close it.
+///
+/// The database is migrated when the node starts anyway, this allows to migrate it ahead of
+/// time, e.g. while the previous version of the node keeps running on a copy.
+pub fn migrate_database<TBl: BlockT>(config: &Configuration) -> Result<(), Error> {
+	let db_type = match config.role {
+		Role::Light => sc_client_db::DatabaseType::Light,
+		_ => sc_client_db::DatabaseType::Full,
+	};
+	sc_client_db::migrate_database::<TBl>(&database_settings(config), db_type)?;
+	Ok(())
+}
+
+/// Write a snapshot of the database of the full node to the directory at `path`.
+///
+/// The database is only read, through a secondary instance, so the node may keep running
+/// meanwhile. Running nodes can also take snapshots with the `system_snapshotDatabase` RPC.
+pub fn snapshot_database<TBl: BlockT>(config: &Configuration, path: &Path) -> Result<(), Error> {
+	if let Role::Light = config.role {
+		return Err(Error::Other("Snapshots of light client databases are not supported".into()));
+	}
+	sc_client_db::snapshot_database(&database_settings(config), path)?;
+	Ok(())
+}
+
+/// Restore the snapshot at `snapshot` as the database of the full node, which must be empty.
+///
+/// The snapshot is rejected unless it starts with the genesis block of the chain of `config`.
+pub fn restore_database<TBl: BlockT>(config: &Configuration, snapshot: &Path) -> Result<(), Error> {
+	if let Role::Light = config.role {
+		return Err(Error::Other("Snapshots of light client databases are not supported".into()));
+	}
+	let genesis_storage = config.chain_spec.as_storage_builder().build_storage()
+		.map_err(sp_blockchain::Error::Storage)?;
+	let state = sp_state_machine::InMemoryBackend::<HashFor<TBl>>::from(genesis_storage);
+	let genesis_block = crate::client::genesis::construct_genesis_block::<TBl>(
+		(*state.root()).into(),
+	);
+	sc_client_db::restore_snapshot::<TBl>(
+		snapshot,
+		&database_settings(config),
+		genesis_block.header().hash(),
+	)?;
+	Ok(())
+}
+
+/// Seed the empty database of the full node with the given state, read in chunks, as the genesis
+/// state of a new chain. Returns the hash of the genesis block of the new chain.
+pub fn import_state<TBl: BlockT>(
+	config: &Configuration,
+	chunks: impl IntoIterator<Item = Result<Storage, Error>>,
+) -> Result<TBl::Hash, Error> {
+	if let Role::Light = config.role {
+		return Err(Error::Other("The state can't be imported into light client databases".into()));
+	}
+	let backend = Backend::<TBl>::new(database_settings(config), canonicalization_delay(config))?;
+	let chunks = chunks.into_iter()
+		.map(|chunk| chunk.map_err(|e| sp_blockchain::Error::Storage(e.to_string())));
+	let genesis_hash = backend.import_genesis_state(chunks, |state_root| {
+		crate::client::genesis::construct_genesis_block::<TBl>(state_root).deconstruct().0
+	})?;
+	Ok(genesis_hash)
+}
+
+/// Remove a part of the database of the full node, keeping the rest of it.
+pub fn purge_database<TBl: BlockT>(
+	config: &Configuration,
+	part: DatabasePart,
+) -> Result<(), Error> {
+	if let Role::Light = config.role {
+		return Err(Error::Other("Light client databases can't be purged partially".into()));
+	}
+	sc_client_db::purge_database::<TBl>(&database_settings(config), part)?;
+	Ok(())
+}
+
+/// Check the database of the full node for missing or corrupt entries.
+///
+/// With `repair`, the chain is reverted to the last block before the first inconsistent one,
+/// even if it was finalized, so the node imports the blocks past it again once it syncs.
+pub fn check_database<TBl: BlockT>(
+	config: &Configuration,
+	repair: bool,
+) -> Result<crate::chain_ops::DatabaseReport<TBl>, Error> {
+	if let Role::Light = config.role {
+		return Err(Error::Other("Checks of light client databases are not supported".into()));
+	}
+	let backend = Backend::<TBl>::new(database_settings(config), canonicalization_delay(config))?;
+	let mut report = crate::chain_ops::check_database(&backend, config.keep_blocks.clone())?;
+
+	if let (true, Some(first)) = (repair, report.first_inconsistent_block()) {
+		if first.is_zero() {
+			return Err(Error::Other("Can't repair an inconsistent genesis block".into()));
+		}
+		let best = sc_client_api::backend::Backend::blockchain(&backend).info().best_number;
+		let blocks = best - first + One::one();
+		let (reverted, _) = sc_client_api::backend::Backend::revert(&backend, blocks, true)?;
+		info!("Reverted {} of {} blocks. Best: #{}", reverted, blocks, best - reverted);
+		report.reverted = Some((blocks, reverted));
+	}
+	Ok(report)
+}
+
+/// Report the storage used by the state of the full node at the given block, the best one by
+/// default, by key prefix.
+///
+/// The given named prefixes are reported along with the ones of the FRAME pallets.
+pub fn storage_report<TBl: BlockT>(
+	config: &Configuration,
+	block: Option<BlockId<TBl>>,
+	prefixes: &[(Vec<u8>, String)],
+) -> Result<crate::chain_ops::StorageReport, Error> {
+	if let Role::Light = config.role {
+		return Err(Error::Other("Storage reports of light clients are not supported".into()));
+	}
+	let backend = Backend::<TBl>::new(database_settings(config), canonicalization_delay(config))?;
+	let block = match block {
+		Some(block) => block,
+		None => BlockId::Hash(
+			sc_client_api::backend::Backend::blockchain(&backend).info().best_hash,
+		),
+	};
+	crate::chain_ops::storage_report(&backend, block, prefixes)
+}
+
+/// Measure the latencies of the reads and writes of the state of the full node at the given
+/// block, the best one by default.
+///
+/// The state is benchmarked on a snapshot of the database, taken next to it so it is stored on
+/// the same disk, and removed once done. So the database, which must be a RocksDB database, is
+/// left as it is. The caches of the state should be disabled, to measure the latencies of the
+/// database.
+pub fn benchmark_storage<TBl: BlockT>(
+	config: &Configuration,
+	block: Option<BlockId<TBl>>,
+	params: crate::chain_ops::StorageBenchmarkParams,
+) -> Result<crate::chain_ops::StorageBenchmark, Error> {
+	if let Role::Light = config.role {
+		return Err(Error::Other("Storage benchmarks of light clients are not supported".into()));
+	}
+	let settings = database_settings(config);
+	let copy = match settings.source.path() {
+		Some(path) => path.with_extension("benchmark"),
+		None => return Err(Error::Other("The database must be stored on the disk".into())),
+	};
+	if copy.exists() {
+		std::fs::remove_dir_all(&copy).map_err(|e| Error::Other(e.to_string()))?;
+	}
+	info!("Copying the database to {}", copy.display());
+	sc_client_db::snapshot_database(&settings, &copy)?;
+
+	let benchmark = sc_client_db::snapshot_settings(settings, &copy)
+		.and_then(|settings| Backend::<TBl>::new(settings, canonicalization_delay(config)))
+		.map_err(Error::from)
+		.and_then(|backend| {
+			let block = match block {
+				Some(block) => block,
+				None => BlockId::Hash(
+					sc_client_api::backend::Backend::blockchain(&backend).info().best_hash,
+				),
+			};
+			crate::chain_ops::benchmark_storage(&backend, block, params)
+		});
+	if let Err(e) = std::fs::remove_dir_all(&copy) {
+		warn!("Failed to remove the copy of the database at {}: {}", copy.display(), e);
+	}
+	benchmark
+}
+
+/// Read the state of the full node at the given block, the best one by default.
+///
+/// Returns the hash of the block along with its state.
+pub fn export_state<TBl: BlockT>(
+	config: &Configuration,
+	block: Option<BlockId<TBl>>,
+) -> Result<(TBl::Hash, sp_core::storage::Storage), Error> {
+	if let Role::Light = config.role {
+		return Err(Error::Other("The state of light clients can't be exported".into()));
+	}
+	let backend = Backend::<TBl>::new(database_settings(config), canonicalization_delay(config))?;
+	let blockchain = sc_client_api::backend::Backend::blockchain(&backend);
+	let hash = match block {
+		Some(block) => blockchain.expect_block_hash_from_id(&block)?,
+		None => blockchain.info().best_hash,
+	};
+	let storage = crate::chain_ops::export_backend_state(&backend, BlockId::Hash(hash))?;
+	Ok((hash, storage))
+}
+
+/// Creates the executor of the runtimes for the given config.
+fn new_native_executor<TExecDisp>(
+	config: &Configuration,
+) -> Result<NativeExecutor<TExecDisp>, Error> where
+	TExecDisp: NativeExecutionDispatch + 'static,
+{
+	let registry = config.prometheus_config.as_ref().map(|config| &config.registry);
+	// the compiled runtimes are cached by hash of their code, so they are shared by the chains.
+	let cache_path = config.base_path.as_ref().map(|base_path| base_path.path().join("cache"));
+	NativeExecutor::<TExecDisp>::new(
+		config.wasm_method,
+		config.default_heap_pages,
+		config.max_runtime_instances,
+	)
+		.with_preinstantiated_instances(config.preinstantiated_runtime_instances)
+		.with_cache_path(cache_path)
+		.with_runtime_cache_limits(config.runtime_cache_size, config.runtime_cache_max_code_size)
+		.with_max_stack_height(config.chain_spec.wasm_max_stack_height())
+		.with_wasm_features(config.wasm_features)
+		.map_err(|e| Error::Other(format!("Failed to enable the WebAssembly proposals: {}", e)))?
+		.with_host_function_sets(&config.host_functions)
+		.map_err(|e| Error::Other(format!("Failed to register the host functions: {}", e)))?
+		.with_prometheus_registry(registry)
+		.map_err(|e| Error::Other(format!("Failed to register the executor metrics: {}", e)))
+}
+
 /// Creates a new full client for the given config.
 pub fn new_full_client<TBl, TRtApi, TExecDisp>(
 	config: &Configuration,
@@ -312,11 +604,7 @@ pub fn new_full_parts<TBl, TRtApi, TExecDisp>(
 		TaskManager::new(config.task_executor.clone(), registry)?
 	};
 
-	let executor = NativeExecutor::<TExecDisp>::new(
-		config.wasm_method,
-		config.default_heap_pages,
-		config.max_runtime_instances,
-	);
+	let executor = new_native_executor::<TExecDisp>(config)?;
 
 	let chain_spec = &config.chain_spec;
 	let fork_blocks = get_extension::<ForkBlocks<TBl>>(chain_spec.extensions())
@@ -328,23 +616,16 @@ pub fn new_full_parts<TBl, TRtApi, TExecDisp>(
 		.unwrap_or_default();
 
 	let (client, backend) = {
-		let db_config = sc_client_db::DatabaseSettings {
-			state_cache_size: config.state_cache_size,
-			state_cache_child_ratio:
-			config.state_cache_child_ratio.map(|v| (v, 100)),
-			state_pruning: config.state_pruning.clone(),
-			source: config.database.clone(),
-			keep_blocks: config.keep_blocks.clone(),
-			transaction_storage: config.transaction_storage.clone(),
-		};
+		let db_config = database_settings(config);
 
 		let extensions = sc_client_api::execution_extensions::ExecutionExtensions::new(
 			config.execution_strategies.clone(),
 			Some(keystore_container.sync_keystore()),
-		);
+		).with_memory_limits(config.execution_memory_limits);
 
 		new_client(
 			db_config,
+			canonicalization_delay(config),
 			executor,
 			chain_spec.as_storage_builder(),
 			fork_blocks,
@@ -360,6 +641,38 @@ pub fn new_full_parts<TBl, TRtApi, TExecDisp>(
 		)?
 	};
 
+	if config.compress_blocks {
+		let backend = backend.clone();
+		task_manager.spawn_handle().spawn_blocking("db-compression", async move {
+			loop {
+				match backend.compress_stored_blocks(COMPRESSION_BATCH) {
+					Ok(true) => futures_timer::Delay::new(COMPRESSION_INTERVAL).await,
+					Ok(false) => break,
+					Err(e) => {
+						warn!("Failed to compress the stored blocks: {:?}", e);
+						break;
+					},
+				}
+			}
+		});
+	}
+
+	if config.cold_storage.is_some() {
+		let backend = backend.clone();
+		task_manager.spawn_handle().spawn_blocking("db-cold-storage", async move {
+			loop {
+				match backend.migrate_cold_blocks(COLD_STORAGE_BATCH) {
+					Ok(true) => futures_timer::Delay::new(COLD_STORAGE_INTERVAL).await,
+					Ok(false) => futures_timer::Delay::new(COLD_STORAGE_IDLE_INTERVAL).await,
+					Err(e) => {
+						warn!("Failed to move the ancient blocks to the cold database: {:?}", e);
+						break;
+					},
+				}
+			}
+		});
+	}
+
 	Ok((
 		client,
 		backend,
@@ -381,24 +694,9 @@ pub fn new_light_parts<TBl, TRtApi, TExecDisp>(
 		TaskManager::new(config.task_executor.clone(), registry)?
 	};
 
-	let executor = NativeExecutor::<TExecDisp>::new(
-		config.wasm_method,
-		config.default_heap_pages,
-		config.max_runtime_instances,
-	);
+	let executor = new_native_executor::<TExecDisp>(config)?;
 
-	let db_storage = {
-		let db_settings = sc_client_db::DatabaseSettings {
-			state_cache_size: config.state_cache_size,
-			state_cache_child_ratio:
-				config.state_cache_child_ratio.map(|v| (v, 100)),
-			state_pruning: config.state_pruning.clone(),
-			source: config.database.clone(),
-			keep_blocks: config.keep_blocks.clone(),
-			transaction_storage: config.transaction_storage.clone(),
-		};
-		sc_client_db::light::LightStorage::new(db_settings)?
-	};
+	let db_storage = sc_client_db::light::LightStorage::new(database_settings(config))?;
 	let light_blockchain = sc_light::new_light_blockchain(db_storage);
 	let fetch_checker = Arc::new(
 		sc_light::new_fetch_checker::<_, TBl, _>(
@@ -421,8 +719,12 @@ pub fn new_light_parts<TBl, TRtApi, TExecDisp>(
 }
 
 /// Create an instance of db-backed client.
+///
+/// The blocks not finalized yet are canonicalized `canonicalization_delay` blocks behind the best
+/// block, or more if the database settings adapt the delay to the finality lag.
 pub fn new_client<E, Block, RA>(
 	settings: DatabaseSettings,
+	canonicalization_delay: u64,
 	executor: E,
 	genesis_storage: &dyn BuildStorage,
 	fork_blocks: ForkBlocks<Block>,
@@ -446,9 +748,11 @@ pub fn new_client<E, Block, RA>(
 		Block: BlockT,
 		E: CodeExecutor + RuntimeInfo,
 {
-	const CANONICALIZATION_DELAY: u64 = 4096;
-
-	let backend = Arc::new(Backend::new(settings, CANONICALIZATION_DELAY)?);
+	let backend = Arc::new(Backend::new_with_registry(
+		settings,
+		canonicalization_delay,
+		prometheus_registry.as_ref(),
+	)?);
 	let executor = crate::client::LocalCallExecutor::new(backend.clone(), executor, spawn_handle, config.clone())?;
 	Ok((
 		crate::client::Client::new(
@@ -484,6 +788,9 @@ pub struct SpawnTasksParams<'a, TBl: BlockT, TCl, TExPool, TRpc, Backend> {
 	/// A RPC extension builder. Use `NoopRpcExtensionBuilder` if you just want to pass in the
 	/// extensions directly.
 	pub rpc_extensions_builder: Box<dyn RpcExtensionBuilder<Output = TRpc> + Send>,
+	/// The RPC extensions registered by the hooks passed to [`build_network()`], served
+	/// along with the ones built by `rpc_extensions_builder`.
+	pub hooks_rpc_extensions: Option<HookRpcExtensions>,
 	/// An optional, shared remote blockchain instance. Used for light clients.
 	pub remote_blockchain: Option<Arc<dyn RemoteBlockchain<TBl>>>,
 	/// A shared network instance.
@@ -514,7 +821,12 @@ pub fn build_offchain_workers<TBl, TBackend, TCl>(
 {
 	let offchain_workers = match backend.offchain_storage() {
 		Some(db) => {
-			Some(Arc::new(sc_offchain::OffchainWorkers::new(client.clone(), db)))
+			let mut offchain_workers = sc_offchain::OffchainWorkers::new(client.clone(), db);
+			// the offchain workers write to the offchain storage.
+			if let Some(low_space) = config.disk_space.as_ref().map(|c| c.low_space.clone()) {
+				offchain_workers = offchain_workers.pause_when(move || low_space.is_low());
+			}
+			Some(Arc::new(offchain_workers))
 		},
 		None => {
 			warn!("Offchain workers disabled, due to lack of offchain storage support in backend.");
@@ -570,6 +882,7 @@ pub fn spawn_tasks<TBl, TBackend, TExPool, TRpc, TCl>(
 		keystore,
 		transaction_pool,
 		rpc_extensions_builder,
+		hooks_rpc_extensions,
 		remote_blockchain,
 		network,
 		network_status_sinks,
@@ -608,14 +921,19 @@ pub fn spawn_tasks<TBl, TBackend, TExPool, TRpc, TCl>(
 	);
 
 	// Prometheus metrics.
-	let metrics_service = if let Some(PrometheusConfig { port, registry }) =
+	let metrics_service = if let Some(PrometheusConfig { port, registry, security }) =
 		config.prometheus_config.clone()
 	{
 		// Set static metrics.
 		let metrics = MetricsService::with_prometheus(&registry, &config)?;
+		let endpoint =
+			prometheus_endpoint::init_prometheus_with_security(port, registry, security)
+				.map_err(|e| Error::Other(e.to_string()))?;
 		spawn_handle.spawn(
 			"prometheus-endpoint",
-			prometheus_endpoint::init_prometheus(port, registry).map(drop)
+			endpoint.map(|result| if let Err(e) = result {
+				log::error!("Prometheus endpoint failed: {}", e);
+			}),
 		);
 
 		metrics
@@ -623,6 +941,33 @@ pub fn spawn_tasks<TBl, TBackend, TExPool, TRpc, TCl>(
 		MetricsService::new()
 	};
 
+	// Health, readiness and status endpoints.
+	#[cfg(not(target_os = "unknown"))]
+	if let Some(address) = config.health_endpoint {
+		spawn_handle.spawn(
+			"health-endpoint",
+			crate::health::run(
+				address,
+				config.impl_version.clone(),
+				client.clone(),
+				system_rpc_tx.clone(),
+			),
+		);
+	}
+
+	// Disk space of the database.
+	#[cfg(not(target_os = "unknown"))]
+	if let (Some(disk_space), Some(path)) = (config.disk_space.clone(), config.database.path()) {
+		spawn_handle.spawn_blocking(
+			"disk-space-monitor",
+			crate::disk_space::run(
+				path.to_path_buf(),
+				disk_space,
+				config.prometheus_registry().cloned(),
+			),
+		);
+	}
+
 	// Periodically updated metrics and telemetry updates.
 	spawn_handle.spawn("telemetry-periodic-send",
 		metrics_service.run(
@@ -637,17 +982,23 @@ pub fn spawn_tasks<TBl, TBackend, TExPool, TRpc, TCl>(
 		deny_unsafe: sc_rpc::DenyUnsafe,
 		rpc_middleware: sc_rpc_server::RpcMiddleware
 	| gen_handler(
-		deny_unsafe, rpc_middleware, &config, task_manager.spawn_handle(),
+		deny_unsafe, rpc_middleware, &config, task_manager.spawn_handle().in_group(TaskGroup::Rpc),
 		client.clone(), transaction_pool.clone(), keystore.clone(),
 		on_demand.clone(), remote_blockchain.clone(), &*rpc_extensions_builder,
-		backend.offchain_storage(), system_rpc_tx.clone()
+		hooks_rpc_extensions.as_ref(), backend.clone(), system_rpc_tx.clone()
 	);
 	let rpc_metrics = sc_rpc_server::RpcMetrics::new(config.prometheus_registry())?;
-	let rpc = start_rpc_servers(&config, gen_handler, rpc_metrics.clone())?;
+	let rpc = start_rpc_servers(
+		&config,
+		gen_handler,
+		rpc_metrics.clone(),
+		task_manager.spawn_handle().in_group(TaskGroup::Rpc),
+	)?;
 	// This is used internally, so don't restrict access to unsafe RPC
 	let rpc_handlers = RpcHandlers(Arc::new(gen_handler(
 		sc_rpc::DenyUnsafe::No,
 		sc_rpc_server::RpcMiddleware::new(rpc_metrics, "inbrowser")
+			.with_aliases(config.rpc_aliases.clone())
 	).into()));
 
 	// Spawn informant task
@@ -658,7 +1009,9 @@ pub fn spawn_tasks<TBl, TBackend, TExPool, TRpc, TCl>(
 		config.informant_output_format,
 	));
 
-	task_manager.keep_alive((config.base_path, rpc, rpc_handlers.clone()));
+	// stop accepting requests as soon as the node shuts down.
+	task_manager.close_on_shutdown(rpc);
+	task_manager.keep_alive((config.base_path, rpc_handlers.clone()));
 
 	Ok((rpc_handlers, telemetry_connection_notifier))
 }
@@ -729,7 +1082,8 @@ fn gen_handler<TBl, TBackend, TExPool, TRpc, TCl>(
 	on_demand: Option<Arc<OnDemand<TBl>>>,
 	remote_blockchain: Option<Arc<dyn RemoteBlockchain<TBl>>>,
 	rpc_extensions_builder: &(dyn RpcExtensionBuilder<Output = TRpc> + Send),
-	offchain_storage: Option<<TBackend as sc_client_api::backend::Backend<TBl>>::OffchainStorage>,
+	hooks_rpc_extensions: Option<&HookRpcExtensions>,
+	backend: Arc<TBackend>,
 	system_rpc_tx: TracingUnboundedSender<sc_rpc::system::Request<TBl>>
 ) -> sc_rpc_server::RpcHandler<sc_rpc::Metadata>
 	where
@@ -755,6 +1109,29 @@ fn gen_handler<TBl, TBackend, TExPool, TRpc, TCl>(
 		chain_type: config.chain_spec.chain_type(),
 	};
 
+	let offchain_storage = backend.offchain_storage();
+	// the snapshots are written to the directory of the operator on a blocking task, as they
+	// read the whole database. The snapshots taken within the same second are told apart by
+	// their index among the snapshots taken since the node started.
+	let database_snapshot = config.database_snapshot_dir.clone().map(|dir| {
+		let spawn_handle = spawn_handle.clone();
+		let snapshots = AtomicU64::new(0);
+		Arc::new(move || {
+			let backend = backend.clone();
+			let secs = SystemTime::UNIX_EPOCH.elapsed().map_or(0, |elapsed| elapsed.as_secs());
+			let index = snapshots.fetch_add(1, Ordering::Relaxed);
+			let name = format!("snapshot-{}-{}", secs, index);
+			let path = dir.join(&name);
+			let (tx, rx) = oneshot::channel();
+			spawn_handle.spawn_blocking("database-snapshot", async move {
+				let _ = tx.send(backend.snapshot(&path).map(|()| name).map_err(|e| e.to_string()));
+			});
+			async move {
+				rx.await.unwrap_or_else(|_| Err("The snapshot was interrupted".into()))
+			}.boxed()
+		}) as system::DatabaseSnapshot
+	});
+
 	let task_executor = sc_rpc::SubscriptionTaskExecutor::new(spawn_handle);
 	let subscriptions = SubscriptionManager::new(Arc::new(task_executor.clone()));
 
@@ -778,7 +1155,8 @@ fn gen_handler<TBl, TBackend, TExPool, TRpc, TCl>(
 
 	} else {
 		// Full nodes
-		let chain = sc_rpc::chain::new_full(client.clone(), subscriptions.clone());
+		let chain = sc_rpc::chain::new_full(client.clone(), subscriptions.clone())
+			.with_type_aliases(config.rpc_type_aliases.clone());
 		let (state, child_state) = sc_rpc::state::new_full(
 			client.clone(),
 			subscriptions.clone(),
@@ -794,7 +1172,10 @@ fn gen_handler<TBl, TBackend, TExPool, TRpc, TCl>(
 		keystore,
 		deny_unsafe,
 	);
-	let system = system::System::new(system_info, system_rpc_tx, deny_unsafe);
+	let mut system = system::System::new(system_info, system_rpc_tx, deny_unsafe);
+	if let Some(database_snapshot) = database_snapshot {
+		system = system.with_database_snapshot(database_snapshot);
+	}
 
 	let maybe_offchain_rpc = offchain_storage.map(|storage| {
 		let offchain = sc_rpc::offchain::Offchain::new(storage, deny_unsafe);
@@ -809,6 +1190,7 @@ fn gen_handler<TBl, TBackend, TExPool, TRpc, TCl>(
 			maybe_offchain_rpc,
 			author::AuthorApi::to_delegate(author),
 			system::SystemApi::to_delegate(system),
+			hooks_rpc_extensions.map(|hooks| hooks.build(deny_unsafe, task_executor.clone())),
 			rpc_extensions_builder.build(deny_unsafe, task_executor),
 		),
 		rpc_middleware
@@ -833,6 +1215,8 @@ pub struct BuildNetworkParams<'a, TBl: BlockT, TExPool, TImpQu, TCl> {
 	pub block_announce_validator_builder: Option<Box<
 		dyn FnOnce(Arc<TCl>) -> Box<dyn BlockAnnounceValidator<TBl> + Send> + Send
 	>>,
+	/// Optional hooks run before the network is created, e.g. to register additional protocols.
+	pub hooks: Option<BuilderHooks<TCl, TExPool>>,
 }
 
 /// Build the network service, the network status sinks and an RPC sender.
@@ -857,9 +1241,16 @@ pub fn build_network<TBl, TExPool, TImpQu, TCl>(
 {
 	let BuildNetworkParams {
 		config, client, transaction_pool, spawn_handle, import_queue, on_demand,
-		block_announce_validator_builder,
+		block_announce_validator_builder, hooks,
 	} = params;
 
+	let mut network_config = config.network.clone();
+	if let Some(hooks) = hooks {
+		hooks.run(config, &client, &transaction_pool, &mut network_config, &spawn_handle)?;
+	}
+
+	let spawn_handle = spawn_handle.in_group(TaskGroup::Network);
+
 	let transaction_pool_adapter = Arc::new(TransactionPoolAdapter {
 		imports_external_transactions: !matches!(config.role, Role::Light),
 		pool: transaction_pool,
@@ -883,8 +1274,8 @@ pub fn build_network<TBl, TExPool, TImpQu, TCl>(
 			let (handler, protocol_config) = BlockRequestHandler::new(
 				&protocol_id,
 				client.clone(),
-				config.network.default_peers_set.in_peers as usize
-					+ config.network.default_peers_set.out_peers as usize,
+				network_config.default_peers_set.in_peers as usize
+					+ network_config.default_peers_set.out_peers as usize,
 			);
 			spawn_handle.spawn("block_request_handler", handler.run());
 			protocol_config
@@ -920,7 +1311,7 @@ pub fn build_network<TBl, TExPool, TImpQu, TCl>(
 				spawn_handle.spawn("network-transactions-handler", fut);
 			})
 		},
-		network_config: config.network.clone(),
+		network_config,
 		chain: client.clone(),
 		on_demand: on_demand,
 		transaction_pool: transaction_pool_adapter as _,
@@ -0,0 +1,234 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::time::{Duration, Instant};
+
+use crate::error::Error;
+use log::info;
+use rand::{Rng, seq::SliceRandom};
+use sc_client_api::{
+	Backend,
+	backend::{BlockImportOperation, NewBlockState, StateBackend},
+};
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::{Block as BlockT, Header as HeaderT, One}};
+
+/// The parameters of a storage benchmark, see `benchmark_storage`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StorageBenchmarkParams {
+	/// Number of distinct keys of the state read and written.
+	pub keys: usize,
+	/// Number of times every key is read and written.
+	pub repeat: usize,
+}
+
+impl Default for StorageBenchmarkParams {
+	fn default() -> Self {
+		StorageBenchmarkParams { keys: 1_000, repeat: 1 }
+	}
+}
+
+/// The statistics of the latencies of an operation, in nanoseconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LatencyStats {
+	/// Number of operations measured.
+	pub count: usize,
+	/// The fastest operation.
+	pub min: u64,
+	/// The average latency.
+	pub average: u64,
+	/// The median latency.
+	pub median: u64,
+	/// The latency 99% of the operations are faster than.
+	pub p99: u64,
+	/// The slowest operation.
+	pub max: u64,
+}
+
+impl LatencyStats {
+	/// Returns the statistics of the given latencies.
+	pub fn new(latencies: &[Duration]) -> Self {
+		if latencies.is_empty() {
+			return Default::default();
+		}
+		let mut nanos = latencies.iter().map(|d| d.as_nanos() as u64).collect::<Vec<_>>();
+		nanos.sort_unstable();
+		let percentile = |p: usize| nanos[(nanos.len() * p / 100).min(nanos.len() - 1)];
+		LatencyStats {
+			count: nanos.len(),
+			min: nanos[0],
+			average: nanos.iter().sum::<u64>() / nanos.len() as u64,
+			median: percentile(50),
+			p99: percentile(99),
+			max: nanos[nanos.len() - 1],
+		}
+	}
+}
+
+/// The latencies of the reads and writes of the state, see `benchmark_storage`.
+#[derive(Debug, Clone, Default)]
+pub struct StorageBenchmark {
+	/// Number of keys of the state sampled.
+	pub keys: usize,
+	/// The latencies of the reads of a value.
+	pub reads: LatencyStats,
+	/// The latencies of the writes of a value, from the calculation of the new root of the trie
+	/// to the commit of the changed nodes to the database.
+	pub writes: LatencyStats,
+}
+
+/// Measure the latencies of the reads and writes of random keys of the state at the given
+/// block.
+///
+/// The keys are sampled by looking up the keys following random ones, so the keys of the
+/// pallets with the most entries are sampled the most, like they are accessed the most. They
+/// are read in a random order, to defeat the caches of the database. A write is measured as
+/// the calculation of the root of the state with the value of one key changed, which reads and
+/// hashes the trie nodes on the path of the key, and the commit of the changed nodes through
+/// the backend, as a block on top of the given one. The blocks written are left in the backend,
+/// so it should be a copy of the database of the node.
+pub fn benchmark_storage<B, BA>(
+	backend: &BA,
+	block: BlockId<B>,
+	params: StorageBenchmarkParams,
+) -> Result<StorageBenchmark, Error> where
+	B: BlockT,
+	BA: Backend<B>,
+{
+	let error = |e| Error::Other(format!("Error reading the state: {}", e));
+	let mut rng = rand::thread_rng();
+
+	let header = backend.blockchain().header(block)?
+		.ok_or_else(|| Error::Other(format!("Unknown block {}", block)))?;
+	let parent_hash = header.hash();
+
+	let keys = {
+		let state = backend.state_at(block)?;
+		let mut keys = Vec::with_capacity(params.keys);
+		for _ in 0..params.keys {
+			let start = rng.gen::<[u8; 32]>();
+			let next = match state.next_storage_key(&start).map_err(error)? {
+				Some(next) => Some(next),
+				// past the last key, wrap around.
+				None => state.next_storage_key(&[]).map_err(error)?,
+			};
+			match next {
+				Some(next) => keys.push(next),
+				None => return Err(Error::Other("The state is empty".into())),
+			}
+		}
+		keys.sort();
+		keys.dedup();
+		keys
+	};
+	info!("Sampled {} keys of the state", keys.len());
+
+	// a fresh state, so the lookups of the sampling don't warm up its caches.
+	let state = backend.state_at(block)?;
+	let mut order = (0..params.repeat).flat_map(|_| 0..keys.len()).collect::<Vec<_>>();
+	order.shuffle(&mut rng);
+
+	let mut values = vec![Vec::new(); keys.len()];
+	let mut reads = Vec::with_capacity(order.len());
+	for &index in &order {
+		let start = Instant::now();
+		let value = state.storage(&keys[index]).map_err(error)?;
+		reads.push(start.elapsed());
+		values[index] = value.unwrap_or_default();
+	}
+
+	order.shuffle(&mut rng);
+	let mut writes = Vec::with_capacity(order.len());
+	for (write, &index) in order.iter().enumerate() {
+		// every value written is distinct, so are the blocks.
+		let mut value = values[index].clone();
+		value.extend_from_slice(&(write as u32).to_le_bytes());
+		let mut operation = backend.begin_operation()?;
+		backend.begin_state_operation(&mut operation, BlockId::Hash(parent_hash))?;
+
+		let start = Instant::now();
+		let (state_root, transaction) =
+			state.storage_root(std::iter::once((&keys[index][..], Some(&value[..]))));
+		operation.update_db_storage(transaction)?;
+		operation.set_block_data(
+			<B::Header as HeaderT>::new(
+				*header.number() + One::one(),
+				Default::default(),
+				state_root,
+				parent_hash,
+				Default::default(),
+			),
+			None,
+			None,
+			NewBlockState::Normal,
+		)?;
+		backend.commit_operation(operation)?;
+		writes.push(start.elapsed());
+	}
+
+	Ok(StorageBenchmark {
+		keys: keys.len(),
+		reads: LatencyStats::new(&reads),
+		writes: LatencyStats::new(&writes),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_blockchain::Backend as _;
+	use substrate_test_runtime_client::{
+		DefaultTestClientBuilderExt, TestClientBuilder, TestClientBuilderExt,
+		runtime::Block,
+	};
+
+	#[test]
+	fn latency_stats() {
+		let latencies = (1..=100).rev().map(Duration::from_nanos).collect::<Vec<_>>();
+		assert_eq!(LatencyStats::new(&latencies), LatencyStats {
+			count: 100,
+			min: 1,
+			average: 50,
+			median: 51,
+			p99: 100,
+			max: 100,
+		});
+		assert_eq!(LatencyStats::new(&[]), LatencyStats::default());
+	}
+
+	#[test]
+	fn benchmarks_the_keys_of_the_state() {
+		let builder = TestClientBuilder::new();
+		let backend = builder.backend();
+		let _client = builder.build();
+
+		let params = StorageBenchmarkParams { keys: 10, repeat: 2 };
+		let benchmark = benchmark_storage::<Block, _>(&*backend, BlockId::Number(0), params)
+			.unwrap();
+		assert!(benchmark.keys > 0 && benchmark.keys <= 10);
+		assert_eq!(benchmark.reads.count, benchmark.keys * 2);
+		assert_eq!(benchmark.writes.count, benchmark.keys * 2);
+		assert!(benchmark.reads.min <= benchmark.reads.max);
+
+		// the blocks written are forks of the given block.
+		let info = backend.blockchain().info();
+		assert_eq!(info.best_number, 0);
+		assert_eq!(backend.blockchain().hash(1).unwrap(), None);
+		assert_eq!(backend.blockchain().leaves().unwrap().len(), benchmark.writes.count);
+	}
+}
@@ -0,0 +1,213 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Export and import of the state in a compact binary format.
+//!
+//! Unlike chain specs, the binary format is written as the state is read: the keys and values
+//! aren't collected, hex encoded or sorted first. Likewise, it is read in chunks, so the state
+//! is never held in memory as a whole.
+
+use crate::error::Error;
+use codec::{Decode, Encode, IoReader};
+use sc_client_api::{StorageProvider, UsageProvider};
+use sp_blockchain::HeaderBackend;
+use sp_core::storage::{
+	ChildInfo, Storage, StorageChild, well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX,
+};
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use std::{io::{Read, Write}, sync::Arc};
+
+/// The first bytes of the binary format, followed by its version.
+const MAGIC: &[u8; 8] = b"substate";
+
+/// The version of the binary format.
+const VERSION: u32 = 1;
+
+/// Number of entries of the state read at once.
+const CHUNK_SIZE: usize = 10_000;
+
+/// An entry of the state, the last one being `End`.
+#[derive(Encode, Decode)]
+enum Entry {
+	/// A key and value of the top trie.
+	Top(Vec<u8>, Vec<u8>),
+	/// The storage key (without prefix) of a default child trie and a key and value of it.
+	Child(Vec<u8>, Vec<u8>, Vec<u8>),
+	End,
+}
+
+/// Write the state at the given `block` to `output` in the binary format. If `block` is `None`,
+/// the best block will be used.
+pub fn export_binary_state<B, BA, C>(
+	client: Arc<C>,
+	block: Option<BlockId<B>>,
+	mut output: impl Write,
+) -> Result<(), Error>
+where
+	C: UsageProvider<B> + StorageProvider<B, BA> + HeaderBackend<B>,
+	B: BlockT,
+	BA: sc_client_api::backend::Backend<B>,
+{
+	let hash = match block {
+		Some(block) => client.expect_block_hash_from_id(&block)?,
+		None => client.usage_info().chain.best_hash,
+	};
+	let block = BlockId::Hash(hash);
+
+	output.write_all(MAGIC)?;
+	output.write_all(&VERSION.encode())?;
+	output.write_all(&hash.encode())?;
+
+	for key in client.storage_keys_iter(&block, None, None)? {
+		if key.0.starts_with(DEFAULT_CHILD_STORAGE_KEY_PREFIX) {
+			let child_key = &key.0[DEFAULT_CHILD_STORAGE_KEY_PREFIX.len()..];
+			let child_info = ChildInfo::new_default(child_key);
+			let keys = client.child_storage_keys_iter(&block, child_info.clone(), None, None)?;
+			for key in keys {
+				if let Some(value) = client.child_storage(&block, &child_info, &key)? {
+					output.write_all(&Entry::Child(child_key.to_vec(), key.0, value.0).encode())?;
+				}
+			}
+		} else if let Some(value) = client.storage(&block, &key)? {
+			output.write_all(&Entry::Top(key.0, value.0).encode())?;
+		}
+	}
+	output.write_all(&Entry::End.encode())?;
+	output.flush()?;
+	Ok(())
+}
+
+/// Read the header of a state written by `export_binary_state`: the hash of the block it is the
+/// state of, and the chunks of the state that follow.
+pub fn read_binary_state<B: BlockT, R: Read>(
+	input: R,
+) -> Result<(B::Hash, BinaryStateChunks<R>), Error> {
+	let mut reader = IoReader(input);
+
+	let mut magic = [0; 8];
+	reader.0.read_exact(&mut magic)?;
+	if &magic != MAGIC {
+		return Err("Invalid binary state: the file is not a binary state".into());
+	}
+	let version = u32::decode(&mut reader).map_err(decoding_error)?;
+	if version != VERSION {
+		return Err(format!("Unsupported binary state version {}", version).into());
+	}
+	let hash = B::Hash::decode(&mut reader).map_err(decoding_error)?;
+
+	Ok((hash, BinaryStateChunks { reader, chunk_size: CHUNK_SIZE, done: false }))
+}
+
+fn decoding_error(e: codec::Error) -> String {
+	format!("Invalid binary state: {}", e)
+}
+
+/// The state following the header of a binary state, read in chunks of at most `CHUNK_SIZE`
+/// entries.
+pub struct BinaryStateChunks<R: Read> {
+	reader: IoReader<R>,
+	chunk_size: usize,
+	done: bool,
+}
+
+impl<R: Read> Iterator for BinaryStateChunks<R> {
+	type Item = Result<Storage, Error>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.done {
+			return None;
+		}
+
+		let mut chunk = Storage::default();
+		for _ in 0..self.chunk_size {
+			let entry = match Entry::decode(&mut self.reader) {
+				Ok(entry) => entry,
+				Err(e) => {
+					self.done = true;
+					return Some(Err(decoding_error(e).into()));
+				},
+			};
+			match entry {
+				Entry::Top(key, value) => {
+					chunk.top.insert(key, value);
+				},
+				Entry::Child(child_key, key, value) => {
+					chunk.children_default.entry(child_key.clone())
+						.or_insert_with(|| StorageChild {
+							data: Default::default(),
+							child_info: ChildInfo::new_default(&child_key),
+						})
+						.data.insert(key, value);
+				},
+				Entry::End => {
+					self.done = true;
+					break;
+				},
+			}
+		}
+		Some(Ok(chunk))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use substrate_test_runtime_client::{
+		DefaultTestClientBuilderExt, TestClientBuilder, TestClientBuilderExt,
+		runtime::Block,
+	};
+
+	#[test]
+	fn imports_the_exported_state() {
+		let child_info = ChildInfo::new_default(b"child");
+		let client = Arc::new(
+			TestClientBuilder::new()
+				.add_extra_child_storage(&child_info, b"key".to_vec(), vec![1])
+				.build(),
+		);
+		let genesis_hash = client.info().genesis_hash;
+
+		let mut output = Vec::new();
+		export_binary_state(client.clone(), None, &mut output).unwrap();
+		let (hash, mut chunks) = read_binary_state::<Block, _>(&output[..]).unwrap();
+		assert_eq!(hash, genesis_hash);
+		chunks.chunk_size = 2;
+		let mut storage = Storage::default();
+		for chunk in chunks {
+			let chunk = chunk.unwrap();
+			assert!(chunk.top.len() + chunk.children_default.len() <= 2);
+			storage.top.extend(chunk.top);
+			for (child_key, child) in chunk.children_default {
+				storage.children_default.entry(child_key)
+					.or_insert_with(|| StorageChild {
+						data: Default::default(),
+						child_info: child.child_info.clone(),
+					})
+					.data.extend(child.data);
+			}
+		}
+		let expected = super::super::export_raw_state(client, None).unwrap();
+		assert_eq!(storage.top, expected.top);
+		assert_eq!(storage.children_default, expected.children_default);
+		assert_eq!(storage.children_default[&b"child"[..]].data[&b"key"[..]], vec![1]);
+
+		let (_, truncated) = read_binary_state::<Block, _>(&output[..output.len() - 1]).unwrap();
+		assert!(truncated.last().unwrap().is_err());
+		assert!(read_binary_state::<Block, _>(&b"not a state"[..]).is_err());
+	}
+}
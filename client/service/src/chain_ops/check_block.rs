@@ -23,6 +23,7 @@ use sp_runtime::generic::BlockId;
 use codec::Encode;
 use sp_consensus::import_queue::ImportQueue;
 use sc_client_api::{BlockBackend, UsageProvider};
+use sp_blockchain::HeaderBackend;
 
 use std::pin::Pin;
 use std::sync::Arc;
@@ -35,7 +36,7 @@ pub fn check_block<B, IQ, C>(
 	block_id: BlockId<B>
 ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send>>
 where
-	C: BlockBackend<B> + UsageProvider<B> + Send + Sync + 'static,
+	C: BlockBackend<B> + UsageProvider<B> + HeaderBackend<B> + Send + Sync + 'static,
 	B: BlockT + for<'de> serde::Deserialize<'de>,
 	IQ: ImportQueue<B> + 'static,
 {
@@ -45,7 +46,7 @@ where
 			1u64.encode_to(&mut buf);
 			block.encode_to(&mut buf);
 			let reader = std::io::Cursor::new(buf);
-			import_blocks(client, import_queue, reader, true, true)
+			import_blocks(client, import_queue, reader, true, true, None)
 		}
 		Ok(None) => Box::pin(future::err("Unknown block".into())),
 		Err(e) => Box::pin(future::err(format!("Error reading block: {:?}", e).into())),
@@ -0,0 +1,218 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::error::Error;
+use codec::Encode;
+use log::info;
+use sc_client_api::{Backend, backend::StateBackend};
+use sc_client_db::KeepBlocks;
+use sp_blockchain::{Backend as BlockchainBackend, HeaderBackend};
+use sp_core::storage::{ChildInfo, well_known_keys};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{
+		Block as BlockT, Header as HeaderT, Hash as HashT, HashFor, NumberFor, One, Saturating,
+		Zero,
+	},
+};
+
+/// The inconsistencies found in the database by `check_database`.
+#[derive(Debug)]
+pub struct DatabaseReport<B: BlockT> {
+	/// Number of blocks of the best chain checked.
+	pub blocks: u64,
+	/// Number of keys of the state of the best block checked.
+	pub state_keys: u64,
+	/// The inconsistencies, with the number of the block they were found at.
+	pub inconsistencies: Vec<(NumberFor<B>, String)>,
+	/// With `repair`, the number of blocks to revert and the number of blocks reverted.
+	pub reverted: Option<(NumberFor<B>, NumberFor<B>)>,
+}
+
+impl<B: BlockT> DatabaseReport<B> {
+	/// Returns the number of the first inconsistent block, if any.
+	pub fn first_inconsistent_block(&self) -> Option<NumberFor<B>> {
+		self.inconsistencies.iter().map(|(number, _)| *number).min()
+	}
+
+	/// Returns whether fewer blocks were reverted than requested by the repair.
+	pub fn partially_reverted(&self) -> bool {
+		self.reverted.map_or(false, |(requested, reverted)| reverted < requested)
+	}
+}
+
+/// Check the headers, bodies and justifications of the best chain, and the state of the best
+/// block, for missing or corrupt entries.
+///
+/// The bodies pruned according to `keep_blocks` aren't expected to be stored, the others have
+/// to match the extrinsics root of their header.
+pub fn check_database<B, BA>(
+	backend: &BA,
+	keep_blocks: KeepBlocks,
+) -> Result<DatabaseReport<B>, Error> where
+	B: BlockT,
+	BA: Backend<B>,
+{
+	let blockchain = backend.blockchain();
+	let info = blockchain.info();
+	let bodies_from = match keep_blocks {
+		KeepBlocks::All => Zero::zero(),
+		KeepBlocks::Some(n) => info.finalized_number.saturating_sub(n.into()),
+	};
+	let mut report = DatabaseReport {
+		blocks: 0,
+		state_keys: 0,
+		inconsistencies: Vec::new(),
+		reverted: None,
+	};
+	let mut parent_hash = None;
+
+	let mut number = Zero::zero();
+	while number <= info.best_number {
+		if let Err(e) = check_block(blockchain, number, bodies_from, &mut parent_hash) {
+			report.inconsistencies.push((number, e));
+		}
+		report.blocks += 1;
+		if report.blocks % 10_000 == 0 {
+			info!("Checked #{}", number);
+		}
+		number += One::one();
+	}
+
+	match backend.state_at(BlockId::Hash(info.best_hash)) {
+		Ok(state) => match check_state::<B, _>(&state, &mut report.state_keys) {
+			Ok(()) => {},
+			Err(e) => report.inconsistencies.push((info.best_number, e)),
+		},
+		Err(e) => report.inconsistencies.push((info.best_number, format!("Missing state: {}", e))),
+	}
+	Ok(report)
+}
+
+/// Check the block with the given number of the best chain, whose parent has the given hash.
+fn check_block<B: BlockT>(
+	blockchain: &impl BlockchainBackend<B>,
+	number: NumberFor<B>,
+	bodies_from: NumberFor<B>,
+	parent_hash: &mut Option<B::Hash>,
+) -> Result<(), String> {
+	let expected_parent_hash = parent_hash.take();
+	let hash = blockchain.hash(number)
+		.map_err(|e| format!("Corrupt hash: {}", e))?
+		.ok_or("Missing hash")?;
+	*parent_hash = Some(hash);
+
+	let header = blockchain.header(BlockId::Hash(hash))
+		.map_err(|e| format!("Corrupt header: {}", e))?
+		.ok_or("Missing header")?;
+	if header.hash() != hash {
+		return Err(format!("Header hash mismatch: {}", header.hash()));
+	}
+	if let Some(expected_parent_hash) = expected_parent_hash {
+		if *header.parent_hash() != expected_parent_hash {
+			return Err(format!("Parent hash mismatch: {}", header.parent_hash()));
+		}
+	}
+
+	if number >= bodies_from {
+		let body = blockchain.body(BlockId::Hash(hash))
+			.map_err(|e| format!("Corrupt body: {}", e))?
+			.ok_or("Missing body")?;
+		let extrinsics_root = HashFor::<B>::ordered_trie_root(
+			body.iter().map(Encode::encode).collect(),
+		);
+		if extrinsics_root != *header.extrinsics_root() {
+			return Err(format!("Body doesn't match the extrinsics root: {}", extrinsics_root));
+		}
+	}
+	blockchain.justification(BlockId::Hash(hash))
+		.map_err(|e| format!("Corrupt justification: {}", e))?;
+	Ok(())
+}
+
+/// Read every key of the state and of its child tries, counting them in `keys`.
+fn check_state<B: BlockT, S: StateBackend<HashFor<B>>>(
+	state: &S,
+	keys: &mut u64,
+) -> Result<(), String> {
+	let error = |e: S::Error| format!("Incomplete state: {}", e);
+	let mut key = Vec::new();
+	while let Some(next) = state.next_storage_key(&key).map_err(error)? {
+		state.storage(&next).map_err(error)?;
+		*keys += 1;
+
+		if next.starts_with(well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX) {
+			let storage_key = &next[well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX.len()..];
+			let child_info = ChildInfo::new_default(storage_key);
+			let mut child_key = Vec::new();
+			while let Some(next) = state.next_child_storage_key(&child_info, &child_key)
+				.map_err(error)?
+			{
+				state.child_storage(&child_info, &next).map_err(error)?;
+				*keys += 1;
+				child_key = next;
+			}
+		}
+		key = next;
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc_client_api::StorageProvider;
+	use sp_core::storage::StorageKey;
+	use sc_block_builder::BlockBuilderProvider;
+	use sp_consensus::BlockOrigin;
+	use substrate_test_runtime_client::{
+		AccountKeyring, ClientBlockImportExt, DefaultTestClientBuilderExt, TestClientBuilder,
+		TestClientBuilderExt,
+		runtime::{Block, Transfer},
+	};
+
+	#[test]
+	fn checks_the_blocks_and_the_state() {
+		let child_info = ChildInfo::new_default(b"child");
+		let builder = TestClientBuilder::new()
+			.add_extra_child_storage(&child_info, b"key".to_vec(), vec![1]);
+		let backend = builder.backend();
+		let mut client = builder.build();
+
+		let mut builder = client.new_block(Default::default()).unwrap();
+		builder.push(Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Bob.into(),
+			amount: 42,
+			nonce: 0,
+		}.into_signed_tx()).unwrap();
+		let block = builder.build().unwrap().block;
+		client.import(BlockOrigin::Own, block).unwrap();
+
+		let top_keys = client.storage_keys(
+			&BlockId::Number(1),
+			&StorageKey(Vec::new()),
+		).unwrap();
+
+		let report = check_database::<Block, _>(&*backend, KeepBlocks::All).unwrap();
+		assert_eq!(report.blocks, 2);
+		assert_eq!(report.state_keys, top_keys.len() as u64 + 1);
+		assert!(report.inconsistencies.is_empty());
+		assert_eq!(report.first_inconsistent_block(), None);
+	}
+}
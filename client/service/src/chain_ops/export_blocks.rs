@@ -31,12 +31,16 @@ use std::sync::Arc;
 use std::task::Poll;
 
 /// Performs the blocks export.
+///
+/// The justifications of the blocks are exported along with them, unless `justifications` is
+/// `false`.
 pub fn export_blocks<B, C>(
 	client: Arc<C>,
 	mut output: impl Write + 'static,
 	from: NumberFor<B>,
 	to: Option<NumberFor<B>>,
-	binary: bool
+	binary: bool,
+	justifications: bool,
 ) -> Pin<Box<dyn Future<Output = Result<(), Error>>>>
 where
 	C: BlockBackend<B> + UsageProvider<B> + 'static,
@@ -78,7 +82,10 @@ where
 		}
 
 		match client.block(&BlockId::number(block))? {
-			Some(block) => {
+			Some(mut block) => {
+				if !justifications {
+					block.justification = None;
+				}
 				if binary {
 					output.write_all(&block.encode())?;
 				} else {
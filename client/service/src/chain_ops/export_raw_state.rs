@@ -20,7 +20,7 @@ use crate::error::Error;
 use sp_runtime::traits::Block as BlockT;
 use sp_runtime::generic::BlockId;
 use sp_core::storage::{StorageKey, well_known_keys, ChildInfo, Storage, StorageChild, StorageMap};
-use sc_client_api::{StorageProvider, UsageProvider};
+use sc_client_api::{Backend, StorageProvider, UsageProvider, backend::StateBackend};
 
 use std::{collections::HashMap, sync::Arc};
 
@@ -71,3 +71,34 @@ where
 	let top = top_storage.into_iter().map(|(k, v)| (k.0, v.0)).collect();
 	Ok(Storage { top, children_default })
 }
+
+/// Export the raw state of the backend at the given `block`.
+///
+/// Unlike `export_raw_state`, it doesn't need a client, so no executor.
+pub fn export_backend_state<B, BA>(backend: &BA, block: BlockId<B>) -> Result<Storage, Error>
+where
+	B: BlockT,
+	BA: Backend<B>,
+{
+	let error = |e| Error::Other(format!("Error reading the state: {}", e));
+	let state = backend.state_at(block)?;
+	let mut storage = Storage::default();
+
+	for (key, value) in state.pairs() {
+		if !key.starts_with(well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX) {
+			storage.top.insert(key, value);
+			continue;
+		}
+
+		let key = key[well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX.len()..].to_vec();
+		let child_info = ChildInfo::new_default(&key);
+		let mut pairs = StorageMap::new();
+		for k in state.child_keys(&child_info, &[]) {
+			if let Some(value) = state.child_storage(&child_info, &k).map_err(error)? {
+				pairs.insert(k, value);
+			}
+		}
+		storage.children_default.insert(key, StorageChild { child_info, data: pairs });
+	}
+	Ok(storage)
+}
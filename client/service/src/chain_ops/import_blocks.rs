@@ -24,14 +24,14 @@ use futures::{future, prelude::*};
 use sp_runtime::traits::{
 	Block as BlockT, NumberFor, Zero, Header, MaybeSerializeDeserialize,
 };
-use sp_runtime::generic::SignedBlock;
-use codec::{Decode, IoReader as CodecIoReader};
+use sp_runtime::generic::{BlockId, SignedBlock};
+use codec::{Decode, Encode, IoReader as CodecIoReader};
 use sp_consensus::{
 	BlockOrigin,
 	import_queue::{IncomingBlock, Link, BlockImportError, BlockImportResult, ImportQueue},
 };
 
-use std::{io::{Read, Seek}, pin::Pin};
+use std::{collections::VecDeque, fs, io::{self, Read, Seek, SeekFrom}, path::PathBuf, pin::Pin};
 use std::time::{Duration, Instant};
 use futures_timer::Delay;
 use std::task::Poll;
@@ -39,6 +39,8 @@ use serde_json::{de::IoRead as JsonIoRead, Deserializer, StreamDeserializer};
 use std::convert::{TryFrom, TryInto};
 use sp_runtime::traits::{CheckedDiv, Saturating};
 use sc_client_api::UsageProvider;
+use sp_blockchain::HeaderBackend;
+use sp_core::bytes;
 
 /// Number of blocks we will add to the queue before waiting for the queue to catch up.
 const MAX_PENDING_BLOCKS: u64 = 1_024;
@@ -49,6 +51,9 @@ const DELAY_TIME: u64 = 2_000;
 /// Number of milliseconds that must have passed between two updates.
 const TIME_BETWEEN_UPDATES: u64 = 3_000;
 
+/// Number of milliseconds that must have passed between two checkpoints.
+const TIME_BETWEEN_CHECKPOINTS: u64 = 30_000;
+
 use std::sync::Arc;
 
 /// Build a chain spec json
@@ -261,6 +266,145 @@ impl<B: BlockT> Speedometer<B> {
 	}
 }
 
+/// Structure used to log the progress of the import and the time it should still take.
+struct Progress {
+	started: Instant,
+	last_update: Instant,
+	/// Number of blocks imported before the import was resumed.
+	resumed_at: u64,
+	/// Total number of blocks to be imported, if known.
+	num_expected_blocks: Option<u64>,
+}
+
+impl Progress {
+	fn new(resumed_at: u64, num_expected_blocks: Option<u64>) -> Self {
+		let now = Instant::now();
+		Self { started: now, last_update: now, resumed_at, num_expected_blocks }
+	}
+
+	/// Logs the number of imported blocks, the throughput and the estimated time of arrival,
+	/// if more than TIME_BETWEEN_UPDATES has elapsed since last time.
+	fn notify_user(&mut self, imported_blocks: u64) {
+		if self.last_update.elapsed() < Duration::from_millis(TIME_BETWEEN_UPDATES) {
+			return
+		}
+		self.last_update = Instant::now();
+
+		let elapsed = self.started.elapsed().as_secs_f64();
+		let throughput = imported_blocks.saturating_sub(self.resumed_at) as f64 / elapsed;
+		match self.num_expected_blocks {
+			Some(total) if throughput > 0.0 => {
+				let remaining = total.saturating_sub(imported_blocks) as f64 / throughput;
+				info!(
+					"⏳ Imported {}/{} blocks ({:.1} blocks/s), ETA {}",
+					imported_blocks,
+					total,
+					throughput,
+					format_duration(Duration::from_secs_f64(remaining)),
+				);
+			},
+			_ => info!("⏳ Imported {} blocks ({:.1} blocks/s)", imported_blocks, throughput),
+		}
+	}
+}
+
+/// Formats the given duration as hours, minutes and seconds, e.g. `1h 02m 03s`.
+fn format_duration(duration: Duration) -> String {
+	let secs = duration.as_secs();
+	match (secs / 3600, secs / 60 % 60, secs % 60) {
+		(0, 0, s) => format!("{}s", s),
+		(0, m, s) => format!("{}m {:02}s", m, s),
+		(h, m, s) => format!("{}h {:02}m {:02}s", h, m, s),
+	}
+}
+
+/// The progress of an import recorded to a file, so an interrupted import can be resumed.
+///
+/// The file holds the number of blocks of the input that are imported and the hash of the last
+/// one, so a checkpoint of another database or input is noticed. It is removed once the import
+/// is done.
+struct Checkpoint<H> {
+	path: PathBuf,
+	imported_blocks: u64,
+	/// The hash of the last block imported, if any.
+	last_block: Option<H>,
+	last_update: Instant,
+}
+
+impl<H: Encode + Decode + PartialEq> Checkpoint<H> {
+	/// Reads the checkpoint of the given file, if it exists.
+	fn open(path: PathBuf) -> Result<Self, Error> {
+		let (imported_blocks, last_block) = match fs::read_to_string(&path) {
+			Ok(content) => Self::parse(&content).ok_or_else(|| Error::Other(
+				format!("Invalid checkpoint {}", path.display())
+			))?,
+			Err(e) if e.kind() == io::ErrorKind::NotFound => (0, None),
+			Err(e) => return Err(e.into()),
+		};
+		Ok(Self { path, imported_blocks, last_block, last_update: Instant::now() })
+	}
+
+	/// Parses the number of imported blocks and the hash of the last one.
+	fn parse(content: &str) -> Option<(u64, Option<H>)> {
+		let mut fields = content.split_whitespace();
+		let imported_blocks = fields.next()?.parse().ok()?;
+		let last_block = bytes::from_hex(fields.next()?).ok()?;
+		let last_block = H::decode(&mut &last_block[..]).ok()?;
+		if fields.next().is_some() {
+			return None;
+		}
+		Some((imported_blocks, Some(last_block)))
+	}
+
+	/// Forget the recorded progress, so the import starts from the first block.
+	fn reset(&mut self) {
+		self.imported_blocks = 0;
+		self.last_block = None;
+	}
+
+	/// Records the given number of imported blocks and the last one, if more than
+	/// TIME_BETWEEN_CHECKPOINTS has elapsed since last time.
+	fn update(&mut self, imported_blocks: u64, last_block: H) {
+		if self.last_update.elapsed() >= Duration::from_millis(TIME_BETWEEN_CHECKPOINTS) {
+			self.write(imported_blocks, last_block);
+		}
+	}
+
+	/// Records the given number of imported blocks and the last one.
+	fn write(&mut self, imported_blocks: u64, last_block: H) {
+		self.last_update = Instant::now();
+		if imported_blocks == self.imported_blocks {
+			return
+		}
+		// the checkpoint is replaced at once, so it is never left half written.
+		let mut temporary = self.path.clone().into_os_string();
+		temporary.push(".tmp");
+		let content = format!(
+			"{} {}\n",
+			imported_blocks,
+			bytes::to_hex(&last_block.encode(), false),
+		);
+		let result = fs::write(&temporary, content)
+			.and_then(|_| fs::rename(&temporary, &self.path));
+		match result {
+			Ok(()) => {
+				self.imported_blocks = imported_blocks;
+				self.last_block = Some(last_block);
+			},
+			Err(e) => warn!("Failed to write the checkpoint {}: {}", self.path.display(), e),
+		}
+	}
+
+	/// Removes the checkpoint of a finished import.
+	fn remove(self) {
+		if let Err(e) = fs::remove_file(&self.path) {
+			if e.kind() != io::ErrorKind::NotFound {
+				warn!("Failed to remove the checkpoint {}: {}", self.path.display(), e);
+			}
+		}
+	}
+}
+
 /// Different State that the `import_blocks` future could be in.
 enum ImportState<R, B> where 
 	R: Read + Seek + 'static,
@@ -283,16 +427,42 @@ enum ImportState<R, B> where
 	},
 }
 
+/// Returns whether the given blocks, as exported by `export_blocks`, are in binary rather than
+/// JSON.
+///
+/// The JSON exports start with the first block, the binary ones with the number of blocks. The
+/// input is read from and left at its current position.
+pub fn blocks_are_binary(input: &mut (impl Read + Seek)) -> io::Result<bool> {
+	const JSON_START: &[u8] = b"{\"block\"";
+
+	let position = input.seek(SeekFrom::Current(0))?;
+	let mut start = Vec::new();
+	input.by_ref().take(1024).read_to_end(&mut start)?;
+	input.seek(SeekFrom::Start(position))?;
+
+	let start = start.iter()
+		.position(|byte| !byte.is_ascii_whitespace())
+		.map_or(&[][..], |first| &start[first..]);
+	Ok(!start.is_empty() && !start.starts_with(JSON_START))
+}
+
 /// Starts the process of importing blocks.
+///
+/// If a checkpoint file is given, the progress of the import is periodically recorded to it
+/// and the blocks it records as imported are skipped, so an interrupted import resumes where
+/// it stopped. A checkpoint whose last block is unknown to the client, e.g. left after the
+/// chain was purged, is ignored, and the import fails if the input doesn't have the last block
+/// where the checkpoint records it.
 pub fn import_blocks<B, IQ, C>(
 	client: Arc<C>,
 	mut import_queue: IQ,
 	input: impl Read + Seek + Send + 'static,
 	force: bool,
 	binary: bool,
+	checkpoint: Option<PathBuf>,
 ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send>>
 where
-	C: UsageProvider<B> + Send + Sync + 'static,
+	C: UsageProvider<B> + HeaderBackend<B> + Send + Sync + 'static,
 	B: BlockT + for<'de> serde::Deserialize<'de>,
 	IQ: ImportQueue<B> + 'static,
 {
@@ -341,6 +511,34 @@ where
 		}
 	};
 
+	let mut checkpoint = match checkpoint.map(Checkpoint::<B::Hash>::open).transpose() {
+		Ok(checkpoint) => checkpoint,
+		Err(e) => return future::ready(Err(e)).boxed(),
+	};
+	if let Some(checkpoint) = &mut checkpoint {
+		if let Some(last_block) = checkpoint.last_block {
+			if !matches!(client.header(BlockId::Hash(last_block)), Ok(Some(_))) {
+				warn!(
+					"Ignoring the checkpoint {}, its last block {:?} isn't in the database",
+					checkpoint.path.display(),
+					last_block,
+				);
+				checkpoint.reset();
+			}
+		}
+	}
+	// the blocks imported before the import was interrupted are read again, but not imported.
+	let skipped_blocks = checkpoint.as_ref().map_or(0, |checkpoint| checkpoint.imported_blocks);
+	let mut last_imported = checkpoint.as_ref().and_then(|checkpoint| checkpoint.last_block);
+	if skipped_blocks > 0 {
+		info!("Resuming the import after {} blocks", skipped_blocks);
+	}
+	link.imported_blocks = skipped_blocks;
+	// the hashes of the blocks queued, but not imported yet.
+	let mut queued = VecDeque::new();
+	let mut counted_blocks = skipped_blocks;
+
+	let mut progress = Progress::new(skipped_blocks, block_iter.num_expected_blocks());
 	let mut state = Some(ImportState::Reading{block_iter});
 	let mut speedometer = Speedometer::<B>::new();
 
@@ -369,6 +567,18 @@ where
 					Some(block_result) => {
 						let read_block_count = block_iter.read_block_count();
 						match block_result {
+							Ok(block) if read_block_count <= skipped_blocks => {
+								let hash = block.block.header().hash();
+								let last_skipped = read_block_count == skipped_blocks;
+								if last_skipped && last_imported != Some(hash) {
+									return Poll::Ready(Err(Error::Other(format!(
+										"The checkpoint doesn't match the input, block #{} is {:?}",
+										read_block_count,
+										hash,
+									))))
+								}
+								state = Some(ImportState::Reading{block_iter});
+							},
 							Ok(block) => {
 								if read_block_count - link.imported_blocks >= MAX_PENDING_BLOCKS {
 									// The queue is full, so do not add this block and simply wait
@@ -379,6 +589,7 @@ where
 									});
 								} else {
 									// Queue is not full, we can keep on adding blocks to the queue.
+									queued.push_back(block.block.header().hash());
 									import_block_to_queue(block, queue, force);
 									state = Some(ImportState::Reading{block_iter});
 								}
@@ -413,6 +624,7 @@ where
 					});
 				} else {
 					// Queue is no longer full, so we can add our block to the queue.
+					queued.push_back(block.block.header().hash());
 					import_block_to_queue(block, queue, force);
 					// Switch back to Reading state.
 					state = Some(ImportState::Reading{block_iter});
@@ -424,6 +636,16 @@ where
 				// All the blocks have been added to the queue, which doesn't mean they 
 				// have all been properly imported.
 				if importing_is_done(num_expected_blocks, read_block_count, link.imported_blocks) {
+					if read_block_count < skipped_blocks {
+						return Poll::Ready(Err(Error::Other(format!(
+							"The checkpoint records {} imported blocks, but the input has only {}",
+							skipped_blocks,
+							read_block_count,
+						))))
+					}
+					if let Some(checkpoint) = checkpoint.take() {
+						checkpoint.remove();
+					}
 					// Importing is done, we can log the result and return.
 					info!(
 						"🎉 Imported {} blocks. Best: #{}",
@@ -453,11 +675,23 @@ where
 		}
 
 		queue.poll_actions(cx, &mut link);
+		// the blocks are imported in the order they are queued.
+		while counted_blocks < link.imported_blocks {
+			last_imported = queued.pop_front().or(last_imported);
+			counted_blocks += 1;
+		}
 
 		let best_number = client.usage_info().chain.best_number;
 		speedometer.notify_user(best_number);
+		progress.notify_user(link.imported_blocks);
+		if let (Some(checkpoint), Some(last_imported)) = (&mut checkpoint, last_imported) {
+			checkpoint.update(link.imported_blocks, last_imported);
+		}
 
 		if link.has_error {
+			if let (Some(checkpoint), Some(last_imported)) = (&mut checkpoint, last_imported) {
+				checkpoint.write(link.imported_blocks, last_imported);
+			}
 			return Poll::Ready(Err(
 				Error::Other(
 					format!("Stopping after #{} blocks because of an error", link.imported_blocks)
@@ -470,3 +704,56 @@ where
 	});
 	Box::pin(import)
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+
+	#[test]
+	fn detects_the_format_of_the_blocks() {
+		let detect = |input: &[u8]| {
+			let mut input = io::Cursor::new(input.to_vec());
+			let binary = blocks_are_binary(&mut input).unwrap();
+			assert_eq!(input.position(), 0);
+			binary
+		};
+
+		assert!(!detect(b"{\"block\":{\"header\":{}},\"justification\":null}"));
+		assert!(!detect(b"\n {\"block\":{}}"));
+		assert!(!detect(b""));
+		// the number of blocks, then the blocks.
+		let mut binary = 123u64.encode();
+		binary.extend_from_slice(b"block");
+		assert!(detect(&binary));
+	}
+
+	#[test]
+	fn records_checkpoints() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("blocks.checkpoint");
+
+		let mut checkpoint = Checkpoint::<[u8; 2]>::open(path.clone()).unwrap();
+		assert_eq!(checkpoint.imported_blocks, 0);
+		assert_eq!(checkpoint.last_block, None);
+		checkpoint.write(1234, [0xab, 0xcd]);
+		assert_eq!(fs::read_to_string(&path).unwrap(), "1234 0xabcd\n");
+		let reopened = Checkpoint::<[u8; 2]>::open(path.clone()).unwrap();
+		assert_eq!(reopened.imported_blocks, 1234);
+		assert_eq!(reopened.last_block, Some([0xab, 0xcd]));
+
+		checkpoint.remove();
+		assert!(!path.exists());
+		for invalid in &["not a number", "1234", "1234 0xabcd 1"] {
+			fs::write(&path, invalid).unwrap();
+			assert!(Checkpoint::<[u8; 2]>::open(path.clone()).is_err());
+		}
+	}
+
+	#[test]
+	fn formats_durations() {
+		assert_eq!(format_duration(Duration::from_secs(42)), "42s");
+		assert_eq!(format_duration(Duration::from_secs(185)), "3m 05s");
+		assert_eq!(format_duration(Duration::from_secs(3723)), "1h 02m 03s");
+	}
+}
@@ -18,14 +18,22 @@
 
 //! Chain utilities.
 
+mod benchmark_storage;
+mod binary_state;
 mod check_block;
+mod check_database;
 mod export_blocks;
 mod export_raw_state;
 mod import_blocks;
 mod revert_chain;
+mod storage_report;
 
+pub use benchmark_storage::*;
+pub use binary_state::*;
 pub use check_block::*;
+pub use check_database::*;
 pub use export_blocks::*;
 pub use export_raw_state::*;
 pub use import_blocks::*;
 pub use revert_chain::*;
+pub use storage_report::*;
@@ -22,24 +22,34 @@ use sp_runtime::traits::{Block as BlockT, NumberFor, Zero};
 use sc_client_api::{Backend, UsageProvider};
 use std::sync::Arc;
 
-/// Performs a revert of `blocks` blocks.
+/// Performs a revert of `blocks` blocks, reverting the finalized ones only if
+/// `revert_finalized` is set. Returns the number of blocks actually reverted.
 pub fn revert_chain<B, BA, C>(
 	client: Arc<C>,
 	backend: Arc<BA>,
-	blocks: NumberFor<B>
-) -> Result<(), Error>
+	blocks: NumberFor<B>,
+	revert_finalized: bool,
+) -> Result<NumberFor<B>, Error>
 where
 	B: BlockT,
 	C: UsageProvider<B>,
 	BA: Backend<B>,
 {
-	let reverted = backend.revert(blocks, false)?;
+	let reverted = backend.revert(blocks, revert_finalized)?;
 	let info = client.usage_info().chain;
 
 	if reverted.0.is_zero() {
 		info!("There aren't any non-finalized blocks to revert.");
+	} else if !reverted.1.is_empty() {
+		info!(
+			"Reverted {} blocks, {} of them finalized. Best and finalized: #{} ({})",
+			reverted.0,
+			reverted.1.len(),
+			info.best_number,
+			info.best_hash,
+		);
 	} else {
 		info!("Reverted {} blocks. Best: #{} ({})", reverted.0, info.best_number, info.best_hash);
 	}
-	Ok(())
+	Ok(reverted.0)
 }
@@ -0,0 +1,222 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+
+use crate::error::Error;
+use log::info;
+use sc_client_api::{Backend, backend::StateBackend};
+use sp_core::{hexdisplay::HexDisplay, storage::{ChildInfo, well_known_keys}, twox_128};
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+/// The names of the FRAME pallets whose storage is reported under their name, unless another
+/// name is given for their prefix.
+const KNOWN_PALLETS: &[&str] = &[
+	"Assets", "Aura", "AuthorityDiscovery", "Authorship", "Babe", "Balances", "Bounties",
+	"Contracts", "Council", "Democracy", "ElectionProviderMultiPhase", "Elections",
+	"Gilt", "Grandpa", "GrandpaFinality", "Historical", "Identity", "ImOnline", "Indices",
+	"Lottery", "Mmr", "Multisig", "Nicks", "Offences", "PhragmenElection", "Proxy",
+	"RandomnessCollectiveFlip", "Recovery", "Scheduler", "Session", "Society", "Staking",
+	"Sudo", "System", "TechnicalCommittee", "TechnicalMembership", "Timestamp", "Tips",
+	"TransactionPayment", "Treasury", "Uniques", "Utility", "Vesting",
+];
+
+/// The name the storage of the child tries is reported under.
+const CHILD_TRIES: &str = "Child tries";
+
+/// The length of the prefix of the storage keys of a pallet.
+const PALLET_PREFIX_LEN: usize = 16;
+
+/// The storage used under a key prefix.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PrefixUsage {
+	/// The name of the prefix, or the prefix in hex if it isn't known.
+	pub name: String,
+	/// Number of keys.
+	pub keys: u64,
+	/// Total size of the keys, in bytes.
+	pub key_bytes: u64,
+	/// Total size of the values, in bytes.
+	pub value_bytes: u64,
+}
+
+impl PrefixUsage {
+	/// Returns the total size of the keys and values, in bytes.
+	pub fn total_bytes(&self) -> u64 {
+		self.key_bytes + self.value_bytes
+	}
+
+	fn add(&mut self, key: &[u8], value: &[u8]) {
+		self.keys += 1;
+		self.key_bytes += key.len() as u64;
+		self.value_bytes += value.len() as u64;
+	}
+}
+
+/// The storage used by a state, by key prefix, see `storage_report`.
+#[derive(Debug, Clone, Default)]
+pub struct StorageReport {
+	/// The usage of every prefix, the largest ones first.
+	pub prefixes: Vec<PrefixUsage>,
+}
+
+impl StorageReport {
+	/// Returns the usage of the whole state.
+	pub fn total(&self) -> PrefixUsage {
+		self.prefixes.iter().fold(
+			PrefixUsage { name: "Total".into(), ..Default::default() },
+			|mut total, usage| {
+				total.keys += usage.keys;
+				total.key_bytes += usage.key_bytes;
+				total.value_bytes += usage.value_bytes;
+				total
+			},
+		)
+	}
+}
+
+/// Names the prefixes of the storage keys.
+struct Prefixes(Vec<(Vec<u8>, String)>);
+
+impl Prefixes {
+	/// The given named prefixes take precedence over the known ones.
+	fn new(named: &[(Vec<u8>, String)]) -> Self {
+		let mut prefixes = named.to_vec();
+		let well_known = [
+			(well_known_keys::CODE, ":code"),
+			(well_known_keys::HEAP_PAGES, ":heappages"),
+			(well_known_keys::EXTRINSIC_INDEX, ":extrinsic_index"),
+			(well_known_keys::CHANGES_TRIE_CONFIG, ":changes_trie"),
+			(well_known_keys::CHILD_STORAGE_KEY_PREFIX, CHILD_TRIES),
+		];
+		prefixes.extend(well_known.iter().map(|(key, name)| (key.to_vec(), name.to_string())));
+		prefixes.extend(
+			KNOWN_PALLETS.iter().map(|name| (twox_128(name.as_bytes()).to_vec(), name.to_string()))
+		);
+		// the longest prefixes first, so they're matched before the prefixes they start with.
+		prefixes.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()));
+		Prefixes(prefixes)
+	}
+
+	/// Returns the name of the prefix of the given key, the first bytes of the key in hex if
+	/// the prefix isn't known.
+	fn name(&self, key: &[u8]) -> String {
+		let named = self.0.iter().find(|(prefix, _)| key.starts_with(prefix));
+		match named {
+			Some((_, name)) => name.clone(),
+			None => {
+				let prefix = &key[..std::cmp::min(key.len(), PALLET_PREFIX_LEN)];
+				format!("0x{}", HexDisplay::from(&prefix))
+			},
+		}
+	}
+}
+
+/// Walk the state at the given block, including its child tries, reporting the number and size
+/// of the keys and values under every prefix.
+///
+/// The keys of the FRAME pallets and the well-known keys are reported under their name, as well
+/// as the keys under the given named prefixes, which take precedence. The other keys are
+/// reported under their first 16 bytes, the length of the prefixes of the pallets.
+pub fn storage_report<B, BA>(
+	backend: &BA,
+	block: BlockId<B>,
+	named_prefixes: &[(Vec<u8>, String)],
+) -> Result<StorageReport, Error> where
+	B: BlockT,
+	BA: Backend<B>,
+{
+	let state = backend.state_at(block)?;
+	let prefixes = Prefixes::new(named_prefixes);
+	let mut usages = HashMap::<String, PrefixUsage>::new();
+	let mut usage = |name: String, key: &[u8], value: &[u8]| usages.entry(name.clone())
+		.or_insert_with(|| PrefixUsage { name, ..Default::default() })
+		.add(key, value);
+
+	let error = |e| Error::Other(format!("Error reading the state: {}", e));
+	let mut keys = 0u64;
+	let mut key = Vec::new();
+	while let Some(next) = state.next_storage_key(&key).map_err(error)? {
+		let value = state.storage(&next).map_err(error)?.unwrap_or_default();
+		usage(prefixes.name(&next), &next, &value);
+
+		if next.starts_with(well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX) {
+			let storage_key = &next[well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX.len()..];
+			let child_info = ChildInfo::new_default(storage_key);
+			let mut child_key = Vec::new();
+			while let Some(next) = state.next_child_storage_key(&child_info, &child_key)
+				.map_err(error)?
+			{
+				let value = state.child_storage(&child_info, &next).map_err(error)?
+					.unwrap_or_default();
+				usage(CHILD_TRIES.into(), &next, &value);
+				child_key = next;
+			}
+		}
+
+		keys += 1;
+		if keys % 100_000 == 0 {
+			info!("Read {} keys", keys);
+		}
+		key = next;
+	}
+
+	let mut prefixes = usages.into_iter().map(|(_, usage)| usage).collect::<Vec<_>>();
+	prefixes.sort_by(|a, b| b.total_bytes().cmp(&a.total_bytes()).then(a.name.cmp(&b.name)));
+	Ok(StorageReport { prefixes })
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use substrate_test_runtime_client::{
+		DefaultTestClientBuilderExt, TestClientBuilder, TestClientBuilderExt,
+		runtime::Block,
+	};
+
+	#[test]
+	fn reports_the_storage_by_prefix() {
+		let child_info = ChildInfo::new_default(b"child");
+		let mut index_key = twox_128(b"Index").to_vec();
+		index_key.extend_from_slice(b"key");
+		let builder = TestClientBuilder::new()
+			.add_extra_storage(index_key, vec![1, 2, 3])
+			.add_extra_child_storage(&child_info, b"key".to_vec(), vec![1]);
+		let backend = builder.backend();
+		let _client = builder.build();
+
+		let named = vec![(twox_128(b"Index").to_vec(), "Index".to_string())];
+		let report = storage_report::<Block, _>(&*backend, BlockId::Number(0), &named).unwrap();
+		let usage = |name: &str| report.prefixes.iter().find(|usage| usage.name == name).cloned();
+
+		assert_eq!(usage("Index"), Some(PrefixUsage {
+			name: "Index".into(),
+			keys: 1,
+			key_bytes: 19,
+			value_bytes: 3,
+		}));
+		// the root of the child trie and its key.
+		assert_eq!(usage(CHILD_TRIES).unwrap().keys, 2);
+		assert_eq!(usage(":code").unwrap().keys, 1);
+		assert!(report.prefixes.windows(2).all(|w| w[0].total_bytes() >= w[1].total_bytes()));
+		assert_eq!(
+			report.total().keys,
+			report.prefixes.iter().map(|usage| usage.keys).sum::<u64>(),
+		);
+	}
+}
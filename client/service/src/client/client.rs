@@ -20,11 +20,11 @@
 
 use std::{
 	marker::PhantomData,
-	collections::{HashSet, BTreeMap, HashMap},
+	collections::{HashSet, BTreeMap, HashMap, hash_map::Entry},
 	sync::Arc, panic::UnwindSafe, result,
 	path::PathBuf
 };
-use log::{info, trace, warn};
+use log::{debug, info, trace, warn};
 use parking_lot::{Mutex, RwLock};
 use codec::{Encode, Decode};
 use hash_db::Prefix;
@@ -46,8 +46,8 @@ use sp_runtime::{
 };
 use sp_state_machine::{
 	DBValue, Backend as StateBackend, ChangesTrieAnchorBlockId,
-	prove_read, prove_child_read, ChangesTrieRootsStorage, ChangesTrieStorage,
-	ChangesTrieConfigurationRange, key_changes, key_changes_proof,
+	prove_read, prove_child_read, prove_range_read_with_size, ChangesTrieRootsStorage,
+	ChangesTrieStorage, ChangesTrieConfigurationRange, key_changes, key_changes_proof,
 };
 use sc_executor::RuntimeVersion;
 use sp_consensus::{
@@ -61,7 +61,7 @@ use sp_blockchain::{
 	well_known_cache_keys::Id as CacheKeyId,
 	HeaderMetadata, CachedHeaderMetadata,
 };
-use sp_trie::StorageProof;
+use sp_trie::{StorageProof, CompactProof};
 use sp_api::{
 	CallApiAt, ConstructRuntimeApi, Core as CoreApi, ApiExt, ApiRef, ProvideRuntimeApi,
 	CallApiAtParams,
@@ -71,20 +71,21 @@ use sc_client_api::{
 	backend::{
 		self, BlockImportOperation, PrunableStateChangesTrieStorage,
 		ClientImportOperation, Finalizer, ImportSummary, NewBlockState,
-		changes_tries_state_at_block, StorageProvider,
+		changes_tries_state_at_block, StorageProvider, StateProvider,
 		LockImportRun, apply_aux,
 	},
 	client::{
 		ImportNotifications, FinalityNotification, FinalityNotifications, BlockImportNotification,
 		ClientInfo, BlockchainEvents, BlockBackend, ProvideUncles, BadBlocks, ForkBlocks,
-		BlockOf,
+		BlockOf, UnpinHandle, RuntimeUpgradeEvents, RuntimeUpgradeNotification,
+		RuntimeUpgradeNotifications,
 	},
 	execution_extensions::ExecutionExtensions,
 	notifications::{StorageNotifications, StorageEventStream},
-	KeyIterator, CallExecutor, ExecutorProvider, ProofProvider,
+	KeyIterator, CallExecutor, ExecutorProvider, ProofProvider, StateChunk,
 	cht, UsageProvider
 };
-use sp_utils::mpsc::{TracingUnboundedSender, tracing_unbounded};
+use sp_utils::mpsc::{TracingUnboundedReceiver, TracingUnboundedSender, tracing_unbounded};
 use sp_blockchain::Error;
 use prometheus_endpoint::Registry;
 use super::{
@@ -103,13 +104,85 @@ use {
 
 type NotificationSinks<T> = Mutex<Vec<TracingUnboundedSender<T>>>;
 
+/// The sinks of the notifications pinning their block, with the subscriber they belong to.
+type PinningNotificationSinks<T> = Mutex<Vec<(u64, TracingUnboundedSender<T>)>>;
+
+/// Maximum number of blocks pinned for the notifications of a single subscriber. Past it,
+/// the oldest block pinned for the subscriber is unpinned.
+const MAX_PINNED_BLOCKS_PER_SUBSCRIBER: usize = 64;
+
+/// Maximum number of blocks pinned for all the notifications. Past it, the oldest block
+/// pinned is unpinned.
+const MAX_PINNED_BLOCKS: usize = 512;
+
+/// The pins of the blocks of the notifications in use, see `Client::pin_for_notification`.
+#[derive(Default)]
+struct NotificationPins<Hash> {
+	next_pin: u64,
+	next_subscriber: u64,
+	/// The pinned blocks with the subscriber they were sent to, by pin, the oldest first.
+	pins: BTreeMap<u64, (Hash, u64)>,
+	/// The number of pins of each subscriber holding any.
+	subscribers: HashMap<u64, usize>,
+}
+
+impl<Hash: Copy> NotificationPins<Hash> {
+	fn new_subscriber(&mut self) -> u64 {
+		self.next_subscriber += 1;
+		self.next_subscriber
+	}
+
+	fn insert(&mut self, hash: Hash, subscriber: u64) -> u64 {
+		self.next_pin += 1;
+		self.pins.insert(self.next_pin, (hash, subscriber));
+		*self.subscribers.entry(subscriber).or_default() += 1;
+		self.next_pin
+	}
+
+	/// Remove the given pin, returning its block if it wasn't removed already.
+	fn remove(&mut self, pin: u64) -> Option<Hash> {
+		let (hash, subscriber) = self.pins.remove(&pin)?;
+		if let Entry::Occupied(mut pins) = self.subscribers.entry(subscriber) {
+			*pins.get_mut() -= 1;
+			if *pins.get() == 0 {
+				pins.remove();
+			}
+		}
+		Some(hash)
+	}
+
+	/// Returns the oldest pin of the given subscriber if it reached its limit, or else the
+	/// oldest pin if all the pins reached theirs.
+	fn lagging_pin(&self, subscriber: u64) -> Option<u64> {
+		let pins = self.subscribers.get(&subscriber).copied().unwrap_or_default();
+		if pins > MAX_PINNED_BLOCKS_PER_SUBSCRIBER {
+			self.pins.iter()
+				.find(|(_, (_, pin_subscriber))| *pin_subscriber == subscriber)
+				.map(|(pin, _)| *pin)
+		} else if self.pins.len() > MAX_PINNED_BLOCKS {
+			self.pins.keys().next().copied()
+		} else {
+			None
+		}
+	}
+}
+
 /// Substrate Client
 pub struct Client<B, E, Block, RA> where Block: BlockT {
 	backend: Arc<B>,
 	executor: E,
 	storage_notifications: Mutex<StorageNotifications<Block>>,
-	import_notification_sinks: NotificationSinks<BlockImportNotification<Block>>,
-	finality_notification_sinks: NotificationSinks<FinalityNotification<Block>>,
+	import_notification_sinks: PinningNotificationSinks<BlockImportNotification<Block>>,
+	finality_notification_sinks: PinningNotificationSinks<FinalityNotification<Block>>,
+	runtime_upgrade_notification_sinks: NotificationSinks<RuntimeUpgradeNotification<Block>>,
+	// the hash of the `:code` and the runtime version at the last finalized block, tracked
+	// while there are runtime upgrade notification sinks.
+	finalized_runtime: Mutex<Option<(Option<Block::Hash>, RuntimeVersion)>>,
+	// the blocks pinned for notifications.
+	notification_pins: Mutex<NotificationPins<Block::Hash>>,
+	// receives the pins of the notifications, once they are dropped.
+	unpin_sender: TracingUnboundedSender<u64>,
+	unpin_receiver: Mutex<TracingUnboundedReceiver<u64>>,
 	// holds the block hash currently being imported. TODO: replace this with block queue
 	importing_block: RwLock<Option<Block::Hash>>,
 	block_rules: BlockRules<Block>,
@@ -316,12 +389,18 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 			backend.commit_operation(op)?;
 		}
 
+		let (unpin_sender, unpin_receiver) = tracing_unbounded("mpsc_unpin_notified_blocks");
 		Ok(Client {
 			backend,
 			executor,
 			storage_notifications: Mutex::new(StorageNotifications::new(prometheus_registry)),
 			import_notification_sinks: Default::default(),
 			finality_notification_sinks: Default::default(),
+			runtime_upgrade_notification_sinks: Default::default(),
+			finalized_runtime: Default::default(),
+			notification_pins: Default::default(),
+			unpin_sender,
+			unpin_receiver: Mutex::new(unpin_receiver),
 			importing_block: Default::default(),
 			block_rules: BlockRules::new(fork_blocks, bad_blocks),
 			execution_extensions,
@@ -332,13 +411,17 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 
 	/// returns a reference to the block import notification sinks
 	/// useful for test environments.
-	pub fn import_notification_sinks(&self) -> &NotificationSinks<BlockImportNotification<Block>> {
+	pub fn import_notification_sinks(
+		&self,
+	) -> &PinningNotificationSinks<BlockImportNotification<Block>> {
 		&self.import_notification_sinks
 	}
 
 	/// returns a reference to the finality notification sinks
 	/// useful for test environments.
-	pub fn finality_notification_sinks(&self) -> &NotificationSinks<FinalityNotification<Block>> {
+	pub fn finality_notification_sinks(
+		&self,
+	) -> &PinningNotificationSinks<FinalityNotification<Block>> {
 		&self.finality_notification_sinks
 	}
 
@@ -974,7 +1057,7 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 			// cleanup any closed finality notification sinks
 			// since we won't be running the loop below which
 			// would also remove any closed sinks.
-			sinks.retain(|sink| !sink.is_closed());
+			sinks.retain(|(_, sink)| !sink.is_closed());
 
 			return Ok(());
 		}
@@ -994,24 +1077,91 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 			);
 		}
 
+		let mut last_finalized = None;
 		for finalized_hash in notify_finalized {
 			let header = self.header(&BlockId::Hash(finalized_hash))?
 				.expect(
 					"Header already known to exist in DB because it is \
 					 indicated in the tree route; qed"
 				);
+			last_finalized = Some((finalized_hash, *header.number()));
 
 			let notification = FinalityNotification {
 				header,
 				hash: finalized_hash,
+				unpin_handle: None,
 			};
 
-			sinks.retain(|sink| sink.unbounded_send(notification.clone()).is_ok());
+			sinks.retain(|(subscriber, sink)| sink.unbounded_send(FinalityNotification {
+				unpin_handle: self.pin_for_notification(finalized_hash, *subscriber),
+				..notification.clone()
+			}).is_ok());
+		}
+		drop(sinks);
+
+		if let Some((hash, number)) = last_finalized {
+			self.notify_runtime_upgrade(hash, number);
 		}
 
 		Ok(())
 	}
 
+	/// Notify the runtime upgrade notification sinks if the `:code` changed at the given
+	/// finalized block.
+	fn notify_runtime_upgrade(&self, hash: Block::Hash, number: NumberFor<Block>) {
+		let mut sinks = self.runtime_upgrade_notification_sinks.lock();
+		sinks.retain(|sink| !sink.is_closed());
+		let mut finalized_runtime = self.finalized_runtime.lock();
+		if sinks.is_empty() {
+			// the runtime is only tracked while there are sinks.
+			*finalized_runtime = None;
+			return;
+		}
+
+		let runtime = match self.runtime_at(&BlockId::Hash(hash), finalized_runtime.as_ref()) {
+			Ok(Some(runtime)) => runtime,
+			Ok(None) => return,
+			Err(e) => {
+				warn!("Failed to check the runtime of the finalized block {}: {:?}", hash, e);
+				return;
+			},
+		};
+		if let Some((_, old_version)) = finalized_runtime.replace(runtime) {
+			let new_version = finalized_runtime.as_ref()
+				.map(|(_, version)| version.clone())
+				.expect("replaced just above; qed");
+			info!(
+				"⬆️  Runtime upgraded from {} to {} at block #{} ({})",
+				old_version, new_version, number, hash,
+			);
+			let notification = RuntimeUpgradeNotification {
+				hash,
+				number,
+				old_version,
+				new_version,
+			};
+			sinks.retain(|sink| sink.unbounded_send(notification.clone()).is_ok());
+		}
+	}
+
+	/// Returns the hash of the `:code` and the runtime version at the given block, `None` if
+	/// the `:code` has the given hash.
+	fn runtime_at(
+		&self,
+		id: &BlockId<Block>,
+		last: Option<&(Option<Block::Hash>, RuntimeVersion)>,
+	) -> sp_blockchain::Result<Option<(Option<Block::Hash>, RuntimeVersion)>> {
+		let code_hash = StorageProvider::storage_hash(
+			self,
+			id,
+			&StorageKey(well_known_keys::CODE.to_vec()),
+		)?;
+		match last {
+			Some((last_code_hash, _)) if *last_code_hash == code_hash => Ok(None),
+			_ => Ok(Some((code_hash, self.runtime_version_at(id)?))),
+		}
+	}
+
 	fn notify_imported(
 		&self,
 		notify_import: Option<ImportSummary<Block>>,
@@ -1027,7 +1177,7 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 				// from consensus code).
 				self.import_notification_sinks
 					.lock()
-					.retain(|sink| !sink.is_closed());
+					.retain(|(_, sink)| !sink.is_closed());
 
 				return Ok(());
 			}
@@ -1049,14 +1199,58 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 			header: notify_import.header,
 			is_new_best: notify_import.is_new_best,
 			tree_route: notify_import.tree_route.map(Arc::new),
+			unpin_handle: None,
 		};
 
 		self.import_notification_sinks.lock()
-			.retain(|sink| sink.unbounded_send(notification.clone()).is_ok());
+			.retain(|(subscriber, sink)| sink.unbounded_send(BlockImportNotification {
+				unpin_handle: self.pin_for_notification(notification.hash, *subscriber),
+				..notification.clone()
+			}).is_ok());
 
 		Ok(())
 	}
 
+	/// Pin the given block while its notification to the given subscriber is in use,
+	/// unpinning the blocks of the notifications dropped since.
+	///
+	/// The pins are capped per subscriber and in total, so a subscriber lagging behind
+	/// doesn't keep the blocks from being pruned forever: past the caps, the oldest blocks
+	/// are unpinned even though their notifications are still in use.
+	fn pin_for_notification(
+		&self,
+		hash: Block::Hash,
+		subscriber: u64,
+	) -> Option<UnpinHandle<Block>> {
+		self.unpin_notified_blocks();
+		if let Err(e) = self.backend.pin_block(&hash) {
+			debug!("Failed to pin block {:?} for its notification: {:?}", hash, e);
+			return None;
+		}
+
+		let mut notification_pins = self.notification_pins.lock();
+		let pin = notification_pins.insert(hash, subscriber);
+		while let Some(lagging) = notification_pins.lagging_pin(subscriber) {
+			if let Some(lagging) = notification_pins.remove(lagging) {
+				debug!("Unpinning block {:?} of a lagging notification subscriber", lagging);
+				self.backend.unpin_block(&lagging);
+			}
+		}
+		Some(UnpinHandle::new(hash, pin, self.unpin_sender.clone()))
+	}
+
+	/// Unpin the blocks of the notifications dropped so far.
+	fn unpin_notified_blocks(&self) {
+		let mut unpin_receiver = self.unpin_receiver.lock();
+		let mut notification_pins = self.notification_pins.lock();
+		while let Ok(Some(pin)) = unpin_receiver.try_next() {
+			// the pins of the lagging subscribers are already removed.
+			if let Some(hash) = notification_pins.remove(pin) {
+				self.backend.unpin_block(&hash);
+			}
+		}
+	}
+
 	/// Attempts to revert the chain by `n` blocks guaranteeing that no block is
 	/// reverted past the last finalized block. Returns the number of blocks
 	/// that were successfully reverted.
@@ -1169,6 +1363,17 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 			Default::default(),
 		))
 	}
+
+	/// Compact the given proof of the state of the given block.
+	fn compact_proof(
+		&self,
+		id: &BlockId<Block>,
+		proof: StorageProof,
+	) -> sp_blockchain::Result<CompactProof> {
+		let state_root = *self.backend.blockchain().expect_header(*id)?.state_root();
+		proof.into_compact_proof::<HashFor<Block>>(state_root)
+			.map_err(|e| sp_blockchain::Error::CompactProof(e.to_string()))
+	}
 }
 
 impl<B, E, Block, RA> UsageProvider<Block> for Client<B, E, Block, RA> where
@@ -1239,6 +1444,48 @@ impl<B, E, Block, RA> ProofProvider<Block> for Client<B, E, Block, RA> where
 		})
 	}
 
+	fn read_with_proof(
+		&self,
+		id: &BlockId<Block>,
+		keys: &mut dyn Iterator<Item=&[u8]>,
+	) -> sp_blockchain::Result<(Vec<Option<Vec<u8>>>, CompactProof)> {
+		let keys = keys.map(|key| key.to_vec()).collect::<Vec<_>>();
+		let state = self.state_at(id)?;
+		let values = keys.iter()
+			.map(|key| state.storage(key))
+			.collect::<Result<Vec<_>, _>>()
+			.map_err(|e| sp_blockchain::Error::from_state(Box::new(e)))?;
+		let proof = self.read_proof(id, &mut keys.iter().map(|key| &key[..]))?;
+		Ok((values, self.compact_proof(id, proof)?))
+	}
+
+	fn execute_with_proof(
+		&self,
+		id: &BlockId<Block>,
+		method: &str,
+		call_data: &[u8],
+	) -> sp_blockchain::Result<(Vec<u8>, CompactProof)> {
+		let (result, proof) = self.execution_proof(id, method, call_data)?;
+		Ok((result, self.compact_proof(id, proof)?))
+	}
+
+	fn read_state_chunk(
+		&self,
+		id: &BlockId<Block>,
+		child_info: Option<&ChildInfo>,
+		start_at: Option<&[u8]>,
+		size_limit: usize,
+	) -> sp_blockchain::Result<StateChunk> {
+		let state = self.state_at(id)?;
+		let (entries, complete, proof) = prove_range_read_with_size::<_, HashFor<Block>>(
+			state,
+			child_info,
+			start_at,
+			size_limit,
+		).map_err(|e| sp_blockchain::Error::Backend(e.to_string()))?;
+		Ok(StateChunk { entries, complete, proof: self.compact_proof(id, proof)? })
+	}
+
 	fn header_proof(&self, id: &BlockId<Block>) -> sp_blockchain::Result<(Block::Header, StorageProof)> {
 		self.header_proof_with_cht_size(id, cht::size())
 	}
@@ -1322,6 +1569,18 @@ impl<B, E, Block, RA> ExecutorProvider<Block> for Client<B, E, Block, RA> where
 	}
 }
 
+impl<B, E, Block, RA> StateProvider<Block> for Client<B, E, Block, RA> where
+	B: backend::Backend<Block>,
+	E: CallExecutor<Block>,
+	Block: BlockT,
+{
+	type State = B::State;
+
+	fn state_at(&self, id: &BlockId<Block>) -> sp_blockchain::Result<B::State> {
+		self.backend.state_at(*id)
+	}
+}
+
 impl<B, E, Block, RA> StorageProvider<Block, B> for Client<B, E, Block, RA> where
 	B: backend::Backend<Block>,
 	E: CallExecutor<Block>,
@@ -1362,6 +1621,21 @@ impl<B, E, Block, RA> StorageProvider<Block, B> for Client<B, E, Block, RA> wher
 		Ok(KeyIterator::new(state, prefix, start_key))
 	}
 
+	fn child_storage_keys_iter<'a>(
+		&self,
+		id: &BlockId<Block>,
+		child_info: ChildInfo,
+		prefix: Option<&'a StorageKey>,
+		start_key: Option<&StorageKey>
+	) -> sp_blockchain::Result<KeyIterator<'a, B::State, Block>> {
+		let state = self.state_at(id)?;
+		let start_key = start_key
+			.or(prefix)
+			.map(|key| key.0.clone())
+			.unwrap_or_else(Vec::new);
+		Ok(KeyIterator::new_child(state, child_info, prefix, start_key))
+	}
+
 
 	fn storage(
 		&self,
@@ -1645,6 +1919,7 @@ impl<B, E, Block, RA> CallApiAt<Block> for Client<B, E, Block, RA> where
 		let (manager, extensions) = self.execution_extensions.manager_and_extensions(
 			at,
 			params.context,
+			params.function,
 		);
 
 		self.executor.contextual_call::<_, fn(_,_) -> _,_,_>(
@@ -1863,6 +2138,28 @@ impl<B, E, Block, RA> Finalizer<Block, B> for &Client<B, E, Block, RA> where
 	}
 }
 
+impl<B, E, Block, RA> RuntimeUpgradeEvents<Block> for Client<B, E, Block, RA>
+where
+	B: backend::Backend<Block>,
+	E: CallExecutor<Block>,
+	Block: BlockT,
+{
+	fn runtime_upgrade_notification_stream(&self) -> RuntimeUpgradeNotifications<Block> {
+		let (sink, stream) = tracing_unbounded("mpsc_runtime_upgrade_notification_stream");
+		let mut sinks = self.runtime_upgrade_notification_sinks.lock();
+		let mut finalized_runtime = self.finalized_runtime.lock();
+		if finalized_runtime.is_none() {
+			let finalized = BlockId::Hash(self.backend.blockchain().info().finalized_hash);
+			match self.runtime_at(&finalized, None) {
+				Ok(runtime) => *finalized_runtime = runtime,
+				Err(e) => warn!("Failed to check the runtime of the finalized block: {:?}", e),
+			}
+		}
+		sinks.push(sink);
+		stream
+	}
+}
+
 impl<B, E, Block, RA> BlockchainEvents<Block> for Client<B, E, Block, RA>
 where
 	E: CallExecutor<Block>,
@@ -1871,13 +2168,15 @@ where
 	/// Get block import event stream.
 	fn import_notification_stream(&self) -> ImportNotifications<Block> {
 		let (sink, stream) = tracing_unbounded("mpsc_import_notification_stream");
-		self.import_notification_sinks.lock().push(sink);
+		let subscriber = self.notification_pins.lock().new_subscriber();
+		self.import_notification_sinks.lock().push((subscriber, sink));
 		stream
 	}
 
 	fn finality_notification_stream(&self) -> FinalityNotifications<Block> {
 		let (sink, stream) = tracing_unbounded("mpsc_finality_notification_stream");
-		self.finality_notification_sinks.lock().push(sink);
+		let subscriber = self.notification_pins.lock().new_subscriber();
+		self.finality_notification_sinks.lock().push((subscriber, sink));
 		stream
 	}
 
@@ -1931,6 +2230,13 @@ impl<B, E, Block, RA> BlockBackend<Block> for Client<B, E, Block, RA>
 	fn have_extrinsic(&self, hash: &Block::Hash) -> sp_blockchain::Result<bool> {
 		self.backend.blockchain().have_extrinsic(hash)
 	}
+
+	fn extrinsic_location(
+		&self,
+		hash: &Block::Hash,
+	) -> sp_blockchain::Result<Option<(Block::Hash, u32)>> {
+		self.backend.blockchain().extrinsic_location(hash)
+	}
 }
 
 impl<B, E, Block, RA> backend::AuxStore for Client<B, E, Block, RA>
@@ -1998,3 +2304,39 @@ impl<BE, E, B, RA> sp_consensus::block_validation::Chain<B> for Client<BE, E, B,
 		Client::block_status(self, id).map_err(|e| Box::new(e) as Box<_>)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn notification_pins_unpin_the_lagging_subscribers() {
+		let mut pins = NotificationPins::<u64>::default();
+		let (lagging, other) = (pins.new_subscriber(), pins.new_subscriber());
+
+		let first = pins.insert(0, lagging);
+		for hash in 1 .. MAX_PINNED_BLOCKS_PER_SUBSCRIBER as u64 {
+			pins.insert(hash, lagging);
+		}
+		assert_eq!(pins.lagging_pin(lagging), None);
+		pins.insert(1000, other);
+		pins.insert(MAX_PINNED_BLOCKS_PER_SUBSCRIBER as u64, lagging);
+		assert_eq!(pins.lagging_pin(other), None);
+		assert_eq!(pins.lagging_pin(lagging), Some(first));
+		assert_eq!(pins.remove(first), Some(0));
+		assert_eq!(pins.remove(first), None);
+		assert_eq!(pins.lagging_pin(lagging), None);
+
+		// past the total cap, the oldest pin is unpinned whoever it belongs to.
+		let mut pins = NotificationPins::<u64>::default();
+		let first = pins.insert(0, pins.next_subscriber);
+		for hash in 1 ..= MAX_PINNED_BLOCKS as u64 {
+			let subscriber = pins.new_subscriber();
+			pins.insert(hash, subscriber);
+		}
+		assert_eq!(pins.lagging_pin(0), Some(first));
+		assert_eq!(pins.remove(first), Some(0));
+		assert_eq!(pins.lagging_pin(0), None);
+		assert!(pins.subscribers.get(&0).is_none());
+	}
+}
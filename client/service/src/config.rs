@@ -20,18 +20,40 @@
 
 pub use sc_client_db::{
 	Database, PruningMode, DatabaseSettingsSrc as DatabaseConfig,
-	KeepBlocks, TransactionStorageMode
+	KeepBlocks, TransactionStorageMode, ColdStorageSettings as ColdStorageConfig, DatabasePart,
 };
 pub use sc_network::Multiaddr;
 pub use sc_network::config::{ExtTransport, MultiaddrWithPeerId, NetworkConfiguration, Role, NodeKeyConfig};
-pub use sc_executor::WasmExecutionMethod;
-use sc_client_api::execution_extensions::ExecutionStrategies;
+pub use sc_executor::{WasmExecutionMethod, WasmFeatures, HostFunctionSets, DEFAULT_MAX_RUNTIMES};
+#[cfg(feature = "wasmtime")]
+pub use sc_executor::{WasmtimeConfig, WasmtimeMemoryStrategy, WasmtimeOptLevel};
+use sc_client_api::execution_extensions::{ExecutionStrategies, ExecutionMemoryLimits};
 
-use std::{io, future::Future, path::{PathBuf, Path}, pin::Pin, net::SocketAddr, sync::Arc};
+use std::{
+	io, future::Future, path::{PathBuf, Path}, pin::Pin, net::{IpAddr, SocketAddr}, time::Duration,
+	sync::{Arc, atomic::{AtomicBool, Ordering}},
+};
 pub use sc_transaction_pool::txpool::Options as TransactionPoolOptions;
+pub use sc_transaction_pool::txpool::base_pool::{
+	Lane as TransactionPoolLane, Limit as TransactionPoolLimit,
+};
 use sc_chain_spec::ChainSpec;
 use sp_core::crypto::SecretString;
+use sp_inherents::{InherentDataProviders, ProvideInherentData};
 pub use sc_telemetry::TelemetryEndpoints;
+pub use sc_rpc_server::{
+	RateLimit as RpcRateLimit, RequestLimits as RpcRequestLimits, RpcAliases, RpcAuth,
+	RpcInterceptors, RpcMethodPolicy,
+};
+pub use sc_rpc::chain::TypeAliases as RpcTypeAliases;
+pub use sc_informant::{
+	DuplicateName as DuplicateInformantStatusProvider, OutputFormat as InformantOutputFormat,
+	OutputStyle as InformantOutputStyle, StatusProvider as InformantStatusProvider,
+	StatusProviders as InformantStatusProviders,
+};
+pub use prometheus_endpoint::{
+	Auth as PrometheusAuth, EndpointSecurity as PrometheusSecurity, TlsConfig as PrometheusTls,
+};
 use prometheus_endpoint::Registry;
 #[cfg(not(target_os = "unknown"))]
 use tempfile::TempDir;
@@ -61,36 +83,83 @@ pub struct Configuration {
 	pub state_cache_size: usize,
 	/// Size in percent of cache size dedicated to child tries
 	pub state_cache_child_ratio: Option<usize>,
+	/// Size of the trie node cache shared by all the blocks in Bytes, `0` disables it.
+	pub trie_cache_size: usize,
+	/// Read the trie nodes cached when the node was last stopped into the cache on startup.
+	pub trie_cache_warm_up: bool,
 	/// State pruning settings.
 	pub state_pruning: PruningMode,
 	/// Number of blocks to keep in the db.
 	pub keep_blocks: KeepBlocks,
 	/// Transaction storage scheme.
 	pub transaction_storage: TransactionStorageMode,
+	/// Maintain an index of the blocks the extrinsics are included in.
+	pub index_extrinsics: bool,
+	/// Directory the snapshots of the database are written to over RPC. `None` if disabled.
+	pub database_snapshot_dir: Option<PathBuf>,
+	/// Store the block bodies and justifications compressed.
+	pub compress_blocks: bool,
+	/// Move the bodies and justifications of the ancient blocks to a second database.
+	pub cold_storage: Option<ColdStorageConfig>,
+	/// Number of blocks behind the best block the blocks not finalized yet are canonicalized
+	/// at, discarding the state of the other forks. 4096 blocks by default.
+	pub canonicalization_delay: Option<u64>,
+	/// Adapt the canonicalization delay to the finality lag, up to the given number of blocks.
+	pub max_canonicalization_delay: Option<u64>,
 	/// Chain configuration.
 	pub chain_spec: Box<dyn ChainSpec>,
 	/// Wasm execution method.
 	pub wasm_method: WasmExecutionMethod,
+	/// The WebAssembly proposals the runtimes may use.
+	pub wasm_features: WasmFeatures,
 	/// Directory where local WASM runtimes live. These runtimes take precedence
 	/// over on-chain runtimes when the spec version matches. Set to `None` to
 	/// disable overrides (default).
 	pub wasm_runtime_overrides: Option<PathBuf>,
 	/// Execution strategies.
 	pub execution_strategies: ExecutionStrategies,
+	/// Memory limits of the runtime calls.
+	pub execution_memory_limits: ExecutionMemoryLimits,
 	/// RPC over HTTP binding address. `None` if disabled.
 	pub rpc_http: Option<SocketAddr>,
 	/// RPC over Websockets binding address. `None` if disabled.
 	pub rpc_ws: Option<SocketAddr>,
+	/// gRPC gateway binding address. `None` if disabled.
+	pub rpc_grpc: Option<SocketAddr>,
 	/// RPC over IPC binding path. `None` if disabled.
 	pub rpc_ipc: Option<String>,
+	/// File permissions of the IPC socket, e.g. `0o660`. `None` if default, only the
+	/// owner of the socket may connect.
+	pub rpc_ipc_permissions: Option<u32>,
 	/// Maximum number of connections for WebSockets RPC server. `None` if default.
 	pub rpc_ws_max_connections: Option<usize>,
 	/// CORS settings for HTTP & WS servers. `None` if all origins are allowed.
 	pub rpc_cors: Option<Vec<String>>,
 	/// RPC methods to expose (by default only a safe subset or all of them).
 	pub rpc_methods: RpcMethods,
+	/// Rate limit of the requests of every HTTP & WS RPC client. `None` if unlimited.
+	pub rpc_rate_limit: Option<RpcRateLimit>,
+	/// Reverse proxies trusted to report the IP of the HTTP & WS RPC clients.
+	pub rpc_trusted_proxies: Vec<IpAddr>,
+	/// Credential required to call the unsafe methods over HTTP & WS. `None` if not required.
+	pub rpc_auth: Option<RpcAuth>,
+	/// Access rules of individual methods over HTTP & WS, overriding `rpc_methods`.
+	pub rpc_method_policy: RpcMethodPolicy,
+	/// Limits of the batches and of the calls in flight of every RPC client.
+	pub rpc_request_limits: RpcRequestLimits,
+	/// Interceptors of the calls handled by every RPC server.
+	pub rpc_interceptors: RpcInterceptors,
+	/// Additional, possibly deprecated, names of the RPC methods.
+	pub rpc_aliases: RpcAliases,
+	/// Types of the type aliases of the runtime calls, to decode the extrinsics over RPC.
+	pub rpc_type_aliases: RpcTypeAliases,
 	/// Prometheus endpoint configuration. `None` if disabled.
 	pub prometheus_config: Option<PrometheusConfig>,
+	/// Address the `/health`, `/ready` and `/status` HTTP endpoints listen on. `None` if
+	/// disabled.
+	pub health_endpoint: Option<SocketAddr>,
+	/// Monitoring of the disk space of the database. `None` if disabled.
+	pub disk_space: Option<DiskSpaceConfig>,
 	/// Telemetry service URL. `None` if disabled.
 	pub telemetry_endpoints: Option<TelemetryEndpoints>,
 	/// External WASM transport for the telemetry. If `Some`, when connection to a telemetry
@@ -125,12 +194,72 @@ pub struct Configuration {
 	///
 	/// The default value is 8.
 	pub max_runtime_instances: usize,
+	/// The number of instances of each runtime created as soon as it is loaded.
+	pub preinstantiated_runtime_instances: usize,
+	/// The number of runtimes kept in the cache.
+	///
+	/// The default value is `DEFAULT_MAX_RUNTIMES`.
+	pub runtime_cache_size: usize,
+	/// The maximum size of the code of the runtimes kept in the cache, in bytes.
+	pub runtime_cache_max_code_size: Option<usize>,
+	/// Sets of host functions provided to the runtimes besides those of the executor, e.g.
+	/// custom crypto of the chain.
+	pub host_functions: HostFunctionSets,
 	/// Announce block automatically after they have been imported
 	pub announce_block: bool,
 	/// Base path of the configuration
 	pub base_path: Option<BasePath>,
 	/// Configuration of the output format that the informant uses.
-	pub informant_output_format: sc_informant::OutputFormat,
+	pub informant_output_format: InformantOutputFormat,
+	/// Hooks registering additional inherent data providers.
+	pub inherent_data_providers: InherentDataProvidersHooks,
+}
+
+/// A hook registering inherent data providers into the given registry.
+pub type InherentDataProvidersHook = Arc<
+	dyn Fn(&InherentDataProviders) -> Result<(), sp_inherents::Error> + Send + Sync
+>;
+
+/// Hooks registering additional inherent data providers (e.g. price oracles or
+/// randomness beacons), whose data is injected into every block authored by the
+/// service.
+///
+/// The hooks are run by [`new_inherent_data_providers`](crate::new_inherent_data_providers),
+/// which creates the registry that is handed to the consensus worker.
+#[derive(Clone, Default)]
+pub struct InherentDataProvidersHooks(Vec<InherentDataProvidersHook>);
+
+impl InherentDataProvidersHooks {
+	/// Add a hook that is run when the inherent data providers are created.
+	pub fn add_hook<F>(&mut self, hook: F) where
+		F: Fn(&InherentDataProviders) -> Result<(), sp_inherents::Error> + Send + Sync + 'static,
+	{
+		self.0.push(Arc::new(hook));
+	}
+
+	/// Add a hook registering a copy of the given provider.
+	pub fn add_provider<P>(&mut self, provider: P) where
+		P: ProvideInherentData + Clone + Send + Sync + 'static,
+	{
+		self.add_hook(move |providers| {
+			if providers.has_provider(provider.inherent_identifier()) {
+				return Ok(());
+			}
+
+			providers.register_provider(provider.clone())
+		});
+	}
+
+	/// Run all the hooks on the given registry.
+	pub fn register(&self, providers: &InherentDataProviders) -> Result<(), sp_inherents::Error> {
+		self.0.iter().try_for_each(|hook| hook(providers))
+	}
+}
+
+impl std::fmt::Debug for InherentDataProvidersHooks {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "InherentDataProvidersHooks({} hooks)", self.0.len())
+	}
 }
 
 /// Type for tasks spawned by the executor.
@@ -174,6 +303,50 @@ pub struct OffchainWorkerConfig {
 	pub indexing_enabled: bool,
 }
 
+/// Configuration of the monitoring of the disk space of the database.
+#[derive(Debug, Clone)]
+pub struct DiskSpaceConfig {
+	/// How often the disk space is checked.
+	pub interval: Duration,
+	/// The free space, in bytes, below which warnings are logged. Errors are logged once it
+	/// falls below a quarter of it.
+	pub warning_threshold: u64,
+	/// The free space, in bytes, below which the offchain workers are paused. `None` if they
+	/// are never paused.
+	pub pause_threshold: Option<u64>,
+	/// Whether the free space is below the pause threshold.
+	pub low_space: LowDiskSpace,
+}
+
+impl Default for DiskSpaceConfig {
+	fn default() -> Self {
+		DiskSpaceConfig {
+			interval: Duration::from_secs(60),
+			warning_threshold: 1024 * 1024 * 1024,
+			pause_threshold: None,
+			low_space: Default::default(),
+		}
+	}
+}
+
+/// Whether the free space of the disk of the database is below the pause threshold, in which
+/// case the non-essential writes, e.g. of the offchain workers, should be paused.
+///
+/// The clones share the state, so it can be checked by the components writing to the disk.
+#[derive(Debug, Clone, Default)]
+pub struct LowDiskSpace(Arc<AtomicBool>);
+
+impl LowDiskSpace {
+	/// Returns `true` if the non-essential writes should be paused.
+	pub fn is_low(&self) -> bool {
+		self.0.load(Ordering::Relaxed)
+	}
+
+	pub(crate) fn set(&self, low: bool) {
+		self.0.store(low, Ordering::Relaxed)
+	}
+}
+
 /// Configuration of the Prometheus endpoint.
 #[derive(Debug, Clone)]
 pub struct PrometheusConfig {
@@ -181,6 +354,8 @@ pub struct PrometheusConfig {
 	pub port: SocketAddr,
 	/// A metrics registry to use. Useful for setting the metric prefix.
 	pub registry: Registry,
+	/// The TLS and authentication settings of the endpoint.
+	pub security: PrometheusSecurity,
 }
 
 impl PrometheusConfig {
@@ -191,7 +366,8 @@ impl PrometheusConfig {
 		Self {
 			port,
 			registry: Registry::new_custom(Some("substrate".into()), None)
-				.expect("this can only fail if the prefix is empty")
+				.expect("this can only fail if the prefix is empty"),
+			security: Default::default(),
 		}
 	}
 }
@@ -362,3 +538,69 @@ impl TaskExecutor {
 		self.0(future, task_type)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_inherents::{InherentData, InherentIdentifier};
+
+	const ORACLE_ID: InherentIdentifier = *b"testorcl";
+
+	#[derive(Clone)]
+	struct Oracle(u64);
+
+	impl ProvideInherentData for Oracle {
+		fn inherent_identifier(&self) -> &'static InherentIdentifier {
+			&ORACLE_ID
+		}
+
+		fn provide_inherent_data(
+			&self,
+			inherent_data: &mut InherentData,
+		) -> Result<(), sp_inherents::Error> {
+			inherent_data.put_data(ORACLE_ID, &self.0)
+		}
+
+		fn error_to_string(&self, _: &[u8]) -> Option<String> {
+			None
+		}
+	}
+
+	#[test]
+	fn hooks_register_additional_providers() {
+		let mut hooks = InherentDataProvidersHooks::default();
+		hooks.add_provider(Oracle(42));
+
+		let providers = InherentDataProviders::new();
+		hooks.register(&providers).unwrap();
+
+		assert!(providers.has_provider(&ORACLE_ID));
+		let data = providers.create_inherent_data().unwrap();
+		assert_eq!(data.get_data::<u64>(&ORACLE_ID).unwrap(), Some(42));
+	}
+
+	#[test]
+	fn provider_hook_keeps_already_registered_provider() {
+		let mut hooks = InherentDataProvidersHooks::default();
+		hooks.add_provider(Oracle(42));
+
+		let providers = InherentDataProviders::new();
+		providers.register_provider(Oracle(1)).unwrap();
+		hooks.register(&providers).unwrap();
+
+		let data = providers.create_inherent_data().unwrap();
+		assert_eq!(data.get_data::<u64>(&ORACLE_ID).unwrap(), Some(1));
+	}
+
+	#[test]
+	fn hook_errors_are_returned() {
+		let mut hooks = InherentDataProvidersHooks::default();
+		hooks.add_hook(|_| Err("oracle unavailable".into()));
+		hooks.add_provider(Oracle(42));
+
+		let providers = InherentDataProviders::new();
+		assert!(hooks.register(&providers).is_err());
+		// the hooks after the failing one are not run.
+		assert!(!providers.has_provider(&ORACLE_ID));
+	}
+}
@@ -0,0 +1,161 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Monitoring of the disk space of the database.
+//!
+//! The free and total space of the disk of the database are exposed as gauges, its size is by
+//! the database itself. Warnings are logged once the free space falls below the warning
+//! threshold, errors once it falls below a quarter of it, and the offchain workers are paused
+//! below the pause threshold: a full disk can corrupt the database. The blocks are still
+//! imported, the node can't follow the chain without writing them.
+
+use std::{io, path::{Path, PathBuf}};
+
+use futures_timer::Delay;
+use log::{error, info, warn};
+use prometheus_endpoint::{register, Gauge, PrometheusError, Registry, U64};
+
+use crate::config::DiskSpaceConfig;
+
+struct Metrics {
+	available_space: Gauge<U64>,
+	total_space: Gauge<U64>,
+}
+
+impl Metrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Metrics {
+			available_space: register(Gauge::new(
+				"database_disk_available_bytes",
+				"Space available on the disk of the database",
+			)?, registry)?,
+			total_space: register(Gauge::new(
+				"database_disk_total_bytes",
+				"Total space of the disk of the database",
+			)?, registry)?,
+		})
+	}
+}
+
+/// How low the free space is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Level {
+	Normal,
+	Low,
+	Critical,
+}
+
+impl Level {
+	fn of(available: u64, warning_threshold: u64) -> Self {
+		if available < warning_threshold / 4 {
+			Level::Critical
+		} else if available < warning_threshold {
+			Level::Low
+		} else {
+			Level::Normal
+		}
+	}
+}
+
+/// Check the disk space of the database at the given path periodically.
+pub(crate) async fn run(path: PathBuf, config: DiskSpaceConfig, registry: Option<Registry>) {
+	let metrics = match registry.as_ref().map(Metrics::register).transpose() {
+		Ok(metrics) => metrics,
+		Err(e) => {
+			warn!("Failed to register the disk space metrics: {}", e);
+			None
+		},
+	};
+
+	loop {
+		match check(&path, &config) {
+			Ok((available, total)) => if let Some(metrics) = &metrics {
+				metrics.available_space.set(available);
+				metrics.total_space.set(total);
+			},
+			Err(e) => warn!("Failed to check the disk space of {}: {}", path.display(), e),
+		}
+		Delay::new(config.interval).await;
+	}
+}
+
+/// Check the disk space of the database, log it if it is low and pause the offchain workers
+/// if needed. Returns the free and the total space.
+fn check(path: &Path, config: &DiskSpaceConfig) -> io::Result<(u64, u64)> {
+	let available = fs2::available_space(path)?;
+	let total = fs2::total_space(path)?;
+
+	match Level::of(available, config.warning_threshold) {
+		Level::Normal => {},
+		Level::Low => warn!(
+			"💾 Only {} MiB left on the disk of the database at {}",
+			available / MIB,
+			path.display(),
+		),
+		Level::Critical => error!(
+			"💾 Only {} MiB left on the disk of the database at {}, free some space before \
+			the database gets corrupted",
+			available / MIB,
+			path.display(),
+		),
+	}
+
+	let pause = config.pause_threshold.map_or(false, |threshold| available < threshold);
+	if pause != config.low_space.is_low() {
+		config.low_space.set(pause);
+		if pause {
+			warn!("💾 Pausing the offchain workers until some disk space is freed");
+		} else {
+			info!("💾 Resuming the offchain workers");
+		}
+	}
+
+	Ok((available, total))
+}
+
+const MIB: u64 = 1024 * 1024;
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn escalates_below_the_warning_threshold() {
+		assert_eq!(Level::of(2000, 1000), Level::Normal);
+		assert_eq!(Level::of(999, 1000), Level::Low);
+		assert_eq!(Level::of(249, 1000), Level::Critical);
+	}
+
+	#[test]
+	fn pauses_the_writes_below_the_pause_threshold() {
+		let dir = tempfile::tempdir().unwrap();
+
+		let paused = DiskSpaceConfig {
+			pause_threshold: Some(u64::max_value()),
+			..Default::default()
+		};
+		let (available, total) = check(dir.path(), &paused).unwrap();
+		assert!(available <= total);
+		assert!(paused.low_space.is_low());
+
+		// the clones share whether the space is low.
+		let resumed = DiskSpaceConfig { pause_threshold: None, ..paused.clone() };
+		check(dir.path(), &resumed).unwrap();
+		assert!(!paused.low_space.is_low());
+	}
+}
@@ -0,0 +1,336 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! HTTP endpoints reporting the health, readiness and status of the node.
+//!
+//! `/health` answers `200 OK` as long as the network of the node is running, and `/ready`
+//! answers `200 OK` once the node is also synced and connected to peers (if it should have
+//! any). Both answer `503 Service Unavailable` otherwise. While the network is running, they
+//! return a JSON report of the node status, so probes don't have to speak JSON-RPC. `/status`
+//! adds the version and roles of the node and its best and finalized blocks to the report, for
+//! load balancers and dashboards that must not be given access to the RPC methods.
+//!
+//! The endpoints are read-only: requests other than `GET` and `HEAD` are answered with
+//! `405 Method Not Allowed`.
+
+use std::{net::SocketAddr, sync::Arc, time::Instant};
+
+use futures::{channel::oneshot, future, stream, FutureExt, StreamExt};
+use hyper::{
+	Body, Method, Request, Response, Server, StatusCode, header::{ALLOW, CONTENT_TYPE},
+	service::{make_service_fn, service_fn},
+};
+use log::{error, info};
+use parking_lot::Mutex;
+use sc_client_api::{blockchain::HeaderBackend, BlockchainEvents};
+use sc_rpc::system::{Health, Request as SystemRequest};
+use sp_runtime::{traits::Block as BlockT, SaturatedConversion};
+use sp_utils::mpsc::TracingUnboundedSender;
+
+/// The times of the last imported and finalized blocks.
+struct BlockTimes {
+	imported: Instant,
+	finalized: Instant,
+}
+
+/// The endpoints served.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Endpoint {
+	Health,
+	Ready,
+	Status,
+}
+
+impl Endpoint {
+	fn from_path(path: &str) -> Option<Self> {
+		match path {
+			"/health" => Some(Endpoint::Health),
+			"/ready" => Some(Endpoint::Ready),
+			"/status" => Some(Endpoint::Status),
+			_ => None,
+		}
+	}
+}
+
+/// Returns `true` if the node is synced and connected to peers, if it should have any.
+fn is_ready(health: &Health) -> bool {
+	!health.is_syncing && (health.peers > 0 || !health.should_have_peers)
+}
+
+/// Serve the endpoints on the given address until the client shuts down.
+///
+/// The given version of the node is reported by `/status`.
+pub(crate) async fn run<B, C>(
+	address: SocketAddr,
+	version: String,
+	client: Arc<C>,
+	system_rpc_tx: TracingUnboundedSender<SystemRequest<B>>,
+) where
+	B: BlockT,
+	C: BlockchainEvents<B> + HeaderBackend<B> + Send + Sync + 'static,
+{
+	let server = match Server::try_bind(&address) {
+		Ok(server) => server,
+		Err(e) => {
+			error!("Failed to start the health endpoints at {}: {}", address, e);
+			return;
+		},
+	};
+	info!("Health endpoints started at {}", address);
+
+	let now = Instant::now();
+	let blocks = Arc::new(Mutex::new(BlockTimes { imported: now, finalized: now }));
+
+	let version = Arc::new(version);
+	let service = {
+		let client = client.clone();
+		let blocks = blocks.clone();
+		make_service_fn(move |_| {
+			let version = version.clone();
+			let client = client.clone();
+			let blocks = blocks.clone();
+			let system_rpc_tx = system_rpc_tx.clone();
+			async move {
+				Ok::<_, hyper::Error>(service_fn(move |request: Request<Body>| respond(
+					request.method().clone(),
+					request.uri().path().to_owned(),
+					version.clone(),
+					client.clone(),
+					blocks.clone(),
+					system_rpc_tx.clone(),
+				)))
+			}
+		})
+	};
+	let server = server.serve(service).map(|result| if let Err(e) = result {
+		error!("Health endpoints failed: {}", e);
+	});
+
+	future::select(track_blocks(client, blocks).boxed(), server.boxed()).await;
+}
+
+/// Note the times blocks are imported and finalized at.
+async fn track_blocks<B, C>(client: Arc<C>, blocks: Arc<Mutex<BlockTimes>>) where
+	B: BlockT,
+	C: BlockchainEvents<B>,
+{
+	let imported = client.import_notification_stream().map(|_| true);
+	let finalized = client.finality_notification_stream().map(|_| false);
+	let mut notifications = stream::select(imported, finalized);
+
+	while let Some(is_import) = notifications.next().await {
+		let mut blocks = blocks.lock();
+		if is_import {
+			blocks.imported = Instant::now();
+		} else {
+			blocks.finalized = Instant::now();
+		}
+	}
+}
+
+/// Send the given request to the network and wait for its answer.
+async fn ask<B: BlockT, T>(
+	system_rpc_tx: &TracingUnboundedSender<SystemRequest<B>>,
+	request: impl FnOnce(oneshot::Sender<T>) -> SystemRequest<B>,
+) -> Option<T> {
+	let (tx, rx) = oneshot::channel();
+	let _ = system_rpc_tx.unbounded_send(request(tx));
+	rx.await.ok()
+}
+
+async fn respond<B, C>(
+	method: Method,
+	path: String,
+	version: Arc<String>,
+	client: Arc<C>,
+	blocks: Arc<Mutex<BlockTimes>>,
+	system_rpc_tx: TracingUnboundedSender<SystemRequest<B>>,
+) -> Result<Response<Body>, hyper::http::Error> where
+	B: BlockT,
+	C: HeaderBackend<B>,
+{
+	let endpoint = match Endpoint::from_path(&path) {
+		Some(endpoint) => endpoint,
+		None => return Response::builder()
+			.status(StatusCode::NOT_FOUND)
+			.body(Body::from("Not found.")),
+	};
+	if method != Method::GET && method != Method::HEAD {
+		return Response::builder()
+			.status(StatusCode::METHOD_NOT_ALLOWED)
+			.header(ALLOW, "GET, HEAD")
+			.body(Body::from("Method not allowed."));
+	}
+	let unavailable = || Response::builder()
+		.status(StatusCode::SERVICE_UNAVAILABLE)
+		.body(Body::from("Network unavailable."));
+
+	let health = match ask(&system_rpc_tx, SystemRequest::Health).await {
+		Some(health) => health,
+		None => return unavailable(),
+	};
+
+	let info = client.info();
+	let ready = is_ready(&health);
+	// the ages are counted from the start of the node until a block is imported (finalized).
+	let mut report = {
+		let blocks = blocks.lock();
+		serde_json::json!({
+			"peers": health.peers,
+			"isSyncing": health.is_syncing,
+			"shouldHavePeers": health.should_have_peers,
+			"bestNumber": info.best_number.saturated_into::<u64>(),
+			"finalizedNumber": info.finalized_number.saturated_into::<u64>(),
+			"lastImportedBlockAge": blocks.imported.elapsed().as_secs(),
+			"lastFinalizedBlockAge": blocks.finalized.elapsed().as_secs(),
+			"ready": ready,
+		})
+	};
+
+	if endpoint == Endpoint::Status {
+		let answers = future::join(
+			ask(&system_rpc_tx, SystemRequest::NodeRoles),
+			ask(&system_rpc_tx, SystemRequest::SyncState),
+		);
+		let (roles, sync) = match answers.await {
+			(Some(roles), Some(sync)) => (roles, sync),
+			_ => return unavailable(),
+		};
+		report["version"] = version.as_str().into();
+		report["roles"] = serde_json::json!(roles);
+		report["bestHash"] = serde_json::json!(info.best_hash);
+		report["finalizedHash"] = serde_json::json!(info.finalized_hash);
+		report["startingBlock"] = sync.starting_block.saturated_into::<u64>().into();
+		report["highestBlock"] =
+			serde_json::json!(sync.highest_block.map(|number| number.saturated_into::<u64>()));
+	}
+
+	let status = if endpoint == Endpoint::Ready && !ready {
+		StatusCode::SERVICE_UNAVAILABLE
+	} else {
+		StatusCode::OK
+	};
+	Response::builder()
+		.status(status)
+		.header(CONTENT_TYPE, "application/json")
+		.body(Body::from(report.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::executor::block_on;
+	use sc_rpc::system::{NodeRole, SyncState};
+	use sp_utils::mpsc::tracing_unbounded;
+	use substrate_test_runtime_client::runtime::Block;
+
+	/// Answer the given request to the endpoints for a node connected to one peer.
+	fn request(method: Method, path: &str) -> (StatusCode, serde_json::Value) {
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let now = Instant::now();
+		let blocks = Arc::new(Mutex::new(BlockTimes { imported: now, finalized: now }));
+		let (system_rpc_tx, mut system_rpc_rx) = tracing_unbounded::<SystemRequest<Block>>("test");
+		let network = async move {
+			while let Some(request) = system_rpc_rx.next().await {
+				match request {
+					SystemRequest::Health(sender) => {
+						let _ = sender.send(Health {
+							peers: 1,
+							is_syncing: false,
+							should_have_peers: true,
+						});
+					},
+					SystemRequest::NodeRoles(sender) => {
+						let _ = sender.send(vec![NodeRole::Full]);
+					},
+					SystemRequest::SyncState(sender) => {
+						let _ = sender.send(SyncState {
+							starting_block: 0,
+							current_block: 0,
+							highest_block: Some(7),
+						});
+					},
+					_ => panic!("unexpected request"),
+				}
+			}
+		};
+		let response = respond(
+			method,
+			path.to_owned(),
+			Arc::new("1.0.0".to_owned()),
+			client,
+			blocks,
+			system_rpc_tx,
+		);
+
+		let response = match block_on(future::select(response.boxed(), network.boxed())) {
+			future::Either::Left((response, _)) => response.unwrap(),
+			future::Either::Right(_) => unreachable!("the network never stops"),
+		};
+		let status = response.status();
+		let body = block_on(hyper::body::to_bytes(response.into_body())).unwrap();
+		(status, serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null))
+	}
+
+	#[test]
+	fn reports_the_status_of_the_node() {
+		let (status, report) = request(Method::GET, "/status");
+
+		assert_eq!(status, StatusCode::OK);
+		assert_eq!(report["version"], "1.0.0");
+		assert_eq!(report["roles"], serde_json::json!(["Full"]));
+		assert_eq!(report["peers"], 1);
+		assert_eq!(report["bestNumber"], 0);
+		assert_eq!(report["finalizedNumber"], 0);
+		assert!(report["bestHash"].is_string());
+		assert!(report["finalizedHash"].is_string());
+		assert_eq!(report["startingBlock"], 0);
+		assert_eq!(report["highestBlock"], 7);
+		assert_eq!(report["ready"], true);
+	}
+
+	#[test]
+	fn only_answers_reads() {
+		assert_eq!(request(Method::GET, "/health").0, StatusCode::OK);
+		assert_eq!(request(Method::HEAD, "/ready").0, StatusCode::OK);
+		assert_eq!(request(Method::POST, "/status").0, StatusCode::METHOD_NOT_ALLOWED);
+		assert_eq!(request(Method::GET, "/rpc").0, StatusCode::NOT_FOUND);
+	}
+
+	#[test]
+	fn serves_the_health_readiness_and_status() {
+		assert_eq!(Endpoint::from_path("/health"), Some(Endpoint::Health));
+		assert_eq!(Endpoint::from_path("/ready"), Some(Endpoint::Ready));
+		assert_eq!(Endpoint::from_path("/status"), Some(Endpoint::Status));
+		assert_eq!(Endpoint::from_path("/status/"), None);
+	}
+
+	#[test]
+	fn ready_once_synced_and_connected() {
+		let health = |peers, is_syncing, should_have_peers| Health {
+			peers,
+			is_syncing,
+			should_have_peers,
+		};
+
+		assert!(is_ready(&health(3, false, true)));
+		assert!(is_ready(&health(0, false, false)));
+		assert!(!is_ready(&health(0, false, true)));
+		assert!(!is_ready(&health(3, true, true)));
+	}
+}
@@ -0,0 +1,184 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Hooks run by [`build_network`](crate::build_network) once the client and the transaction
+//! pool are built, before the network and the consensus are started.
+//!
+//! They let downstream nodes register additional network protocols, RPC extensions and
+//! background tasks without copying the service builder.
+
+use std::sync::Arc;
+
+use jsonrpc_core::IoHandler;
+use parking_lot::Mutex;
+use sc_network::config::NetworkConfiguration;
+use sc_rpc::{DenyUnsafe, Metadata, RpcExtension, SubscriptionTaskExecutor};
+
+use crate::{config::Configuration, Error, RpcExtensionBuilder, SpawnTaskHandle};
+
+/// The components handed to the builder hooks.
+pub struct BuilderContext<'a, TCl, TExPool> {
+	/// The service configuration.
+	pub config: &'a Configuration,
+	/// The client.
+	pub client: &'a Arc<TCl>,
+	/// The transaction pool.
+	pub transaction_pool: &'a Arc<TExPool>,
+	/// The configuration the network is started with, e.g. to register additional
+	/// notifications or request-response protocols.
+	pub network_config: &'a mut NetworkConfiguration,
+	/// A handle for spawning background tasks.
+	pub spawn_handle: &'a SpawnTaskHandle,
+	rpc_extensions: &'a HookRpcExtensions,
+}
+
+impl<'a, TCl, TExPool> BuilderContext<'a, TCl, TExPool> {
+	/// Register an RPC extension, served by [`spawn_tasks`](crate::spawn_tasks) once it is given
+	/// the [`BuilderHooks::rpc_extensions`].
+	pub fn register_rpc_extension<F>(&self, builder: F) where
+		F: Fn(DenyUnsafe, SubscriptionTaskExecutor) -> IoHandler<Metadata> + Send + Sync + 'static,
+	{
+		self.rpc_extensions.0.lock().push(Arc::new(builder));
+	}
+}
+
+/// A hook run by the service builder.
+pub type BuilderHook<TCl, TExPool> = Box<
+	dyn for<'a> FnOnce(&mut BuilderContext<'a, TCl, TExPool>) -> Result<(), Error> + Send
+>;
+
+/// Hooks run by [`build_network`](crate::build_network) once the client and the transaction
+/// pool are built, before the network is created.
+///
+/// The RPC extensions registered by the hooks are served along with the other ones by passing
+/// [`BuilderHooks::rpc_extensions`] to [`spawn_tasks`](crate::spawn_tasks).
+pub struct BuilderHooks<TCl, TExPool> {
+	hooks: Vec<(&'static str, BuilderHook<TCl, TExPool>)>,
+	rpc_extensions: HookRpcExtensions,
+}
+
+impl<TCl, TExPool> Default for BuilderHooks<TCl, TExPool> {
+	fn default() -> Self {
+		BuilderHooks { hooks: Vec::new(), rpc_extensions: Default::default() }
+	}
+}
+
+impl<TCl, TExPool> BuilderHooks<TCl, TExPool> {
+	/// Add a hook with the given name, run after the previously added ones.
+	pub fn add_hook<F>(&mut self, name: &'static str, hook: F) where
+		F: for<'a> FnOnce(&mut BuilderContext<'a, TCl, TExPool>) -> Result<(), Error>
+			+ Send + 'static,
+	{
+		self.hooks.push((name, Box::new(hook)));
+	}
+
+	/// Returns the RPC extensions registered by the hooks.
+	pub fn rpc_extensions(&self) -> HookRpcExtensions {
+		self.rpc_extensions.clone()
+	}
+
+	/// Run all the hooks, in the order they were added in.
+	pub(crate) fn run(
+		self,
+		config: &Configuration,
+		client: &Arc<TCl>,
+		transaction_pool: &Arc<TExPool>,
+		network_config: &mut NetworkConfiguration,
+		spawn_handle: &SpawnTaskHandle,
+	) -> Result<(), Error> {
+		let BuilderHooks { hooks, rpc_extensions } = self;
+		let mut context = BuilderContext {
+			config,
+			client,
+			transaction_pool,
+			network_config,
+			spawn_handle,
+			rpc_extensions: &rpc_extensions,
+		};
+		for (name, hook) in hooks {
+			log::debug!("Running the builder hook `{}`", name);
+			hook(&mut context)
+				.map_err(|e| Error::Other(format!("Builder hook `{}` failed: {}", name, e)))?;
+		}
+		Ok(())
+	}
+}
+
+impl<TCl, TExPool> std::fmt::Debug for BuilderHooks<TCl, TExPool> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "BuilderHooks({} hooks)", self.hooks.len())
+	}
+}
+
+type HookRpcExtension =
+	Arc<dyn Fn(DenyUnsafe, SubscriptionTaskExecutor) -> IoHandler<Metadata> + Send + Sync>;
+
+/// The RPC extensions registered by the builder hooks.
+///
+/// The clones share the extensions, so it can be taken before the hooks are run.
+#[derive(Clone, Default)]
+pub struct HookRpcExtensions(Arc<Mutex<Vec<HookRpcExtension>>>);
+
+impl RpcExtensionBuilder for HookRpcExtensions {
+	type Output = IoHandler<Metadata>;
+
+	fn build(
+		&self,
+		deny: DenyUnsafe,
+		subscription_executor: SubscriptionTaskExecutor,
+	) -> Self::Output {
+		let mut io = IoHandler::default();
+		for extension in self.0.lock().iter() {
+			extension(deny, subscription_executor.clone()).augment(&mut io);
+		}
+		io
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use jsonrpc_core::Value;
+
+	#[test]
+	fn builds_the_rpc_extensions_of_all_the_hooks() {
+		let extensions = HookRpcExtensions::default();
+		extensions.0.lock().push(Arc::new(|_, _| {
+			let mut io = IoHandler::default();
+			io.add_method("hook_status", |_| Ok(Value::Bool(true)));
+			io
+		}));
+		// Registered through a clone, like the hooks do.
+		extensions.clone().0.lock().push(Arc::new(|_, _| {
+			let mut io = IoHandler::default();
+			io.add_method("other_hook_status", |_| Ok(Value::Bool(false)));
+			io
+		}));
+
+		let executor = SubscriptionTaskExecutor::new(sp_core::testing::TaskExecutor::new());
+		let io = extensions.build(DenyUnsafe::No, executor);
+		let call = |method| io.handle_request_sync(
+			&format!(r#"{{"jsonrpc":"2.0","method":"{}","id":1}}"#, method),
+		);
+		assert_eq!(call("hook_status"), Some(r#"{"jsonrpc":"2.0","result":true,"id":1}"#.into()));
+		assert_eq!(
+			call("other_hook_status"),
+			Some(r#"{"jsonrpc":"2.0","result":false,"id":1}"#.into()),
+		);
+	}
+}
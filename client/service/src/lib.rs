@@ -28,6 +28,11 @@ pub mod error;
 
 mod metrics;
 mod builder;
+mod hooks;
+#[cfg(not(target_os = "unknown"))]
+mod disk_space;
+#[cfg(not(target_os = "unknown"))]
+mod health;
 #[cfg(feature = "test-helpers")]
 pub mod client;
 #[cfg(not(feature = "test-helpers"))]
@@ -51,19 +56,23 @@ use sp_utils::{status_sinks, mpsc::{tracing_unbounded, TracingUnboundedReceiver}
 
 pub use self::error::Error;
 pub use self::builder::{
-	new_full_client, new_client, new_full_parts, new_light_parts,
-	spawn_tasks, build_network, build_offchain_workers,
+	new_full_client, new_client, new_full_parts, new_light_parts, new_inherent_data_providers,
+	new_transaction_pool_options, migrate_database, spawn_tasks, build_network, build_offchain_workers,
 	BuildNetworkParams, KeystoreContainer, NetworkStarter, SpawnTasksParams, TFullClient, TLightClient,
 	TFullBackend, TLightBackend, TLightBackendWithHash, TLightClientWithBackend,
 	TFullCallExecutor, TLightCallExecutor, RpcExtensionBuilder, NoopRpcExtensionBuilder,
+	snapshot_database, restore_database, import_state, purge_database, check_database,
+	storage_report, benchmark_storage, export_state, CANONICALIZATION_DELAY,
 };
+pub use self::hooks::{BuilderContext, BuilderHook, BuilderHooks, HookRpcExtensions};
 pub use config::{
 	BasePath, Configuration, DatabaseConfig, PruningMode, Role, RpcMethods, TaskExecutor, TaskType,
-	KeepBlocks, TransactionStorageMode,
+	KeepBlocks, TransactionStorageMode, InherentDataProvidersHook, InherentDataProvidersHooks,
+	ColdStorageConfig, DatabasePart, HostFunctionSets,
 };
 pub use sc_chain_spec::{
 	ChainSpec, GenericChainSpec, Properties, RuntimeGenesis, Extension as ChainSpecExtension,
-	NoExtension, ChainType,
+	NoExtension, ChainType, JsonValue,
 };
 pub use sp_transaction_pool::{TransactionPool, InPoolTransaction, error::IntoPoolError};
 pub use sc_transaction_pool::txpool::Options as TransactionPoolOptions;
@@ -78,7 +87,7 @@ pub use sc_network::config::{
 };
 pub use sc_tracing::TracingReceiver;
 pub use task_manager::SpawnTaskHandle;
-pub use task_manager::TaskManager;
+pub use task_manager::{RestartPolicy, TaskGroup, TaskManager};
 pub use sp_consensus::import_queue::ImportQueue;
 pub use self::client::{LocalCallExecutor, ClientConfig};
 use sc_client_api::{blockchain::HeaderBackend, BlockchainEvents};
@@ -352,8 +361,7 @@ mod waiting {
 	impl Drop for HttpServer {
 		fn drop(&mut self) {
 			if let Some(server) = self.0.take() {
-				server.close_handle().close();
-				server.wait();
+				server.close();
 			}
 		}
 	}
@@ -380,6 +388,8 @@ mod waiting {
 }
 
 /// Starts RPC servers that run in their own thread, and returns an opaque object that keeps them alive.
+///
+/// The gRPC gateway runs as a task of the given spawn handle instead.
 #[cfg(not(target_os = "unknown"))]
 fn start_rpc_servers<
 	H: FnMut(sc_rpc::DenyUnsafe, sc_rpc_server::RpcMiddleware)
@@ -388,6 +398,7 @@ fn start_rpc_servers<
 	config: &Configuration,
 	mut gen_handler: H,
 	rpc_metrics: sc_rpc_server::RpcMetrics,
+	spawn_handle: SpawnTaskHandle,
 ) -> Result<Box<dyn std::any::Any + Send + Sync>, error::Error> {
 	fn maybe_start_server<T, F>(address: Option<SocketAddr>, mut start: F) -> Result<Option<T>, io::Error>
 		where F: FnMut(&SocketAddr) -> Result<T, io::Error>,
@@ -407,44 +418,163 @@ fn start_rpc_servers<
 		})
 	}
 
-	fn deny_unsafe(addr: &SocketAddr, methods: &RpcMethods) -> sc_rpc::DenyUnsafe {
+	fn deny_unsafe(addr: &SocketAddr, methods: &RpcMethods, auth: bool) -> sc_rpc::DenyUnsafe {
 		let is_exposed_addr = !addr.ip().is_loopback();
 		match (is_exposed_addr, methods) {
+			// only authenticated clients may call the unsafe methods.
+			_ if auth => sc_rpc::DenyUnsafe::Yes,
 			| (_, RpcMethods::Unsafe)
 			| (false, RpcMethods::Auto) => sc_rpc::DenyUnsafe::No,
 			_ => sc_rpc::DenyUnsafe::Yes
 		}
 	}
 
+	fn with_auth<H>(
+		auth: &Option<sc_rpc_server::RpcAuth>,
+		policy: &sc_rpc_server::RpcMethodPolicy,
+		gen_handler: &mut H,
+		rpc_middleware: impl Fn() -> sc_rpc_server::RpcMiddleware,
+	) -> sc_rpc_server::RpcMiddleware where
+		H: FnMut(sc_rpc::DenyUnsafe, sc_rpc_server::RpcMiddleware)
+			-> sc_rpc_server::RpcHandler<sc_rpc::Metadata>
+	{
+		let public = if policy.is_empty() {
+			rpc_middleware()
+		} else {
+			// the methods allowed to every client are handled as if they were safe.
+			let unrestricted = if policy.allows_any() {
+				Some(gen_handler(sc_rpc::DenyUnsafe::No, rpc_middleware()))
+			} else {
+				None
+			};
+			rpc_middleware().with_method_policy(policy.clone(), unrestricted)
+		};
+
+		match auth {
+			Some(auth) => {
+				let privileged = with_batch_handler(
+					sc_rpc::DenyUnsafe::No,
+					gen_handler,
+					rpc_middleware().with_method_policy(
+						policy.authenticated(),
+						None::<sc_rpc_server::RpcHandler<sc_rpc::Metadata>>,
+					),
+				);
+				public.with_auth(auth.clone(), gen_handler(sc_rpc::DenyUnsafe::No, privileged))
+			},
+			None => public,
+		}
+	}
+
+	// the calls of sequential batches are passed to a handler of their own one after another.
+	fn with_batch_handler<H>(
+		deny_unsafe: sc_rpc::DenyUnsafe,
+		gen_handler: &mut H,
+		rpc_middleware: sc_rpc_server::RpcMiddleware,
+	) -> sc_rpc_server::RpcMiddleware where
+		H: FnMut(sc_rpc::DenyUnsafe, sc_rpc_server::RpcMiddleware)
+			-> sc_rpc_server::RpcHandler<sc_rpc::Metadata>
+	{
+		if !rpc_middleware.sequential_batches() {
+			return rpc_middleware;
+		}
+		let batch_handler = gen_handler(deny_unsafe, rpc_middleware.for_calls());
+		rpc_middleware.with_batch_handler(batch_handler)
+	}
+
+	let rate_limiter = |transport_label| config.rpc_rate_limit.map(|limit| Arc::new(
+		sc_rpc_server::RateLimiter::new(limit, &rpc_metrics, transport_label)
+	));
+	let http_rate_limiter = rate_limiter("http");
+	let ws_rate_limiter = rate_limiter("ws");
+
+	if let Some(address) = config.rpc_grpc {
+		// gRPC clients can't authenticate, they are only given the methods of every client.
+		let rpc_middleware = with_auth(
+			&None,
+			&config.rpc_method_policy,
+			&mut gen_handler,
+			|| sc_rpc_server::RpcMiddleware::new(rpc_metrics.clone(), "grpc")
+				.with_request_limits(config.rpc_request_limits)
+				.with_interceptors(config.rpc_interceptors.clone())
+				.with_aliases(config.rpc_aliases.clone())
+		);
+		let deny = deny_unsafe(&address, &config.rpc_methods, config.rpc_auth.is_some());
+		spawn_handle.spawn(
+			"rpc-grpc",
+			sc_rpc_grpc::run(address, gen_handler(deny, rpc_middleware), rate_limiter("grpc")),
+		);
+	}
+
 	Ok(Box::new((
-		config.rpc_ipc.as_ref().map(|path| sc_rpc_server::start_ipc(
-			&*path, gen_handler(
-				sc_rpc::DenyUnsafe::No,
+		config.rpc_ipc.as_ref().map(|path| {
+			// the socket is local, as a loopback address.
+			let deny = match config.rpc_methods {
+				RpcMethods::Safe => sc_rpc::DenyUnsafe::Yes,
+				RpcMethods::Auto | RpcMethods::Unsafe => sc_rpc::DenyUnsafe::No,
+			};
+			let rpc_middleware = with_batch_handler(
+				deny,
+				&mut gen_handler,
 				sc_rpc_server::RpcMiddleware::new(rpc_metrics.clone(), "ipc")
+					.with_request_limits(config.rpc_request_limits)
+					.with_interceptors(config.rpc_interceptors.clone())
+					.with_aliases(config.rpc_aliases.clone()),
+			);
+			sc_rpc_server::start_ipc(
+				&*path,
+				gen_handler(deny, rpc_middleware),
+				config.rpc_ipc_permissions.unwrap_or(sc_rpc_server::DEFAULT_IPC_PERMISSIONS),
 			)
-		)),
+		}),
 		maybe_start_server(
 			config.rpc_http,
-			|address| sc_rpc_server::start_http(
-				address,
-				config.rpc_cors.as_ref(),
-				gen_handler(
-					deny_unsafe(&address, &config.rpc_methods),
-					sc_rpc_server::RpcMiddleware::new(rpc_metrics.clone(), "http")
-				),
-			),
+			|address| {
+				let rpc_middleware = with_auth(
+					&config.rpc_auth,
+					&config.rpc_method_policy,
+					&mut gen_handler,
+					|| sc_rpc_server::RpcMiddleware::new(rpc_metrics.clone(), "http")
+						.with_rate_limiter(http_rate_limiter.clone())
+						.with_request_limits(config.rpc_request_limits)
+						.with_interceptors(config.rpc_interceptors.clone())
+						.with_aliases(config.rpc_aliases.clone())
+				);
+				let deny = deny_unsafe(&address, &config.rpc_methods, config.rpc_auth.is_some());
+				let rpc_middleware = with_batch_handler(deny, &mut gen_handler, rpc_middleware);
+				sc_rpc_server::start_http(
+					address,
+					config.rpc_cors.as_ref(),
+					gen_handler(deny, rpc_middleware),
+					http_rate_limiter.clone(),
+					&config.rpc_trusted_proxies,
+				)
+			},
 		)?.map(|s| waiting::HttpServer(Some(s))),
 		maybe_start_server(
 			config.rpc_ws,
-			|address| sc_rpc_server::start_ws(
-				address,
-				config.rpc_ws_max_connections,
-				config.rpc_cors.as_ref(),
-				gen_handler(
-					deny_unsafe(&address, &config.rpc_methods),
-					sc_rpc_server::RpcMiddleware::new(rpc_metrics.clone(), "ws")
-				),
-			),
+			|address| {
+				let rpc_middleware = with_auth(
+					&config.rpc_auth,
+					&config.rpc_method_policy,
+					&mut gen_handler,
+					|| sc_rpc_server::RpcMiddleware::new(rpc_metrics.clone(), "ws")
+						.with_rate_limiter(ws_rate_limiter.clone())
+						.with_request_limits(config.rpc_request_limits)
+						.with_interceptors(config.rpc_interceptors.clone())
+						.with_aliases(config.rpc_aliases.clone())
+				);
+				let deny = deny_unsafe(&address, &config.rpc_methods, config.rpc_auth.is_some());
+				let rpc_middleware = with_batch_handler(deny, &mut gen_handler, rpc_middleware);
+				sc_rpc_server::start_ws(
+					address,
+					config.rpc_ws_max_connections,
+					config.rpc_cors.as_ref(),
+					gen_handler(deny, rpc_middleware),
+					ws_rate_limiter.clone(),
+					&config.rpc_trusted_proxies,
+				)
+			},
 		)?.map(|s| waiting::WsServer(Some(s))),
 	)))
 }
@@ -458,6 +588,7 @@ fn start_rpc_servers<
 	_: &Configuration,
 	_: H,
 	_: sc_rpc_server::RpcMetrics,
+	_: SpawnTaskHandle,
 ) -> Result<Box<dyn std::any::Any + Send + Sync>, error::Error> {
 	Ok(Box::new(()))
 }
@@ -493,7 +624,7 @@ pub struct TransactionPoolAdapter<C, P> {
 /// Get transactions for propagation.
 ///
 /// Function extracted to simplify the test and prevent creating `ServiceFactory`.
-fn transactions_to_propagate<Pool, B, H, E>(pool: &Pool)
+fn transactions_to_propagate<Pool, B, H, E>(pool: &Pool, local_only: bool)
 	-> Vec<(H, B::Extrinsic)>
 where
 	Pool: TransactionPool<Block=B, Hash=H, Error=E>,
@@ -502,7 +633,7 @@ where
 	E: IntoPoolError + From<sp_transaction_pool::error::Error>,
 {
 	pool.ready()
-		.filter(|t| t.is_propagable())
+		.filter(|t| t.is_propagable() && (!local_only || t.is_local()))
 		.map(|t| {
 			let hash = t.hash().clone();
 			let ex: B::Extrinsic = t.data().clone();
@@ -521,7 +652,11 @@ where
 	E: 'static + IntoPoolError + From<sp_transaction_pool::error::Error>,
 {
 	fn transactions(&self) -> Vec<(H, B::Extrinsic)> {
-		transactions_to_propagate(&*self.pool)
+		transactions_to_propagate(&*self.pool, false)
+	}
+
+	fn local_transactions(&self) -> Vec<(H, B::Extrinsic)> {
+		transactions_to_propagate(&*self.pool, true)
 	}
 
 	fn hash_of(&self, transaction: &B::Extrinsic) -> H {
@@ -530,6 +665,7 @@ where
 
 	fn import(
 		&self,
+		peer: &PeerId,
 		transaction: B::Extrinsic,
 	) -> TransactionImportFuture {
 		if !self.imports_external_transactions {
@@ -548,12 +684,14 @@ where
 
 		let best_block_id = BlockId::hash(self.client.info().best_hash);
 
-		let import_future = self.pool.submit_one(&best_block_id, sp_transaction_pool::TransactionSource::External, uxt);
+		let import_future = self.pool.submit_from_peer(&best_block_id, peer.to_base58(), uxt);
 		Box::pin(async move {
 			match import_future.await {
 				Ok(_) => TransactionImport::NewGood,
 				Err(e) => match e.into_pool_error() {
 					Ok(sp_transaction_pool::error::Error::AlreadyImported(_)) => TransactionImport::KnownGood,
+					// the peer isn't to blame for the local admission policy.
+					Ok(sp_transaction_pool::error::Error::NotAdmitted) => TransactionImport::KnownGood,
 					Ok(e) => {
 						debug!("Error adding transaction to the pool: {:?}", e);
 						TransactionImport::Bad
@@ -620,7 +758,7 @@ mod tests {
 		assert_eq!(pool.status().ready, 2);
 
 		// when
-		let transactions = transactions_to_propagate(&*pool);
+		let transactions = transactions_to_propagate(&*pool, false);
 
 		// then
 		assert_eq!(transactions.len(), 1);
@@ -20,7 +20,9 @@ use std::{convert::TryFrom, time::SystemTime};
 
 use crate::{NetworkStatus, NetworkState, NetworkStatusSinks, config::Configuration};
 use futures_timer::Delay;
-use prometheus_endpoint::{register, Gauge, U64, Registry, PrometheusError, Opts, GaugeVec};
+use prometheus_endpoint::{
+	register, Gauge, U64, Registry, PrometheusError, Opts, GaugeVec, CounterVec,
+};
 use sc_telemetry::{telemetry, SUBSTRATE_INFO};
 use sp_api::ProvideRuntimeApi;
 use sp_runtime::traits::{NumberFor, Block, SaturatedConversion, UniqueSaturatedInto};
@@ -42,6 +44,8 @@ struct PrometheusMetrics {
 	// I/O
 	database_cache: Gauge<U64>,
 	state_cache: Gauge<U64>,
+	trie_cache: Gauge<U64>,
+	trie_cache_reads: CounterVec<U64>,
 	state_db: GaugeVec<U64>,
 }
 
@@ -96,6 +100,16 @@ impl PrometheusMetrics {
 			state_cache: register(Gauge::new(
 				"state_cache_bytes", "State cache size in bytes",
 			)?, registry)?,
+			trie_cache: register(Gauge::new(
+				"trie_cache_bytes", "Trie node and value cache size in bytes",
+			)?, registry)?,
+			trie_cache_reads: register(CounterVec::new(
+				Opts::new(
+					"trie_cache_reads_total",
+					"Trie node and value reads, found in the cache or not",
+				),
+				&["cache", "result"]
+			)?, registry)?,
 			state_db: register(GaugeVec::new(
 				Opts::new("state_db_cache_bytes", "State DB cache in bytes"),
 				&["subtype"]
@@ -276,6 +290,15 @@ impl MetricsService {
 			if let Some(info) = info.usage.as_ref() {
 				metrics.database_cache.set(info.memory.database_cache.as_bytes() as u64);
 				metrics.state_cache.set(info.memory.state_cache.as_bytes() as u64);
+				metrics.trie_cache.set(info.memory.trie_cache.as_bytes() as u64);
+				metrics.trie_cache_reads.with_label_values(&["node", "hit"])
+					.inc_by(info.io.trie_cache_hits);
+				metrics.trie_cache_reads.with_label_values(&["node", "miss"])
+					.inc_by(info.io.trie_cache_misses);
+				metrics.trie_cache_reads.with_label_values(&["value", "hit"])
+					.inc_by(info.io.trie_value_cache_hits);
+				metrics.trie_cache_reads.with_label_values(&["value", "miss"])
+					.inc_by(info.io.trie_value_cache_misses);
 
 				metrics.state_db.with_label_values(&["non_canonical"]).set(
 					info.memory.state_db.non_canonical.as_bytes() as u64,
@@ -18,18 +18,24 @@
 
 //! Substrate service tasks management module.
 
-use std::{panic, result::Result, pin::Pin};
+use std::{
+	collections::{HashMap, HashSet}, fmt, panic, result::Result, pin::Pin, sync::Arc,
+	task::{Context, Poll},
+	time::{Duration, Instant},
+};
 use exit_future::Signal;
-use log::{debug, error};
+use log::{debug, error, warn};
 use futures::{
 	Future, FutureExt, StreamExt,
 	future::{select, Either, BoxFuture, join_all, try_join_all, pending},
 	sink::SinkExt,
 };
+use futures_timer::Delay;
+use parking_lot::Mutex;
 use prometheus_endpoint::{
 	exponential_buckets, register,
 	PrometheusError,
-	CounterVec, HistogramOpts, HistogramVec, Opts, Registry, U64
+	CounterVec, GaugeVec, HistogramOpts, HistogramVec, Opts, Registry, F64, U64
 };
 use sp_utils::mpsc::{TracingUnboundedSender, TracingUnboundedReceiver, tracing_unbounded};
 use tracing_futures::Instrument;
@@ -39,16 +45,186 @@ mod prometheus_future;
 #[cfg(test)]
 mod tests;
 
+/// The group of a task. On shutdown, the groups are stopped one after the other, in the
+/// order set with [`TaskManager::set_shutdown_order`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TaskGroup {
+	/// The tasks of the RPC servers, e.g. their subscriptions.
+	Rpc,
+	/// The authoring and finality tasks.
+	Consensus,
+	/// The block import tasks. The import queue stops taking new blocks on shutdown and
+	/// finishes the import of the current ones before it's stopped.
+	Import,
+	/// The networking tasks.
+	Network,
+	/// Any other group, e.g. of the tasks of a downstream node.
+	Custom(&'static str),
+}
+
+impl TaskGroup {
+	/// The group of the tasks spawned without one.
+	pub const DEFAULT: TaskGroup = TaskGroup::Custom("default");
+}
+
+impl fmt::Display for TaskGroup {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			TaskGroup::Rpc => write!(f, "rpc"),
+			TaskGroup::Consensus => write!(f, "consensus"),
+			TaskGroup::Import => write!(f, "import"),
+			TaskGroup::Network => write!(f, "network"),
+			TaskGroup::Custom(name) => write!(f, "{}", name),
+		}
+	}
+}
+
+/// How often the longest polls of the tasks are reset, so they're the longest of the last
+/// interval rather than since the start of the node.
+const POLL_MAX_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The order the groups are shut down in by default.
+const DEFAULT_SHUTDOWN_ORDER: [TaskGroup; 5] = [
+	TaskGroup::Rpc,
+	TaskGroup::DEFAULT,
+	TaskGroup::Consensus,
+	TaskGroup::Import,
+	TaskGroup::Network,
+];
+
+/// The tasks of a group.
+struct GroupTasks {
+	/// A signal that makes the `on_exit` future resolve, fired when the group is shut down.
+	signal: Signal,
+	/// A future that resolves when the group is shut down, which stops its tasks.
+	on_exit: exit_future::Exit,
+	/// A signal that makes the `drain` future resolve, fired first when the group is shut down.
+	drain_signal: Signal,
+	/// The drain signal of the tasks spawned with one, which end by themselves once it
+	/// resolved. They are only stopped with the other tasks if they don't end in time.
+	drain: exit_future::Exit,
+	/// A sender to a stream of the tasks of the group. This is used for the completion future.
+	task_notifier: TracingUnboundedSender<JoinFuture>,
+	/// This future will complete when all the tasks are joined and the stream is closed.
+	completion_future: JoinFuture,
+	/// Same as `task_notifier`, for the tasks spawned with a drain signal.
+	draining_task_notifier: TracingUnboundedSender<JoinFuture>,
+	/// Same as `completion_future`, for the tasks spawned with a drain signal.
+	draining_completion_future: JoinFuture,
+}
+
+impl GroupTasks {
+	fn new(executor: &TaskExecutor) -> Self {
+		let (signal, on_exit) = exit_future::signal();
+		let (drain_signal, drain) = exit_future::signal();
+		let join_tasks = |name| {
+			let (task_notifier, background_tasks) = tracing_unbounded(name);
+			// NOTE: for_each_concurrent will await on all the JoinHandle futures at the same
+			// time. It is possible to limit this but it's actually better for the memory foot
+			// print to await them all to not accumulate anything on that stream.
+			let completion_future = executor.spawn(
+				Box::pin(background_tasks.for_each_concurrent(None, |x| x)),
+				TaskType::Async,
+			);
+			(task_notifier, completion_future)
+		};
+		let (task_notifier, completion_future) = join_tasks("mpsc_background_tasks");
+		let (draining_task_notifier, draining_completion_future) =
+			join_tasks("mpsc_draining_background_tasks");
+
+		GroupTasks {
+			signal,
+			on_exit,
+			drain_signal,
+			drain,
+			task_notifier,
+			completion_future,
+			draining_task_notifier,
+			draining_completion_future,
+		}
+	}
+
+	/// Close the streams of the tasks of the group, so no task can be spawned in it anymore.
+	fn close(&self) {
+		self.task_notifier.close_channel();
+		self.draining_task_notifier.close_channel();
+	}
+
+	/// Drain the tasks of the group spawned with a drain signal, then stop the other ones, and
+	/// wait for them all to end, at most for the given timeout.
+	async fn shutdown(self, group: TaskGroup, timeout: Option<Duration>) {
+		let mut delay = timeout.map(Delay::new);
+
+		let _ = self.drain_signal.fire();
+		if !wait_for(self.draining_completion_future, &mut delay).await {
+			warn!("The tasks of the {} group didn't drain within {:?}", group, timeout);
+		}
+		let _ = self.signal.fire();
+		if !wait_for(self.completion_future, &mut delay).await {
+			warn!("The tasks of the {} group didn't stop within {:?}", group, timeout);
+		}
+	}
+}
+
+/// Wait for the given future, until the given delay elapsed if any. Returns `false` if the
+/// delay elapsed first.
+async fn wait_for(future: JoinFuture, delay: &mut Option<Delay>) -> bool {
+	match delay {
+		Some(delay) => matches!(select(future, delay).await, Either::Left(_)),
+		None => {
+			future.await;
+			true
+		},
+	}
+}
+
+/// The groups of the tasks of a task manager, created once a task is spawned in them.
+#[derive(Default)]
+struct TaskGroups {
+	groups: HashMap<TaskGroup, GroupTasks>,
+	/// Set once the task manager is terminated, no task can be spawned afterwards.
+	terminated: bool,
+}
+
+impl TaskGroups {
+	/// Returns the exit future, the drain signal and the task notifier of the given group,
+	/// the notifier of the tasks drained if `draining`. `None` if no task can be spawned
+	/// anymore.
+	fn spawner(
+		&mut self,
+		group: TaskGroup,
+		draining: bool,
+		executor: &TaskExecutor,
+	) -> Option<(exit_future::Exit, exit_future::Exit, TracingUnboundedSender<JoinFuture>)> {
+		if self.terminated {
+			return None;
+		}
+		let tasks = self.groups.entry(group).or_insert_with(|| GroupTasks::new(executor));
+		let task_notifier = if draining {
+			tasks.draining_task_notifier.clone()
+		} else {
+			tasks.task_notifier.clone()
+		};
+		Some((tasks.on_exit.clone(), tasks.drain.clone(), task_notifier))
+	}
+}
+
 /// An handle for spawning tasks in the service.
 #[derive(Clone)]
 pub struct SpawnTaskHandle {
-	on_exit: exit_future::Exit,
+	group: TaskGroup,
+	groups: Arc<Mutex<TaskGroups>>,
+	on_stop: exit_future::Exit,
 	executor: TaskExecutor,
 	metrics: Option<Metrics>,
-	task_notifier: TracingUnboundedSender<JoinFuture>,
 }
 
 impl SpawnTaskHandle {
+	/// Returns a handle spawning the tasks in the given group.
+	pub fn in_group(&self, group: TaskGroup) -> Self {
+		SpawnTaskHandle { group, ..self.clone() }
+	}
+
 	/// Spawns the given task with the given name.
 	///
 	/// Note that the `name` is a `&'static str`. The reason for this choice is that statistics
@@ -66,6 +242,87 @@ impl SpawnTaskHandle {
 		self.spawn_inner(name, task, TaskType::Blocking)
 	}
 
+	/// Spawns the blocking task created by `task` with the given name, passing it the drain
+	/// signal of its group: the task must stop taking new work once it resolves, and end after
+	/// finishing the work in progress. The task is only stopped if it doesn't end within the
+	/// shutdown timeout of the group, see [`TaskManager::set_shutdown_timeout`].
+	pub fn spawn_blocking_with_drain<Fut>(
+		&self,
+		name: &'static str,
+		task: impl FnOnce(BoxFuture<'static, ()>) -> Fut,
+	) where
+		Fut: Future<Output = ()> + Send + 'static,
+	{
+		self.spawn_in_group(name, TaskType::Blocking, true, |drain| task(drain.boxed()))
+	}
+
+	/// Spawns the task created by `task` with the given name, and creates and spawns it again
+	/// if it fails, according to the given restart policy.
+	///
+	/// See also [`SpawnTaskHandle::spawn`].
+	pub fn spawn_with_restart<F, Fut>(&self, name: &'static str, policy: RestartPolicy, task: F)
+	where
+		F: FnMut() -> Fut + Send + 'static,
+		Fut: Future<Output = ()> + Send + 'static,
+	{
+		self.spawn_inner(name, self.restartable(name, policy, task), TaskType::Async)
+	}
+
+	/// Spawns the blocking task created by `task` with the given name, and creates and spawns
+	/// it again if it fails, according to the given restart policy.
+	///
+	/// See also [`SpawnTaskHandle::spawn_blocking`].
+	pub fn spawn_blocking_with_restart<F, Fut>(
+		&self,
+		name: &'static str,
+		policy: RestartPolicy,
+		task: F,
+	) where
+		F: FnMut() -> Fut + Send + 'static,
+		Fut: Future<Output = ()> + Send + 'static,
+	{
+		self.spawn_inner(name, self.restartable(name, policy, task), TaskType::Blocking)
+	}
+
+	/// Returns a future running the tasks created by `task` one after the other, as long as
+	/// they fail and the restart policy allows it.
+	fn restartable<F, Fut>(
+		&self,
+		name: &'static str,
+		policy: RestartPolicy,
+		mut task: F,
+	) -> impl Future<Output = ()> + Send + 'static
+	where
+		F: FnMut() -> Fut + Send + 'static,
+		Fut: Future<Output = ()> + Send + 'static,
+	{
+		let restarted = self.metrics.as_ref()
+			.map(|metrics| metrics.tasks_restarted.with_label_values(&[name]));
+		async move {
+			let mut restarts = 0;
+			loop {
+				let started = Instant::now();
+				let failed = std::panic::AssertUnwindSafe(Unwinding(task()))
+					.catch_unwind()
+					.await
+					.is_err();
+				if policy.is_stable(started.elapsed()) {
+					restarts = 0;
+				}
+				let backoff = match policy.backoff(restarts) {
+					Some(backoff) if failed => backoff,
+					_ => return,
+				};
+				log::warn!("Task `{}` failed. Restarting it in {:?}.", name, backoff);
+				Delay::new(backoff).await;
+				restarts = restarts.saturating_add(1);
+				if let Some(restarted) = &restarted {
+					restarted.inc();
+				}
+			}
+		}
+	}
+
 	/// Helper function that implements the spawning logic. See `spawn` and `spawn_blocking`.
 	fn spawn_inner(
 		&self,
@@ -73,12 +330,31 @@ impl SpawnTaskHandle {
 		task: impl Future<Output = ()> + Send + 'static,
 		task_type: TaskType,
 	) {
-		if self.task_notifier.is_closed() {
-			debug!("Attempt to spawn a new task has been prevented: {}", name);
-			return;
-		}
+		self.spawn_in_group(name, task_type, false, |_| task)
+	}
+
+	/// Spawns the task created by `task` given the drain signal of the group, and drains it on
+	/// shutdown if `draining`.
+	fn spawn_in_group<Fut>(
+		&self,
+		name: &'static str,
+		task_type: TaskType,
+		draining: bool,
+		task: impl FnOnce(exit_future::Exit) -> Fut,
+	) where
+		Fut: Future<Output = ()> + Send + 'static,
+	{
+		let spawner = self.groups.lock().spawner(self.group, draining, &self.executor);
+		let (group_exit, drain, mut task_notifier) = match spawner {
+			Some(spawner) => spawner,
+			None => {
+				debug!("Attempt to spawn a new task has been prevented: {}", name);
+				return;
+			},
+		};
+		let task = task(drain);
+		let on_exit = select(group_exit, self.on_stop.clone()).map(|_| ());
 
-		let on_exit = self.on_exit.clone();
 		let metrics = self.metrics.clone();
 
 		// Note that we increase the started counter here and not within the future. This way,
@@ -93,9 +369,7 @@ impl SpawnTaskHandle {
 			if let Some(metrics) = metrics {
 				// Add some wrappers around `task`.
 				let task = {
-					let poll_duration = metrics.poll_duration.with_label_values(&[name]);
-					let poll_start = metrics.poll_start.with_label_values(&[name]);
-					let inner = prometheus_future::with_poll_durations(poll_duration, poll_start, task);
+					let inner = prometheus_future::with_poll_durations(metrics.poll(name), task);
 					// The logic of `AssertUnwindSafe` here is ok considering that we throw
 					// away the `Future` after it has panicked.
 					panic::AssertUnwindSafe(inner).catch_unwind()
@@ -124,7 +398,6 @@ impl SpawnTaskHandle {
 
 		let join_handle = self.executor.spawn(future.in_current_span().boxed(), task_type);
 
-		let mut task_notifier = self.task_notifier.clone();
 		self.executor.spawn(
 			Box::pin(async move {
 				if let Err(err) = task_notifier.send(join_handle).await {
@@ -146,6 +419,63 @@ impl sp_core::traits::SpawnNamed for SpawnTaskHandle {
 	}
 }
 
+/// What to do once an essential task failed, i.e. panicked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestartPolicy {
+	/// Shut down the service.
+	Never,
+	/// Restart the task, after a delay doubled on every restart from `initial_backoff`
+	/// up to `max_backoff`. The service is still shut down if the task ends without failing.
+	OnFailure {
+		/// The delay before the first restart.
+		initial_backoff: Duration,
+		/// The maximum delay before a restart.
+		max_backoff: Duration,
+		/// How long the task must run before failing for the delay to be reset to
+		/// `initial_backoff`.
+		stable_period: Duration,
+	},
+}
+
+impl Default for RestartPolicy {
+	fn default() -> Self {
+		RestartPolicy::Never
+	}
+}
+
+impl RestartPolicy {
+	/// Restart the task on failure after 1 second, up to 1 minute, the delay being reset once
+	/// the task ran for 10 minutes.
+	pub fn on_failure() -> Self {
+		RestartPolicy::OnFailure {
+			initial_backoff: Duration::from_secs(1),
+			max_backoff: Duration::from_secs(60),
+			stable_period: Duration::from_secs(600),
+		}
+	}
+
+	/// Returns the delay before restarting a task that was already restarted the given number
+	/// of times, `None` if it must not be restarted.
+	fn backoff(&self, restarts: u32) -> Option<Duration> {
+		match *self {
+			RestartPolicy::Never => None,
+			RestartPolicy::OnFailure { initial_backoff, max_backoff, .. } => Some(
+				initial_backoff.checked_mul(2u32.saturating_pow(restarts))
+					.map_or(max_backoff, |backoff| backoff.min(max_backoff))
+			),
+		}
+	}
+
+	/// Whether a task which ran for the given time before failing was stable, so its restarts
+	/// are counted from zero again.
+	fn is_stable(&self, run: Duration) -> bool {
+		match *self {
+			RestartPolicy::Never => false,
+			RestartPolicy::OnFailure { stable_period, .. } => run >= stable_period,
+		}
+	}
+}
+
 /// A wrapper over `SpawnTaskHandle` that will notify a receiver whenever any
 /// task spawned through it fails. The service should be on the receiver side
 /// and will shut itself down whenever it receives any message, i.e. an
@@ -168,6 +498,14 @@ impl SpawnEssentialTaskHandle {
 		}
 	}
 
+	/// Returns a handle spawning the essential tasks in the given group.
+	pub fn in_group(&self, group: TaskGroup) -> Self {
+		SpawnEssentialTaskHandle {
+			essential_failed_tx: self.essential_failed_tx.clone(),
+			inner: self.inner.in_group(group),
+		}
+	}
+
 	/// Spawns the given task with the given name.
 	///
 	/// See also [`SpawnTaskHandle::spawn`].
@@ -202,6 +540,87 @@ impl SpawnEssentialTaskHandle {
 
 		let _ = self.inner.spawn_inner(name, essential_task, task_type);
 	}
+
+	/// Spawns the blocking task created by `task` with the given name and the drain signal of
+	/// its group. The service is only shut down if the task ends before it's drained.
+	///
+	/// See also [`SpawnTaskHandle::spawn_blocking_with_drain`].
+	pub fn spawn_blocking_with_drain<Fut>(
+		&self,
+		name: &'static str,
+		task: impl FnOnce(BoxFuture<'static, ()>) -> Fut,
+	) where
+		Fut: Future<Output = ()> + Send + 'static,
+	{
+		let essential_failed = self.essential_failed_tx.clone();
+		self.inner.spawn_in_group(name, TaskType::Blocking, true, move |drain| {
+			std::panic::AssertUnwindSafe(task(drain.clone().boxed()))
+				.catch_unwind()
+				.map(move |result| {
+					if result.is_ok() && drain.now_or_never().is_some() {
+						return;
+					}
+					log::error!("Essential task `{}` failed. Shutting down service.", name);
+					let _ = essential_failed.close_channel();
+				})
+		})
+	}
+
+	/// Spawns the task created by `task` with the given name, and creates and spawns it again
+	/// if it fails, according to the given restart policy.
+	///
+	/// See also [`SpawnEssentialTaskHandle::spawn`].
+	pub fn spawn_with_restart<F, Fut>(&self, name: &'static str, policy: RestartPolicy, task: F)
+	where
+		F: FnMut() -> Fut + Send + 'static,
+		Fut: Future<Output = ()> + Send + 'static,
+	{
+		self.spawn_restartable(name, policy, task, TaskType::Async)
+	}
+
+	/// Spawns the blocking task created by `task` with the given name, and creates and spawns
+	/// it again if it fails, according to the given restart policy.
+	///
+	/// See also [`SpawnEssentialTaskHandle::spawn_blocking`].
+	pub fn spawn_blocking_with_restart<F, Fut>(
+		&self,
+		name: &'static str,
+		policy: RestartPolicy,
+		task: F,
+	) where
+		F: FnMut() -> Fut + Send + 'static,
+		Fut: Future<Output = ()> + Send + 'static,
+	{
+		self.spawn_restartable(name, policy, task, TaskType::Blocking)
+	}
+
+	fn spawn_restartable<F, Fut>(
+		&self,
+		name: &'static str,
+		policy: RestartPolicy,
+		task: F,
+		task_type: TaskType,
+	) where
+		F: FnMut() -> Fut + Send + 'static,
+		Fut: Future<Output = ()> + Send + 'static,
+	{
+		self.spawn_inner(name, self.inner.restartable(name, policy, task), task_type)
+	}
+}
+
+/// Polls the future it wraps with its panics unwinding, so they can be caught to restart the
+/// task, even once the panic handler of the node, which aborts on panics, is installed.
+#[pin_project::pin_project]
+struct Unwinding<T>(#[pin] T);
+
+impl<T: Future> Future for Unwinding<T> {
+	type Output = T::Output;
+
+	fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+		// The guard isn't `Send`, so it can't be kept across the polls.
+		let _guard = sp_panic_handler::AbortGuard::force_unwind();
+		self.project().0.poll(cx)
+	}
 }
 
 impl sp_core::traits::SpawnEssentialNamed for SpawnEssentialTaskHandle {
@@ -212,6 +631,14 @@ impl sp_core::traits::SpawnEssentialNamed for SpawnEssentialTaskHandle {
 	fn spawn_essential(&self, name: &'static str, future: BoxFuture<'static, ()>) {
 		self.spawn(name, future);
 	}
+
+	fn spawn_essential_blocking_with_drain(
+		&self,
+		name: &'static str,
+		future: sp_core::traits::DrainableFuture,
+	) {
+		self.spawn_blocking_with_drain(name, future);
+	}
 }
 
 /// Helper struct to manage background/async tasks in Service.
@@ -232,10 +659,20 @@ pub struct TaskManager {
 	essential_failed_rx: TracingUnboundedReceiver<()>,
 	/// Things to keep alive until the task manager is dropped.
 	keep_alive: Box<dyn std::any::Any + Send + Sync>,
-	/// A sender to a stream of background tasks. This is used for the completion future.
-	task_notifier: TracingUnboundedSender<JoinFuture>,
-	/// This future will complete when all the tasks are joined and the stream is closed.
-	completion_future: JoinFuture,
+	/// Things to drop as soon as the shutdown starts, e.g. the servers accepting requests.
+	close_on_shutdown: Vec<Box<dyn std::any::Any + Send + Sync>>,
+	/// The futures run once the shutdown starts, before the groups are shut down.
+	shutdown_hooks: Vec<(&'static str, BoxFuture<'static, ()>)>,
+	/// The groups of the spawned tasks.
+	groups: Arc<Mutex<TaskGroups>>,
+	/// A signal that stops all the tasks once it is dropped, with the task manager.
+	stop_signal: Signal,
+	/// A future that resolves once the task manager is dropped.
+	on_stop: exit_future::Exit,
+	/// The order the groups are shut down in, the groups missing from it are shut down first.
+	shutdown_order: Vec<TaskGroup>,
+	/// How long to wait for the tasks of the groups to stop when shutting them down.
+	shutdown_timeouts: HashMap<TaskGroup, Duration>,
 	/// A list of other `TaskManager`'s to terminate and gracefully shutdown when the parent
 	/// terminates and gracefully shutdown. Also ends the parent `future()` if a child's essential
 	/// task fails.
@@ -255,17 +692,9 @@ impl TaskManager {
 		let (essential_failed_tx, essential_failed_rx) = tracing_unbounded("mpsc_essential_tasks");
 
 		let metrics = prometheus_registry.map(Metrics::register).transpose()?;
+		let (stop_signal, on_stop) = exit_future::signal();
 
-		let (task_notifier, background_tasks) = tracing_unbounded("mpsc_background_tasks");
-		// NOTE: for_each_concurrent will await on all the JoinHandle futures at the same time. It
-		// is possible to limit this but it's actually better for the memory foot print to await
-		// them all to not accumulate anything on that stream.
-		let completion_future = executor.spawn(
-			Box::pin(background_tasks.for_each_concurrent(None, |x| x)),
-			TaskType::Async,
-		);
-
-		Ok(Self {
+		let task_manager = Self {
 			on_exit,
 			signal: Some(signal),
 			executor,
@@ -273,19 +702,37 @@ impl TaskManager {
 			essential_failed_tx,
 			essential_failed_rx,
 			keep_alive: Box::new(()),
-			task_notifier,
-			completion_future,
+			close_on_shutdown: Vec::new(),
+			shutdown_hooks: Vec::new(),
+			groups: Default::default(),
+			stop_signal,
+			on_stop,
+			shutdown_order: DEFAULT_SHUTDOWN_ORDER.to_vec(),
+			shutdown_timeouts: HashMap::new(),
 			children: Vec::new(),
-		})
+		};
+
+		if let Some(metrics) = task_manager.metrics.clone() {
+			task_manager.spawn_handle().spawn("tasks-polling-max-reset", async move {
+				loop {
+					Delay::new(POLL_MAX_INTERVAL).await;
+					metrics.reset_poll_max();
+				}
+			});
+		}
+
+		Ok(task_manager)
 	}
 
-	/// Get a handle for spawning tasks.
+	/// Get a handle for spawning tasks in the default group, see also
+	/// [`SpawnTaskHandle::in_group`].
 	pub fn spawn_handle(&self) -> SpawnTaskHandle {
 		SpawnTaskHandle {
-			on_exit: self.on_exit.clone(),
+			group: TaskGroup::DEFAULT,
+			groups: self.groups.clone(),
+			on_stop: self.on_stop.clone(),
 			executor: self.executor.clone(),
 			metrics: self.metrics.clone(),
-			task_notifier: self.task_notifier.clone(),
 		}
 	}
 
@@ -303,19 +750,57 @@ impl TaskManager {
 	///
 	/// This is an issue in some cases as some of our dependencies do require that we drop all the
 	/// objects properly otherwise it triggers a SIGABRT on exit.
+	///
+	/// What is registered with `close_on_shutdown` is dropped first, then the shutdown
+	/// hooks are run and the groups of tasks are shut down one after the other, see
+	/// `set_shutdown_order`. What is kept alive is dropped last.
 	pub fn clean_shutdown(mut self) -> Pin<Box<dyn Future<Output = ()> + Send>> {
 		self.terminate();
+		drop(std::mem::take(&mut self.close_on_shutdown));
 		let children_shutdowns = self.children.into_iter().map(|x| x.clean_shutdown());
+		let shutdown_hooks = self.shutdown_hooks.into_iter().map(|(name, hook)| {
+			debug!("Running the shutdown hook `{}`", name);
+			hook
+		});
 		let keep_alive = self.keep_alive;
-		let completion_future = self.completion_future;
+		let stop_signal = self.stop_signal;
+		let mut groups = std::mem::take(&mut self.groups.lock().groups);
+		let ordered_groups = self.shutdown_order.iter()
+			.filter_map(|group| groups.remove(group).map(|tasks| vec![(*group, tasks)]))
+			.collect::<Vec<_>>();
+		let stages = std::iter::once(groups.into_iter().collect()).chain(ordered_groups);
+		let shutdown_timeouts = self.shutdown_timeouts;
+		let stages = stages
+			.map(|stage: Vec<_>| join_all(stage.into_iter().map(|(group, tasks)| {
+				tasks.shutdown(group, shutdown_timeouts.get(&group).cloned())
+			})))
+			.collect::<Vec<_>>();
 
 		Box::pin(async move {
 			join_all(children_shutdowns).await;
-			completion_future.await;
+			join_all(shutdown_hooks).await;
+			for stage in stages {
+				stage.await;
+			}
+			drop(stop_signal);
 			drop(keep_alive);
 		})
 	}
 
+	/// Set the order the groups of tasks are shut down in by `clean_shutdown`, the groups
+	/// missing from it are shut down first, all at once. By default, the RPC group is shut
+	/// down first, then the default one, the consensus one, the import one and the network
+	/// one.
+	pub fn set_shutdown_order(&mut self, order: Vec<TaskGroup>) {
+		self.shutdown_order = order;
+	}
+
+	/// Set how long `clean_shutdown` waits for the tasks of the given group to stop before
+	/// shutting down the next group. By default it waits until they are all stopped.
+	pub fn set_shutdown_timeout(&mut self, group: TaskGroup, timeout: Duration) {
+		self.shutdown_timeouts.insert(group, timeout);
+	}
+
 	/// Return a future that will end with success if the signal to terminate was sent
 	/// (`self.terminate()`) or with an error if an essential task fails.
 	///
@@ -346,8 +831,14 @@ impl TaskManager {
 	pub fn terminate(&mut self) {
 		if let Some(signal) = self.signal.take() {
 			let _ = signal.fire();
-			// NOTE: this will prevent new tasks to be spawned
-			self.task_notifier.close_channel();
+			// NOTE: this will prevent new tasks to be spawned, the running ones are stopped
+			// on `clean_shutdown`, or once the task manager is dropped.
+			let mut groups = self.groups.lock();
+			groups.terminated = true;
+			for tasks in groups.groups.values() {
+				tasks.close();
+			}
+			drop(groups);
 			for child in self.children.iter_mut() {
 				child.terminate();
 			}
@@ -362,6 +853,22 @@ impl TaskManager {
 		self.keep_alive = Box::new((to_keep_alive, old));
 	}
 
+	/// Set what must be dropped as soon as `clean_shutdown` is called, e.g. the servers
+	/// accepting requests. Can be called multiple times.
+	pub fn close_on_shutdown<T: 'static + Send + Sync>(&mut self, to_close: T) {
+		self.close_on_shutdown.push(Box::new(to_close));
+	}
+
+	/// Run the given future once `clean_shutdown` is called, before the groups of tasks are
+	/// shut down, e.g. to flush buffers. The hooks are run at the same time.
+	pub fn on_shutdown(
+		&mut self,
+		name: &'static str,
+		hook: impl Future<Output = ()> + Send + 'static,
+	) {
+		self.shutdown_hooks.push((name, hook.boxed()));
+	}
+
 	/// Register another TaskManager to terminate and gracefully shutdown when the parent
 	/// terminates and gracefully shutdown. Also ends the parent `future()` if a child's essential
 	/// task fails. (But don't end the parent if a child's normal task fails.)
@@ -374,9 +881,14 @@ impl TaskManager {
 struct Metrics {
 	// This list is ordered alphabetically
 	poll_duration: HistogramVec,
+	poll_max: GaugeVec<F64>,
 	poll_start: CounterVec<U64>,
+	poll_wall_time: CounterVec<F64>,
+	/// The names of the tasks whose longest polls are reported.
+	polled_tasks: Arc<Mutex<HashSet<&'static str>>>,
 	tasks_spawned: CounterVec<U64>,
 	tasks_ended: CounterVec<U64>,
+	tasks_restarted: CounterVec<U64>,
 }
 
 impl Metrics {
@@ -393,6 +905,14 @@ impl Metrics {
 				},
 				&["task_name"]
 			)?, registry)?,
+			poll_max: register(GaugeVec::new(
+				Opts::new(
+					"tasks_polling_max_duration_seconds",
+					"Duration in seconds of the longest invocation of Future::poll, reset every \
+					minute"
+				),
+				&["task_name"]
+			)?, registry)?,
 			poll_start: register(CounterVec::new(
 				Opts::new(
 					"tasks_polling_started_total",
@@ -400,6 +920,15 @@ impl Metrics {
 				),
 				&["task_name"]
 			)?, registry)?,
+			poll_wall_time: register(CounterVec::new(
+				Opts::new(
+					"tasks_polling_wall_time_seconds_total",
+					"Total wall-clock time in seconds spent in Future::poll, including the time \
+					the thread was blocked or descheduled"
+				),
+				&["task_name"]
+			)?, registry)?,
+			polled_tasks: Default::default(),
 			tasks_spawned: register(CounterVec::new(
 				Opts::new(
 					"tasks_spawned_total",
@@ -414,6 +943,31 @@ impl Metrics {
 				),
 				&["task_name", "reason"]
 			)?, registry)?,
+			tasks_restarted: register(CounterVec::new(
+				Opts::new(
+					"tasks_restarted_total",
+					"Total number of times the tasks have been restarted after failing"
+				),
+				&["task_name"]
+			)?, registry)?,
 		})
 	}
+
+	/// Returns the metrics of the polls of the tasks with the given name.
+	fn poll(&self, name: &'static str) -> prometheus_future::PollMetrics {
+		self.polled_tasks.lock().insert(name);
+		prometheus_future::PollMetrics {
+			poll_duration: self.poll_duration.with_label_values(&[name]),
+			poll_start: self.poll_start.with_label_values(&[name]),
+			poll_wall_time: self.poll_wall_time.with_label_values(&[name]),
+			poll_max: self.poll_max.with_label_values(&[name]),
+		}
+	}
+
+	/// Resets the longest polls of the tasks, for the next ones to be reported.
+	fn reset_poll_max(&self) {
+		for name in self.polled_tasks.lock().iter() {
+			self.poll_max.with_label_values(&[name]).set(0.0);
+		}
+	}
 }
@@ -19,20 +19,28 @@
 //! Wrapper around a `Future` that reports statistics about when the `Future` is polled.
 
 use futures::prelude::*;
-use prometheus_endpoint::{Counter, Histogram, U64};
-use std::{fmt, pin::Pin, task::{Context, Poll}};
-
-/// Wraps around a `Future`. Report the polling duration to the `Histogram` and when the polling
-/// starts to the `Counter`.
-pub fn with_poll_durations<T>(
-	poll_duration: Histogram,
-	poll_start: Counter<U64>,
-	inner: T
-) -> PrometheusFuture<T> {
+use prometheus_endpoint::{Counter, Gauge, Histogram, F64, U64};
+use std::{fmt, pin::Pin, task::{Context, Poll}, time::Instant};
+
+/// The metrics reported by a [`PrometheusFuture`], labelled by the name of its task.
+#[derive(Clone)]
+pub struct PollMetrics {
+	/// The duration of every poll.
+	pub poll_duration: Histogram,
+	/// The number of polls.
+	pub poll_start: Counter<U64>,
+	/// The total wall-clock time spent in polls, in seconds.
+	pub poll_wall_time: Counter<F64>,
+	/// The duration of the longest poll since it was last reset, in seconds.
+	pub poll_max: Gauge<F64>,
+}
+
+/// Wraps around a `Future`. Report the number of polls, their durations, their total duration
+/// and the longest of them to the given metrics.
+pub fn with_poll_durations<T>(metrics: PollMetrics, inner: T) -> PrometheusFuture<T> {
 	PrometheusFuture {
 		inner,
-		poll_duration,
-		poll_start,
+		metrics,
 	}
 }
 
@@ -43,8 +51,7 @@ pub struct PrometheusFuture<T> {
 	/// The inner future doing the actual work.
 	#[pin]
 	inner: T,
-	poll_duration: Histogram,
-	poll_start: Counter<U64>,
+	metrics: PollMetrics,
 }
 
 impl<T> Future for PrometheusFuture<T>
@@ -56,11 +63,19 @@ where
 	fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
 		let this = self.project();
 
-		this.poll_start.inc();
-		let _timer = this.poll_duration.start_timer();
-		Future::poll(this.inner, cx)
+		this.metrics.poll_start.inc();
+		let start = Instant::now();
+		let poll = Future::poll(this.inner, cx);
+		let elapsed = start.elapsed().as_secs_f64();
 
-		// `_timer` is dropped here and will observe the duration
+		this.metrics.poll_duration.observe(elapsed);
+		this.metrics.poll_wall_time.inc_by(elapsed);
+		// The tasks with the same name share the gauge, so concurrent polls may miss a
+		// maximum by a race, which is fine for diagnostics.
+		if elapsed > this.metrics.poll_max.get() {
+			this.metrics.poll_max.set(elapsed);
+		}
+		poll
 	}
 }
 
@@ -72,3 +87,39 @@ where
 		fmt::Debug::fmt(&self.inner, f)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use prometheus_endpoint::HistogramOpts;
+	use std::time::Duration;
+
+	#[test]
+	fn reports_the_polls_of_the_future() {
+		let metrics = PollMetrics {
+			poll_duration: Histogram::with_opts(HistogramOpts::new("duration", "help")).unwrap(),
+			poll_start: Counter::new("start", "help").unwrap(),
+			poll_wall_time: Counter::new("wall_time", "help").unwrap(),
+			poll_max: Gauge::new("max", "help").unwrap(),
+		};
+
+		let mut polls = 0;
+		let task = future::poll_fn(|cx| {
+			polls += 1;
+			std::thread::sleep(Duration::from_millis(polls * 10));
+			if polls < 3 {
+				cx.waker().wake_by_ref();
+				Poll::Pending
+			} else {
+				Poll::Ready(())
+			}
+		});
+		futures::executor::block_on(with_poll_durations(metrics.clone(), task));
+
+		assert_eq!(metrics.poll_start.get(), 3);
+		assert_eq!(metrics.poll_duration.get_sample_count(), 3);
+		assert!(metrics.poll_wall_time.get() >= 0.06);
+		assert!(metrics.poll_max.get() >= 0.03);
+		assert!(metrics.poll_max.get() < metrics.poll_wall_time.get());
+	}
+}
@@ -17,7 +17,8 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::config::TaskExecutor;
-use crate::task_manager::TaskManager;
+use crate::task_manager::{Metrics, RestartPolicy, TaskGroup, TaskManager};
+use prometheus_endpoint::Registry;
 use futures::{future::FutureExt, pin_mut, select};
 use parking_lot::Mutex;
 use sc_telemetry::TelemetrySpan;
@@ -153,6 +154,122 @@ fn ensure_blocking_futures_are_awaited_on_shutdown() {
 	assert_eq!(drop_tester, 0);
 }
 
+#[test]
+fn ensure_groups_are_shut_down_in_order() {
+	struct OnDrop(&'static str, Arc<Mutex<Vec<&'static str>>>);
+
+	impl Drop for OnDrop {
+		fn drop(&mut self) {
+			self.1.lock().push(self.0);
+		}
+	}
+
+	let shut_down = |order: Option<Vec<TaskGroup>>| {
+		let mut runtime = tokio::runtime::Runtime::new().unwrap();
+		let handle = runtime.handle().clone();
+		let task_executor: TaskExecutor =
+			(move |future, _| handle.spawn(future).map(|_| ())).into();
+
+		let mut task_manager = new_task_manager(task_executor);
+		if let Some(order) = order {
+			task_manager.set_shutdown_order(order);
+		}
+		let spawn_handle = task_manager.spawn_handle();
+		let dropped = Arc::new(Mutex::new(Vec::new()));
+		for (group, name) in vec![
+			(TaskGroup::Network, "network"),
+			(TaskGroup::Rpc, "rpc"),
+			(TaskGroup::Custom("other"), "other"),
+			(TaskGroup::DEFAULT, "default"),
+		] {
+			let on_drop = OnDrop(name, dropped.clone());
+			spawn_handle.in_group(group).spawn(name, run_background_task(on_drop));
+		}
+		// allow the tasks to even start
+		runtime.block_on(async { tokio::time::delay_for(Duration::from_secs(1)).await });
+		assert!(dropped.lock().is_empty());
+		runtime.block_on(task_manager.clean_shutdown());
+		let dropped = dropped.lock().clone();
+		dropped
+	};
+
+	assert_eq!(shut_down(None), vec!["other", "rpc", "default", "network"]);
+	// the groups missing from the order are shut down first, at the same time.
+	let mut dropped = shut_down(Some(vec![TaskGroup::Network, TaskGroup::DEFAULT]));
+	dropped[..2].sort();
+	assert_eq!(dropped, vec!["other", "rpc", "network", "default"]);
+}
+
+#[test]
+fn ensure_shutdown_hooks_run_before_groups_are_shut_down() {
+	let mut runtime = tokio::runtime::Runtime::new().unwrap();
+	let handle = runtime.handle().clone();
+	let task_executor: TaskExecutor = (move |future, _| handle.spawn(future).map(|_| ())).into();
+
+	let mut task_manager = new_task_manager(task_executor);
+	let events = Arc::new(Mutex::new(Vec::new()));
+	let (tx, rx) = futures::channel::oneshot::channel::<()>();
+	{
+		let events = events.clone();
+		task_manager.spawn_handle().spawn("task", async move {
+			let _ = rx.await;
+			events.lock().push("task");
+			futures::future::pending::<()>().await;
+		});
+	}
+	{
+		let events = events.clone();
+		task_manager.on_shutdown("hook", async move {
+			// the task is still running when the hook is run.
+			let _ = tx.send(());
+			tokio::time::delay_for(Duration::from_millis(100)).await;
+			events.lock().push("hook");
+		});
+	}
+	let server = Arc::new(());
+	task_manager.close_on_shutdown(server.clone());
+	assert_eq!(Arc::strong_count(&server), 2);
+
+	runtime.block_on(task_manager.clean_shutdown());
+	assert_eq!(Arc::strong_count(&server), 1);
+	assert_eq!(*events.lock(), vec!["task", "hook"]);
+}
+
+#[test]
+fn ensure_tasks_are_drained_before_their_group_is_stopped() {
+	struct OnDrop(Arc<Mutex<Vec<&'static str>>>);
+
+	impl Drop for OnDrop {
+		fn drop(&mut self) {
+			self.0.lock().push("stopped");
+		}
+	}
+
+	let mut runtime = tokio::runtime::Runtime::new().unwrap();
+	let handle = runtime.handle().clone();
+	let task_executor: TaskExecutor = (move |future, _| handle.spawn(future).map(|_| ())).into();
+
+	let task_manager = new_task_manager(task_executor);
+	let events = Arc::new(Mutex::new(Vec::new()));
+	let spawn_handle = task_manager.spawn_essential_handle().in_group(TaskGroup::Import);
+	{
+		let events = events.clone();
+		spawn_handle.spawn_blocking_with_drain("draining", move |drain| async move {
+			drain.await;
+			// the work in progress is finished before the other tasks are stopped.
+			tokio::time::delay_for(Duration::from_millis(100)).await;
+			events.lock().push("drained");
+		});
+	}
+	spawn_handle.spawn("task", run_background_task(OnDrop(events.clone())));
+	// allow the tasks to even start
+	runtime.block_on(async { tokio::time::delay_for(Duration::from_secs(1)).await });
+	assert!(events.lock().is_empty());
+
+	runtime.block_on(task_manager.clean_shutdown());
+	assert_eq!(*events.lock(), vec!["drained", "stopped"]);
+}
+
 #[test]
 fn ensure_no_task_can_be_spawn_after_terminate() {
 	let mut runtime = tokio::runtime::Runtime::new().unwrap();
@@ -218,6 +335,79 @@ fn ensure_task_manager_future_ends_with_error_when_essential_task_fails() {
 	assert_eq!(drop_tester, 0);
 }
 
+#[test]
+fn ensure_essential_task_is_restarted_after_failing() {
+	let mut runtime = tokio::runtime::Runtime::new().unwrap();
+	let handle = runtime.handle().clone();
+	let task_executor: TaskExecutor = (move |future, _| handle.spawn(future).map(|_| ())).into();
+
+	let mut task_manager = new_task_manager(task_executor);
+	let spawn_essential_handle = task_manager.spawn_essential_handle();
+	let starts = Arc::new(Mutex::new(0));
+	let policy = RestartPolicy::OnFailure {
+		initial_backoff: Duration::from_millis(10),
+		max_backoff: Duration::from_millis(20),
+		stable_period: Duration::from_secs(60),
+	};
+	let task_starts = starts.clone();
+	spawn_essential_handle.spawn_with_restart("task1", policy, move || {
+		let starts = task_starts.clone();
+		async move {
+			*starts.lock() += 1;
+			if *starts.lock() < 3 {
+				panic!("task failed");
+			}
+			run_background_task(()).await
+		}
+	});
+	runtime.block_on(async {
+		let t1 = task_manager.future().fuse();
+		let t2 = tokio::time::delay_for(Duration::from_secs(1)).fuse();
+
+		pin_mut!(t1, t2);
+
+		select! {
+			res = t1 => panic!("task should not have stopped: {:?}", res),
+			_ = t2 => {},
+		}
+	});
+	assert_eq!(*starts.lock(), 3);
+	runtime.block_on(task_manager.clean_shutdown());
+}
+
+#[test]
+fn restart_backoff_is_capped() {
+	let policy = RestartPolicy::OnFailure {
+		initial_backoff: Duration::from_secs(1),
+		max_backoff: Duration::from_secs(5),
+		stable_period: Duration::from_secs(60),
+	};
+	let backoffs = (0..5).map(|restarts| policy.backoff(restarts).unwrap().as_secs());
+	assert_eq!(backoffs.collect::<Vec<_>>(), vec![1, 2, 4, 5, 5]);
+	assert_eq!(policy.backoff(u32::MAX), Some(Duration::from_secs(5)));
+	assert_eq!(RestartPolicy::Never.backoff(0), None);
+}
+
+#[test]
+fn restarts_are_reset_after_a_stable_period() {
+	let policy = RestartPolicy::OnFailure {
+		initial_backoff: Duration::from_secs(1),
+		max_backoff: Duration::from_secs(5),
+		stable_period: Duration::from_secs(60),
+	};
+	assert!(!policy.is_stable(Duration::from_secs(59)));
+	assert!(policy.is_stable(Duration::from_secs(60)));
+	assert!(!RestartPolicy::Never.is_stable(Duration::from_secs(u64::MAX)));
+}
+
+#[test]
+fn longest_polls_are_reset() {
+	let metrics = Metrics::register(&Registry::new()).unwrap();
+	metrics.poll("task1").poll_max.set(1.0);
+	metrics.reset_poll_max();
+	assert_eq!(metrics.poll_max.with_label_values(&["task1"]).get(), 0.0);
+}
+
 #[test]
 fn ensure_children_tasks_ends_when_task_manager_terminated() {
 	let mut runtime = tokio::runtime::Runtime::new().unwrap();
@@ -408,3 +598,62 @@ fn telemetry_span_is_forwarded_to_task() {
 	eprintln!("{}", String::from_utf8(output.stderr).unwrap());
 	assert!(output.status.success());
 }
+
+/// This is not an actual test, it is used by the
+/// `essential_task_is_restarted_with_the_panic_handler_installed` test, which runs it in a
+/// separate process, as the panic handler it installs aborts on the panics of the other tests.
+#[test]
+fn subprocess_essential_task_is_restarted_with_the_panic_handler_installed() {
+	if env::var("SUBPROCESS_TEST").is_err() {
+		return;
+	}
+
+	sp_panic_handler::set("https://github.com/paritytech/substrate/issues", "1.0.0");
+
+	let mut runtime = tokio::runtime::Runtime::new().unwrap();
+	let handle = runtime.handle().clone();
+	let task_executor: TaskExecutor = (move |future, _| handle.spawn(future).map(|_| ())).into();
+
+	let task_manager = new_task_manager(task_executor);
+	let starts = Arc::new(Mutex::new(0));
+	let (sender, receiver) = futures::channel::oneshot::channel::<()>();
+	let mut sender = Some(sender);
+	let policy = RestartPolicy::OnFailure {
+		initial_backoff: Duration::from_millis(10),
+		max_backoff: Duration::from_millis(20),
+		stable_period: Duration::from_secs(60),
+	};
+	let task_starts = starts.clone();
+	task_manager.spawn_essential_handle().spawn_with_restart("task1", policy, move || {
+		let starts = task_starts.clone();
+		let sender = sender.take();
+		async move {
+			*starts.lock() += 1;
+			if sender.is_some() {
+				panic!("task failed");
+			}
+			run_background_task(()).await
+		}
+	});
+
+	runtime.block_on(receiver).expect_err("the sender is dropped by the first start");
+	runtime.block_on(async { tokio::time::delay_for(Duration::from_millis(200)).await });
+	assert_eq!(*starts.lock(), 2);
+	runtime.block_on(task_manager.clean_shutdown());
+}
+
+#[test]
+fn essential_task_is_restarted_with_the_panic_handler_installed() {
+	let executable = env::current_exe().unwrap();
+	let output = std::process::Command::new(executable)
+		.env("SUBPROCESS_TEST", "1")
+		.args(&[
+			"--nocapture",
+			"subprocess_essential_task_is_restarted_with_the_panic_handler_installed",
+		])
+		.output()
+		.unwrap();
+	println!("{}", String::from_utf8(output.stdout).unwrap());
+	eprintln!("{}", String::from_utf8(output.stderr).unwrap());
+	assert!(output.status.success());
+}
@@ -442,6 +442,83 @@ fn code_is_executed_at_genesis_only() {
 	}
 }
 
+#[test]
+fn compact_proofs_are_generated_and_checked() {
+	let remote_client = substrate_test_runtime_client::new();
+	let remote_block_id = BlockId::Number(0);
+	let remote_header = remote_client.header(&remote_block_id).unwrap().unwrap();
+	let state_root = *remote_header.state_root();
+
+	let (values, proof) = remote_client.read_with_proof(
+		&remote_block_id,
+		&mut std::iter::once(well_known_keys::HEAP_PAGES),
+	).unwrap();
+	let (proof, _) = proof.to_storage_proof::<BlakeTwo256>(Some(&state_root)).unwrap();
+	let local_values = sp_state_machine::read_proof_check::<BlakeTwo256, _>(
+		state_root,
+		proof,
+		&[well_known_keys::HEAP_PAGES],
+	).unwrap();
+	assert!(values[0].is_some());
+	assert_eq!(local_values[well_known_keys::HEAP_PAGES], values[0]);
+
+	let (result, proof) = remote_client.execute_with_proof(
+		&remote_block_id,
+		"Core_version",
+		&[],
+	).unwrap();
+	let (proof, _) = proof.to_storage_proof::<BlakeTwo256>(Some(&state_root)).unwrap();
+	let local_result = check_execution_proof::<_, _, BlakeTwo256>(
+		&local_executor(),
+		Box::new(TaskExecutor::new()),
+		&RemoteCallRequest {
+			block: remote_client.block_hash(0).unwrap().unwrap(),
+			header: remote_header,
+			method: "Core_version".into(),
+			call_data: vec![],
+			retry_count: None,
+		},
+		proof,
+	).unwrap();
+	assert_eq!(result, local_result);
+}
+
+#[test]
+fn state_chunks_are_generated_and_checked() {
+	let remote_client = substrate_test_runtime_client::new();
+	let remote_block_id = BlockId::Number(0);
+	let remote_header = remote_client.header(&remote_block_id).unwrap().unwrap();
+	let state_root = *remote_header.state_root();
+	let mut pairs = remote_client.state_at(&remote_block_id).unwrap().pairs();
+	pairs.sort();
+
+	let mut read = Vec::new();
+	let mut start_at: Option<Vec<u8>> = None;
+	loop {
+		let chunk = remote_client.read_state_chunk(
+			&remote_block_id,
+			None,
+			start_at.as_deref(),
+			1024,
+		).unwrap();
+		let (proof, _) = chunk.proof.to_storage_proof::<BlakeTwo256>(Some(&state_root)).unwrap();
+		let checked = sp_state_machine::read_range_proof_check::<BlakeTwo256>(
+			state_root,
+			proof,
+			None,
+			start_at.as_deref(),
+			1024,
+		).unwrap();
+		assert_eq!(checked, (chunk.entries.clone(), chunk.complete));
+
+		start_at = chunk.entries.last().map(|(key, _)| key.clone());
+		read.extend(chunk.entries);
+		if chunk.complete {
+			break;
+		}
+	}
+	assert_eq!(read, pairs);
+}
 
 type TestChecker = LightDataChecker<
 	NativeExecutor<substrate_test_runtime_client::LocalExecutor>,
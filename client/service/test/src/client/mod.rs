@@ -1270,9 +1270,15 @@ fn doesnt_import_blocks_that_revert_finality() {
 		DatabaseSettings {
 			state_cache_size: 1 << 20,
 			state_cache_child_ratio: None,
+			trie_cache_size: 0,
 			state_pruning: PruningMode::ArchiveAll,
 			keep_blocks: KeepBlocks::All,
 			transaction_storage: TransactionStorageMode::BlockBody,
+			index_extrinsics: false,
+			compress_blocks: false,
+			trie_cache_warm_up: false,
+			cold_storage: None,
+			max_canonicalization_delay: None,
 			source: DatabaseSettingsSrc::RocksDb {
 				path: tmp.path().into(),
 				cache_size: 1024,
@@ -1475,9 +1481,15 @@ fn returns_status_for_pruned_blocks() {
 		DatabaseSettings {
 			state_cache_size: 1 << 20,
 			state_cache_child_ratio: None,
+			trie_cache_size: 0,
 			state_pruning: PruningMode::keep_blocks(1),
 			keep_blocks: KeepBlocks::All,
 			transaction_storage: TransactionStorageMode::BlockBody,
+			index_extrinsics: false,
+			compress_blocks: false,
+			trie_cache_warm_up: false,
+			cold_storage: None,
+			max_canonicalization_delay: None,
 			source: DatabaseSettingsSrc::RocksDb {
 				path: tmp.path().into(),
 				cache_size: 1024,
@@ -1857,3 +1869,43 @@ fn reorg_triggers_a_notification_even_for_sources_that_should_not_trigger_notifi
 	let tree_route = notification.tree_route.unwrap();
 	assert_eq!(tree_route.enacted()[0].hash, b1.hash());
 }
+
+#[test]
+fn finalizing_a_new_code_triggers_a_runtime_upgrade_notification() {
+	let mut client = TestClientBuilder::new().build();
+	let mut notification_stream = futures::executor::block_on_stream(
+		client.runtime_upgrade_notification_stream()
+	);
+
+	// the same runtime with another implementation name.
+	let code = runtime::wasm_binary_unwrap().to_vec();
+	let (name, new_name) = (&b"parity-test"[..], &b"parity-tesu"[..]);
+	let position = code.windows(name.len()).position(|window| window == name)
+		.expect("the implementation name is in the data of the runtime");
+	let mut new_code = code.clone();
+	new_code[position..position + name.len()].copy_from_slice(new_name);
+
+	let mut builder = client.new_block_at(
+		&BlockId::Number(0),
+		Default::default(),
+		false,
+	).unwrap();
+	builder.push_storage_change(sp_core::storage::well_known_keys::CODE.to_vec(), Some(new_code))
+		.unwrap();
+	let block = builder.build().unwrap().block;
+	client.import_as_final(BlockOrigin::Own, block.clone()).unwrap();
+
+	let notification = notification_stream.next().unwrap();
+	assert_eq!(notification.hash, block.hash());
+	assert_eq!(notification.number, 1);
+	assert_eq!(notification.old_version, runtime::VERSION);
+	assert_eq!(&*notification.new_version.impl_name, "parity-tesu");
+	assert_eq!(notification.new_version.spec_version, runtime::VERSION.spec_version);
+
+	// finalizing a block with the same code doesn't notify.
+	let block = client.new_block_at(&BlockId::Hash(block.hash()), Default::default(), false)
+		.unwrap().build().unwrap().block;
+	client.import_as_final(BlockOrigin::Own, block).unwrap();
+	drop(client);
+	assert!(notification_stream.next().is_none());
+}
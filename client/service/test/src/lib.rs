@@ -251,20 +251,42 @@ fn node_config<G: RuntimeGenesis + 'static, E: ChainSpecExtension + Clone + 'sta
 		},
 		state_cache_size: 16777216,
 		state_cache_child_ratio: None,
+		trie_cache_size: 0,
 		state_pruning: Default::default(),
 		keep_blocks: KeepBlocks::All,
 		transaction_storage: TransactionStorageMode::BlockBody,
+		index_extrinsics: false,
+		database_snapshot_dir: None,
+		compress_blocks: false,
+		cold_storage: None,
+		canonicalization_delay: None,
+		max_canonicalization_delay: None,
+		trie_cache_warm_up: false,
 		chain_spec: Box::new((*spec).clone()),
 		wasm_method: sc_service::config::WasmExecutionMethod::Interpreted,
+		wasm_features: Default::default(),
 		wasm_runtime_overrides: Default::default(),
 		execution_strategies: Default::default(),
+		execution_memory_limits: Default::default(),
 		rpc_http: None,
 		rpc_ipc: None,
+		rpc_grpc: None,
+		rpc_ipc_permissions: None,
 		rpc_ws: None,
 		rpc_ws_max_connections: None,
 		rpc_cors: None,
 		rpc_methods: Default::default(),
+		rpc_rate_limit: None,
+		rpc_trusted_proxies: Default::default(),
+		rpc_auth: None,
+		rpc_method_policy: Default::default(),
+		rpc_request_limits: Default::default(),
+		rpc_interceptors: Default::default(),
+		rpc_aliases: Default::default(),
+		rpc_type_aliases: Default::default(),
 		prometheus_config: None,
+		health_endpoint: None,
+		disk_space: None,
 		telemetry_endpoints: None,
 		telemetry_external_transport: None,
 		telemetry_handle: None,
@@ -276,9 +298,14 @@ fn node_config<G: RuntimeGenesis + 'static, E: ChainSpecExtension + Clone + 'sta
 		tracing_targets: None,
 		tracing_receiver: Default::default(),
 		max_runtime_instances: 8,
+		preinstantiated_runtime_instances: 0,
+		runtime_cache_size: sc_service::config::DEFAULT_MAX_RUNTIMES,
+		runtime_cache_max_code_size: None,
+		host_functions: Default::default(),
 		announce_block: true,
 		base_path: Some(BasePath::new(root)),
 		informant_output_format: Default::default(),
+		inherent_data_providers: Default::default(),
 		disable_log_reloading: false,
 	}
 }
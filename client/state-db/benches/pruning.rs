@@ -0,0 +1,128 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Import and canonicalization of blocks with a large storage churn, pruning all but the
+//! last 256 blocks.
+
+use std::collections::HashMap;
+
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use sc_state_db::{ChangeSet, CommitSet, Constraints, MetaDb, PruningMode, StateDb};
+use sp_core::H256;
+
+/// The number of blocks kept, as with `--pruning 256`.
+const KEEP_BLOCKS: u32 = 256;
+/// The number of nodes inserted and deleted by every block.
+const CHURN: u64 = 1_000;
+
+#[derive(Default)]
+struct Db {
+	data: HashMap<H256, Vec<u8>>,
+	meta: HashMap<Vec<u8>, Vec<u8>>,
+}
+
+impl MetaDb for Db {
+	type Error = ();
+
+	fn get_meta(&self, key: &[u8]) -> Result<Option<Vec<u8>>, ()> {
+		Ok(self.meta.get(key).cloned())
+	}
+}
+
+impl Db {
+	fn commit(&mut self, commit: CommitSet<H256>) {
+		self.data.extend(commit.data.inserted);
+		for key in commit.data.deleted {
+			self.data.remove(&key);
+		}
+		self.meta.extend(commit.meta.inserted);
+		for key in commit.meta.deleted {
+			self.meta.remove(&key);
+		}
+	}
+}
+
+fn block_hash(number: u64) -> H256 {
+	H256::from_low_u64_be(number + 1)
+}
+
+/// The changes of the given block: it inserts new nodes, deletes nodes inserted by
+/// previous blocks and re-inserts some of the nodes deleted before, including some of the
+/// nodes deleted by the block pruned along with it.
+fn changeset(number: u64) -> ChangeSet<H256> {
+	let node = |n: u64| (H256::from_low_u64_be(n), n.to_le_bytes().to_vec());
+	let first = number * CHURN;
+	let mut inserted = (first .. first + CHURN).map(node).collect::<Vec<_>>();
+	let mut deleted = Vec::new();
+	if number > 0 {
+		let previous = first - CHURN;
+		deleted.extend((previous .. previous + CHURN / 2).map(|n| H256::from_low_u64_be(n)));
+	}
+	if number > 1 {
+		let reinserted = first - 2 * CHURN;
+		inserted.extend((reinserted .. reinserted + CHURN / 10).map(node));
+	}
+	if number > KEEP_BLOCKS as u64 + 1 {
+		let pruned = first - (KEEP_BLOCKS as u64 + 2) * CHURN;
+		inserted.extend((pruned .. pruned + CHURN / 10).map(node));
+	}
+	ChangeSet { inserted, deleted }
+}
+
+/// Import and canonicalize `blocks` blocks after the given ones.
+fn import(state_db: &StateDb<H256, H256>, db: &mut Db, start: u64, blocks: u64) {
+	for number in start .. start + blocks {
+		let hash = block_hash(number);
+		let parent = if number == 0 { H256::zero() } else { block_hash(number - 1) };
+		let mut commit = state_db.insert_block::<()>(&hash, number, &parent, changeset(number))
+			.unwrap();
+		let canonical = state_db.canonicalize_block::<()>(&hash).unwrap();
+		commit.data.inserted.extend(canonical.data.inserted);
+		commit.data.deleted.extend(canonical.data.deleted);
+		commit.meta.inserted.extend(canonical.meta.inserted);
+		commit.meta.deleted.extend(canonical.meta.deleted);
+		db.commit(commit);
+		state_db.apply_pending();
+	}
+}
+
+fn bench_pruning(c: &mut Criterion) {
+	let mode = PruningMode::Constrained(Constraints {
+		max_blocks: Some(KEEP_BLOCKS),
+		max_mem: None,
+	});
+	let window = KEEP_BLOCKS as u64 + 1;
+
+	for &ref_counting in &[false, true] {
+		let name = if ref_counting { "import ref counted" } else { "import" };
+		c.bench_function(name, |b| b.iter_batched(
+			|| {
+				// starts with a full pruning window.
+				let mut db = Db::default();
+				let state_db = StateDb::new(mode.clone(), ref_counting, &db).unwrap();
+				import(&state_db, &mut db, 0, window);
+				(state_db, db)
+			},
+			|(state_db, mut db)| import(&state_db, &mut db, window, 32),
+			BatchSize::LargeInput,
+		));
+	}
+}
+
+criterion_group!(benches, bench_pruning);
+criterion_main!(benches);
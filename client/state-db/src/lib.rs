@@ -111,7 +111,11 @@ impl<E: fmt::Debug> fmt::Debug for Error<E> {
 			Error::InvalidBlock => write!(f, "Trying to canonicalize invalid block"),
 			Error::InvalidBlockNumber => write!(f, "Trying to insert block with invalid number"),
 			Error::InvalidParent => write!(f, "Trying to insert block with unknown parent"),
-			Error::InvalidPruningMode(e) => write!(f, "Expected pruning mode: {}", e),
+			Error::InvalidPruningMode(e) => write!(
+				f,
+				"Expected pruning mode: {}, the database was created with it",
+				e,
+			),
 		}
 	}
 }
@@ -353,6 +357,36 @@ impl<BlockHash: Hash + MallocSizeOf, Key: Hash + MallocSizeOf> StateDbSync<Block
 		}
 	}
 
+	/// Revert the given canonicalized block, which has to be the last one and the only
+	/// block left, onto its parent. The nodes it deleted are kept and, when pruning, the
+	/// nodes it inserted are deleted.
+	/// Returns a database commit or `None` if not possible.
+	/// For archive an empty commit set is returned.
+	fn revert_canonical<D: MetaDb>(
+		&mut self,
+		hash: &BlockHash,
+		parent_hash: &BlockHash,
+		db: &D,
+	) -> Result<Option<CommitSet<Key>>, Error<D::Error>> {
+		match self.mode {
+			PruningMode::ArchiveAll => {
+				Ok(Some(CommitSet::default()))
+			},
+			PruningMode::ArchiveCanonical | PruningMode::Constrained(_) => {
+				let in_window = self.pruning.as_ref().map_or(true, |p| p.is_last_block(hash));
+				if !in_window || !self.non_canonical.is_last_canonicalized(hash) {
+					return Ok(None);
+				}
+				let mut commit = CommitSet::default();
+				if let Some(pruning) = &mut self.pruning {
+					pruning.revert_last(db, &mut commit)?;
+				}
+				self.non_canonical.revert_canonical(parent_hash, &mut commit);
+				Ok(Some(commit))
+			},
+		}
+	}
+
 	fn pin(&mut self, hash: &BlockHash) -> Result<(), PinError> {
 		match self.mode {
 			PruningMode::ArchiveAll => Ok(()),
@@ -498,6 +532,19 @@ impl<BlockHash: Hash + MallocSizeOf, Key: Hash + MallocSizeOf> StateDb<BlockHash
 		self.db.write().revert_one()
 	}
 
+	/// Revert the given canonicalized block onto its parent, once all non-canonical blocks
+	/// are reverted. Only the last block of the pruning window can be reverted.
+	/// Returns a database commit or `None` if not possible.
+	/// For archive an empty commit set is returned.
+	pub fn revert_canonical<D: MetaDb>(
+		&self,
+		hash: &BlockHash,
+		parent_hash: &BlockHash,
+		db: &D,
+	) -> Result<Option<CommitSet<Key>>, Error<D::Error>> {
+		self.db.write().revert_canonical(hash, parent_hash, db)
+	}
+
 	/// Returns last finalized block number.
 	pub fn best_canonical(&self) -> Option<u64> {
 		return self.db.read().best_canonical()
@@ -645,6 +692,36 @@ mod tests {
 		assert!(db.data_eq(&make_db(&[1, 21, 3, 921, 922, 93, 94])));
 	}
 
+	#[test]
+	fn revert_canonicalized_blocks() {
+		let mode = PruningMode::Constrained(Constraints { max_blocks: Some(2), max_mem: None });
+		let (mut db, sdb) = make_test_db(mode.clone());
+		let hash = H256::from_low_u64_be;
+		assert!(sdb.revert_canonical(&hash(3), &hash(21), &db).unwrap().is_none());
+
+		db.commit(&sdb.revert_one().unwrap());
+		let commit = sdb.revert_canonical(&hash(3), &hash(21), &db).unwrap().unwrap();
+		db.commit(&commit);
+		let commit = sdb.revert_canonical(&hash(21), &hash(1), &db).unwrap().unwrap();
+		db.commit(&commit);
+		// the state of the first block is pruned.
+		assert!(sdb.revert_canonical(&hash(1), &hash(0), &db).unwrap().is_none());
+		assert_eq!(sdb.best_canonical(), Some(1));
+		// the nodes inserted by the reverted blocks are deleted.
+		assert!(db.data_eq(&make_db(&[1, 921, 922, 93, 94])));
+
+		let sdb = StateDb::<H256, H256>::new(mode, false, &db).unwrap();
+		assert_eq!(sdb.best_canonical(), Some(1));
+		db.commit(
+			&sdb.insert_block::<io::Error>(&hash(23), 2, &hash(1), make_changeset(&[23], &[921]))
+				.unwrap(),
+		);
+		sdb.apply_pending();
+		db.commit(&sdb.canonicalize_block::<io::Error>(&hash(23)).unwrap());
+		sdb.apply_pending();
+		assert_eq!(sdb.best_canonical(), Some(2));
+	}
+
 	#[test]
 	fn detects_incompatible_mode() {
 		let mut db = make_db(&[]);
@@ -425,6 +425,25 @@ impl<BlockHash: Hash, Key: Hash> NonCanonicalOverlay<BlockHash, Key> {
 		})
 	}
 
+	/// Returns `true` if the given block is the last canonicalized block and no block is
+	/// left in the overlay.
+	pub fn is_last_canonicalized(&self, hash: &BlockHash) -> bool {
+		self.levels.is_empty() &&
+			self.pending_canonicalizations.is_empty() &&
+			self.last_canonicalized.as_ref().map_or(false, |(h, n)| h == hash && *n > 0)
+	}
+
+	/// Revert the last canonicalized block, expects it to be the only block left (see
+	/// `is_last_canonicalized`). Its parent becomes the last canonicalized block.
+	pub fn revert_canonical(&mut self, parent_hash: &BlockHash, commit: &mut CommitSet<Key>) {
+		if let Some((_, number)) = self.last_canonicalized.take() {
+			let last_canonicalized = (parent_hash.clone(), number - 1);
+			let key = to_meta_key(LAST_CANONICAL, &());
+			commit.meta.inserted.push((key, last_canonicalized.encode()));
+			self.last_canonicalized = Some(last_canonicalized);
+		}
+	}
+
 	fn revert_insertions(&mut self) {
 		self.pending_insertions.reverse();
 		for hash in self.pending_insertions.drain(..) {
@@ -22,7 +22,16 @@
 //! There is also a global index of node key to block number.
 //! If a node is re-inserted into the window it gets removed from
 //! the death list.
-//! The changes are journaled in the DB.
+//! The changes are journaled in the DB. Only the re-inserted nodes are
+//! journaled along with the deleted ones, since the other inserted nodes
+//! don't affect the death lists.
+//! With a reference counting backend, the deletions of a pruned block are
+//! batched with the insertions of the commit pruning it: a node both
+//! inserted and deleted by the commit keeps its reference count, so it
+//! isn't written at all.
+//! The nodes inserted by each block of the window are journaled separately,
+//! so they can be deleted if the last block of the window is reverted. They
+//! are only read back then.
 
 use std::collections::{HashMap, HashSet, VecDeque};
 use codec::{Encode, Decode};
@@ -31,6 +40,7 @@ use log::{trace, warn};
 
 const LAST_PRUNED: &[u8] = b"last_pruned";
 const PRUNING_JOURNAL: &[u8] = b"pruning_journal";
+const PRUNING_INSERTIONS: &[u8] = b"pruning_insertions";
 
 /// See module documentation.
 #[derive(parity_util_mem_derive::MallocSizeOf)]
@@ -71,6 +81,10 @@ fn to_journal_key(block: u64) -> Vec<u8> {
 	to_meta_key(PRUNING_JOURNAL, &block)
 }
 
+fn to_insertions_key(block: u64) -> Vec<u8> {
+	to_meta_key(PRUNING_INSERTIONS, &block)
+}
+
 impl<BlockHash: Hash, Key: Hash> RefWindow<BlockHash, Key> {
 	pub fn new<D: MetaDb>(db: &D, count_insertions: bool) -> Result<RefWindow<BlockHash, Key>, Error<D::Error>> {
 		let last_pruned = db.get_meta(&to_meta_key(LAST_PRUNED, &()))
@@ -149,14 +163,86 @@ impl<BlockHash: Hash, Key: Hash> RefWindow<BlockHash, Key> {
 		self.death_rows.iter().skip(self.pending_prunings).any(|r| r.hash == *hash)
 	}
 
+	/// Returns `true` if the given block is the last block of the window.
+	pub fn is_last_block(&self, hash: &BlockHash) -> bool {
+		self.pending_canonicalizations == 0 &&
+			self.death_rows.len() > self.pending_prunings &&
+			self.death_rows.back().map_or(false, |r| r.hash == *hash)
+	}
+
+	/// Remove the last block of the window, so the nodes it deleted aren't pruned, and delete
+	/// the nodes it inserted. Adds changes to `commit`.
+	///
+	/// Without reference counting, the nodes it re-inserted are pending deletion by the new
+	/// last block instead, since their previous deletion was cancelled by the re-insertion.
+	pub fn revert_last<D: MetaDb>(
+		&mut self,
+		db: &D,
+		commit: &mut CommitSet<Key>,
+	) -> Result<(), Error<D::Error>> {
+		let block = match self.death_rows.len() {
+			0 => return Ok(()),
+			len => self.pending_number + len as u64 - 1,
+		};
+		let insertions_key = to_insertions_key(block);
+		let inserted: Vec<Key> = match db.get_meta(&insertions_key).map_err(Error::Db)? {
+			Some(inserted) => Decode::decode(&mut inserted.as_slice())?,
+			None => Vec::new(),
+		};
+		let reverted = self.death_rows.pop_back().expect("the window isn't empty; qed");
+		trace!(
+			target: "state-db",
+			"Reverting {:?} ({} inserted, {} deleted)",
+			reverted.hash,
+			inserted.len(),
+			reverted.deleted.len(),
+		);
+
+		if self.count_insertions {
+			let new_max_block = self.death_rows.len() as u64 + self.pending_number;
+			self.death_index.retain(|_, block| *block < new_max_block);
+
+			let record = db.get_meta(&reverted.journal_key).map_err(Error::Db)?
+				.map(|record| JournalRecord::<BlockHash, Key>::decode(&mut record.as_slice()))
+				.transpose()?;
+			let reinserted: HashSet<Key> = record.map(|r| r.inserted.into_iter().collect())
+				.unwrap_or_default();
+			let (reinserted, inserted): (Vec<_>, Vec<_>) = inserted.into_iter()
+				.partition(|k| reinserted.contains(k));
+			commit.data.deleted.extend(inserted);
+			match self.death_rows.back_mut() {
+				// the journal of the block which deleted them still has them, so they are
+				// pending deletion by it once the window is read again, which is safe too.
+				Some(last) => for k in reinserted {
+					self.death_index.insert(k.clone(), new_max_block - 1);
+					last.deleted.insert(k);
+				},
+				None => commit.data.deleted.extend(reinserted),
+			}
+		} else {
+			commit.data.deleted.extend(inserted);
+		}
+		commit.meta.deleted.push(reverted.journal_key);
+		commit.meta.deleted.push(insertions_key);
+		Ok(())
+	}
+
 	/// Prune next block. Expects at least one block in the window. Adds changes to `commit`.
 	pub fn prune_one(&mut self, commit: &mut CommitSet<Key>) {
 		if let Some(pruned) = self.death_rows.get(self.pending_prunings) {
 			trace!(target: "state-db", "Pruning {:?} ({} deleted)", pruned.hash, pruned.deleted.len());
 			let index = self.pending_number + self.pending_prunings as u64;
-			commit.data.deleted.extend(pruned.deleted.iter().cloned());
+			if self.count_insertions || commit.data.inserted.is_empty() {
+				commit.data.deleted.extend(pruned.deleted.iter().cloned());
+			} else {
+				// each insertion of a deleted node cancels its deletion.
+				let mut deleted = pruned.deleted.clone();
+				commit.data.inserted.retain(|(k, _)| !deleted.remove(k));
+				commit.data.deleted.extend(deleted);
+			}
 			commit.meta.inserted.push((to_meta_key(LAST_PRUNED, &()), index.encode()));
 			commit.meta.deleted.push(pruned.journal_key.clone());
+			commit.meta.deleted.push(to_insertions_key(index));
 			self.pending_prunings += 1;
 		} else {
 			warn!(target: "state-db", "Trying to prune when there's nothing to prune");
@@ -166,8 +252,12 @@ impl<BlockHash: Hash, Key: Hash> RefWindow<BlockHash, Key> {
 	/// Add a change set to the window. Creates a journal record and pushes it to `commit`
 	pub fn note_canonical(&mut self, hash: &BlockHash, commit: &mut CommitSet<Key>) {
 		trace!(target: "state-db", "Adding to pruning window: {:?} ({} inserted, {} deleted)", hash, commit.data.inserted.len(), commit.data.deleted.len());
+		// the nodes that aren't pending deletion are left out of the journal.
 		let inserted = if self.count_insertions {
-			commit.data.inserted.iter().map(|(k, _)| k.clone()).collect()
+			commit.data.inserted.iter()
+				.filter(|(k, _)| self.death_index.contains_key(k))
+				.map(|(k, _)| k.clone())
+				.collect()
 		} else {
 			Default::default()
 		};
@@ -180,6 +270,8 @@ impl<BlockHash: Hash, Key: Hash> RefWindow<BlockHash, Key> {
 		let block = self.pending_number + self.death_rows.len() as u64;
 		let journal_key = to_journal_key(block);
 		commit.meta.inserted.push((journal_key.clone(), journal_record.encode()));
+		let all_inserted: Vec<&Key> = commit.data.inserted.iter().map(|(k, _)| k).collect();
+		commit.meta.inserted.push((to_insertions_key(block), all_inserted.encode()));
 		self.import(&journal_record.hash, journal_key, journal_record.inserted.into_iter(), journal_record.deleted);
 		self.pending_canonicalizations += 1;
 	}
@@ -192,7 +284,10 @@ impl<BlockHash: Hash, Key: Hash> RefWindow<BlockHash, Key> {
 			trace!(target: "state-db", "Applying pruning {:?} ({} deleted)", pruned.hash, pruned.deleted.len());
 			if self.count_insertions {
 				for k in pruned.deleted.iter() {
-					self.death_index.remove(&k);
+					// the node may be deleted again by a later block.
+					if self.death_index.get(k) == Some(&self.pending_number) {
+						self.death_index.remove(k);
+					}
 				}
 			}
 			self.pending_number += 1;
@@ -219,6 +314,7 @@ impl<BlockHash: Hash, Key: Hash> RefWindow<BlockHash, Key> {
 #[cfg(test)]
 mod tests {
 	use super::RefWindow;
+	use codec::Decode;
 	use sp_core::H256;
 	use crate::CommitSet;
 	use crate::test::{make_db, make_commit, TestDb};
@@ -395,6 +491,75 @@ mod tests {
 		assert_eq!(pruning.pending_number, 3);
 	}
 
+	#[test]
+	fn journals_only_reinserted_keys() {
+		let mut db = make_db(&[1, 2, 3]);
+		let mut pruning: RefWindow<H256, H256> = RefWindow::new(&db, true).unwrap();
+		let mut commit = make_commit(&[4, 5], &[2]);
+		pruning.note_canonical(&H256::random(), &mut commit);
+		db.commit(&commit);
+		let mut commit = make_commit(&[2, 6, 7], &[3]);
+		pruning.note_canonical(&H256::random(), &mut commit);
+		db.commit(&commit);
+		let mut commit = make_commit(&[8], &[3]);
+		pruning.note_canonical(&H256::random(), &mut commit);
+		db.commit(&commit);
+		pruning.apply_pending();
+
+		let record = |block| {
+			let record = db.meta.get(&super::to_journal_key(block)).unwrap();
+			super::JournalRecord::<H256, H256>::decode(&mut record.as_slice()).unwrap()
+		};
+		assert!(record(0).inserted.is_empty());
+		assert_eq!(record(1).inserted, vec![H256::from_low_u64_be(2)]);
+		check_journal(&pruning, &db);
+
+		// `3` is still pending deletion by the last block once the second one is pruned.
+		let mut commit = CommitSet::default();
+		pruning.prune_one(&mut commit);
+		pruning.prune_one(&mut commit);
+		db.commit(&commit);
+		pruning.apply_pending();
+		assert_eq!(pruning.death_index.get(&H256::from_low_u64_be(3)), Some(&2));
+		check_journal(&pruning, &db);
+	}
+
+	#[test]
+	fn revert_last_deletes_the_inserted_nodes() {
+		for count_insertions in &[true, false] {
+			let mut db = make_db(&[1, 2, 3]);
+			let mut pruning: RefWindow<H256, H256> =
+				RefWindow::new(&db, *count_insertions).unwrap();
+			let mut commit = make_commit(&[4], &[2]);
+			pruning.note_canonical(&H256::random(), &mut commit);
+			db.commit(&commit);
+			let mut commit = make_commit(&[2, 5], &[3]);
+			pruning.note_canonical(&H256::random(), &mut commit);
+			db.commit(&commit);
+			pruning.apply_pending();
+
+			let mut commit = CommitSet::default();
+			pruning.revert_last(&db, &mut commit).unwrap();
+			db.commit(&commit);
+			assert_eq!(pruning.death_rows.len(), 1);
+			if *count_insertions {
+				// `2` is pending deletion by the first block again.
+				assert!(db.data_eq(&make_db(&[1, 2, 3, 4])));
+				assert_eq!(pruning.death_index.get(&H256::from_low_u64_be(2)), Some(&0));
+				check_journal(&pruning, &db);
+
+				let mut commit = CommitSet::default();
+				pruning.prune_one(&mut commit);
+				db.commit(&commit);
+				assert!(db.data_eq(&make_db(&[1, 3, 4])));
+			} else {
+				// the reference counts of the inserted nodes are decreased.
+				assert!(db.data_eq(&make_db(&[1, 3, 4])));
+			}
+			assert!(db.meta.get(&super::to_insertions_key(1)).is_none());
+		}
+	}
+
 	#[test]
 	fn reinserted_ignores() {
 		let mut db = make_db(&[1, 2, 3]);
@@ -420,4 +585,23 @@ mod tests {
 		assert!(pruning.death_index.is_empty());
 	}
 
+	#[test]
+	fn batches_the_ref_counts_of_reinserted_nodes() {
+		let db = make_db(&[1, 2, 3]);
+		let mut pruning: RefWindow<H256, H256> = RefWindow::new(&db, false).unwrap();
+		let mut commit = make_commit(&[], &[1, 2]);
+		pruning.note_canonical(&H256::random(), &mut commit);
+		pruning.apply_pending();
+
+		// the commit pruning the block inserts `2` again.
+		let mut commit = make_commit(&[2, 4], &[3]);
+		pruning.note_canonical(&H256::random(), &mut commit);
+		pruning.prune_one(&mut commit);
+		let expected = make_commit(&[4], &[1]);
+		assert_eq!(commit.data.inserted, expected.data.inserted);
+		assert_eq!(commit.data.deleted, expected.data.deleted);
+		pruning.apply_pending();
+		assert_eq!(pruning.death_rows.len(), 1);
+	}
+
 }
@@ -17,17 +17,116 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use libp2p::Multiaddr;
-use serde::{Deserialize, Deserializer, Serialize};
+use rand::Rng as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{collections::BTreeMap, path::PathBuf, time::Duration};
 
 /// List of telemetry servers we want to talk to. Contains the URL of the server, and the
 /// maximum verbosity level.
 ///
 /// The URL string can be either a URL or a multiaddress.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
-pub struct TelemetryEndpoints(
-	#[serde(deserialize_with = "url_or_multiaddr_deser")]
-	pub(crate) Vec<(Multiaddr, u8)>,
-);
+///
+/// The servers can be given [`EndpointOptions`], which aren't part of the serialized list.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TelemetryEndpoints {
+	pub(crate) endpoints: Vec<(Multiaddr, u8)>,
+	pub(crate) options: Vec<(Multiaddr, EndpointOptions)>,
+}
+
+impl Serialize for TelemetryEndpoints {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		self.endpoints.serialize(serializer)
+	}
+}
+
+impl<'de> Deserialize<'de> for TelemetryEndpoints {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		url_or_multiaddr_deser(deserializer)
+			.map(|endpoints| TelemetryEndpoints { endpoints, options: Vec::new() })
+	}
+}
+
+/// The connection settings of a telemetry server, e.g. to report into a private backend.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct EndpointOptions {
+	/// Headers added to the WebSocket handshake, e.g. an API key.
+	#[serde(default)]
+	pub headers: BTreeMap<String, String>,
+	/// The certificate presented to the server over TLS.
+	#[serde(default)]
+	pub client_certificate: Option<ClientCertificate>,
+	/// Path of a PEM-encoded certificate authority trusted in addition to the usual ones,
+	/// e.g. the one of a private server.
+	#[serde(default)]
+	pub ca_certificate: Option<PathBuf>,
+	/// How long to wait before reconnecting to the server.
+	#[serde(default)]
+	pub reconnect_backoff: ReconnectBackoff,
+}
+
+impl EndpointOptions {
+	/// Returns `true` if the server can't be connected to with the default transport.
+	pub(crate) fn needs_custom_transport(&self) -> bool {
+		!self.headers.is_empty() ||
+			self.client_certificate.is_some() ||
+			self.ca_certificate.is_some()
+	}
+}
+
+/// A TLS client certificate.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ClientCertificate {
+	/// Path of the PEM-encoded certificate chain, starting with the client certificate.
+	pub certificate: PathBuf,
+	/// Path of the PEM-encoded private key of the certificate, in PKCS#8 or RSA format.
+	pub private_key: PathBuf,
+}
+
+/// The delay before reconnecting to a server is the current backoff plus a random delay of up to
+/// `jitter`. The backoff starts at `initial`, and is doubled after every failed attempt up to
+/// `max`.
+///
+/// The default reconnects after 5 to 10 seconds, every time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct ReconnectBackoff {
+	/// The backoff of the first reconnection, in seconds in the JSON format.
+	#[serde(rename = "initialSecs", deserialize_with = "secs_deser")]
+	pub initial: Duration,
+	/// The maximum delay before reconnecting, in seconds in the JSON format.
+	#[serde(rename = "maxSecs", deserialize_with = "secs_deser")]
+	pub max: Duration,
+	/// The maximum random delay added to the backoff, in seconds in the JSON format.
+	#[serde(rename = "jitterSecs", deserialize_with = "secs_deser")]
+	pub jitter: Duration,
+}
+
+impl Default for ReconnectBackoff {
+	fn default() -> Self {
+		ReconnectBackoff {
+			initial: Duration::from_secs(5),
+			max: Duration::from_secs(5),
+			jitter: Duration::from_secs(5),
+		}
+	}
+}
+
+impl ReconnectBackoff {
+	/// Returns the delay before the reconnection following the given number of failed attempts.
+	pub(crate) fn delay(&self, failed_attempts: u32) -> Duration {
+		let backoff = self.initial
+			.checked_mul(2u32.saturating_pow(failed_attempts))
+			.map_or(self.max, |backoff| backoff.min(self.max));
+		let jitter = self.jitter.as_millis() as u64;
+		backoff + Duration::from_millis(rand::thread_rng().gen_range(0, jitter + 1))
+	}
+}
+
+fn secs_deser<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+	u64::deserialize(deserializer).map(Duration::from_secs)
+}
 
 /// Custom deserializer for TelemetryEndpoints, used to convert urls or multiaddr to multiaddr.
 fn url_or_multiaddr_deser<'de, D>(deserializer: D) -> Result<Vec<(Multiaddr, u8)>, D::Error>
@@ -51,14 +150,32 @@ impl TelemetryEndpoints {
 			.iter()
 			.map(|e| Ok((url_to_multiaddr(&e.0)?, e.1)))
 			.collect();
-		endpoints.map(Self)
+		endpoints.map(|endpoints| TelemetryEndpoints { endpoints, options: Vec::new() })
+	}
+
+	/// Connect to the server of the given URL or multiaddress with the given options. They are
+	/// ignored if it isn't one of the servers.
+	pub fn set_options(
+		&mut self,
+		url: &str,
+		options: EndpointOptions,
+	) -> Result<(), libp2p::multiaddr::Error> {
+		let addr = url_to_multiaddr(url)?;
+		self.options.retain(|(other, _)| *other != addr);
+		self.options.push((addr, options));
+		Ok(())
+	}
+
+	/// Returns the options of the server of the given address, if any.
+	pub(crate) fn options(&self, addr: &Multiaddr) -> Option<&EndpointOptions> {
+		self.options.iter().find(|(other, _)| other == addr).map(|(_, options)| options)
 	}
 }
 
 impl TelemetryEndpoints {
 	/// Return `true` if there are no telemetry endpoints, `false` otherwise.
 	pub fn is_empty(&self) -> bool {
-		self.0.is_empty()
+		self.endpoints.is_empty()
 	}
 }
 
@@ -83,8 +200,9 @@ fn url_to_multiaddr(url: &str) -> Result<Multiaddr, libp2p::multiaddr::Error> {
 #[cfg(test)]
 mod tests {
 	use super::url_to_multiaddr;
-	use super::TelemetryEndpoints;
+	use super::{EndpointOptions, ReconnectBackoff, TelemetryEndpoints};
 	use libp2p::Multiaddr;
+	use std::time::Duration;
 
 	#[test]
 	fn valid_endpoints() {
@@ -101,7 +219,58 @@ mod tests {
 				*b,
 			))
 		}
-		assert_eq!(telem.0, res);
+		assert_eq!(telem.endpoints, res);
+	}
+
+	#[test]
+	fn endpoint_options_are_not_serialized() {
+		let url = "wss://telemetry.polkadot.io/submit/";
+		let mut telem = TelemetryEndpoints::new(vec![(url.into(), 3)])
+			.expect("Telemetry endpoint should be valid");
+		let options: EndpointOptions = serde_json::from_str(r#"{
+			"headers": { "X-Api-Key": "key" },
+			"reconnectBackoff": { "initialSecs": 1, "maxSecs": 60 }
+		}"#).expect("options should be valid");
+		assert!(options.needs_custom_transport());
+		assert_eq!(options.reconnect_backoff.max, Duration::from_secs(60));
+		telem.set_options(url, options.clone()).expect("provided url should be valid");
+		assert_eq!(telem.options(&telem.endpoints[0].0), Some(&options));
+
+		let json = serde_json::to_string(&telem).expect("endpoints should serialize");
+		let decoded: TelemetryEndpoints =
+			serde_json::from_str(&json).expect("endpoints should deserialize");
+		assert_eq!(decoded.endpoints, telem.endpoints);
+		assert!(decoded.options.is_empty());
+	}
+
+	#[test]
+	fn reconnect_backoff_is_capped() {
+		let backoff = ReconnectBackoff {
+			initial: Duration::from_secs(1),
+			max: Duration::from_secs(5),
+			jitter: Duration::from_secs(1),
+		};
+		let delay = backoff.delay(0);
+		assert!(delay >= Duration::from_secs(1) && delay <= Duration::from_secs(2));
+		let delay = backoff.delay(2);
+		assert!(delay >= Duration::from_secs(4) && delay <= Duration::from_secs(5));
+		// the delays are still jittered once the backoff reached `max`.
+		let delays: std::collections::HashSet<_> =
+			(0..20).map(|_| backoff.delay(u32::MAX)).collect();
+		assert!(delays.iter().all(|delay| *delay >= Duration::from_secs(5)));
+		assert!(delays.iter().all(|delay| *delay <= Duration::from_secs(6)));
+		assert!(delays.len() > 1);
+	}
+
+	#[test]
+	fn default_reconnect_backoff_is_between_5_and_10_seconds() {
+		let backoff: ReconnectBackoff =
+			serde_json::from_str("{}").expect("all fields are optional");
+		assert_eq!(backoff, ReconnectBackoff::default());
+		for failed_attempts in 0..5 {
+			let delay = backoff.delay(failed_attempts);
+			assert!(delay >= Duration::from_secs(5) && delay <= Duration::from_secs(10));
+		}
 	}
 
 	#[test]
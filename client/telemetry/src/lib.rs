@@ -36,12 +36,12 @@
 
 #![warn(missing_docs)]
 
-use futures::{channel::mpsc, prelude::*};
+use futures::{channel::{mpsc, oneshot}, prelude::*};
 use libp2p::Multiaddr;
 use log::{error, warn};
 use serde::Serialize;
 use sp_utils::mpsc::{tracing_unbounded, TracingUnboundedReceiver};
-use std::collections::HashMap;
+use std::{collections::{hash_map::Entry, HashMap}, sync::Arc};
 use tracing::Id;
 
 pub use libp2p::wasm_ext::ExtTransport;
@@ -52,6 +52,8 @@ mod endpoints;
 mod layer;
 mod node;
 mod transport;
+#[cfg(not(target_os = "unknown"))]
+mod ws_client;
 
 pub use endpoints::*;
 pub use layer::*;
@@ -118,6 +120,67 @@ pub struct ConnectionMessage {
 	pub network_id: String,
 }
 
+/// The state of a running [`TelemetryWorker`].
+struct WorkerState {
+	message_receiver: mpsc::Receiver<TelemetryMessage>,
+	register_receiver: mpsc::UnboundedReceiver<Register>,
+	transport: WsTrans,
+	node_map: HashMap<Id, Vec<(u8, Multiaddr)>>,
+	node_pool: HashMap<Multiaddr, Node<WsTrans>>,
+	// kept so the channels are never closed.
+	_message_sender: mpsc::Sender<TelemetryMessage>,
+	_register_sender: mpsc::UnboundedSender<Register>,
+}
+
+impl From<TelemetryWorker> for WorkerState {
+	fn from(worker: TelemetryWorker) -> Self {
+		WorkerState {
+			message_receiver: worker.message_receiver,
+			register_receiver: worker.register_receiver,
+			transport: worker.transport,
+			node_map: HashMap::new(),
+			node_pool: HashMap::new(),
+			_message_sender: worker.message_sender,
+			_register_sender: worker.register_sender,
+		}
+	}
+}
+
+impl WorkerState {
+	async fn run(&mut self) {
+		let WorkerState {
+			message_receiver,
+			register_receiver,
+			transport,
+			node_map,
+			node_pool,
+			..
+		} = self;
+
+		loop {
+			futures::select! {
+				message = message_receiver.next() => TelemetryWorker::process_message(
+					message,
+					node_pool,
+					node_map,
+				).await,
+				init_payload = register_receiver.next() => match init_payload {
+					Some(Register::Flush(flushed)) => {
+						TelemetryWorker::flush(message_receiver, node_pool, node_map).await;
+						let _ = flushed.send(());
+					},
+					init_payload => TelemetryWorker::process_register(
+						init_payload,
+						node_pool,
+						node_map,
+						transport.clone(),
+					).await,
+				},
+			}
+		}
+	}
+}
+
 /// Telemetry worker.
 ///
 /// It should run as a background task using the [`TelemetryWorker::run`] method. This method
@@ -164,31 +227,32 @@ impl TelemetryWorker {
 	///
 	/// This should be run in a background task.
 	pub async fn run(self) {
-		let Self {
-			mut message_receiver,
-			message_sender: _,
-			mut register_receiver,
-			register_sender: _,
-			transport,
-		} = self;
+		WorkerState::from(self).run().await
+	}
 
-		let mut node_map: HashMap<Id, Vec<(u8, Multiaddr)>> = HashMap::new();
-		let mut node_pool: HashMap<Multiaddr, _> = HashMap::new();
+	/// Returns a function creating the future running the telemetry worker.
+	///
+	/// The worker can be run again by calling it again, e.g. once the task running it panicked,
+	/// and resumes with the registrations and the connections of the telemetry nodes it had.
+	pub fn restartable(self) -> impl FnMut() -> future::BoxFuture<'static, ()> + Send + 'static {
+		let state = Arc::new(futures::lock::Mutex::new(WorkerState::from(self)));
+		move || {
+			let state = state.clone();
+			async move { state.lock().await.run().await }.boxed()
+		}
+	}
 
-		loop {
-			futures::select! {
-				message = message_receiver.next() => Self::process_message(
-					message,
-					&mut node_pool,
-					&node_map,
-				).await,
-				init_payload = register_receiver.next() => Self::process_register(
-					init_payload,
-					&mut node_pool,
-					&mut node_map,
-					transport.clone(),
-				).await,
-			}
+	// dispatch the buffered messages and wait for the telemetry nodes to send them
+	async fn flush(
+		message_receiver: &mut mpsc::Receiver<TelemetryMessage>,
+		node_pool: &mut HashMap<Multiaddr, Node<WsTrans>>,
+		node_map: &HashMap<Id, Vec<(u8, Multiaddr)>>,
+	) {
+		while let Ok(Some(message)) = message_receiver.try_next() {
+			Self::process_message(Some(message), node_pool, node_map).await;
+		}
+		for node in node_pool.values_mut() {
+			let _ = node.flush().await;
 		}
 	}
 
@@ -206,7 +270,6 @@ impl TelemetryWorker {
 				endpoints,
 				connection_message,
 			} => {
-				let endpoints = endpoints.0;
 
 				let connection_message = match serde_json::to_value(&connection_message) {
 					Ok(serde_json::Value::Object(mut value)) => {
@@ -229,24 +292,48 @@ impl TelemetryWorker {
 					}
 				};
 
-				for (addr, verbosity) in endpoints {
+				for (addr, verbosity) in endpoints.endpoints.iter().cloned() {
 					log::trace!(
 						target: "telemetry",
 						"Initializing telemetry for: {:?}",
 						addr,
 					);
+					let options = endpoints.options(&addr).cloned().unwrap_or_default();
+					let node = match node_pool.entry(addr.clone()) {
+						Entry::Occupied(entry) => entry.into_mut(),
+						Entry::Vacant(entry) => {
+							let transport = match Self::endpoint_transport(&options, &transport) {
+								Ok(transport) => transport,
+								Err(err) => {
+									log::error!(
+										target: "telemetry",
+										"Could not initialize the transport of {}: {}",
+										addr,
+										err,
+									);
+									continue;
+								}
+							};
+							entry.insert(Node::new(
+								transport,
+								addr.clone(),
+								Vec::new(),
+								Vec::new(),
+								options.reconnect_backoff,
+							))
+						}
+					};
 					node_map
 						.entry(id.clone())
 						.or_default()
 						.push((verbosity, addr.clone()));
 
-					let node = node_pool.entry(addr.clone()).or_insert_with(|| {
-						Node::new(transport.clone(), addr.clone(), Vec::new(), Vec::new())
-					});
-
 					node.connection_messages.extend(connection_message.clone());
 				}
 			}
+			Register::Flush(flushed) => {
+				let _ = flushed.send(());
+			}
 			Register::Notifier {
 				addresses,
 				connection_notifier,
@@ -267,6 +354,25 @@ impl TelemetryWorker {
 		}
 	}
 
+	/// Returns the transport of a server with the given options.
+	fn endpoint_transport(
+		options: &EndpointOptions,
+		transport: &WsTrans,
+	) -> Result<WsTrans, std::io::Error> {
+		if !options.needs_custom_transport() {
+			return Ok(transport.clone());
+		}
+
+		#[cfg(not(target_os = "unknown"))]
+		return initialize_custom_transport(options);
+
+		#[cfg(target_os = "unknown")]
+		Err(std::io::Error::new(
+			std::io::ErrorKind::Other,
+			"custom headers and certificates are not supported in the browser",
+		))
+	}
+
 	// dispatch messages to the telemetry nodes
 	async fn process_message(
 		input: Option<TelemetryMessage>,
@@ -343,7 +449,7 @@ impl TelemetryHandle {
 
 		let connection_notifier = TelemetryConnectionNotifier {
 			message_sender: message_sender.clone(),
-			addresses: endpoints.0.iter().map(|(addr, _)| addr.clone()).collect(),
+			addresses: endpoints.endpoints.iter().map(|(addr, _)| addr.clone()).collect(),
 		};
 
 		match span.0.id() {
@@ -370,6 +476,16 @@ impl TelemetryHandle {
 
 		connection_notifier
 	}
+
+	/// Send the buffered telemetry messages to the servers and wait until they are sent,
+	/// e.g. before the node shuts down.
+	///
+	/// Resolves immediately if the [`TelemetryWorker`] is not running.
+	pub async fn flush(&self) {
+		let (flushed, on_flushed) = oneshot::channel();
+		let _ = self.message_sender.unbounded_send(Register::Flush(flushed));
+		let _ = on_flushed.await;
+	}
 }
 
 /// Used to create a stream of events with only one event: when a telemetry connection
@@ -413,6 +529,7 @@ enum Register {
 		addresses: Vec<Multiaddr>,
 		connection_notifier: ConnectionNotifierSender,
 	},
+	Flush(oneshot::Sender<()>),
 }
 
 /// Report a telemetry.
@@ -16,11 +16,11 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+use crate::ReconnectBackoff;
 use futures::prelude::*;
 use libp2p::core::transport::Transport;
 use libp2p::Multiaddr;
-use rand::Rng as _;
-use std::{fmt, mem, pin::Pin, task::Context, task::Poll, time::Duration};
+use std::{fmt, mem, pin::Pin, task::Context, task::Poll};
 use wasm_timer::Delay;
 
 pub(crate) type ConnectionNotifierSender = sp_utils::mpsc::TracingUnboundedSender<()>;
@@ -48,6 +48,10 @@ pub(crate) struct Node<TTrans: Transport> {
 	pub(crate) connection_messages: Vec<serde_json::Map<String, serde_json::Value>>,
 	/// Notifier for when the connection (re-)establishes.
 	pub(crate) telemetry_connection_notifier: Vec<ConnectionNotifierSender>,
+	/// How long to wait before reconnecting.
+	backoff: ReconnectBackoff,
+	/// Number of failed connections since the last successful one.
+	failed_attempts: u32,
 }
 
 enum NodeSocket<TTrans: Transport> {
@@ -63,14 +67,6 @@ enum NodeSocket<TTrans: Transport> {
 	Poisoned,
 }
 
-impl<TTrans: Transport> NodeSocket<TTrans> {
-	fn wait_reconnect() -> NodeSocket<TTrans> {
-		let random_delay = rand::thread_rng().gen_range(5, 10);
-		let delay = Delay::new(Duration::from_secs(random_delay));
-		NodeSocket::WaitingReconnect(delay)
-	}
-}
-
 struct NodeSocketConnected<TTrans: Transport> {
 	/// Where to send data.
 	sink: TTrans::Output,
@@ -85,6 +81,7 @@ impl<TTrans: Transport> Node<TTrans> {
 		addr: Multiaddr,
 		connection_messages: Vec<serde_json::Map<String, serde_json::Value>>,
 		telemetry_connection_notifier: Vec<ConnectionNotifierSender>,
+		backoff: ReconnectBackoff,
 	) -> Self {
 		Node {
 			addr,
@@ -92,8 +89,16 @@ impl<TTrans: Transport> Node<TTrans> {
 			transport,
 			connection_messages,
 			telemetry_connection_notifier,
+			backoff,
+			failed_attempts: 0,
 		}
 	}
+
+	fn wait_reconnect(&mut self) -> NodeSocket<TTrans> {
+		let delay = Delay::new(self.backoff.delay(self.failed_attempts));
+		self.failed_attempts = self.failed_attempts.saturating_add(1);
+		NodeSocket::WaitingReconnect(delay)
+	}
 }
 
 impl<TTrans: Transport, TSinkErr> Node<TTrans>
@@ -142,7 +147,7 @@ where
 						match self.as_mut().try_send_connection_messages(cx, &mut conn) {
 							Poll::Ready(Err(err)) => {
 								log::warn!(target: "telemetry", "⚠️  Disconnected from {}: {:?}", self.addr, err);
-								socket = NodeSocket::wait_reconnect();
+								socket = self.wait_reconnect();
 							}
 							Poll::Ready(Ok(())) => {
 								self.socket = NodeSocket::Connected(conn);
@@ -156,7 +161,7 @@ where
 					}
 					Poll::Ready(Err(err)) => {
 						log::warn!(target: "telemetry", "⚠️  Disconnected from {}: {:?}", self.addr, err);
-						socket = NodeSocket::wait_reconnect();
+						socket = self.wait_reconnect();
 					}
 					Poll::Pending => {
 						self.socket = NodeSocket::Connected(conn);
@@ -166,6 +171,7 @@ where
 				NodeSocket::Dialing(mut s) => match Future::poll(Pin::new(&mut s), cx) {
 					Poll::Ready(Ok(sink)) => {
 						log::debug!(target: "telemetry", "✅ Connected to {}", self.addr);
+						self.failed_attempts = 0;
 
 						for sender in self.telemetry_connection_notifier.iter_mut() {
 							let _ = sender.send(());
@@ -201,7 +207,7 @@ where
 					Poll::Pending => break NodeSocket::Dialing(s),
 					Poll::Ready(Err(err)) => {
 						log::warn!(target: "telemetry", "❌ Error while dialing {}: {:?}", self.addr, err);
-						socket = NodeSocket::wait_reconnect();
+						socket = self.wait_reconnect();
 					}
 				},
 				NodeSocket::ReconnectNow => match self.transport.clone().dial(self.addr.clone()) {
@@ -211,7 +217,7 @@ where
 					}
 					Err(err) => {
 						log::warn!(target: "telemetry", "❌ Error while dialing {}: {:?}", self.addr, err);
-						socket = NodeSocket::wait_reconnect();
+						socket = self.wait_reconnect();
 					}
 				},
 				NodeSocket::WaitingReconnect(mut s) => {
@@ -254,7 +260,7 @@ where
 		match &mut self.socket {
 			NodeSocket::Connected(conn) => match conn.sink.poll_flush_unpin(cx) {
 				Poll::Ready(Err(_)) => {
-					self.socket = NodeSocket::wait_reconnect();
+					self.socket = self.wait_reconnect();
 					Poll::Ready(Ok(()))
 				}
 				Poll::Ready(Ok(())) => Poll::Ready(Ok(())),
@@ -72,6 +72,15 @@ pub(crate) fn initialize_transport(
 	.boxed())
 }
 
+/// Initializes the transport of a server with the given options.
+#[cfg(not(target_os = "unknown"))]
+pub(crate) fn initialize_custom_transport(
+	options: &crate::EndpointOptions,
+) -> Result<WsTrans, io::Error> {
+	let client = crate::ws_client::WsClient::new(options)?;
+	Ok(TransportTimeout::new(client, CONNECT_TIMEOUT).boxed())
+}
+
 /// A trait that implements `Stream` and `Sink`.
 pub(crate) trait StreamAndSink<I>: Stream + Sink<I> {}
 impl<T: ?Sized + Stream + Sink<I>, I> StreamAndSink<I> for T {}
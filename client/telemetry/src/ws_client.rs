@@ -0,0 +1,280 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! WebSocket client connecting to the telemetry servers with [`EndpointOptions`], i.e. sending
+//! custom headers in the handshake or presenting a TLS client certificate, which the libp2p
+//! WebSocket transport doesn't support.
+
+use crate::{transport::StreamAndSink, ClientCertificate, EndpointOptions};
+use futures::{future::{self, BoxFuture}, prelude::*, sink, stream};
+use futures_rustls::{
+	rustls::{self, ClientConfig},
+	webpki::DNSNameRef,
+	TlsConnector,
+};
+use libp2p::{
+	core::transport::{ListenerEvent, TransportError},
+	multiaddr::Protocol,
+	Multiaddr, Transport,
+};
+use soketto::handshake::client::{Client, Header, ServerResponse};
+use std::{fs::File, io, io::BufReader, path::Path, pin::Pin, sync::Arc};
+
+pub(crate) type WsConnection = Pin<
+	Box<dyn StreamAndSink<Vec<u8>, Item = Result<Vec<u8>, io::Error>, Error = io::Error> + Send>,
+>;
+
+trait Io: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> Io for T {}
+
+/// A server, parsed from its multiaddress.
+struct Target {
+	host: String,
+	port: u16,
+	path: String,
+	tls: bool,
+}
+
+impl Target {
+	/// Parses `/dns/<host>/tcp/<port>/x-parity-wss/<path>` and the like.
+	fn parse(addr: &Multiaddr) -> Option<Self> {
+		let mut protocols = addr.iter();
+		let host = match protocols.next()? {
+			Protocol::Dns(host) | Protocol::Dns4(host) | Protocol::Dns6(host) => host.to_string(),
+			Protocol::Ip4(ip) => ip.to_string(),
+			Protocol::Ip6(ip) => format!("[{}]", ip),
+			_ => return None,
+		};
+		let port = match protocols.next()? {
+			Protocol::Tcp(port) => port,
+			_ => return None,
+		};
+		let (path, tls) = match protocols.next()? {
+			Protocol::Ws(path) => (path.to_string(), false),
+			Protocol::Wss(path) => (path.to_string(), true),
+			_ => return None,
+		};
+		if protocols.next().is_some() {
+			return None;
+		}
+
+		Some(Target { host, port, path, tls })
+	}
+
+	/// The value of the `Host` header.
+	fn host_header(&self) -> String {
+		match (self.tls, self.port) {
+			(true, 443) | (false, 80) => self.host.clone(),
+			_ => format!("{}:{}", self.host, self.port),
+		}
+	}
+}
+
+/// A transport dialing the telemetry servers with the given options, over TCP.
+#[derive(Clone)]
+pub(crate) struct WsClient {
+	connector: TlsConnector,
+	headers: Arc<Vec<(String, String)>>,
+}
+
+impl WsClient {
+	pub(crate) fn new(options: &EndpointOptions) -> Result<Self, io::Error> {
+		let mut config = ClientConfig::new();
+		config.root_store.add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+		if let Some(ca_certificate) = &options.ca_certificate {
+			let (added, _) = config.root_store.add_pem_file(&mut open(ca_certificate)?)
+				.map_err(|()| invalid_data("Invalid PEM certificate authority"))?;
+			if added == 0 {
+				return Err(invalid_data("No certificate authority found"));
+			}
+		}
+		if let Some(ClientCertificate { certificate, private_key }) = &options.client_certificate {
+			let certificates = rustls::internal::pemfile::certs(&mut open(certificate)?)
+				.map_err(|()| invalid_data("Invalid PEM certificate chain"))?;
+			let mut keys = rustls::internal::pemfile::pkcs8_private_keys(&mut open(private_key)?)
+				.map_err(|()| invalid_data("Invalid PEM private key"))?;
+			if keys.is_empty() {
+				keys = rustls::internal::pemfile::rsa_private_keys(&mut open(private_key)?)
+					.map_err(|()| invalid_data("Invalid PEM private key"))?;
+			}
+			let key = keys.into_iter().next().ok_or_else(|| invalid_data("No private key found"))?;
+			config.set_single_client_cert(certificates, key)
+				.map_err(|e| invalid_data(&e.to_string()))?;
+		}
+
+		Ok(WsClient {
+			connector: TlsConnector::from(Arc::new(config)),
+			headers: Arc::new(options.headers.clone().into_iter().collect()),
+		})
+	}
+
+	async fn connect(self, target: Target) -> Result<WsConnection, io::Error> {
+		let host = target.host.trim_start_matches('[').trim_end_matches(']');
+		let socket = async_std::net::TcpStream::connect((host, target.port)).await?;
+		let socket: Box<dyn Io> = if target.tls {
+			let domain = DNSNameRef::try_from_ascii_str(host)
+				.map_err(|_| invalid_data("TLS requires the domain name of the server"))?;
+			Box::new(self.connector.connect(domain, socket).await?)
+		} else {
+			Box::new(socket)
+		};
+
+		let host_header = target.host_header();
+		let headers = self.headers.iter()
+			.map(|(name, value)| Header { name, value: value.as_bytes() })
+			.collect::<Vec<_>>();
+		let mut client = Client::new(socket, &host_header, &target.path);
+		client.set_headers(&headers);
+		match client.handshake().await.map_err(other)? {
+			ServerResponse::Accepted { .. } => {},
+			ServerResponse::Redirect { status_code, location } => return Err(invalid_data(
+				&format!("Handshake redirected with status {} to {}", status_code, location),
+			)),
+			ServerResponse::Rejected { status_code } => return Err(invalid_data(
+				&format!("Handshake rejected with status {}", status_code),
+			)),
+		}
+
+		let (sender, receiver) = client.into_builder().finish();
+		let sink = sink::unfold(sender, |mut sender, item: Vec<u8>| async move {
+			sender.send_binary(&item).await.map_err(other)?;
+			sender.flush().await.map_err(other)?;
+			Ok::<_, io::Error>(sender)
+		});
+		let stream = stream::unfold(receiver, |mut receiver| async move {
+			let mut data = Vec::new();
+			match receiver.receive_data(&mut data).await {
+				Ok(_) => Some((Ok(data), receiver)),
+				Err(soketto::connection::Error::Closed) => None,
+				Err(e) => Some((Err(other(e)), receiver)),
+			}
+		});
+
+		Ok(Box::pin(Duplex { stream, sink }))
+	}
+}
+
+impl Transport for WsClient {
+	type Output = WsConnection;
+	type Error = io::Error;
+	type Listener = stream::Pending<
+		Result<ListenerEvent<Self::ListenerUpgrade, Self::Error>, Self::Error>,
+	>;
+	type ListenerUpgrade = future::Pending<Result<Self::Output, Self::Error>>;
+	type Dial = BoxFuture<'static, Result<Self::Output, Self::Error>>;
+
+	fn listen_on(self, addr: Multiaddr) -> Result<Self::Listener, TransportError<Self::Error>> {
+		Err(TransportError::MultiaddrNotSupported(addr))
+	}
+
+	fn dial(self, addr: Multiaddr) -> Result<Self::Dial, TransportError<Self::Error>> {
+		match Target::parse(&addr) {
+			Some(target) => Ok(self.connect(target).boxed()),
+			None => Err(TransportError::MultiaddrNotSupported(addr)),
+		}
+	}
+
+	fn address_translation(&self, _: &Multiaddr, _: &Multiaddr) -> Option<Multiaddr> {
+		None
+	}
+}
+
+fn open(path: &Path) -> Result<BufReader<File>, io::Error> {
+	File::open(path).map(BufReader::new)
+}
+
+fn invalid_data(error: &str) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, error.to_owned())
+}
+
+fn other(error: impl std::error::Error + Send + Sync + 'static) -> io::Error {
+	io::Error::new(io::ErrorKind::Other, error)
+}
+
+/// Combines a stream and a sink.
+#[pin_project::pin_project]
+struct Duplex<St, Si> {
+	#[pin]
+	stream: St,
+	#[pin]
+	sink: Si,
+}
+
+impl<St: Stream, Si> Stream for Duplex<St, Si> {
+	type Item = St::Item;
+
+	fn poll_next(
+		self: Pin<&mut Self>,
+		cx: &mut std::task::Context,
+	) -> std::task::Poll<Option<Self::Item>> {
+		self.project().stream.poll_next(cx)
+	}
+}
+
+impl<St, Si: Sink<I>, I> Sink<I> for Duplex<St, Si> {
+	type Error = Si::Error;
+
+	fn poll_ready(
+		self: Pin<&mut Self>,
+		cx: &mut std::task::Context,
+	) -> std::task::Poll<Result<(), Self::Error>> {
+		self.project().sink.poll_ready(cx)
+	}
+
+	fn start_send(self: Pin<&mut Self>, item: I) -> Result<(), Self::Error> {
+		self.project().sink.start_send(item)
+	}
+
+	fn poll_flush(
+		self: Pin<&mut Self>,
+		cx: &mut std::task::Context,
+	) -> std::task::Poll<Result<(), Self::Error>> {
+		self.project().sink.poll_flush(cx)
+	}
+
+	fn poll_close(
+		self: Pin<&mut Self>,
+		cx: &mut std::task::Context,
+	) -> std::task::Poll<Result<(), Self::Error>> {
+		self.project().sink.poll_close(cx)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_websocket_addresses() {
+		let target = |url| Target::parse(&libp2p::multiaddr::from_url(url).unwrap());
+
+		let secure = target("wss://telemetry.example.com/submit/").unwrap();
+		assert_eq!(
+			(secure.host.as_str(), secure.port, secure.path.as_str(), secure.tls),
+			("telemetry.example.com", 443, "/submit/", true),
+		);
+		assert_eq!(secure.host_header(), "telemetry.example.com");
+
+		let plain = target("ws://127.0.0.1:8000/submit").unwrap();
+		assert_eq!((plain.port, plain.tls), (8000, false));
+		assert_eq!(plain.host_header(), "127.0.0.1:8000");
+
+		assert!(Target::parse(&"/ip4/127.0.0.1/tcp/8000".parse().unwrap()).is_none());
+	}
+}
@@ -0,0 +1,118 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Tracing of the re-execution of blocks.
+//!
+//! The spans and events of the given targets are collected while a block is executed, e.g. to
+//! let debugging tools follow the storage accesses of every extrinsic. The spans entered in
+//! the runtime are only recorded if it was built with the `with-tracing` feature.
+
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use tracing::Level;
+use tracing_subscriber::layer::SubscriberExt;
+use sp_rpc::tracing::{Event, Span};
+
+use crate::{check_target, parse_target, ProfilingLayer, SpanDatum, TraceEvent, TraceHandler};
+
+/// The targets traced if none are given: the pallet spans and the storage accesses.
+pub const DEFAULT_TARGETS: &str = "pallet,frame,state";
+
+/// The spans and events recorded so far.
+#[derive(Default)]
+struct Trace {
+	spans: Vec<Span>,
+	events: Vec<Event>,
+}
+
+/// Collects the spans and events of the traced targets.
+struct Collector {
+	targets: Vec<(String, Level)>,
+	trace: Arc<Mutex<Trace>>,
+}
+
+impl TraceHandler for Collector {
+	fn handle_span(&self, mut span: SpanDatum) {
+		if !check_target(&self.targets, &span.target, &span.level) {
+			return;
+		}
+		let wasm = span.values.bool_values.remove("wasm").unwrap_or(false);
+		self.trace.lock().spans.push(Span {
+			id: span.id.into_u64(),
+			parent_id: span.parent_id.map(|id| id.into_u64()),
+			name: span.name,
+			target: span.target,
+			wasm,
+			values: span.values.into_strings(),
+		});
+	}
+
+	fn handle_event(&self, event: TraceEvent) {
+		if !check_target(&self.targets, &event.target, &event.level) {
+			return;
+		}
+		self.trace.lock().events.push(Event {
+			target: event.target,
+			parent_id: event.parent_id.map(|id| id.into_u64()),
+			values: event.values.into_strings(),
+		});
+	}
+}
+
+/// Run `execute` with the given comma separated targets traced on this thread, returning
+/// its result and the spans and events that were recorded.
+///
+/// The targets are given as for the [`ProfilingLayer`], e.g. "pallet=debug,state".
+pub fn trace<R>(targets: &str, execute: impl FnOnce() -> R) -> (R, Vec<Span>, Vec<Event>) {
+	let trace = Arc::new(Mutex::new(Trace::default()));
+	let collector = Collector {
+		targets: targets.split(',').map(parse_target).collect(),
+		trace: trace.clone(),
+	};
+	let subscriber = tracing_subscriber::Registry::default()
+		.with(ProfilingLayer::new_with_handler(Box::new(collector), targets));
+
+	let result = tracing::subscriber::with_default(subscriber, execute);
+	let Trace { spans, events } = std::mem::take(&mut *trace.lock());
+	(result, spans, events)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn records_the_spans_and_events_of_the_targets() {
+		let ((), spans, events) = trace("pallet,state=debug", || {
+			let span = tracing::info_span!(target: "pallet", "on_initialize");
+			let _enter = span.enter();
+			tracing::debug!(target: "state", key = "0x01", "Get");
+			tracing::trace!(target: "state", key = "0x02", "Get");
+			tracing::info!(target: "network", "Ignored");
+		});
+
+		assert_eq!(spans.len(), 1);
+		assert_eq!(spans[0].name, "on_initialize");
+		assert!(!spans[0].wasm);
+		assert_eq!(events.len(), 1);
+		assert_eq!(events[0].target, "state");
+		assert_eq!(events[0].parent_id, Some(spans[0].id));
+		assert_eq!(events[0].values.get("key").map(String::as_str), Some("0x01"));
+	}
+}
@@ -28,9 +28,11 @@
 
 #![warn(missing_docs)]
 
+pub mod block;
 pub mod logging;
 
 use rustc_hash::FxHashMap;
+use std::collections::BTreeMap;
 use std::fmt;
 use std::time::{Duration, Instant};
 use parking_lot::Mutex;
@@ -149,6 +151,14 @@ impl Values {
 			self.u64_values.is_empty() &&
 			self.string_values.is_empty()
 	}
+
+	/// Returns all the values, formatted as strings.
+	pub fn into_strings(self) -> BTreeMap<String, String> {
+		let bool_iter = self.bool_values.into_iter().map(|(k, v)| (k, v.to_string()));
+		let i64_iter = self.i64_values.into_iter().map(|(k, v)| (k, v.to_string()));
+		let u64_iter = self.u64_values.into_iter().map(|(k, v)| (k, v.to_string()));
+		bool_iter.chain(i64_iter).chain(u64_iter).chain(self.string_values).collect()
+	}
 }
 
 impl Visit for Values {
@@ -237,13 +247,17 @@ impl ProfilingLayer {
 	}
 
 	fn check_target(&self, target: &str, level: &Level) -> bool {
-		for t in &self.targets {
-			if target.starts_with(t.0.as_str()) && level <= &t.1 {
-				return true;
-			}
+		check_target(&self.targets, target, level)
+	}
+}
+
+fn check_target(targets: &[(String, Level)], target: &str, level: &Level) -> bool {
+	for t in targets {
+		if target.starts_with(t.0.as_str()) && level <= &t.1 {
+			return true;
 		}
-		false
 	}
+	false
 }
 
 // Default to TRACE if no level given or unable to parse Level
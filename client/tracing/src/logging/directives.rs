@@ -16,13 +16,12 @@
 
 use once_cell::sync::OnceCell;
 use parking_lot::Mutex;
-use tracing_subscriber::{
-	filter::Directive, fmt as tracing_fmt, fmt::time::ChronoLocal, layer, reload::Handle,
-	EnvFilter, Registry,
-};
+use tracing_subscriber::{filter::Directive, EnvFilter};
+
+use crate::logging::SharedFilter;
 
 // Handle to reload the tracing log filter
-static FILTER_RELOAD_HANDLE: OnceCell<Handle<EnvFilter, SCSubscriber>> = OnceCell::new();
+static FILTER_RELOAD_HANDLE: OnceCell<SharedFilter> = OnceCell::new();
 // Directives that are defaulted to when resetting the log filter
 static DEFAULT_DIRECTIVES: OnceCell<Mutex<Vec<String>>> = OnceCell::new();
 // Current state of log filter
@@ -90,8 +89,8 @@ pub fn reload_filter() -> Result<(), String> {
 	FILTER_RELOAD_HANDLE
 		.get()
 		.ok_or("No reload handle present".to_string())?
-		.reload(env_filter)
-		.map_err(|e| format!("{}", e))
+		.reload(env_filter);
+	Ok(())
 }
 
 /// Resets the log filter back to the original state when the node was started.
@@ -110,14 +109,6 @@ pub fn reset_log_filter() -> Result<(), String> {
 }
 
 /// Initialize FILTER_RELOAD_HANDLE, only possible once
-pub(crate) fn set_reload_handle(handle: Handle<EnvFilter, SCSubscriber>) {
+pub(crate) fn set_reload_handle(handle: SharedFilter) {
 	let _ = FILTER_RELOAD_HANDLE.set(handle);
 }
-
-// The layered Subscriber as built up in `LoggerBuilder::init()`.
-// Used in the reload `Handle`.
-type SCSubscriber<
-	N = tracing_fmt::format::DefaultFields,
-	E = crate::logging::EventFormat<ChronoLocal>,
-	W = fn() -> std::io::Stderr,
-> = layer::Layered<tracing_fmt::Layer<Registry, N, E, W>, Registry>;
@@ -0,0 +1,202 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! A layer writing the logs to a file, rotated once it grows too large or too old.
+
+use std::{
+	fs::{self, File, OpenOptions},
+	io::{self, Write},
+	path::PathBuf,
+	sync::Arc,
+	time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+use tracing::Subscriber;
+use tracing_subscriber::{
+	fmt::{format, time::ChronoLocal, Layer as FmtLayer, MakeWriter},
+	registry::LookupSpan,
+};
+
+use crate::logging::{EventFormat, FilteredLayer, Result, SharedFilter};
+
+/// The configuration of the file the logs are written to.
+#[derive(Debug, Clone)]
+pub struct FileLogConfig {
+	/// The path of the file.
+	pub path: PathBuf,
+	/// The filter of the logs written to the file, with the same syntax as the log filter of
+	/// the node, which is used instead if `None`.
+	///
+	/// It is applied after the directives of `RUST_LOG`, and unlike the filter of the node it
+	/// isn't changed by the RPCs adding or resetting log filters.
+	pub directives: Option<String>,
+	/// Rotate the file once it's larger than this, in bytes.
+	pub max_size: Option<u64>,
+	/// Rotate the file once it was opened this long ago.
+	pub max_age: Option<Duration>,
+	/// The number of rotated files to keep, `path.1` being the latest.
+	pub keep: usize,
+}
+
+impl FileLogConfig {
+	/// Write the logs to the given file, without rotating it.
+	pub fn new(path: impl Into<PathBuf>) -> Self {
+		FileLogConfig {
+			path: path.into(),
+			directives: None,
+			max_size: None,
+			max_age: None,
+			keep: 5,
+		}
+	}
+}
+
+/// A file that is rotated once it grows too large or too old.
+struct RotatingFile {
+	config: FileLogConfig,
+	file: File,
+	size: u64,
+	opened: Instant,
+}
+
+impl RotatingFile {
+	fn open(config: FileLogConfig) -> io::Result<Self> {
+		if let Some(parent) = config.path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+		let file = OpenOptions::new().create(true).append(true).open(&config.path)?;
+		let size = file.metadata()?.len();
+		Ok(RotatingFile { config, file, size, opened: Instant::now() })
+	}
+
+	fn should_rotate(&self) -> bool {
+		self.size > 0 && (
+			self.config.max_size.map_or(false, |max_size| self.size >= max_size) ||
+			self.config.max_age.map_or(false, |max_age| self.opened.elapsed() >= max_age)
+		)
+	}
+
+	/// The path of the rotated file with the given index.
+	fn rotated_path(&self, index: usize) -> PathBuf {
+		let mut path = self.config.path.clone().into_os_string();
+		path.push(format!(".{}", index));
+		path.into()
+	}
+
+	/// Move the file to `path.1`, shifting the previously rotated files and removing the
+	/// oldest one, and start a new file.
+	fn rotate(&mut self) -> io::Result<()> {
+		self.file.flush()?;
+		if self.config.keep == 0 {
+			fs::remove_file(&self.config.path)?;
+		} else {
+			for index in (1..self.config.keep).rev() {
+				let from = self.rotated_path(index);
+				if from.exists() {
+					fs::rename(from, self.rotated_path(index + 1))?;
+				}
+			}
+			fs::rename(&self.config.path, self.rotated_path(1))?;
+		}
+
+		self.file = OpenOptions::new().create(true).append(true).open(&self.config.path)?;
+		self.size = 0;
+		self.opened = Instant::now();
+		Ok(())
+	}
+}
+
+/// A writer to the shared log file. The file is rotated, if needed, when a writer is made,
+/// so that every log line ends up in a single file.
+#[derive(Clone)]
+pub struct FileLogWriter(Arc<Mutex<RotatingFile>>);
+
+impl MakeWriter for FileLogWriter {
+	type Writer = FileLogWriter;
+
+	fn make_writer(&self) -> Self::Writer {
+		let mut file = self.0.lock();
+		if file.should_rotate() {
+			if let Err(e) = file.rotate() {
+				// logging the error would deadlock.
+				eprintln!("Failed to rotate the log file {}: {}", file.config.path.display(), e);
+			}
+		}
+		self.clone()
+	}
+}
+
+impl Write for FileLogWriter {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		let mut file = self.0.lock();
+		let written = file.file.write(buf)?;
+		file.size += written as u64;
+		Ok(written)
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		self.0.lock().file.flush()
+	}
+}
+
+/// A `Layer` writing the logs to a file, see [`FileLogConfig`].
+pub type FileLogLayer<S> =
+	FilteredLayer<FmtLayer<S, format::DefaultFields, EventFormat<ChronoLocal>, FileLogWriter>>;
+
+/// Open the file of the given configuration, to write the logs passing the given filter to it.
+pub fn file_log_layer<S>(config: FileLogConfig, filter: SharedFilter) -> Result<FileLogLayer<S>>
+where
+	S: Subscriber + for<'a> LookupSpan<'a>,
+{
+	let writer = FileLogWriter(Arc::new(Mutex::new(RotatingFile::open(config)?)));
+	// the file keeps the history of the node, so its logs are always detailed.
+	let event_format = EventFormat {
+		timer: ChronoLocal::with_format("%Y-%m-%d %H:%M:%S%.3f".to_string()),
+		display_target: true,
+		display_level: true,
+		display_thread_name: true,
+		enable_color: false,
+	};
+	let layer = FmtLayer::default().event_format(event_format).with_writer(writer);
+
+	Ok(FilteredLayer::new(layer, filter))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn rotates_the_file_once_too_large() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("logs").join("node.log");
+		let config = FileLogConfig { max_size: Some(10), keep: 2, ..FileLogConfig::new(&path) };
+		let writer = FileLogWriter(Arc::new(Mutex::new(RotatingFile::open(config).unwrap())));
+
+		for line in &["first line\n", "second line\n", "third line\n", "fourth line\n"] {
+			writer.make_writer().write_all(line.as_bytes()).unwrap();
+		}
+
+		let read = |path| fs::read_to_string(path).unwrap();
+		assert_eq!(read(path.clone()), "fourth line\n");
+		assert_eq!(read(dir.path().join("logs/node.log.1")), "third line\n");
+		assert_eq!(read(dir.path().join("logs/node.log.2")), "second line\n");
+		assert!(!dir.path().join("logs/node.log.3").exists());
+	}
+}
@@ -0,0 +1,201 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Filters of the logs of every layer, so that the log file can be more verbose than the console.
+//!
+//! A callsite disabled by a layer is disabled for all of them, so the layers don't filter the
+//! callsites: the [`AnyFilterLayer`] enables what passes one of the filters, and each
+//! [`FilteredLayer`] only receives the events that pass its own filter.
+
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+use tracing::{
+	span::{Attributes, Record}, subscriber::Interest, Event, Id, Metadata, Subscriber,
+};
+use tracing_log::NormalizeEvent;
+use tracing_subscriber::{filter::LevelFilter, layer::Context, EnvFilter, Layer, Registry};
+
+/// A filter of the logs, shared by the layers it applies to and reloaded in place.
+#[derive(Clone)]
+pub struct SharedFilter(Arc<RwLock<EnvFilter>>);
+
+impl SharedFilter {
+	/// Share the given filter.
+	pub fn new(filter: EnvFilter) -> Self {
+		SharedFilter(Arc::new(RwLock::new(filter)))
+	}
+
+	/// Replace the filter, for all the layers it applies to.
+	pub fn reload(&self, filter: EnvFilter) {
+		*self.0.write() = filter;
+		// the interests of the callsites were computed with the previous filter.
+		tracing::callsite::rebuild_interest_cache();
+	}
+
+	/// Returns the most verbose level enabled by the filter, if it is known.
+	pub fn max_level_hint(&self) -> Option<LevelFilter> {
+		Layer::<Registry>::max_level_hint(&*self.0.read())
+	}
+
+	fn enabled<S: Subscriber>(&self, metadata: &Metadata<'_>, ctx: Context<'_, S>) -> bool {
+		Layer::<S>::enabled(&*self.0.read(), metadata, ctx)
+	}
+}
+
+/// A `Layer` enabling the spans and events that pass any of the given filters.
+///
+/// The filters are told about the spans here, so this layer must come before the
+/// [`FilteredLayer`]s using them.
+pub struct AnyFilterLayer {
+	filters: Vec<SharedFilter>,
+}
+
+impl AnyFilterLayer {
+	/// Enable what passes any of the given filters.
+	pub fn new(filters: Vec<SharedFilter>) -> Self {
+		AnyFilterLayer { filters }
+	}
+}
+
+impl<S: Subscriber> Layer<S> for AnyFilterLayer {
+	fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+		let (mut always, mut never) = (true, true);
+		for filter in &self.filters {
+			let interest = Layer::<S>::register_callsite(&*filter.0.read(), metadata);
+			always &= interest.is_always();
+			never &= interest.is_never();
+		}
+		if never {
+			Interest::never()
+		} else if always {
+			Interest::always()
+		} else {
+			Interest::sometimes()
+		}
+	}
+
+	fn enabled(&self, metadata: &Metadata<'_>, ctx: Context<'_, S>) -> bool {
+		self.filters.iter().any(|filter| filter.enabled(metadata, ctx.clone()))
+	}
+
+	fn max_level_hint(&self) -> Option<LevelFilter> {
+		self.filters.iter()
+			.map(SharedFilter::max_level_hint)
+			.collect::<Option<Vec<_>>>()?
+			.into_iter()
+			.max()
+	}
+
+	fn new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+		for filter in &self.filters {
+			filter.0.read().new_span(attrs, id, ctx.clone());
+		}
+	}
+
+	fn on_record(&self, span: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+		for filter in &self.filters {
+			filter.0.read().on_record(span, values, ctx.clone());
+		}
+	}
+
+	fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+		for filter in &self.filters {
+			filter.0.read().on_enter(id, ctx.clone());
+		}
+	}
+
+	fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+		for filter in &self.filters {
+			filter.0.read().on_exit(id, ctx.clone());
+		}
+	}
+
+	fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+		for filter in &self.filters {
+			filter.0.read().on_close(id.clone(), ctx.clone());
+		}
+	}
+}
+
+/// A `Layer` only receiving the events that pass its own filter.
+///
+/// The layer still receives all the spans, which the events are formatted with.
+pub struct FilteredLayer<L> {
+	layer: L,
+	filter: SharedFilter,
+}
+
+impl<L> FilteredLayer<L> {
+	/// Filter the events of the given layer.
+	pub fn new(layer: L, filter: SharedFilter) -> Self {
+		FilteredLayer { layer, filter }
+	}
+}
+
+impl<S, L> Layer<S> for FilteredLayer<L>
+where
+	S: Subscriber,
+	L: Layer<S>,
+{
+	fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+		self.layer.register_callsite(metadata)
+	}
+
+	fn enabled(&self, metadata: &Metadata<'_>, ctx: Context<'_, S>) -> bool {
+		self.layer.enabled(metadata, ctx)
+	}
+
+	fn new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+		self.layer.new_span(attrs, id, ctx)
+	}
+
+	fn on_record(&self, span: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+		self.layer.on_record(span, values, ctx)
+	}
+
+	fn on_follows_from(&self, span: &Id, follows: &Id, ctx: Context<'_, S>) {
+		self.layer.on_follows_from(span, follows, ctx)
+	}
+
+	fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+		// the events of the `log` records all have the same metadata, their target and level
+		// are in their fields.
+		let normalized_metadata = event.normalized_metadata();
+		let metadata = normalized_metadata.as_ref().unwrap_or_else(|| event.metadata());
+		if self.filter.enabled(metadata, ctx.clone()) {
+			self.layer.on_event(event, ctx)
+		}
+	}
+
+	fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+		self.layer.on_enter(id, ctx)
+	}
+
+	fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+		self.layer.on_exit(id, ctx)
+	}
+
+	fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+		self.layer.on_close(id, ctx)
+	}
+
+	fn on_id_change(&self, old: &Id, new: &Id, ctx: Context<'_, S>) {
+		self.layer.on_id_change(old, new, ctx)
+	}
+}
@@ -18,8 +18,12 @@
 
 #[cfg(target_os = "unknown")]
 mod console_log;
+mod file_log;
+mod filtered_layer;
 mod prefix_layer;
 
 #[cfg(target_os = "unknown")]
 pub use console_log::*;
+pub use file_log::*;
+pub use filtered_layer::*;
 pub use prefix_layer::*;
@@ -33,14 +33,11 @@ use sc_telemetry::{ExtTransport, TelemetryWorker};
 use std::io;
 use tracing::Subscriber;
 use tracing_subscriber::{
-	fmt::time::ChronoLocal,
-	fmt::{
-		format, FormatEvent, FormatFields, Formatter, Layer as FmtLayer, MakeWriter,
-		SubscriberBuilder,
-	},
-	layer::{self, SubscriberExt}, filter::LevelFilter,
+	filter::LevelFilter,
+	fmt::{time::ChronoLocal, Layer as FmtLayer},
+	layer::SubscriberExt,
 	registry::LookupSpan,
-	EnvFilter, FmtSubscriber, Layer, Registry,
+	EnvFilter, Layer, Registry,
 };
 
 pub use event_format::*;
@@ -61,84 +58,93 @@ pub enum Error {
 	SetLoggerError(#[from] tracing_log::log_tracer::SetLoggerError),
 }
 
-macro_rules! enable_log_reloading {
-	($builder:expr) => {{
-		let builder = $builder.with_filter_reloading();
-		let handle = builder.reload_handle();
-		set_reload_handle(handle);
-		builder
-	}};
-}
+/// The directives the log filters start with, e.g. to disable the info logs of some modules.
+const BUILTIN_DIRECTIVES: [&str; 6] = [
+	"info",
+	"ws=off",
+	"yamux=off",
+	"cranelift_codegen=off",
+	"cranelift_wasm=warn",
+	"hyper=warn",
+];
 
 /// Common implementation to get the subscriber.
-fn prepare_subscriber<N, E, F, W>(
+fn prepare_subscriber(
 	directives: &str,
 	profiling_targets: Option<&str>,
 	force_colors: Option<bool>,
 	telemetry_buffer_size: Option<usize>,
 	telemetry_external_transport: Option<ExtTransport>,
-	builder_hook: impl Fn(
-		SubscriberBuilder<
-			format::DefaultFields,
-			EventFormat<ChronoLocal>,
-			EnvFilter,
-			fn() -> std::io::Stderr,
-		>,
-	) -> SubscriberBuilder<N, E, F, W>,
-) -> Result<(impl Subscriber + for<'a> LookupSpan<'a>, TelemetryWorker)>
-where
-	N: for<'writer> FormatFields<'writer> + 'static,
-	E: FormatEvent<Registry, N> + 'static,
-	W: MakeWriter + 'static,
-	F: layer::Layer<Formatter<N, E, W>> + Send + Sync + 'static,
-	FmtLayer<Registry, N, E, W>: layer::Layer<Registry> + Send + Sync + 'static,
-{
-	// Accept all valid directives and print invalid ones
-	fn parse_user_directives(mut env_filter: EnvFilter, dirs: &str) -> Result<EnvFilter> {
+	file_log: Option<FileLogConfig>,
+	log_reloading: bool,
+) -> Result<(impl Subscriber + for<'a> LookupSpan<'a>, TelemetryWorker)> {
+	// Accept all valid directives and print invalid ones. Only the directives of the console
+	// are kept to be restored when its filter is reset by RPC.
+	fn parse_user_directives(
+		mut env_filter: EnvFilter,
+		dirs: &str,
+		reloadable: bool,
+	) -> Result<EnvFilter> {
 		for dir in dirs.split(',') {
-			env_filter = env_filter.add_directive(parse_default_directive(&dir)?);
+			let directive = if reloadable { parse_default_directive(&dir)? } else { dir.parse()? };
+			env_filter = env_filter.add_directive(directive);
 		}
 		Ok(env_filter)
 	}
 
 	// Initialize filter - ensure to use `parse_default_directive` for any defaults to persist
 	// after log filter reloading by RPC
-	let mut env_filter = EnvFilter::default()
-		// Enable info
-		.add_directive(parse_default_directive("info").expect("provided directive is valid"))
-		// Disable info logging by default for some modules.
-		.add_directive(parse_default_directive("ws=off").expect("provided directive is valid"))
-		.add_directive(parse_default_directive("yamux=off").expect("provided directive is valid"))
-		.add_directive(
-			parse_default_directive("cranelift_codegen=off").expect("provided directive is valid"),
-		)
-		// Set warn logging by default for some modules.
-		.add_directive(
-			parse_default_directive("cranelift_wasm=warn").expect("provided directive is valid"),
-		)
-		.add_directive(parse_default_directive("hyper=warn").expect("provided directive is valid"));
-
-	if let Ok(lvl) = std::env::var("RUST_LOG") {
-		if lvl != "" {
-			env_filter = parse_user_directives(env_filter, &lvl)?;
-		}
+	let mut env_filter = EnvFilter::default();
+	for directive in &BUILTIN_DIRECTIVES {
+		let directive = parse_default_directive(directive).expect("provided directive is valid");
+		env_filter = env_filter.add_directive(directive);
+	}
+
+	let rust_log = std::env::var("RUST_LOG").ok().filter(|lvl| lvl != "");
+	if let Some(lvl) = &rust_log {
+		env_filter = parse_user_directives(env_filter, lvl, true)?;
 	}
 
 	if directives != "" {
-		env_filter = parse_user_directives(env_filter, directives)?;
+		env_filter = parse_user_directives(env_filter, directives, true)?;
 	}
 
 	if let Some(profiling_targets) = profiling_targets {
-		env_filter = parse_user_directives(env_filter, profiling_targets)?;
+		env_filter = parse_user_directives(env_filter, profiling_targets, true)?;
 		env_filter = env_filter
 			.add_directive(
 				parse_default_directive("sc_tracing=trace").expect("provided directive is valid")
 			);
 	}
 
-	let max_level_hint = Layer::<FmtSubscriber>::max_level_hint(&env_filter);
+	// the log file has its own filter, if any, independent of the console: it starts with the
+	// builtin directives and `RUST_LOG` too, but isn't changed by the RPCs reloading the filter
+	// of the console.
+	let file_filter = match file_log.as_ref().and_then(|config| config.directives.as_deref()) {
+		Some(directives) => {
+			let mut file_filter = EnvFilter::default();
+			for directive in &BUILTIN_DIRECTIVES {
+				let directive = directive.parse().expect("provided directive is valid");
+				file_filter = file_filter.add_directive(directive);
+			}
+			if let Some(lvl) = &rust_log {
+				file_filter = parse_user_directives(file_filter, lvl, false)?;
+			}
+			Some(SharedFilter::new(parse_user_directives(file_filter, directives, false)?))
+		},
+		None => None,
+	};
 
-	let max_level = match max_level_hint {
+	let console_filter = SharedFilter::new(env_filter);
+	if log_reloading {
+		set_reload_handle(console_filter.clone());
+	}
+	let console_level_hint = console_filter.max_level_hint();
+	let filter_layer = AnyFilterLayer::new(
+		std::iter::once(console_filter.clone()).chain(file_filter.clone()).collect(),
+	);
+
+	let max_level = match Layer::<Registry>::max_level_hint(&filter_layer) {
 		Some(LevelFilter::INFO) | None => log::LevelFilter::Info,
 		Some(LevelFilter::TRACE) => log::LevelFilter::Trace,
 		Some(LevelFilter::WARN) => log::LevelFilter::Warn,
@@ -152,7 +158,7 @@ where
 		.init()?;
 
 	// If we're only logging `INFO` entries then we'll use a simplified logging format.
-	let simple = match max_level_hint {
+	let simple = match console_level_hint {
 		Some(level) if level <= tracing_subscriber::filter::LevelFilter::INFO => true,
 		_ => false,
 	};
@@ -173,24 +179,24 @@ where
 		display_thread_name: !simple,
 		enable_color,
 	};
-	let builder = FmtSubscriber::builder().with_env_filter(env_filter);
-
-	#[cfg(not(target_os = "unknown"))]
-	let builder = builder.with_writer(std::io::stderr as _);
-
-	#[cfg(target_os = "unknown")]
-	let builder = builder.with_writer(std::io::sink);
 
 	#[cfg(not(target_os = "unknown"))]
-	let builder = builder.event_format(event_format);
-
-	#[cfg(not(target_os = "unknown"))]
-	let builder = builder_hook(builder);
-
-	let subscriber = builder.finish().with(PrefixLayer).with(telemetry_layer);
+	let console_layer = FmtLayer::default()
+		.event_format(event_format)
+		.with_writer(std::io::stderr as fn() -> io::Stderr);
 
 	#[cfg(target_os = "unknown")]
-	let subscriber = subscriber.with(ConsoleLogLayer::new(event_format));
+	let console_layer = ConsoleLogLayer::new(event_format);
+
+	let file_layer = file_log
+		.map(|config| file_log_layer(config, file_filter.unwrap_or_else(|| console_filter.clone())))
+		.transpose()?;
+	let subscriber = Registry::default()
+		.with(filter_layer)
+		.with(FilteredLayer::new(console_layer, console_filter))
+		.with(PrefixLayer)
+		.with(telemetry_layer)
+		.with(file_layer);
 
 	Ok((subscriber, telemetry_worker))
 }
@@ -203,6 +209,7 @@ pub struct LoggerBuilder {
 	telemetry_external_transport: Option<ExtTransport>,
 	log_reloading: bool,
 	force_colors: Option<bool>,
+	file_log: Option<FileLogConfig>,
 }
 
 impl LoggerBuilder {
@@ -215,6 +222,7 @@ impl LoggerBuilder {
 			telemetry_external_transport: None,
 			log_reloading: true,
 			force_colors: None,
+			file_log: None,
 		}
 	}
 
@@ -252,69 +260,35 @@ impl LoggerBuilder {
 		self
 	}
 
+	/// Also write the logs to a file.
+	pub fn with_log_file(&mut self, config: FileLogConfig) -> &mut Self {
+		self.file_log = Some(config);
+		self
+	}
+
 	/// Initialize the global logger
 	///
 	/// This sets various global logging and tracing instances and thus may only be called once.
 	pub fn init(self) -> Result<TelemetryWorker> {
+		let (subscriber, telemetry_worker) = prepare_subscriber(
+			&self.directives,
+			self.profiling.as_ref().map(|(_, profiling_targets)| profiling_targets.as_str()),
+			self.force_colors,
+			self.telemetry_buffer_size,
+			self.telemetry_external_transport,
+			self.file_log,
+			self.log_reloading,
+		)?;
+
 		if let Some((tracing_receiver, profiling_targets)) = self.profiling {
-			if self.log_reloading {
-				let (subscriber, telemetry_worker) = prepare_subscriber(
-					&self.directives,
-					Some(&profiling_targets),
-					self.force_colors,
-					self.telemetry_buffer_size,
-					self.telemetry_external_transport,
-					|builder| enable_log_reloading!(builder),
-				)?;
-				let profiling = crate::ProfilingLayer::new(tracing_receiver, &profiling_targets);
-
-				tracing::subscriber::set_global_default(subscriber.with(profiling))?;
-
-				Ok(telemetry_worker)
-			} else {
-				let (subscriber, telemetry_worker) = prepare_subscriber(
-					&self.directives,
-					Some(&profiling_targets),
-					self.force_colors,
-					self.telemetry_buffer_size,
-					self.telemetry_external_transport,
-					|builder| builder,
-				)?;
-				let profiling = crate::ProfilingLayer::new(tracing_receiver, &profiling_targets);
-
-				tracing::subscriber::set_global_default(subscriber.with(profiling))?;
-
-				Ok(telemetry_worker)
-			}
+			let profiling = crate::ProfilingLayer::new(tracing_receiver, &profiling_targets);
+
+			tracing::subscriber::set_global_default(subscriber.with(profiling))?;
 		} else {
-			if self.log_reloading {
-				let (subscriber, telemetry_worker) = prepare_subscriber(
-					&self.directives,
-					None,
-					self.force_colors,
-					self.telemetry_buffer_size,
-					self.telemetry_external_transport,
-					|builder| enable_log_reloading!(builder),
-				)?;
-
-				tracing::subscriber::set_global_default(subscriber)?;
-
-				Ok(telemetry_worker)
-			} else {
-				let (subscriber, telemetry_worker) = prepare_subscriber(
-					&self.directives,
-					None,
-					self.force_colors,
-					self.telemetry_buffer_size,
-					self.telemetry_external_transport,
-					|builder| builder,
-				)?;
-
-				tracing::subscriber::set_global_default(subscriber)?;
-
-				Ok(telemetry_worker)
-			}
+			tracing::subscriber::set_global_default(subscriber)?;
 		}
+
+		Ok(telemetry_worker)
 	}
 }
 
@@ -532,4 +506,65 @@ mod tests {
 			);
 		}
 	}
+
+	fn run_log_file_entrypoint(path: &std::path::Path, rust_log: Option<&str>) -> String {
+		let executable = env::current_exe().unwrap();
+		let mut command = Command::new(executable);
+		command
+			.env("ENABLE_LOGGING", "1")
+			.env("LOG_FILE", path)
+			.env_remove("RUST_LOG")
+			.args(&["--nocapture", "log_file_has_its_own_filter_entrypoint"]);
+		if let Some(rust_log) = rust_log {
+			command.env("RUST_LOG", rust_log);
+		}
+
+		String::from_utf8(command.output().unwrap().stderr).unwrap()
+	}
+
+	#[test]
+	fn log_file_has_its_own_filter() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("node.log");
+		let stderr = run_log_file_entrypoint(&path, None);
+
+		assert!(stderr.contains("a warning"), "Got:\n{}", stderr);
+		assert!(!stderr.contains("a debug log"), "Got:\n{}", stderr);
+
+		let file = std::fs::read_to_string(&path).unwrap();
+		assert!(file.contains("a warning"), "Got:\n{}", file);
+		assert!(file.contains("a debug log"), "Got:\n{}", file);
+		assert!(!file.contains("a trace log"), "Got:\n{}", file);
+	}
+
+	#[test]
+	fn log_file_filter_starts_with_rust_log() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("node.log");
+		run_log_file_entrypoint(&path, Some("other=trace"));
+
+		let file = std::fs::read_to_string(&path).unwrap();
+		assert!(file.contains("a debug log"), "Got:\n{}", file);
+		assert!(file.contains("a trace log"), "Got:\n{}", file);
+	}
+
+	/// This is not an actual test, it is used by the `log_file_has_its_own_filter` test.
+	/// The given test will call the test executable and only execute this one test that
+	/// only logs a warning, a debug log and a trace log, with a log file more verbose than the
+	/// console.
+	#[test]
+	fn log_file_has_its_own_filter_entrypoint() {
+		if env::var("ENABLE_LOGGING").is_ok() {
+			let path = env::var("LOG_FILE").unwrap();
+			let mut builder = LoggerBuilder::new("warn");
+			builder.with_log_file(FileLogConfig {
+				directives: Some("debug".into()),
+				..FileLogConfig::new(path)
+			});
+			let _guard = builder.init().unwrap();
+			log::warn!(target: "test", "a warning");
+			log::debug!(target: "test", "a debug log");
+			log::trace!(target: "other", "a trace log");
+		}
+	}
 }
@@ -54,6 +54,7 @@ impl ChainApi for TestApi {
 	type Error = sp_transaction_pool::error::Error;
 	type ValidationFuture = Ready<sp_transaction_pool::error::Result<TransactionValidity>>;
 	type BodyFuture = Ready<sp_transaction_pool::error::Result<Option<Vec<Extrinsic>>>>;
+	type SimulationFuture = Ready<sp_transaction_pool::error::Result<SimulationOutcome>>;
 
 	fn validate_transaction(
 		&self,
@@ -86,6 +87,14 @@ impl ChainApi for TestApi {
 		)
 	}
 
+	fn simulate_transaction(
+		&self,
+		_at: &BlockId<Self::Block>,
+		_uxt: ExtrinsicFor<Self>,
+	) -> Self::SimulationFuture {
+		ready(Ok((Ok(Ok(())), Vec::new())))
+	}
+
 	fn block_id_to_number(
 		&self,
 		at: &BlockId<Self::Block>,
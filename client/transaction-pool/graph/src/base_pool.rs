@@ -21,16 +21,17 @@
 //! For a more full-featured pool, have a look at the `pool` module.
 
 use std::{
-	collections::HashSet,
+	collections::{HashMap, HashSet},
 	fmt,
 	hash,
 	sync::Arc,
+	time::Duration,
 };
 
 use log::{trace, debug, warn};
 use serde::Serialize;
 use sp_core::hexdisplay::HexDisplay;
-use sp_runtime::traits::Member;
+use sp_runtime::{traits::Member, Percent};
 use sp_runtime::transaction_validity::{
 	TransactionTag as Tag,
 	TransactionLongevity as Longevity,
@@ -38,9 +39,11 @@ use sp_runtime::transaction_validity::{
 	TransactionSource as Source,
 };
 use sp_transaction_pool::{error, PoolStatus, InPoolTransaction};
+use wasm_timer::Instant;
 
 use crate::future::{FutureTransactions, WaitingTransaction};
 use crate::ready::ReadyTransactions;
+use crate::rotator::deadline;
 
 /// Successful import result.
 #[derive(Debug, PartialEq, Eq)]
@@ -146,6 +149,10 @@ impl<Hash, Extrinsic> InPoolTransaction for Transaction<Hash, Extrinsic> {
 	fn is_propagable(&self) -> bool {
 		self.propagate
 	}
+
+	fn is_local(&self) -> bool {
+		self.source == Source::Local
+	}
 }
 
 impl<Hash: Clone, Extrinsic: Clone> Transaction<Hash, Extrinsic> {
@@ -206,6 +213,10 @@ impl<Hash, Extrinsic> fmt::Debug for Transaction<Hash, Extrinsic> where
 /// Store last pruned tags for given number of invocations.
 const RECENTLY_PRUNED_TAGS: usize = 2;
 
+/// The number of transactions the index of the senders may contain regardless of the pool size
+/// before it is compacted.
+const MIN_INDEXED_SENDERS: usize = 64;
+
 /// Transaction pool.
 ///
 /// Builds a dependency graph for all transactions in the pool and returns
@@ -228,6 +239,13 @@ pub struct BasePool<Hash: hash::Hash + Eq, Ex> {
 	/// transactions to future in case they were just stuck in verification.
 	recently_pruned: [HashSet<Tag>; RECENTLY_PRUNED_TAGS],
 	recently_pruned_index: usize,
+	/// The transactions of every sender, see [`BasePool::import_from`].
+	///
+	/// The transactions leaving the pool are forgotten lazily, when the transactions of their
+	/// sender are looked up or when the index is compacted.
+	senders: HashMap<Vec<u8>, HashSet<Hash>>,
+	/// The number of transactions in `senders`.
+	indexed: usize,
 }
 
 impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> Default for BasePool<Hash, Ex> {
@@ -245,9 +263,18 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 			ready: Default::default(),
 			recently_pruned: Default::default(),
 			recently_pruned_index: 0,
+			senders: Default::default(),
+			indexed: 0,
 		}
 	}
 
+	/// Set the minimum priority increase, relative to the collective priority of the
+	/// ready transactions being replaced, that a transaction providing the same tags
+	/// needs to replace them.
+	pub fn set_min_replacement_bump(&mut self, bump: Percent) {
+		self.ready.set_min_replacement_bump(bump);
+	}
+
 	/// Temporary enables future transactions, runs closure and then restores
 	/// `reject_future_transactions` flag back to previous value.
 	///
@@ -308,6 +335,27 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		self.import_to_ready(tx)
 	}
 
+	/// Imports the transaction of the given sender to the pool, see [`BasePool::import`].
+	///
+	/// The transactions of the sender can then be limited with
+	/// [`BasePool::enforce_sender_limit`].
+	pub fn import_from(
+		&mut self,
+		tx: Transaction<Hash, Ex>,
+		sender: Option<Vec<u8>>,
+	) -> error::Result<Imported<Hash, Ex>> {
+		let hash = tx.hash.clone();
+		let imported = self.import(tx)?;
+
+		if let Some(sender) = sender {
+			if self.senders.entry(sender).or_default().insert(hash) {
+				self.indexed += 1;
+			}
+			self.compact_senders();
+		}
+		Ok(imported)
+	}
+
 	/// Imports transaction to ready queue.
 	///
 	/// NOTE the transaction has to have all requirements satisfied.
@@ -379,7 +427,7 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 	}
 
 	/// Returns an iterator over future transactions in the pool.
-	pub fn futures(&self) -> impl Iterator<Item=&Transaction<Hash, Ex>> {
+	pub fn futures(&self) -> impl Iterator<Item=&Arc<Transaction<Hash, Ex>>> {
 		self.future.all()
 	}
 
@@ -408,19 +456,41 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 	/// Removes and returns worst transactions from the queues and all transactions that depend on them.
 	/// Technically the worst transaction should be evaluated by computing the entire pending set.
 	/// We use a simplified approach to remove the transaction that occupies the pool for the longest time.
-	pub fn enforce_limits(&mut self, ready: &Limit, future: &Limit) -> Vec<Arc<Transaction<Hash, Ex>>> {
+	///
+	/// Transactions submitted locally are never removed, neither are the transactions of the
+	/// given lanes as long as the lane stays within its reservation.
+	pub fn enforce_limits(
+		&mut self,
+		ready: &Limit,
+		future: &Limit,
+		lanes: &[Lane],
+	) -> Vec<Arc<Transaction<Hash, Ex>>> {
 		let mut removed = vec![];
 
 		while ready.is_exceeded(self.ready.len(), self.ready.bytes()) {
+			let by_density = ready.total_bytes < self.ready.bytes();
+			let mut usage = vec![(0, 0); lanes.len()];
+			self.ready.fold::<(), _>(|_, current| {
+				note_lane_usage(&mut usage, lanes, &*current.transaction.transaction);
+				None
+			});
+
 			// find the worst transaction
 			let minimal = self.ready
 				.fold(|minimal, current| {
 					let transaction = &current.transaction;
+					if transaction.transaction.is_local()
+						|| is_reserved(&usage, lanes, &*transaction.transaction)
+					{
+						return minimal;
+					}
 					match minimal {
 						None => Some(transaction.clone()),
-						Some(ref tx) if tx.insertion_id > transaction.insertion_id => {
-							Some(transaction.clone())
-						},
+						Some(ref tx) if evict_before(
+							by_density,
+							(&*transaction.transaction, transaction.insertion_id),
+							(&*tx.transaction, tx.insertion_id),
+						) => Some(transaction.clone()),
 						other => other,
 					}
 				});
@@ -433,14 +503,25 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		}
 
 		while future.is_exceeded(self.future.len(), self.future.bytes()) {
+			let by_density = future.total_bytes < self.future.bytes();
+			let mut usage = vec![(0, 0); lanes.len()];
+			self.future.all().for_each(|tx| note_lane_usage(&mut usage, lanes, &**tx));
+
 			// find the worst transaction
 			let minimal = self.future
 				.fold(|minimal, current| {
+					if current.transaction.is_local()
+						|| is_reserved(&usage, lanes, &*current.transaction)
+					{
+						return minimal;
+					}
 					match minimal {
 						None => Some(current.clone()),
-						Some(ref tx) if tx.imported_at > current.imported_at => {
-							Some(current.clone())
-						},
+						Some(ref tx) if evict_before(
+							by_density,
+							(&*current.transaction, current.imported_at),
+							(&*tx.transaction, tx.imported_at),
+						) => Some(current.clone()),
 						other => other,
 					}
 				});
@@ -455,6 +536,103 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		removed
 	}
 
+	/// Makes sure that the given sender doesn't occupy more than the provided limit of
+	/// ready and future transactions.
+	///
+	/// Removes and returns the lowest priority transactions of the sender and all transactions
+	/// that depend on them, until it is within the limit. Only the transactions imported with
+	/// [`BasePool::import_from`] are attributed to their sender, and the transactions submitted
+	/// locally are not subject to the limit.
+	pub fn enforce_sender_limit(
+		&mut self,
+		sender: &[u8],
+		limit: &Limit,
+	) -> Vec<Arc<Transaction<Hash, Ex>>> {
+		let mut transactions = self.sender_transactions(sender)
+			.into_iter()
+			.filter(|tx| !tx.is_local())
+			.map(|tx| (tx.priority, tx.bytes, tx.hash.clone()))
+			.collect::<Vec<_>>();
+		// the lowest priority transaction is removed first
+		transactions.sort_by(|a, b| b.0.cmp(&a.0));
+
+		let mut count = transactions.len();
+		let mut bytes = transactions.iter().map(|(_, bytes, _)| bytes).sum::<usize>();
+		let mut removed = vec![];
+		let mut removed_hashes = HashSet::new();
+
+		while limit.is_exceeded(count, bytes) {
+			let (_, tx_bytes, hash) = match transactions.pop() {
+				Some(tx) => tx,
+				None => break,
+			};
+
+			for tx in self.remove_subtree(&[hash]) {
+				removed_hashes.insert(tx.hash.clone());
+				removed.push(tx);
+			}
+			count -= 1;
+			bytes = bytes.saturating_sub(tx_bytes);
+
+			// account for the transactions of the sender depending on the removed one.
+			transactions.retain(|(_, tx_bytes, hash)| {
+				let keep = !removed_hashes.contains(hash);
+				if !keep {
+					count -= 1;
+					bytes = bytes.saturating_sub(*tx_bytes);
+				}
+				keep
+			});
+		}
+
+		if let Some(hashes) = self.senders.get_mut(sender) {
+			let indexed = hashes.len();
+			hashes.retain(|hash| !removed_hashes.contains(hash));
+			self.indexed -= indexed - hashes.len();
+		}
+		removed
+	}
+
+	/// Returns the transactions of the given sender that are in the pool, forgetting the
+	/// ones that left it.
+	fn sender_transactions(&mut self, sender: &[u8]) -> Vec<Arc<Transaction<Hash, Ex>>> {
+		let hashes = match self.senders.get(sender) {
+			Some(hashes) => hashes.iter().cloned().collect::<Vec<_>>(),
+			None => return Vec::new(),
+		};
+		let transactions = self.by_hashes(&hashes).into_iter().flatten().collect::<Vec<_>>();
+
+		if transactions.len() < hashes.len() {
+			self.indexed -= hashes.len() - transactions.len();
+			if transactions.is_empty() {
+				self.senders.remove(sender);
+			} else {
+				self.senders.insert(
+					sender.to_vec(),
+					transactions.iter().map(|tx| tx.hash.clone()).collect(),
+				);
+			}
+		}
+		transactions
+	}
+
+	/// Forget the transactions that left the pool once the index of the senders grew twice as
+	/// large as the pool, so that its size stays proportional to the pool.
+	fn compact_senders(&mut self) {
+		if self.indexed <= 2 * (self.ready.len() + self.future.len()) + MIN_INDEXED_SENDERS {
+			return;
+		}
+
+		let (ready, future) = (&self.ready, &self.future);
+		let mut indexed = 0;
+		self.senders.retain(|_, hashes| {
+			hashes.retain(|hash| ready.contains(hash) || future.contains(hash));
+			indexed += hashes.len();
+			!hashes.is_empty()
+		});
+		self.indexed = indexed;
+	}
+
 	/// Removes all transactions represented by the hashes and all other transactions
 	/// that depend on them.
 	///
@@ -469,6 +647,28 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		removed
 	}
 
+	/// Removes and returns the future transactions that have been waiting for their
+	/// requirements for at least `max_age`.
+	///
+	/// Transactions whose expiry can't be represented never expire.
+	pub fn remove_expired_futures(
+		&mut self,
+		now: Instant,
+		max_age: Duration,
+	) -> Vec<Arc<Transaction<Hash, Ex>>> {
+		let expired = self.future
+			.fold(|expired, current| {
+				let mut expired = expired.unwrap_or_else(Vec::new);
+				if deadline(&current.imported_at, max_age).map_or(false, |expiry| expiry <= now) {
+					expired.push(current.transaction.hash.clone());
+				}
+				Some(expired)
+			})
+			.unwrap_or_default();
+
+		self.future.remove(&expired)
+	}
+
 	/// Removes and returns all transactions from the future queue.
 	pub fn clear_future(&mut self) -> Vec<Arc<Transaction<Hash, Ex>>> {
 		self.future.clear()
@@ -525,6 +725,90 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 			future_bytes: self.future.bytes(),
 		}
 	}
+
+	/// Returns the status of each of the given lanes.
+	pub fn lane_status(&self, lanes: &[Lane]) -> Vec<PoolStatus> {
+		let mut ready = vec![(0, 0); lanes.len()];
+		self.ready.get().for_each(|tx| note_lane_usage(&mut ready, lanes, &*tx));
+		let mut future = vec![(0, 0); lanes.len()];
+		self.future.all().for_each(|tx| note_lane_usage(&mut future, lanes, &**tx));
+
+		ready.into_iter().zip(future)
+			.map(|((ready, ready_bytes), (future, future_bytes))| PoolStatus {
+				ready,
+				ready_bytes,
+				future,
+				future_bytes,
+			})
+			.collect()
+	}
+}
+
+/// A class of transactions with a reserved share of the pool capacity.
+///
+/// A transaction belongs to the first lane for which it provides a tag starting with
+/// the lane's prefix. As long as the transactions of a lane stay within its reservation,
+/// they are not evicted when the pool limits are exceeded, so the reservations should
+/// not add up to more than the pool limits.
+#[derive(Debug, Clone)]
+pub struct Lane {
+	/// Prefix of the tags provided by the transactions of the lane.
+	pub tag_prefix: Vec<u8>,
+	/// Capacity of the ready and of the future queue reserved for the lane.
+	pub reserved: Limit,
+}
+
+impl Lane {
+	/// Returns true if the transaction belongs to the lane.
+	pub fn contains<Hash, Ex>(&self, tx: &Transaction<Hash, Ex>) -> bool {
+		tx.provides.iter().any(|tag| tag.starts_with(&self.tag_prefix))
+	}
+}
+
+/// Adds the transaction to the number of transactions and bytes of its lane, if any.
+fn note_lane_usage<Hash, Ex>(
+	usage: &mut [(usize, usize)],
+	lanes: &[Lane],
+	tx: &Transaction<Hash, Ex>,
+) {
+	if let Some(index) = lanes.iter().position(|lane| lane.contains(tx)) {
+		usage[index].0 += 1;
+		usage[index].1 += tx.bytes;
+	}
+}
+
+/// Returns true if the transaction belongs to a lane that is within its reservation.
+fn is_reserved<Hash, Ex>(
+	usage: &[(usize, usize)],
+	lanes: &[Lane],
+	tx: &Transaction<Hash, Ex>,
+) -> bool {
+	lanes.iter()
+		.position(|lane| lane.contains(tx))
+		.map_or(false, |index| {
+			let (count, bytes) = usage[index];
+			!lanes[index].reserved.is_exceeded(count, bytes)
+		})
+}
+
+/// Returns true if transaction `a` should be evicted before transaction `b`.
+///
+/// When the size limit of the queue is exceeded the transaction with the lowest priority
+/// per byte is evicted first, otherwise (or if both are equally dense) the oldest one.
+fn evict_before<Hash, Ex, Age: Ord>(
+	by_density: bool,
+	(a, a_age): (&Transaction<Hash, Ex>, Age),
+	(b, b_age): (&Transaction<Hash, Ex>, Age),
+) -> bool {
+	if by_density {
+		// compare `a.priority / a.bytes` and `b.priority / b.bytes` without dividing.
+		let a_density = a.priority as u128 * b.bytes as u128;
+		let b_density = b.priority as u128 * a.bytes as u128;
+		if a_density != b_density {
+			return a_density < b_density;
+		}
+	}
+	a_age < b_age
 }
 
 /// Queue limits
@@ -1201,4 +1485,196 @@ source: TransactionSource::External, requires: [03,02], provides: [04], data: [4
 		assert_eq!(pool.reject_future_transactions, true);
 		assert_eq!(pool.future.len(), 1);
 	}
+
+	fn nonce_tx(hash: Hash, sender: u8, nonce: u8, priority: u64) -> Transaction<Hash, Vec<u8>> {
+		Transaction {
+			data: vec![sender, nonce],
+			bytes: 1,
+			hash,
+			priority,
+			valid_till: 64u64,
+			requires: if nonce == 0 { vec![] } else { vec![vec![sender, sender, nonce - 1]] },
+			provides: vec![vec![sender, sender, nonce]],
+			propagate: true,
+			source: Source::External,
+		}
+	}
+
+	#[test]
+	fn should_enforce_sender_limits() {
+		// given
+		let mut pool = pool();
+		let limit = Limit { count: 2, total_bytes: 1024 };
+		pool.import_from(nonce_tx(1, 1, 0, 5), Some(vec![1])).unwrap();
+		pool.import_from(nonce_tx(2, 1, 1, 5), Some(vec![1])).unwrap();
+		pool.import_from(nonce_tx(3, 1, 2, 3), Some(vec![1])).unwrap();
+		pool.import_from(nonce_tx(4, 1, 3, 4), Some(vec![1])).unwrap();
+		pool.import_from(nonce_tx(5, 2, 5, 1), Some(vec![2])).unwrap();
+		pool.import(nonce_tx(6, 3, 0, 1)).unwrap();
+		assert_eq!(pool.ready().count(), 5);
+		assert_eq!(pool.future.len(), 1);
+		assert!(pool.enforce_sender_limit(&[2], &limit).is_empty());
+
+		// when
+		let removed = pool.enforce_sender_limit(&[1], &limit);
+
+		// then
+		let mut removed = removed.into_iter().map(|tx| tx.hash).collect::<Vec<_>>();
+		removed.sort();
+		assert_eq!(removed, vec![3, 4]);
+		let mut ready = pool.ready().map(|tx| tx.hash).collect::<Vec<_>>();
+		ready.sort();
+		assert_eq!(ready, vec![1, 2, 6]);
+		assert_eq!(pool.future.len(), 1);
+		// the removed transactions are forgotten.
+		assert_eq!(pool.senders[&vec![1]].len(), 2);
+		assert_eq!(pool.indexed, 3);
+	}
+
+	#[test]
+	fn should_not_evict_transactions_of_reserved_lanes() {
+		// given
+		let mut pool = pool();
+		pool.import(nonce_tx(1, 9, 0, 1)).unwrap();
+		pool.import(nonce_tx(2, 2, 0, 1)).unwrap();
+		pool.import(nonce_tx(3, 3, 0, 1)).unwrap();
+		pool.import(nonce_tx(4, 4, 0, 1)).unwrap();
+		let lanes = vec![Lane {
+			tag_prefix: vec![9],
+			reserved: Limit { count: 1, total_bytes: 1024 },
+		}];
+		let limit = Limit { count: 2, total_bytes: 1024 };
+
+		// when
+		let removed = pool.enforce_limits(&limit, &limit, &lanes);
+
+		// then
+		let mut removed = removed.into_iter().map(|tx| tx.hash).collect::<Vec<_>>();
+		removed.sort();
+		assert_eq!(removed, vec![2, 3]);
+		let mut ready = pool.ready().map(|tx| tx.hash).collect::<Vec<_>>();
+		ready.sort();
+		assert_eq!(ready, vec![1, 4]);
+	}
+
+	#[test]
+	fn should_report_the_status_of_lanes() {
+		// given
+		let mut pool = pool();
+		pool.import(nonce_tx(1, 9, 0, 1)).unwrap();
+		pool.import(nonce_tx(2, 9, 2, 1)).unwrap();
+		pool.import(nonce_tx(3, 3, 0, 1)).unwrap();
+		let lanes = vec![
+			Lane { tag_prefix: vec![9], reserved: Limit { count: 1, total_bytes: 1024 } },
+			Lane { tag_prefix: vec![4], reserved: Limit { count: 1, total_bytes: 1024 } },
+		];
+
+		// when
+		let status = pool.lane_status(&lanes);
+
+		// then
+		assert_eq!(status.len(), 2);
+		assert_eq!(
+			(status[0].ready, status[0].ready_bytes, status[0].future, status[0].future_bytes),
+			(1, 1, 1, 1),
+		);
+		assert_eq!((status[1].ready, status[1].future), (0, 0));
+	}
+
+	#[test]
+	fn should_not_evict_local_transactions() {
+		// given
+		let mut pool = pool();
+		let mut local = nonce_tx(1, 1, 0, 1);
+		local.source = Source::Local;
+		pool.import(local).unwrap();
+		pool.import(nonce_tx(2, 2, 0, 1)).unwrap();
+		pool.import(nonce_tx(3, 3, 0, 1)).unwrap();
+		let limit = Limit { count: 1, total_bytes: 1024 };
+
+		// when
+		let removed = pool.enforce_limits(&limit, &limit, &[]);
+
+		// then
+		let mut removed = removed.into_iter().map(|tx| tx.hash).collect::<Vec<_>>();
+		removed.sort();
+		assert_eq!(removed, vec![2, 3]);
+		assert_eq!(pool.ready().map(|tx| tx.hash).collect::<Vec<_>>(), vec![1]);
+	}
+
+	#[test]
+	fn should_evict_lowest_priority_per_byte_when_size_limit_is_exceeded() {
+		// given
+		let mut pool = pool();
+		let tx = |hash, sender, priority, bytes| Transaction {
+			bytes,
+			..nonce_tx(hash, sender, 0, priority)
+		};
+		// 10 priority per byte
+		pool.import(tx(1, 1, 1000, 100)).unwrap();
+		// 1 priority per byte
+		pool.import(tx(2, 2, 500, 500)).unwrap();
+		// 5 priority per byte
+		pool.import(tx(3, 3, 1000, 200)).unwrap();
+		let limit = Limit { count: 10, total_bytes: 600 };
+
+		// when
+		let removed = pool.enforce_limits(&limit, &limit, &[]);
+
+		// then
+		assert_eq!(removed.into_iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![2]);
+		let mut ready = pool.ready().map(|tx| tx.hash).collect::<Vec<_>>();
+		ready.sort();
+		assert_eq!(ready, vec![1, 3]);
+	}
+
+	#[test]
+	fn should_evict_oldest_transactions_when_count_limit_is_exceeded() {
+		// given
+		let mut pool = pool();
+		pool.import(nonce_tx(1, 1, 0, 1000)).unwrap();
+		pool.import(nonce_tx(2, 2, 0, 1)).unwrap();
+		pool.import(nonce_tx(3, 3, 0, 1000)).unwrap();
+		let limit = Limit { count: 2, total_bytes: 1024 };
+
+		// when
+		let removed = pool.enforce_limits(&limit, &limit, &[]);
+
+		// then
+		assert_eq!(removed.into_iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![1]);
+	}
+
+	#[test]
+	fn should_remove_expired_future_transactions() {
+		// given
+		let mut pool = pool();
+		pool.import(nonce_tx(1, 1, 1, 1)).unwrap();
+		pool.import(nonce_tx(2, 2, 0, 1)).unwrap();
+		let now = Instant::now();
+		pool.import(nonce_tx(3, 3, 1, 1)).unwrap();
+		assert_eq!(pool.status().future, 2);
+
+		// when
+		let removed = pool.remove_expired_futures(now, Duration::from_secs(0));
+
+		// then
+		assert_eq!(removed.into_iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![1]);
+		assert_eq!(pool.futures().map(|tx| tx.hash).collect::<Vec<_>>(), vec![3]);
+		assert_eq!(pool.status().ready, 1);
+		assert!(pool.remove_expired_futures(Instant::now(), Duration::from_secs(60)).is_empty());
+	}
+
+	#[test]
+	fn should_not_expire_future_transactions_if_the_max_age_overflows() {
+		// given
+		let mut pool = pool();
+		pool.import(nonce_tx(1, 1, 1, 1)).unwrap();
+
+		// when
+		let removed = pool.remove_expired_futures(Instant::now(), Duration::from_secs(u64::MAX));
+
+		// then
+		assert!(removed.is_empty());
+		assert_eq!(pool.status().future, 1);
+	}
 }
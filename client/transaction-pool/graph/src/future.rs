@@ -227,8 +227,8 @@ impl<Hash: hash::Hash + Eq + Clone, Ex> FutureTransactions<Hash, Ex> {
 	}
 
 	/// Returns iterator over all future transactions
-	pub fn all(&self) -> impl Iterator<Item=&Transaction<Hash, Ex>> {
-		self.waiting.values().map(|waiting| &*waiting.transaction)
+	pub fn all(&self) -> impl Iterator<Item=&Arc<Transaction<Hash, Ex>>> {
+		self.waiting.values().map(|waiting| &waiting.transaction)
 	}
 
 	/// Removes and returns all future transactions.
@@ -20,26 +20,34 @@
 use std::{
 	collections::HashMap, hash, fmt::Debug,
 };
+use futures::channel::mpsc::{channel, Receiver, Sender};
 use linked_hash_map::LinkedHashMap;
+use retain_mut::RetainMut;
 use serde::Serialize;
 use crate::{watcher, ChainApi, ExtrinsicHash, BlockHash};
 use log::{debug, trace, warn};
 use sp_runtime::traits;
+use sp_transaction_pool::{DroppedReason, PoolEvent};
 
 /// Extrinsic pool default listener.
 pub struct Listener<H: hash::Hash + Eq, C: ChainApi> {
 	watchers: HashMap<H, watcher::Sender<H, ExtrinsicHash<C>>>,
 	finality_watchers: LinkedHashMap<ExtrinsicHash<C>, Vec<H>>,
+	event_sinks: Vec<Sender<PoolEvent<H, BlockHash<C>>>>,
 }
 
 /// Maximum number of blocks awaiting finality at any time.
 const MAX_FINALITY_WATCHERS: usize = 512;
 
+/// Size of the buffer of every pool event stream.
+const EVENT_BUFFER_SIZE: usize = 1024;
+
 impl<H: hash::Hash + Eq + Debug, C: ChainApi> Default for Listener<H, C> {
 	fn default() -> Self {
 		Listener {
 			watchers: Default::default(),
 			finality_watchers: Default::default(),
+			event_sinks: Default::default(),
 		}
 	}
 }
@@ -58,6 +66,27 @@ impl<H: hash::Hash + traits::Member + Serialize, C: ChainApi> Listener<H, C> {
 		}
 	}
 
+	fn notify(&mut self, event: PoolEvent<H, BlockHash<C>>) {
+		self.event_sinks.retain_mut(|sink| match sink.try_send(event.clone()) {
+			Ok(()) => true,
+			Err(e) => {
+				if e.is_full() {
+					warn!(target: "txpool", "Trying to notify a pool event but the channel is full");
+					true
+				} else {
+					false
+				}
+			},
+		});
+	}
+
+	/// Creates a new stream of pool-wide events about transactions leaving the pool.
+	pub fn event_stream(&mut self) -> Receiver<PoolEvent<H, BlockHash<C>>> {
+		let (sink, stream) = channel(EVENT_BUFFER_SIZE);
+		self.event_sinks.push(sink);
+		stream
+	}
+
 	/// Creates a new watcher for given verified extrinsic.
 	///
 	/// The watcher can be used to subscribe to life-cycle events of that extrinsic.
@@ -78,6 +107,7 @@ impl<H: hash::Hash + traits::Member + Serialize, C: ChainApi> Listener<H, C> {
 		self.fire(tx, |watcher| watcher.ready());
 		if let Some(old) = old {
 			self.fire(old, |watcher| watcher.usurped(tx.clone()));
+			self.notify(PoolEvent::Dropped(old.clone(), DroppedReason::Usurped(tx.clone())));
 		}
 	}
 
@@ -89,11 +119,35 @@ impl<H: hash::Hash + traits::Member + Serialize, C: ChainApi> Listener<H, C> {
 
 	/// Transaction was dropped from the pool because of the limit.
 	pub fn dropped(&mut self, tx: &H, by: Option<&H>) {
-		trace!(target: "txpool", "[{:?}] Dropped (replaced with {:?})", tx, by);
-		self.fire(tx, |watcher| match by {
-			Some(t) => watcher.usurped(t.clone()),
-			None => watcher.dropped(),
-		})
+		let reason = match by {
+			Some(t) => DroppedReason::Usurped(t.clone()),
+			None => DroppedReason::LimitsExceeded,
+		};
+		self.dropped_for(tx, reason);
+	}
+
+	/// Transaction was dropped from the pool for the given reason.
+	pub fn dropped_for(&mut self, tx: &H, reason: DroppedReason<H>) {
+		trace!(target: "txpool", "[{:?}] Dropped ({:?})", tx, reason);
+		self.fire(tx, |watcher| match &reason {
+			DroppedReason::Usurped(t) => watcher.usurped(t.clone()),
+			_ => watcher.dropped(),
+		});
+		self.notify(PoolEvent::Dropped(tx.clone(), reason));
+	}
+
+	/// Transaction was dropped after waiting for its requirements for too long.
+	pub fn expired(&mut self, tx: &H) {
+		debug!(target: "txpool", "[{:?}] Expired in the future queue", tx);
+		self.fire(tx, |watcher| watcher.dropped());
+		self.notify(PoolEvent::Dropped(tx.clone(), DroppedReason::Expired));
+	}
+
+	/// Transaction was removed and banned on request.
+	pub fn banned(&mut self, tx: &H) {
+		debug!(target: "txpool", "[{:?}] Banned", tx);
+		self.fire(tx, |watcher| watcher.dropped());
+		self.notify(PoolEvent::Banned(tx.clone()));
 	}
 
 	/// Transaction was removed as invalid.
@@ -104,12 +158,14 @@ impl<H: hash::Hash + traits::Member + Serialize, C: ChainApi> Listener<H, C> {
 			debug!(target: "txpool", "[{:?}] Extrinsic invalid", tx);
 		}
 		self.fire(tx, |watcher| watcher.invalid());
+		self.notify(PoolEvent::Invalid(tx.clone()));
 	}
 
 	/// Transaction was pruned from the pool.
 	pub fn pruned(&mut self, block_hash: BlockHash<C>, tx: &H) {
 		debug!(target: "txpool", "[{:?}] Pruned at {:?}", tx, block_hash);
 		self.fire(tx, |s| s.in_block(block_hash));
+		self.notify(PoolEvent::Pruned(tx.clone(), block_hash));
 		self.finality_watchers.entry(block_hash).or_insert(vec![]).push(tx.clone());
 
 		while self.finality_watchers.len() > MAX_FINALITY_WATCHERS {
@@ -19,6 +19,7 @@
 use std::{
 	collections::HashMap,
 	sync::Arc,
+	time::Duration,
 };
 
 use crate::{base_pool as base, watcher::Watcher};
@@ -26,12 +27,15 @@ use crate::{base_pool as base, watcher::Watcher};
 use futures::Future;
 use sp_runtime::{
 	generic::BlockId,
+	ApplyExtrinsicResult,
+	Percent,
 	traits::{self, SaturatedConversion, Block as BlockT},
 	transaction_validity::{
 		TransactionValidity, TransactionTag as Tag, TransactionValidityError, TransactionSource,
+		TransactionPriority,
 	},
 };
-use sp_transaction_pool::error;
+use sp_transaction_pool::{error, AdmissionFilter, SenderExtractor};
 use wasm_timer::Instant;
 use futures::channel::mpsc::Receiver;
 
@@ -58,6 +62,10 @@ pub type ValidatedTransactionFor<A> = ValidatedTransaction<
 	<A as ChainApi>::Error,
 >;
 
+/// The outcome of applying an extrinsic in a block built on top of another one, along with
+/// the SCALE encoded events deposited meanwhile.
+pub type SimulationOutcome = (ApplyExtrinsicResult, Vec<u8>);
+
 /// Concrete extrinsic validation and query logic.
 pub trait ChainApi: Send + Sync {
 	/// Block type.
@@ -70,6 +78,8 @@ pub trait ChainApi: Send + Sync {
 	type BodyFuture: Future<
 		Output = Result<Option<Vec<<Self::Block as traits::Block>::Extrinsic>>, Self::Error>
 	> + Unpin + Send + 'static;
+	/// Simulation future.
+	type SimulationFuture: Future<Output=Result<SimulationOutcome, Self::Error>> + Send + Unpin;
 
 	/// Verify extrinsic at given block.
 	fn validate_transaction(
@@ -79,6 +89,13 @@ pub trait ChainApi: Send + Sync {
 		uxt: ExtrinsicFor<Self>,
 	) -> Self::ValidationFuture;
 
+	/// Apply extrinsic in a block initialized on top of the given one, discarding the changes.
+	fn simulate_transaction(
+		&self,
+		at: &BlockId<Self::Block>,
+		uxt: ExtrinsicFor<Self>,
+	) -> Self::SimulationFuture;
+
 	/// Returns a block number given the block id.
 	fn block_id_to_number(
 		&self,
@@ -105,8 +122,69 @@ pub struct Options {
 	pub ready: base::Limit,
 	/// Future queue limits.
 	pub future: base::Limit,
+	/// Limits of the ready and future transactions of a single sender, if any.
+	///
+	/// The senders of the transactions are given by the `sender_extractor`.
+	pub per_sender: Option<base::Limit>,
+	/// Extracts the senders of the transactions, the transactions without a known sender
+	/// aren't subject to the `per_sender` limits.
+	pub sender_extractor: Option<Arc<dyn SenderExtractor>>,
+	/// Lanes of transactions with a reserved share of the ready and future queue limits.
+	pub lanes: Vec<base::Lane>,
+	/// How long invalid, stale or dropped transactions are banned from re-entering the pool.
+	pub ban_time: Duration,
 	/// Reject future transactions.
 	pub reject_future_transactions: bool,
+	/// How long transactions may wait in the future queue before being dropped, if limited.
+	pub future_max_age: Option<Duration>,
+	/// Persist the pool contents across restarts.
+	///
+	/// This is not used by the pool itself, the persistence is set up by the pool service.
+	pub persist: bool,
+	/// Minimum priority increase, relative to the priority of the ready transactions
+	/// providing the same tags, required for a transaction to replace them.
+	pub min_replacement_bump: Percent,
+	/// Priority added to the transactions submitted locally.
+	pub local_priority_bonus: TransactionPriority,
+	/// Schedule of the background revalidation of the pool.
+	///
+	/// This is not used by the pool itself, the revalidation is driven by the pool service.
+	pub revalidation: RevalidationOptions,
+	/// Hook consulted on every submission to the pool, rejecting the transactions it doesn't
+	/// admit before they are validated.
+	pub admission_filter: Option<Arc<dyn AdmissionFilter>>,
+}
+
+/// Schedule of the revalidation of the transactions in the pool.
+#[derive(Debug, Clone)]
+pub struct RevalidationOptions {
+	/// Interval between two batches of background revalidation.
+	pub interval: Duration,
+	/// Minimal number of transactions revalidated in a single batch.
+	pub min_batch_size: usize,
+	/// Maximal number of transactions revalidated in a single batch.
+	pub max_batch_size: usize,
+	/// Time after which the whole pool is revalidated, if it isn't revalidated on every block.
+	pub max_age: Duration,
+	/// Number of blocks after which the whole pool is revalidated, if it isn't revalidated
+	/// on every block.
+	pub max_age_blocks: u32,
+	/// Shrink the batches while blocks are imported faster than the batches are processed,
+	/// to leave the CPU to the block import.
+	pub adaptive: bool,
+}
+
+impl Default for RevalidationOptions {
+	fn default() -> Self {
+		RevalidationOptions {
+			interval: Duration::from_millis(200),
+			min_batch_size: 20,
+			max_batch_size: usize::max_value(),
+			max_age: Duration::from_secs(60),
+			max_age_blocks: 20,
+			adaptive: true,
+		}
+	}
 }
 
 impl Default for Options {
@@ -120,7 +198,17 @@ impl Default for Options {
 				count: 512,
 				total_bytes: 1 * 1024 * 1024,
 			},
+			per_sender: None,
+			sender_extractor: None,
+			lanes: Vec::new(),
+			ban_time: Duration::from_secs(60 * 30),
 			reject_future_transactions: false,
+			future_max_age: None,
+			persist: false,
+			min_replacement_bump: Percent::zero(),
+			local_priority_bonus: 0,
+			revalidation: Default::default(),
+			admission_filter: None,
 		}
 	}
 }
@@ -193,6 +281,27 @@ impl<B: ChainApi> Pool<B> {
 		res.expect("One extrinsic passed; one result returned; qed")
 	}
 
+	/// Imports one unverified extrinsic received from the network peer with given id.
+	pub async fn submit_from_peer(
+		&self,
+		at: &BlockId<B::Block>,
+		peer: &str,
+		xt: ExtrinsicFor<B>,
+	) -> Result<ExtrinsicHash<B>, B::Error> {
+		let block_number = self.resolve_block_number(at)?;
+		let (_, tx) = self.verify_one(
+			at,
+			block_number,
+			TransactionSource::External,
+			Some(peer),
+			xt,
+			CheckBannedBeforeVerify::Yes,
+		).await;
+		self.validated_pool.submit(std::iter::once(tx))
+			.pop()
+			.expect("One extrinsic passed; one result returned; qed")
+	}
+
 	/// Import a single extrinsic and starts to watch their progress in the pool.
 	pub async fn submit_and_watch(
 		&self,
@@ -205,12 +314,31 @@ impl<B: ChainApi> Pool<B> {
 			at,
 			block_number,
 			source,
+			None,
 			xt,
 			CheckBannedBeforeVerify::Yes,
 		).await;
 		self.validated_pool.submit_and_watch(tx)
 	}
 
+	/// Apply a single extrinsic on top of the state of the given block without importing it
+	/// to the pool.
+	///
+	/// Returns the hash of the extrinsic, the outcome of applying it and the events it deposited.
+	pub async fn simulate(
+		&self,
+		at: &BlockId<B::Block>,
+		xt: ExtrinsicFor<B>,
+	) -> Result<(ExtrinsicHash<B>, SimulationOutcome), B::Error> {
+		let hash = self.hash_of(&xt);
+		if self.validated_pool.is_banned(&hash) {
+			return Err(error::Error::TemporarilyBanned.into());
+		}
+
+		let result = self.validated_pool.api().simulate_transaction(at, xt).await?;
+		Ok((hash, result))
+	}
+
 	/// Resubmit some transaction that were validated elsewhere.
 	pub fn resubmit(
 		&self,
@@ -383,7 +511,7 @@ impl<B: ChainApi> Pool<B> {
 
 		let res = futures::future::join_all(
 			xts.into_iter()
-				.map(|(source, xt)| self.verify_one(at, block_number, source, xt, check))
+				.map(|(source, xt)| self.verify_one(at, block_number, source, None, xt, check))
 		).await.into_iter().collect::<HashMap<_, _>>();
 
 		Ok(res)
@@ -395,6 +523,7 @@ impl<B: ChainApi> Pool<B> {
 		block_id: &BlockId<B::Block>,
 		block_number: NumberFor<B>,
 		source: TransactionSource,
+		peer: Option<&str>,
 		xt: ExtrinsicFor<B>,
 		check: CheckBannedBeforeVerify,
 	) -> (ExtrinsicHash<B>, ValidatedTransactionFor<B>) {
@@ -405,6 +534,12 @@ impl<B: ChainApi> Pool<B> {
 			return (hash.clone(), ValidatedTransaction::Invalid(hash, err.into()))
 		}
 
+		// not admitted transactions are not banned, they may be admitted from another source.
+		if !self.validated_pool.is_admitted(&xt, source, peer) {
+			let err = error::Error::NotAdmitted.into();
+			return (hash.clone(), ValidatedTransaction::Unknown(hash, err))
+		}
+
 		let validation_result = self.validated_pool.api().validate_transaction(
 			block_id,
 			source,
@@ -460,7 +595,7 @@ mod tests {
 	use parking_lot::Mutex;
 	use futures::executor::block_on;
 	use super::*;
-	use sp_transaction_pool::TransactionStatus;
+	use sp_transaction_pool::{DroppedReason, PoolEvent, TransactionStatus};
 	use sp_runtime::{
 		traits::Hash,
 		transaction_validity::{ValidTransaction, InvalidTransaction, TransactionSource},
@@ -487,6 +622,7 @@ mod tests {
 		type Error = error::Error;
 		type ValidationFuture = futures::future::Ready<error::Result<TransactionValidity>>;
 		type BodyFuture = futures::future::Ready<error::Result<Option<Vec<Extrinsic>>>>;
+		type SimulationFuture = futures::future::Ready<error::Result<SimulationOutcome>>;
 
 		/// Verify extrinsic at given block.
 		fn validate_transaction(
@@ -551,6 +687,22 @@ mod tests {
 			futures::future::ready(Ok(res))
 		}
 
+		fn simulate_transaction(
+			&self,
+			at: &BlockId<Self::Block>,
+			uxt: ExtrinsicFor<Self>,
+		) -> Self::SimulationFuture {
+			let block_number = self.block_id_to_number(at).unwrap().unwrap();
+
+			let res = match uxt {
+				Extrinsic::Transfer { transfer, .. } if transfer.nonce < block_number =>
+					Err(InvalidTransaction::Stale.into()),
+				_ => Ok(Ok(())),
+			};
+
+			futures::future::ready(Ok((res, Vec::new())))
+		}
+
 		/// Returns a block number given the block id.
 		fn block_id_to_number(
 			&self,
@@ -817,6 +969,70 @@ mod tests {
 		assert_eq!(pool.validated_pool().status().future, 0);
 	}
 
+	#[test]
+	fn should_only_import_transactions_admitted_by_the_filter() {
+		// given
+		struct OnlyFromPeers;
+		impl AdmissionFilter for OnlyFromPeers {
+			fn admit(&self, _xt: &[u8], source: TransactionSource, peer: Option<&str>) -> bool {
+				source == TransactionSource::Local || peer.is_some()
+			}
+		}
+
+		let options = Options {
+			admission_filter: Some(Arc::new(OnlyFromPeers)),
+			..Default::default()
+		};
+		let pool = Pool::new(options, true.into(), TestApi::default().into());
+		let xt = |nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+
+		// when
+		let err = block_on(pool.submit_one(&BlockId::Number(0), SOURCE, xt(0))).unwrap_err();
+
+		// then
+		assert_matches!(err, error::Error::NotAdmitted);
+		assert!(!pool.validated_pool().is_banned(&pool.hash_of(&xt(0))));
+		assert_eq!(pool.validated_pool().status().ready, 0);
+
+		block_on(pool.submit_from_peer(&BlockId::Number(0), "peer", xt(0))).unwrap();
+		block_on(pool.submit_one(&BlockId::Number(0), TransactionSource::Local, xt(1))).unwrap();
+		assert_eq!(pool.validated_pool().status().ready, 2);
+	}
+
+	#[test]
+	fn should_simulate_transaction_without_importing_it() {
+		// given
+		let pool = pool();
+		let xt = uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 1,
+		});
+
+		// when
+		let (hash, (result, events)) =
+			block_on(pool.simulate(&BlockId::Number(1), xt.clone())).unwrap();
+		let (_, (stale, _)) = block_on(pool.simulate(&BlockId::Number(2), xt.clone())).unwrap();
+
+		// then
+		assert_eq!(hash, pool.hash_of(&xt));
+		assert_eq!(result, Ok(Ok(())));
+		assert!(events.is_empty());
+		assert_eq!(stale, Err(InvalidTransaction::Stale.into()));
+		assert!(pool.validated_pool().status().is_empty());
+
+		// banned transactions are not simulated
+		pool.validated_pool().ban(&Instant::now(), vec![hash]);
+		let err = block_on(pool.simulate(&BlockId::Number(1), xt)).unwrap_err();
+		assert_matches!(err, error::Error::TemporarilyBanned);
+	}
+
 	#[test]
 	fn should_reject_transactions_with_no_provides() {
 		// given
@@ -947,6 +1163,72 @@ mod tests {
 			assert_eq!(stream.next(), None);
 		}
 
+		#[test]
+		fn should_emit_pool_events() {
+			// given
+			let pool = pool();
+			let events = pool.validated_pool().pool_event_stream();
+			let xt = uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(1)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce: 0,
+			});
+			let invalid = block_on(pool.submit_one(&BlockId::Number(0), SOURCE, xt)).unwrap();
+			let xt = uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(1)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce: 2,
+			});
+			let banned = block_on(pool.submit_one(&BlockId::Number(0), SOURCE, xt)).unwrap();
+			assert_eq!(pool.validated_pool().status().ready, 1);
+			assert_eq!(pool.validated_pool().status().future, 1);
+
+			// when
+			pool.validated_pool().remove_invalid(&[invalid]);
+			pool.validated_pool().ban_and_remove(&[banned], None);
+
+			// then
+			let mut stream = futures::executor::block_on_stream(events);
+			assert_eq!(stream.next(), Some(PoolEvent::Invalid(invalid)));
+			assert_eq!(stream.next(), Some(PoolEvent::Banned(banned)));
+			assert!(pool.validated_pool().status().is_empty());
+		}
+
+		#[test]
+		fn should_emit_the_reason_of_resubmitted_transactions_dropped() {
+			// given
+			let options = Options { reject_future_transactions: true, ..Default::default() };
+			let pool = Pool::new(options, true.into(), TestApi::default().into());
+			let events = pool.validated_pool().pool_event_stream();
+			let xt = |nonce| uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(1)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce,
+			});
+			let first = block_on(pool.submit_one(&BlockId::Number(0), SOURCE, xt(0))).unwrap();
+			let second = block_on(pool.submit_one(&BlockId::Number(0), SOURCE, xt(1))).unwrap();
+			assert_eq!(pool.validated_pool().status().ready, 2);
+
+			// when
+			let mut revalidated = HashMap::new();
+			revalidated.insert(
+				first,
+				ValidatedTransaction::Invalid(first, error::Error::NoTagsProvided.into()),
+			);
+			pool.resubmit(revalidated);
+
+			// then
+			// the second transaction waits for the invalid one and the future queue is rejected.
+			let mut stream = futures::executor::block_on_stream(events);
+			let events = vec![stream.next().unwrap(), stream.next().unwrap()];
+			assert!(events.contains(&PoolEvent::Invalid(first)));
+			assert!(events.contains(&PoolEvent::Dropped(second, DroppedReason::FutureRejected)));
+			assert!(pool.validated_pool().status().is_empty());
+		}
+
 		#[test]
 		fn should_trigger_broadcasted() {
 			// given
@@ -25,7 +25,7 @@ use std::{
 
 use serde::Serialize;
 use log::trace;
-use sp_runtime::traits::Member;
+use sp_runtime::{traits::Member, Percent};
 use sp_runtime::transaction_validity::{
 	TransactionTag as Tag,
 };
@@ -118,6 +118,9 @@ pub struct ReadyTransactions<Hash: hash::Hash + Eq, Ex> {
 	ready: TrackedMap<Hash, ReadyTx<Hash, Ex>>,
 	/// Best transactions that are ready to be included to the block without any other previous transaction.
 	best: BTreeSet<TransactionRef<Hash, Ex>>,
+	/// Minimum priority increase over the replaced transactions required for a replacement.
+	#[ignore_malloc_size_of = "not allocated"]
+	min_replacement_bump: Percent,
 }
 
 impl<Hash, Ex> tracked_map::Size for ReadyTx<Hash, Ex> {
@@ -133,11 +136,18 @@ impl<Hash: hash::Hash + Eq, Ex> Default for ReadyTransactions<Hash, Ex> {
 			provided_tags: Default::default(),
 			ready: Default::default(),
 			best: Default::default(),
+			min_replacement_bump: Percent::zero(),
 		}
 	}
 }
 
 impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
+	/// Set the minimum priority increase, relative to the collective priority of the
+	/// transactions being replaced, that a transaction needs to replace them.
+	pub fn set_min_replacement_bump(&mut self, bump: Percent) {
+		self.min_replacement_bump = bump;
+	}
+
 	/// Borrows a map of tags that are provided by transactions in this queue.
 	pub fn provided_tags(&self) -> &HashMap<Tag, Hash> {
 		&self.provided_tags
@@ -440,8 +450,11 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 					)
 			};
 
-			// bail - the transaction has too low priority to replace the old ones
-			if old_priority >= tx.priority {
+			// bail - the transaction has too low priority to replace the old ones, it must be
+			// higher than theirs and at least bumped by the minimum increase.
+			let min_priority = old_priority
+				.saturating_add(self.min_replacement_bump * old_priority);
+			if old_priority >= tx.priority || min_priority > tx.priority {
 				return Err(error::Error::TooLowPriority { old: old_priority, new: tx.priority })
 			}
 
@@ -605,6 +618,34 @@ mod tests {
 		assert_eq!(ready.get().count(), 1);
 	}
 
+	#[test]
+	fn should_require_minimum_priority_bump_to_replace_transaction() {
+		// given
+		let mut ready = ReadyTransactions::default();
+		ready.set_min_replacement_bump(Percent::from_percent(10));
+		let mut tx1 = tx(1);
+		tx1.requires.clear();
+		tx1.priority = 100;
+		let mut tx2 = tx(2);
+		tx2.requires.clear();
+
+		// when
+		import(&mut ready, tx1).unwrap();
+
+		// then
+		tx2.priority = 109;
+		assert!(matches!(
+			import(&mut ready, tx2.clone()),
+			Err(error::Error::TooLowPriority { old: 100, new: 109 })
+		));
+
+		tx2.priority = 110;
+		let replaced = import(&mut ready, tx2).unwrap();
+		assert_eq!(replaced.len(), 1);
+		assert_eq!(replaced[0].hash, 1);
+		assert_eq!(ready.get().map(|tx| tx.hash).collect::<Vec<_>>(), vec![2]);
+	}
+
 	#[test]
 	fn should_replace_multiple_transactions_correctly() {
 		// given
@@ -42,14 +42,21 @@ const EXPECTED_SIZE: usize = 2048;
 pub struct PoolRotator<Hash> {
 	/// How long the extrinsic is banned for.
 	ban_time: Duration,
-	/// Currently banned extrinsics.
-	banned_until: RwLock<HashMap<Hash, Instant>>,
+	/// Currently banned extrinsics, `None` if banned until they are unbanned.
+	banned_until: RwLock<HashMap<Hash, Option<Instant>>>,
 }
 
 impl<Hash: hash::Hash + Eq> Default for PoolRotator<Hash> {
 	fn default() -> Self {
+		Self::new(Duration::from_secs(60 * 30))
+	}
+}
+
+impl<Hash: hash::Hash + Eq> PoolRotator<Hash> {
+	/// Create a new rotator banning extrinsics for the given duration.
+	pub fn new(ban_time: Duration) -> Self {
 		PoolRotator {
-			ban_time: Duration::from_secs(60 * 30),
+			ban_time,
 			banned_until: Default::default(),
 		}
 	}
@@ -63,18 +70,30 @@ impl<Hash: hash::Hash + Eq + Clone> PoolRotator<Hash> {
 
 	/// Bans given set of hashes.
 	pub fn ban(&self, now: &Instant, hashes: impl IntoIterator<Item=Hash>) {
+		self.ban_for(now, self.ban_time, hashes)
+	}
+
+	/// Bans given set of hashes for the given duration instead of the default ban time.
+	///
+	/// The hashes are banned until they are unbanned if the duration is too large to be
+	/// represented.
+	pub fn ban_for(&self, now: &Instant, duration: Duration, hashes: impl IntoIterator<Item=Hash>) {
 		let mut banned = self.banned_until.write();
 
 		for hash in hashes {
-			banned.insert(hash, *now + self.ban_time);
+			banned.insert(hash, deadline(now, duration));
 		}
 
 		if banned.len() > 2 * EXPECTED_SIZE {
-			while banned.len() > EXPECTED_SIZE {
-				if let Some(key) = banned.keys().next().cloned() {
-					banned.remove(&key);
+			// the bans until unbanned are set by the operator and are never collected.
+			let mut excess = banned.len() - EXPECTED_SIZE;
+			banned.retain(|_, until| {
+				if excess == 0 || until.is_none() {
+					return true;
 				}
-			}
+				excess -= 1;
+				false
+			});
 		}
 	}
 
@@ -91,14 +110,34 @@ impl<Hash: hash::Hash + Eq + Clone> PoolRotator<Hash> {
 		true
 	}
 
+	/// Lifts the ban of the given hash.
+	///
+	/// Returns `true` if the hash was banned.
+	pub fn unban(&self, hash: &Hash) -> bool {
+		self.banned_until.write().remove(hash).is_some()
+	}
+
 	/// Removes timed bans.
 	pub fn clear_timeouts(&self, now: &Instant) {
 		let mut banned = self.banned_until.write();
 
-		banned.retain(|_, &mut v| v >= *now);
+		banned.retain(|_, &mut v| v.map_or(true, |v| v >= *now));
 	}
 }
 
+/// Returns the instant the given duration after `now`, `None` if it can't be represented.
+#[cfg(not(target_os = "unknown"))]
+pub(crate) fn deadline(now: &Instant, duration: Duration) -> Option<Instant> {
+	now.checked_add(duration)
+}
+
+/// Returns the instant the given duration after `now`, the instants of the browsers are
+/// floating point numbers and don't overflow.
+#[cfg(target_os = "unknown")]
+pub(crate) fn deadline(now: &Instant, duration: Duration) -> Option<Instant> {
+	Some(*now + duration)
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -178,6 +217,42 @@ mod tests {
 		assert!(!rotator.is_banned(&hash));
 	}
 
+	#[test]
+	fn should_ban_for_given_duration_and_unban() {
+		// given
+		let (hash, _) = tx();
+		let rotator = rotator();
+		let now = Instant::now();
+		let duration = rotator.ban_time * 10;
+
+		// when
+		rotator.ban_for(&now, duration, iter::once(hash));
+		rotator.clear_timeouts(&(now + rotator.ban_time * 2));
+
+		// then
+		assert!(rotator.is_banned(&hash));
+		assert!(rotator.unban(&hash));
+		assert!(!rotator.is_banned(&hash));
+		assert!(!rotator.unban(&hash));
+	}
+
+	#[test]
+	fn should_ban_until_unbanned_if_the_duration_overflows() {
+		// given
+		let (hash, _) = tx();
+		let rotator = rotator();
+		let now = Instant::now();
+
+		// when
+		rotator.ban_for(&now, Duration::from_secs(u64::max_value()), iter::once(hash));
+		rotator.clear_timeouts(&(now + rotator.ban_time * 2));
+
+		// then
+		assert!(rotator.is_banned(&hash));
+		assert!(rotator.unban(&hash));
+		assert!(!rotator.is_banned(&hash));
+	}
+
 	#[test]
 	fn should_garbage_collect() {
 		// given
@@ -214,4 +289,20 @@ mod tests {
 		assert!(rotator.ban_if_stale(&now, past_block, &tx));
 		assert_eq!(rotator.banned_until.read().len(), EXPECTED_SIZE);
 	}
+
+	#[test]
+	fn should_not_garbage_collect_bans_until_unbanned() {
+		// given
+		let rotator = rotator();
+		let now = Instant::now();
+		let forever = Duration::from_secs(u64::max_value());
+		rotator.ban_for(&now, forever, (0..EXPECTED_SIZE as u64 + 10).map(|i| i * 2));
+
+		// when
+		rotator.ban(&now, (0..EXPECTED_SIZE as u64).map(|i| i * 2 + 1));
+
+		// then
+		assert_eq!(rotator.banned_until.read().len(), EXPECTED_SIZE + 10);
+		assert!((0..EXPECTED_SIZE as u64 + 10).all(|i| rotator.is_banned(&(i * 2))));
+	}
 }
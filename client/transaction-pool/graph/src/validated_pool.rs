@@ -20,8 +20,10 @@ use std::{
 	collections::{HashSet, HashMap},
 	hash,
 	sync::Arc,
+	time::Duration,
 };
 
+use codec::Encode;
 use crate::base_pool as base;
 use crate::listener::Listener;
 use crate::rotator::PoolRotator;
@@ -34,9 +36,9 @@ use sp_runtime::{
 	traits::{self, SaturatedConversion},
 	transaction_validity::{TransactionTag as Tag, ValidTransaction, TransactionSource},
 };
-use sp_transaction_pool::{error, PoolStatus};
+use sp_transaction_pool::{error, DroppedReason, PoolEvent, PoolStatus};
 use wasm_timer::Instant;
-use futures::channel::mpsc::{channel, Sender};
+use futures::channel::mpsc::{channel, Receiver, Sender};
 use retain_mut::RetainMut;
 
 use crate::base_pool::PruneStatus;
@@ -133,7 +135,17 @@ where
 impl<B: ChainApi> ValidatedPool<B> {
 	/// Create a new transaction pool.
 	pub fn new(options: Options, is_validator: IsValidator, api: Arc<B>) -> Self {
-		let base_pool = base::BasePool::new(options.reject_future_transactions);
+		if options.per_sender.is_some() && options.sender_extractor.is_none() {
+			log::warn!(
+				target: "txpool",
+				"Per-sender limits are configured but the senders of the transactions are unknown, \
+				the limits are not enforced.",
+			);
+		}
+
+		let rotator = PoolRotator::new(options.ban_time);
+		let mut base_pool = base::BasePool::new(options.reject_future_transactions);
+		base_pool.set_min_replacement_bump(options.min_replacement_bump);
 		ValidatedPool {
 			is_validator,
 			options,
@@ -141,7 +153,7 @@ impl<B: ChainApi> ValidatedPool<B> {
 			api,
 			pool: RwLock::new(base_pool),
 			import_notification_sinks: Default::default(),
-			rotator: Default::default(),
+			rotator,
 		}
 	}
 
@@ -150,11 +162,54 @@ impl<B: ChainApi> ValidatedPool<B> {
 		self.rotator.ban(now, hashes)
 	}
 
+	/// Bans the given set of hashes for the given duration, or the configured ban time
+	/// if `None`, removing them and the transactions depending on them from the pool.
+	///
+	/// Returns the removed transactions.
+	pub fn ban_and_remove(
+		&self,
+		hashes: &[ExtrinsicHash<B>],
+		duration: Option<Duration>,
+	) -> Vec<TransactionFor<B>> {
+		let now = Instant::now();
+		match duration {
+			Some(duration) => self.rotator.ban_for(&now, duration, hashes.iter().cloned()),
+			None => self.rotator.ban(&now, hashes.iter().cloned()),
+		}
+
+		let removed = self.pool.write().remove_subtree(hashes);
+
+		let mut listener = self.listener.write();
+		for tx in &removed {
+			listener.banned(&tx.hash);
+		}
+
+		removed
+	}
+
+	/// Lifts the ban of the given hash.
+	///
+	/// Returns `true` if the hash was banned.
+	pub fn unban(&self, hash: &ExtrinsicHash<B>) -> bool {
+		self.rotator.unban(hash)
+	}
+
 	/// Returns true if transaction with given hash is currently banned from the pool.
 	pub fn is_banned(&self, hash: &ExtrinsicHash<B>) -> bool {
 		self.rotator.is_banned(hash)
 	}
 
+	/// Returns true if the configured admission filter, if any, admits the transaction.
+	pub fn is_admitted(
+		&self,
+		xt: &ExtrinsicFor<B>,
+		source: TransactionSource,
+		peer: Option<&str>,
+	) -> bool {
+		self.options.admission_filter.as_ref()
+			.map_or(true, |filter| filter.admit(&xt.encode(), source, peer))
+	}
+
 	/// A fast check before doing any further processing of a transaction, like validation.
 	///
 	/// If `ingore_banned` is `true`, it will not check if the transaction is banned.
@@ -179,13 +234,14 @@ impl<B: ChainApi> ValidatedPool<B> {
 		&self,
 		txs: impl IntoIterator<Item=ValidatedTransactionFor<B>>,
 	) -> Vec<Result<ExtrinsicHash<B>, B::Error>> {
+		let mut senders = HashSet::new();
 		let results = txs.into_iter()
-			.map(|validated_tx| self.submit_one(validated_tx))
+			.map(|validated_tx| self.submit_one(validated_tx, &mut senders))
 			.collect::<Vec<_>>();
 
 		// only enforce limits if there is at least one imported transaction
 		let removed = if results.iter().any(|res| res.is_ok()) {
-			self.enforce_limits()
+			self.enforce_limits(senders)
 		} else {
 			Default::default()
 		};
@@ -196,15 +252,38 @@ impl<B: ChainApi> ValidatedPool<B> {
 		}).collect()
 	}
 
-	/// Submit single pre-validated transaction to the pool.
-	fn submit_one(&self, tx: ValidatedTransactionFor<B>) -> Result<ExtrinsicHash<B>, B::Error> {
+	/// Add the configured priority bonus to a freshly validated local transaction.
+	fn with_local_priority_bonus(
+		&self,
+		tx: ValidatedTransactionFor<B>,
+	) -> ValidatedTransactionFor<B> {
 		match tx {
+			ValidatedTransaction::Valid(mut tx) if tx.source == TransactionSource::Local => {
+				tx.priority = tx.priority.saturating_add(self.options.local_priority_bonus);
+				ValidatedTransaction::Valid(tx)
+			},
+			other => other,
+		}
+	}
+
+	/// Submit single pre-validated transaction to the pool, noting its sender in `senders`.
+	fn submit_one(
+		&self,
+		tx: ValidatedTransactionFor<B>,
+		senders: &mut HashSet<Vec<u8>>,
+	) -> Result<ExtrinsicHash<B>, B::Error> {
+		match self.with_local_priority_bonus(tx) {
 			ValidatedTransaction::Valid(tx) => {
 				if !tx.propagate && !(self.is_validator.0)() {
 					return Err(error::Error::Unactionable.into());
 				}
 
-				let imported = self.pool.write().import(tx)?;
+				let sender = match (&self.options.per_sender, &self.options.sender_extractor) {
+					(Some(_), Some(extractor)) => extractor.sender(&tx.data.encode()),
+					_ => None,
+				};
+				let imported = self.pool.write().import_from(tx, sender.clone())?;
+				senders.extend(sender);
 
 				if let base::Imported::Ready { ref hash, .. } = imported {
 					self.import_notification_sinks.lock()
@@ -238,13 +317,14 @@ impl<B: ChainApi> ValidatedPool<B> {
 		}
 	}
 
-	fn enforce_limits(&self) -> HashSet<ExtrinsicHash<B>> {
+	/// Enforce the limits of the pool and the limits of the given senders.
+	fn enforce_limits(&self, senders: HashSet<Vec<u8>>) -> HashSet<ExtrinsicHash<B>> {
 		let status = self.pool.read().status();
 		let ready_limit = &self.options.ready;
 		let future_limit = &self.options.future;
 
 		log::debug!(target: "txpool", "Pool Status: {:?}", status);
-		if ready_limit.is_exceeded(status.ready, status.ready_bytes)
+		let mut removed = if ready_limit.is_exceeded(status.ready, status.ready_bytes)
 			|| future_limit.is_exceeded(status.future, status.future_bytes)
 		{
 			log::debug!(
@@ -254,29 +334,45 @@ impl<B: ChainApi> ValidatedPool<B> {
 				future_limit.count, future_limit.total_bytes / 1024,
 			);
 
-			// clean up the pool
-			let removed = {
+			self.pool.write().enforce_limits(ready_limit, future_limit, &self.options.lanes)
+		} else {
+			Vec::new()
+		};
+
+		if let Some(sender_limit) = self.options.per_sender.as_ref() {
+			let removed_by_sender = {
 				let mut pool = self.pool.write();
-				let removed = pool.enforce_limits(ready_limit, future_limit)
-					.into_iter().map(|x| x.hash.clone()).collect::<HashSet<_>>();
-				// ban all removed transactions
-				self.rotator.ban(&Instant::now(), removed.iter().map(|x| x.clone()));
-				removed
+				senders.iter()
+					.flat_map(|sender| pool.enforce_sender_limit(sender, sender_limit))
+					.collect::<Vec<_>>()
 			};
-			if !removed.is_empty() {
-				log::debug!(target: "txpool", "Enforcing limits: {} dropped", removed.len());
+			if !removed_by_sender.is_empty() {
+				log::debug!(
+					target: "txpool",
+					"Enforcing sender limits ({}/{}kB): {} dropped",
+					sender_limit.count, sender_limit.total_bytes / 1024,
+					removed_by_sender.len(),
+				);
 			}
+			removed.extend(removed_by_sender);
+		}
 
-			// run notifications
-			let mut listener = self.listener.write();
-			for h in &removed {
-				listener.dropped(h, None);
-			}
+		if removed.is_empty() {
+			return Default::default();
+		}
 
-			removed
-		} else {
-			Default::default()
+		// ban all removed transactions
+		let removed = removed.into_iter().map(|x| x.hash.clone()).collect::<HashSet<_>>();
+		self.rotator.ban(&Instant::now(), removed.iter().map(|x| x.clone()));
+		log::debug!(target: "txpool", "Enforcing limits: {} dropped", removed.len());
+
+		// run notifications
+		let mut listener = self.listener.write();
+		for h in &removed {
+			listener.dropped(h, None);
 		}
+
+		removed
 	}
 
 	/// Import a single extrinsic and starts to watch their progress in the pool.
@@ -306,8 +402,8 @@ impl<B: ChainApi> ValidatedPool<B> {
 	/// Removes and then submits passed transactions and all dependent transactions.
 	/// Transactions that are missing from the pool are not submitted.
 	pub fn resubmit(&self, mut updated_transactions: HashMap<ExtrinsicHash<B>, ValidatedTransactionFor<B>>) {
-		#[derive(Debug, Clone, Copy, PartialEq)]
-		enum Status { Future, Ready, Failed, Dropped }
+		#[derive(Debug, Clone, PartialEq)]
+		enum Status<H> { Future, Ready, Failed, Dropped(DroppedReason<H>) }
 
 		let (mut initial_statuses, final_statuses) = {
 			let mut pool = self.pool.write();
@@ -332,7 +428,7 @@ impl<B: ChainApi> ValidatedPool<B> {
 					let removed_hash = removed_tx.hash.clone();
 					let updated_transaction = updated_transactions.remove(&removed_hash);
 					let tx_to_resubmit = if let Some(updated_tx) = updated_transaction {
-						updated_tx
+						self.with_local_priority_bonus(updated_tx)
 					} else {
 						// in most cases we'll end up in successful `try_unwrap`, but if not
 						// we still need to reinsert transaction back to the pool => duplicate call
@@ -362,6 +458,12 @@ impl<B: ChainApi> ValidatedPool<B> {
 						ValidatedTransaction::Valid(tx) => match pool.import(tx) {
 							Ok(imported) => match imported {
 								base::Imported::Ready { promoted, failed, removed, .. } => {
+									for tx in removed {
+										final_statuses.insert(
+											tx.hash.clone(),
+											Status::Dropped(DroppedReason::Usurped(hash.clone())),
+										);
+									}
 									final_statuses.insert(hash, Status::Ready);
 									for hash in promoted {
 										final_statuses.insert(hash, Status::Ready);
@@ -369,9 +471,6 @@ impl<B: ChainApi> ValidatedPool<B> {
 									for hash in failed {
 										final_statuses.insert(hash, Status::Failed);
 									}
-									for tx in removed {
-										final_statuses.insert(tx.hash.clone(), Status::Dropped);
-									}
 								},
 								base::Imported::Future { .. } => {
 									final_statuses.insert(hash, Status::Future);
@@ -400,7 +499,10 @@ impl<B: ChainApi> ValidatedPool<B> {
 				// queue, updating final statuses as required
 				if reject_future_transactions {
 					for future_tx in pool.clear_future() {
-						final_statuses.insert(future_tx.hash.clone(), Status::Dropped);
+						final_statuses.insert(
+							future_tx.hash.clone(),
+							Status::Dropped(DroppedReason::FutureRejected),
+						);
 					}
 				}
 
@@ -412,11 +514,11 @@ impl<B: ChainApi> ValidatedPool<B> {
 		let mut listener = self.listener.write();
 		for (hash, final_status) in final_statuses {
 			let initial_status = initial_statuses.remove(&hash);
-			if initial_status.is_none() || Some(final_status) != initial_status {
+			if initial_status.as_ref() != Some(&final_status) {
 				match final_status {
 					Status::Future => listener.future(&hash),
 					Status::Ready => listener.ready(&hash, None),
-					Status::Dropped => listener.dropped(&hash, None),
+					Status::Dropped(reason) => listener.dropped_for(&hash, reason),
 					Status::Failed => listener.invalid(&hash, initial_status.is_some()),
 				}
 			}
@@ -452,7 +554,7 @@ impl<B: ChainApi> ValidatedPool<B> {
 				fire_events(&mut *listener, promoted);
 			}
 			for f in &status.failed {
-				listener.dropped(f, None);
+				listener.dropped_for(f, DroppedReason::PromotionFailed);
 			}
 		}
 
@@ -541,12 +643,32 @@ impl<B: ChainApi> ValidatedPool<B> {
 		// removing old transactions
 		self.remove_invalid(&to_remove);
 		self.remove_invalid(&futures_to_remove);
+		// dropping transactions waiting for their requirements for too long
+		if let Some(max_age) = self.options.future_max_age {
+			self.remove_expired_futures(now, max_age);
+		}
 		// clear banned transactions timeouts
 		self.rotator.clear_timeouts(&now);
 
 		Ok(())
 	}
 
+	/// Removes and bans the future transactions that have been waiting for at least `max_age`.
+	fn remove_expired_futures(&self, now: Instant, max_age: Duration) {
+		let expired = self.pool.write().remove_expired_futures(now, max_age);
+		if expired.is_empty() {
+			return;
+		}
+
+		log::debug!(target: "txpool", "Dropping {} expired future transactions", expired.len());
+		self.rotator.ban(&now, expired.iter().map(|tx| tx.hash.clone()));
+
+		let mut listener = self.listener.write();
+		for tx in &expired {
+			listener.expired(&tx.hash);
+		}
+	}
+
 	/// Get rotator reference.
 	#[cfg(test)]
 	pub fn rotator(&self) -> &PoolRotator<ExtrinsicHash<B>> {
@@ -570,6 +692,11 @@ impl<B: ChainApi> ValidatedPool<B> {
 		stream
 	}
 
+	/// Get a stream of pool-wide events about transactions leaving the pool.
+	pub fn pool_event_stream(&self) -> Receiver<PoolEvent<ExtrinsicHash<B>, BlockHash<B>>> {
+		self.listener.write().event_stream()
+	}
+
 	/// Invoked when extrinsics are broadcasted.
 	pub fn on_broadcasted(&self, propagated: HashMap<ExtrinsicHash<B>, Vec<String>>) {
 		let mut listener = self.listener.write();
@@ -612,11 +739,25 @@ impl<B: ChainApi> ValidatedPool<B> {
 		self.pool.read().ready()
 	}
 
+	/// Returns all future transactions.
+	pub fn futures(&self) -> Vec<TransactionFor<B>> {
+		self.pool.read().futures().cloned().collect()
+	}
+
 	/// Returns pool status.
 	pub fn status(&self) -> PoolStatus {
 		self.pool.read().status()
 	}
 
+	/// Returns the configured lanes along with their status.
+	pub fn lane_status(&self) -> Vec<(&base::Lane, PoolStatus)> {
+		let lanes = &self.options.lanes;
+		if lanes.is_empty() {
+			return Vec::new()
+		}
+		lanes.iter().zip(self.pool.read().lane_status(lanes)).collect()
+	}
+
 	/// Notify all watchers that transactions in the block with hash have been finalized
 	pub async fn on_block_finalized(&self, block_hash: BlockHash<B>) -> Result<(), B::Error> {
 		log::trace!(target: "txpool", "Attempting to notify watchers of finalization for {}", block_hash);
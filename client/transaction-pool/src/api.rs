@@ -26,26 +26,37 @@ use futures::{
 
 use sc_client_api::{
 	blockchain::HeaderBackend, light::{Fetcher, RemoteCallRequest, RemoteBodyRequest}, BlockBackend,
+	StateProvider,
 };
+use sp_core::{hashing::twox_128, ExecutionContext};
 use sp_runtime::{
-	generic::BlockId, traits::{self, Block as BlockT, BlockIdTo, Header as HeaderT, Hash as HashT},
+	generic::BlockId,
+	traits::{self, Block as BlockT, BlockIdTo, Header as HeaderT, Hash as HashT, One},
 	transaction_validity::{TransactionValidity, TransactionSource},
+	ApplyExtrinsicResult,
 };
 use sp_transaction_pool::runtime_api::TaggedTransactionQueue;
-use sp_api::{ProvideRuntimeApi, ApiExt};
+use sp_block_builder::BlockBuilder;
+use sp_api::{ProvideRuntimeApi, ApiExt, Core, StateBackendFor};
 use prometheus_endpoint::Registry as PrometheusRegistry;
+use parking_lot::Mutex;
 
-use crate::{metrics::{ApiMetrics, ApiMetricsExt}, error::{self, Error}};
+use crate::{
+	metrics::{ApiMetrics, ApiMetricsExt},
+	error::{self, Error},
+	validation_cache::ValidationCache,
+};
 
 /// The transaction pool logic for full client.
-pub struct FullChainApi<Client, Block> {
+pub struct FullChainApi<Client, Block: BlockT> {
 	client: Arc<Client>,
 	pool: ThreadPool,
 	_marker: PhantomData<Block>,
 	metrics: Option<Arc<ApiMetrics>>,
+	validation_cache: Arc<Mutex<ValidationCache<Block::Hash, Block::Hash>>>,
 }
 
-impl<Client, Block> FullChainApi<Client, Block> {
+impl<Client, Block: BlockT> FullChainApi<Client, Block> {
 	/// Create new transaction pool logic.
 	pub fn new(
 		client: Arc<Client>,
@@ -74,6 +85,7 @@ impl<Client, Block> FullChainApi<Client, Block> {
 				.expect("Failed to spawn verifier threads, that are critical for node operation."),
 			_marker: Default::default(),
 			metrics,
+			validation_cache: Default::default(),
 		}
 	}
 }
@@ -82,8 +94,9 @@ impl<Client, Block> sc_transaction_graph::ChainApi for FullChainApi<Client, Bloc
 where
 	Block: BlockT,
 	Client: ProvideRuntimeApi<Block> + BlockBackend<Block> + BlockIdTo<Block>,
+	Client: StateProvider<Block, State = StateBackendFor<Client, Block>>,
 	Client: Send + Sync + 'static,
-	Client::Api: TaggedTransactionQueue<Block>,
+	Client::Api: TaggedTransactionQueue<Block> + BlockBuilder<Block>,
 {
 	type Block = Block;
 	type Error = error::Error;
@@ -91,6 +104,9 @@ where
 		Box<dyn Future<Output = error::Result<TransactionValidity>> + Send>
 	>;
 	type BodyFuture = Ready<error::Result<Option<Vec<<Self::Block as BlockT>::Extrinsic>>>>;
+	type SimulationFuture = Pin<
+		Box<dyn Future<Output = error::Result<(ApplyExtrinsicResult, Vec<u8>)>> + Send>
+	>;
 
 	fn block_body(&self, id: &BlockId<Self::Block>) -> Self::BodyFuture {
 		ready(self.client.block_body(&id).map_err(|e| error::Error::from(e)))
@@ -107,16 +123,40 @@ where
 		let at = at.clone();
 
 		let metrics = self.metrics.clone();
+
+		// only results at a given block hash can be cached, since the block a number
+		// refers to may change.
+		let cache_key = match at {
+			BlockId::Hash(block_hash) => {
+				let hash = uxt.using_encoded(|x| <traits::HashFor<Block> as traits::Hash>::hash(x));
+				if let Some(validity) = self.validation_cache.lock().get(&block_hash, source, hash) {
+					metrics.report(|m| m.validation_cache_hits.inc());
+					return Box::pin(ready(Ok(validity)));
+				}
+				metrics.report(|m| m.validation_cache_misses.inc());
+				Some((block_hash, hash))
+			},
+			BlockId::Number(_) => None,
+		};
+		let validation_cache = self.validation_cache.clone();
+
 		metrics.report(|m| m.validations_scheduled.inc());
 
 		self.pool.spawn_ok(futures_diagnose::diagnose(
 			"validate-transaction",
 			async move {
+				let started = std::time::Instant::now();
 				let res = validate_transaction_blocking(&*client, &at, source, uxt);
+				if let (Some((block_hash, hash)), Ok(validity)) = (cache_key, &res) {
+					validation_cache.lock().insert(&block_hash, source, hash, validity.clone());
+				}
 				if let Err(e) = tx.send(res) {
 					log::warn!("Unable to send a validate transaction result: {:?}", e);
 				}
-				metrics.report(|m| m.validations_finished.inc());
+				metrics.report(|m| {
+					m.validations_finished.inc();
+					m.validation_time.observe(started.elapsed().as_secs_f64());
+				});
 			},
 		));
 
@@ -128,6 +168,33 @@ where
 		})
 	}
 
+	fn simulate_transaction(
+		&self,
+		at: &BlockId<Self::Block>,
+		uxt: sc_transaction_graph::ExtrinsicFor<Self>,
+	) -> Self::SimulationFuture {
+		let (tx, rx) = oneshot::channel();
+		let client = self.client.clone();
+		let at = at.clone();
+
+		self.pool.spawn_ok(futures_diagnose::diagnose(
+			"simulate-transaction",
+			async move {
+				let res = simulate_transaction_blocking(&*client, &at, uxt);
+				if let Err(e) = tx.send(res) {
+					log::warn!("Unable to send a simulate transaction result: {:?}", e);
+				}
+			},
+		));
+
+		Box::pin(async move {
+			match rx.await {
+				Ok(r) => r,
+				Err(_) => Err(Error::RuntimeApi("Simulation was canceled".into())),
+			}
+		})
+	}
+
 	fn block_id_to_number(
 		&self,
 		at: &BlockId<Self::Block>,
@@ -158,7 +225,7 @@ fn validate_transaction_blocking<Client, Block>(
 	client: &Client,
 	at: &BlockId<Block>,
 	source: TransactionSource,
-	uxt: sc_transaction_graph::ExtrinsicFor<FullChainApi<Client, Block>>,
+	uxt: <Block as BlockT>::Extrinsic,
 ) -> error::Result<TransactionValidity>
 where
 	Block: BlockT,
@@ -190,6 +257,58 @@ where
 	})
 }
 
+/// Helper function to simulate a transaction using a full chain API.
+///
+/// A block is initialized on top of the given one and the transaction is applied in it. Returns
+/// the outcome along with the SCALE encoded events read from `System::Events` afterwards.
+fn simulate_transaction_blocking<Client, Block>(
+	client: &Client,
+	at: &BlockId<Block>,
+	uxt: <Block as BlockT>::Extrinsic,
+) -> error::Result<(ApplyExtrinsicResult, Vec<u8>)>
+where
+	Block: BlockT,
+	Client: ProvideRuntimeApi<Block> + BlockIdTo<Block>,
+	Client: StateProvider<Block, State = StateBackendFor<Client, Block>>,
+	Client::Api: BlockBuilder<Block>,
+{
+	let unknown_block = || Error::BlockIdConversion(format!("Unknown block {}", at));
+	let parent_hash = client.to_hash(at)
+		.map_err(|e| Error::BlockIdConversion(format!("{:?}", e)))?
+		.ok_or_else(unknown_block)?;
+	let parent_number = client.to_number(at)
+		.map_err(|e| Error::BlockIdConversion(format!("{:?}", e)))?
+		.ok_or_else(unknown_block)?;
+	let header = <Block::Header as HeaderT>::new(
+		parent_number + One::one(),
+		Default::default(),
+		Default::default(),
+		parent_hash,
+		Default::default(),
+	);
+
+	let runtime_api = client.runtime_api();
+	runtime_api
+		.initialize_block_with_context(at, ExecutionContext::BlockConstruction, &header)
+		.map_err(|e| Error::RuntimeApi(e.to_string()))?;
+	let result = runtime_api
+		.apply_extrinsic_with_context(at, ExecutionContext::BlockConstruction, uxt)
+		.map_err(|e| Error::RuntimeApi(e.to_string()))?;
+
+	let state = client.state_at(at)?;
+	let changes = runtime_api
+		.into_storage_changes(&state, None, parent_hash)
+		.map_err(Error::RuntimeApi)?;
+	let events_key = [twox_128(b"System"), twox_128(b"Events")].concat();
+	let events = changes.main_storage_changes
+		.into_iter()
+		.find(|(key, _)| *key == events_key)
+		.and_then(|(_, value)| value)
+		.unwrap_or_default();
+
+	Ok((result, events))
+}
+
 impl<Client, Block> FullChainApi<Client, Block>
 where
 	Block: BlockT,
@@ -205,7 +324,7 @@ where
 		&self,
 		at: &BlockId<Block>,
 		source: TransactionSource,
-		uxt: sc_transaction_graph::ExtrinsicFor<Self>,
+		uxt: <Block as BlockT>::Extrinsic,
 	) -> error::Result<TransactionValidity> {
 		validate_transaction_blocking(&*self.client, at, source, uxt)
 	}
@@ -246,6 +365,7 @@ impl<Client, F, Block> sc_transaction_graph::ChainApi for
 				+ Send
 		>
 	>;
+	type SimulationFuture = Ready<error::Result<(ApplyExtrinsicResult, Vec<u8>)>>;
 
 	fn validate_transaction(
 		&self,
@@ -282,6 +402,18 @@ impl<Client, F, Block> sc_transaction_graph::ChainApi for
 		Box::new(remote_validation_request)
 	}
 
+	fn simulate_transaction(
+		&self,
+		_at: &BlockId<Self::Block>,
+		_uxt: sc_transaction_graph::ExtrinsicFor<Self>,
+	) -> Self::SimulationFuture {
+		// a remote call executes a single runtime method, so it can't initialize the block the
+		// transaction would have to be applied in, nor return the events it deposited.
+		ready(Err(Error::RuntimeApi(
+			"Transaction simulation is not supported by light clients".into()
+		)))
+	}
+
 	fn block_id_to_number(
 		&self,
 		at: &BlockId<Self::Block>,
@@ -25,14 +25,18 @@
 mod api;
 mod revalidation;
 mod metrics;
+mod sender;
+mod validation_cache;
 
 pub mod error;
+pub mod persistence;
 
 #[cfg(test)]
 pub mod testing;
 
 pub use sc_transaction_graph as txpool;
 pub use crate::api::{FullChainApi, LightChainApi};
+pub use crate::sender::SignedExtrinsicSender;
 
 use std::{collections::{HashMap, HashSet}, sync::Arc, pin::Pin, convert::TryInto};
 use futures::{prelude::*, future::{self, ready}, channel::oneshot};
@@ -46,7 +50,7 @@ use sp_core::traits::SpawnNamed;
 use sp_transaction_pool::{
 	TransactionPool, PoolStatus, ImportNotificationStream, TxHash, TransactionFor,
 	TransactionStatusStreamFor, MaintainedTransactionPool, PoolFuture, ChainEvent,
-	TransactionSource,
+	TransactionSource, PoolEventStream, BlockHash, TransactionSimulation,
 };
 use sc_transaction_graph::{ChainApi, ExtrinsicHash};
 use wasm_timer::Instant;
@@ -79,6 +83,7 @@ pub struct BasicPool<PoolApi, Block>
 	api: Arc<PoolApi>,
 	revalidation_strategy: Arc<Mutex<RevalidationStrategy<NumberFor<Block>>>>,
 	revalidation_queue: Arc<revalidation::RevalidationQueue<PoolApi>>,
+	revalidation_options: sc_transaction_graph::RevalidationOptions,
 	ready_poll: Arc<Mutex<ReadyPoll<ReadyIteratorFor<PoolApi>, Block>>>,
 	metrics: PrometheusMetrics,
 }
@@ -172,6 +177,7 @@ impl<PoolApi, Block> BasicPool<PoolApi, Block>
 				pool,
 				revalidation_queue: Arc::new(revalidation_queue),
 				revalidation_strategy: Arc::new(Mutex::new(RevalidationStrategy::Always)),
+				revalidation_options: Default::default(),
 				ready_poll: Default::default(),
 				metrics: Default::default(),
 			},
@@ -190,11 +196,16 @@ impl<PoolApi, Block> BasicPool<PoolApi, Block>
 		revalidation_type: RevalidationType,
 		spawner: impl SpawnNamed,
 	) -> Self {
+		let revalidation_options = options.revalidation.clone();
 		let pool = Arc::new(sc_transaction_graph::Pool::new(options, is_validator, pool_api.clone()));
 		let (revalidation_queue, background_task) = match revalidation_type {
 			RevalidationType::Light => (revalidation::RevalidationQueue::new(pool_api.clone(), pool.clone()), None),
 			RevalidationType::Full => {
-				let (queue, background) = revalidation::RevalidationQueue::new_background(pool_api.clone(), pool.clone());
+				let (queue, background) = revalidation::RevalidationQueue::new_background(
+					pool_api.clone(),
+					pool.clone(),
+					revalidation_options.clone(),
+				);
 				(queue, Some(background))
 			},
 		};
@@ -203,6 +214,14 @@ impl<PoolApi, Block> BasicPool<PoolApi, Block>
 			spawner.spawn("txpool-background", background_task);
 		}
 
+		let metrics = PrometheusMetrics::new(prometheus);
+		if metrics.is_enabled() {
+			spawner.spawn(
+				"txpool-metrics",
+				metrics::report_pool_events(pool.clone(), metrics.clone()).boxed(),
+			);
+		}
+
 		BasicPool {
 			api: pool_api,
 			pool,
@@ -213,8 +232,9 @@ impl<PoolApi, Block> BasicPool<PoolApi, Block>
 					RevalidationType::Full => RevalidationStrategy::Always,
 				}
 			)),
+			revalidation_options,
 			ready_poll: Default::default(),
-			metrics: PrometheusMetrics::new(prometheus),
+			metrics,
 		}
 	}
 
@@ -264,6 +284,20 @@ impl<PoolApi, Block> TransactionPool for BasicPool<PoolApi, Block>
 		async move { pool.submit_one(&at, source, xt).await }.boxed()
 	}
 
+	fn submit_from_peer(
+		&self,
+		at: &BlockId<Self::Block>,
+		peer: String,
+		xt: TransactionFor<Self>,
+	) -> PoolFuture<TxHash<Self>, Self::Error> {
+		let pool = self.pool.clone();
+		let at = *at;
+
+		self.metrics.report(|metrics| metrics.submitted_transactions.inc());
+
+		async move { pool.submit_from_peer(&at, &peer, xt).await }.boxed()
+	}
+
 	fn submit_and_watch(
 		&self,
 		at: &BlockId<Self::Block>,
@@ -282,12 +316,43 @@ impl<PoolApi, Block> TransactionPool for BasicPool<PoolApi, Block>
 		}.boxed()
 	}
 
+	fn submit_and_simulate(
+		&self,
+		at: &BlockId<Self::Block>,
+		xt: TransactionFor<Self>,
+	) -> PoolFuture<TransactionSimulation<TxHash<Self>>, Self::Error> {
+		let pool = self.pool.clone();
+		let at = *at;
+
+		async move {
+			pool.simulate(&at, xt)
+				.map(|simulation| simulation.map(|(hash, (result, events))| TransactionSimulation {
+					hash,
+					result,
+					events,
+				}))
+				.await
+		}.boxed()
+	}
+
 	fn remove_invalid(&self, hashes: &[TxHash<Self>]) -> Vec<Arc<Self::InPoolTransaction>> {
 		let removed = self.pool.validated_pool().remove_invalid(hashes);
 		self.metrics.report(|metrics| metrics.validations_invalid.inc_by(removed.len() as u64));
 		removed
 	}
 
+	fn ban(
+		&self,
+		hashes: &[TxHash<Self>],
+		duration: Option<std::time::Duration>,
+	) -> Vec<Arc<Self::InPoolTransaction>> {
+		self.pool.validated_pool().ban_and_remove(hashes, duration)
+	}
+
+	fn unban(&self, hash: &TxHash<Self>) -> bool {
+		self.pool.validated_pool().unban(hash)
+	}
+
 	fn status(&self) -> PoolStatus {
 		self.pool.validated_pool().status()
 	}
@@ -296,6 +361,10 @@ impl<PoolApi, Block> TransactionPool for BasicPool<PoolApi, Block>
 		self.pool.validated_pool().import_notification_stream()
 	}
 
+	fn pool_event_stream(&self) -> PoolEventStream<TxHash<Self>, BlockHash<Self>> {
+		self.pool.validated_pool().pool_event_stream()
+	}
+
 	fn hash_of(&self, xt: &TransactionFor<Self>) -> TxHash<Self> {
 		self.pool.hash_of(xt)
 	}
@@ -304,6 +373,10 @@ impl<PoolApi, Block> TransactionPool for BasicPool<PoolApi, Block>
 		self.pool.validated_pool().on_broadcasted(propagations)
 	}
 
+	fn futures(&self) -> Vec<Arc<Self::InPoolTransaction>> {
+		self.pool.validated_pool().futures()
+	}
+
 	fn ready_transaction(&self, hash: &TxHash<Self>) -> Option<Arc<Self::InPoolTransaction>> {
 		self.pool.validated_pool().ready_by_hash(hash)
 	}
@@ -358,8 +431,10 @@ where
 	Client: sp_api::ProvideRuntimeApi<Block>
 		+ sc_client_api::BlockBackend<Block>
 		+ sp_runtime::traits::BlockIdTo<Block>,
+	Client: sc_client_api::StateProvider<Block, State = sp_api::StateBackendFor<Client, Block>>,
 	Client: sc_client_api::ExecutorProvider<Block> + Send + Sync + 'static,
-	Client::Api: sp_transaction_pool::runtime_api::TaggedTransactionQueue<Block>,
+	Client::Api: sp_transaction_pool::runtime_api::TaggedTransactionQueue<Block>
+		+ sp_block_builder::BlockBuilder<Block>,
 {
 	/// Create new basic transaction pool for a full node with the provided api.
 	pub fn new_full(
@@ -388,8 +463,10 @@ where
 	Client: sp_api::ProvideRuntimeApi<Block>
 		+ sc_client_api::BlockBackend<Block>
 		+ sp_runtime::traits::BlockIdTo<Block>,
+	Client: sc_client_api::StateProvider<Block, State = sp_api::StateBackendFor<Client, Block>>,
 	Client: Send + Sync + 'static,
-	Client::Api: sp_transaction_pool::runtime_api::TaggedTransactionQueue<Block>,
+	Client::Api: sp_transaction_pool::runtime_api::TaggedTransactionQueue<Block>
+		+ sp_block_builder::BlockBuilder<Block>,
 {
 	type Block = Block;
 	type Hash = sc_transaction_graph::ExtrinsicHash<FullChainApi<Client, Block>>;
@@ -404,6 +481,10 @@ where
 		use sp_runtime::traits::SaturatedConversion;
 		use sp_runtime::transaction_validity::TransactionValidityError;
 
+		if !self.pool.validated_pool().is_admitted(&xt, TransactionSource::Local, None) {
+			return Err(Self::Error::Pool(sp_transaction_pool::error::Error::NotAdmitted));
+		}
+
 		let validity = self
 			.api
 			.validate_transaction_blocking(at, TransactionSource::Local, xt.clone())?
@@ -568,8 +649,8 @@ impl<PoolApi, Block> MaintainedTransactionPool for BasicPool<PoolApi, Block>
 
 				let next_action = self.revalidation_strategy.lock().next(
 					block_number,
-					Some(std::time::Duration::from_secs(60)),
-					Some(20u32.into()),
+					Some(self.revalidation_options.max_age),
+					Some(self.revalidation_options.max_age_blocks.into()),
 				);
 				let revalidation_strategy = self.revalidation_strategy.clone();
 				let revalidation_queue = self.revalidation_queue.clone();
@@ -18,9 +18,23 @@
 
 //! Transaction pool Prometheus metrics.
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
-use prometheus_endpoint::{register, Counter, PrometheusError, Registry, U64};
+use futures::prelude::*;
+use sc_transaction_graph::{base_pool::Lane, ChainApi, Pool};
+use sp_core::hexdisplay::HexDisplay;
+use sp_transaction_pool::{DroppedReason, PoolEvent, PoolStatus};
+use prometheus_endpoint::{
+	exponential_buckets, register, Counter, CounterVec, Gauge, GaugeVec, Histogram,
+	HistogramOpts, Opts, PrometheusError, Registry, U64,
+};
+use wasm_timer::Instant;
+
+/// Minimal interval between two computations of the occupancy of the tag classes.
+const TAG_STATUS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Label of the transactions that don't belong to any of the configured tag classes.
+const OTHER_TAG_LABEL: &str = "other";
 
 #[derive(Clone, Default)]
 pub struct MetricsLink(Arc<Option<Metrics>>);
@@ -41,6 +55,47 @@ impl MetricsLink {
 			do_this(metrics);
 		}
 	}
+
+	pub fn is_enabled(&self) -> bool {
+		self.0.is_some()
+	}
+}
+
+/// Keep the flow and occupancy metrics up to date with the events of the pool.
+pub async fn report_pool_events<Api: ChainApi>(pool: Arc<Pool<Api>>, metrics: MetricsLink) {
+	let imports = pool.validated_pool().import_notification_stream().map(|_| None);
+	let events = pool.validated_pool().pool_event_stream().map(Some);
+	let mut events = stream::select(imports, events);
+	let mut tag_status_reported_at: Option<Instant> = None;
+
+	while let Some(event) = events.next().await {
+		metrics.report(|metrics| {
+			match event {
+				None => metrics.imported_transactions.inc(),
+				Some(event) => {
+					let reason = match event {
+						PoolEvent::Dropped(_, DroppedReason::LimitsExceeded) => "limits",
+						PoolEvent::Dropped(_, DroppedReason::Usurped(_)) => "usurped",
+						PoolEvent::Dropped(_, DroppedReason::Expired) => "expired",
+						PoolEvent::Dropped(_, DroppedReason::FutureRejected) => "future_rejected",
+						PoolEvent::Dropped(_, DroppedReason::PromotionFailed) => "promotion_failed",
+						PoolEvent::Invalid(_) => "invalid",
+						PoolEvent::Banned(_) => "banned",
+						PoolEvent::Pruned(..) => "pruned",
+					};
+					metrics.transactions_removed.with_label_values(&[reason]).inc();
+				},
+			}
+			let status = pool.validated_pool().status();
+			metrics.report_status(&status);
+
+			// computing the occupancy of the tag classes requires to go through the whole pool
+			if tag_status_reported_at.map_or(true, |at| at.elapsed() >= TAG_STATUS_INTERVAL) {
+				metrics.report_tag_status(&status, pool.validated_pool().lane_status());
+				tag_status_reported_at = Some(Instant::now());
+			}
+		});
+	}
 }
 
 /// Transaction pool Prometheus metrics.
@@ -49,6 +104,14 @@ pub struct Metrics {
 	pub validations_invalid: Counter<U64>,
 	pub block_transactions_pruned: Counter<U64>,
 	pub block_transactions_resubmitted: Counter<U64>,
+	pub imported_transactions: Counter<U64>,
+	pub transactions_removed: CounterVec<U64>,
+	pub ready_transactions: Gauge<U64>,
+	pub ready_bytes: Gauge<U64>,
+	pub future_transactions: Gauge<U64>,
+	pub future_bytes: Gauge<U64>,
+	pub tag_transactions: GaugeVec<U64>,
+	pub tag_bytes: GaugeVec<U64>,
 }
 
 impl Metrics {
@@ -82,14 +145,123 @@ impl Metrics {
 				)?,
 				registry,
 			)?,
+			imported_transactions: register(
+				Counter::new(
+					"sub_txpool_imported_transactions",
+					"Total number of transactions imported to the ready queue",
+				)?,
+				registry,
+			)?,
+			transactions_removed: register(
+				CounterVec::new(
+					Opts::new(
+						"sub_txpool_transactions_removed",
+						"Total number of transactions removed from the pool, by reason",
+					),
+					&["reason"],
+				)?,
+				registry,
+			)?,
+			ready_transactions: register(
+				Gauge::new(
+					"sub_txpool_ready_transactions",
+					"Number of transactions in the ready queue",
+				)?,
+				registry,
+			)?,
+			ready_bytes: register(
+				Gauge::new(
+					"sub_txpool_ready_bytes",
+					"Total size of the transactions in the ready queue",
+				)?,
+				registry,
+			)?,
+			future_transactions: register(
+				Gauge::new(
+					"sub_txpool_future_transactions",
+					"Number of transactions in the future queue",
+				)?,
+				registry,
+			)?,
+			future_bytes: register(
+				Gauge::new(
+					"sub_txpool_future_bytes",
+					"Total size of the transactions in the future queue",
+				)?,
+				registry,
+			)?,
+			tag_transactions: register(
+				GaugeVec::new(
+					Opts::new(
+						"sub_txpool_tag_transactions",
+						"Number of transactions in the pool, by tag class and status",
+					),
+					&["tag", "status"],
+				)?,
+				registry,
+			)?,
+			tag_bytes: register(
+				GaugeVec::new(
+					Opts::new(
+						"sub_txpool_tag_bytes",
+						"Total size of the transactions in the pool, by tag class and status",
+					),
+					&["tag", "status"],
+				)?,
+				registry,
+			)?,
 		})
 	}
+
+	/// Report the occupancy of the pool.
+	pub fn report_status(&self, status: &PoolStatus) {
+		self.ready_transactions.set(status.ready as u64);
+		self.ready_bytes.set(status.ready_bytes as u64);
+		self.future_transactions.set(status.future as u64);
+		self.future_bytes.set(status.future_bytes as u64);
+	}
+
+	/// Report the occupancy of the pool by tag class.
+	///
+	/// The tag classes are the configured lanes, labelled with their hex encoded tag prefix, and
+	/// the class of the transactions that don't belong to any lane.
+	pub fn report_tag_status<'a>(
+		&self,
+		status: &PoolStatus,
+		lanes: impl IntoIterator<Item = (&'a Lane, PoolStatus)>,
+	) {
+		let mut other = PoolStatus {
+			ready: status.ready,
+			ready_bytes: status.ready_bytes,
+			future: status.future,
+			future_bytes: status.future_bytes,
+		};
+		for (lane, lane_status) in lanes {
+			let tag = HexDisplay::from(&lane.tag_prefix).to_string();
+			self.report_tag(&tag, &lane_status);
+			other.ready = other.ready.saturating_sub(lane_status.ready);
+			other.ready_bytes = other.ready_bytes.saturating_sub(lane_status.ready_bytes);
+			other.future = other.future.saturating_sub(lane_status.future);
+			other.future_bytes = other.future_bytes.saturating_sub(lane_status.future_bytes);
+		}
+		self.report_tag(OTHER_TAG_LABEL, &other);
+	}
+
+	fn report_tag(&self, tag: &str, status: &PoolStatus) {
+		self.tag_transactions.with_label_values(&[tag, "ready"]).set(status.ready as u64);
+		self.tag_transactions.with_label_values(&[tag, "future"]).set(status.future as u64);
+		self.tag_bytes.with_label_values(&[tag, "ready"]).set(status.ready_bytes as u64);
+		self.tag_bytes.with_label_values(&[tag, "future"]).set(status.future_bytes as u64);
+	}
 }
 
 /// Transaction pool api Prometheus metrics.
 pub struct ApiMetrics {
 	pub validations_scheduled: Counter<U64>,
 	pub validations_finished: Counter<U64>,
+	pub validation_time: Histogram,
+	pub validation_cache_hits: Counter<U64>,
+	pub validation_cache_misses: Counter<U64>,
 }
 
 impl ApiMetrics {
@@ -110,6 +282,31 @@ impl ApiMetrics {
 				)?,
 				registry,
 			)?,
+			validation_time: register(
+				Histogram::with_opts(HistogramOpts {
+					common_opts: Opts::new(
+						"sub_txpool_validation_time",
+						"Time taken to validate a transaction, in seconds",
+					),
+					buckets: exponential_buckets(0.0001, 2.0, 14)
+						.expect("parameters are always valid values; qed"),
+				})?,
+				registry,
+			)?,
+			validation_cache_hits: register(
+				Counter::new(
+					"sub_txpool_validation_cache_hits",
+					"Total number of transaction validations answered from the cache",
+				)?,
+				registry,
+			)?,
+			validation_cache_misses: register(
+				Counter::new(
+					"sub_txpool_validation_cache_misses",
+					"Total number of cacheable transaction validations not found in the cache",
+				)?,
+				registry,
+			)?,
 		})
 	}
 }
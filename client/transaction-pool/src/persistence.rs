@@ -0,0 +1,214 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Persistence of the transaction pool contents across restarts.
+//!
+//! The ready and future transactions are written to the auxiliary storage of the
+//! client when the node shuts down, see [`Persistence::persist`], and are resubmitted (and
+//! hence revalidated) when it starts again. They are removed from the auxiliary storage once
+//! resubmitted, so that they aren't resubmitted again after a crash.
+
+use std::sync::Arc;
+
+use codec::{Decode, Encode};
+use futures::prelude::*;
+use parking_lot::Mutex;
+use sc_client_api::backend::AuxStore;
+use sc_transaction_graph::{ChainApi, Pool};
+use sp_blockchain::HeaderBackend;
+use sp_core::traits::SpawnNamed;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use sp_transaction_pool::TransactionSource;
+
+use crate::BasicPool;
+
+const PERSISTED_TRANSACTIONS_KEY: &[u8] = b"txpool_persisted_transactions";
+
+/// Persists the contents of the pool, see [`enable`].
+pub struct Persistence<PoolApi: ChainApi, Client: AuxStore> {
+	guard: Arc<Mutex<Option<PersistOnDrop<PoolApi, Client>>>>,
+}
+
+impl<PoolApi: ChainApi, Client: AuxStore> Persistence<PoolApi, Client> {
+	/// Persist the contents of the pool now, e.g. once the node starts shutting down, instead
+	/// of when the task spawned by [`enable`] is dropped. Only the first call persists them.
+	pub fn persist(&self) {
+		drop(self.guard.lock().take());
+	}
+}
+
+/// Restore the transactions persisted by a previous run into the given pool and
+/// spawn a task that persists the pool contents when it's dropped, i.e. when the node shuts
+/// down, unless they are persisted earlier with the returned [`Persistence`].
+pub fn enable<PoolApi, Block, Client>(
+	pool: &BasicPool<PoolApi, Block>,
+	client: Arc<Client>,
+	spawner: impl SpawnNamed,
+) -> Persistence<PoolApi, Client>
+where
+	Block: BlockT,
+	PoolApi: ChainApi<Block = Block> + 'static,
+	Client: AuxStore + HeaderBackend<Block> + Send + Sync + 'static,
+{
+	let persisted = load::<_, Block>(&*client).unwrap_or_else(|e| {
+		log::warn!(target: "txpool", "Failed to load persisted transactions: {:?}", e);
+		Vec::new()
+	});
+
+	let guard = Arc::new(Mutex::new(Some(PersistOnDrop {
+		pool: pool.pool().clone(),
+		client: client.clone(),
+		restored: false,
+	})));
+	let persistence = Persistence { guard: guard.clone() };
+	let pool = pool.pool().clone();
+
+	let task = async move {
+		let at = BlockId::Hash(client.info().best_hash);
+
+		if !persisted.is_empty() {
+			let total = persisted.len();
+			let mut imported = 0;
+
+			let sources = [
+				TransactionSource::Local,
+				TransactionSource::External,
+				TransactionSource::InBlock,
+			];
+
+			for source in &sources {
+				let xts = persisted.iter()
+					.filter(|(s, _)| s == source)
+					.map(|(_, xt)| xt.clone())
+					.collect::<Vec<_>>();
+
+				if xts.is_empty() {
+					continue;
+				}
+
+				match pool.submit_at(&at, *source, xts).await {
+					Ok(results) => imported += results.iter().filter(|r| r.is_ok()).count(),
+					Err(e) => log::debug!(
+						target: "txpool",
+						"Failed to resubmit persisted transactions: {:?}",
+						e,
+					),
+				}
+			}
+
+			log::info!(
+				target: "txpool",
+				"Restored {} out of {} persisted transactions",
+				imported,
+				total,
+			);
+
+			if let Err(e) = clear(&*client) {
+				log::warn!(target: "txpool", "Failed to clear persisted transactions: {:?}", e);
+			}
+		}
+
+		if let Some(persist_on_drop) = guard.lock().as_mut() {
+			persist_on_drop.restored = true;
+		}
+
+		// keep the guard alive until the task is dropped on shutdown.
+		future::pending::<()>().await;
+		drop(guard);
+	};
+
+	spawner.spawn("txpool-persistence", task.boxed());
+	persistence
+}
+
+/// Writes the contents of the pool to the auxiliary storage when dropped.
+struct PersistOnDrop<PoolApi: ChainApi, Client: AuxStore> {
+	pool: Arc<Pool<PoolApi>>,
+	client: Arc<Client>,
+	/// Whether the previously persisted transactions were resubmitted. If they
+	/// weren't, they are kept as they are instead of being overwritten.
+	restored: bool,
+}
+
+impl<PoolApi: ChainApi, Client: AuxStore> Drop for PersistOnDrop<PoolApi, Client> {
+	fn drop(&mut self) {
+		if !self.restored {
+			return;
+		}
+
+		let validated_pool = self.pool.validated_pool();
+		let transactions = validated_pool.ready()
+			.chain(validated_pool.futures())
+			.map(|tx| (tx.source, tx.data.clone()))
+			.collect::<Vec<_>>();
+
+		match write(&*self.client, &transactions) {
+			Ok(()) => log::info!(
+				target: "txpool",
+				"Persisted {} transactions of the pool",
+				transactions.len(),
+			),
+			Err(e) => log::warn!(target: "txpool", "Failed to persist transactions: {:?}", e),
+		}
+	}
+}
+
+fn write<C: AuxStore, Ex: Encode>(
+	client: &C,
+	transactions: &[(TransactionSource, Ex)],
+) -> sp_blockchain::Result<()> {
+	client.insert_aux(&[(PERSISTED_TRANSACTIONS_KEY, transactions.encode().as_slice())], &[])
+}
+
+fn clear<C: AuxStore>(client: &C) -> sp_blockchain::Result<()> {
+	client.insert_aux(&[], &[PERSISTED_TRANSACTIONS_KEY])
+}
+
+fn load<C: AuxStore, Block: BlockT>(
+	client: &C,
+) -> sp_blockchain::Result<Vec<(TransactionSource, Block::Extrinsic)>> {
+	match client.get_aux(PERSISTED_TRANSACTIONS_KEY)? {
+		None => Ok(Vec::new()),
+		Some(encoded) => Decode::decode(&mut &encoded[..])
+			.map_err(|e| sp_blockchain::Error::Backend(
+				format!("Persisted transactions are corrupted: {}", e)
+			)),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use substrate_test_runtime_client::runtime::{Block, Extrinsic};
+
+	#[test]
+	fn write_read_clear_persisted_transactions() {
+		let client = substrate_test_runtime_client::new();
+		assert!(load::<_, Block>(&client).unwrap().is_empty());
+
+		let transactions = vec![
+			(TransactionSource::Local, Extrinsic::IncludeData(vec![1])),
+			(TransactionSource::External, Extrinsic::IncludeData(vec![2])),
+		];
+		write(&client, &transactions).unwrap();
+		assert_eq!(load::<_, Block>(&client).unwrap(), transactions);
+
+		clear(&client).unwrap();
+		assert!(load::<_, Block>(&client).unwrap().is_empty());
+	}
+}
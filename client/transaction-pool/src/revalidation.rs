@@ -20,22 +20,21 @@
 
 use std::{sync::Arc, pin::Pin, collections::{HashMap, HashSet, BTreeMap}};
 
-use sc_transaction_graph::{ChainApi, Pool, ExtrinsicHash, NumberFor, ValidatedTransaction};
+use sc_transaction_graph::{
+	ChainApi, Pool, ExtrinsicHash, NumberFor, RevalidationOptions, ValidatedTransaction,
+};
 use sp_runtime::traits::{Zero, SaturatedConversion};
 use sp_runtime::generic::BlockId;
 use sp_runtime::transaction_validity::TransactionValidityError;
 use sp_utils::mpsc::{tracing_unbounded, TracingUnboundedSender, TracingUnboundedReceiver};
 
 use futures::prelude::*;
+#[cfg(test)]
 use std::time::Duration;
 
-#[cfg(not(test))]
-const BACKGROUND_REVALIDATION_INTERVAL: Duration = Duration::from_millis(200);
 #[cfg(test)]
 pub const BACKGROUND_REVALIDATION_INTERVAL: Duration = Duration::from_millis(1);
 
-const MIN_BACKGROUND_REVALIDATION_BATCH_SIZE: usize = 20;
-
 /// Payload from queue to worker.
 struct WorkerPayload<Api: ChainApi> {
 	at: NumberFor<Api>,
@@ -51,6 +50,9 @@ struct RevalidationWorker<Api: ChainApi> {
 	best_block: NumberFor<Api>,
 	block_ordered: BTreeMap<NumberFor<Api>, HashSet<ExtrinsicHash<Api>>>,
 	members: HashMap<ExtrinsicHash<Api>, NumberFor<Api>>,
+	options: RevalidationOptions,
+	/// Number of blocks imported since the last batch.
+	blocks_since_batch: usize,
 }
 
 impl<Api: ChainApi> Unpin for RevalidationWorker<Api> {}
@@ -123,6 +125,7 @@ impl<Api: ChainApi> RevalidationWorker<Api> {
 	fn new(
 		api: Arc<Api>,
 		pool: Arc<Pool<Api>>,
+		options: RevalidationOptions,
 	) -> Self {
 		Self {
 			api,
@@ -130,12 +133,29 @@ impl<Api: ChainApi> RevalidationWorker<Api> {
 			block_ordered: Default::default(),
 			members: Default::default(),
 			best_block: Zero::zero(),
+			options,
+			blocks_since_batch: 0,
 		}
 	}
 
+	/// Number of transactions to revalidate in the next batch.
+	///
+	/// A quarter of the queue is revalidated at once, so that the queue doesn't lag behind.
+	/// If more than one block was imported since the last batch, the node is busy importing
+	/// blocks and the batch is shrunk accordingly.
+	fn batch_size(&self) -> usize {
+		let mut size = self.members.len() / 4;
+		if self.options.adaptive && self.blocks_since_batch > 1 {
+			size /= self.blocks_since_batch;
+		}
+
+		std::cmp::min(std::cmp::max(size, self.options.min_batch_size), self.options.max_batch_size)
+	}
+
 	fn prepare_batch(&mut self) -> Vec<ExtrinsicHash<Api>> {
 		let mut queued_exts = Vec::new();
-		let mut left = std::cmp::max(MIN_BACKGROUND_REVALIDATION_BATCH_SIZE, self.members.len() / 4);
+		let mut left = self.batch_size();
+		self.blocks_since_batch = 0;
 
 		// Take maximum of count transaction by order
 		// which they got into the pool
@@ -247,6 +267,7 @@ impl<Api: ChainApi> RevalidationWorker<Api> {
 					match workload {
 						Some(worker_payload) => {
 							this.best_block = worker_payload.at;
+							this.blocks_since_batch += 1;
 							this.push(worker_payload);
 
 							if this.members.len() > 0 {
@@ -297,10 +318,11 @@ where
 		api: Arc<Api>,
 		pool: Arc<Pool<Api>>,
 		interval: R,
+		options: RevalidationOptions,
 	) -> (Self, Pin<Box<dyn Future<Output=()> + Send>>) where R: Send + 'static, R::Guard: Send {
 		let (to_worker, from_queue) = tracing_unbounded("mpsc_revalidation_queue");
 
-		let worker = RevalidationWorker::new(api.clone(), pool.clone());
+		let worker = RevalidationWorker::new(api.clone(), pool.clone(), options);
 
 		let queue =
 			Self {
@@ -313,10 +335,13 @@ where
 	}
 
 	/// New revalidation queue with background worker.
-	pub fn new_background(api: Arc<Api>, pool: Arc<Pool<Api>>) ->
-		(Self, Pin<Box<dyn Future<Output=()> + Send>>)
-	{
-		Self::new_with_interval(api, pool, intervalier::Interval::new(BACKGROUND_REVALIDATION_INTERVAL))
+	pub fn new_background(
+		api: Arc<Api>,
+		pool: Arc<Pool<Api>>,
+		options: RevalidationOptions,
+	) -> (Self, Pin<Box<dyn Future<Output=()> + Send>>) {
+		let interval = intervalier::Interval::new(options.interval);
+		Self::new_with_interval(api, pool, interval, options)
 	}
 
 	/// New revalidation queue with background worker and test signal.
@@ -325,7 +350,12 @@ where
 		(Self, Pin<Box<dyn Future<Output=()> + Send>>, intervalier::BackSignalControl)
 	{
 		let (interval, notifier) = intervalier::BackSignalInterval::new(BACKGROUND_REVALIDATION_INTERVAL);
-		let (queue, background) = Self::new_with_interval(api, pool, interval);
+		let options = RevalidationOptions {
+			interval: BACKGROUND_REVALIDATION_INTERVAL,
+			adaptive: false,
+			..Default::default()
+		};
+		let (queue, background) = Self::new_with_interval(api, pool, interval, options);
 
 		(queue, background, notifier)
 	}
@@ -392,4 +422,27 @@ mod tests {
 		// number of ready
 		assert_eq!(pool.validated_pool().status().ready, 1);
 	}
+
+	#[test]
+	fn should_shrink_batches_under_import_pressure() {
+		let (api, pool) = setup();
+		let options = RevalidationOptions { min_batch_size: 1, ..Default::default() };
+		let mut worker = RevalidationWorker::new(api, Arc::new(pool), options);
+		worker.push(WorkerPayload {
+			at: 0,
+			transactions: (0..100).map(sp_core::H256::from_low_u64_be).collect(),
+		});
+
+		worker.blocks_since_batch = 1;
+		assert_eq!(worker.batch_size(), 25);
+
+		worker.blocks_since_batch = 5;
+		assert_eq!(worker.batch_size(), 5);
+
+		worker.options.adaptive = false;
+		assert_eq!(worker.batch_size(), 25);
+
+		worker.options.max_batch_size = 10;
+		assert_eq!(worker.batch_size(), 10);
+	}
 }
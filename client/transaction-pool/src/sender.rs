@@ -0,0 +1,70 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Senders of the signed FRAME extrinsics.
+
+use std::marker::PhantomData;
+
+use codec::{Compact, Decode, Encode};
+use sp_transaction_pool::SenderExtractor;
+
+/// The bit of the version byte of an extrinsic set if it is signed.
+const SIGNED_BIT: u8 = 0b1000_0000;
+
+/// Extracts the signers of FRAME's `UncheckedExtrinsic`s, encoded as `Address`.
+///
+/// The extrinsics are encoded with their length, followed by their version byte and, if they
+/// are signed, by the address of their signer. The unsigned extrinsics have no sender.
+pub struct SignedExtrinsicSender<Address>(PhantomData<fn() -> Address>);
+
+impl<Address> Default for SignedExtrinsicSender<Address> {
+	fn default() -> Self {
+		SignedExtrinsicSender(PhantomData)
+	}
+}
+
+impl<Address: Decode + Encode> SenderExtractor for SignedExtrinsicSender<Address> {
+	fn sender(&self, mut xt: &[u8]) -> Option<Vec<u8>> {
+		let _length = Compact::<u32>::decode(&mut xt).ok()?;
+		let version = u8::decode(&mut xt).ok()?;
+		if version & SIGNED_BIT == 0 {
+			return None;
+		}
+		Address::decode(&mut xt).ok().map(|address| address.encode())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn extracts_the_signer_of_signed_extrinsics() {
+		let extractor = SignedExtrinsicSender::<[u8; 4]>::default();
+		let extrinsic = |version: u8| {
+			let mut body = vec![version, 1, 2, 3, 4, 5, 6];
+			let mut xt = Compact(body.len() as u32).encode();
+			xt.append(&mut body);
+			xt
+		};
+
+		assert_eq!(extractor.sender(&extrinsic(0b1000_0100)), Some(vec![1, 2, 3, 4]));
+		assert_eq!(extractor.sender(&extrinsic(0b0000_0100)), None);
+		assert_eq!(extractor.sender(&[]), None);
+	}
+}
@@ -0,0 +1,113 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Cache of transaction validation results.
+//!
+//! The same transaction is usually received from many peers and hence validated
+//! many times against the same block. The results are cached by block and transaction,
+//! the least recently used ones being evicted once the cache is full.
+
+use std::hash::Hash;
+
+use lru::LruCache;
+use sp_runtime::transaction_validity::{TransactionSource, TransactionValidity};
+
+/// Maximum number of validation results kept in the cache.
+const MAX_CACHED_VALIDATIONS: usize = 4096;
+
+/// Validation results of transactions, keyed by the block they were validated at.
+pub struct ValidationCache<BlockHash: Hash + Eq, TxHash: Hash + Eq> {
+	results: LruCache<(BlockHash, TxHash), (TransactionSource, TransactionValidity)>,
+}
+
+impl<BlockHash: Hash + Eq, TxHash: Hash + Eq> Default for ValidationCache<BlockHash, TxHash> {
+	fn default() -> Self {
+		ValidationCache {
+			results: LruCache::new(MAX_CACHED_VALIDATIONS),
+		}
+	}
+}
+
+impl<BlockHash: Hash + Eq + Clone, TxHash: Hash + Eq> ValidationCache<BlockHash, TxHash> {
+	/// Get the cached validation result of the given transaction at the given block.
+	pub fn get(
+		&mut self,
+		at: &BlockHash,
+		source: TransactionSource,
+		hash: TxHash,
+	) -> Option<TransactionValidity> {
+		self.results.get(&(at.clone(), hash))
+			.filter(|(cached_source, _)| *cached_source == source)
+			.map(|(_, validity)| validity.clone())
+	}
+
+	/// Cache the validation result of the given transaction at the given block.
+	///
+	/// The least recently used result is evicted if the cache is full.
+	pub fn insert(
+		&mut self,
+		at: &BlockHash,
+		source: TransactionSource,
+		hash: TxHash,
+		validity: TransactionValidity,
+	) {
+		self.results.put((at.clone(), hash), (source, validity));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_runtime::transaction_validity::{InvalidTransaction, ValidTransaction};
+
+	#[test]
+	fn caches_results_by_block() {
+		let mut cache = ValidationCache::<u64, u64>::default();
+		let valid: TransactionValidity = Ok(ValidTransaction::default());
+		let invalid: TransactionValidity = Err(InvalidTransaction::Stale.into());
+
+		cache.insert(&1, TransactionSource::External, 10, valid.clone());
+		cache.insert(&1, TransactionSource::External, 11, invalid.clone());
+		assert_eq!(cache.get(&1, TransactionSource::External, 10), Some(valid.clone()));
+		assert_eq!(cache.get(&1, TransactionSource::External, 11), Some(invalid.clone()));
+		assert_eq!(cache.get(&1, TransactionSource::Local, 10), None);
+		assert_eq!(cache.get(&2, TransactionSource::External, 10), None);
+
+		// the results at other blocks are kept.
+		cache.insert(&2, TransactionSource::External, 10, invalid.clone());
+		assert_eq!(cache.get(&1, TransactionSource::External, 10), Some(valid));
+		assert_eq!(cache.get(&2, TransactionSource::External, 10), Some(invalid));
+	}
+
+	#[test]
+	fn evicts_the_least_recently_used_results() {
+		let mut cache = ValidationCache::<u64, u64>::default();
+		let valid: TransactionValidity = Ok(ValidTransaction::default());
+
+		for hash in 0..MAX_CACHED_VALIDATIONS as u64 {
+			cache.insert(&1, TransactionSource::External, hash, valid.clone());
+		}
+		// using the first result makes the second one the least recently used.
+		assert!(cache.get(&1, TransactionSource::External, 0).is_some());
+		cache.insert(&2, TransactionSource::External, 0, valid.clone());
+
+		assert!(cache.get(&1, TransactionSource::External, 0).is_some());
+		assert!(cache.get(&1, TransactionSource::External, 1).is_none());
+		assert!(cache.get(&2, TransactionSource::External, 0).is_some());
+	}
+}
@@ -20,15 +20,22 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use codec::Codec;
-use sp_runtime::traits::MaybeDisplay;
+use sp_runtime::{sp_std::vec::Vec, traits::MaybeDisplay};
 
 pub use pallet_transaction_payment::{FeeDetails, InclusionFee, RuntimeDispatchInfo};
 
 sp_api::decl_runtime_apis! {
+	#[api_version(2)]
 	pub trait TransactionPaymentApi<Balance> where
 		Balance: Codec + MaybeDisplay,
 	{
 		fn query_info(uxt: Block::Extrinsic, len: u32) -> RuntimeDispatchInfo<Balance>;
 		fn query_fee_details(uxt: Block::Extrinsic, len: u32) -> FeeDetails<Balance>;
+		/// Query the dispatch info of every given extrinsic and its encoded length.
+		///
+		/// Added in version 2.
+		fn query_info_batch(
+			uxts: Vec<(Block::Extrinsic, u32)>,
+		) -> Vec<RuntimeDispatchInfo<Balance>>;
 	}
 }
@@ -24,7 +24,7 @@ use sp_blockchain::HeaderBackend;
 use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
 use jsonrpc_derive::rpc;
 use sp_runtime::{generic::BlockId, traits::{Block as BlockT, MaybeDisplay}};
-use sp_api::ProvideRuntimeApi;
+use sp_api::{ApiError, ApiExt, ProvideRuntimeApi};
 use sp_core::Bytes;
 use sp_rpc::number::NumberOrHex;
 use pallet_transaction_payment_rpc_runtime_api::{FeeDetails, InclusionFee, RuntimeDispatchInfo};
@@ -45,6 +45,13 @@ pub trait TransactionPaymentApi<BlockHash, ResponseType> {
 		encoded_xt: Bytes,
 		at: Option<BlockHash>
 	) -> Result<FeeDetails<NumberOrHex>>;
+	/// Query the dispatch info of every given extrinsic with a single runtime call.
+	#[rpc(name = "payment_queryInfoBatch")]
+	fn query_info_batch(
+		&self,
+		encoded_xts: Vec<Bytes>,
+		at: Option<BlockHash>
+	) -> Result<Vec<ResponseType>>;
 }
 
 /// A struct that implements the [`TransactionPaymentApi`].
@@ -155,4 +162,155 @@ where
 			tip: Default::default(),
 		})
 	}
+
+	fn query_info_batch(
+		&self,
+		encoded_xts: Vec<Bytes>,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<RuntimeDispatchInfo<Balance>>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash
+		));
+
+		let uxts = encoded_xts.iter().enumerate().map(|(index, encoded_xt)| {
+			let encoded_len = encoded_xt.len() as u32;
+			let uxt: Block::Extrinsic = Decode::decode(&mut &**encoded_xt).map_err(|e| RpcError {
+				code: ErrorCode::ServerError(Error::DecodeError.into()),
+				message: "Unable to query dispatch info.".into(),
+				data: Some(format!("Extrinsic {}: {:?}", index, e).into()),
+			})?;
+			Ok((uxt, encoded_len))
+		}).collect::<Result<Vec<_>>>()?;
+
+		let runtime_error = |e: ApiError| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to query dispatch info.".into(),
+			data: Some(format!("{:?}", e).into()),
+		};
+		let batched = api.has_api_with::<dyn TransactionPaymentRuntimeApi<Block, Balance>, _>(
+			&at,
+			|version| version >= 2,
+		).map_err(runtime_error)?;
+
+		if batched {
+			api.query_info_batch(&at, uxts).map_err(runtime_error)
+		} else {
+			// runtimes before version 2 of the api are queried one extrinsic at a time.
+			uxts.into_iter()
+				.map(|(uxt, encoded_len)| api.query_info(&at, uxt, encoded_len))
+				.collect::<std::result::Result<Vec<_>, _>>()
+				.map_err(runtime_error)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+	use sp_runtime::traits::{NumberFor, Zero};
+	use substrate_test_runtime_client::runtime::{Block, Extrinsic};
+
+	struct TestClient;
+
+	impl ProvideRuntimeApi<Block> for TestClient {
+		type Api = RuntimeApi;
+
+		fn runtime_api<'a>(&'a self) -> sp_api::ApiRef<'a, Self::Api> {
+			RuntimeApi.into()
+		}
+	}
+
+	impl HeaderBackend<Block> for TestClient {
+		fn header(
+			&self,
+			_id: BlockId<Block>,
+		) -> sp_blockchain::Result<Option<<Block as BlockT>::Header>> {
+			Ok(None)
+		}
+
+		fn info(&self) -> sp_blockchain::Info<Block> {
+			sp_blockchain::Info {
+				best_hash: Default::default(),
+				best_number: Zero::zero(),
+				finalized_hash: Default::default(),
+				finalized_number: Zero::zero(),
+				genesis_hash: Default::default(),
+				number_leaves: Default::default(),
+			}
+		}
+
+		fn status(&self, _id: BlockId<Block>) -> sp_blockchain::Result<sp_blockchain::BlockStatus> {
+			Ok(sp_blockchain::BlockStatus::Unknown)
+		}
+
+		fn number(
+			&self,
+			_hash: <Block as BlockT>::Hash,
+		) -> sp_blockchain::Result<Option<NumberFor<Block>>> {
+			Ok(None)
+		}
+
+		fn hash(
+			&self,
+			_number: NumberFor<Block>,
+		) -> sp_blockchain::Result<Option<<Block as BlockT>::Hash>> {
+			Ok(None)
+		}
+	}
+
+	struct RuntimeApi;
+
+	// the fee of an extrinsic is its length when queried in a batch, and zero otherwise.
+	sp_api::mock_impl_runtime_apis! {
+		impl pallet_transaction_payment_rpc_runtime_api::TransactionPaymentApi<Block, u64>
+			for RuntimeApi
+		{
+			fn query_info(_uxt: Extrinsic, _len: u32) -> RuntimeDispatchInfo<u64> {
+				Default::default()
+			}
+
+			fn query_fee_details(_uxt: Extrinsic, _len: u32) -> FeeDetails<u64> {
+				unimplemented!()
+			}
+
+			fn query_info_batch(uxts: Vec<(Extrinsic, u32)>) -> Vec<RuntimeDispatchInfo<u64>> {
+				uxts.into_iter().map(|(_, len)| RuntimeDispatchInfo {
+					partial_fee: len.into(),
+					..Default::default()
+				}).collect()
+			}
+		}
+	}
+
+	fn api() -> TransactionPayment<TestClient, Block> {
+		TransactionPayment::new(Arc::new(TestClient))
+	}
+
+	#[test]
+	fn queries_the_info_of_a_batch_with_one_runtime_call() {
+		let xts = vec![
+			Extrinsic::IncludeData(vec![1]).encode().into(),
+			Extrinsic::IncludeData(vec![1; 100]).encode().into(),
+		];
+
+		let infos = api().query_info_batch(xts, None).unwrap();
+
+		assert_eq!(
+			infos.iter().map(|info| info.partial_fee).collect::<Vec<_>>(),
+			vec![3, 103],
+		);
+	}
+
+	#[test]
+	fn reports_the_index_of_an_undecodable_extrinsic() {
+		let xts = vec![Extrinsic::IncludeData(vec![1]).encode().into(), vec![0xff].into()];
+
+		let error = api().query_info_batch(xts, None).unwrap_err();
+
+		assert_eq!(error.code, ErrorCode::ServerError(Error::DecodeError.into()));
+		assert!(error.data.unwrap().as_str().unwrap().starts_with("Extrinsic 1: "));
+	}
 }
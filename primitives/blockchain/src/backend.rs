@@ -226,6 +226,12 @@ pub trait Backend<Block: BlockT>: HeaderBackend<Block> + HeaderMetadata<Block, E
 	fn have_extrinsic(&self, hash: &Block::Hash) -> Result<bool> {
 		Ok(self.extrinsic(hash)?.is_some())
 	}
+
+	/// Get the hash of the best chain block the extrinsic with the given hash is included in,
+	/// and its index in the block. Always `None` unless the backend indexes extrinsics.
+	fn extrinsic_location(&self, _hash: &Block::Hash) -> Result<Option<(Block::Hash, u32)>> {
+		Ok(None)
+	}
 }
 
 /// Provides access to the optional cache.
@@ -75,7 +75,7 @@ impl<B: BlockT, Transaction: Send + 'static> BasicQueue<B, Transaction> {
 				.ok()
 		});
 
-		let (future, justification_sender, block_import_sender) = BlockImportWorker::new(
+		let (worker, justification_sender, block_import_sender) = BlockImportWorker::new(
 			result_sender,
 			verifier,
 			block_import,
@@ -83,7 +83,10 @@ impl<B: BlockT, Transaction: Send + 'static> BasicQueue<B, Transaction> {
 			metrics,
 		);
 
-		spawner.spawn_essential_blocking("basic-block-import-worker", future.boxed());
+		spawner.spawn_essential_blocking_with_drain(
+			"basic-block-import-worker",
+			Box::new(worker),
+		);
 
 		Self {
 			justification_sender,
@@ -152,7 +155,8 @@ mod worker_messages {
 /// After each block is imported, this async function yields once to give other futures the possibility
 /// to be run.
 ///
-/// Returns when `block_import` ended.
+/// Returns when `block_import` ended, or once `drain` resolved, after importing the blocks
+/// being imported.
 async fn block_import_process<B: BlockT, Transaction: Send>(
 	mut block_import: BoxBlockImport<B, Transaction>,
 	mut verifier: impl Verifier<B>,
@@ -160,11 +164,18 @@ async fn block_import_process<B: BlockT, Transaction: Send>(
 	mut block_import_receiver: TracingUnboundedReceiver<worker_messages::ImportBlocks<B>>,
 	metrics: Option<Metrics>,
 	delay_between_blocks: Duration,
+	mut drain: future::BoxFuture<'static, ()>,
 ) {
 	loop {
-		let worker_messages::ImportBlocks(origin, blocks) = match block_import_receiver.next().await {
-			Some(blocks) => blocks,
-			None => {
+		// the drain signal is checked first, so no new blocks are taken once it resolved.
+		let next_blocks = future::select(&mut drain, block_import_receiver.next()).await;
+		let worker_messages::ImportBlocks(origin, blocks) = match next_blocks {
+			future::Either::Left(_) => {
+				log::debug!(target: "block-import", "Stopping block import to shut down");
+				return
+			},
+			future::Either::Right((Some(blocks), _)) => blocks,
+			future::Either::Right((None, _)) => {
 				log::debug!(
 					target: "block-import",
 					"Stopping block import because the import channel was closed!",
@@ -193,14 +204,14 @@ struct BlockImportWorker<B: BlockT> {
 }
 
 impl<B: BlockT> BlockImportWorker<B> {
-	fn new<V: 'static + Verifier<B>, Transaction: Send>(
+	fn new<V: 'static + Verifier<B>, Transaction: Send + 'static>(
 		result_sender: BufferedLinkSender<B>,
 		verifier: V,
 		block_import: BoxBlockImport<B, Transaction>,
 		justification_import: Option<BoxJustificationImport<B>>,
 		metrics: Option<Metrics>,
 	) -> (
-		impl Future<Output = ()> + Send,
+		impl FnOnce(future::BoxFuture<'static, ()>) -> future::BoxFuture<'static, ()> + Send,
 		TracingUnboundedSender<worker_messages::ImportJustification<B>>,
 		TracingUnboundedSender<worker_messages::ImportBlocks<B>>,
 	) {
@@ -227,7 +238,7 @@ impl<B: BlockT> BlockImportWorker<B> {
 
 		let delay_between_blocks = Duration::default();
 
-		let future = async move {
+		let future = move |drain: future::BoxFuture<'static, ()>| async move {
 			let block_import_process = block_import_process(
 				block_import,
 				verifier,
@@ -235,6 +246,7 @@ impl<B: BlockT> BlockImportWorker<B> {
 				block_import_port,
 				worker.metrics.clone(),
 				delay_between_blocks,
+				drain,
 			);
 			futures::pin_mut!(block_import_process);
 
@@ -271,7 +283,7 @@ impl<B: BlockT> BlockImportWorker<B> {
 				// All futures that we polled are now pending.
 				futures::pending!()
 			}
-		};
+		}.boxed();
 
 		(future, justification_sender, block_import_sender)
 	}
@@ -522,6 +534,7 @@ mod tests {
 
 		let (worker, mut finality_sender, mut block_import_sender) =
 			BlockImportWorker::new(result_sender, (), Box::new(()), Some(Box::new(())), None);
+		let worker = worker(future::pending().boxed());
 		futures::pin_mut!(worker);
 
 		let mut import_block = |n| {
@@ -609,4 +622,62 @@ mod tests {
 			]
 		);
 	}
+
+	#[test]
+	fn stops_taking_blocks_once_drained() {
+		let (result_sender, mut result_port) = buffered_link::buffered_link();
+		let (drain_signal, drain) = futures::channel::oneshot::channel::<()>();
+
+		let (worker, _finality_sender, mut block_import_sender) =
+			BlockImportWorker::new(result_sender, (), Box::new(()), Some(Box::new(())), None);
+		let worker = worker(drain.map(drop).boxed());
+		futures::pin_mut!(worker);
+
+		let mut import_block = |n| {
+			let header = Header {
+				parent_hash: Hash::random(),
+				number: n,
+				extrinsics_root: Hash::random(),
+				state_root: Default::default(),
+				digest: Default::default(),
+			};
+			let hash = header.hash();
+			block_on(block_import_sender.send(worker_messages::ImportBlocks(
+				BlockOrigin::Own,
+				vec![IncomingBlock {
+					hash,
+					header: Some(header),
+					body: None,
+					justification: None,
+					origin: None,
+					allow_missing_state: false,
+					import_existing: false,
+				}],
+			)))
+			.unwrap();
+			hash
+		};
+
+		let mut link = TestLink::default();
+		let block1 = import_block(1);
+		block_on(futures::future::poll_fn(|cx| {
+			while link.events.is_empty() {
+				if let Poll::Ready(()) = Future::poll(Pin::new(&mut worker), cx) {
+					panic!("import queue worker should not conclude.");
+				}
+				result_port.poll_actions(cx, &mut link).unwrap();
+			}
+			Poll::Ready(())
+		}));
+
+		// the blocks sent once drained aren't imported.
+		drain_signal.send(()).unwrap();
+		import_block(2);
+		block_on(&mut worker);
+		block_on(futures::future::poll_fn(|cx| {
+			result_port.poll_actions(cx, &mut link).unwrap();
+			Poll::Ready(())
+		}));
+		assert_eq!(link.events, vec![Event::BlockImported(block1)]);
+	}
 }
@@ -162,6 +162,38 @@ pub trait CallInWasm: Send + Sync {
 	) -> Result<Vec<u8>, String>;
 }
 
+/// Limits of the memory of the runtime calls, e.g. so the calls of the RPC clients can't exhaust
+/// the memory of the node.
+///
+/// The memory of a call is the memory the runtime declares and its heap pages, which it can't
+/// grow past.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryLimits {
+	/// The maximum number of 64KB heap pages of the calls, `None` for no limit.
+	///
+	/// The calls to a runtime requiring more heap pages, see `RuntimeCode::heap_pages`, fail
+	/// before it is instantiated, unless `cap_heap_pages` is set.
+	pub max_heap_pages: Option<u64>,
+	/// Whether the calls to a runtime requiring more heap pages run with `max_heap_pages`
+	/// instead, so only the calls allocating past them fail.
+	///
+	/// Only for the calls the node doesn't have to agree on with the other nodes, e.g. of the
+	/// RPC clients and the offchain workers.
+	pub cap_heap_pages: bool,
+}
+
+sp_externalities::decl_extension! {
+	/// The memory limits of the calls, registered with the externalities of the calls the
+	/// executor should limit.
+	pub struct MemoryLimitsExt(MemoryLimits);
+}
+
+sp_externalities::decl_extension! {
+	/// The message of the panic a runtime call is aborting with, registered when the runtime logs
+	/// it just before it traps, so the executor can report it.
+	pub struct PanicMessageExt(String);
+}
+
 sp_externalities::decl_extension! {
 	/// The call-in-wasm extension to register/retrieve from the externalities.
 	pub struct CallInWasmExt(Box<dyn CallInWasm>);
@@ -228,6 +260,13 @@ impl SpawnNamed for Box<dyn SpawnNamed> {
 	}
 }
 
+/// Creates a future given its drain signal, see
+/// [`SpawnEssentialNamed::spawn_essential_blocking_with_drain`].
+pub type DrainableFuture = Box<
+	dyn FnOnce(futures::future::BoxFuture<'static, ()>) -> futures::future::BoxFuture<'static, ()>
+		+ Send
+>;
+
 /// Something that can spawn essential tasks (blocking and non-blocking) with an assigned name.
 ///
 /// Essential tasks are special tasks that should take down the node when they end.
@@ -241,6 +280,19 @@ pub trait SpawnEssentialNamed: Clone + Send + Sync {
 	///
 	/// The given `name` is used to identify the future in tracing.
 	fn spawn_essential(&self, name: &'static str, future: futures::future::BoxFuture<'static, ()>);
+	/// Spawn the blocking future created by `future` with a drain signal: a future resolving
+	/// once the spawned future should stop taking new work and end, after finishing the work
+	/// in progress, e.g. when the node shuts down. It isn't stopped before it ends or the drain
+	/// times out.
+	///
+	/// By default the drain signal never resolves.
+	fn spawn_essential_blocking_with_drain(
+		&self,
+		name: &'static str,
+		future: DrainableFuture,
+	) {
+		self.spawn_essential_blocking(name, future(Box::pin(futures::future::pending())))
+	}
 }
 
 impl SpawnEssentialNamed for Box<dyn SpawnEssentialNamed> {
@@ -251,4 +303,12 @@ impl SpawnEssentialNamed for Box<dyn SpawnEssentialNamed> {
 	fn spawn_essential(&self, name: &'static str, future: futures::future::BoxFuture<'static, ()>) {
 		(**self).spawn_essential(name, future)
 	}
+
+	fn spawn_essential_blocking_with_drain(
+		&self,
+		name: &'static str,
+		future: DrainableFuture,
+	) {
+		(**self).spawn_essential_blocking_with_drain(name, future)
+	}
 }
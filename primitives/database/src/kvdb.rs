@@ -57,4 +57,8 @@ impl<D: KeyValueDB, H: Clone> Database<H> for DbAdapter<D> {
 	fn lookup(&self, _hash: &H) -> Option<Vec<u8>> {
 		unimplemented!();
 	}
+
+	fn iter(&self, col: ColumnId) -> Option<Box<dyn Iterator<Item = (Vec<u8>, Vec<u8>)> + '_>> {
+		Some(Box::new(self.0.iter(col).map(|(k, v)| (k.into_vec(), v.into_vec()))))
+	}
 }
@@ -171,6 +171,13 @@ pub trait Database<H: Clone>: Send + Sync {
 		t.release(hash.clone());
 		self.commit(t)
 	}
+
+	/// Iterate over the keys and values of `col`, in no particular order.
+	///
+	/// Returns `None` if the database can't be iterated over.
+	fn iter(&self, _col: ColumnId) -> Option<Box<dyn Iterator<Item = (Vec<u8>, Vec<u8>)> + '_>> {
+		None
+	}
 }
 
 impl<H> std::fmt::Debug for dyn Database<H> {
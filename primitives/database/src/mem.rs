@@ -52,6 +52,14 @@ impl<H> Database<H> for MemDb<H>
 		let s = self.0.read();
 		s.1.get(hash).cloned()
 	}
+
+	fn iter(&self, col: ColumnId) -> Option<Box<dyn Iterator<Item = (Vec<u8>, Vec<u8>)> + '_>> {
+		let s = self.0.read();
+		let entries = s.0.get(&col)
+			.map(|c| c.iter().map(|(k, v)| (k.clone(), v.clone())).collect::<Vec<_>>())
+			.unwrap_or_default();
+		Some(Box::new(entries.into_iter()))
+	}
 }
 
 impl<H> MemDb<H>
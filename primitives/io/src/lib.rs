@@ -38,7 +38,7 @@ use tracing;
 #[cfg(feature = "std")]
 use sp_core::{
 	crypto::Pair,
-	traits::{CallInWasmExt, TaskExecutorExt, RuntimeSpawnExt},
+	traits::{CallInWasmExt, TaskExecutorExt, RuntimeSpawnExt, PanicMessageExt},
 	offchain::{OffchainExt, TransactionPoolExt},
 	hexdisplay::HexDisplay,
 	storage::ChildInfo,
@@ -1070,7 +1070,16 @@ pub trait Logging {
 				log::Level::from(level),
 				"{}",
 				message,
-			)
+			);
+
+			// The panic handler of the runtime logs the message of the panic like this right
+			// before it traps, so the executor can report it in the error of the call.
+			if matches!(level, LogLevel::Error) && target == "runtime" {
+				sp_externalities::with_externalities(|mut ext| {
+					let _ = ext.deregister_extension::<PanicMessageExt>();
+					let _ = ext.register_extension(PanicMessageExt(message.into()));
+				});
+			}
 		}
 	}
 }
@@ -0,0 +1,68 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Types of the traces of the re-execution of blocks.
+
+use std::collections::BTreeMap;
+use serde::{Serialize, Deserialize};
+
+/// The spans and events recorded while executing a block.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockTrace<Hash> {
+	/// Hash of the traced block.
+	pub block_hash: Hash,
+	/// Hash of the parent of the traced block, whose state the block was executed on.
+	pub parent_hash: Hash,
+	/// The targets that were traced, e.g. `pallet,frame,state`.
+	pub tracing_targets: String,
+	/// The spans that were closed during the execution, in the order they were closed.
+	pub spans: Vec<Span>,
+	/// The events that were emitted during the execution, in order.
+	pub events: Vec<Event>,
+}
+
+/// A span recorded while executing a block.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Span {
+	/// Id of the span, unique during the execution.
+	pub id: u64,
+	/// Id of the span this span was entered in, if any.
+	pub parent_id: Option<u64>,
+	/// Name of the span.
+	pub name: String,
+	/// Target of the span, e.g. the pallet it was entered in.
+	pub target: String,
+	/// Whether the span was entered in the runtime, rather than on the host.
+	pub wasm: bool,
+	/// The fields recorded in the span.
+	pub values: BTreeMap<String, String>,
+}
+
+/// An event recorded while executing a block.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Event {
+	/// Target of the event, e.g. `state` for storage accesses.
+	pub target: String,
+	/// Id of the span the event was emitted in, if any.
+	pub parent_id: Option<u64>,
+	/// The fields of the event, e.g. its `message`.
+	pub values: BTreeMap<String, String>,
+}
@@ -48,6 +48,16 @@ sp_api::decl_runtime_apis! {
 		/// Returns the list of public raw public keys + key type.
 		fn decode_session_keys(encoded: Vec<u8>) -> Option<Vec<(Vec<u8>, KeyTypeId)>>;
 	}
+
+	/// Disabled validators runtime api.
+	pub trait DisabledValidators {
+		/// Returns the indices of the validators of the current session that have been
+		/// disabled, e.g. as a consequence of an offence.
+		///
+		/// The indices refer to the position of the validator in the current session's
+		/// validator set, which is also the order of the consensus authorities.
+		fn disabled_validators() -> Vec<u32>;
+	}
 }
 
 /// Number of validators in a given session.
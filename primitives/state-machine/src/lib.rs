@@ -140,7 +140,9 @@ mod changes_trie {
 
 #[cfg(feature = "std")]
 mod std_reexport {
-	pub use sp_trie::{trie_types::{Layout, TrieDBMut}, StorageProof, TrieMut, DBValue, MemoryDB};
+	pub use sp_trie::{
+		trie_types::{Layout, TrieDBMut}, StorageProof, CompactProof, TrieMut, DBValue, MemoryDB,
+	};
 	pub use crate::testing::TestExternalities;
 	pub use crate::basic::BasicExternalities;
 	pub use crate::read_only::{ReadOnlyExternalities, InspectState};
@@ -856,6 +858,92 @@ mod execution {
 		proving_backend.child_storage(child_info, key)
 			.map_err(|e| Box::new(e) as Box<dyn Error>)
 	}
+
+	/// Generate a proof of the key-value pairs of a trie following `start_at`, or from its
+	/// first key, e.g. to serve the state in chunks.
+	///
+	/// The pairs of the child trie are read if `child_info` is given, of the top trie otherwise.
+	/// They are read in key order until their values add up to `size_limit` bytes, so at least
+	/// one pair is read. Returns the pairs, whether the trie ends after them and the proof.
+	pub fn prove_range_read_with_size<B, H>(
+		mut backend: B,
+		child_info: Option<&ChildInfo>,
+		start_at: Option<&[u8]>,
+		size_limit: usize,
+	) -> Result<(Vec<(Vec<u8>, Vec<u8>)>, bool, StorageProof), Box<dyn Error>>
+	where
+		B: Backend<H>,
+		H: Hasher,
+		H::Out: Ord + Codec,
+	{
+		let trie_backend = backend.as_trie_backend()
+			.ok_or_else(|| Box::new(ExecutionError::UnableToGenerateProof) as Box<dyn Error>)?;
+		let proving_backend = proving_backend::ProvingBackend::<_, H>::new(trie_backend);
+		let (entries, complete) =
+			read_range_with_size(&proving_backend, child_info, start_at, size_limit)?;
+		Ok((entries, complete, proving_backend.extract_proof()))
+	}
+
+	/// Check a proof generated by `prove_range_read_with_size` with the same arguments.
+	///
+	/// Returns the key-value pairs and whether the trie ends after them.
+	pub fn read_range_proof_check<H>(
+		root: H::Out,
+		proof: StorageProof,
+		child_info: Option<&ChildInfo>,
+		start_at: Option<&[u8]>,
+		size_limit: usize,
+	) -> Result<(Vec<(Vec<u8>, Vec<u8>)>, bool), Box<dyn Error>>
+	where
+		H: Hasher,
+		H::Out: Ord + Codec,
+	{
+		let proving_backend = create_proof_check_backend::<H>(root, proof)?;
+		read_range_with_size(&proving_backend, child_info, start_at, size_limit)
+	}
+
+	fn read_range_with_size<B, H>(
+		backend: &B,
+		child_info: Option<&ChildInfo>,
+		start_at: Option<&[u8]>,
+		size_limit: usize,
+	) -> Result<(Vec<(Vec<u8>, Vec<u8>)>, bool), Box<dyn Error>>
+	where
+		B: Backend<H>,
+		H: Hasher,
+	{
+		let to_error = |e: B::Error| Box::new(e) as Box<dyn Error>;
+		let next_key = |key: &[u8]| match child_info {
+			Some(child_info) => backend.next_child_storage_key(child_info, key),
+			None => backend.next_storage_key(key),
+		}.map_err(to_error);
+		let read_value = |key: &[u8]| match child_info {
+			Some(child_info) => backend.child_storage(child_info, key),
+			None => backend.storage(key),
+		}.map_err(to_error);
+
+		let mut key = match start_at {
+			Some(start_at) => next_key(start_at)?,
+			// the empty key comes first, if it's set.
+			None if read_value(&[][..])?.is_some() => Some(Vec::new()),
+			None => next_key(&[][..])?,
+		};
+		let mut entries = Vec::new();
+		let mut size = 0;
+		while let Some(current) = key {
+			let value = read_value(&current)?
+				.ok_or_else(|| Box::new(ExecutionError::InvalidProof) as Box<dyn Error>)?;
+			size += value.len();
+			// the following key is read even once the chunk is full, to prove whether the
+			// trie ends.
+			key = next_key(&current)?;
+			entries.push((current, value));
+			if size >= size_limit {
+				break;
+			}
+		}
+		Ok((entries, key.is_none()))
+	}
 }
 
 #[cfg(test)]
@@ -1458,6 +1546,78 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn compact_proof_check_works() {
+		let child_info = ChildInfo::new_default(b"sub1");
+		let remote_backend = trie_backend::tests::test_trie();
+		let remote_root = remote_backend.storage_root(::std::iter::empty()).0;
+		let remote_proof = prove_child_read(remote_backend, &child_info, &[b"value3"]).unwrap();
+		let proof_size = remote_proof.clone().iter_nodes().map(|node| node.len()).sum::<usize>();
+
+		let compact_proof = remote_proof.into_compact_proof::<BlakeTwo256>(remote_root).unwrap();
+		assert!(compact_proof.encoded_size() < proof_size);
+		assert!(compact_proof.to_storage_proof::<BlakeTwo256>(Some(&Default::default())).is_err());
+
+		let (proof, root) = compact_proof.to_storage_proof::<BlakeTwo256>(Some(&remote_root))
+			.unwrap();
+		assert_eq!(root, remote_root);
+		let local_result = read_child_proof_check::<BlakeTwo256, _>(
+			remote_root,
+			proof,
+			&child_info,
+			&[b"value3"],
+		).unwrap();
+		assert_eq!(
+			local_result.into_iter().collect::<Vec<_>>(),
+			vec![(b"value3".to_vec(), Some(vec![142]))],
+		);
+	}
+
+	#[test]
+	fn range_proof_check_works() {
+		let remote_backend = trie_backend::tests::test_trie();
+		let remote_root = remote_backend.storage_root(::std::iter::empty()).0;
+		let mut pairs = remote_backend.pairs();
+		pairs.sort();
+
+		let mut read = Vec::new();
+		let mut start_at = None;
+		loop {
+			let (entries, complete, proof) = prove_range_read_with_size(
+				trie_backend::tests::test_trie(),
+				None,
+				start_at.as_deref(),
+				10,
+			).unwrap();
+			let checked = read_range_proof_check::<BlakeTwo256>(
+				remote_root,
+				proof,
+				None,
+				start_at.as_deref(),
+				10,
+			).unwrap();
+			assert_eq!(checked, (entries.clone(), complete));
+			assert!(!entries.is_empty());
+
+			start_at = entries.last().map(|(key, _)| key.clone());
+			read.extend(entries);
+			if complete {
+				break;
+			}
+		}
+		assert_eq!(read, pairs);
+
+		let child_info = ChildInfo::new_default(b"sub1");
+		let (entries, complete, proof) =
+			prove_range_read_with_size(remote_backend, Some(&child_info), None, 1).unwrap();
+		assert_eq!((entries.clone(), complete), (vec![(b"value3".to_vec(), vec![142])], false));
+		assert_eq!(
+			read_range_proof_check::<BlakeTwo256>(remote_root, proof, Some(&child_info), None, 1)
+				.unwrap(),
+			(entries, complete),
+		);
+	}
+
 	#[test]
 	fn child_storage_uuid() {
 
@@ -69,6 +69,9 @@ pub enum Error {
 
 	#[error("The pool is not accepting future transactions")]
 	RejectedFutureTransaction,
+
+	#[error("Transaction was rejected by the admission filter of the pool")]
+	NotAdmitted,
 }
 
 /// Transaction pool error conversion.
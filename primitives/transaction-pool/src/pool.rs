@@ -22,12 +22,14 @@ use std::{
 	hash::Hash,
 	sync::Arc,
 	pin::Pin,
+	time::Duration,
 };
 use futures::{Future, Stream};
 use serde::{Deserialize, Serialize};
 use sp_runtime::{
 	generic::BlockId,
 	traits::{Block as BlockT, Member, NumberFor},
+	ApplyExtrinsicResult,
 	transaction_validity::{
 		TransactionLongevity, TransactionPriority, TransactionTag, TransactionSource,
 	},
@@ -53,6 +55,18 @@ impl PoolStatus {
 	}
 }
 
+/// The outcome of applying a transaction in a block built on top of another one without
+/// importing it into the pool, see [`TransactionPool::submit_and_simulate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionSimulation<Hash> {
+	/// Hash of the transaction.
+	pub hash: Hash,
+	/// The result of applying the transaction.
+	pub result: ApplyExtrinsicResult,
+	/// SCALE encoded events deposited in the block, as stored under `System::Events`.
+	pub events: Vec<u8>,
+}
+
 /// Possible transaction status events.
 ///
 /// This events are being emitted by `TransactionPool` watchers,
@@ -126,12 +140,53 @@ pub enum TransactionStatus<Hash, BlockHash> {
 	Invalid,
 }
 
+/// Pool-wide events about transactions leaving the pool.
+///
+/// Unlike the [`TransactionStatus`] events, which are only emitted to the watchers
+/// of a particular transaction, these events are emitted for all transactions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PoolEvent<Hash, BlockHash> {
+	/// Transaction has been dropped from the pool for the given reason.
+	Dropped(Hash, DroppedReason<Hash>),
+	/// Transaction is no longer valid in the current state and has been removed
+	/// from the pool. Invalid transactions are temporarily banned.
+	Invalid(Hash),
+	/// Transaction has been removed from the pool and banned on request.
+	Banned(Hash),
+	/// Transaction has been included in block with given hash and removed from the pool.
+	Pruned(Hash, BlockHash),
+}
+
+/// The reason a transaction has been dropped from the pool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DroppedReason<Hash> {
+	/// The pool limits have been exceeded. Such transactions are temporarily banned.
+	LimitsExceeded,
+	/// Transaction has been replaced by the given transaction, that provides the
+	/// same tags.
+	Usurped(Hash),
+	/// Transaction has been waiting in the future queue for longer than allowed.
+	/// Such transactions are temporarily banned.
+	Expired,
+	/// Transaction has been waiting in the future queue of a pool that rejects future
+	/// transactions.
+	FutureRejected,
+	/// Transaction could not be moved from the future to the ready queue once its requirements
+	/// were satisfied, e.g. because it conflicts with a ready transaction.
+	PromotionFailed,
+}
+
 /// The stream of transaction events.
 pub type TransactionStatusStream<Hash, BlockHash> = dyn Stream<Item=TransactionStatus<Hash, BlockHash>> + Send + Unpin;
 
 /// The import notification event stream.
 pub type ImportNotificationStream<H> = futures::channel::mpsc::Receiver<H>;
 
+/// The pool-wide event stream.
+pub type PoolEventStream<H, BH> = futures::channel::mpsc::Receiver<PoolEvent<H, BH>>;
+
 /// Transaction hash type for a pool.
 pub type TxHash<P> = <P as TransactionPool>::Hash;
 /// Block hash type for a pool.
@@ -170,6 +225,9 @@ pub trait InPoolTransaction {
 	fn provides(&self) -> &[TransactionTag];
 	/// Return a flag indicating if the transaction should be propagated to other peers.
 	fn is_propagable(&self) -> bool;
+	/// Return a flag indicating if the transaction was submitted locally, e.g. by an offchain
+	/// worker or over RPC.
+	fn is_local(&self) -> bool;
 }
 
 /// Transaction pool interface.
@@ -204,6 +262,15 @@ pub trait TransactionPool: Send + Sync {
 		xt: TransactionFor<Self>,
 	) -> PoolFuture<TxHash<Self>, Self::Error>;
 
+	/// Returns a future that imports one unverified transaction received from the network
+	/// peer with the given id to the pool.
+	fn submit_from_peer(
+		&self,
+		at: &BlockId<Self::Block>,
+		peer: String,
+		xt: TransactionFor<Self>,
+	) -> PoolFuture<TxHash<Self>, Self::Error>;
+
 	/// Returns a future that import a single transaction and starts to watch their progress in the pool.
 	fn submit_and_watch(
 		&self,
@@ -212,6 +279,14 @@ pub trait TransactionPool: Send + Sync {
 		xt: TransactionFor<Self>,
 	) -> PoolFuture<Box<TransactionStatusStreamFor<Self>>, Self::Error>;
 
+	/// Returns a future that applies a single unverified transaction in a block initialized on
+	/// top of the given one, without importing it to the pool.
+	fn submit_and_simulate(
+		&self,
+		at: &BlockId<Self::Block>,
+		xt: TransactionFor<Self>,
+	) -> PoolFuture<TransactionSimulation<TxHash<Self>>, Self::Error>;
+
 	// *** Block production / Networking
 	/// Get an iterator for ready transactions ordered by priority.
 	///
@@ -223,10 +298,29 @@ pub trait TransactionPool: Send + Sync {
 	/// Get an iterator for ready transactions ordered by priority.
 	fn ready(&self) -> Box<dyn Iterator<Item=Arc<Self::InPoolTransaction>> + Send>;
 
+	// *** RPC
+	/// Get all transactions in the future queue, i.e. waiting for some of their tags to be
+	/// provided.
+	fn futures(&self) -> Vec<Arc<Self::InPoolTransaction>>;
+
 	// *** Block production
 	/// Remove transactions identified by given hashes (and dependent transactions) from the pool.
 	fn remove_invalid(&self, hashes: &[TxHash<Self>]) -> Vec<Arc<Self::InPoolTransaction>>;
 
+	// *** RPC
+	/// Remove transactions identified by given hashes (and dependent transactions) from the pool
+	/// and ban them for the given duration, or the default ban time of the pool if `None`.
+	fn ban(
+		&self,
+		hashes: &[TxHash<Self>],
+		duration: Option<Duration>,
+	) -> Vec<Arc<Self::InPoolTransaction>>;
+
+	/// Lift the ban of the transaction identified by given hash.
+	///
+	/// Returns `true` if the transaction was banned.
+	fn unban(&self, hash: &TxHash<Self>) -> bool;
+
 	// *** logging
 	/// Returns pool status.
 	fn status(&self) -> PoolStatus;
@@ -235,6 +329,9 @@ pub trait TransactionPool: Send + Sync {
 	/// Return an event stream of transactions imported to the pool.
 	fn import_notification_stream(&self) -> ImportNotificationStream<TxHash<Self>>;
 
+	/// Return a stream of events about transactions leaving the pool.
+	fn pool_event_stream(&self) -> PoolEventStream<TxHash<Self>, BlockHash<Self>>;
+
 	// *** networking
 	/// Notify the pool about transactions broadcast.
 	fn on_broadcasted(&self, propagations: HashMap<TxHash<Self>, Vec<String>>);
@@ -246,6 +343,36 @@ pub trait TransactionPool: Send + Sync {
 	fn ready_transaction(&self, hash: &TxHash<Self>) -> Option<Arc<Self::InPoolTransaction>>;
 }
 
+/// A hook consulted on every submission to the pool, allowing to implement custom
+/// admission policies, e.g. allowlists or call filters for public RPC endpoints.
+pub trait AdmissionFilter: Send + Sync {
+	/// Returns `true` if the given SCALE encoded transaction may enter the pool.
+	///
+	/// `peer` is the id of the network peer the transaction was received from, if any.
+	/// External transactions without a peer were submitted over RPC.
+	fn admit(&self, xt: &[u8], source: TransactionSource, peer: Option<&str>) -> bool;
+}
+
+impl std::fmt::Debug for dyn AdmissionFilter {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "AdmissionFilter")
+	}
+}
+
+/// Extracts the senders of the transactions submitted to the pool, so that the transactions
+/// of every sender can be limited.
+pub trait SenderExtractor: Send + Sync {
+	/// Returns the encoded sender of the given SCALE encoded transaction, `None` if it has no
+	/// sender, e.g. if it is unsigned.
+	fn sender(&self, xt: &[u8]) -> Option<Vec<u8>>;
+}
+
+impl std::fmt::Debug for dyn SenderExtractor {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "SenderExtractor")
+	}
+}
+
 /// Events that the transaction pool listens for.
 pub enum ChainEvent<B: BlockT> {
 	/// New best block have been added to the chain
@@ -18,6 +18,7 @@
 use sp_std::vec::Vec;
 use codec::{Encode, Decode};
 use hash_db::{Hasher, HashDB};
+use crate::Layout;
 
 /// A proof that some set of key-value pairs are included in the storage trie. The proof contains
 /// the storage values so that the partial storage backend can be reconstructed by a verifier that
@@ -75,6 +76,51 @@ impl StorageProof {
 
 		Self { trie_nodes }
 	}
+
+	/// Encode as a compact proof of the state with the given root.
+	pub fn into_compact_proof<H: Hasher>(
+		self,
+		root: H::Out,
+	) -> Result<CompactProof, crate::CompactProofError<H::Out, crate::Error>> {
+		crate::encode_compact::<Layout<H>>(self, root)
+	}
+}
+
+/// A storage proof in compact form.
+///
+/// The nodes don't contain the hashes of their children, which are recomputed when the proof
+/// is decoded. See `StorageProof` for the meaning of the proof.
+#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+pub struct CompactProof {
+	/// The compact encoded trie nodes.
+	pub encoded_nodes: Vec<Vec<u8>>,
+}
+
+impl CompactProof {
+	/// Returns the total size of the encoded nodes.
+	pub fn encoded_size(&self) -> usize {
+		self.encoded_nodes.iter().map(|node| node.len()).sum()
+	}
+
+	/// Decode into a `StorageProof`, returning the root of the proved state.
+	///
+	/// Fails if the root isn't `expected_root`, if given.
+	pub fn to_storage_proof<H: Hasher>(
+		&self,
+		expected_root: Option<&H::Out>,
+	) -> Result<(StorageProof, H::Out), crate::CompactProofError<H::Out, crate::Error>> {
+		let mut db = crate::MemoryDB::<H>::default();
+		let root = crate::decode_compact::<Layout<H>, _>(
+			&mut db,
+			&self.encoded_nodes,
+			expected_root,
+		)?;
+		let trie_nodes = db.drain()
+			.into_iter()
+			.filter_map(|(_, (node, references))| if references > 0 { Some(node) } else { None })
+			.collect();
+		Ok((StorageProof::new(trie_nodes), root))
+	}
 }
 
 /// An iterator over trie nodes constructed from a storage proof. The nodes are not guaranteed to
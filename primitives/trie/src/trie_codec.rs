@@ -0,0 +1,177 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Compact proof support.
+//!
+//! A compact proof omits the hashes of the nodes of the proof from their parent nodes, since
+//! the verifier recomputes them from the nodes themselves. The child tries read by the proof
+//! are encoded after the top trie, in the order of their roots in the top trie.
+
+use crate::{
+	EMPTY_PREFIX, HashDBT, TrieHash, TrieConfiguration, CError, CompactProof, StorageProof,
+};
+use sp_std::{boxed::Box, vec::Vec};
+use trie_db::{DBValue, Trie};
+use hash_db::HashDBRef;
+#[cfg(feature = "std")]
+use std::fmt;
+
+/// Error for the compaction and the decompaction of proofs.
+#[derive(Debug)]
+pub enum Error<H, CodecError> {
+	/// The root of the decoded proof doesn't match the expected one.
+	RootMismatch(H, H),
+	/// The proof misses the root node.
+	IncompleteProof,
+	/// The compact proof has nodes left once all its tries are decoded.
+	ExtraneousChildNode,
+	/// The compact proof has a child trie whose root isn't referenced by the top trie.
+	ExtraneousChildProof(H),
+	/// A child trie root in the top trie isn't a hash.
+	InvalidChildRoot(Vec<u8>, Vec<u8>),
+	/// Error of the trie crate.
+	TrieError(Box<trie_db::TrieError<H, CodecError>>),
+}
+
+impl<H, CodecError> From<Box<trie_db::TrieError<H, CodecError>>> for Error<H, CodecError> {
+	fn from(error: Box<trie_db::TrieError<H, CodecError>>) -> Self {
+		Error::TrieError(error)
+	}
+}
+
+#[cfg(feature = "std")]
+impl<H: fmt::Debug, CodecError: fmt::Debug> fmt::Display for Error<H, CodecError> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Error::RootMismatch(root, expected) =>
+				write!(f, "Root {:?} does not match the expected root {:?}", root, expected),
+			Error::IncompleteProof => write!(f, "Proof is missing its root node"),
+			Error::ExtraneousChildNode => write!(f, "Proof has extraneous nodes"),
+			Error::ExtraneousChildProof(root) =>
+				write!(f, "Proof has an extraneous child trie with root {:?}", root),
+			Error::InvalidChildRoot(key, root) =>
+				write!(f, "Child trie root {:?} at {:?} is not a hash", root, key),
+			Error::TrieError(e) => write!(f, "Trie error: {:?}", e),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl<H: fmt::Debug, CodecError: fmt::Debug> std::error::Error for Error<H, CodecError> {}
+
+/// Returns the roots of the child tries in the top trie of the given database.
+///
+/// The nodes missing from the database are skipped, the proof only contains the nodes that
+/// were read.
+fn child_trie_roots<L: TrieConfiguration>(
+	trie: &crate::TrieDB<L>,
+) -> Result<Vec<TrieHash<L>>, Error<TrieHash<L>, CError<L>>> {
+	let prefix = sp_core::storage::well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX;
+	let mut roots = Vec::new();
+	let mut iter = trie.iter()?;
+	if iter.seek(prefix).is_ok() {
+		loop {
+			match iter.next() {
+				Some(Ok((key, value))) if key.starts_with(prefix) => {
+					let mut root = TrieHash::<L>::default();
+					if root.as_mut().len() != value.len() {
+						return Err(Error::InvalidChildRoot(key, value));
+					}
+					root.as_mut().copy_from_slice(&value[..]);
+					roots.push(root);
+				},
+				Some(Err(error)) => match *error {
+					trie_db::TrieError::IncompleteDatabase(..) => (),
+					error => return Err(Box::new(error).into()),
+				},
+				_ => break,
+			}
+		}
+	}
+	Ok(roots)
+}
+
+/// Compact the given proof of the state with the given root.
+pub fn encode_compact<L: TrieConfiguration>(
+	proof: StorageProof,
+	root: TrieHash<L>,
+) -> Result<CompactProof, Error<TrieHash<L>, CError<L>>> {
+	let db = proof.into_memory_db::<L::Hash>();
+	let (mut encoded_nodes, child_roots) = {
+		let trie = crate::TrieDB::<L>::new(&db, &root)?;
+		(trie_db::encode_compact::<L>(&trie)?, child_trie_roots::<L>(&trie)?)
+	};
+
+	for child_root in child_roots {
+		// the proof doesn't contain the child tries that weren't read.
+		if !HashDBT::<L::Hash, _>::contains(&db, &child_root, EMPTY_PREFIX) {
+			continue;
+		}
+		let trie = crate::TrieDB::<L>::new(&db, &child_root)?;
+		encoded_nodes.extend(trie_db::encode_compact::<L>(&trie)?);
+	}
+
+	Ok(CompactProof { encoded_nodes })
+}
+
+/// Decode the given compact proof into the database and return its root.
+///
+/// Fails if the root isn't the expected one, if given.
+pub fn decode_compact<L, DB>(
+	db: &mut DB,
+	encoded: &[Vec<u8>],
+	expected_root: Option<&TrieHash<L>>,
+) -> Result<TrieHash<L>, Error<TrieHash<L>, CError<L>>> where
+	L: TrieConfiguration,
+	DB: HashDBT<L::Hash, DBValue> + HashDBRef<L::Hash, DBValue>,
+{
+	let (root, mut decoded) = trie_db::decode_compact::<L, _, _>(db, encoded)?;
+	if let Some(expected_root) = expected_root {
+		if expected_root != &root {
+			return Err(Error::RootMismatch(root, *expected_root));
+		}
+	}
+	if !HashDBT::<L::Hash, _>::contains(db, &root, EMPTY_PREFIX) {
+		return Err(Error::IncompleteProof);
+	}
+
+	let child_roots = {
+		let trie = crate::TrieDB::<L>::new(&*db, &root)?;
+		child_trie_roots::<L>(&trie)?
+	};
+	// the child tries are in the order of their roots, which may not all be in the proof.
+	let mut child_root = None;
+	for expected_root in child_roots {
+		if child_root.is_none() && decoded < encoded.len() {
+			let (root, nodes) = trie_db::decode_compact::<L, _, _>(db, &encoded[decoded..])?;
+			decoded += nodes;
+			child_root = Some(root);
+		}
+		if child_root == Some(expected_root) {
+			child_root = None;
+		}
+	}
+	if let Some(child_root) = child_root {
+		return Err(Error::ExtraneousChildProof(child_root));
+	}
+	if decoded < encoded.len() {
+		return Err(Error::ExtraneousChildNode);
+	}
+
+	Ok(root)
+}
@@ -47,6 +47,28 @@ macro_rules! create_apis_vec {
 	( $y:expr ) => { $crate::sp_std::borrow::Cow::Borrowed(& $y) }
 }
 
+/// The name of the custom section of the wasm binary of a runtime advertising the number of
+/// 64KB heap pages the runtime requires, as a little endian `u64`.
+pub const HEAP_PAGES_SECTION: &str = "heap_pages";
+
+/// Advertise the number of 64KB heap pages the runtime requires in the `HEAP_PAGES_SECTION` of
+/// its wasm binary.
+///
+/// The executor allocates these heap pages to the runtime, unless the chain overrides them in
+/// the `:heappages` storage, instead of the default heap pages of the node.
+///
+/// ```
+/// sp_version::runtime_heap_pages!(2048);
+/// ```
+#[macro_export]
+macro_rules! runtime_heap_pages {
+	( $heap_pages:expr ) => {
+		#[cfg(not(feature = "std"))]
+		#[link_section = "heap_pages"]
+		static RUNTIME_HEAP_PAGES: [u8; 8] = ($heap_pages as u64).to_le_bytes();
+	}
+}
+
 /// Runtime version.
 /// This should not be thought of as classic Semver (major/minor/tiny).
 /// This triplet have different semantics and mis-interpretation could cause problems.
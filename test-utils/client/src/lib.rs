@@ -163,6 +163,7 @@ impl<Block: BlockT, Executor, Backend, G: GenesisInit> TestClientBuilder<Block,
 			block_construction: execution_strategy,
 			offchain_worker: execution_strategy,
 			other: execution_strategy,
+			methods: Default::default(),
 		};
 		self
 	}
@@ -832,6 +832,12 @@ cfg_if! {
 				}
 			}
 
+			impl sp_session::DisabledValidators<Block> for Runtime {
+				fn disabled_validators() -> Vec<u32> {
+					Vec::new()
+				}
+			}
+
 			impl sp_finality_grandpa::GrandpaApi<Block> for Runtime {
 				fn grandpa_authorities() -> sp_finality_grandpa::AuthorityList {
 					Vec::new()
@@ -1090,6 +1096,12 @@ cfg_if! {
 				}
 			}
 
+			impl sp_session::DisabledValidators<Block> for Runtime {
+				fn disabled_validators() -> Vec<u32> {
+					Vec::new()
+				}
+			}
+
 			impl frame_system_rpc_runtime_api::AccountNonceApi<Block, AccountId, Index> for Runtime {
 				fn account_nonce(_account: AccountId) -> Index {
 					0
@@ -231,6 +231,9 @@ impl sc_transaction_graph::ChainApi for TestApi {
 	type Error = Error;
 	type ValidationFuture = futures::future::Ready<Result<TransactionValidity, Error>>;
 	type BodyFuture = futures::future::Ready<Result<Option<Vec<Extrinsic>>, Error>>;
+	type SimulationFuture = futures::future::Ready<
+		Result<sc_transaction_graph::SimulationOutcome, Error>
+	>;
 
 	fn validate_transaction(
 		&self,
@@ -297,6 +300,28 @@ impl sc_transaction_graph::ChainApi for TestApi {
 		ready(Ok(Ok(validity)))
 	}
 
+	fn simulate_transaction(
+		&self,
+		_at: &BlockId<Self::Block>,
+		uxt: sc_transaction_graph::ExtrinsicFor<Self>,
+	) -> Self::SimulationFuture {
+		if self.chain.read().invalid_hashes.contains(&self.hash_and_length(&uxt).0) {
+			return ready(Ok((Err(InvalidTransaction::Custom(0).into()), Vec::new())))
+		}
+
+		if let Some(transfer) = uxt.try_transfer() {
+			let chain_nonce = self.chain.read().nonces.get(&transfer.from).cloned().unwrap_or(0);
+			if transfer.nonce < chain_nonce {
+				return ready(Ok((Err(InvalidTransaction::Stale.into()), Vec::new())))
+			}
+			if transfer.nonce > chain_nonce {
+				return ready(Ok((Err(InvalidTransaction::Future.into()), Vec::new())))
+			}
+		}
+
+		ready(Ok((Ok(Ok(())), Vec::new())))
+	}
+
 	fn block_id_to_number(
 		&self,
 		at: &BlockId<Self::Block>,
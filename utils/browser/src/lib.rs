@@ -96,33 +96,61 @@ where
 		dev_key_seed: Default::default(),
 		disable_grandpa: Default::default(),
 		execution_strategies: Default::default(),
+		execution_memory_limits: Default::default(),
 		force_authoring: Default::default(),
 		impl_name: String::from("parity-substrate"),
 		impl_version: String::from("0.0.0"),
 		offchain_worker: Default::default(),
 		prometheus_config: Default::default(),
+		health_endpoint: None,
+		disk_space: None,
 		state_pruning: Default::default(),
 		keep_blocks: KeepBlocks::All,
 		transaction_storage: TransactionStorageMode::BlockBody,
+		index_extrinsics: false,
+		database_snapshot_dir: None,
+		compress_blocks: false,
+		cold_storage: None,
+		canonicalization_delay: None,
+		max_canonicalization_delay: None,
+		trie_cache_warm_up: false,
 		rpc_cors: Default::default(),
 		rpc_http: Default::default(),
 		rpc_ipc: Default::default(),
+		rpc_grpc: Default::default(),
+		rpc_ipc_permissions: Default::default(),
 		rpc_ws: Default::default(),
 		rpc_ws_max_connections: Default::default(),
 		rpc_methods: Default::default(),
+		rpc_rate_limit: None,
+		rpc_trusted_proxies: Default::default(),
+		rpc_auth: None,
+		rpc_method_policy: Default::default(),
+		rpc_request_limits: Default::default(),
+		rpc_interceptors: Default::default(),
+		rpc_aliases: Default::default(),
+		rpc_type_aliases: Default::default(),
 		state_cache_child_ratio: Default::default(),
 		state_cache_size: Default::default(),
+		trie_cache_size: Default::default(),
 		tracing_receiver: Default::default(),
 		tracing_targets: Default::default(),
 		transaction_pool: Default::default(),
 		wasm_method: Default::default(),
+		wasm_features: Default::default(),
 		wasm_runtime_overrides: Default::default(),
 		max_runtime_instances: 8,
+		preinstantiated_runtime_instances: 0,
+		runtime_cache_size: sc_service::config::DEFAULT_MAX_RUNTIMES,
+		runtime_cache_max_code_size: None,
+		host_functions: Default::default(),
 		announce_block: true,
 		base_path: None,
 		informant_output_format: sc_informant::OutputFormat {
 			enable_color: false,
+			..Default::default()
 		},
+		inherent_data_providers: Default::default(),
 		disable_log_reloading: false,
 	};
 
@@ -16,8 +16,11 @@
 // limitations under the License.
 
 mod command;
+mod storage;
 mod writer;
 
+pub use storage::StorageCmd;
+
 use sc_cli::{ExecutionStrategy, WasmExecutionMethod};
 use std::fmt::Debug;
 
@@ -0,0 +1,180 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020-2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The `benchmark-storage` command, measuring the latencies of the database of the node.
+
+use std::{fmt::Debug, io::Write, path::PathBuf, str::FromStr};
+
+use sc_cli::{
+	BlockNumberOrHash, CliConfiguration, DatabaseParams, PruningParams, Result, SharedParams,
+};
+use sc_service::{
+	Configuration,
+	chain_ops::{LatencyStats, StorageBenchmark, StorageBenchmarkParams},
+};
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
+
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The `benchmark-storage` command used to measure the latencies of the reads and writes of
+/// the database of the node, on its state.
+///
+/// The writes are committed to a copy of the database, which must be a RocksDB database, so it
+/// needs the disk space of another database.
+#[derive(Debug, structopt::StructOpt)]
+pub struct StorageCmd {
+	/// Block hash or number of the state, the best block by default.
+	#[structopt(value_name = "HASH or NUMBER")]
+	pub input: Option<BlockNumberOrHash>,
+
+	/// Number of random keys of the state to read and write.
+	#[structopt(long, default_value = "1000")]
+	pub keys: usize,
+
+	/// Number of times every key is read and written.
+	#[structopt(short, long, default_value = "1")]
+	pub repeat: usize,
+
+	/// The latency the weights are derived from.
+	#[structopt(
+		long,
+		value_name = "METRIC",
+		possible_values = &["average", "median", "p99", "max"],
+		default_value = "average",
+	)]
+	pub weight_metric: String,
+
+	/// Output the weights to a Rust file at the given path.
+	#[structopt(long)]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+}
+
+impl StorageCmd {
+	/// Runs the command and benchmarks the database.
+	pub fn run<B>(&self, config: Configuration) -> Result<()> where
+		B: BlockT,
+		B::Hash: FromStr,
+		<B::Hash as FromStr>::Err: Debug,
+		<<B::Header as HeaderT>::Number as FromStr>::Err: Debug,
+	{
+		if self.keys == 0 || self.repeat == 0 {
+			return Err("`--keys` and `--repeat` must be positive".into());
+		}
+		let block = self.input.as_ref().map(|b| b.parse::<B>()).transpose()?;
+		let params = StorageBenchmarkParams { keys: self.keys, repeat: self.repeat };
+		let benchmark = sc_service::benchmark_storage::<B>(&config, block, params)?;
+
+		println!("Sampled {} keys of the state, latencies in ns:", benchmark.keys);
+		println!(
+			"{:<8} {:>8} {:>10} {:>10} {:>10} {:>10} {:>10}",
+			"", "Count", "Min", "Average", "Median", "p99", "Max",
+		);
+		for (name, stats) in &[("Read", &benchmark.reads), ("Write", &benchmark.writes)] {
+			println!(
+				"{:<8} {:>8} {:>10} {:>10} {:>10} {:>10} {:>10}",
+				name, stats.count, stats.min, stats.average, stats.median, stats.p99, stats.max,
+			);
+		}
+
+		let weights = self.weights(&benchmark);
+		match &self.output {
+			Some(path) => {
+				let mut file = std::fs::File::create(path)?;
+				file.write_all(weights.as_bytes())?;
+				println!("Weights written to {:?}.", path);
+			},
+			None => print!("\n{}", weights),
+		}
+		Ok(())
+	}
+
+	/// Returns the latency the weights are derived from.
+	fn metric(&self, stats: &LatencyStats) -> u64 {
+		match self.weight_metric.as_str() {
+			"median" => stats.median,
+			"p99" => stats.p99,
+			"max" => stats.max,
+			_ => stats.average,
+		}
+	}
+
+	/// Returns the Rust definition of the weights of the database operations, to be used as
+	/// the `DbWeight` of `frame_system`.
+	fn weights(&self, benchmark: &StorageBenchmark) -> String {
+		let mut weights = format!(
+			"//! THIS FILE WAS AUTO-GENERATED USING THE SUBSTRATE BENCHMARK CLI VERSION {}\n\
+			//! DATE: {}, KEYS: {}, REPEAT: {}, METRIC: {}\n\n",
+			VERSION,
+			chrono::Utc::now().format("%Y-%m-%d"),
+			benchmark.keys,
+			self.repeat,
+			self.weight_metric,
+		);
+		weights.push_str("use frame_support::{\n");
+		weights.push_str("\tparameter_types,\n");
+		weights.push_str("\tweights::{RuntimeDbWeight, constants::WEIGHT_PER_NANOS},\n");
+		weights.push_str("};\n\n");
+		weights.push_str("parameter_types! {\n");
+		weights.push_str("\t/// The weights of the reads and writes of the state of a node.\n");
+		weights.push_str("\tpub const StorageDbWeight: RuntimeDbWeight = RuntimeDbWeight {\n");
+		let (read, write) = (self.metric(&benchmark.reads), self.metric(&benchmark.writes));
+		weights.push_str(&format!("\t\tread: {} * WEIGHT_PER_NANOS,\n", read));
+		weights.push_str(&format!("\t\twrite: {} * WEIGHT_PER_NANOS,\n", write));
+		weights.push_str("\t};\n");
+		weights.push_str("}\n");
+		weights
+	}
+}
+
+impl CliConfiguration for StorageCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	// the caches of the state would hide the latencies of the database.
+	fn state_cache_size(&self) -> Result<usize> {
+		Ok(0)
+	}
+
+	fn trie_cache_size(&self) -> Result<usize> {
+		Ok(0)
+	}
+
+	fn trie_cache_warm_up(&self) -> Result<bool> {
+		Ok(false)
+	}
+}
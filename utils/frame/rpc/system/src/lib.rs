@@ -33,10 +33,11 @@ use sp_blockchain::{
 };
 use sp_runtime::{
 	generic::BlockId,
-	traits,
+	traits::{self, One},
 };
 use sp_core::{hexdisplay::HexDisplay, Bytes};
 use sp_transaction_pool::{TransactionPool, InPoolTransaction};
+use sp_api::Core;
 use sp_block_builder::BlockBuilder;
 use sc_rpc_api::DenyUnsafe;
 
@@ -57,7 +58,11 @@ pub trait SystemApi<BlockHash, AccountId, Index> {
 	#[rpc(name = "system_accountNextIndex", alias("account_nextIndex"))]
 	fn nonce(&self, account: AccountId) -> FutureResult<Index>;
 
-	/// Dry run an extrinsic at a given block. Return SCALE encoded ApplyExtrinsicResult.
+	/// Dry run an extrinsic on top of a given block. Return SCALE encoded ApplyExtrinsicResult.
+	///
+	/// The extrinsic is applied to a new block built on top of the given one, like a block
+	/// author would, so the nonce, the balance and the call filter are checked against the
+	/// state it would be included in. The changes are discarded afterwards.
 	#[rpc(name = "system_dryRun", alias("system_dryRunAt"))]
 	fn dry_run(&self, extrinsic: Bytes, at: Option<BlockHash>) -> FutureResult<Bytes>;
 }
@@ -136,11 +141,18 @@ where
 		}
 
 		let dry_run = || {
+			let runtime_error = |e: &dyn std::fmt::Debug| RpcError {
+				code: ErrorCode::ServerError(Error::RuntimeError.into()),
+				message: "Unable to dry run extrinsic.".into(),
+				data: Some(format!("{:?}", e).into()),
+			};
 			let api = self.client.runtime_api();
-			let at = BlockId::<Block>::hash(at.unwrap_or_else(||
-				// If the block hash is not supplied assume the best block.
-				self.client.info().best_hash
-			));
+			// If the block hash is not supplied assume the best block.
+			let parent_hash = at.unwrap_or_else(|| self.client.info().best_hash);
+			let at = BlockId::<Block>::hash(parent_hash);
+			let parent_number = self.client.number(parent_hash)
+				.map_err(|e| runtime_error(&e))?
+				.ok_or_else(|| runtime_error(&format!("Unknown block {}", parent_hash)))?;
 
 			let uxt: <Block as traits::Block>::Extrinsic = Decode::decode(&mut &*extrinsic).map_err(|e| RpcError {
 				code: ErrorCode::ServerError(Error::DecodeError.into()),
@@ -148,12 +160,18 @@ where
 				data: Some(format!("{:?}", e).into()),
 			})?;
 
-			let result = api.apply_extrinsic(&at, uxt)
-				.map_err(|e| RpcError {
-					code: ErrorCode::ServerError(Error::RuntimeError.into()),
-					message: "Unable to dry run extrinsic.".into(),
-					data: Some(format!("{:?}", e).into()),
-				})?;
+			// Start a block on top of the given one, like the block builder does. Its
+			// changes are kept in the overlay of `api`, which is dropped afterwards.
+			let header = <<Block as traits::Block>::Header as traits::Header>::new(
+				parent_number + One::one(),
+				Default::default(),
+				Default::default(),
+				parent_hash,
+				Default::default(),
+			);
+			api.initialize_block(&at, &header).map_err(|e| runtime_error(&e))?;
+
+			let result = api.apply_extrinsic(&at, uxt).map_err(|e| runtime_error(&e))?;
 
 			Ok(Encode::encode(&result).into())
 		};
@@ -421,4 +439,37 @@ mod tests {
 		let apply_res: ApplyExtrinsicResult = Decode::decode(&mut bytes.as_slice()).unwrap();
 		assert_eq!(apply_res, Err(TransactionValidityError::Invalid(InvalidTransaction::Stale)));
 	}
+
+	#[test]
+	fn dry_run_should_not_change_the_state() {
+		sp_tracing::try_init_simple();
+
+		// given
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let spawner = sp_core::testing::TaskExecutor::new();
+		let pool = BasicPool::new_full(
+			Default::default(),
+			true.into(),
+			None,
+			spawner,
+			client.clone(),
+		);
+
+		let accounts = FullSystem::new(client, pool, DenyUnsafe::No);
+
+		let tx = Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Bob.into(),
+			amount: 5,
+			nonce: 0,
+		}.into_signed_tx();
+
+		// when
+		let first = accounts.dry_run(tx.encode().into(), None).wait().unwrap();
+		let second = accounts.dry_run(tx.encode().into(), None).wait().unwrap();
+
+		// then
+		assert_eq!(first, second);
+		assert_eq!(accounts.nonce(AccountKeyring::Alice.into()).wait().unwrap(), 0);
+	}
 }
@@ -19,13 +19,18 @@
 
 use parity_scale_codec::Decode;
 use std::{fmt::Debug, str::FromStr};
+use remote_externalities::TestExternalities;
 use sc_service::Configuration;
-use sc_cli::{CliConfiguration, ExecutionStrategy, WasmExecutionMethod};
+use sc_cli::{BlockNumberOrHash, CliConfiguration, ExecutionStrategy, WasmExecutionMethod};
 use sc_executor::NativeExecutor;
 use sc_service::NativeExecutionDispatch;
 use sp_state_machine::StateMachine;
-use sp_runtime::traits::{Block as BlockT, NumberFor};
-use sp_core::storage::{StorageData, StorageKey, well_known_keys};
+use sp_runtime::{generic::BlockId, traits::{Block as BlockT, NumberFor}};
+use sp_core::{H256, storage::{StorageData, StorageKey, well_known_keys}};
+
+mod upgrade;
+
+pub use upgrade::TryUpgradeCmd;
 
 /// Various commands to try out the new runtime, over configurable states.
 ///
@@ -37,7 +42,8 @@ pub struct TryRuntimeCmd {
 	#[structopt(flatten)]
 	pub shared_params: sc_cli::SharedParams,
 
-	/// The state to use to run the migration. Should be a valid FILE or HTTP URI.
+	/// The state to use to run the migration. Should be a valid FILE or HTTP URI, or `db`
+	/// for the database of the node.
 	#[structopt(short, long, default_value = "http://localhost:9933")]
 	pub state: State,
 
@@ -70,11 +76,17 @@ pub enum State {
 
 	/// A live chain. Inner value is the HTTP uri.
 	Live(String),
+
+	/// The database of the node, as configured by the shared parameters.
+	Db,
 }
 
 impl FromStr for State {
 	type Err = &'static str;
 	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		if s == "db" {
+			return Ok(State::Db);
+		}
 		match s.get(..7) {
 			// could use Url crate as well, but lets keep it simple for now.
 			Some("http://") => Ok(State::Live(s.to_string())),
@@ -84,18 +96,76 @@ impl FromStr for State {
 				.get(1)
 				.map(|s| State::Snap(s.to_string()))
 				.ok_or("invalid file URI"),
-			_ => Err("invalid format. Must be a valid HTTP or File URI"),
+			_ => Err("invalid format. Must be a valid HTTP or File URI, or `db`"),
 		}
 	}
 }
 
+/// Build the externalities of the given state at the given block, with the given keys
+/// injected.
+///
+/// The block of a live chain must be given by hash, it is the finalized one by default. The
+/// block of the database is the best one by default.
+async fn build_externalities<B>(
+	state: &State,
+	at: Option<&BlockNumberOrHash>,
+	config: &Configuration,
+	injections: &[(StorageKey, StorageData)],
+) -> sc_cli::Result<TestExternalities>
+where
+	B: BlockT,
+	B::Hash: FromStr,
+	<B::Hash as FromStr>::Err: Debug,
+	NumberFor<B>: FromStr,
+	<NumberFor<B> as FromStr>::Err: Debug,
+{
+	use remote_externalities::{Builder, Mode, CacheConfig, OfflineConfig, OnlineConfig};
+	let at = at.map(|at| at.parse::<B>()).transpose()?;
+	let builder = match state {
+		State::Snap(file_path) => Builder::new().mode(Mode::Offline(OfflineConfig {
+			cache: CacheConfig { name: file_path.into(), ..Default::default() },
+		})),
+		State::Live(http_uri) => {
+			let at = match at {
+				Some(BlockId::Hash(hash)) => Some(
+					H256::decode(&mut hash.as_ref())
+						.map_err(|e| format!("invalid block hash: {:?}", e))?
+				),
+				Some(BlockId::Number(_)) =>
+					return Err("the block of a live chain must be given by hash".into()),
+				None => None,
+			};
+			Builder::new().mode(Mode::Online(OnlineConfig {
+				uri: http_uri.into(),
+				at,
+				..Default::default()
+			}))
+		},
+		State::Db => {
+			let (hash, storage) = sc_service::export_state::<B>(config, at)?;
+			log::info!("Read the state at block {} of the database", hash);
+			let mut ext = TestExternalities::new(storage);
+			for (key, value) in injections {
+				ext.insert(key.0.clone(), value.0.clone());
+			}
+			return Ok(ext);
+		},
+	};
+
+	Ok(builder.inject(injections).build().await?)
+}
+
 impl TryRuntimeCmd {
 	pub async fn run<B, ExecDispatch>(&self, config: Configuration) -> sc_cli::Result<()>
 	where
 		B: BlockT,
+		B::Hash: FromStr,
+		<B::Hash as FromStr>::Err: Debug,
+		NumberFor<B>: FromStr,
+		<NumberFor<B> as FromStr>::Err: Debug,
 		ExecDispatch: NativeExecutionDispatch + 'static,
 	{
-		let spec = config.chain_spec;
+		let spec = &config.chain_spec;
 		let genesis_storage = spec.build_storage()?;
 
 		let code = StorageData(
@@ -120,21 +190,8 @@ impl TryRuntimeCmd {
 			max_runtime_instances,
 		);
 
-		let ext = {
-			use remote_externalities::{Builder, Mode, CacheConfig, OfflineConfig, OnlineConfig};
-			let builder = match &self.state {
-				State::Snap(file_path) => Builder::new().mode(Mode::Offline(OfflineConfig {
-					cache: CacheConfig { name: file_path.into(), ..Default::default() },
-				})),
-				State::Live(http_uri) => Builder::new().mode(Mode::Online(OnlineConfig {
-					uri: http_uri.into(),
-					..Default::default()
-				})),
-			};
-
-			// inject the code into this ext.
-			builder.inject(&[(code_key, code)]).build().await?
-		};
+		// inject the code into this ext.
+		let ext = build_externalities::<B>(&self.state, None, &config, &[(code_key, code)]).await?;
 
 		let encoded_result = StateMachine::<_, _, NumberFor<B>, _>::new(
 			&ext.backend,
@@ -0,0 +1,289 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! `try-upgrade`, a dry-run of the upgrade to a given runtime.
+
+use std::{collections::BTreeMap, fmt::Debug, fs, path::PathBuf, str::FromStr};
+use parity_scale_codec::Decode;
+use sc_cli::{BlockNumberOrHash, CliConfiguration, DatabaseParams, WasmExecutionMethod};
+use sc_executor::NativeExecutor;
+use sc_service::{Configuration, NativeExecutionDispatch};
+use sp_core::{Hasher, hexdisplay::HexDisplay, storage::{StorageData, StorageKey, well_known_keys}};
+use sp_runtime::traits::{Block as BlockT, NumberFor};
+use sp_state_machine::{Backend, ExecutionStrategy, OverlayedChanges, StateMachine};
+
+use crate::{State, build_externalities};
+
+/// Length of the prefix of the storage keys of a pallet, the `twox_128` hash of its name.
+const PALLET_PREFIX_LEN: usize = 16;
+
+/// Dry-run the upgrade to the given runtime: execute its migrations against the state of a
+/// chain and report the changes of the storage and the weight consumed.
+///
+/// The runtime must be built with the `try-runtime` feature. Nothing is written to the state.
+#[derive(Debug, structopt::StructOpt)]
+pub struct TryUpgradeCmd {
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: sc_cli::SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+
+	/// The WASM blob of the runtime to upgrade to.
+	#[structopt(long, value_name = "PATH", parse(from_os_str))]
+	pub wasm: PathBuf,
+
+	/// The state to upgrade. Should be a valid FILE or HTTP URI, or `db` for the database of
+	/// the node.
+	#[structopt(short, long, default_value = "http://localhost:9933")]
+	pub state: State,
+
+	/// The block whose state is upgraded.
+	///
+	/// The block of a live chain must be given by hash, it is the finalized one by default.
+	/// The block of the database is the best one by default.
+	#[structopt(long, value_name = "BLOCK")]
+	pub at: Option<BlockNumberOrHash>,
+
+	/// Print every changed key, rather than the number of changes of every pallet.
+	#[structopt(long)]
+	pub show_keys: bool,
+
+	/// Method for executing Wasm runtime code.
+	#[structopt(
+		long = "wasm-execution",
+		value_name = "METHOD",
+		possible_values = &WasmExecutionMethod::enabled_variants(),
+		case_insensitive = true,
+		default_value = "Interpreted"
+	)]
+	pub wasm_method: WasmExecutionMethod,
+}
+
+/// The changes of the storage, by key.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct StorageDiff {
+	added: Vec<Vec<u8>>,
+	modified: Vec<Vec<u8>>,
+	removed: Vec<Vec<u8>>,
+}
+
+impl StorageDiff {
+	/// Compare the given new values of the keys with their values in the backend.
+	fn new<'a, B, H>(
+		backend: &B,
+		changes: impl Iterator<Item = (&'a [u8], Option<&'a [u8]>)>,
+	) -> Result<Self, String>
+	where
+		B: Backend<H>,
+		H: Hasher,
+	{
+		let mut diff = StorageDiff::default();
+		for (key, value) in changes {
+			let old = backend.storage(key).map_err(|e| format!("{:?}", e))?;
+			match (old.as_deref(), value) {
+				(None, Some(_)) => diff.added.push(key.to_vec()),
+				(Some(_), None) => diff.removed.push(key.to_vec()),
+				(Some(old), Some(new)) if old != new => diff.modified.push(key.to_vec()),
+				_ => {},
+			}
+		}
+		Ok(diff)
+	}
+
+	/// The numbers of added, modified and removed keys, by prefix of pallet.
+	fn by_pallet(&self) -> BTreeMap<&[u8], (usize, usize, usize)> {
+		let mut pallets = BTreeMap::<_, (usize, usize, usize)>::new();
+		for key in &self.added {
+			pallets.entry(pallet_prefix(key)).or_default().0 += 1;
+		}
+		for key in &self.modified {
+			pallets.entry(pallet_prefix(key)).or_default().1 += 1;
+		}
+		for key in &self.removed {
+			pallets.entry(pallet_prefix(key)).or_default().2 += 1;
+		}
+		pallets
+	}
+}
+
+/// Returns the prefix of the pallet of the given key.
+fn pallet_prefix(key: &[u8]) -> &[u8] {
+	// the well known keys, e.g. `:code`, aren't keys of a pallet.
+	if key.starts_with(b":") {
+		key
+	} else {
+		&key[..PALLET_PREFIX_LEN.min(key.len())]
+	}
+}
+
+impl TryUpgradeCmd {
+	/// Run the command.
+	pub async fn run<B, ExecDispatch>(&self, config: Configuration) -> sc_cli::Result<()>
+	where
+		B: BlockT,
+		B::Hash: FromStr,
+		<B::Hash as FromStr>::Err: Debug,
+		NumberFor<B>: FromStr,
+		<NumberFor<B> as FromStr>::Err: Debug,
+		ExecDispatch: NativeExecutionDispatch + 'static,
+	{
+		let code = StorageData(fs::read(&self.wasm)?);
+		let code_key = StorageKey(well_known_keys::CODE.to_vec());
+		let ext = build_externalities::<B>(
+			&self.state,
+			self.at.as_ref(),
+			&config,
+			&[(code_key, code)],
+		).await?;
+
+		let executor = NativeExecutor::<ExecDispatch>::new(
+			self.wasm_method.into(),
+			config.default_heap_pages,
+			config.max_runtime_instances,
+		);
+		let mut changes = OverlayedChanges::default();
+		// the code is injected in the state, so the migrations are those of the given runtime.
+		let encoded_result = StateMachine::<_, _, NumberFor<B>, _>::new(
+			&ext.backend,
+			None,
+			&mut changes,
+			&executor,
+			"TryRuntime_on_runtime_upgrade",
+			&[],
+			ext.extensions,
+			&sp_state_machine::backend::BackendRuntimeCode::new(&ext.backend)
+				.runtime_code()?,
+			sp_core::testing::TaskExecutor::new(),
+		)
+		.execute(ExecutionStrategy::AlwaysWasm)
+		.map_err(|e| format!("failed to execute 'TryRuntime_on_runtime_upgrade' due to {:?}", e))?;
+
+		let (weight, total_weight) = <Result<(u64, u64), String>>::decode(&mut &*encoded_result)
+			.map_err(|e| format!("failed to decode output due to {:?}", e))?
+			.map_err(|e| format!("the migrations failed: {}", e))?;
+
+		let diff = StorageDiff::new(
+			&ext.backend,
+			changes.changes()
+				.map(|(key, value)| (key.as_slice(), value.value().map(Vec::as_slice))),
+		)?;
+		let children = changes.children().count();
+
+		println!(
+			"Consumed weight = {}, total weight = {} ({:.2}%)",
+			weight,
+			total_weight,
+			weight as f64 / total_weight as f64 * 100.0,
+		);
+		println!(
+			"{} keys added, {} modified, {} removed",
+			diff.added.len(),
+			diff.modified.len(),
+			diff.removed.len(),
+		);
+		if children > 0 {
+			println!("{} child tries changed", children);
+		}
+
+		if self.show_keys {
+			let keys = diff.added.iter().map(|key| ("+", key))
+				.chain(diff.modified.iter().map(|key| ("~", key)))
+				.chain(diff.removed.iter().map(|key| ("-", key)));
+			for (change, key) in keys {
+				println!("{} 0x{}", change, HexDisplay::from(key));
+			}
+		} else {
+			println!("{:<36} {:>8} {:>8} {:>8}", "Prefix", "Added", "Modified", "Removed");
+			for (prefix, (added, modified, removed)) in diff.by_pallet() {
+				let prefix = format!("0x{}", HexDisplay::from(&prefix));
+				println!("{:<36} {:>8} {:>8} {:>8}", prefix, added, modified, removed);
+			}
+		}
+		Ok(())
+	}
+}
+
+impl CliConfiguration for TryUpgradeCmd {
+	fn shared_params(&self) -> &sc_cli::SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+
+	fn chain_id(&self, _is_dev: bool) -> sc_cli::Result<String> {
+		Ok(match self.shared_params.chain {
+			Some(ref chain) => chain.clone(),
+			None => "dev".into(),
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use remote_externalities::TestExternalities;
+	use sp_core::twox_128;
+
+	#[test]
+	fn diffs_the_storage() {
+		let pallet = |name: &[u8], key: &[u8]| [&twox_128(name)[..], key].concat();
+		let mut ext = TestExternalities::new_empty();
+		ext.insert(pallet(b"System", b"a"), b"1".to_vec());
+		ext.insert(pallet(b"System", b"b"), b"1".to_vec());
+		ext.insert(pallet(b"Balances", b"a"), b"1".to_vec());
+		ext.insert(b":code".to_vec(), b"old".to_vec());
+
+		let changes = vec![
+			(pallet(b"System", b"a"), Some(b"2".to_vec())),
+			(pallet(b"System", b"b"), Some(b"1".to_vec())),
+			(pallet(b"System", b"c"), Some(b"1".to_vec())),
+			(pallet(b"Balances", b"a"), None),
+			(pallet(b"Balances", b"b"), None),
+			(b":code".to_vec(), Some(b"new".to_vec())),
+		];
+		let diff = StorageDiff::new(
+			&ext.backend,
+			changes.iter().map(|(key, value)| (key.as_slice(), value.as_deref())),
+		).unwrap();
+
+		assert_eq!(diff, StorageDiff {
+			added: vec![pallet(b"System", b"c")],
+			modified: vec![pallet(b"System", b"a"), b":code".to_vec()],
+			removed: vec![pallet(b"Balances", b"a")],
+		});
+		let system = twox_128(b"System");
+		let balances = twox_128(b"Balances");
+		assert_eq!(
+			diff.by_pallet().into_iter().collect::<Vec<_>>(),
+			{
+				let mut pallets = vec![
+					(&system[..], (1, 1, 0)),
+					(&balances[..], (0, 0, 1)),
+					(&b":code"[..], (0, 1, 0)),
+				];
+				pallets.sort();
+				pallets
+			},
+		);
+	}
+}
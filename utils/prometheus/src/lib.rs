@@ -32,14 +32,16 @@ use std::net::SocketAddr;
 
 #[cfg(not(target_os = "unknown"))]
 mod networking;
+mod security;
 mod sourced;
 
+pub use security::{Auth, EndpointSecurity, TlsConfig};
 pub use sourced::{SourcedCounter, SourcedGauge, MetricSource, SourcedMetric};
 
 #[cfg(target_os = "unknown")]
-pub use unknown_os::init_prometheus;
+pub use unknown_os::{init_prometheus, init_prometheus_with_security};
 #[cfg(not(target_os = "unknown"))]
-pub use known_os::init_prometheus;
+pub use known_os::{init_prometheus, init_prometheus_with_security};
 
 pub fn register<T: Clone + Collector + 'static>(metric: T, registry: &Registry) -> Result<T, PrometheusError> {
 	registry.register(Box::new(metric.clone()))?;
@@ -56,13 +58,37 @@ mod unknown_os {
 	pub async fn init_prometheus(_: SocketAddr, _registry: Registry) -> Result<(), Error> {
 		Ok(())
 	}
+
+	impl std::fmt::Display for Error {
+		fn fmt(&self, _: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+			match *self {}
+		}
+	}
+
+	pub fn init_prometheus_with_security(
+		_: SocketAddr,
+		_registry: Registry,
+		_security: EndpointSecurity,
+	) -> Result<impl Future<Output = Result<(), Error>>, Error> {
+		Ok(async { Ok(()) })
+	}
 }
 
 #[cfg(not(target_os = "unknown"))]
 mod known_os {
 	use super::*;
-	use hyper::http::StatusCode;
+	use futures_rustls::{TlsAcceptor, rustls::{self, NoClientAuth, ServerConfig}};
+	use futures_util::stream::{BoxStream, StreamExt};
+	use hyper::http::{StatusCode, header::{AUTHORIZATION, WWW_AUTHENTICATE}};
 	use hyper::{Server, Body, Request, Response, service::{service_fn, make_service_fn}};
+	use networking::Connection;
+	use std::{fs::File, io::{self, BufReader}, sync::Arc, time::Duration};
+
+	/// The maximum number of TLS handshakes in progress at the same time.
+	const MAX_HANDSHAKES: usize = 32;
+	/// The time a client has to complete its TLS handshake, so slow clients can't prevent the
+	/// others from connecting.
+	const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
 
 	#[derive(Debug, derive_more::Display, derive_more::From)]
 	pub enum Error {
@@ -73,7 +99,10 @@ mod known_os {
 		/// i/o error.
 		Io(std::io::Error),
 		#[display(fmt = "Prometheus port {} already in use.", _0)]
-		PortInUse(SocketAddr)
+		PortInUse(SocketAddr),
+		/// Invalid TLS certificate or private key.
+		#[display(fmt = "Invalid Prometheus TLS configuration: {}", _0)]
+		Tls(String),
 	}
 
 	impl std::error::Error for Error {
@@ -82,12 +111,28 @@ mod known_os {
 				Error::Hyper(error) => Some(error),
 				Error::Http(error) => Some(error),
 				Error::Io(error) => Some(error),
-				Error::PortInUse(_) => None
+				Error::PortInUse(_) => None,
+				Error::Tls(_) => None,
 			}
 		}
 	}
 
-	async fn request_metrics(req: Request<Body>, registry: Registry) -> Result<Response<Body>, Error> {
+	async fn request_metrics(
+		req: Request<Body>,
+		registry: Registry,
+		auth: Option<Auth>,
+	) -> Result<Response<Body>, Error> {
+		if let Some(auth) = auth {
+			let authorization = req.headers().get(AUTHORIZATION)
+				.and_then(|value| value.to_str().ok());
+			if !authorization.map_or(false, |authorization| auth.verify(authorization)) {
+				return Response::builder().status(StatusCode::UNAUTHORIZED)
+					.header(WWW_AUTHENTICATE, auth.challenge())
+					.body(Body::from("Unauthorized."))
+					.map_err(Error::Http)
+			}
+		}
+
 		if req.uri().path() == "/metrics" {
 			let metric_families = registry.gather();
 			let mut buffer = vec![];
@@ -119,27 +164,104 @@ mod known_os {
 		}
 	}
 
+	/// Load the certificate chain and the private key of the given configuration.
+	fn server_config(tls: &TlsConfig) -> Result<ServerConfig, Error> {
+		let open = |path: &std::path::PathBuf| File::open(path)
+			.map(BufReader::new)
+			.map_err(|e| Error::Tls(format!("Failed to open {}: {}", path.display(), e)));
+
+		let certificates = rustls::internal::pemfile::certs(&mut open(&tls.certificate)?)
+			.map_err(|()| Error::Tls("Invalid PEM certificate chain".into()))?;
+		let mut keys = rustls::internal::pemfile::pkcs8_private_keys(&mut open(&tls.private_key)?)
+			.map_err(|()| Error::Tls("Invalid PEM private key".into()))?;
+		if keys.is_empty() {
+			keys = rustls::internal::pemfile::rsa_private_keys(&mut open(&tls.private_key)?)
+				.map_err(|()| Error::Tls("Invalid PEM private key".into()))?;
+		}
+		let key = keys.into_iter().next()
+			.ok_or_else(|| Error::Tls("No private key found".into()))?;
+
+		let mut config = ServerConfig::new(NoClientAuth::new());
+		config.set_single_cert(certificates, key).map_err(|e| Error::Tls(e.to_string()))?;
+		Ok(config)
+	}
+
 	/// Initializes the metrics context, and starts an HTTP server
 	/// to serve metrics.
 	pub async fn init_prometheus(prometheus_addr: SocketAddr, registry: Registry) -> Result<(), Error>{
-		use networking::Incoming;
+		init_prometheus_with_security(prometheus_addr, registry, Default::default())?.await
+	}
+
+	/// Loads the TLS certificate of the given settings, and returns the future starting an
+	/// HTTP server to serve metrics, over TLS and to authenticated requests only if configured
+	/// so.
+	///
+	/// Fails right away if the certificate or its private key can't be loaded.
+	pub fn init_prometheus_with_security(
+		prometheus_addr: SocketAddr,
+		registry: Registry,
+		security: EndpointSecurity,
+	) -> Result<impl Future<Output = Result<(), Error>>, Error> {
+		let acceptor = security.tls.as_ref()
+			.map(|tls| server_config(tls).map(|config| TlsAcceptor::from(Arc::new(config))))
+			.transpose()?;
+		Ok(serve(prometheus_addr, registry, acceptor, security.auth))
+	}
+
+	async fn serve(
+		prometheus_addr: SocketAddr,
+		registry: Registry,
+		acceptor: Option<TlsAcceptor>,
+		auth: Option<Auth>,
+	) -> Result<(), Error> {
 		let listener = async_std::net::TcpListener::bind(&prometheus_addr)
 			.await
 			.map_err(|_| Error::PortInUse(prometheus_addr))?;
 
-		log::info!("〽️ Prometheus server started at {}", prometheus_addr);
+		log::info!(
+			"〽️ Prometheus server started at {}{}",
+			prometheus_addr,
+			if acceptor.is_some() { " over TLS" } else { "" },
+		);
+
+		let incoming = listener.incoming();
+		let connections: BoxStream<io::Result<Connection>> = match acceptor {
+			Some(acceptor) => incoming
+				.map(move |stream| {
+					let acceptor = acceptor.clone();
+					async move {
+						async_std::future::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream?))
+							.await
+							.map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "timed out"))?
+					}
+				})
+				.buffer_unordered(MAX_HANDSHAKES)
+				// a failed handshake only closes its connection.
+				.filter_map(|stream| async move { match stream {
+					Ok(stream) => Some(Ok(Connection(Box::new(stream)))),
+					Err(e) => {
+						log::debug!("Prometheus TLS handshake failed: {}", e);
+						None
+					},
+				}})
+				.boxed(),
+			None => incoming
+				.map(|stream| stream.map(|stream| Connection(Box::new(stream))))
+				.boxed(),
+		};
 
 		let service = make_service_fn(move |_| {
 			let registry = registry.clone();
+			let auth = auth.clone();
 
 			async move {
 				Ok::<_, hyper::Error>(service_fn(move |req: Request<Body>| {
-					request_metrics(req, registry.clone())
+					request_metrics(req, registry.clone(), auth.clone())
 				}))
 			}
 		});
 
-		let server = Server::builder(Incoming(listener.incoming()))
+		let server = Server::builder(hyper::server::accept::from_stream(connections))
 			.executor(Executor)
 			.serve(service)
 			.boxed();
@@ -17,24 +17,17 @@
 
 use async_std::pin::Pin;
 use std::task::{Poll, Context};
-use futures_util::{stream::Stream, io::{AsyncRead, AsyncWrite}};
+use futures_util::io::{AsyncRead, AsyncWrite};
 
-pub struct Incoming<'a>(pub async_std::net::Incoming<'a>);
+/// A stream the metrics can be served over.
+pub trait Io: AsyncRead + AsyncWrite + Send + Unpin {}
 
-impl hyper::server::accept::Accept for Incoming<'_> {
-	type Conn = TcpStream;
-	type Error = async_std::io::Error;
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> Io for T {}
 
-	fn poll_accept(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
-		Pin::new(&mut Pin::into_inner(self).0)
-			.poll_next(cx)
-			.map(|opt| opt.map(|res| res.map(TcpStream)))
-	}
-}
-
-pub struct TcpStream(pub async_std::net::TcpStream);
+/// A connection to the endpoint, over TLS or not.
+pub struct Connection(pub Box<dyn Io>);
 
-impl tokio::io::AsyncRead for TcpStream {
+impl tokio::io::AsyncRead for Connection {
 	fn poll_read(
 		self: Pin<&mut Self>,
 		cx: &mut Context,
@@ -45,7 +38,7 @@ impl tokio::io::AsyncRead for TcpStream {
 	}
 }
 
-impl tokio::io::AsyncWrite for TcpStream {
+impl tokio::io::AsyncWrite for Connection {
 	fn poll_write(
 		self: Pin<&mut Self>,
 		cx: &mut Context,
@@ -0,0 +1,116 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! TLS and authentication of the metrics endpoint.
+
+use std::{fmt, path::PathBuf};
+
+/// Credential required to scrape the metrics, passed in the `Authorization` header.
+#[derive(Clone)]
+pub enum Auth {
+	/// HTTP basic authentication with the given username and password.
+	Basic {
+		/// The username.
+		username: String,
+		/// The password.
+		password: String,
+	},
+	/// A static bearer token.
+	Bearer(String),
+}
+
+impl fmt::Debug for Auth {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		// never print the secrets.
+		match self {
+			Auth::Basic { username, .. } => write!(f, "Basic {{ username: {:?}, .. }}", username),
+			Auth::Bearer(_) => write!(f, "Bearer(..)"),
+		}
+	}
+}
+
+impl Auth {
+	/// Returns `true` if the given value of the `Authorization` header is valid.
+	pub fn verify(&self, authorization: &str) -> bool {
+		match self {
+			Auth::Basic { username, password } => {
+				let credentials = match authorization.strip_prefix("Basic ") {
+					Some(credentials) => credentials.trim(),
+					None => return false,
+				};
+				let expected = format!("{}:{}", username, password);
+				base64::decode(credentials)
+					.map(|credentials| constant_time_eq(&credentials, expected.as_bytes()))
+					.unwrap_or(false)
+			},
+			Auth::Bearer(token) => authorization.strip_prefix("Bearer ")
+				.map(|credential| constant_time_eq(credential.trim().as_bytes(), token.as_bytes()))
+				.unwrap_or(false),
+		}
+	}
+
+	/// Returns the value of the `WWW-Authenticate` header of the rejected requests.
+	pub(crate) fn challenge(&self) -> &'static str {
+		match self {
+			Auth::Basic { .. } => "Basic realm=\"metrics\"",
+			Auth::Bearer(_) => "Bearer",
+		}
+	}
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+	a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// The certificate the metrics are served over TLS with.
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+	/// Path of the PEM-encoded certificate chain, starting with the certificate of the endpoint.
+	pub certificate: PathBuf,
+	/// Path of the PEM-encoded private key of the certificate, in PKCS#8 or RSA format.
+	pub private_key: PathBuf,
+}
+
+/// The TLS and authentication settings of the endpoint, by default the metrics are served
+/// over plain HTTP to anyone.
+#[derive(Debug, Clone, Default)]
+pub struct EndpointSecurity {
+	/// Serve the metrics over TLS.
+	pub tls: Option<TlsConfig>,
+	/// Only serve the metrics to the requests with this credential.
+	pub auth: Option<Auth>,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn verifies_credentials() {
+		let basic = Auth::Basic { username: "prometheus".into(), password: "secret".into() };
+		let encoded = base64::encode("prometheus:secret");
+		assert!(basic.verify(&format!("Basic {}", encoded)));
+		assert!(!basic.verify(&format!("Basic {}", base64::encode("prometheus:wrong"))));
+		assert!(!basic.verify(&format!("Bearer {}", encoded)));
+		assert!(!basic.verify("Basic not base64"));
+
+		let bearer = Auth::Bearer("token".into());
+		assert!(bearer.verify("Bearer token"));
+		assert!(!bearer.verify("Bearer other"));
+		assert!(!bearer.verify("token"));
+	}
+}